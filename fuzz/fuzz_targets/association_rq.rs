@@ -0,0 +1,19 @@
+#![no_main]
+//! A-ASSOCIATE-RQ read-ahead, as done by the receiver before negotiation
+
+use libfuzzer_sys::fuzz_target;
+use rust_dicom::common::ts_preference::{parse_association_rq, pdu_length, TransferSyntaxPreference};
+
+fuzz_target!(|data: &[u8]| {
+    let _ = pdu_length(data);
+    if let Ok(contexts) = parse_association_rq(data) {
+        let preference = TransferSyntaxPreference {
+            order: vec!["1.2.840.10008.1.2.4.90".to_string(), "1.2.840.10008.1.2.1".to_string()],
+        };
+        let supported = |uid: &str| uid.starts_with("1.2.840.10008.1.2");
+        let _ = preference.acceptor_list(&contexts, supported);
+        for context in &contexts {
+            let _ = preference.choose(&context.transfer_syntaxes, supported);
+        }
+    }
+});
@@ -0,0 +1,56 @@
+#![no_main]
+//! P-DATA values (presentation context, command flag, last flag, bytes) fed
+//! through reassembly and the receiver's processing of completed data sets
+
+use dicom_object::InMemDicomObject;
+use dicom_transfer_syntax_registry::entries::{EXPLICIT_VR_BIG_ENDIAN, EXPLICIT_VR_LITTLE_ENDIAN, IMPLICIT_VR_LITTLE_ENDIAN};
+use libfuzzer_sys::fuzz_target;
+use rust_dicom::common::dimse::decode_command;
+use rust_dicom::common::iod::validate_iod;
+use rust_dicom::common::key_objects::collect_referenced_instances;
+use rust_dicom::common::reassembly::{DicomTransfer, Transfers};
+use rust_dicom::common::repair::repair_dataset;
+use rust_dicom::common::time_sanity::{check_timestamps, TimeSanityPolicy};
+
+fn process(dataset: &[u8]) {
+    let explicit_le = EXPLICIT_VR_LITTLE_ENDIAN.erased();
+    for ts in [IMPLICIT_VR_LITTLE_ENDIAN.erased(), explicit_le.clone(), EXPLICIT_VR_BIG_ENDIAN.erased()] {
+        if let Ok(mut obj) = InMemDicomObject::read_dataset_with_ts(dataset, &ts) {
+            let _ = repair_dataset(&mut obj);
+            let _ = validate_iod(&obj);
+            let _ = check_timestamps(&obj, chrono::Local::now().naive_local(), &TimeSanityPolicy::default());
+            let mut referenced = Default::default();
+            collect_referenced_instances(&obj, &mut referenced);
+            let mut encoded = Vec::new();
+            let _ = obj.write_dataset_with_ts(&mut encoded, &explicit_le);
+        }
+    }
+}
+
+fuzz_target!(|values: Vec<(u8, bool, bool, Vec<u8>)>| {
+    let mut transfers = Transfers::default();
+    for (presentation_context_id, is_command, is_last, data) in values {
+        if is_command {
+            let command = decode_command(&data).ok();
+            let transfer = DicomTransfer {
+                message_id: command.as_ref().and_then(|command| command.message_id),
+                sop_class_uid: command.as_ref().and_then(|command| command.affected_sop_class_uid.clone()),
+                sop_instance_uid: command.and_then(|command| command.affected_sop_instance_uid),
+                ..DicomTransfer::new(presentation_context_id)
+            };
+            if let Some(interrupted) = transfers.begin(transfer) {
+                let _ = interrupted.reconstruct_dataset();
+            }
+        } else {
+            transfers.get_mut(presentation_context_id).add_chunk(data);
+            if is_last {
+                if let Some(transfer) = transfers.finish(presentation_context_id) {
+                    process(&transfer.reconstruct_dataset());
+                }
+            }
+        }
+    }
+    for transfer in transfers.take_pending() {
+        let _ = transfer.reconstruct_dataset();
+    }
+});
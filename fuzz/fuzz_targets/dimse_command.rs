@@ -0,0 +1,9 @@
+#![no_main]
+//! Command sets of command P-DATA values
+
+use libfuzzer_sys::fuzz_target;
+use rust_dicom::common::dimse::decode_command;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = decode_command(data);
+});
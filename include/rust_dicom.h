@@ -0,0 +1,131 @@
+#ifndef RUST_DICOM_H
+#define RUST_DICOM_H
+
+/* Generated by cbindgen from src/ffi.rs. Do not edit. */
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#define RD_ERROR -1
+
+/**
+ * Running receiver, owned by the caller until `rd_receiver_stop`
+ */
+typedef struct RdReceiver RdReceiver;
+
+/**
+ * Destination and timeouts for `rd_send_batch`
+ */
+typedef struct RdSendOptions {
+  const char *calling_ae;
+  const char *called_ae;
+  const char *host;
+  uint16_t port;
+  /**
+   * Per-address connection timeout in seconds (0 = 5)
+   */
+  uint32_t connect_timeout_seconds;
+} RdSendOptions;
+
+/**
+ * Outcome of one file of a batch
+ */
+typedef struct RdFileResult {
+  const char *path;
+  /**
+   * Empty when the file could not be read
+   */
+  const char *sop_instance_uid;
+  bool success;
+  /**
+   * Null on success
+   */
+  const char *error_message;
+  uint64_t transfer_time_ms;
+  uint64_t file_size;
+} RdFileResult;
+
+typedef void (*RdFileResultCallback)(const RdFileResult *result, void *user_data);
+
+/**
+ * Outcome of one object received by `rd_receiver_start`
+ */
+typedef struct RdReceivedObject {
+  const char *calling_ae;
+  const char *sop_class_uid;
+  const char *sop_instance_uid;
+  const char *study_instance_uid;
+  /**
+   * "stored", "quarantined", "forwarded", "duplicate", "rejected" or "failed"
+   */
+  const char *status;
+  uint64_t size;
+} RdReceivedObject;
+
+typedef void (*RdReceivedObjectCallback)(const RdReceivedObject *object, void *user_data);
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Version of the library, e.g. "0.1.0"
+ */
+const char *rd_version(void);
+
+/**
+ * Description of the last failure on this thread, or null. Valid until the
+ * next call into the library on the same thread.
+ */
+const char *rd_last_error(void);
+
+/**
+ * Send `count` files over one association and report each file to `callback`.
+ * Returns the number of files that failed, or -1 if the batch could not be
+ * sent at all (e.g. the association was rejected).
+ *
+ * # Safety
+ *
+ * `options` must point to a valid `RdSendOptions` whose strings are valid,
+ * and `paths` to `count` valid strings. `callback` may be null.
+ */
+int rd_send_batch(const RdSendOptions *options,
+                  const char *const *paths,
+                  size_t count,
+                  RdFileResultCallback callback,
+                  void *user_data);
+
+/**
+ * Start a receiver storing objects in `output_dir` and reporting each one to
+ * `callback`. Returns once it is listening, or null if it cannot be started
+ * (e.g. the port is in use).
+ *
+ * # Safety
+ *
+ * `ae_title` and `output_dir` must be valid strings. `callback` may be null;
+ * otherwise it and `user_data` must stay valid until `rd_receiver_stop`
+ * returns, and `callback` must not call `rd_receiver_stop` itself.
+ */
+RdReceiver *rd_receiver_start(const char *ae_title,
+                              const char *output_dir,
+                              uint16_t port,
+                              uint32_t max_connections,
+                              RdReceivedObjectCallback callback,
+                              void *user_data);
+
+/**
+ * Stop a receiver, waiting up to five seconds for open associations, and free
+ * it. No callback runs once this returns.
+ *
+ * # Safety
+ *
+ * `receiver` must come from `rd_receiver_start` and not be used afterwards.
+ */
+void rd_receiver_stop(RdReceiver *receiver);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* RUST_DICOM_H */
@@ -0,0 +1,127 @@
+use clap::Parser;
+use console::style;
+use rust_dicom::common::sop_classes::{SopClassCategory, SopClassRegistry};
+use rust_dicom::common::transfer_syntaxes::TransferSyntaxRegistry;
+use rust_dicom::sender::conformance::{plan, run_check, CheckStatus, ConformanceReport, NATIVE_TRANSFER_SYNTAXES};
+use rust_dicom::sender::dicom_client::{DicomClient, DicomClientConfig};
+use std::path::PathBuf;
+use std::time::Duration;
+
+#[derive(Parser)]
+#[command(name = "dicom-conformance")]
+#[command(about = "Run a scripted conformance battery against a remote SCP and report pass/fail per check")]
+#[command(version = "1.0")]
+struct Args {
+    /// Called AE Title of the SCP under test
+    #[arg(short = 'a', long)]
+    ae_title: String,
+
+    /// Host of the SCP under test
+    #[arg(short = 'H', long)]
+    host: String,
+
+    /// Port of the SCP under test
+    #[arg(short, long, default_value = "104")]
+    port: u16,
+
+    /// Calling AE Title
+    #[arg(short = 'c', long, default_value = "RUST_SCU")]
+    calling_ae: String,
+
+    /// Per-address connection timeout in seconds
+    #[arg(long, default_value = "5")]
+    connect_timeout: u64,
+
+    /// Only store the representative SOP class of this category, e.g. ComputedTomography (repeatable)
+    #[arg(long = "category", value_parser = parse_category)]
+    categories: Vec<SopClassCategory>,
+
+    /// Store in this native transfer syntax instead of both Implicit and Explicit VR Little Endian (repeatable)
+    #[arg(long = "transfer-syntax")]
+    transfer_syntaxes: Vec<String>,
+
+    /// Write the report to this file (.csv for CSV, JSON otherwise)
+    #[arg(short, long)]
+    report: Option<PathBuf>,
+}
+
+fn parse_category(name: &str) -> Result<SopClassCategory, String> {
+    SopClassCategory::from_name(name).ok_or_else(|| format!("unknown SOP class category '{}'", name))
+}
+
+#[tokio::main]
+async fn main() {
+    let args = Args::parse();
+
+    if let Err(e) = run(&args).await {
+        eprintln!("❌ {}", e);
+        std::process::exit(1);
+    }
+}
+
+async fn run(args: &Args) -> anyhow::Result<()> {
+    let ts_registry = TransferSyntaxRegistry::global();
+    let transfer_syntaxes: Vec<String> = if args.transfer_syntaxes.is_empty() {
+        NATIVE_TRANSFER_SYNTAXES.iter().map(|uid| uid.to_string()).collect()
+    } else {
+        args.transfer_syntaxes.iter()
+            .map(|query| ts_registry.find(query).map(|info| info.uid.to_string()).unwrap_or_else(|| query.trim().to_string()))
+            .collect()
+    };
+    if let Some(unsupported) = transfer_syntaxes.iter().find(|ts| !NATIVE_TRANSFER_SYNTAXES.contains(&ts.as_str())) {
+        anyhow::bail!("Test objects can only be stored in Implicit or Explicit VR Little Endian, not {}", unsupported);
+    }
+    let categories = if args.categories.is_empty() {
+        SopClassCategory::ALL.to_vec()
+    } else {
+        args.categories.clone()
+    };
+
+    let checks = plan(SopClassRegistry::global(), &categories, &transfer_syntaxes);
+    println!("🔬 Running {} checks against {}@{}:{}", checks.len(), style(&args.ae_title).green(), args.host, args.port);
+
+    let client = DicomClient::new(DicomClientConfig {
+        calling_ae: args.calling_ae.clone(),
+        called_ae: args.ae_title.clone(),
+        host: args.host.clone(),
+        port: args.port,
+        timeout: Duration::from_secs(30),
+        connect_timeout: Duration::from_secs(args.connect_timeout),
+        lenient_repair: false,
+        compute_checksums: false,
+        pack_pdvs: false,
+        tls: None,
+        preview: None,
+    });
+    let run_at = chrono::Utc::now();
+    let mut results = Vec::with_capacity(checks.len());
+    for check in &checks {
+        let result = run_check(&client, check).await;
+        let status = match result.status {
+            CheckStatus::Passed => style("PASS").green(),
+            CheckStatus::Failed => style("FAIL").red(),
+            CheckStatus::Skipped => style("SKIP").yellow(),
+        };
+        println!("  {} {} — {}", status, result.name, style(&result.detail).dim());
+        results.push(result);
+    }
+
+    let report = ConformanceReport::new(&args.ae_title, &args.host, args.port, run_at, results);
+    println!();
+    println!("Passed: {}  Failed: {}  Skipped: {}",
+             style(report.passed).green(), style(report.failed).red(), style(report.skipped).yellow());
+
+    if let Some(path) = &args.report {
+        let contents = match path.extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("csv") => report.to_csv(),
+            _ => serde_json::to_string_pretty(&report)?,
+        };
+        std::fs::write(path, contents)?;
+        println!("Report: {}", style(path.display()).yellow());
+    }
+
+    if report.failed > 0 {
+        anyhow::bail!("{} of {} checks failed", report.failed, report.checks.len());
+    }
+    Ok(())
+}
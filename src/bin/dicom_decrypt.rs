@@ -0,0 +1,84 @@
+use clap::Parser;
+use rust_dicom::common::encryption::{is_encrypted, StorageKey};
+use rust_dicom::common::fs::collect_dicom_files;
+use std::path::{Path, PathBuf};
+
+#[derive(Parser)]
+#[command(name = "dicom-decrypt")]
+#[command(about = "Decrypt objects stored by dicom-receiver with encryption at rest")]
+#[command(version = "1.0")]
+struct Args {
+    /// Files or directories to decrypt
+    #[arg(required = true)]
+    paths: Vec<PathBuf>,
+
+    /// Output directory for the decrypted files
+    #[arg(short, long)]
+    output: PathBuf,
+
+    /// Recursive directory scanning
+    #[arg(short, long)]
+    recursive: bool,
+
+    /// File holding the AES-256 key (32 raw bytes or 64 hex digits)
+    #[arg(long, required_unless_present = "key_env", conflicts_with = "key_env")]
+    key_file: Option<PathBuf>,
+
+    /// Environment variable holding the AES-256 key (64 hex digits)
+    #[arg(long)]
+    key_env: Option<String>,
+}
+
+fn main() {
+    let args = Args::parse();
+
+    if let Err(e) = run(&args) {
+        eprintln!("❌ {}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run(args: &Args) -> anyhow::Result<()> {
+    let key = match (&args.key_file, &args.key_env) {
+        (Some(path), _) => StorageKey::from_file(path)?,
+        (None, Some(variable)) => StorageKey::from_env(variable)?,
+        (None, None) => anyhow::bail!("--key-file or --key-env is required"),
+    };
+    std::fs::create_dir_all(&args.output)?;
+
+    let mut files = Vec::new();
+    for path in &args.paths {
+        collect_dicom_files(path, args.recursive, &mut files);
+    }
+
+    let mut decrypted = 0;
+    let mut plain = 0;
+    let mut failed = 0;
+    for file in &files {
+        let target = args.output.join(file.file_name().map(PathBuf::from).unwrap_or_else(|| PathBuf::from("object.dcm")));
+        match decrypt_file(file, &target, &key) {
+            Ok(true) => decrypted += 1,
+            Ok(false) => plain += 1,
+            Err(e) => {
+                eprintln!("❌ {}: {:#}", file.display(), e);
+                failed += 1;
+            }
+        }
+    }
+
+    println!("Wrote {} file(s) to {}: {} decrypted, {} already plain, {} failed",
+             decrypted + plain, args.output.display(), decrypted, plain, failed);
+    if failed > 0 {
+        anyhow::bail!("{} of {} file(s) could not be decrypted", failed, files.len());
+    }
+    Ok(())
+}
+
+/// Write the decrypted object to `target`; false if it was not encrypted and is copied as is
+fn decrypt_file(file: &Path, target: &Path, key: &StorageKey) -> anyhow::Result<bool> {
+    let bytes = std::fs::read(file)?;
+    let encrypted = is_encrypted(&bytes);
+    let contents = if encrypted { key.open(&bytes)? } else { bytes };
+    std::fs::write(target, contents)?;
+    Ok(encrypted)
+}
@@ -0,0 +1,234 @@
+use clap::{Parser, Subcommand};
+use rust_dicom::common::capabilities::{negotiate, CapabilitySet};
+use rust_dicom::common::rejection::presentation_context_result_text;
+use rust_dicom::common::sop_classes::{SopClassCategory, SopClassInfo, SopClassRegistry};
+use rust_dicom::common::transfer_syntaxes::{TransferSyntaxPolicy, TransferSyntaxCategory, TransferSyntaxInfo, TransferSyntaxRegistry};
+use serde_json::{json, Value};
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+#[derive(Parser)]
+#[command(name = "dicom-info")]
+#[command(about = "Query the SOP class and transfer syntax registries")]
+#[command(version = "1.0")]
+struct Args {
+    /// Print JSON instead of text
+    #[arg(long, global = true)]
+    json: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Look up a SOP class or transfer syntax by UID, name or keyword
+    Lookup {
+        /// UID, name or keyword (e.g. CTImageStorage)
+        query: String,
+    },
+    /// List SOP classes
+    SopClasses {
+        /// Only this category (e.g. ComputedTomography)
+        #[arg(short, long)]
+        category: Option<String>,
+    },
+    /// List transfer syntaxes
+    TransferSyntaxes {
+        /// Only this category (e.g. LosslessCompressed)
+        #[arg(short, long)]
+        category: Option<String>,
+    },
+    /// Check whether two nodes would negotiate a SOP class (and transfer syntax)
+    Negotiate {
+        /// Capability set of the proposing node (JSON)
+        #[arg(long)]
+        scu: PathBuf,
+
+        /// Capability set of the accepting node (JSON)
+        #[arg(long)]
+        scp: PathBuf,
+
+        /// SOP class UID, name or keyword
+        #[arg(long)]
+        sop_class: String,
+
+        /// Transfer syntax that must be the negotiated one
+        #[arg(long)]
+        transfer_syntax: Option<String>,
+
+        /// How the accepting node picks among the transfer syntaxes both support
+        #[arg(long, value_enum, default_value = "proposer-order")]
+        policy: TransferSyntaxPolicy,
+    },
+}
+
+fn main() {
+    let args = Args::parse();
+
+    match run(args) {
+        Ok(true) => {}
+        Ok(false) => std::process::exit(1),
+        Err(e) if e.downcast_ref::<io::Error>().is_some_and(|e| e.kind() == io::ErrorKind::BrokenPipe) => {
+            // Ignore broken pipe errors (e.g., when piped to `head`)
+        }
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            std::process::exit(2);
+        }
+    }
+}
+
+fn sop_class_json(info: &SopClassInfo) -> Value {
+    json!({
+        "kind": "sop_class",
+        "uid": info.uid,
+        "name": info.name,
+        "category": format!("{:?}", info.category),
+    })
+}
+
+fn transfer_syntax_json(info: &TransferSyntaxInfo) -> Value {
+    json!({
+        "kind": "transfer_syntax",
+        "uid": info.uid,
+        "name": info.name,
+        "category": format!("{:?}", info.category),
+        "compression": format!("{:?}", info.compression),
+        "little_endian": info.is_little_endian,
+        "explicit_vr": info.is_explicit_vr,
+        "encapsulated": info.supports_encapsulation,
+        "lossless": info.is_lossless(),
+    })
+}
+
+/// Returns whether the query was answered positively (found, or negotiated)
+fn run(args: Args) -> anyhow::Result<bool> {
+    let sop_classes = SopClassRegistry::global();
+    let transfer_syntaxes = TransferSyntaxRegistry::global();
+
+    match args.command {
+        Command::Lookup { query } => {
+            let found = if let Some(info) = sop_classes.find(&query) {
+                Some((sop_class_json(info), format!("{}\n  SOP class, {:?}", info.uid, info.category), info.name))
+            } else {
+                transfer_syntaxes.find(&query).map(|info| {
+                    let detail = format!(
+                        "{}\n  Transfer syntax, {:?}, compression {:?}, {} endian, {} VR{}",
+                        info.uid, info.category, info.compression,
+                        if info.is_little_endian { "little" } else { "big" },
+                        if info.is_explicit_vr { "explicit" } else { "implicit" },
+                        if info.supports_encapsulation { ", encapsulated" } else { "" },
+                    );
+                    (transfer_syntax_json(info), detail, info.name)
+                })
+            };
+
+            match &found {
+                Some((value, _, _)) if args.json => println!("{}", serde_json::to_string_pretty(&value)?),
+                Some((_, detail, name)) => println!("{}: {}", name, detail),
+                None if args.json => println!("null"),
+                None => eprintln!("No SOP class or transfer syntax matches {}", query),
+            }
+            Ok(found.is_some())
+        }
+        Command::SopClasses { category } => {
+            let classes: Vec<&SopClassInfo> = match category {
+                Some(name) => {
+                    let category = SopClassCategory::from_name(&name)
+                        .ok_or_else(|| anyhow::anyhow!("Unknown SOP class category {} (expected one of {:?})", name, SopClassCategory::ALL))?;
+                    sop_classes.get_by_category(category)
+                }
+                None => sop_classes.get_all_uids().into_iter().filter_map(|uid| sop_classes.get(uid)).collect(),
+            };
+
+            if args.json {
+                let values: Vec<Value> = classes.iter().map(|info| sop_class_json(info)).collect();
+                writeln!(io::stdout(), "{}", serde_json::to_string_pretty(&values)?)?;
+            } else {
+                let mut out = io::stdout().lock();
+                for info in &classes {
+                    writeln!(out, "{:<40} {:<60} {:?}", info.uid, info.name, info.category)?;
+                }
+            }
+            Ok(true)
+        }
+        Command::TransferSyntaxes { category } => {
+            let syntaxes: Vec<&TransferSyntaxInfo> = match category {
+                Some(name) => {
+                    let category = TransferSyntaxCategory::from_name(&name)
+                        .ok_or_else(|| anyhow::anyhow!("Unknown transfer syntax category {} (expected one of {:?})", name, TransferSyntaxCategory::ALL))?;
+                    transfer_syntaxes.get_by_category(category)
+                }
+                None => transfer_syntaxes.get_all_uids().into_iter().filter_map(|uid| transfer_syntaxes.get(uid)).collect(),
+            };
+
+            if args.json {
+                let values: Vec<Value> = syntaxes.iter().map(|info| transfer_syntax_json(info)).collect();
+                writeln!(io::stdout(), "{}", serde_json::to_string_pretty(&values)?)?;
+            } else {
+                let mut out = io::stdout().lock();
+                for info in &syntaxes {
+                    writeln!(out, "{:<32} {:<60} {:?}", info.uid, info.name, info.category)?;
+                }
+            }
+            Ok(true)
+        }
+        Command::Negotiate { scu, scp, sop_class, transfer_syntax, policy } => {
+            let scu = CapabilitySet::load(&scu)?;
+            let scp = CapabilitySet::load(&scp)?;
+            let sop_class_uid = sop_classes.find(&sop_class)
+                .map(|info| info.uid.to_string())
+                .unwrap_or_else(|| sop_class.trim().to_string());
+            let wanted = transfer_syntax.map(|ts| {
+                transfer_syntaxes.find(&ts).map(|info| info.uid.to_string()).unwrap_or_else(|| ts.trim().to_string())
+            });
+
+            // Capability sets may name SOP classes and transfer syntaxes instead of giving UIDs
+            let resolve = |value: &str| {
+                sop_classes.find(value).map(|info| info.uid)
+                    .or_else(|| transfer_syntaxes.find(value).map(|info| info.uid))
+                    .map(str::to_string)
+                    .unwrap_or_else(|| value.trim().to_string())
+            };
+            let outcome = negotiate(&scu, &scp, &sop_class_uid, policy, resolve);
+            let negotiated = match &wanted {
+                Some(ts) => outcome.transfer_syntax.as_ref() == Some(ts),
+                None => outcome.is_accepted(),
+            };
+
+            if args.json {
+                let mut value = serde_json::to_value(&outcome)?;
+                value["negotiated"] = json!(negotiated);
+                value["wanted_transfer_syntax"] = json!(wanted);
+                println!("{}", serde_json::to_string_pretty(&value)?);
+                return Ok(negotiated);
+            }
+
+            let sop_name = sop_classes.get_name(&sop_class_uid).unwrap_or("unknown SOP class");
+            let ts_name = |uid: &str| transfer_syntaxes.get_name(uid).unwrap_or("unknown transfer syntax").to_string();
+            println!("SOP class: {} ({})", sop_name, sop_class_uid);
+            match (outcome.result, &outcome.transfer_syntax) {
+                (None, _) => println!("❌ Not proposed: the SCU capability set does not list this SOP class"),
+                (Some(0), Some(ts)) => println!("✅ Accepted with {} ({})", ts_name(ts), ts),
+                (Some(code), _) => println!("❌ Rejected: {} (result {})", presentation_context_result_text(code), code),
+            }
+            if outcome.common_transfer_syntaxes.len() > 1 {
+                println!("Also supported by both:");
+                for ts in outcome.common_transfer_syntaxes.iter().skip(1) {
+                    println!("  {} ({})", ts_name(ts), ts);
+                }
+            }
+            if let Some(ts) = &wanted {
+                if negotiated {
+                    println!("✅ {} would be negotiated", ts_name(ts));
+                } else if outcome.common_transfer_syntaxes.contains(ts) {
+                    println!("⚠️  {} is supported by both, but another syntax is chosen first", ts_name(ts));
+                } else {
+                    println!("❌ {} is not supported by both nodes", ts_name(ts));
+                }
+            }
+            Ok(negotiated)
+        }
+    }
+}
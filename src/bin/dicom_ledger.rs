@@ -0,0 +1,136 @@
+use clap::{Parser, Subcommand, ValueEnum};
+use rust_dicom::common::ledger::{head_path, read_entries, read_key, verify_entries, verify_head, Direction, LedgerEntry, LedgerHead, RecordedHead};
+use rust_dicom::common::reports::csv_field;
+use std::path::{Path, PathBuf};
+
+#[derive(Parser)]
+#[command(name = "dicom-ledger")]
+#[command(about = "Verify and export the hash-chained transfer ledger")]
+#[command(version = "1.0")]
+struct Args {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Check that the ledger chain is intact
+    Verify {
+        /// Ledger file written by --ledger
+        ledger: PathBuf,
+
+        /// Key the ledger was written with (--ledger-key-file)
+        #[arg(long)]
+        key_file: Option<PathBuf>,
+
+        /// Head to check the ledger against, e.g. one exported earlier; defaults to <ledger>.head
+        #[arg(long)]
+        head: Option<PathBuf>,
+    },
+    /// Verify the ledger and export its entries
+    Export {
+        /// Ledger file written by --ledger
+        ledger: PathBuf,
+
+        /// Key the ledger was written with (--ledger-key-file)
+        #[arg(long)]
+        key_file: Option<PathBuf>,
+
+        /// Output format
+        #[arg(short, long, value_enum, default_value = "csv")]
+        format: ExportFormat,
+
+        /// Write to this file instead of standard output
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy)]
+enum ExportFormat {
+    Csv,
+    Json,
+}
+
+fn main() {
+    let args = Args::parse();
+
+    if let Err(e) = run(args) {
+        eprintln!("❌ {}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run(args: Args) -> anyhow::Result<()> {
+    match args.command {
+        Command::Verify { ledger, key_file, head } => {
+            let (_, verified) = verify(&ledger, key_file.as_deref(), head.as_deref())?;
+            println!("✅ {} entries, chain intact", verified.entries);
+            println!("Head hash: {}", verified.last_hash);
+        }
+        Command::Export { ledger, key_file, format, output } => {
+            let (entries, _) = verify(&ledger, key_file.as_deref(), None)?;
+
+            let exported = match format {
+                ExportFormat::Json => serde_json::to_string_pretty(&entries)?,
+                ExportFormat::Csv => {
+                    let mut csv = String::from(
+                        "seq,timestamp,direction,local_ae,peer_ae,sop_class_uid,sop_instance_uid,study_instance_uid,size,status,prev_hash,hash\n",
+                    );
+                    for entry in &entries {
+                        let r = &entry.record;
+                        let direction = match r.direction {
+                            Direction::Sent => "sent",
+                            Direction::Received => "received",
+                        };
+                        csv.push_str(&format!(
+                            "{},{},{},{},{},{},{},{},{},{},{},{}\n",
+                            entry.seq,
+                            r.timestamp.to_rfc3339(),
+                            direction,
+                            csv_field(&r.local_ae),
+                            csv_field(&r.peer_ae),
+                            r.sop_class_uid,
+                            r.sop_instance_uid,
+                            r.study_instance_uid,
+                            r.size,
+                            csv_field(&r.status),
+                            entry.prev_hash,
+                            entry.hash,
+                        ));
+                    }
+                    csv
+                }
+            };
+
+            match output {
+                Some(path) => {
+                    std::fs::write(&path, exported)?;
+                    eprintln!("✅ Exported {} entries to {}", entries.len(), path.display());
+                }
+                None => print!("{}", exported),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Verify the chain of `ledger` and check it against the recorded `head`, or
+/// the one beside the ledger; only an unkeyed ledger may go without one
+fn verify(ledger: &Path, key_file: Option<&Path>, head: Option<&Path>) -> anyhow::Result<(Vec<LedgerEntry>, LedgerHead)> {
+    let key = key_file.map(read_key).transpose()?;
+    let entries = read_entries(ledger)?;
+    let verified = verify_entries(&entries, key.as_deref())?;
+    let recorded = match head {
+        Some(head) => Some(head.to_path_buf()),
+        None => Some(head_path(ledger)).filter(|head| head.exists()),
+    };
+    match recorded {
+        Some(recorded) => verify_head(&entries, &RecordedHead::from_file(&recorded)?, key.as_deref())?,
+        None if key.is_some() => anyhow::bail!("No recorded head for keyed ledger {}: entries cut from the end cannot be detected",
+                                               ledger.display()),
+        None => eprintln!("⚠️  No recorded head: entries cut from the end cannot be detected"),
+    }
+    Ok((entries, verified))
+}
@@ -0,0 +1,119 @@
+use clap::Parser;
+use dicom::object::open_file;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use rust_dicom::common::fs::collect_dicom_files;
+use rust_dicom::common::nifti::{Slice, Volume};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+#[derive(Parser)]
+#[command(name = "dicom-nifti")]
+#[command(about = "Convert stored CT and MR series to NIfTI volumes")]
+#[command(version = "1.0")]
+struct Args {
+    /// Files or directories holding the series
+    #[arg(required = true)]
+    paths: Vec<PathBuf>,
+
+    /// Output directory for the volumes, named after the Series Instance UID
+    #[arg(short, long)]
+    output: PathBuf,
+
+    /// Recursive directory scanning
+    #[arg(short, long)]
+    recursive: bool,
+
+    /// Write gzip-compressed .nii.gz files
+    #[arg(short = 'z', long)]
+    gzip: bool,
+}
+
+fn main() {
+    let args = Args::parse();
+
+    if let Err(e) = std::fs::create_dir_all(&args.output) {
+        eprintln!("❌ Cannot create {}: {}", args.output.display(), e);
+        std::process::exit(2);
+    }
+
+    let mut files = Vec::new();
+    for path in &args.paths {
+        collect_dicom_files(path, args.recursive, &mut files);
+    }
+
+    // Slices by Series Instance UID
+    let mut series: BTreeMap<String, Vec<Slice>> = BTreeMap::new();
+    let mut skipped = 0;
+    let mut failed = 0;
+
+    for file in &files {
+        let obj = match open_file(file) {
+            Ok(obj) => obj,
+            Err(e) => {
+                eprintln!("❌ {}: {}", file.display(), e);
+                failed += 1;
+                continue;
+            }
+        };
+
+        let text = |keyword: &str| obj.element_by_name(keyword).ok()
+            .and_then(|e| e.to_str().ok())
+            .map(|value| value.trim_end_matches('\0').trim().to_string())
+            .unwrap_or_default();
+        let series_uid = text("SeriesInstanceUID");
+        if !matches!(text("Modality").as_str(), "CT" | "MR") || series_uid.is_empty() {
+            skipped += 1;
+            continue;
+        }
+        match Slice::read(&obj) {
+            Ok(slice) => series.entry(series_uid).or_default().push(slice),
+            Err(e) => {
+                eprintln!("❌ {}: {}", file.display(), e);
+                failed += 1;
+            }
+        }
+    }
+
+    let mut converted = 0;
+    for (series_uid, slices) in series {
+        let extension = if args.gzip { "nii.gz" } else { "nii" };
+        let target = args.output.join(format!("{}.{}", series_uid, extension));
+        let written = Volume::assemble(slices)
+            .and_then(|volume| {
+                write_volume(&volume, &target, args.gzip)?;
+                Ok(volume)
+            });
+        match written {
+            Ok(volume) => {
+                let [columns, rows, slices] = volume.dimensions;
+                println!("🧊 Series {}: {}x{}x{} → {}", series_uid, columns, rows, slices, target.display());
+                converted += 1;
+            }
+            Err(e) => {
+                eprintln!("❌ Series {}: {}", series_uid, e);
+                failed += 1;
+            }
+        }
+    }
+
+    println!();
+    println!("Converted {} series, skipped {} file(s) that are not CT or MR, {} failed", converted, skipped, failed);
+
+    if failed > 0 {
+        std::process::exit(1);
+    }
+}
+
+fn write_volume(volume: &Volume, target: &Path, gzip: bool) -> anyhow::Result<()> {
+    let nifti = volume.to_nifti();
+    if gzip {
+        let mut encoder = GzEncoder::new(std::fs::File::create(target)?, Compression::default());
+        encoder.write_all(&nifti)?;
+        encoder.finish()?;
+    } else {
+        std::fs::write(target, nifti)?;
+    }
+    Ok(())
+}
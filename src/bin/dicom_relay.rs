@@ -0,0 +1,282 @@
+use anyhow::Context;
+use clap::Parser;
+use console::style;
+use dicom::encoding::transfer_syntax::TransferSyntaxIndex;
+use rust_dicom::common::listen::{parse_bind_address, DEFAULT_BIND_ADDRESS};
+use rust_dicom::common::part10::dataset_offset;
+use rust_dicom::common::relay_mapping::{MappingRule, RelayMapping};
+use rust_dicom::common::shutdown::shutdown_signal;
+use rust_dicom::common::size_limits::parse_size;
+use rust_dicom::common::tls::{client_config, server_config, CipherPolicy, DICOM_TLS_PORT};
+use rust_dicom::receiver::receiver::{DicomReceiver, ForwardedDataset, ForwardedObject, ObjectForwarder};
+use rust_dicom::sender::dicom_client::{DicomClient, DicomClientConfig};
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{error, info, warn};
+
+#[derive(Parser)]
+#[command(name = "dicom-relay")]
+#[command(about = "Relay every C-STORE received to a downstream SCP as it arrives, without storing it")]
+#[command(version = "1.0")]
+struct Args {
+    /// AE Title the relay answers to
+    #[arg(short = 'a', long, default_value = "RUST_RELAY")]
+    ae_title: String,
+
+    /// Port to listen on
+    #[arg(short, long, default_value = "4242")]
+    port: u16,
+
+    /// Address to listen on, IPv4 or IPv6
+    #[arg(long, value_parser = parse_bind_address, default_value_t = DEFAULT_BIND_ADDRESS)]
+    bind: std::net::IpAddr,
+
+    /// Called AE Title of the downstream SCP
+    #[arg(long)]
+    called_ae: String,
+
+    /// Host of the downstream SCP
+    #[arg(short = 'H', long)]
+    host: String,
+
+    /// Port of the downstream SCP
+    #[arg(long, default_value = "104")]
+    downstream_port: u16,
+
+    /// Calling AE Title towards the downstream SCP; by default each object
+    /// goes on under the calling AE Title it arrived with
+    #[arg(short = 'c', long)]
+    calling_ae: Option<String>,
+
+    /// Per-address connection timeout towards the downstream SCP, in seconds
+    #[arg(long, default_value = "5")]
+    connect_timeout: u64,
+
+    /// Maximum number of concurrent upstream associations
+    #[arg(long, default_value = "10")]
+    max_connections: usize,
+
+    /// Directory for objects larger than --spool-threshold and for quarantined objects
+    #[arg(long)]
+    spool_dir: Option<PathBuf>,
+
+    /// Objects larger than this are spooled to disk as they arrive instead of held in memory, e.g. 256MB
+    #[arg(long, default_value = "64MB", value_parser = parse_size)]
+    spool_threshold: u64,
+
+    /// Repair common VR and value-length problems before relaying objects
+    #[arg(long)]
+    lenient_repair: bool,
+
+    /// TOML file of rules translating AE titles and attributes per sending device
+    #[arg(long)]
+    mapping: Option<PathBuf>,
+
+    /// Also accept DICOM over TLS, with the PEM certificate chain in this file
+    #[arg(long, requires = "tls_key")]
+    tls_cert: Option<PathBuf>,
+
+    /// PEM private key for --tls-cert
+    #[arg(long, requires = "tls_cert")]
+    tls_key: Option<PathBuf>,
+
+    /// Port for DICOM over TLS
+    #[arg(long, default_value_t = DICOM_TLS_PORT)]
+    tls_port: u16,
+
+    /// Require TLS clients to present a certificate issued by a CA in this PEM file
+    #[arg(long, requires = "tls_cert")]
+    tls_client_ca: Option<PathBuf>,
+
+    /// TLS versions and cipher suites: bcp195, tls13, or a comma-separated list of suites
+    #[arg(long, default_value = "bcp195", value_parser = CipherPolicy::parse)]
+    tls_cipher_policy: CipherPolicy,
+
+    /// Accept DICOM over TLS only, not on --port
+    #[arg(long, requires = "tls_cert")]
+    tls_only: bool,
+
+    /// Relay over DICOM TLS to the downstream SCP; Linux only
+    #[arg(long)]
+    downstream_tls: bool,
+
+    /// Trust only the CAs in this PEM file for the downstream SCP
+    #[arg(long, requires = "downstream_tls")]
+    downstream_tls_ca: Option<PathBuf>,
+
+    /// Client certificate chain (PEM) presented to the downstream SCP
+    #[arg(long, requires_all = ["downstream_tls", "downstream_tls_key"])]
+    downstream_tls_cert: Option<PathBuf>,
+
+    /// PEM private key for --downstream-tls-cert
+    #[arg(long, requires_all = ["downstream_tls", "downstream_tls_cert"])]
+    downstream_tls_key: Option<PathBuf>,
+}
+
+/// Forwards received objects to the downstream SCP, one association each
+struct Relay {
+    downstream: DicomClientConfig,
+    /// Relay under the upstream calling AE Title rather than the configured one
+    preserve_calling_ae: bool,
+    mapping: RelayMapping,
+}
+
+impl Relay {
+    /// Relay one object and return the status to answer its sender with
+    fn forward(&self, object: &ForwardedObject) -> u16 {
+        let rule = self.mapping.rule_for(object.calling_ae, object.called_ae);
+        let mut config = self.downstream.clone();
+        match rule.and_then(|rule| rule.new_calling_ae.as_deref()) {
+            Some(calling_ae) => config.calling_ae = calling_ae.trim().to_string(),
+            None if self.preserve_calling_ae => config.calling_ae = object.calling_ae.trim().to_string(),
+            None => {}
+        }
+        if let Some(called_ae) = rule.and_then(|rule| rule.new_called_ae.as_deref()) {
+            config.called_ae = called_ae.trim().to_string();
+        }
+        let route = format!("{} → {}@{}", object.calling_ae.trim(), config.calling_ae, config.called_ae);
+        let client = DicomClient::new(config);
+        let relayed = translate(rule, object).and_then(|translated| match (translated, &object.dataset) {
+            (Some(dataset), _) => client.relay_dataset(
+                object.sop_class_uid, object.sop_instance_uid, object.transfer_syntax_uid, &mut &dataset[..]),
+            (None, ForwardedDataset::Memory(dataset)) => client.relay_dataset(
+                object.sop_class_uid, object.sop_instance_uid, object.transfer_syntax_uid, &mut &dataset[..]),
+            (None, ForwardedDataset::Spooled(path)) => open_spooled(path).and_then(|mut dataset| client.relay_dataset(
+                object.sop_class_uid, object.sop_instance_uid, object.transfer_syntax_uid, &mut dataset)),
+        });
+        match relayed {
+            Ok(status) => {
+                info!("Relayed {} ({}): status 0x{:04X}", object.sop_instance_uid, route, status);
+                println!("🔁 {}: 0x{:04X}", route, status);
+                status
+            }
+            Err(e) => {
+                error!("Failed to relay {} ({}): {:#}", object.sop_instance_uid, route, e);
+                println!("❌ {}: {:#}", route, e);
+                // Refused: Out of Resources, so the sender tries again later
+                0xA700
+            }
+        }
+    }
+}
+
+/// The data set re-encoded with the attributes of `rule` set, or `None` when
+/// it goes on as received
+fn translate(rule: Option<&MappingRule>, object: &ForwardedObject) -> anyhow::Result<Option<Vec<u8>>> {
+    let Some(rule) = rule.filter(|rule| !rule.attributes.is_empty()) else {
+        return Ok(None);
+    };
+    let mut obj = match (&object.dataset, object.object) {
+        (ForwardedDataset::Memory(_), Some(obj)) => obj.clone(),
+        (ForwardedDataset::Memory(_), None) => anyhow::bail!("the data set could not be parsed to translate its attributes"),
+        // Only the attributes up to the pixel data were read from a spooled object
+        (ForwardedDataset::Spooled(path), _) => dicom_object::open_file(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?
+            .into_inner(),
+    };
+    if rule.apply(&mut obj)? == 0 {
+        return Ok(None);
+    }
+    let ts_uid = object.transfer_syntax_uid.trim_end_matches('\0');
+    let ts = dicom_transfer_syntax_registry::TransferSyntaxRegistry.get(ts_uid)
+        .with_context(|| format!("Unknown transfer syntax: {}", ts_uid))?;
+    let mut dataset = Vec::new();
+    obj.write_dataset_with_ts(&mut dataset, ts).context("Failed to encode translated data set")?;
+    Ok(Some(dataset))
+}
+
+/// The data set of a spooled Part 10 file, read from just after its File Meta Information
+fn open_spooled(path: &Path) -> anyhow::Result<BufReader<std::fs::File>> {
+    let mut file = std::fs::File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut head = Vec::new();
+    (&mut file).take(4096).read_to_end(&mut head)?;
+    let offset = dataset_offset(&head).with_context(|| format!("{} is not a Part 10 file", path.display()))?;
+    file.seek(SeekFrom::Start(offset as u64))?;
+    Ok(BufReader::new(file))
+}
+
+#[tokio::main]
+async fn main() {
+    let args = Args::parse();
+
+    if let Err(e) = run(args).await {
+        eprintln!("❌ {:#}", e);
+        std::process::exit(1);
+    }
+}
+
+async fn run(args: Args) -> anyhow::Result<()> {
+    std::fs::create_dir_all("logs")?;
+    let log_file = format!("logs/dicom_relay_{}.log", uuid::Uuid::new_v4());
+    tracing_subscriber::fmt()
+        .with_writer(std::fs::File::create(&log_file)?)
+        .init();
+
+    let spool_dir = args.spool_dir.clone().unwrap_or_else(|| std::env::temp_dir().join("dicom-relay"));
+    std::fs::create_dir_all(&spool_dir).with_context(|| format!("Failed to create {}", spool_dir.display()))?;
+
+    let tls = if args.downstream_tls {
+        let identity = args.downstream_tls_cert.as_deref().zip(args.downstream_tls_key.as_deref());
+        Some(client_config(args.downstream_tls_ca.as_deref(), identity, &CipherPolicy::Bcp195)?)
+    } else {
+        None
+    };
+    let relay = Relay {
+        downstream: DicomClientConfig {
+            calling_ae: args.calling_ae.clone().unwrap_or_default(),
+            called_ae: args.called_ae.clone(),
+            host: args.host.clone(),
+            port: args.downstream_port,
+            timeout: Duration::from_secs(30),
+            connect_timeout: Duration::from_secs(args.connect_timeout),
+            lenient_repair: false,
+            compute_checksums: false,
+            pack_pdvs: false,
+            tls,
+            preview: None,
+        },
+        preserve_calling_ae: args.calling_ae.is_none(),
+        mapping: match &args.mapping {
+            Some(path) => RelayMapping::load(path)?,
+            None => RelayMapping::default(),
+        },
+    };
+
+    println!("🔁 DICOM Relay v1.0");
+    println!("Log file: {}", style(&log_file).yellow());
+    println!("Listening: {} on {}:{}", style(&args.ae_title).green(), args.bind, style(args.port).green());
+    println!("Downstream: {}@{}:{}{}", style(&args.called_ae).green(), args.host, args.downstream_port,
+             if args.downstream_tls { " over TLS" } else { "" });
+    match &args.calling_ae {
+        Some(calling_ae) => println!("Calling AE: {}", style(calling_ae).green()),
+        None => println!("Calling AE: {}", style("as received").green()),
+    }
+    if let Some(path) = &args.mapping {
+        println!("Mapping rules: {} ({} rules)", style(path.display()).green(), relay.mapping.len());
+    }
+    println!("Spool: {} (objects over {} bytes)", style(spool_dir.display()).green(), args.spool_threshold);
+
+    let mut receiver = DicomReceiver::new(args.ae_title.clone(), spool_dir, args.max_connections)
+        .with_bind_address(args.bind, false)
+        .with_spool_threshold(args.spool_threshold)
+        .with_lenient_repair(args.lenient_repair)
+        .with_forwarder(ObjectForwarder::new(move |object| relay.forward(object)));
+    if args.lenient_repair {
+        println!("Lenient repair: {}", style("enabled").green());
+    }
+    if let (Some(certificate), Some(private_key)) = (&args.tls_cert, &args.tls_key) {
+        let config = server_config(certificate, private_key, args.tls_client_ca.as_deref(), &args.tls_cipher_policy)?;
+        println!("DICOM over TLS: {} on port {}", style("enabled").green(), args.tls_port);
+        receiver = receiver.with_tls(args.tls_port, config);
+        if args.tls_only {
+            receiver = receiver.with_plaintext_listener(false);
+        }
+    }
+    println!();
+
+    Arc::new(receiver).start(args.port, shutdown_signal()).await?;
+    println!("🔁 Relay stopped");
+    Ok(())
+}
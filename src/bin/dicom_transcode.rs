@@ -0,0 +1,85 @@
+use clap::Parser;
+use dicom::object::open_file;
+use rust_dicom::common::charset::transcode_to_utf8;
+use rust_dicom::common::fs::collect_dicom_files;
+use std::path::PathBuf;
+
+#[derive(Parser)]
+#[command(name = "dicom-transcode")]
+#[command(about = "Export DICOM files with legacy character sets rewritten to UTF-8")]
+#[command(version = "1.0")]
+struct Args {
+    /// Files or directories to export
+    #[arg(required = true)]
+    paths: Vec<PathBuf>,
+
+    /// Output directory for the exported files
+    #[arg(short, long)]
+    output: PathBuf,
+
+    /// Recursive directory scanning
+    #[arg(short, long)]
+    recursive: bool,
+
+    /// Keep the original character set and text values in an Original Attributes Sequence
+    #[arg(long)]
+    preserve_original: bool,
+}
+
+fn main() {
+    let args = Args::parse();
+
+    if let Err(e) = std::fs::create_dir_all(&args.output) {
+        eprintln!("❌ Cannot create {}: {}", args.output.display(), e);
+        std::process::exit(2);
+    }
+
+    let mut files = Vec::new();
+    for path in &args.paths {
+        collect_dicom_files(path, args.recursive, &mut files);
+    }
+
+    let mut transcoded = 0;
+    let mut unchanged = 0;
+    let mut failed = 0;
+
+    for file in &files {
+        let mut obj = match open_file(file) {
+            Ok(obj) => obj,
+            Err(e) => {
+                eprintln!("❌ {}: {}", file.display(), e);
+                failed += 1;
+                continue;
+            }
+        };
+
+        match transcode_to_utf8(&mut obj, args.preserve_original) {
+            Ok(Some(report)) => {
+                println!("🔤 {}: {} → UTF-8 ({} text elements)",
+                         file.display(), report.original_charset.join("\\"), report.transcoded_tags.len());
+                transcoded += 1;
+            }
+            Ok(None) => unchanged += 1,
+            Err(e) => {
+                eprintln!("❌ {}: {}", file.display(), e);
+                failed += 1;
+                continue;
+            }
+        }
+
+        let file_name = file.file_name().map(PathBuf::from).unwrap_or_else(|| PathBuf::from("export.dcm"));
+        let target = args.output.join(file_name);
+        if let Err(e) = obj.write_to_file(&target) {
+            eprintln!("❌ Failed to write {}: {}", target.display(), e);
+            failed += 1;
+        }
+    }
+
+    println!();
+    println!("Exported {} file(s): {} transcoded, {} already UTF-8 compatible, {} failed",
+             transcoded + unchanged, transcoded, unchanged, failed);
+
+    if failed > 0 {
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,90 @@
+use clap::Parser;
+use dicom::object::open_file;
+use rust_dicom::common::fs::collect_dicom_files;
+use rust_dicom::common::iod::validate_iod;
+use std::path::PathBuf;
+
+#[derive(Parser)]
+#[command(name = "dicom-validate")]
+#[command(about = "Check DICOM files against IOD module and attribute requirements")]
+#[command(version = "1.0")]
+struct Args {
+    /// Files or directories to validate
+    #[arg(required = true)]
+    paths: Vec<PathBuf>,
+
+    /// Recursive directory scanning
+    #[arg(short, long)]
+    recursive: bool,
+
+    /// Print the reports as JSON
+    #[arg(long)]
+    json: bool,
+}
+
+fn main() {
+    let args = Args::parse();
+
+    let mut files = Vec::new();
+    for path in &args.paths {
+        collect_dicom_files(path, args.recursive, &mut files);
+    }
+
+    let mut checked = 0;
+    let mut non_conformant = 0;
+    let mut reports = Vec::new();
+
+    for file in &files {
+        let obj = match open_file(file) {
+            Ok(obj) => obj,
+            Err(e) => {
+                eprintln!("❌ {}: {}", file.display(), e);
+                non_conformant += 1;
+                continue;
+            }
+        };
+
+        let report = match validate_iod(&obj) {
+            Some(report) => report,
+            None => {
+                if !args.json {
+                    println!("➖ {}: no IOD definition for this SOP class", file.display());
+                }
+                continue;
+            }
+        };
+
+        checked += 1;
+        if !report.is_conformant() {
+            non_conformant += 1;
+        }
+
+        if args.json {
+            reports.push(serde_json::json!({
+                "file": file.display().to_string(),
+                "report": report,
+            }));
+        } else if report.is_conformant() {
+            println!("✅ {}: conforms to {}", file.display(), report.iod);
+        } else {
+            println!("⚠️  {}: {} violation(s) of {}", file.display(), report.violations.len(), report.iod);
+            for violation in &report.violations {
+                println!("   • [{}] {} {}: {:?}", violation.module, violation.tag, violation.keyword, violation.kind);
+            }
+        }
+    }
+
+    if args.json {
+        match serde_json::to_string_pretty(&reports) {
+            Ok(json) => println!("{}", json),
+            Err(e) => eprintln!("Error: {}", e),
+        }
+    } else {
+        println!();
+        println!("Checked {} file(s), {} with problems", checked, non_conformant);
+    }
+
+    if non_conformant > 0 {
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,96 @@
+use clap::Parser;
+use dicom::object::open_file;
+use rust_dicom::common::fs::collect_dicom_files;
+use rust_dicom::common::video::{export_mp4, VideoOptions};
+use std::path::PathBuf;
+
+#[derive(Parser)]
+#[command(name = "dicom-video")]
+#[command(about = "Export multi-frame ultrasound and angiography clips as H.264 MP4 video")]
+#[command(version = "1.0")]
+struct Args {
+    /// Files or directories to export
+    #[arg(required = true)]
+    paths: Vec<PathBuf>,
+
+    /// Output directory for the videos, named after the SOP Instance UID
+    #[arg(short, long)]
+    output: PathBuf,
+
+    /// Recursive directory scanning
+    #[arg(short, long)]
+    recursive: bool,
+
+    /// Modalities to export (repeatable)
+    #[arg(long = "modality", default_values = ["US", "XA", "RF"])]
+    modalities: Vec<String>,
+
+    /// Largest number of rows or columns; larger clips are shrunk by an integer factor
+    #[arg(long, default_value = "1920")]
+    max_size: u16,
+
+    /// Target bit rate in kbit/s
+    #[arg(long, default_value = "2000")]
+    bitrate: u32,
+}
+
+fn main() {
+    let args = Args::parse();
+
+    if let Err(e) = std::fs::create_dir_all(&args.output) {
+        eprintln!("❌ Cannot create {}: {}", args.output.display(), e);
+        std::process::exit(2);
+    }
+    let options = VideoOptions { max_dimension: args.max_size, bitrate: args.bitrate.saturating_mul(1000) };
+
+    let mut files = Vec::new();
+    for path in &args.paths {
+        collect_dicom_files(path, args.recursive, &mut files);
+    }
+
+    let mut exported = 0;
+    let mut skipped = 0;
+    let mut failed = 0;
+
+    for file in &files {
+        let obj = match open_file(file) {
+            Ok(obj) => obj,
+            Err(e) => {
+                eprintln!("❌ {}: {}", file.display(), e);
+                failed += 1;
+                continue;
+            }
+        };
+
+        let text = |keyword: &str| obj.element_by_name(keyword).ok()
+            .and_then(|e| e.to_str().ok())
+            .map(|value| value.trim_end_matches('\0').trim().to_string())
+            .unwrap_or_default();
+        let frames = text("NumberOfFrames").parse::<u32>().unwrap_or(1);
+        if frames < 2 || !args.modalities.iter().any(|modality| modality.eq_ignore_ascii_case(&text("Modality"))) {
+            skipped += 1;
+            continue;
+        }
+
+        let uid = text("SOPInstanceUID");
+        let name = if uid.is_empty() { file.file_stem().unwrap_or_default().to_string_lossy().to_string() } else { uid };
+        let target = args.output.join(format!("{}.mp4", name));
+        match export_mp4(&obj, &options).and_then(|video| Ok(std::fs::write(&target, video)?)) {
+            Ok(()) => {
+                println!("🎞️  {}: {} frames → {}", file.display(), frames, target.display());
+                exported += 1;
+            }
+            Err(e) => {
+                eprintln!("❌ {}: {}", file.display(), e);
+                failed += 1;
+            }
+        }
+    }
+
+    println!();
+    println!("Exported {} clip(s), skipped {} single-frame or other-modality file(s), {} failed", exported, skipped, failed);
+
+    if failed > 0 {
+        std::process::exit(1);
+    }
+}
@@ -15,7 +15,7 @@ fn main() {
 }
 
 fn run() -> io::Result<()> {
-    let registry = SopClassRegistry::new();
+    let registry = SopClassRegistry::global();
     let all_uids = registry.get_all_uids();
     
     println!("🚀 Comprehensive SOP Class Support");
@@ -16,7 +16,7 @@ fn main() {
 }
 
 fn run() -> io::Result<()> {
-    let ts_registry = TransferSyntaxRegistry::new();
+    let ts_registry = TransferSyntaxRegistry::global();
     
     println!("🚀 Comprehensive Transfer Syntax Support");
     println!("=====================================");
@@ -24,8 +24,8 @@ fn run() -> io::Result<()> {
     println!();
     
     // Show transfer syntaxes by category
-    show_by_category(&ts_registry, TransferSyntaxCategory::Uncompressed, "📋 Uncompressed Transfer Syntaxes")?;
-    show_by_category(&ts_registry, TransferSyntaxCategory::LosslessCompressed, "🔒 Lossless Compressed Transfer Syntaxes")?;
+    show_by_category(ts_registry, TransferSyntaxCategory::Uncompressed, "📋 Uncompressed Transfer Syntaxes")?;
+    show_by_category(ts_registry, TransferSyntaxCategory::LosslessCompressed, "🔒 Lossless Compressed Transfer Syntaxes")?;
     
     // Try to flush and catch potential broken pipe early
     if let Err(e) = io::stdout().flush() {
@@ -35,9 +35,9 @@ fn run() -> io::Result<()> {
         return Err(e);
     }
     
-    show_by_category(&ts_registry, TransferSyntaxCategory::LossyCompressed, "📉 Lossy Compressed Transfer Syntaxes")?;
-    show_by_category(&ts_registry, TransferSyntaxCategory::Video, "🎬 Video Transfer Syntaxes")?;
-    show_by_category(&ts_registry, TransferSyntaxCategory::Legacy, "📜 Legacy Transfer Syntaxes")?;
+    show_by_category(ts_registry, TransferSyntaxCategory::LossyCompressed, "📉 Lossy Compressed Transfer Syntaxes")?;
+    show_by_category(ts_registry, TransferSyntaxCategory::Video, "🎬 Video Transfer Syntaxes")?;
+    show_by_category(ts_registry, TransferSyntaxCategory::Legacy, "📜 Legacy Transfer Syntaxes")?;
     
     println!();
     println!("✨ Key Benefits:");
@@ -81,7 +81,7 @@ fn show_smart_selection(sop_registry: &SopClassRegistry, category: SopClassCateg
     
     println!("📌 {} ({} transfer syntaxes):", name, transfer_syntaxes.len());
     
-    let ts_registry = TransferSyntaxRegistry::new();
+    let ts_registry = TransferSyntaxRegistry::global();
     let mut shown = 0;
     for ts_uid in transfer_syntaxes.iter().take(5) {
         if let Some(ts_info) = ts_registry.get(ts_uid) {
@@ -0,0 +1,95 @@
+use clap::Parser;
+use rust_dicom::common::manifest::{hash_file, Manifest};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use walkdir::WalkDir;
+
+#[derive(Parser)]
+#[command(name = "dicom-verify-manifest")]
+#[command(about = "Confirm that stored DICOM files match a sender checksum manifest")]
+#[command(version = "1.0")]
+struct Args {
+    /// Manifest written by dicom-sender --manifest
+    manifest: PathBuf,
+
+    /// Directory holding the received files (e.g. the receiver output directory)
+    directory: PathBuf,
+
+    /// File containing the HMAC key the manifest was signed with
+    #[arg(long)]
+    key_file: Option<PathBuf>,
+
+    /// List every unexpected file instead of only counting them
+    #[arg(short, long)]
+    verbose: bool,
+}
+
+fn main() {
+    let args = Args::parse();
+
+    if let Err(e) = run(&args) {
+        eprintln!("❌ {}", e);
+        std::process::exit(2);
+    }
+}
+
+fn run(args: &Args) -> anyhow::Result<()> {
+    let manifest = Manifest::from_file(&args.manifest)?;
+    println!("📋 Manifest {}: {} instances", manifest.session_id, manifest.entries.len());
+
+    let mut failed = false;
+
+    match &args.key_file {
+        Some(key_file) => {
+            let key = std::fs::read_to_string(key_file)?;
+            if manifest.verify_signature(key.trim().as_bytes()) {
+                println!("✅ Signature valid");
+            } else {
+                println!("❌ Signature invalid or missing");
+                failed = true;
+            }
+        }
+        None if manifest.signature.is_some() => {
+            println!("⚠️  Manifest is signed but no --key-file was given; signature not checked");
+        }
+        None => {}
+    }
+
+    let mut stored = HashMap::new();
+    for entry in WalkDir::new(&args.directory).into_iter().filter_map(|e| e.ok()) {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        match hash_file(entry.path()) {
+            Ok(hash) => {
+                stored.insert(hash, entry.path().to_path_buf());
+            }
+            Err(e) => eprintln!("⚠️  {}: {}", entry.path().display(), e),
+        }
+    }
+
+    let report = manifest.verify(&stored);
+    println!("✅ {} of {} instances verified", report.matched, manifest.entries.len());
+
+    if !report.missing.is_empty() {
+        failed = true;
+        println!("❌ {} instances missing or altered:", report.missing.len());
+        for entry in &report.missing {
+            println!("   • {} ({})", entry.sop_instance_uid, entry.source_path);
+        }
+    }
+
+    if !report.unexpected.is_empty() {
+        println!("➖ {} files not listed in the manifest", report.unexpected.len());
+        if args.verbose {
+            for path in &report.unexpected {
+                println!("   • {}", path.display());
+            }
+        }
+    }
+
+    if failed {
+        std::process::exit(1);
+    }
+    Ok(())
+}
@@ -0,0 +1,255 @@
+//! Corner annotations burned into previews
+//!
+//! PACS exports usually carry the patient's name, ID and study date in the
+//! image corners. Each annotation puts one line of text into a corner; the
+//! text may use the placeholders of filename templates (`{patient_name}`,
+//! `{patient_id}`, `{study_date}`, ...) or be a literal such as `ANONYMIZED`.
+//! Several annotations for the same corner stack from the edge inwards. Text is
+//! drawn with a built-in 5x7 font, upper case, with a dark outline so it stays
+//! legible on bright anatomy, and scaled up for larger images.
+
+use super::naming::PLACEHOLDERS;
+
+pub const GLYPH_WIDTH: usize = 5;
+pub const GLYPH_HEIGHT: usize = 7;
+
+/// Rows of each glyph, most significant of the five bits leftmost
+const FONT: [(char, [u8; GLYPH_HEIGHT]); 52] = [
+    (' ', [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]),
+    ('#', [0x0A, 0x0A, 0x1F, 0x0A, 0x1F, 0x0A, 0x0A]),
+    ('\'', [0x0C, 0x04, 0x08, 0x00, 0x00, 0x00, 0x00]),
+    ('(', [0x02, 0x04, 0x08, 0x08, 0x08, 0x04, 0x02]),
+    (')', [0x08, 0x04, 0x02, 0x02, 0x02, 0x04, 0x08]),
+    ('*', [0x00, 0x04, 0x15, 0x0E, 0x15, 0x04, 0x00]),
+    ('+', [0x00, 0x04, 0x04, 0x1F, 0x04, 0x04, 0x00]),
+    (',', [0x00, 0x00, 0x00, 0x00, 0x0C, 0x04, 0x08]),
+    ('-', [0x00, 0x00, 0x00, 0x1F, 0x00, 0x00, 0x00]),
+    ('.', [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C]),
+    ('/', [0x00, 0x01, 0x02, 0x04, 0x08, 0x10, 0x00]),
+    ('0', [0x0E, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0E]),
+    ('1', [0x04, 0x0C, 0x04, 0x04, 0x04, 0x04, 0x0E]),
+    ('2', [0x0E, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1F]),
+    ('3', [0x1F, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0E]),
+    ('4', [0x02, 0x06, 0x0A, 0x12, 0x1F, 0x02, 0x02]),
+    ('5', [0x1F, 0x10, 0x1E, 0x01, 0x01, 0x11, 0x0E]),
+    ('6', [0x06, 0x08, 0x10, 0x1E, 0x11, 0x11, 0x0E]),
+    ('7', [0x1F, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08]),
+    ('8', [0x0E, 0x11, 0x11, 0x0E, 0x11, 0x11, 0x0E]),
+    ('9', [0x0E, 0x11, 0x11, 0x0F, 0x01, 0x02, 0x0C]),
+    (':', [0x00, 0x0C, 0x0C, 0x00, 0x0C, 0x0C, 0x00]),
+    ('=', [0x00, 0x00, 0x1F, 0x00, 0x1F, 0x00, 0x00]),
+    ('?', [0x0E, 0x11, 0x01, 0x02, 0x04, 0x00, 0x04]),
+    ('A', [0x0E, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11]),
+    ('B', [0x1E, 0x11, 0x11, 0x1E, 0x11, 0x11, 0x1E]),
+    ('C', [0x0E, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0E]),
+    ('D', [0x1E, 0x11, 0x11, 0x11, 0x11, 0x11, 0x1E]),
+    ('E', [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x1F]),
+    ('F', [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x10]),
+    ('G', [0x0E, 0x11, 0x10, 0x17, 0x11, 0x11, 0x0F]),
+    ('H', [0x11, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11]),
+    ('I', [0x0E, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0E]),
+    ('J', [0x07, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0C]),
+    ('K', [0x11, 0x12, 0x14, 0x18, 0x14, 0x12, 0x11]),
+    ('L', [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1F]),
+    ('M', [0x11, 0x1B, 0x15, 0x15, 0x11, 0x11, 0x11]),
+    ('N', [0x11, 0x11, 0x19, 0x15, 0x13, 0x11, 0x11]),
+    ('O', [0x0E, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E]),
+    ('P', [0x1E, 0x11, 0x11, 0x1E, 0x10, 0x10, 0x10]),
+    ('Q', [0x0E, 0x11, 0x11, 0x11, 0x15, 0x12, 0x0D]),
+    ('R', [0x1E, 0x11, 0x11, 0x1E, 0x14, 0x12, 0x11]),
+    ('S', [0x0F, 0x10, 0x10, 0x0E, 0x01, 0x01, 0x1E]),
+    ('T', [0x1F, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04]),
+    ('U', [0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E]),
+    ('V', [0x11, 0x11, 0x11, 0x11, 0x11, 0x0A, 0x04]),
+    ('W', [0x11, 0x11, 0x11, 0x15, 0x15, 0x15, 0x0A]),
+    ('X', [0x11, 0x11, 0x0A, 0x04, 0x0A, 0x11, 0x11]),
+    ('Y', [0x11, 0x11, 0x0A, 0x04, 0x04, 0x04, 0x04]),
+    ('Z', [0x1F, 0x01, 0x02, 0x04, 0x08, 0x10, 0x1F]),
+    ('^', [0x04, 0x0A, 0x11, 0x00, 0x00, 0x00, 0x00]),
+    ('_', [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1F]),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Literal(String),
+    Attribute((u16, u16)),
+}
+
+/// One line of text in a corner
+#[derive(Debug, Clone, PartialEq)]
+pub struct Annotation {
+    pub corner: Corner,
+    parts: Vec<Part>,
+}
+
+impl Annotation {
+    /// `<corner>:<text>`, the corner one of `top-left`, `top-right`,
+    /// `bottom-left` or `bottom-right`, e.g. `top-left:{patient_name} {patient_id}`
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let (corner, text) = spec.split_once(':')
+            .ok_or_else(|| format!("annotation '{}' is not <corner>:<text>", spec))?;
+        let corner = match corner.trim().to_ascii_lowercase().as_str() {
+            "top-left" => Corner::TopLeft,
+            "top-right" => Corner::TopRight,
+            "bottom-left" => Corner::BottomLeft,
+            "bottom-right" => Corner::BottomRight,
+            other => return Err(format!("unknown corner '{}', expected top-left, top-right, bottom-left or bottom-right", other)),
+        };
+
+        let mut parts = Vec::new();
+        let mut rest = text;
+        while let Some(start) = rest.find('{') {
+            let end = rest[start..].find('}')
+                .map(|end| start + end)
+                .ok_or_else(|| format!("unclosed '{{' in annotation '{}'", spec))?;
+            let name = &rest[start + 1..end];
+            let tag = PLACEHOLDERS.iter()
+                .find(|(placeholder, _)| *placeholder == name)
+                .map(|(_, tag)| *tag)
+                .ok_or_else(|| format!("unknown placeholder {{{}}}, expected one of {}", name,
+                                       PLACEHOLDERS.iter().map(|(placeholder, _)| *placeholder).collect::<Vec<_>>().join(", ")))?;
+            if start > 0 {
+                parts.push(Part::Literal(rest[..start].to_string()));
+            }
+            parts.push(Part::Attribute(tag));
+            rest = &rest[end + 1..];
+        }
+        if !rest.is_empty() {
+            parts.push(Part::Literal(rest.to_string()));
+        }
+        Ok(Self { corner, parts })
+    }
+
+    /// The text, each placeholder replaced by the value `value` gives for its
+    /// tag; missing values are left out
+    pub fn expand(&self, value: impl Fn((u16, u16)) -> Option<String>) -> String {
+        let mut text = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Literal(literal) => text.push_str(literal),
+                Part::Attribute(tag) => text.push_str(value(*tag).as_deref().unwrap_or_default().trim_end_matches('\0').trim()),
+            }
+        }
+        text.trim().to_string()
+    }
+}
+
+/// Glyph rows of `c`; lower case is drawn as upper case and characters
+/// without a glyph as `?`
+fn glyph(c: char) -> [u8; GLYPH_HEIGHT] {
+    let c = c.to_ascii_uppercase();
+    FONT.iter()
+        .find(|(glyph, _)| *glyph == c)
+        .or_else(|| FONT.iter().find(|(glyph, _)| *glyph == '?'))
+        .map(|(_, rows)| *rows)
+        .unwrap_or_default()
+}
+
+/// Draw `lines` into interleaved 8-bit pixels in `foreground`, outlined in
+/// its opposite; text running past the image edge is cut off
+pub fn burn_in(pixels: &mut [u8], rows: u16, columns: u16, samples_per_pixel: u16, lines: &[(Corner, String)], foreground: u8) {
+    let (rows, columns, samples) = (rows as usize, columns as usize, samples_per_pixel as usize);
+    let scale = (rows.min(columns) / 256).max(1);
+    let margin = 2 * scale;
+    let advance = (GLYPH_WIDTH + 1) * scale;
+    let line_height = (GLYPH_HEIGHT + 2) * scale;
+
+    let mut text = vec![false; rows * columns];
+    for corner in [Corner::TopLeft, Corner::TopRight, Corner::BottomLeft, Corner::BottomRight] {
+        let corner_lines: Vec<&str> = lines.iter().filter(|(c, _)| *c == corner).map(|(_, line)| line.as_str()).collect();
+        for (i, line) in corner_lines.iter().enumerate() {
+            let width = line.chars().count() * advance;
+            let top = match corner {
+                Corner::TopLeft | Corner::TopRight => (margin + i * line_height) as isize,
+                _ => rows as isize - (margin + (corner_lines.len() - i) * line_height) as isize,
+            };
+            let left = match corner {
+                Corner::TopLeft | Corner::BottomLeft => margin as isize,
+                _ => columns as isize - (margin + width) as isize,
+            };
+            for (n, c) in line.chars().enumerate() {
+                for (glyph_row, bits) in glyph(c).iter().enumerate() {
+                    for glyph_column in (0..GLYPH_WIDTH).filter(|column| bits & (0x10 >> column) != 0) {
+                        for dy in 0..scale {
+                            for dx in 0..scale {
+                                let y = top + (glyph_row * scale + dy) as isize;
+                                let x = left + (n * advance + glyph_column * scale + dx) as isize;
+                                if (0..rows as isize).contains(&y) && (0..columns as isize).contains(&x) {
+                                    text[y as usize * columns + x as usize] = true;
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    let mut outline = vec![false; rows * columns];
+    for (index, _) in text.iter().enumerate().filter(|(_, set)| **set) {
+        let (y, x) = (index / columns, index % columns);
+        for oy in y.saturating_sub(scale)..(y + scale + 1).min(rows) {
+            for ox in x.saturating_sub(scale)..(x + scale + 1).min(columns) {
+                outline[oy * columns + ox] = true;
+            }
+        }
+    }
+    for (index, (set, outlined)) in text.iter().zip(&outline).enumerate() {
+        let value = match (set, outlined) {
+            (true, _) => foreground,
+            (false, true) => 255 - foreground,
+            (false, false) => continue,
+        };
+        pixels[index * samples..(index + 1) * samples].fill(value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_expand() {
+        let annotation = Annotation::parse("top-left:{patient_name} ({patient_id})").unwrap();
+        assert_eq!(annotation.corner, Corner::TopLeft);
+        let value = |tag: (u16, u16)| match tag {
+            (0x0010, 0x0010) => Some("DOE^JANE ".to_string()),
+            (0x0010, 0x0020) => Some("PAT001\0".to_string()),
+            _ => None,
+        };
+        assert_eq!(annotation.expand(value), "DOE^JANE (PAT001)");
+        assert_eq!(Annotation::parse("Bottom-Right:ANONYMIZED").unwrap().expand(value), "ANONYMIZED");
+        assert_eq!(Annotation::parse("top-right:{study_date}").unwrap().expand(value), "");
+
+        for invalid in ["ANONYMIZED", "middle:text", "top-left:{nope}", "top-left:{patient_id"] {
+            assert!(Annotation::parse(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_burn_in_corners() {
+        let (rows, columns) = (40u16, 60u16);
+        let mut pixels = vec![128u8; rows as usize * columns as usize];
+        burn_in(&mut pixels, rows, columns, 1, &[(Corner::TopLeft, "A".to_string()), (Corner::BottomRight, "I".to_string())], 255);
+        let at = |y: usize, x: usize| pixels[y * columns as usize + x];
+
+        // Top row of the A starts one column in, with the outline around it
+        assert_eq!((at(2, 2), at(2, 3), at(2, 5)), (0, 255, 255));
+        assert_eq!(at(1, 3), 0);
+        // The I ends at the margin of the bottom right corner
+        let (bottom, right) = (rows as usize - 2 - 9, columns as usize - 2);
+        assert_eq!(at(bottom + 6, right - 5), 255);
+        assert_eq!((at(bottom + 6, right - 2), at(bottom + 6, right - 1)), (0, 128));
+        // Untouched elsewhere
+        assert_eq!(at(20, 30), 128);
+        assert_eq!(pixels.iter().filter(|&&p| p == 255).count(), 18 + 11);
+    }
+}
@@ -0,0 +1,157 @@
+/// Presentation context capability sets and offline negotiation checks
+///
+/// A capability set lists, per SOP class, the transfer syntaxes a node
+/// proposes (as SCU) or accepts (as SCP). Given one of each, `negotiate`
+/// predicts the A-ASSOCIATE-AC result: by default the way dicom-ul decides it,
+/// the first transfer syntax in the proposer's order that the acceptor also
+/// supports, or as an acceptor with another selection policy would.
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use super::transfer_syntaxes::{TransferSyntaxPolicy, TransferSyntaxRegistry};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ContextCapability {
+    /// SOP class UID or name
+    pub sop_class: String,
+    /// Transfer syntax UIDs or names, in order of preference
+    pub transfer_syntaxes: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct CapabilitySet {
+    #[serde(default)]
+    pub ae_title: Option<String>,
+    pub contexts: Vec<ContextCapability>,
+}
+
+impl CapabilitySet {
+    /// Load a capability set from a JSON file
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        serde_json::from_str(&content)
+            .map_err(|e| anyhow::anyhow!("Invalid capability set {}: {}", path.display(), e))
+    }
+
+    /// Transfer syntaxes listed for a SOP class, `resolve` mapping names to UIDs
+    pub fn transfer_syntaxes_for(&self, sop_class_uid: &str, resolve: &impl Fn(&str) -> String) -> Option<Vec<String>> {
+        self.contexts.iter()
+            .find(|context| resolve(&context.sop_class) == sop_class_uid)
+            .map(|context| context.transfer_syntaxes.iter().map(|ts| resolve(ts)).collect())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NegotiationOutcome {
+    pub sop_class_uid: String,
+    /// Presentation context result code (0 = acceptance, 3 = abstract syntax
+    /// not supported, 4 = transfer syntaxes not supported); `None` when the
+    /// SCU would not propose the SOP class at all
+    pub result: Option<u8>,
+    pub transfer_syntax: Option<String>,
+    /// Transfer syntaxes both sides support, best first
+    pub common_transfer_syntaxes: Vec<String>,
+}
+
+impl NegotiationOutcome {
+    pub fn is_accepted(&self) -> bool {
+        self.result == Some(0)
+    }
+}
+
+/// Predict the negotiation of `sop_class_uid` between an SCU and an SCP that
+/// picks transfer syntaxes under `policy`
+pub fn negotiate(
+    scu: &CapabilitySet,
+    scp: &CapabilitySet,
+    sop_class_uid: &str,
+    policy: TransferSyntaxPolicy,
+    resolve: impl Fn(&str) -> String,
+) -> NegotiationOutcome {
+    let mut outcome = NegotiationOutcome {
+        sop_class_uid: sop_class_uid.to_string(),
+        result: None,
+        transfer_syntax: None,
+        common_transfer_syntaxes: Vec::new(),
+    };
+
+    let Some(proposed) = scu.transfer_syntaxes_for(sop_class_uid, &resolve) else {
+        return outcome;
+    };
+    let Some(accepted) = scp.transfer_syntaxes_for(sop_class_uid, &resolve) else {
+        outcome.result = Some(3);
+        return outcome;
+    };
+
+    outcome.common_transfer_syntaxes = TransferSyntaxRegistry::global()
+        .common_transfer_syntaxes(&proposed, &accepted, policy)
+        .into_iter()
+        .map(str::to_string)
+        .collect();
+    outcome.transfer_syntax = outcome.common_transfer_syntaxes.first().cloned();
+    outcome.result = Some(if outcome.transfer_syntax.is_some() { 0 } else { 4 });
+    outcome
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CT: &str = "1.2.840.10008.5.1.4.1.1.2";
+    const MR: &str = "1.2.840.10008.5.1.4.1.1.4";
+    const IMPLICIT_LE: &str = "1.2.840.10008.1.2";
+    const EXPLICIT_LE: &str = "1.2.840.10008.1.2.1";
+    const JPEG_2000_LOSSLESS: &str = "1.2.840.10008.1.2.4.90";
+
+    fn set(contexts: &[(&str, &[&str])]) -> CapabilitySet {
+        CapabilitySet {
+            ae_title: None,
+            contexts: contexts.iter()
+                .map(|(sop_class, transfer_syntaxes)| ContextCapability {
+                    sop_class: sop_class.to_string(),
+                    transfer_syntaxes: transfer_syntaxes.iter().map(|ts| ts.to_string()).collect(),
+                })
+                .collect(),
+        }
+    }
+
+    fn resolve(value: &str) -> String {
+        match value {
+            "CT Image Storage" => CT.to_string(),
+            other => other.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_negotiate_follows_proposer_order() {
+        let scu = set(&[("CT Image Storage", &[JPEG_2000_LOSSLESS, EXPLICIT_LE, IMPLICIT_LE])]);
+        let scp = set(&[(CT, &[IMPLICIT_LE, EXPLICIT_LE])]);
+
+        let outcome = negotiate(&scu, &scp, CT, TransferSyntaxPolicy::ProposerOrder, resolve);
+        assert!(outcome.is_accepted());
+        assert_eq!(outcome.transfer_syntax.as_deref(), Some(EXPLICIT_LE));
+        assert_eq!(outcome.common_transfer_syntaxes, vec![EXPLICIT_LE, IMPLICIT_LE]);
+    }
+
+    #[test]
+    fn test_negotiate_under_acceptor_policy() {
+        let scu = set(&[(CT, &[IMPLICIT_LE, EXPLICIT_LE, JPEG_2000_LOSSLESS])]);
+        let scp = set(&[(CT, &[JPEG_2000_LOSSLESS, EXPLICIT_LE, IMPLICIT_LE])]);
+
+        let outcome = negotiate(&scu, &scp, CT, TransferSyntaxPolicy::PreferNative, resolve);
+        assert_eq!(outcome.transfer_syntax.as_deref(), Some(EXPLICIT_LE));
+        assert_eq!(outcome.common_transfer_syntaxes, vec![EXPLICIT_LE, IMPLICIT_LE, JPEG_2000_LOSSLESS]);
+        assert_eq!(negotiate(&scu, &scp, CT, TransferSyntaxPolicy::ProposerOrder, resolve).transfer_syntax.as_deref(), Some(IMPLICIT_LE));
+    }
+
+    #[test]
+    fn test_negotiate_rejections() {
+        let scu = set(&[(CT, &[JPEG_2000_LOSSLESS]), (MR, &[EXPLICIT_LE])]);
+        let scp = set(&[(CT, &[IMPLICIT_LE])]);
+
+        assert_eq!(negotiate(&scu, &scp, CT, TransferSyntaxPolicy::ProposerOrder, resolve).result, Some(4));
+        assert_eq!(negotiate(&scu, &scp, MR, TransferSyntaxPolicy::ProposerOrder, resolve).result, Some(3));
+        assert_eq!(negotiate(&scp, &scu, MR, TransferSyntaxPolicy::ProposerOrder, resolve).result, None);
+    }
+}
@@ -0,0 +1,161 @@
+/// Character set transcoding to UTF-8
+///
+/// Objects using legacy Specific Character Sets (ISO_IR 100, ISO_IR 144, the
+/// Japanese ISO 2022 IR 13/87 sets, ...) are decoded to Unicode when read, and
+/// the encoder switches to whatever Specific Character Set (0008,0005) names
+/// when writing. Transcoding therefore declares ISO_IR 192 (UTF-8) and lets the
+/// text be re-encoded on write. The original character set and text values can
+/// be kept in an Original Attributes Sequence (0400,0561) item, the standard
+/// place for values that were coerced.
+
+use anyhow::Result;
+use chrono::Local;
+use dicom::encoding::text::SpecificCharacterSet;
+use dicom_core::value::{DataSetSequence, PrimitiveValue};
+use dicom_core::{DataElement, Tag, VR};
+use dicom_object::InMemDicomObject;
+
+pub const SPECIFIC_CHARACTER_SET: Tag = Tag(0x0008, 0x0005);
+pub const UTF8_CHARSET: &str = "ISO_IR 192";
+
+const ORIGINAL_ATTRIBUTES_SEQUENCE: Tag = Tag(0x0400, 0x0561);
+const MODIFIED_ATTRIBUTES_SEQUENCE: Tag = Tag(0x0400, 0x0550);
+const ATTRIBUTE_MODIFICATION_DATETIME: Tag = Tag(0x0400, 0x0562);
+const MODIFYING_SYSTEM: Tag = Tag(0x0400, 0x0563);
+const SOURCE_OF_PREVIOUS_VALUES: Tag = Tag(0x0400, 0x0564);
+const REASON_FOR_MODIFICATION: Tag = Tag(0x0400, 0x0565);
+
+/// VRs whose values are affected by the Specific Character Set
+pub fn is_charset_sensitive(vr: VR) -> bool {
+    matches!(vr, VR::PN | VR::LO | VR::SH | VR::ST | VR::LT | VR::UT | VR::UC)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TranscodeReport {
+    /// Specific Character Set before transcoding, empty for the default repertoire
+    pub original_charset: Vec<String>,
+    /// Text elements whose encoding changed
+    pub transcoded_tags: Vec<Tag>,
+}
+
+/// Rewrite a dataset's text to UTF-8, optionally shadowing the original values
+///
+/// Returns `None` when the dataset is already UTF-8 or uses only the default
+/// repertoire, which is a subset of UTF-8. Fails for character sets that
+/// cannot be decoded, since their text would already be garbled.
+pub fn transcode_to_utf8(obj: &mut InMemDicomObject, preserve_original: bool) -> Result<Option<TranscodeReport>> {
+    let original_charset: Vec<String> = obj.element(SPECIFIC_CHARACTER_SET).ok()
+        .and_then(|e| e.to_multi_str().ok())
+        .map(|codes| codes.iter().map(|c| c.trim().to_string()).collect())
+        .unwrap_or_default();
+
+    let legacy = original_charset.iter()
+        .any(|code| !code.is_empty() && code != "ISO_IR 6" && code != UTF8_CHARSET);
+    if !legacy {
+        return Ok(None);
+    }
+
+    if let Some(unsupported) = original_charset.iter()
+        .filter(|code| !code.is_empty())
+        .find(|code| SpecificCharacterSet::from_code(code).is_none()) {
+        anyhow::bail!("Cannot transcode from unsupported character set '{}'", unsupported);
+    }
+
+    let originals: Vec<DataElement<InMemDicomObject>> = obj.iter()
+        .filter(|e| is_charset_sensitive(e.header().vr) && e.header().tag.0 != 0x0002)
+        .cloned()
+        .collect();
+
+    if preserve_original {
+        let mut modified = InMemDicomObject::new_empty();
+        modified.put(DataElement::new(
+            SPECIFIC_CHARACTER_SET,
+            VR::CS,
+            PrimitiveValue::Strs(original_charset.iter().cloned().collect()),
+        ));
+        for element in &originals {
+            modified.put(element.clone());
+        }
+
+        shadow_original_attributes(obj, modified, "COERCE");
+    }
+
+    obj.put(DataElement::new(SPECIFIC_CHARACTER_SET, VR::CS, PrimitiveValue::from(UTF8_CHARSET)));
+
+    Ok(Some(TranscodeReport {
+        original_charset,
+        transcoded_tags: originals.iter().map(|e| e.header().tag).collect(),
+    }))
+}
+
+/// Append an Original Attributes Sequence (0400,0561) item recording that the
+/// attributes in `modified`, with their previous values, were changed by this
+/// system for `reason` ("COERCE" or "CORRECT")
+pub fn shadow_original_attributes(obj: &mut InMemDicomObject, modified: InMemDicomObject, reason: &str) {
+    let shadow = InMemDicomObject::from_element_iter([
+        DataElement::new(MODIFIED_ATTRIBUTES_SEQUENCE, VR::SQ, DataSetSequence::from(vec![modified])),
+        DataElement::new(
+            ATTRIBUTE_MODIFICATION_DATETIME,
+            VR::DT,
+            PrimitiveValue::from(Local::now().format("%Y%m%d%H%M%S").to_string()),
+        ),
+        DataElement::new(MODIFYING_SYSTEM, VR::LO, PrimitiveValue::from("RUST_DICOM")),
+        DataElement::new(SOURCE_OF_PREVIOUS_VALUES, VR::LO, PrimitiveValue::Empty),
+        DataElement::new(REASON_FOR_MODIFICATION, VR::CS, PrimitiveValue::from(reason)),
+    ]);
+
+    let mut items: Vec<InMemDicomObject> = obj.element(ORIGINAL_ATTRIBUTES_SEQUENCE).ok()
+        .and_then(|e| e.items())
+        .map(|items| items.to_vec())
+        .unwrap_or_default();
+    items.push(shadow);
+    obj.put(DataElement::new(ORIGINAL_ATTRIBUTES_SEQUENCE, VR::SQ, DataSetSequence::from(items)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn latin1_object() -> InMemDicomObject {
+        InMemDicomObject::from_element_iter([
+            DataElement::new(SPECIFIC_CHARACTER_SET, VR::CS, PrimitiveValue::from("ISO_IR 100")),
+            DataElement::new(Tag(0x0008, 0x0060), VR::CS, PrimitiveValue::from("CT")),
+            DataElement::new(Tag(0x0010, 0x0010), VR::PN, PrimitiveValue::from("Müller^Jürgen")),
+        ])
+    }
+
+    #[test]
+    fn test_transcode_declares_utf8() {
+        let mut obj = latin1_object();
+        let report = transcode_to_utf8(&mut obj, false).unwrap().unwrap();
+
+        assert_eq!(report.original_charset, vec!["ISO_IR 100".to_string()]);
+        assert_eq!(report.transcoded_tags, vec![Tag(0x0010, 0x0010)]);
+        assert_eq!(obj.element(SPECIFIC_CHARACTER_SET).unwrap().to_str().unwrap(), UTF8_CHARSET);
+        assert_eq!(obj.element(Tag(0x0010, 0x0010)).unwrap().to_str().unwrap(), "Müller^Jürgen");
+        assert!(obj.element(ORIGINAL_ATTRIBUTES_SEQUENCE).is_err());
+
+        // Already UTF-8: nothing to do
+        assert!(transcode_to_utf8(&mut obj, false).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_transcode_preserves_original() {
+        let mut obj = latin1_object();
+        transcode_to_utf8(&mut obj, true).unwrap();
+
+        let shadow = &obj.element(ORIGINAL_ATTRIBUTES_SEQUENCE).unwrap().items().unwrap()[0];
+        let modified = &shadow.element(MODIFIED_ATTRIBUTES_SEQUENCE).unwrap().items().unwrap()[0];
+        assert_eq!(modified.element(SPECIFIC_CHARACTER_SET).unwrap().to_str().unwrap(), "ISO_IR 100");
+        assert_eq!(modified.element(Tag(0x0010, 0x0010)).unwrap().to_str().unwrap(), "Müller^Jürgen");
+        assert_eq!(shadow.element(REASON_FOR_MODIFICATION).unwrap().to_str().unwrap(), "COERCE");
+    }
+
+    #[test]
+    fn test_unsupported_charset_is_refused() {
+        let mut obj = InMemDicomObject::from_element_iter([
+            DataElement::new(SPECIFIC_CHARACTER_SET, VR::CS, PrimitiveValue::from("ISO_IR 999")),
+        ]);
+        assert!(transcode_to_utf8(&mut obj, false).is_err());
+    }
+}
@@ -0,0 +1,79 @@
+//! Transfer syntax codecs compiled into this build
+//!
+//! dicom-rs knows every standard transfer syntax, but whether it can read a
+//! data set in one, or decode and encode its pixel data, depends on the
+//! features dicom-transfer-syntax-registry was built with. The answer is taken
+//! from the dicom-rs registry at runtime, so negotiation only accepts what the
+//! binary can actually handle and `--list-codecs` reports what it supports.
+
+use dicom::encoding::transfer_syntax::{Codec, TransferSyntaxIndex};
+use dicom_transfer_syntax_registry::TransferSyntaxRegistry as CodecRegistry;
+
+use super::transfer_syntaxes::TransferSyntaxRegistry;
+
+/// What this build can do with objects in one transfer syntax
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CodecSupport {
+    /// The data set can be read and written, so objects can be received,
+    /// checked and forwarded as they are
+    pub dataset: bool,
+    /// Pixel data can be decoded to native pixels
+    pub decode: bool,
+    /// Native pixels can be encoded in this transfer syntax
+    pub encode: bool,
+}
+
+impl CodecSupport {
+    /// Look the transfer syntax up in the dicom-rs registry; unknown ones support nothing
+    pub fn of(uid: &str) -> Self {
+        let Some(ts) = CodecRegistry.get(uid.trim_end_matches('\0')) else {
+            return Self::default();
+        };
+        match ts.codec() {
+            Codec::None => Self { dataset: true, decode: true, encode: true },
+            // Deflate: pixel data is native once the data set is inflated
+            Codec::Dataset(adapter) => Self { dataset: adapter.is_some(), decode: adapter.is_some(), encode: adapter.is_some() },
+            // Encapsulated pixel data travels as is without a codec
+            Codec::EncapsulatedPixelData(reader, writer) => Self { dataset: true, decode: reader.is_some(), encode: writer.is_some() },
+        }
+    }
+}
+
+/// One line per registered transfer syntax with what this build supports
+pub fn codec_report(registry: &TransferSyntaxRegistry) -> String {
+    let mut uids = registry.get_all_uids();
+    uids.sort_by_key(|uid| uid.split('.').map(|part| part.parse::<u32>().unwrap_or(u32::MAX)).collect::<Vec<_>>());
+
+    let mark = |supported: bool| if supported { "yes" } else { "-" };
+    let mut report = format!("{:<24} {:<56} {:<8} {:<7} {:<7} {}\n", "UID", "Name", "Dataset", "Decode", "Encode", "Codec");
+    for uid in uids {
+        let Some(info) = registry.get(uid) else { continue };
+        let support = registry.codec_support(uid);
+        let codec = match info.compression.codec_feature() {
+            _ if !info.is_compressed() => "native".to_string(),
+            Some(feature) if !support.decode => format!("not built (feature {})", feature),
+            Some(_) => "built in".to_string(),
+            None => "none, stored as received".to_string(),
+        };
+        report.push_str(&format!("{:<24} {:<56} {:<8} {:<7} {:<7} {}\n",
+                                 uid, info.name, mark(support.dataset), mark(support.decode), mark(support.encode), codec));
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::uids::ts;
+
+    #[test]
+    fn test_codec_support() {
+        assert_eq!(CodecSupport::of(ts::EXPLICIT_VR_LITTLE_ENDIAN), CodecSupport { dataset: true, decode: true, encode: true });
+        assert_eq!(CodecSupport::of("1.2.3.4"), CodecSupport::default());
+        // Encapsulated pixel data is stored as received, with or without a codec
+        assert!(CodecSupport::of(ts::JPEG_2000).dataset);
+
+        let report = codec_report(&TransferSyntaxRegistry::new());
+        assert!(report.lines().any(|line| line.starts_with(ts::EXPLICIT_VR_LITTLE_ENDIAN) && line.ends_with("native")));
+    }
+}
@@ -0,0 +1,130 @@
+/// Strict and lenient protocol compliance for the receiver
+///
+/// Strict mode enforces the PS3.8 upper layer and PS3.7 DIMSE rules exactly:
+/// an association addressed to another AE title is rejected and a peer that
+/// sends malformed items or data on the wrong presentation context is aborted.
+/// Real-world modalities break these rules routinely, so by default each
+/// deviation is only logged as a warning. Rules can be set individually on top
+/// of either mode.
+
+use std::collections::BTreeSet;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, clap::ValueEnum)]
+pub enum ComplianceRule {
+    /// The called AE title of the A-ASSOCIATE-RQ must be ours
+    CalledAe,
+    /// The A-ASSOCIATE-RQ must consist of well-formed items
+    MalformedAssociation,
+    /// Command sets must decode and carry a Command Field
+    MalformedCommand,
+    /// P-DATA values must use a presentation context accepted on the association
+    UnknownContext,
+    /// A data set must be preceded by its command on the same presentation context
+    DataWithoutCommand,
+    /// A new command must not arrive before the previous data set is complete
+    InterruptedDataSet,
+}
+
+impl ComplianceRule {
+    pub const ALL: [ComplianceRule; 6] = [
+        ComplianceRule::CalledAe,
+        ComplianceRule::MalformedAssociation,
+        ComplianceRule::MalformedCommand,
+        ComplianceRule::UnknownContext,
+        ComplianceRule::DataWithoutCommand,
+        ComplianceRule::InterruptedDataSet,
+    ];
+}
+
+impl std::fmt::Display for ComplianceRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = clap::ValueEnum::to_possible_value(self).map(|value| value.get_name().to_string());
+        write!(f, "{}", name.unwrap_or_default())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum RuleAction {
+    /// Log the deviation and carry on
+    Warn,
+    /// Refuse the association, or abort it once established
+    Reject,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CompliancePolicy {
+    rejected: BTreeSet<ComplianceRule>,
+}
+
+impl CompliancePolicy {
+    /// Every deviation is tolerated with a warning
+    pub fn lenient() -> Self {
+        Self::default()
+    }
+
+    /// Every deviation is rejected
+    pub fn strict() -> Self {
+        Self { rejected: ComplianceRule::ALL.into_iter().collect() }
+    }
+
+    pub fn set(&mut self, rule: ComplianceRule, action: RuleAction) {
+        match action {
+            RuleAction::Warn => self.rejected.remove(&rule),
+            RuleAction::Reject => self.rejected.insert(rule),
+        };
+    }
+
+    pub fn rejects(&self, rule: ComplianceRule) -> bool {
+        self.rejected.contains(&rule)
+    }
+
+    pub fn is_strict(&self) -> bool {
+        self.rejected.len() == ComplianceRule::ALL.len()
+    }
+
+    pub fn rejected_rules(&self) -> impl Iterator<Item = ComplianceRule> + '_ {
+        self.rejected.iter().copied()
+    }
+}
+
+/// Parse a `RULE=ACTION` override, e.g. `called-ae=warn`
+pub fn parse_rule_override(value: &str) -> Result<(ComplianceRule, RuleAction), String> {
+    let (rule, action) = value.split_once('=')
+        .ok_or_else(|| format!("expected RULE=ACTION, got '{}'", value))?;
+    let rule = <ComplianceRule as clap::ValueEnum>::from_str(rule.trim(), true)
+        .map_err(|_| format!("unknown compliance rule '{}'", rule.trim()))?;
+    let action = <RuleAction as clap::ValueEnum>::from_str(action.trim(), true)
+        .map_err(|_| format!("unknown action '{}', expected warn or reject", action.trim()))?;
+    Ok((rule, action))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_modes_and_overrides() {
+        let mut policy = CompliancePolicy::strict();
+        assert!(policy.is_strict());
+        assert!(ComplianceRule::ALL.iter().all(|rule| policy.rejects(*rule)));
+
+        let (rule, action) = parse_rule_override("called-ae=warn").unwrap();
+        policy.set(rule, action);
+        assert!(!policy.rejects(ComplianceRule::CalledAe));
+        assert!(policy.rejects(ComplianceRule::UnknownContext));
+        assert!(!policy.is_strict());
+
+        let mut lenient = CompliancePolicy::lenient();
+        lenient.set(ComplianceRule::InterruptedDataSet, RuleAction::Reject);
+        assert_eq!(lenient.rejected_rules().collect::<Vec<_>>(), vec![ComplianceRule::InterruptedDataSet]);
+    }
+
+    #[test]
+    fn test_parse_rule_override() {
+        assert_eq!(parse_rule_override("unknown-context = reject"), Ok((ComplianceRule::UnknownContext, RuleAction::Reject)));
+        assert_eq!(ComplianceRule::DataWithoutCommand.to_string(), "data-without-command");
+        assert!(parse_rule_override("called-ae").is_err());
+        assert!(parse_rule_override("called-ae=ignore").is_err());
+        assert!(parse_rule_override("wrong-rule=warn").is_err());
+    }
+}
@@ -0,0 +1,142 @@
+/// Duplicate-content detection across SOP Instance UIDs
+///
+/// Modalities that re-export a study sometimes assign fresh SOP Instance UIDs
+/// to objects that are otherwise unchanged. UID-based deduplication cannot see
+/// these, so each object is fingerprinted twice: once over the whole dataset
+/// with its SOP Instance UID removed, and once over the Pixel Data alone. A
+/// fingerprint seen before under a different UID flags the object.
+
+use dicom_core::value::Value;
+use dicom_core::Tag;
+use dicom_object::InMemDicomObject;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+
+const SOP_INSTANCE_UID: Tag = Tag(0x0008, 0x0018);
+const PIXEL_DATA: Tag = Tag(0x7FE0, 0x0010);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DuplicateKind {
+    /// Identical apart from the SOP Instance UID
+    Dataset,
+    /// Identical Pixel Data, other attributes differ
+    Pixel,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DuplicateContent {
+    pub kind: DuplicateKind,
+    pub sop_instance_uid: String,
+    /// UID of the earlier object with the same content
+    pub original_sop_instance_uid: String,
+}
+
+#[derive(Debug, Default)]
+pub struct ContentIndex {
+    datasets: HashMap<String, String>,
+    pixels: HashMap<String, String>,
+}
+
+impl ContentIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fingerprint an object and report any earlier object with the same content under another UID
+    pub fn check(&mut self, obj: &InMemDicomObject) -> Option<DuplicateContent> {
+        let sop_instance_uid = obj.element(SOP_INSTANCE_UID).ok()
+            .and_then(|e| e.to_str().ok())
+            .map(|s| s.trim_end_matches('\0').trim().to_string())
+            .unwrap_or_default();
+
+        let dataset_hash = dataset_fingerprint(obj);
+        let pixel_hash = pixel_fingerprint(obj);
+
+        let mut found = None;
+        if let Some(hash) = dataset_hash {
+            found = Self::remember(&mut self.datasets, hash, &sop_instance_uid)
+                .map(|original| (DuplicateKind::Dataset, original));
+        }
+        if let Some(hash) = pixel_hash {
+            let pixel_match = Self::remember(&mut self.pixels, hash, &sop_instance_uid);
+            if found.is_none() {
+                found = pixel_match.map(|original| (DuplicateKind::Pixel, original));
+            }
+        }
+
+        found.map(|(kind, original_sop_instance_uid)| DuplicateContent {
+            kind,
+            sop_instance_uid,
+            original_sop_instance_uid,
+        })
+    }
+
+    fn remember(seen: &mut HashMap<String, String>, hash: String, sop_instance_uid: &str) -> Option<String> {
+        match seen.get(&hash) {
+            Some(original) if original != sop_instance_uid => Some(original.clone()),
+            Some(_) => None,
+            None => {
+                seen.insert(hash, sop_instance_uid.to_string());
+                None
+            }
+        }
+    }
+}
+
+/// SHA-256 over the dataset, encoded as Explicit VR Little Endian without its SOP Instance UID
+pub fn dataset_fingerprint(obj: &InMemDicomObject) -> Option<String> {
+    let mut stripped = obj.clone();
+    stripped.remove_element(SOP_INSTANCE_UID);
+
+    let mut buffer = Vec::new();
+    stripped.write_dataset_with_ts(
+        &mut buffer,
+        &dicom_transfer_syntax_registry::entries::EXPLICIT_VR_LITTLE_ENDIAN.erased(),
+    ).ok()?;
+    Some(hex::encode(Sha256::digest(&buffer)))
+}
+
+/// SHA-256 over the Pixel Data value, native or encapsulated fragments
+pub fn pixel_fingerprint(obj: &InMemDicomObject) -> Option<String> {
+    let element = obj.element(PIXEL_DATA).ok()?;
+    let mut hasher = Sha256::new();
+    match element.value() {
+        Value::PixelSequence(sequence) => {
+            for fragment in sequence.fragments() {
+                hasher.update(fragment);
+            }
+        }
+        _ => hasher.update(element.to_bytes().ok()?),
+    }
+    Some(hex::encode(hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dicom_core::value::PrimitiveValue;
+    use dicom_core::{DataElement, VR};
+
+    fn image(sop_instance_uid: &str, series_description: &str) -> InMemDicomObject {
+        InMemDicomObject::from_element_iter([
+            DataElement::new(Tag(0x0008, 0x0018), VR::UI, PrimitiveValue::from(sop_instance_uid)),
+            DataElement::new(Tag(0x0008, 0x103E), VR::LO, PrimitiveValue::from(series_description)),
+            DataElement::new(PIXEL_DATA, VR::OW, PrimitiveValue::U8([1u8, 2, 3, 4].into_iter().collect())),
+        ])
+    }
+
+    #[test]
+    fn test_detects_reexported_content() {
+        let mut index = ContentIndex::new();
+        assert!(index.check(&image("1.2.3.1", "AXIAL")).is_none());
+        // The same object arriving again is a UID duplicate, not a content duplicate
+        assert!(index.check(&image("1.2.3.1", "AXIAL")).is_none());
+
+        let duplicate = index.check(&image("1.2.3.2", "AXIAL")).unwrap();
+        assert_eq!(duplicate.kind, DuplicateKind::Dataset);
+        assert_eq!(duplicate.original_sop_instance_uid, "1.2.3.1");
+
+        let duplicate = index.check(&image("1.2.3.3", "AXIAL RE-EXPORT")).unwrap();
+        assert_eq!(duplicate.kind, DuplicateKind::Pixel);
+    }
+}
@@ -0,0 +1,39 @@
+/// Reproducible identifiers and timestamps for test fixtures
+///
+/// In deterministic mode session IDs are derived from a seed instead of being
+/// random, and wall-clock timestamps are replaced by a fixed instant, so
+/// golden-file and snapshot tests see identical output on every run.
+
+use chrono::{DateTime, TimeZone, Utc};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+/// Instant reported for every timestamp in deterministic mode
+pub fn fixed_timestamp() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2000, 1, 1, 0, 0, 0).unwrap()
+}
+
+/// UUID derived from a seed and a label, stable across runs and platforms
+pub fn seeded_uuid(seed: u64, label: &str) -> Uuid {
+    let mut hasher = Sha256::new();
+    hasher.update(seed.to_be_bytes());
+    hasher.update(label.as_bytes());
+    let digest = hasher.finalize();
+
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&digest[..16]);
+    uuid::Builder::from_random_bytes(bytes).into_uuid()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seeded_uuid_is_stable() {
+        assert_eq!(seeded_uuid(42, "session"), seeded_uuid(42, "session"));
+        assert_ne!(seeded_uuid(42, "session"), seeded_uuid(43, "session"));
+        assert_ne!(seeded_uuid(42, "session"), seeded_uuid(42, "other"));
+        assert_eq!(seeded_uuid(42, "session").get_version_num(), 4);
+    }
+}
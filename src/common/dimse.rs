@@ -0,0 +1,168 @@
+//! DIMSE command set decoding
+//!
+//! Command sets are always encoded in Implicit VR Little Endian, whatever
+//! transfer syntax was negotiated for the presentation context (PS3.7 6.3.1).
+//! They come straight off the wire, so decoding must fail cleanly on any input.
+
+use dicom_core::value::PrimitiveValue;
+use dicom_core::{DataElement, Tag, VR};
+use dicom_object::InMemDicomObject;
+use super::uids::sop;
+
+pub const VERIFICATION_SOP_CLASS: &str = sop::VERIFICATION;
+pub const C_STORE_RQ: u16 = 0x0001;
+pub const C_STORE_RSP: u16 = 0x8001;
+pub const C_ECHO_RQ: u16 = 0x0030;
+pub const C_ECHO_RSP: u16 = 0x8030;
+
+const AFFECTED_SOP_CLASS_UID: Tag = Tag(0x0000, 0x0002);
+const COMMAND_FIELD: Tag = Tag(0x0000, 0x0100);
+const MESSAGE_ID: Tag = Tag(0x0000, 0x0110);
+const MESSAGE_ID_BEING_RESPONDED_TO: Tag = Tag(0x0000, 0x0120);
+const COMMAND_DATA_SET_TYPE: Tag = Tag(0x0000, 0x0800);
+const STATUS: Tag = Tag(0x0000, 0x0900);
+const AFFECTED_SOP_INSTANCE_UID: Tag = Tag(0x0000, 0x1000);
+/// Command Data Set Type when no data set follows the command
+const NO_DATA_SET: u16 = 0x0101;
+/// Command sets hold a handful of short elements; anything larger is not one
+pub const MAX_COMMAND_LENGTH: usize = 64 * 1024;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommandSet {
+    /// e.g. 0x0001 for C-STORE-RQ, 0x0030 for C-ECHO-RQ
+    pub command_field: u16,
+    pub message_id: Option<u16>,
+    pub affected_sop_class_uid: Option<String>,
+    /// Present in C-STORE-RQ
+    pub affected_sop_instance_uid: Option<String>,
+}
+
+/// Decode the command set carried by a command P-DATA value
+pub fn decode_command(data: &[u8]) -> Result<CommandSet, String> {
+    if data.len() > MAX_COMMAND_LENGTH {
+        return Err(format!("command set of {} bytes is too large", data.len()));
+    }
+    let ts = dicom_transfer_syntax_registry::entries::IMPLICIT_VR_LITTLE_ENDIAN.erased();
+    let command = InMemDicomObject::read_dataset_with_ts(data, &ts)
+        .map_err(|e| format!("malformed command set: {}", e))?;
+
+    let command_field = command.element(COMMAND_FIELD).ok()
+        .and_then(|e| e.to_int::<u16>().ok())
+        .ok_or("command set without a Command Field")?;
+    let message_id = command.element(MESSAGE_ID).ok()
+        .and_then(|e| e.to_int::<u16>().ok());
+    Ok(CommandSet {
+        command_field,
+        message_id,
+        affected_sop_class_uid: uid_value(&command, AFFECTED_SOP_CLASS_UID),
+        affected_sop_instance_uid: uid_value(&command, AFFECTED_SOP_INSTANCE_UID),
+    })
+}
+
+fn uid_value(command: &InMemDicomObject, tag: Tag) -> Option<String> {
+    command.element(tag).ok()
+        .and_then(|e| e.to_str().ok())
+        .map(|uid| uid.trim_end_matches('\0').trim().to_string())
+        .filter(|uid| !uid.is_empty())
+}
+
+/// UID syntax of PS3.5 9.1: at most 64 characters, numeric components separated by dots
+pub fn is_valid_uid(uid: &str) -> bool {
+    uid.len() <= 64
+        && uid.split('.').all(|component| {
+            !component.is_empty()
+                && component.bytes().all(|b| b.is_ascii_digit())
+                && (component == "0" || !component.starts_with('0'))
+        })
+}
+
+/// Command set of a response without a data set, Command Group Length included
+pub fn response_command(command_field: u16, message_id: u16, affected_sop_class_uid: &str,
+                        affected_sop_instance_uid: Option<&str>, status: u16) -> Vec<u8> {
+    let mut command = InMemDicomObject::from_element_iter([
+        DataElement::new(AFFECTED_SOP_CLASS_UID, VR::UI, PrimitiveValue::from(affected_sop_class_uid)),
+        DataElement::new(COMMAND_FIELD, VR::US, PrimitiveValue::from(command_field)),
+        DataElement::new(MESSAGE_ID_BEING_RESPONDED_TO, VR::US, PrimitiveValue::from(message_id)),
+        DataElement::new(COMMAND_DATA_SET_TYPE, VR::US, PrimitiveValue::from(NO_DATA_SET)),
+        DataElement::new(STATUS, VR::US, PrimitiveValue::from(status)),
+    ]);
+    if let Some(uid) = affected_sop_instance_uid {
+        command.put(DataElement::new(AFFECTED_SOP_INSTANCE_UID, VR::UI, PrimitiveValue::from(uid)));
+    }
+    let mut elements = Vec::new();
+    command.write_dataset_with_ts(&mut elements, &dicom_transfer_syntax_registry::entries::IMPLICIT_VR_LITTLE_ENDIAN.erased())
+        .expect("command elements encode to memory");
+
+    // (0000,0000) UL, Implicit VR Little Endian
+    let mut encoded = vec![0x00, 0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00];
+    encoded.extend_from_slice(&(elements.len() as u32).to_le_bytes());
+    encoded.extend(elements);
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(command: &InMemDicomObject) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        command.write_dataset_with_ts(&mut buffer, &dicom_transfer_syntax_registry::entries::IMPLICIT_VR_LITTLE_ENDIAN.erased())
+            .unwrap();
+        buffer
+    }
+
+    #[test]
+    fn test_decode_command() {
+        let bytes = encode(&InMemDicomObject::from_element_iter([
+            DataElement::new(AFFECTED_SOP_CLASS_UID, VR::UI, PrimitiveValue::from("1.2.840.10008.5.1.4.1.1.2\0")),
+            DataElement::new(COMMAND_FIELD, VR::US, PrimitiveValue::from(C_STORE_RQ)),
+            DataElement::new(MESSAGE_ID, VR::US, PrimitiveValue::from(7u16)),
+            DataElement::new(AFFECTED_SOP_INSTANCE_UID, VR::UI, PrimitiveValue::from("1.2.3.4.5\0")),
+        ]));
+
+        assert_eq!(decode_command(&bytes), Ok(CommandSet {
+            command_field: C_STORE_RQ,
+            message_id: Some(7),
+            affected_sop_class_uid: Some("1.2.840.10008.5.1.4.1.1.2".to_string()),
+            affected_sop_instance_uid: Some("1.2.3.4.5".to_string()),
+        }));
+    }
+
+    #[test]
+    fn test_uid_syntax() {
+        assert!(is_valid_uid("1.2.840.10008.5.1.4.1.1.2"));
+        assert!(is_valid_uid("2.25.0.10"));
+        assert!(!is_valid_uid(""));
+        assert!(!is_valid_uid("1.2..3"));
+        assert!(!is_valid_uid("1.02.3"));
+        assert!(!is_valid_uid("../../etc/passwd"));
+        assert!(!is_valid_uid(&"1.".repeat(40)));
+    }
+
+    #[test]
+    fn test_echo_response() {
+        let bytes = response_command(C_ECHO_RSP, 42, VERIFICATION_SOP_CLASS, None, 0x0000);
+        assert_eq!(u32::from_le_bytes(bytes[8..12].try_into().unwrap()) as usize, bytes.len() - 12);
+
+        let ts = dicom_transfer_syntax_registry::entries::IMPLICIT_VR_LITTLE_ENDIAN.erased();
+        let response = InMemDicomObject::read_dataset_with_ts(&bytes[..], &ts).unwrap();
+        let value = |tag| response.element(tag).unwrap().to_int::<u16>().unwrap();
+        assert_eq!(value(COMMAND_FIELD), C_ECHO_RSP);
+        assert_eq!(value(MESSAGE_ID_BEING_RESPONDED_TO), 42);
+        assert_eq!(value(STATUS), 0x0000);
+        assert_eq!(decode_command(&bytes).unwrap().affected_sop_class_uid.as_deref(), Some(VERIFICATION_SOP_CLASS));
+    }
+
+    #[test]
+    fn test_malformed_commands_are_errors() {
+        let bytes = encode(&InMemDicomObject::from_element_iter([
+            DataElement::new(COMMAND_FIELD, VR::US, PrimitiveValue::from(0x0030u16)),
+        ]));
+
+        assert!(decode_command(&bytes[..bytes.len() - 1]).is_err());
+        // (0000,0100) claiming 100 bytes of value
+        assert!(decode_command(&[0x00, 0x00, 0x00, 0x01, 100, 0, 0, 0, 0x01, 0x00]).is_err());
+        assert!(decode_command(&vec![0; MAX_COMMAND_LENGTH + 1]).is_err());
+        assert!(decode_command(&encode(&InMemDicomObject::new_empty())).is_err());
+    }
+}
@@ -0,0 +1,117 @@
+/// Zero-configuration discovery of DICOM services over mDNS/DNS-SD
+///
+/// The receiver can advertise itself as a `_dicom._tcp` service with its AE
+/// title in the `aet` TXT record, and the sender can look a destination up by
+/// AE title instead of being given a host and port. Meant for lab and demo
+/// networks where addresses change; it needs the `mdns` feature.
+
+use anyhow::Result;
+use std::time::Duration;
+
+/// DNS-SD service type registered for DICOM (port 104)
+pub const SERVICE_TYPE: &str = "_dicom._tcp.local.";
+/// TXT record key carrying the AE title
+pub const AE_TITLE_KEY: &str = "aet";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiscoveredService {
+    pub ae_title: String,
+    pub host: String,
+    pub port: u16,
+}
+
+/// Host name published for an AE title, e.g. `rust-scp-dicom.local.`
+pub fn advertised_host_name(ae_title: &str) -> String {
+    let label: String = ae_title.trim()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '-' })
+        .collect();
+    format!("{}-dicom.local.", label.trim_matches('-'))
+}
+
+/// Advertisement that stays registered until dropped
+#[cfg(feature = "mdns")]
+pub struct Advertisement {
+    daemon: mdns_sd::ServiceDaemon,
+}
+
+#[cfg(feature = "mdns")]
+impl Drop for Advertisement {
+    fn drop(&mut self) {
+        let _ = self.daemon.shutdown();
+    }
+}
+
+/// Advertise a DICOM service for `ae_title` on all interfaces
+#[cfg(feature = "mdns")]
+pub fn advertise(ae_title: &str, port: u16) -> Result<Advertisement> {
+    let daemon = mdns_sd::ServiceDaemon::new()?;
+    let info = mdns_sd::ServiceInfo::new(
+        SERVICE_TYPE,
+        ae_title.trim(),
+        &advertised_host_name(ae_title),
+        "",
+        port,
+        &[(AE_TITLE_KEY, ae_title.trim())][..],
+    )?
+    .enable_addr_auto();
+    daemon.register(info)?;
+    Ok(Advertisement { daemon })
+}
+
+/// Browse for a DICOM service advertising `ae_title`, giving up after `timeout`
+#[cfg(feature = "mdns")]
+pub fn discover(ae_title: &str, timeout: Duration) -> Result<DiscoveredService> {
+    let daemon = mdns_sd::ServiceDaemon::new()?;
+    let events = daemon.browse(SERVICE_TYPE)?;
+    let deadline = std::time::Instant::now() + timeout;
+
+    let mut found = None;
+    while let Some(remaining) = deadline.checked_duration_since(std::time::Instant::now()) {
+        let Ok(event) = events.recv_timeout(remaining) else { break };
+        if let mdns_sd::ServiceEvent::ServiceResolved(info) = event {
+            let advertised = info.get_property_val_str(AE_TITLE_KEY).unwrap_or_default().trim();
+            if !advertised.eq_ignore_ascii_case(ae_title.trim()) {
+                continue;
+            }
+            // Prefer IPv4, which every peer can reach on a flat lab network
+            let mut addresses: Vec<_> = info.get_addresses().iter().copied().collect();
+            addresses.sort_by_key(|address| !address.is_ipv4());
+            if let Some(address) = addresses.first() {
+                found = Some(DiscoveredService {
+                    ae_title: advertised.to_string(),
+                    host: address.to_string(),
+                    port: info.get_port(),
+                });
+                break;
+            }
+        }
+    }
+
+    let _ = daemon.shutdown();
+    found.ok_or_else(|| anyhow::anyhow!("No DICOM service advertising AE title {} found within {:?}", ae_title, timeout))
+}
+
+#[cfg(not(feature = "mdns"))]
+pub struct Advertisement;
+
+#[cfg(not(feature = "mdns"))]
+pub fn advertise(_ae_title: &str, _port: u16) -> Result<Advertisement> {
+    anyhow::bail!("mDNS advertisement is not available: rebuild with --features mdns")
+}
+
+#[cfg(not(feature = "mdns"))]
+pub fn discover(_ae_title: &str, _timeout: Duration) -> Result<DiscoveredService> {
+    anyhow::bail!("mDNS discovery is not available: rebuild with --features mdns")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_advertised_host_name() {
+        assert_eq!(advertised_host_name("RUST_SCP"), "rust-scp-dicom.local.");
+        assert_eq!(advertised_host_name(" CT 1 "), "ct-1-dicom.local.");
+    }
+}
@@ -0,0 +1,350 @@
+/// Encryption of stored objects at rest
+///
+/// Objects are sealed with AES-256-GCM before they are written, so a copied
+/// disk or bucket reveals nothing. A sealed file is the 8-byte magic
+/// `RDCMENC1`, a random 96-bit nonce and the ciphertext with its 16-byte tag;
+/// the magic is authenticated as associated data. Files without the magic are
+/// read as they are, so an archive can be encrypted from some point on.
+///
+/// Objects spooled to disk as they arrive are sealed in chunks instead, so they
+/// are never held whole in memory nor written in plaintext: `RDCMENC2`, a random
+/// 56-bit nonce prefix, then chunks of up to 1 MiB each with its tag. A chunk's
+/// nonce is the prefix, its 32-bit counter and a byte flagging the last chunk
+/// (the STREAM construction), so chunks cannot be reordered or dropped, nor the
+/// object truncated, without decryption failing.
+
+use anyhow::{Context, Result};
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+pub const ENCRYPTED_MAGIC: &[u8; 8] = b"RDCMENC1";
+/// Magic of an object sealed in chunks as it was written
+pub const STREAM_MAGIC: &[u8; 8] = b"RDCMENC2";
+pub const KEY_LENGTH: usize = 32;
+/// Plaintext bytes per chunk of a stream-sealed object
+pub const STREAM_CHUNK_LENGTH: usize = 1 << 20;
+const TAG_LENGTH: usize = 16;
+/// The rest of a chunk's nonce is its counter and the last-chunk flag
+const NONCE_PREFIX_LENGTH: usize = NONCE_LEN - 5;
+
+/// AES-256-GCM key for stored objects
+pub struct StorageKey {
+    key: LessSafeKey,
+    rng: SystemRandom,
+}
+
+impl std::fmt::Debug for StorageKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("StorageKey(AES-256-GCM)")
+    }
+}
+
+impl StorageKey {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() != KEY_LENGTH {
+            anyhow::bail!("encryption key must be {} bytes, got {}", KEY_LENGTH, bytes.len());
+        }
+        let key = UnboundKey::new(&AES_256_GCM, bytes)
+            .map_err(|_| anyhow::anyhow!("invalid AES-256-GCM key"))?;
+        Ok(Self { key: LessSafeKey::new(key), rng: SystemRandom::new() })
+    }
+
+    /// Key given as 64 hexadecimal digits
+    pub fn from_hex(value: &str) -> Result<Self> {
+        let bytes = hex::decode(value.trim()).context("encryption key is not valid hex")?;
+        Self::from_bytes(&bytes)
+    }
+
+    /// Key file holding either 32 raw bytes or 64 hexadecimal digits
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let contents = std::fs::read(path)
+            .with_context(|| format!("Failed to read encryption key {}", path.display()))?;
+        match std::str::from_utf8(&contents) {
+            Ok(text) if text.trim().len() == 2 * KEY_LENGTH => Self::from_hex(text),
+            _ => Self::from_bytes(&contents),
+        }
+        .with_context(|| format!("Invalid encryption key in {}", path.display()))
+    }
+
+    /// Key held in an environment variable as 64 hexadecimal digits
+    pub fn from_env(variable: &str) -> Result<Self> {
+        let value = std::env::var(variable)
+            .with_context(|| format!("Environment variable {} is not set", variable))?;
+        Self::from_hex(&value).with_context(|| format!("Invalid encryption key in {}", variable))
+    }
+
+    pub fn seal(&self, plaintext: &[u8]) -> Result<Vec<u8>> {
+        let mut nonce = [0u8; NONCE_LEN];
+        self.rng.fill(&mut nonce).map_err(|_| anyhow::anyhow!("no randomness available for a nonce"))?;
+
+        let mut sealed = Vec::with_capacity(ENCRYPTED_MAGIC.len() + NONCE_LEN + plaintext.len() + AES_256_GCM.tag_len());
+        sealed.extend_from_slice(ENCRYPTED_MAGIC);
+        sealed.extend_from_slice(&nonce);
+        let mut in_out = plaintext.to_vec();
+        self.key.seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::from(ENCRYPTED_MAGIC), &mut in_out)
+            .map_err(|_| anyhow::anyhow!("encryption failed"))?;
+        sealed.extend(in_out);
+        Ok(sealed)
+    }
+
+    /// Decrypt a sealed object; fails if it was not sealed with this key or was altered
+    pub fn open(&self, sealed: &[u8]) -> Result<Vec<u8>> {
+        if sealed.starts_with(STREAM_MAGIC) {
+            let mut plaintext = Vec::new();
+            OpeningReader::new(self, sealed)?.read_to_end(&mut plaintext)?;
+            return Ok(plaintext);
+        }
+        let body = sealed.strip_prefix(ENCRYPTED_MAGIC.as_slice())
+            .context("not an encrypted object")?;
+        if body.len() < NONCE_LEN + AES_256_GCM.tag_len() {
+            anyhow::bail!("encrypted object is truncated");
+        }
+        let (nonce, ciphertext) = body.split_at(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(nonce).map_err(|_| anyhow::anyhow!("invalid nonce"))?;
+        let mut in_out = ciphertext.to_vec();
+        let plaintext = self.key.open_in_place(nonce, Aad::from(ENCRYPTED_MAGIC), &mut in_out)
+            .map_err(|_| anyhow::anyhow!("decryption failed: wrong key or corrupted object"))?;
+        Ok(plaintext.to_vec())
+    }
+}
+
+pub fn is_encrypted(bytes: &[u8]) -> bool {
+    bytes.starts_with(ENCRYPTED_MAGIC) || bytes.starts_with(STREAM_MAGIC)
+}
+
+fn chunk_nonce(prefix: &[u8; NONCE_PREFIX_LENGTH], counter: u32, last: bool) -> Nonce {
+    let mut nonce = [0u8; NONCE_LEN];
+    nonce[..NONCE_PREFIX_LENGTH].copy_from_slice(prefix);
+    nonce[NONCE_PREFIX_LENGTH..NONCE_LEN - 1].copy_from_slice(&counter.to_be_bytes());
+    nonce[NONCE_LEN - 1] = u8::from(last);
+    Nonce::assume_unique_for_key(nonce)
+}
+
+/// Seals everything written to it as a stream-sealed object, one chunk at a
+/// time; only `finish` writes the last chunk, so an object cut short never opens
+pub struct SealingWriter<W: Write> {
+    key: Arc<StorageKey>,
+    inner: W,
+    prefix: [u8; NONCE_PREFIX_LENGTH],
+    counter: u32,
+    /// Plaintext of the chunk being filled
+    buffer: Vec<u8>,
+}
+
+impl<W: Write> std::fmt::Debug for SealingWriter<W> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SealingWriter").field("chunks", &self.counter).finish_non_exhaustive()
+    }
+}
+
+impl<W: Write> SealingWriter<W> {
+    pub fn new(key: Arc<StorageKey>, mut inner: W) -> io::Result<Self> {
+        let mut prefix = [0u8; NONCE_PREFIX_LENGTH];
+        key.rng.fill(&mut prefix).map_err(|_| io::Error::other("no randomness available for a nonce"))?;
+        inner.write_all(STREAM_MAGIC)?;
+        inner.write_all(&prefix)?;
+        Ok(Self { key, inner, prefix, counter: 0, buffer: Vec::with_capacity(STREAM_CHUNK_LENGTH + TAG_LENGTH) })
+    }
+
+    fn seal_chunk(&mut self, last: bool) -> io::Result<()> {
+        let nonce = chunk_nonce(&self.prefix, self.counter, last);
+        self.key.key.seal_in_place_append_tag(nonce, Aad::from(STREAM_MAGIC), &mut self.buffer)
+            .map_err(|_| io::Error::other("encryption failed"))?;
+        self.inner.write_all(&self.buffer)?;
+        self.buffer.clear();
+        self.counter = self.counter.checked_add(1).ok_or_else(|| io::Error::other("object too large to seal"))?;
+        Ok(())
+    }
+
+    /// Seal the last chunk and return the inner writer
+    pub fn finish(mut self) -> io::Result<W> {
+        self.seal_chunk(true)?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> Write for SealingWriter<W> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        // A full chunk is only sealed once more follows, as the last one is flagged
+        if self.buffer.len() == STREAM_CHUNK_LENGTH && !data.is_empty() {
+            self.seal_chunk(false)?;
+        }
+        let taken = data.len().min(STREAM_CHUNK_LENGTH - self.buffer.len());
+        self.buffer.extend_from_slice(&data[..taken]);
+        Ok(taken)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Reads the plaintext of a stream-sealed object, checking each chunk before
+/// handing out any of it
+pub struct OpeningReader<'a, R: Read> {
+    key: &'a StorageKey,
+    inner: R,
+    prefix: [u8; NONCE_PREFIX_LENGTH],
+    counter: u32,
+    /// Ciphertext read ahead: a chunk and one byte more, telling whether it is the last
+    sealed: Vec<u8>,
+    plaintext: Vec<u8>,
+    position: usize,
+    finished: bool,
+}
+
+impl<'a, R: Read> OpeningReader<'a, R> {
+    pub fn new(key: &'a StorageKey, mut inner: R) -> io::Result<Self> {
+        let mut magic = [0u8; 8];
+        inner.read_exact(&mut magic)?;
+        if &magic != STREAM_MAGIC {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "not a stream-sealed object"));
+        }
+        let mut prefix = [0u8; NONCE_PREFIX_LENGTH];
+        inner.read_exact(&mut prefix)?;
+        Ok(Self { key, inner, prefix, counter: 0, sealed: Vec::new(), plaintext: Vec::new(), position: 0, finished: false })
+    }
+
+    fn open_chunk(&mut self) -> io::Result<()> {
+        let record_length = STREAM_CHUNK_LENGTH + TAG_LENGTH;
+        while self.sealed.len() <= record_length {
+            let start = self.sealed.len();
+            self.sealed.resize(record_length + 1, 0);
+            match self.inner.read(&mut self.sealed[start..]) {
+                Ok(read) => {
+                    self.sealed.truncate(start + read);
+                    if read == 0 {
+                        break;
+                    }
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => self.sealed.truncate(start),
+                Err(e) => return Err(e),
+            }
+        }
+        let last = self.sealed.len() <= record_length;
+        let ahead = if last { Vec::new() } else { self.sealed.split_off(record_length) };
+        let mut chunk = std::mem::replace(&mut self.sealed, ahead);
+        let nonce = chunk_nonce(&self.prefix, self.counter, last);
+        let length = self.key.key.open_in_place(nonce, Aad::from(STREAM_MAGIC), &mut chunk)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData,
+                                        "decryption failed: wrong key, or a corrupted or truncated object"))?
+            .len();
+        chunk.truncate(length);
+        self.plaintext = chunk;
+        self.position = 0;
+        self.counter = self.counter.wrapping_add(1);
+        self.finished = last;
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for OpeningReader<'_, R> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        while self.position == self.plaintext.len() {
+            if self.finished || buffer.is_empty() {
+                return Ok(0);
+            }
+            self.open_chunk()?;
+        }
+        let length = buffer.len().min(self.plaintext.len() - self.position);
+        buffer[..length].copy_from_slice(&self.plaintext[self.position..self.position + length]);
+        self.position += length;
+        Ok(length)
+    }
+}
+
+/// Read a stored object, decrypting it if it was sealed
+pub fn read_object(path: &Path, key: Option<&StorageKey>) -> Result<Vec<u8>> {
+    let bytes = std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    match (is_encrypted(&bytes), key) {
+        (false, _) => Ok(bytes),
+        (true, Some(key)) => key.open(&bytes).with_context(|| format!("Failed to decrypt {}", path.display())),
+        (true, None) => anyhow::bail!("{} is encrypted and no key was given", path.display()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY_HEX: &str = "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f";
+
+    #[test]
+    fn test_seal_and_open() {
+        let key = StorageKey::from_hex(KEY_HEX).unwrap();
+        let dataset = b"\x08\x00\x18\x00UI\x06\x001.2.3\0".to_vec();
+
+        let sealed = key.seal(&dataset).unwrap();
+        assert!(is_encrypted(&sealed));
+        assert_eq!(sealed.len(), ENCRYPTED_MAGIC.len() + NONCE_LEN + dataset.len() + 16);
+        assert_ne!(key.seal(&dataset).unwrap(), sealed, "every object gets a fresh nonce");
+        assert_eq!(key.open(&sealed).unwrap(), dataset);
+
+        let mut tampered = sealed.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(key.open(&tampered).is_err());
+        assert!(StorageKey::from_bytes(&[7; KEY_LENGTH]).unwrap().open(&sealed).is_err());
+        assert!(key.open(&sealed[..20]).is_err());
+    }
+
+    #[test]
+    fn test_stream_sealing() {
+        let key = Arc::new(StorageKey::from_hex(KEY_HEX).unwrap());
+        let seal = |plaintext: &[u8]| {
+            let mut writer = SealingWriter::new(Arc::clone(&key), Vec::new()).unwrap();
+            for piece in plaintext.chunks(100_000) {
+                writer.write_all(piece).unwrap();
+            }
+            writer.finish().unwrap()
+        };
+        let object: Vec<u8> = (0..2 * STREAM_CHUNK_LENGTH + 12345).map(|i| (i % 251) as u8).collect();
+
+        let sealed = seal(&object);
+        assert!(is_encrypted(&sealed));
+        assert_eq!(sealed.len(), 8 + NONCE_PREFIX_LENGTH + object.len() + 3 * TAG_LENGTH);
+        assert_eq!(key.open(&sealed).unwrap(), object);
+        let mut streamed = Vec::new();
+        OpeningReader::new(&key, &sealed[..]).unwrap().read_to_end(&mut streamed).unwrap();
+        assert_eq!(streamed, object);
+
+        // A whole number of chunks ends on a full last chunk; cut at a chunk boundary, it no longer opens
+        let exact = &object[..STREAM_CHUNK_LENGTH];
+        let sealed_exact = seal(exact);
+        assert_eq!(key.open(&sealed_exact).unwrap(), exact);
+        assert!(key.open(&sealed[..8 + NONCE_PREFIX_LENGTH + STREAM_CHUNK_LENGTH + TAG_LENGTH]).is_err());
+        assert!(key.open(&sealed[..sealed.len() - 1]).is_err());
+        assert!(key.open(&sealed[..8 + NONCE_PREFIX_LENGTH]).is_err());
+        assert_eq!(key.open(&seal(b"")).unwrap(), b"");
+    }
+
+    #[test]
+    fn test_key_sources_and_transparent_reads() {
+        let dir = std::env::temp_dir().join(format!("encryption_test_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let hex_file = dir.join("key.hex");
+        std::fs::write(&hex_file, format!("{}\n", KEY_HEX)).unwrap();
+        let raw_file = dir.join("key.bin");
+        std::fs::write(&raw_file, hex::decode(KEY_HEX).unwrap()).unwrap();
+        let short_file = dir.join("key.short");
+        std::fs::write(&short_file, b"too short").unwrap();
+
+        let key = StorageKey::from_file(&hex_file).unwrap();
+        let sealed_path = dir.join("sealed.dcm");
+        std::fs::write(&sealed_path, key.seal(b"dataset").unwrap()).unwrap();
+        let plain_path = dir.join("plain.dcm");
+        std::fs::write(&plain_path, b"dataset").unwrap();
+
+        let raw_key = StorageKey::from_file(&raw_file).unwrap();
+        assert_eq!(read_object(&sealed_path, Some(&raw_key)).unwrap(), b"dataset");
+        assert_eq!(read_object(&plain_path, Some(&raw_key)).unwrap(), b"dataset");
+        assert!(read_object(&sealed_path, None).is_err());
+        assert!(StorageKey::from_file(&short_file).is_err());
+        assert!(StorageKey::from_env("RUST_DICOM_TEST_UNSET_KEY_VARIABLE").is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
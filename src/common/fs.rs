@@ -0,0 +1,20 @@
+//! Gathering the input files of the command line tools
+
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Append to `files` the file at `path`, or the files in the directory at
+/// `path` and, with `recursive`, in its subdirectories. Whether they hold
+/// DICOM is left to the caller, which reports the files it cannot read
+pub fn collect_dicom_files(path: &Path, recursive: bool, files: &mut Vec<PathBuf>) {
+    if path.is_file() {
+        files.push(path.to_path_buf());
+    } else if path.is_dir() {
+        let walker = if recursive { WalkDir::new(path) } else { WalkDir::new(path).max_depth(1) };
+        for entry in walker.into_iter().filter_map(|e| e.ok()) {
+            if entry.file_type().is_file() {
+                files.push(entry.path().to_path_buf());
+            }
+        }
+    }
+}
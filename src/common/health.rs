@@ -0,0 +1,236 @@
+//! HTTP health and readiness probes
+//!
+//! With `--health-port`, the receiver and the sender daemon answer two
+//! endpoints over plain HTTP for Kubernetes probes: `/healthz` succeeds as
+//! long as the process serves requests, and `/readyz` only once the DICOM
+//! listeners are bound and accepting associations (for the daemon, once its
+//! job queue is open) and until shutdown begins. It is a deliberately small
+//! HTTP/1.1 responder: one request per connection, no body, no keep-alive.
+//!
+//! The receiver also serves `/metrics`, counting C-ECHO requests apart from
+//! C-STOREs. With `--selftest-interval` it C-ECHOes its own listeners through
+//! the loopback; `/healthz` fails for as long as the last self-test failed,
+//! so a receiver whose DICOM stack no longer answers gets restarted.
+
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tracing::{debug, warn};
+
+use super::listen::bind_listener;
+
+/// Longest request head read; probes send a few dozen bytes
+const MAX_REQUEST_LENGTH: usize = 4096;
+/// A probe connection that sends nothing is closed after this long
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Whether the process is ready for traffic, shared with the probe endpoint
+#[derive(Debug)]
+pub struct Readiness {
+    /// Components that have yet to come up, e.g. listeners not bound yet
+    pending: AtomicUsize,
+    draining: AtomicBool,
+    came_up: tokio::sync::Notify,
+}
+
+impl Readiness {
+    /// Ready once `components` have reported themselves up
+    pub fn new(components: usize) -> Self {
+        Self { pending: AtomicUsize::new(components), draining: AtomicBool::new(false), came_up: tokio::sync::Notify::new() }
+    }
+
+    /// One component is up
+    pub fn component_ready(&self) {
+        if self.pending.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |pending| pending.checked_sub(1)) == Ok(1) {
+            self.came_up.notify_waiters();
+        }
+    }
+
+    /// Resolves once every component has come up
+    pub async fn ready(&self) {
+        loop {
+            let came_up = self.came_up.notified();
+            if self.pending.load(Ordering::SeqCst) == 0 {
+                return;
+            }
+            came_up.await;
+        }
+    }
+
+    /// Shutdown has begun: no longer ready, whatever else
+    pub fn drain(&self) {
+        self.draining.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.pending.load(Ordering::SeqCst) == 0 && !self.draining.load(Ordering::SeqCst)
+    }
+}
+
+/// Calling AE title of the receiver's self-test, so its C-ECHOes are not counted as requests
+pub const SELF_TEST_AE: &str = "RD_SELFTEST";
+
+/// Requests answered and self-test results, served on `/metrics`
+#[derive(Debug, Default)]
+pub struct ServiceMetrics {
+    echo_requests: AtomicU64,
+    store_requests: AtomicU64,
+    self_tests_passed: AtomicU64,
+    self_tests_failed: AtomicU64,
+    /// Why the last self-test failed, until one passes again
+    self_test_failure: Mutex<Option<String>>,
+}
+
+impl ServiceMetrics {
+    /// A C-ECHO request was answered
+    pub fn echo_answered(&self) {
+        self.echo_requests.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// A C-STORE request was answered, whatever its status
+    pub fn store_answered(&self) {
+        self.store_requests.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Outcome of one self-test, with the reason when it failed
+    pub fn record_self_test(&self, outcome: Result<(), String>) {
+        let counter = if outcome.is_ok() { &self.self_tests_passed } else { &self.self_tests_failed };
+        counter.fetch_add(1, Ordering::Relaxed);
+        *self.self_test_failure.lock().unwrap_or_else(PoisonError::into_inner) = outcome.err();
+    }
+
+    /// Why the last self-test failed, if it did
+    pub fn self_test_failure(&self) -> Option<String> {
+        self.self_test_failure.lock().unwrap_or_else(PoisonError::into_inner).clone()
+    }
+
+    /// The counters in the Prometheus text format
+    pub fn render(&self) -> String {
+        let counters = [
+            ("dicom_echo_requests_total", "C-ECHO requests answered, self-tests excluded", &self.echo_requests),
+            ("dicom_store_requests_total", "C-STORE requests answered", &self.store_requests),
+            ("dicom_self_tests_passed_total", "Self-test C-ECHOes answered with Success", &self.self_tests_passed),
+            ("dicom_self_tests_failed_total", "Self-test C-ECHOes that failed", &self.self_tests_failed),
+        ];
+        counters.iter().map(|(name, help, counter)| {
+            format!("# HELP {} {}\n# TYPE {} counter\n{} {}\n", name, help, name, name, counter.load(Ordering::Relaxed))
+        }).collect()
+    }
+}
+
+/// Status line and body answering the request whose first line is `request_line`;
+/// without `metrics` there is no `/metrics` endpoint
+pub fn respond(request_line: &str, readiness: &Readiness, metrics: Option<&ServiceMetrics>) -> (&'static str, String) {
+    let mut parts = request_line.split_whitespace();
+    let (method, target) = (parts.next().unwrap_or_default(), parts.next().unwrap_or_default());
+    let path = target.split('?').next().unwrap_or_default();
+    let self_test_failure = metrics.and_then(ServiceMetrics::self_test_failure);
+    match (method, path) {
+        ("GET" | "HEAD", "/healthz") => match self_test_failure {
+            Some(reason) => ("503 Service Unavailable", format!("self-test failed: {}\n", reason)),
+            None => ("200 OK", "ok\n".to_string()),
+        },
+        ("GET" | "HEAD", "/readyz") if readiness.is_ready() => ("200 OK", "ready\n".to_string()),
+        ("GET" | "HEAD", "/readyz") => ("503 Service Unavailable", "not ready\n".to_string()),
+        ("GET" | "HEAD", "/metrics") if metrics.is_some() => ("200 OK", metrics.map(ServiceMetrics::render).unwrap_or_default()),
+        ("GET" | "HEAD", _) => ("404 Not Found", "not found\n".to_string()),
+        _ => ("405 Method Not Allowed", "method not allowed\n".to_string()),
+    }
+}
+
+/// Bind the probe endpoint on `address`
+pub fn bind_health_listener(address: SocketAddr) -> std::io::Result<tokio::net::TcpListener> {
+    tokio::net::TcpListener::from_std(bind_listener(address.ip(), address.port(), false)?)
+}
+
+/// Answer probes on `listener` until the task is dropped, and `/metrics` given `metrics`
+pub async fn serve_health(listener: tokio::net::TcpListener, readiness: Arc<Readiness>, metrics: Option<Arc<ServiceMetrics>>) {
+    loop {
+        let (stream, addr) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                warn!("⚠️  Health endpoint failed to accept a connection: {}", e);
+                continue;
+            }
+        };
+        let readiness = Arc::clone(&readiness);
+        let metrics = metrics.clone();
+        tokio::spawn(async move {
+            if let Err(e) = answer(stream, &readiness, metrics.as_deref()).await {
+                debug!("Health probe from {} failed: {}", addr, e);
+            }
+        });
+    }
+}
+
+async fn answer(mut stream: tokio::net::TcpStream, readiness: &Readiness, metrics: Option<&ServiceMetrics>) -> std::io::Result<()> {
+    let mut request = Vec::new();
+    let mut buffer = [0u8; 512];
+    // The request line is all that matters; the rest of the head is read only so the peer sees no reset
+    while !request.windows(4).any(|window| window == b"\r\n\r\n") && request.len() < MAX_REQUEST_LENGTH {
+        let read = tokio::time::timeout(REQUEST_TIMEOUT, stream.read(&mut buffer)).await
+            .map_err(|_| std::io::Error::from(std::io::ErrorKind::TimedOut))??;
+        if read == 0 {
+            break;
+        }
+        request.extend_from_slice(&buffer[..read]);
+    }
+    let request = String::from_utf8_lossy(&request);
+    let request_line = request.lines().next().unwrap_or_default();
+    let (status, body) = respond(request_line, readiness, metrics);
+    let body = if request_line.starts_with("HEAD ") { "" } else { body.as_str() };
+    let response = format!("HTTP/1.1 {}\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                           status, body.len(), body);
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_respond() {
+        let readiness = Readiness::new(2);
+        assert_eq!(respond("GET /healthz HTTP/1.1", &readiness, None).0, "200 OK");
+        assert_eq!(respond("GET /readyz HTTP/1.1", &readiness, None).0, "503 Service Unavailable");
+
+        readiness.component_ready();
+        readiness.component_ready();
+        readiness.component_ready();
+        assert_eq!(respond("GET /readyz?verbose HTTP/1.1", &readiness, None).0, "200 OK");
+        assert_eq!(respond("HEAD /readyz HTTP/1.1", &readiness, None).0, "200 OK");
+        assert_eq!(respond("GET /metrics HTTP/1.1", &readiness, None).0, "404 Not Found");
+        assert_eq!(respond("POST /readyz HTTP/1.1", &readiness, None).0, "405 Method Not Allowed");
+
+        readiness.drain();
+        assert_eq!(respond("GET /readyz HTTP/1.1", &readiness, None).0, "503 Service Unavailable");
+        assert_eq!(respond("GET /healthz HTTP/1.1", &readiness, None).0, "200 OK");
+    }
+
+    #[test]
+    fn test_metrics_and_self_test() {
+        let readiness = Readiness::new(0);
+        let metrics = ServiceMetrics::default();
+        metrics.echo_answered();
+        metrics.store_answered();
+        metrics.store_answered();
+        let (status, body) = respond("GET /metrics HTTP/1.1", &readiness, Some(&metrics));
+        assert_eq!(status, "200 OK");
+        assert!(body.contains("\ndicom_echo_requests_total 1\n"));
+        assert!(body.contains("\ndicom_store_requests_total 2\n"));
+
+        metrics.record_self_test(Err("connection refused".to_string()));
+        assert_eq!(respond("GET /healthz HTTP/1.1", &readiness, Some(&metrics)),
+                   ("503 Service Unavailable", "self-test failed: connection refused\n".to_string()));
+        // Readiness is about accepting traffic, not about the last self-test
+        assert_eq!(respond("GET /readyz HTTP/1.1", &readiness, Some(&metrics)).0, "200 OK");
+        metrics.record_self_test(Ok(()));
+        assert_eq!(respond("GET /healthz HTTP/1.1", &readiness, Some(&metrics)).0, "200 OK");
+        let body = metrics.render();
+        assert!(body.contains("\ndicom_self_tests_passed_total 1\n"));
+        assert!(body.contains("\ndicom_self_tests_failed_total 1\n"));
+    }
+}
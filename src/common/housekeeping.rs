@@ -0,0 +1,181 @@
+//! Storage housekeeping against the receive index
+//!
+//! `dicom-receiver gc` keeps an output directory and its index in step after
+//! purges and manual cleanups: it removes the patient, study and series
+//! directories a filename template left empty, and finds stored objects that
+//! the index does not know, which can be imported into the index or deleted.
+//! Only Part 10 files, encrypted or not, count as stored objects; anything
+//! else below the output directory is left alone.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use std::collections::HashSet;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+use super::encryption::{is_encrypted, read_object, StorageKey};
+use super::part10::{dataset_offset, is_part10};
+use super::receive_index::{indexed_attributes, IndexedInstance};
+use super::recovery::{INCOMPLETE_DIR, PARTIAL_EXTENSION};
+use super::resume::RECEIVED_INSTANCES_DIR;
+
+/// Directories below an output directory the receiver keeps its own files in
+const RECEIVER_DIRS: [&str; 4] = [INCOMPLETE_DIR, RECEIVED_INSTANCES_DIR, "study_reports", "bundles"];
+
+/// Bytes enough to tell a Part 10 file or an encrypted object from anything else
+const HEADER_LENGTH: usize = 132;
+
+/// What becomes of stored objects the index does not know
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum OrphanAction {
+    /// List them only
+    Report,
+    /// Record them in the index as if they had just been received
+    Import,
+    /// Delete them, unless their study is under legal hold
+    Delete,
+}
+
+/// `path` as the index would know it, resolved so relative and absolute paths compare equal
+pub fn normalized(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+fn is_receiver_dir(output_dir: &Path, path: &Path) -> bool {
+    RECEIVER_DIRS.iter().any(|dir| path == output_dir.join(dir))
+}
+
+/// Whether the file at `path` holds a stored object
+fn is_stored_object(path: &Path) -> bool {
+    let mut header = Vec::with_capacity(HEADER_LENGTH);
+    let read = std::fs::File::open(path)
+        .and_then(|file| file.take(HEADER_LENGTH as u64).read_to_end(&mut header));
+    read.is_ok() && (is_part10(&header) || is_encrypted(&header))
+}
+
+/// Stored objects below `output_dir` whose normalized path is not in `indexed`
+pub fn find_orphans(output_dir: &Path, indexed: &HashSet<PathBuf>) -> Vec<PathBuf> {
+    WalkDir::new(output_dir)
+        .into_iter()
+        .filter_entry(|entry| !is_receiver_dir(output_dir, entry.path()))
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .map(|entry| entry.into_path())
+        // Still being written
+        .filter(|path| path.extension().is_none_or(|ext| ext != PARTIAL_EXTENSION))
+        .filter(|path| !indexed.contains(&normalized(path)) && is_stored_object(path))
+        .collect()
+}
+
+/// Remove the empty directories below `output_dir`, deepest first, leaving
+/// `output_dir` itself and the receiver's own directories; with `dry_run`
+/// only list them. A directory holding nothing but empty directories is empty
+pub fn remove_empty_dirs(output_dir: &Path, dry_run: bool) -> Result<Vec<PathBuf>> {
+    let dirs: Vec<PathBuf> = WalkDir::new(output_dir)
+        .min_depth(1)
+        .into_iter()
+        .filter_entry(|entry| !is_receiver_dir(output_dir, entry.path()))
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_dir())
+        .map(|entry| entry.into_path())
+        .collect();
+    let mut removed: Vec<PathBuf> = Vec::new();
+    // Every directory is listed before what it holds, so in reverse its subdirectories come first
+    for path in dirs.into_iter().rev() {
+        let mut entries = std::fs::read_dir(&path).with_context(|| format!("Failed to list {}", path.display()))?;
+        // In a dry run nothing was removed, so the children listed as removed count as gone
+        let empty = entries.all(|entry| entry.is_ok_and(|entry| removed.contains(&entry.path())));
+        if !empty {
+            continue;
+        }
+        if !dry_run {
+            std::fs::remove_dir(&path).with_context(|| format!("Failed to remove {}", path.display()))?;
+        }
+        removed.push(path);
+    }
+    Ok(removed)
+}
+
+/// The index record of the orphaned object at `path`, decrypted with `key`
+/// if it was sealed; it counts as received when the file was last modified
+pub fn orphan_instance(path: &Path, key: Option<&StorageKey>) -> Result<IndexedInstance> {
+    let bytes = read_object(path, key)?;
+    let offset = dataset_offset(&bytes).with_context(|| format!("{} is not a DICOM Part 10 file", path.display()))?;
+    let obj = dicom_object::from_reader(&bytes[128..])
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+    let mut attributes = indexed_attributes(&obj);
+    attributes.insert("TransferSyntaxUID".to_string(), obj.meta().transfer_syntax().trim_end_matches('\0').to_string());
+    let sop_instance_uid = attributes.get("SOPInstanceUID").cloned()
+        .with_context(|| format!("{} has no SOP Instance UID", path.display()))?;
+    let modified = std::fs::metadata(path)?.modified()?;
+    Ok(IndexedInstance {
+        sop_instance_uid,
+        calling_ae: obj.meta().source_application_entity_title.clone().unwrap_or_default().trim().to_string(),
+        path: path.to_path_buf(),
+        size: (bytes.len() - offset) as u64,
+        received_at: DateTime::<Utc>::from(modified),
+        attributes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("housekeeping_test_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// A file that passes for a Part 10 object
+    fn part10(path: &Path) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let mut bytes = vec![0u8; 128];
+        bytes.extend_from_slice(b"DICM");
+        std::fs::write(path, bytes).unwrap();
+    }
+
+    #[test]
+    fn test_find_orphans() {
+        let dir = scratch_dir();
+        let indexed = dir.join("CT").join("1.dcm");
+        let orphan = dir.join("CT").join("2.dcm");
+        part10(&indexed);
+        part10(&orphan);
+        part10(&dir.join(INCOMPLETE_DIR).join("3.dcm"));
+        part10(&dir.join("CT").join("4.dcm.partial"));
+        std::fs::write(dir.join("notes.txt"), b"not an object").unwrap();
+
+        let known: HashSet<PathBuf> = [normalized(&indexed)].into_iter().collect();
+        assert_eq!(find_orphans(&dir, &known), [orphan]);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_remove_empty_dirs() {
+        let dir = scratch_dir();
+        let patient = dir.join("PAT-1");
+        std::fs::create_dir_all(patient.join("STUDY-1").join("SERIES-1")).unwrap();
+        std::fs::create_dir_all(patient.join("STUDY-2")).unwrap();
+        part10(&patient.join("STUDY-2").join("1.dcm"));
+        std::fs::create_dir_all(dir.join("PAT-2").join("STUDY-3")).unwrap();
+        std::fs::create_dir_all(dir.join(RECEIVED_INSTANCES_DIR)).unwrap();
+
+        let mut listed = remove_empty_dirs(&dir, true).unwrap();
+        listed.sort();
+        assert_eq!(listed, [dir.join("PAT-1").join("STUDY-1"), dir.join("PAT-1").join("STUDY-1").join("SERIES-1"),
+                            dir.join("PAT-2"), dir.join("PAT-2").join("STUDY-3")]);
+        assert!(patient.join("STUDY-1").exists(), "a dry run removes nothing");
+
+        let mut removed = remove_empty_dirs(&dir, false).unwrap();
+        removed.sort();
+        assert_eq!(removed, listed);
+        assert!(!patient.join("STUDY-1").exists());
+        assert!(!dir.join("PAT-2").exists());
+        assert!(patient.join("STUDY-2").join("1.dcm").exists());
+        assert!(dir.join(RECEIVED_INSTANCES_DIR).exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
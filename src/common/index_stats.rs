@@ -0,0 +1,280 @@
+//! Summary statistics of the receive index
+//!
+//! `dicom-receiver stats` reads the instances recorded with `--index` and
+//! summarises what the archive holds: studies, series and instances by
+//! modality, storage by SOP class category and by transfer syntax, how the
+//! archive grew per day, month or year, and its largest studies. A study
+//! holding several modalities is counted under each of them.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::Write as _;
+
+use super::receive_index::IndexedInstance;
+use super::sop_classes::SopClassRegistry;
+use super::transfer_syntaxes::TransferSyntaxRegistry;
+
+/// Reported for instances recorded without the attribute grouped by
+const UNKNOWN: &str = "(unknown)";
+
+/// Granularity of the growth over time
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Period {
+    Day,
+    Month,
+    Year,
+}
+
+impl Period {
+    fn label(self, received_at: &DateTime<Utc>) -> String {
+        let format = match self {
+            Period::Day => "%Y-%m-%d",
+            Period::Month => "%Y-%m",
+            Period::Year => "%Y",
+        };
+        received_at.format(format).to_string()
+    }
+}
+
+/// Studies, series, instances and bytes of one group of instances
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Tally {
+    pub studies: usize,
+    pub series: usize,
+    pub instances: usize,
+    pub bytes: u64,
+}
+
+/// Instances received during one period
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Growth {
+    pub period: String,
+    pub instances: usize,
+    pub bytes: u64,
+    /// Bytes received up to the end of the period
+    pub total_bytes: u64,
+}
+
+/// A study and the storage it takes
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StudySize {
+    pub study_instance_uid: String,
+    pub patient_id: String,
+    pub study_date: String,
+    pub modalities: Vec<String>,
+    pub instances: usize,
+    pub bytes: u64,
+}
+
+/// What the index holds
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct IndexStats {
+    pub total: Tally,
+    pub by_modality: BTreeMap<String, Tally>,
+    /// By SOP class category, e.g. ComputedTomography
+    pub by_category: BTreeMap<String, Tally>,
+    /// By transfer syntax UID
+    pub by_transfer_syntax: BTreeMap<String, Tally>,
+    pub growth: Vec<Growth>,
+    /// Largest first
+    pub largest_studies: Vec<StudySize>,
+}
+
+/// Tally under construction, counting distinct studies and series
+#[derive(Default)]
+struct Counter<'a> {
+    studies: HashSet<&'a str>,
+    series: HashSet<&'a str>,
+    instances: usize,
+    bytes: u64,
+}
+
+impl<'a> Counter<'a> {
+    fn add(&mut self, instance: &'a IndexedInstance) {
+        self.studies.insert(instance.attribute("StudyInstanceUID"));
+        self.series.insert(instance.attribute("SeriesInstanceUID"));
+        self.instances += 1;
+        self.bytes += instance.size;
+    }
+
+    fn tally(&self) -> Tally {
+        Tally { studies: self.studies.len(), series: self.series.len(), instances: self.instances, bytes: self.bytes }
+    }
+}
+
+fn tallies(counters: BTreeMap<String, Counter>) -> BTreeMap<String, Tally> {
+    counters.into_iter().map(|(key, counter)| (key, counter.tally())).collect()
+}
+
+fn or_unknown(value: &str) -> String {
+    if value.is_empty() { UNKNOWN.to_string() } else { value.to_string() }
+}
+
+/// Summarise `instances`, keeping the `largest` studies by bytes stored
+pub fn index_stats(instances: &[IndexedInstance], period: Period, largest: usize) -> IndexStats {
+    let sop_classes = SopClassRegistry::global();
+    let mut total = Counter::default();
+    let mut by_modality: BTreeMap<String, Counter> = BTreeMap::new();
+    let mut by_category: BTreeMap<String, Counter> = BTreeMap::new();
+    let mut by_transfer_syntax: BTreeMap<String, Counter> = BTreeMap::new();
+    let mut by_period: BTreeMap<String, (usize, u64)> = BTreeMap::new();
+    let mut studies: HashMap<&str, StudySize> = HashMap::new();
+
+    for instance in instances {
+        total.add(instance);
+        by_modality.entry(or_unknown(instance.attribute("Modality"))).or_default().add(instance);
+        let category = sop_classes.get(instance.attribute("SOPClassUID"))
+            .map(|info| format!("{:?}", info.category))
+            .unwrap_or_else(|| UNKNOWN.to_string());
+        by_category.entry(category).or_default().add(instance);
+        by_transfer_syntax.entry(or_unknown(instance.attribute("TransferSyntaxUID"))).or_default().add(instance);
+
+        let received = by_period.entry(period.label(&instance.received_at)).or_default();
+        received.0 += 1;
+        received.1 += instance.size;
+
+        let study_instance_uid = instance.attribute("StudyInstanceUID");
+        let study = studies.entry(study_instance_uid).or_insert_with(|| StudySize {
+            study_instance_uid: study_instance_uid.to_string(),
+            patient_id: instance.attribute("PatientID").to_string(),
+            study_date: instance.attribute("StudyDate").to_string(),
+            modalities: Vec::new(),
+            instances: 0,
+            bytes: 0,
+        });
+        let modality = instance.attribute("Modality");
+        if !modality.is_empty() && !study.modalities.iter().any(|listed| listed == modality) {
+            study.modalities.push(modality.to_string());
+        }
+        study.instances += 1;
+        study.bytes += instance.size;
+    }
+
+    let mut total_bytes = 0;
+    let growth = by_period.into_iter().map(|(period, (instances, bytes))| {
+        total_bytes += bytes;
+        Growth { period, instances, bytes, total_bytes }
+    }).collect();
+
+    let mut largest_studies: Vec<StudySize> = studies.into_values().collect();
+    largest_studies.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.study_instance_uid.cmp(&b.study_instance_uid)));
+    largest_studies.truncate(largest);
+    for study in &mut largest_studies {
+        study.modalities.sort();
+    }
+
+    IndexStats {
+        total: total.tally(),
+        by_modality: tallies(by_modality),
+        by_category: tallies(by_category),
+        by_transfer_syntax: tallies(by_transfer_syntax),
+        growth,
+        largest_studies,
+    }
+}
+
+fn megabytes(bytes: u64) -> f64 {
+    bytes as f64 / (1024.0 * 1024.0)
+}
+
+fn tally_rows(text: &mut String, title: &str, tallies: &BTreeMap<String, Tally>, name: impl Fn(&str) -> String) {
+    let _ = writeln!(text, "\n{}", title);
+    // Largest first
+    let mut rows: Vec<(&String, &Tally)> = tallies.iter().collect();
+    rows.sort_by_key(|(_, tally)| std::cmp::Reverse(tally.bytes));
+    for (key, tally) in rows {
+        let _ = writeln!(text, "  {:<40} {:>8} studies {:>8} series {:>10} instances {:>12.2} MB",
+                         name(key), tally.studies, tally.series, tally.instances, megabytes(tally.bytes));
+    }
+}
+
+/// `stats` as a plain text report
+pub fn stats_text(stats: &IndexStats) -> String {
+    let transfer_syntaxes = TransferSyntaxRegistry::global();
+    let mut text = String::new();
+    let _ = writeln!(text, "Total: {} studies, {} series, {} instances, {:.2} MB",
+                     stats.total.studies, stats.total.series, stats.total.instances, megabytes(stats.total.bytes));
+
+    tally_rows(&mut text, "By modality", &stats.by_modality, str::to_string);
+    tally_rows(&mut text, "By SOP class category", &stats.by_category, str::to_string);
+    tally_rows(&mut text, "By transfer syntax", &stats.by_transfer_syntax, |uid| match transfer_syntaxes.get_name(uid) {
+        Some(name) => format!("{} ({})", name, uid),
+        None => uid.to_string(),
+    });
+
+    let _ = writeln!(text, "\nReceived over time");
+    for growth in &stats.growth {
+        let _ = writeln!(text, "  {:<10} {:>10} instances {:>12.2} MB {:>12.2} MB in total",
+                         growth.period, growth.instances, megabytes(growth.bytes), megabytes(growth.total_bytes));
+    }
+
+    let _ = writeln!(text, "\nLargest studies");
+    for study in &stats.largest_studies {
+        let _ = writeln!(text, "  {}  {:<16} {:<8} {:<12} {:>8} instances {:>12.2} MB", study.study_instance_uid,
+                         or_unknown(&study.patient_id), or_unknown(&study.study_date),
+                         study.modalities.join("/"), study.instances, megabytes(study.bytes));
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use std::path::PathBuf;
+
+    fn instance(study: &str, series: &str, modality: &str, sop_class: &str, size: u64, month: u32) -> IndexedInstance {
+        let attributes = [
+            ("StudyInstanceUID", study),
+            ("SeriesInstanceUID", series),
+            ("PatientID", "PAT-1"),
+            ("Modality", modality),
+            ("SOPClassUID", sop_class),
+            ("TransferSyntaxUID", "1.2.840.10008.1.2.1"),
+        ];
+        IndexedInstance {
+            sop_instance_uid: format!("{}.{}", series, size),
+            calling_ae: "MODALITY".to_string(),
+            path: PathBuf::from("/srv/dicom/instance.dcm"),
+            size,
+            received_at: Utc.with_ymd_and_hms(2024, month, 15, 12, 0, 0).unwrap(),
+            attributes: attributes.iter().map(|(keyword, value)| (keyword.to_string(), value.to_string())).collect(),
+        }
+    }
+
+    #[test]
+    fn test_index_stats() {
+        const CT_IMAGE: &str = "1.2.840.10008.5.1.4.1.1.2";
+        const BASIC_TEXT_SR: &str = "1.2.840.10008.5.1.4.1.1.88.11";
+        let instances = [
+            instance("1.1", "1.1.1", "CT", CT_IMAGE, 500, 1),
+            instance("1.1", "1.1.1", "CT", CT_IMAGE, 500, 1),
+            instance("1.1", "1.1.2", "SR", BASIC_TEXT_SR, 10, 2),
+            instance("1.2", "1.2.1", "CT", CT_IMAGE, 2000, 2),
+        ];
+        let stats = index_stats(&instances, Period::Month, 1);
+
+        assert_eq!(stats.total, Tally { studies: 2, series: 3, instances: 4, bytes: 3010 });
+        assert_eq!(stats.by_modality["CT"], Tally { studies: 2, series: 2, instances: 3, bytes: 3000 });
+        assert_eq!(stats.by_modality["SR"], Tally { studies: 1, series: 1, instances: 1, bytes: 10 });
+        assert_eq!(stats.by_category["ComputedTomography"].bytes, 3000);
+        assert_eq!(stats.by_category["StructuredReporting"].bytes, 10);
+        assert_eq!(stats.by_transfer_syntax["1.2.840.10008.1.2.1"].instances, 4);
+        assert_eq!(stats.growth, [
+            Growth { period: "2024-01".to_string(), instances: 2, bytes: 1000, total_bytes: 1000 },
+            Growth { period: "2024-02".to_string(), instances: 2, bytes: 2010, total_bytes: 3010 },
+        ]);
+        assert_eq!(stats.largest_studies.len(), 1);
+        assert_eq!(stats.largest_studies[0].study_instance_uid, "1.2");
+
+        let mut unknown = instance("1.3", "1.3.1", "", "9.9.9", 1, 3);
+        unknown.attributes.remove("TransferSyntaxUID");
+        let stats = index_stats(&[unknown], Period::Year, 10);
+        assert!(stats.by_modality.contains_key(UNKNOWN));
+        assert!(stats.by_category.contains_key(UNKNOWN));
+        assert!(stats.by_transfer_syntax.contains_key(UNKNOWN));
+        assert_eq!(stats.growth[0].period, "2024");
+        assert!(stats_text(&stats).starts_with("Total: 1 studies, 1 series, 1 instances"));
+    }
+}
@@ -0,0 +1,474 @@
+/// IOD conformance validation
+///
+/// This module checks datasets against the module/attribute requirements of
+/// the common image IODs (PS3.3): presence of Type 1 and Type 2 attributes,
+/// non-empty Type 1 values, simple Type 1C/2C conditions and enumerated values.
+
+use dicom_core::header::HasLength;
+use dicom_core::Tag;
+use dicom_object::InMemDicomObject;
+use serde::Serialize;
+use super::uids::sop;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Condition {
+    /// Required when the given attribute is present
+    Present(Tag),
+    /// Required when the given attribute is absent
+    Absent(Tag),
+}
+
+impl Condition {
+    fn holds(&self, obj: &InMemDicomObject) -> bool {
+        match self {
+            Condition::Present(tag) => obj.element(*tag).is_ok(),
+            Condition::Absent(tag) => obj.element(*tag).is_err(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AttributeType {
+    /// Required, must have a value
+    Type1,
+    /// Conditionally required, must have a value
+    Type1C(Condition),
+    /// Required, may be empty
+    Type2,
+    /// Conditionally required, may be empty
+    Type2C(Condition),
+    /// Optional
+    Type3,
+}
+
+#[derive(Debug, Clone)]
+pub struct AttributeSpec {
+    pub tag: Tag,
+    pub keyword: &'static str,
+    pub attribute_type: AttributeType,
+    /// Allowed values, empty when the attribute is not enumerated
+    pub enumerated: &'static [&'static str],
+}
+
+impl AttributeSpec {
+    pub const fn new(tag: Tag, keyword: &'static str, attribute_type: AttributeType) -> Self {
+        Self { tag, keyword, attribute_type, enumerated: &[] }
+    }
+
+    pub const fn enumerated(
+        tag: Tag,
+        keyword: &'static str,
+        attribute_type: AttributeType,
+        enumerated: &'static [&'static str],
+    ) -> Self {
+        Self { tag, keyword, attribute_type, enumerated }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ModuleSpec {
+    pub name: &'static str,
+    pub attributes: &'static [AttributeSpec],
+}
+
+#[derive(Debug, Clone)]
+pub struct IodSpec {
+    pub name: &'static str,
+    pub sop_class_uids: &'static [&'static str],
+    pub modules: &'static [&'static ModuleSpec],
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum ViolationKind {
+    /// A required attribute is absent
+    Missing,
+    /// A Type 1 attribute is present but has no value
+    Empty,
+    /// The value is not one of the enumerated values
+    InvalidValue(String),
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Violation {
+    pub module: &'static str,
+    pub keyword: &'static str,
+    pub tag: String,
+    pub kind: ViolationKind,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct IodReport {
+    pub iod: &'static str,
+    pub violations: Vec<Violation>,
+}
+
+impl IodReport {
+    pub fn is_conformant(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+/// Find the IOD definition for a SOP Class UID
+pub fn find_iod(sop_class_uid: &str) -> Option<&'static IodSpec> {
+    ALL_IODS.iter().find(|iod| iod.sop_class_uids.contains(&sop_class_uid))
+}
+
+/// Validate a dataset against the IOD of its SOP Class UID.
+///
+/// Returns `None` when the SOP Class UID is missing or has no IOD definition here.
+pub fn validate_iod(obj: &InMemDicomObject) -> Option<IodReport> {
+    let sop_class_uid = obj.element(Tag(0x0008, 0x0016)) // SOP Class UID
+        .ok()
+        .and_then(|e| e.string().ok())
+        .map(|s| s.trim().trim_end_matches('\0').to_string())?;
+
+    let iod = find_iod(&sop_class_uid)?;
+    Some(validate_against(obj, iod))
+}
+
+/// Validate a dataset against a specific IOD definition
+pub fn validate_against(obj: &InMemDicomObject, iod: &'static IodSpec) -> IodReport {
+    let mut violations = Vec::new();
+
+    for module in iod.modules {
+        for spec in module.attributes {
+            let (required, needs_value) = match spec.attribute_type {
+                AttributeType::Type1 => (true, true),
+                AttributeType::Type1C(condition) => (condition.holds(obj), true),
+                AttributeType::Type2 => (true, false),
+                AttributeType::Type2C(condition) => (condition.holds(obj), false),
+                AttributeType::Type3 => (false, false),
+            };
+
+            let violation = |kind| Violation {
+                module: module.name,
+                keyword: spec.keyword,
+                tag: format!("({:04X},{:04X})", spec.tag.0, spec.tag.1),
+                kind,
+            };
+
+            let element = match obj.element(spec.tag) {
+                Ok(element) => element,
+                Err(_) => {
+                    if required {
+                        violations.push(violation(ViolationKind::Missing));
+                    }
+                    continue;
+                }
+            };
+
+            if element.is_empty() {
+                if required && needs_value {
+                    violations.push(violation(ViolationKind::Empty));
+                }
+                continue;
+            }
+
+            if !spec.enumerated.is_empty() {
+                if let Ok(value) = element.to_str() {
+                    for item in value.split('\\').map(|v| v.trim().trim_end_matches('\0')) {
+                        if !spec.enumerated.contains(&item) {
+                            violations.push(violation(ViolationKind::InvalidValue(item.to_string())));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    IodReport { iod: iod.name, violations }
+}
+
+// =============================================================================
+// MODULE DEFINITIONS
+// =============================================================================
+
+const PIXEL_DATA: Tag = Tag(0x7FE0, 0x0010);
+const FLOAT_PIXEL_DATA: Tag = Tag(0x7FE0, 0x0008);
+
+const PATIENT_MODULE: ModuleSpec = ModuleSpec {
+    name: "Patient",
+    attributes: &[
+        AttributeSpec::new(Tag(0x0010, 0x0010), "PatientName", AttributeType::Type2),
+        AttributeSpec::new(Tag(0x0010, 0x0020), "PatientID", AttributeType::Type2),
+        AttributeSpec::new(Tag(0x0010, 0x0030), "PatientBirthDate", AttributeType::Type2),
+        AttributeSpec::enumerated(Tag(0x0010, 0x0040), "PatientSex", AttributeType::Type2, &["M", "F", "O"]),
+    ],
+};
+
+const GENERAL_STUDY_MODULE: ModuleSpec = ModuleSpec {
+    name: "General Study",
+    attributes: &[
+        AttributeSpec::new(Tag(0x0020, 0x000D), "StudyInstanceUID", AttributeType::Type1),
+        AttributeSpec::new(Tag(0x0008, 0x0020), "StudyDate", AttributeType::Type2),
+        AttributeSpec::new(Tag(0x0008, 0x0030), "StudyTime", AttributeType::Type2),
+        AttributeSpec::new(Tag(0x0008, 0x0090), "ReferringPhysicianName", AttributeType::Type2),
+        AttributeSpec::new(Tag(0x0020, 0x0010), "StudyID", AttributeType::Type2),
+        AttributeSpec::new(Tag(0x0008, 0x0050), "AccessionNumber", AttributeType::Type2),
+    ],
+};
+
+const GENERAL_SERIES_MODULE: ModuleSpec = ModuleSpec {
+    name: "General Series",
+    attributes: &[
+        AttributeSpec::new(Tag(0x0008, 0x0060), "Modality", AttributeType::Type1),
+        AttributeSpec::new(Tag(0x0020, 0x000E), "SeriesInstanceUID", AttributeType::Type1),
+        AttributeSpec::new(Tag(0x0020, 0x0011), "SeriesNumber", AttributeType::Type2),
+        AttributeSpec::enumerated(Tag(0x0020, 0x0060), "Laterality", AttributeType::Type3, &["R", "L"]),
+    ],
+};
+
+const GENERAL_EQUIPMENT_MODULE: ModuleSpec = ModuleSpec {
+    name: "General Equipment",
+    attributes: &[
+        AttributeSpec::new(Tag(0x0008, 0x0070), "Manufacturer", AttributeType::Type2),
+    ],
+};
+
+const GENERAL_IMAGE_MODULE: ModuleSpec = ModuleSpec {
+    name: "General Image",
+    attributes: &[
+        AttributeSpec::new(Tag(0x0020, 0x0013), "InstanceNumber", AttributeType::Type2),
+    ],
+};
+
+const IMAGE_PLANE_MODULE: ModuleSpec = ModuleSpec {
+    name: "Image Plane",
+    attributes: &[
+        AttributeSpec::new(Tag(0x0028, 0x0030), "PixelSpacing", AttributeType::Type1),
+        AttributeSpec::new(Tag(0x0020, 0x0037), "ImageOrientationPatient", AttributeType::Type1),
+        AttributeSpec::new(Tag(0x0020, 0x0032), "ImagePositionPatient", AttributeType::Type1),
+        AttributeSpec::new(Tag(0x0018, 0x0050), "SliceThickness", AttributeType::Type2),
+    ],
+};
+
+const IMAGE_PIXEL_MODULE: ModuleSpec = ModuleSpec {
+    name: "Image Pixel",
+    attributes: &[
+        AttributeSpec::new(Tag(0x0028, 0x0002), "SamplesPerPixel", AttributeType::Type1),
+        AttributeSpec::new(Tag(0x0028, 0x0004), "PhotometricInterpretation", AttributeType::Type1),
+        AttributeSpec::new(Tag(0x0028, 0x0010), "Rows", AttributeType::Type1),
+        AttributeSpec::new(Tag(0x0028, 0x0011), "Columns", AttributeType::Type1),
+        AttributeSpec::new(Tag(0x0028, 0x0100), "BitsAllocated", AttributeType::Type1),
+        AttributeSpec::new(Tag(0x0028, 0x0101), "BitsStored", AttributeType::Type1),
+        AttributeSpec::new(Tag(0x0028, 0x0102), "HighBit", AttributeType::Type1),
+        AttributeSpec::enumerated(Tag(0x0028, 0x0103), "PixelRepresentation", AttributeType::Type1, &["0", "1"]),
+        AttributeSpec::new(PIXEL_DATA, "PixelData", AttributeType::Type1C(Condition::Absent(FLOAT_PIXEL_DATA))),
+    ],
+};
+
+const SOP_COMMON_MODULE: ModuleSpec = ModuleSpec {
+    name: "SOP Common",
+    attributes: &[
+        AttributeSpec::new(Tag(0x0008, 0x0016), "SOPClassUID", AttributeType::Type1),
+        AttributeSpec::new(Tag(0x0008, 0x0018), "SOPInstanceUID", AttributeType::Type1),
+    ],
+};
+
+const CT_IMAGE_MODULE: ModuleSpec = ModuleSpec {
+    name: "CT Image",
+    attributes: &[
+        AttributeSpec::new(Tag(0x0008, 0x0008), "ImageType", AttributeType::Type1),
+        AttributeSpec::enumerated(Tag(0x0008, 0x0060), "Modality", AttributeType::Type1, &["CT"]),
+        AttributeSpec::enumerated(Tag(0x0028, 0x0002), "SamplesPerPixel", AttributeType::Type1, &["1"]),
+        AttributeSpec::enumerated(Tag(0x0028, 0x0004), "PhotometricInterpretation", AttributeType::Type1, &["MONOCHROME1", "MONOCHROME2"]),
+        AttributeSpec::enumerated(Tag(0x0028, 0x0100), "BitsAllocated", AttributeType::Type1, &["16"]),
+        AttributeSpec::new(Tag(0x0028, 0x1052), "RescaleIntercept", AttributeType::Type1),
+        AttributeSpec::new(Tag(0x0028, 0x1053), "RescaleSlope", AttributeType::Type1),
+        AttributeSpec::new(Tag(0x0018, 0x0060), "KVP", AttributeType::Type2),
+        AttributeSpec::new(Tag(0x0020, 0x0012), "AcquisitionNumber", AttributeType::Type2),
+    ],
+};
+
+const MR_IMAGE_MODULE: ModuleSpec = ModuleSpec {
+    name: "MR Image",
+    attributes: &[
+        AttributeSpec::new(Tag(0x0008, 0x0008), "ImageType", AttributeType::Type1),
+        AttributeSpec::enumerated(Tag(0x0008, 0x0060), "Modality", AttributeType::Type1, &["MR"]),
+        AttributeSpec::enumerated(Tag(0x0028, 0x0002), "SamplesPerPixel", AttributeType::Type1, &["1"]),
+        AttributeSpec::enumerated(Tag(0x0028, 0x0004), "PhotometricInterpretation", AttributeType::Type1, &["MONOCHROME1", "MONOCHROME2"]),
+        AttributeSpec::enumerated(Tag(0x0028, 0x0100), "BitsAllocated", AttributeType::Type1, &["16"]),
+        AttributeSpec::enumerated(Tag(0x0018, 0x0020), "ScanningSequence", AttributeType::Type1, &["SE", "IR", "GR", "EP", "RM"]),
+        AttributeSpec::new(Tag(0x0018, 0x0021), "SequenceVariant", AttributeType::Type1),
+        AttributeSpec::new(Tag(0x0018, 0x0022), "ScanOptions", AttributeType::Type2),
+        AttributeSpec::enumerated(Tag(0x0018, 0x0023), "MRAcquisitionType", AttributeType::Type2, &["2D", "3D"]),
+        AttributeSpec::new(Tag(0x0018, 0x0081), "EchoTime", AttributeType::Type2),
+        AttributeSpec::new(Tag(0x0018, 0x0091), "EchoTrainLength", AttributeType::Type2),
+    ],
+};
+
+const CR_IMAGE_MODULE: ModuleSpec = ModuleSpec {
+    name: "CR Series/Image",
+    attributes: &[
+        AttributeSpec::enumerated(Tag(0x0008, 0x0060), "Modality", AttributeType::Type1, &["CR"]),
+        AttributeSpec::new(Tag(0x0018, 0x0015), "BodyPartExamined", AttributeType::Type2),
+        AttributeSpec::new(Tag(0x0018, 0x5101), "ViewPosition", AttributeType::Type2),
+        AttributeSpec::enumerated(Tag(0x0028, 0x0004), "PhotometricInterpretation", AttributeType::Type1, &["MONOCHROME1", "MONOCHROME2"]),
+    ],
+};
+
+const DX_IMAGE_MODULE: ModuleSpec = ModuleSpec {
+    name: "DX Series/Image",
+    attributes: &[
+        AttributeSpec::enumerated(Tag(0x0008, 0x0060), "Modality", AttributeType::Type1, &["DX", "PX", "IO", "MG"]),
+        AttributeSpec::new(Tag(0x0008, 0x0008), "ImageType", AttributeType::Type1),
+        AttributeSpec::enumerated(Tag(0x0028, 0x0002), "SamplesPerPixel", AttributeType::Type1, &["1"]),
+        AttributeSpec::enumerated(Tag(0x0028, 0x0004), "PhotometricInterpretation", AttributeType::Type1, &["MONOCHROME1", "MONOCHROME2"]),
+        AttributeSpec::enumerated(Tag(0x0028, 0x0100), "BitsAllocated", AttributeType::Type1, &["8", "16"]),
+        AttributeSpec::enumerated(Tag(0x0028, 0x1040), "PixelIntensityRelationship", AttributeType::Type1, &["LIN", "LOG"]),
+        AttributeSpec::new(Tag(0x0028, 0x1052), "RescaleIntercept", AttributeType::Type1),
+        AttributeSpec::new(Tag(0x0028, 0x1053), "RescaleSlope", AttributeType::Type1),
+        AttributeSpec::enumerated(Tag(0x0028, 0x2110), "LossyImageCompression", AttributeType::Type1C(Condition::Present(Tag(0x0028, 0x2112))), &["00", "01"]),
+    ],
+};
+
+const MG_IMAGE_MODULE: ModuleSpec = ModuleSpec {
+    name: "Mammography Series/Image",
+    attributes: &[
+        AttributeSpec::enumerated(Tag(0x0008, 0x0060), "Modality", AttributeType::Type1, &["MG"]),
+        AttributeSpec::enumerated(Tag(0x0020, 0x0062), "ImageLaterality", AttributeType::Type1, &["R", "L", "U", "B"]),
+        AttributeSpec::new(Tag(0x0018, 0x5101), "ViewPosition", AttributeType::Type2),
+    ],
+};
+
+const US_IMAGE_MODULE: ModuleSpec = ModuleSpec {
+    name: "US Image",
+    attributes: &[
+        AttributeSpec::enumerated(Tag(0x0008, 0x0060), "Modality", AttributeType::Type1, &["US"]),
+        AttributeSpec::new(Tag(0x0008, 0x0008), "ImageType", AttributeType::Type2),
+        AttributeSpec::enumerated(Tag(0x0028, 0x0002), "SamplesPerPixel", AttributeType::Type1, &["1", "3"]),
+        AttributeSpec::enumerated(Tag(0x0028, 0x0100), "BitsAllocated", AttributeType::Type1, &["8", "16"]),
+        AttributeSpec::enumerated(Tag(0x0028, 0x0006), "PlanarConfiguration", AttributeType::Type1C(Condition::Present(Tag(0x0028, 0x0006))), &["0", "1"]),
+    ],
+};
+
+const SC_EQUIPMENT_MODULE: ModuleSpec = ModuleSpec {
+    name: "SC Equipment",
+    attributes: &[
+        AttributeSpec::enumerated(
+            Tag(0x0008, 0x0064),
+            "ConversionType",
+            AttributeType::Type1,
+            &["DV", "DI", "DF", "WSD", "SD", "SI", "DRW", "SYN"],
+        ),
+    ],
+};
+
+const PET_IMAGE_MODULE: ModuleSpec = ModuleSpec {
+    name: "PET Series/Image",
+    attributes: &[
+        AttributeSpec::enumerated(Tag(0x0008, 0x0060), "Modality", AttributeType::Type1, &["PT"]),
+        AttributeSpec::new(Tag(0x0054, 0x1001), "Units", AttributeType::Type1),
+        AttributeSpec::new(Tag(0x0054, 0x1002), "CountsSource", AttributeType::Type1),
+        AttributeSpec::new(Tag(0x0054, 0x1000), "SeriesType", AttributeType::Type1),
+        AttributeSpec::new(Tag(0x0028, 0x0051), "CorrectedImage", AttributeType::Type2),
+        AttributeSpec::new(Tag(0x0054, 0x1330), "ImageIndex", AttributeType::Type1),
+    ],
+};
+
+// =============================================================================
+// IOD DEFINITIONS
+// =============================================================================
+
+const ALL_IODS: &[IodSpec] = &[
+    IodSpec {
+        name: "CT Image",
+        sop_class_uids: &[sop::CT_IMAGE_STORAGE],
+        modules: &[
+            &PATIENT_MODULE, &GENERAL_STUDY_MODULE, &GENERAL_SERIES_MODULE, &GENERAL_EQUIPMENT_MODULE,
+            &GENERAL_IMAGE_MODULE, &IMAGE_PLANE_MODULE, &IMAGE_PIXEL_MODULE, &CT_IMAGE_MODULE, &SOP_COMMON_MODULE,
+        ],
+    },
+    IodSpec {
+        name: "MR Image",
+        sop_class_uids: &[sop::MR_IMAGE_STORAGE],
+        modules: &[
+            &PATIENT_MODULE, &GENERAL_STUDY_MODULE, &GENERAL_SERIES_MODULE, &GENERAL_EQUIPMENT_MODULE,
+            &GENERAL_IMAGE_MODULE, &IMAGE_PLANE_MODULE, &IMAGE_PIXEL_MODULE, &MR_IMAGE_MODULE, &SOP_COMMON_MODULE,
+        ],
+    },
+    IodSpec {
+        name: "Computed Radiography Image",
+        sop_class_uids: &[sop::COMPUTED_RADIOGRAPHY_IMAGE_STORAGE],
+        modules: &[
+            &PATIENT_MODULE, &GENERAL_STUDY_MODULE, &GENERAL_SERIES_MODULE, &GENERAL_EQUIPMENT_MODULE,
+            &GENERAL_IMAGE_MODULE, &IMAGE_PIXEL_MODULE, &CR_IMAGE_MODULE, &SOP_COMMON_MODULE,
+        ],
+    },
+    IodSpec {
+        name: "Digital X-Ray Image",
+        sop_class_uids: &[sop::DIGITAL_X_RAY_IMAGE_STORAGE_FOR_PRESENTATION, sop::DIGITAL_X_RAY_IMAGE_STORAGE_FOR_PROCESSING],
+        modules: &[
+            &PATIENT_MODULE, &GENERAL_STUDY_MODULE, &GENERAL_SERIES_MODULE, &GENERAL_EQUIPMENT_MODULE,
+            &GENERAL_IMAGE_MODULE, &IMAGE_PIXEL_MODULE, &DX_IMAGE_MODULE, &SOP_COMMON_MODULE,
+        ],
+    },
+    IodSpec {
+        name: "Digital Mammography X-Ray Image",
+        sop_class_uids: &[sop::DIGITAL_MAMMOGRAPHY_X_RAY_IMAGE_STORAGE_FOR_PRESENTATION, sop::DIGITAL_MAMMOGRAPHY_X_RAY_IMAGE_STORAGE_FOR_PROCESSING],
+        modules: &[
+            &PATIENT_MODULE, &GENERAL_STUDY_MODULE, &GENERAL_SERIES_MODULE, &GENERAL_EQUIPMENT_MODULE,
+            &GENERAL_IMAGE_MODULE, &IMAGE_PIXEL_MODULE, &DX_IMAGE_MODULE, &MG_IMAGE_MODULE, &SOP_COMMON_MODULE,
+        ],
+    },
+    IodSpec {
+        name: "Ultrasound Image",
+        sop_class_uids: &[sop::ULTRASOUND_IMAGE_STORAGE, sop::ULTRASOUND_MULTI_FRAME_IMAGE_STORAGE],
+        modules: &[
+            &PATIENT_MODULE, &GENERAL_STUDY_MODULE, &GENERAL_SERIES_MODULE, &GENERAL_EQUIPMENT_MODULE,
+            &GENERAL_IMAGE_MODULE, &IMAGE_PIXEL_MODULE, &US_IMAGE_MODULE, &SOP_COMMON_MODULE,
+        ],
+    },
+    IodSpec {
+        name: "Secondary Capture Image",
+        sop_class_uids: &[sop::SECONDARY_CAPTURE_IMAGE_STORAGE],
+        modules: &[
+            &PATIENT_MODULE, &GENERAL_STUDY_MODULE, &GENERAL_SERIES_MODULE, &GENERAL_IMAGE_MODULE,
+            &IMAGE_PIXEL_MODULE, &SC_EQUIPMENT_MODULE, &SOP_COMMON_MODULE,
+        ],
+    },
+    IodSpec {
+        name: "PET Image",
+        sop_class_uids: &[sop::POSITRON_EMISSION_TOMOGRAPHY_IMAGE_STORAGE],
+        modules: &[
+            &PATIENT_MODULE, &GENERAL_STUDY_MODULE, &GENERAL_SERIES_MODULE, &GENERAL_EQUIPMENT_MODULE,
+            &GENERAL_IMAGE_MODULE, &IMAGE_PLANE_MODULE, &IMAGE_PIXEL_MODULE, &PET_IMAGE_MODULE, &SOP_COMMON_MODULE,
+        ],
+    },
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dicom_core::{DataElement, PrimitiveValue, VR};
+
+    #[test]
+    fn test_ct_iod_violations() {
+        let obj = InMemDicomObject::from_element_iter([
+            DataElement::new(Tag(0x0008, 0x0016), VR::UI, PrimitiveValue::from("1.2.840.10008.5.1.4.1.1.2")),
+            DataElement::new(Tag(0x0008, 0x0018), VR::UI, PrimitiveValue::from("1.2.3.4")),
+            DataElement::new(Tag(0x0008, 0x0060), VR::CS, PrimitiveValue::from("MR")),
+            DataElement::new(Tag(0x0020, 0x000D), VR::UI, PrimitiveValue::from("")),
+        ]);
+
+        let report = validate_iod(&obj).unwrap();
+        assert_eq!(report.iod, "CT Image");
+        assert!(!report.is_conformant());
+
+        let find = |keyword: &str| report.violations.iter().find(|v| v.keyword == keyword).map(|v| v.kind.clone());
+        assert_eq!(find("StudyInstanceUID"), Some(ViolationKind::Empty));
+        assert_eq!(find("SeriesInstanceUID"), Some(ViolationKind::Missing));
+        assert_eq!(find("PatientName"), Some(ViolationKind::Missing));
+        assert_eq!(find("Modality"), Some(ViolationKind::InvalidValue("MR".to_string())));
+        assert_eq!(find("SOPInstanceUID"), None);
+    }
+
+    #[test]
+    fn test_unknown_sop_class() {
+        let obj = InMemDicomObject::from_element_iter([
+            DataElement::new(Tag(0x0008, 0x0016), VR::UI, PrimitiveValue::from("1.2.840.10008.5.1.4.1.1.88.11")),
+        ]);
+        assert!(validate_iod(&obj).is_none());
+        assert!(find_iod("1.2.840.10008.5.1.4.1.1.4").is_some());
+    }
+}
@@ -0,0 +1,188 @@
+/// Selecting instances by Key Object Selection and SR references
+///
+/// A Key Object Selection document (or an SR report) names the instances that
+/// matter through Referenced SOP Sequences, in its evidence sequences and its
+/// content tree. Sending only those instances plus the documents themselves
+/// forwards e.g. the key images and the report to a referring physician portal
+/// instead of the whole study.
+
+use dicom_core::value::Value;
+use dicom_core::Tag;
+use dicom_object::InMemDicomObject;
+use std::collections::BTreeSet;
+
+use super::types::DicomFile;
+use super::uids::sop;
+
+pub const KEY_OBJECT_SELECTION: &str = sop::KEY_OBJECT_SELECTION_DOCUMENT_STORAGE;
+/// Prefix of the Structured Report storage SOP classes
+const SR_STORAGE_PREFIX: &str = "1.2.840.10008.5.1.4.1.1.88.";
+
+const SOP_INSTANCE_UID: Tag = Tag(0x0008, 0x0018);
+const SOP_CLASS_UID: Tag = Tag(0x0008, 0x0016);
+const REFERENCED_SOP_INSTANCE_UID: Tag = Tag(0x0008, 0x1155);
+const CONTENT_TEMPLATE_SEQUENCE: Tag = Tag(0x0040, 0xA504);
+const TEMPLATE_IDENTIFIER: Tag = Tag(0x0040, 0xDB00);
+
+pub fn is_key_object_selection(sop_class_uid: &str) -> bool {
+    sop_class_uid == KEY_OBJECT_SELECTION
+}
+
+/// Structured Report storage classes, Key Object Selection included
+pub fn is_structured_report(sop_class_uid: &str) -> bool {
+    sop_class_uid.starts_with(SR_STORAGE_PREFIX)
+}
+
+/// Which documents select the instances to send
+#[derive(Debug, Clone, Default)]
+pub struct SelectionPolicy {
+    /// Every Key Object Selection document
+    pub key_objects: bool,
+    /// SR documents with one of these root template identifiers (e.g. "2000")
+    pub sr_templates: Vec<String>,
+}
+
+impl SelectionPolicy {
+    pub fn is_enabled(&self) -> bool {
+        self.key_objects || !self.sr_templates.is_empty()
+    }
+
+    pub fn selects(&self, document: &ReferencingDocument) -> bool {
+        (self.key_objects && is_key_object_selection(&document.sop_class_uid))
+            || document.template_id.as_ref().is_some_and(|tid| self.sr_templates.contains(tid))
+    }
+}
+
+/// A KOS or SR document and the instances it references
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReferencingDocument {
+    pub sop_instance_uid: String,
+    pub sop_class_uid: String,
+    /// Template identifier of the root content item, e.g. "2010" for key object selections
+    pub template_id: Option<String>,
+    pub referenced: BTreeSet<String>,
+}
+
+impl ReferencingDocument {
+    pub fn from_object(obj: &InMemDicomObject) -> Self {
+        let mut referenced = BTreeSet::new();
+        collect_referenced_instances(obj, &mut referenced);
+        Self {
+            sop_instance_uid: string_value(obj, SOP_INSTANCE_UID).unwrap_or_default(),
+            sop_class_uid: string_value(obj, SOP_CLASS_UID).unwrap_or_default(),
+            template_id: obj.element(CONTENT_TEMPLATE_SEQUENCE).ok()
+                .and_then(|e| e.items())
+                .and_then(|items| items.first())
+                .and_then(|item| string_value(item, TEMPLATE_IDENTIFIER)),
+            referenced,
+        }
+    }
+}
+
+fn string_value(obj: &InMemDicomObject, tag: Tag) -> Option<String> {
+    obj.element(tag).ok()
+        .and_then(|e| e.to_str().ok())
+        .map(|s| s.trim_end_matches('\0').trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+/// Referenced SOP Instance UIDs anywhere in the dataset, nested sequences included
+pub fn collect_referenced_instances(obj: &InMemDicomObject, referenced: &mut BTreeSet<String>) {
+    for element in obj.iter() {
+        if let Value::Sequence(sequence) = element.value() {
+            for item in sequence.items() {
+                collect_referenced_instances(item, referenced);
+            }
+        } else if element.header().tag == REFERENCED_SOP_INSTANCE_UID {
+            if let Ok(uids) = element.to_multi_str() {
+                referenced.extend(uids.iter()
+                    .map(|uid| uid.trim_end_matches('\0').trim().to_string())
+                    .filter(|uid| !uid.is_empty()));
+            }
+        }
+    }
+}
+
+/// Keep the selecting documents and the instances they reference
+///
+/// Returns the files to send and the number left out.
+pub fn select_referenced(files: Vec<DicomFile>, documents: &[ReferencingDocument]) -> (Vec<DicomFile>, usize) {
+    let wanted: BTreeSet<&str> = documents.iter()
+        .flat_map(|doc| std::iter::once(doc.sop_instance_uid.as_str()).chain(doc.referenced.iter().map(String::as_str)))
+        .collect();
+    let total = files.len();
+    let selected: Vec<DicomFile> = files.into_iter()
+        .filter(|file| wanted.contains(file.sop_instance_uid.as_str()))
+        .collect();
+    let skipped = total - selected.len();
+    (selected, skipped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dicom_core::value::{DataSetSequence, PrimitiveValue};
+    use dicom_core::{DataElement, VR};
+
+    fn file(sop_instance_uid: &str, sop_class_uid: &str) -> DicomFile {
+        DicomFile::test(format!("{}.dcm", sop_instance_uid), sop_instance_uid)
+            .with_sop_class(sop_class_uid)
+            .with_modality(None)
+    }
+
+    fn referenced_sop(uid: &str) -> InMemDicomObject {
+        InMemDicomObject::from_element_iter([
+            DataElement::new(Tag(0x0008, 0x1150), VR::UI, PrimitiveValue::from("1.2.840.10008.5.1.4.1.1.2")),
+            DataElement::new(REFERENCED_SOP_INSTANCE_UID, VR::UI, PrimitiveValue::from(uid)),
+        ])
+    }
+
+    #[test]
+    fn test_references_of_key_object_selection() {
+        let series = InMemDicomObject::from_element_iter([
+            DataElement::new(Tag(0x0008, 0x1199), VR::SQ, DataSetSequence::from(vec![referenced_sop("1.2.3.1.1"), referenced_sop("1.2.3.1.4")])),
+        ]);
+        let evidence = InMemDicomObject::from_element_iter([
+            DataElement::new(Tag(0x0008, 0x1115), VR::SQ, DataSetSequence::from(vec![series])),
+        ]);
+        let content_item = InMemDicomObject::from_element_iter([
+            DataElement::new(Tag(0x0040, 0xA040), VR::CS, PrimitiveValue::from("IMAGE")),
+            DataElement::new(Tag(0x0008, 0x1199), VR::SQ, DataSetSequence::from(vec![referenced_sop("1.2.3.1.4")])),
+        ]);
+        let template = InMemDicomObject::from_element_iter([
+            DataElement::new(TEMPLATE_IDENTIFIER, VR::CS, PrimitiveValue::from("2010")),
+        ]);
+        let kos = InMemDicomObject::from_element_iter([
+            DataElement::new(SOP_CLASS_UID, VR::UI, PrimitiveValue::from(KEY_OBJECT_SELECTION)),
+            DataElement::new(SOP_INSTANCE_UID, VR::UI, PrimitiveValue::from("1.2.3.9")),
+            DataElement::new(CONTENT_TEMPLATE_SEQUENCE, VR::SQ, DataSetSequence::from(vec![template])),
+            DataElement::new(Tag(0x0040, 0xA375), VR::SQ, DataSetSequence::from(vec![evidence])),
+            DataElement::new(Tag(0x0040, 0xA730), VR::SQ, DataSetSequence::from(vec![content_item])),
+        ]);
+
+        let document = ReferencingDocument::from_object(&kos);
+        assert_eq!(document.template_id.as_deref(), Some("2010"));
+        assert_eq!(document.referenced.iter().collect::<Vec<_>>(), vec!["1.2.3.1.1", "1.2.3.1.4"]);
+        assert!(SelectionPolicy { key_objects: true, sr_templates: Vec::new() }.selects(&document));
+        assert!(!SelectionPolicy { key_objects: false, sr_templates: vec!["2000".to_string()] }.selects(&document));
+    }
+
+    #[test]
+    fn test_select_referenced() {
+        let files: Vec<DicomFile> = (1..=5)
+            .map(|i| file(&format!("1.2.3.1.{}", i), "1.2.840.10008.5.1.4.1.1.2"))
+            .chain(std::iter::once(file("1.2.3.9", KEY_OBJECT_SELECTION)))
+            .collect();
+        let document = ReferencingDocument {
+            sop_instance_uid: "1.2.3.9".to_string(),
+            sop_class_uid: KEY_OBJECT_SELECTION.to_string(),
+            template_id: Some("2010".to_string()),
+            referenced: ["1.2.3.1.1", "1.2.3.1.4", "1.2.3.7.1"].iter().map(|s| s.to_string()).collect(),
+        };
+
+        let (selected, skipped) = select_referenced(files, &[document]);
+        assert_eq!(selected.iter().map(|f| f.sop_instance_uid.as_str()).collect::<Vec<_>>(), vec!["1.2.3.1.1", "1.2.3.1.4", "1.2.3.9"]);
+        assert_eq!(skipped, 3);
+        assert!(is_structured_report(KEY_OBJECT_SELECTION) && !is_structured_report("1.2.840.10008.5.1.4.1.1.2"));
+    }
+}
@@ -0,0 +1,391 @@
+/// Append-only, hash-chained transfer ledger
+///
+/// Every instance sent or received can be recorded as one JSON line. Each
+/// entry carries the hash of the previous entry, and its own hash covers its
+/// sequence number, contents and that previous hash, so removing, reordering or
+/// editing any entry breaks the chain from that point on. With a key the hash
+/// is an HMAC-SHA256, so the chain cannot be rewritten without the key, and
+/// plain SHA-256 otherwise.
+///
+/// After every append the head of the chain, its length and last hash, is
+/// written to `<ledger>.head`, signed with the key if there is one, so entries
+/// cut from the end are detected too; the head can be exported and checked
+/// against later. A keyed ledger gets its head when it is created, and does
+/// not verify without one. The ledger is verified when it is opened, and can be
+/// verified and exported offline with the `dicom-ledger` tool.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use super::recovery::write_atomically;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Previous-hash value of the first entry
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    Sent,
+    Received,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LedgerRecord {
+    pub direction: Direction,
+    pub timestamp: DateTime<Utc>,
+    pub local_ae: String,
+    pub peer_ae: String,
+    pub sop_class_uid: String,
+    pub sop_instance_uid: String,
+    pub study_instance_uid: String,
+    pub size: u64,
+    /// Outcome, e.g. "success", "failed", "stored", "quarantined", "forwarded", "duplicate", "rejected"
+    pub status: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LedgerEntry {
+    pub seq: u64,
+    #[serde(flatten)]
+    pub record: LedgerRecord,
+    pub prev_hash: String,
+    pub hash: String,
+}
+
+impl LedgerEntry {
+    fn compute_hash(key: Option<&[u8]>, seq: u64, record: &LedgerRecord, prev_hash: &str) -> String {
+        let payload = serde_json::to_vec(&(seq, record)).expect("ledger record serializes to JSON");
+        match key {
+            Some(key) => {
+                let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any length");
+                mac.update(prev_hash.as_bytes());
+                mac.update(b"\n");
+                mac.update(&payload);
+                hex::encode(mac.finalize().into_bytes())
+            }
+            None => {
+                let mut hasher = Sha256::new();
+                hasher.update(prev_hash.as_bytes());
+                hasher.update(b"\n");
+                hasher.update(&payload);
+                hex::encode(hasher.finalize())
+            }
+        }
+    }
+}
+
+/// Result of a successful chain verification
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LedgerHead {
+    pub entries: u64,
+    pub last_hash: String,
+}
+
+/// The head of a ledger as recorded after its last append
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedHead {
+    #[serde(flatten)]
+    pub head: LedgerHead,
+    /// Hex HMAC-SHA256 over the head, when the ledger is keyed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+}
+
+impl RecordedHead {
+    pub fn new(head: LedgerHead, key: Option<&[u8]>) -> Self {
+        let signature = key.map(|key| hex::encode(Self::compute_mac(&head, key).finalize().into_bytes()));
+        Self { head, signature }
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read ledger head {}", path.display()))?;
+        serde_json::from_str(&content).with_context(|| format!("Malformed ledger head {}", path.display()))
+    }
+
+    /// Check the signature against the key; unsigned heads never verify
+    pub fn verify_signature(&self, key: &[u8]) -> bool {
+        let signature = match self.signature.as_deref().and_then(|s| hex::decode(s).ok()) {
+            Some(signature) => signature,
+            None => return false,
+        };
+        Self::compute_mac(&self.head, key).verify_slice(&signature).is_ok()
+    }
+
+    fn compute_mac(head: &LedgerHead, key: &[u8]) -> HmacSha256 {
+        let payload = serde_json::to_vec(head).expect("ledger head serializes to JSON");
+        let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any length");
+        mac.update(b"head\n");
+        mac.update(&payload);
+        mac
+    }
+}
+
+/// Read a ledger key from `path`, ignoring surrounding whitespace
+pub fn read_key(path: &Path) -> Result<Vec<u8>> {
+    let key = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read ledger key {}", path.display()))?;
+    Ok(key.trim().as_bytes().to_vec())
+}
+
+/// Where the head of the ledger at `path` is recorded
+pub fn head_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".head");
+    path.with_file_name(name)
+}
+
+#[derive(Debug)]
+pub struct Ledger {
+    file: File,
+    head_path: PathBuf,
+    key: Option<Vec<u8>>,
+    next_seq: u64,
+    last_hash: String,
+}
+
+impl Ledger {
+    /// Open or create a ledger, keyed with `key` if given, refusing to extend
+    /// a chain that does not verify
+    pub fn open(path: &Path, key: Option<Vec<u8>>) -> Result<Self> {
+        let created = !path.exists();
+        let head = if created {
+            LedgerHead { entries: 0, last_hash: GENESIS_HASH.to_string() }
+        } else {
+            verify_ledger(path, key.as_deref())?
+        };
+
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open ledger {}", path.display()))?;
+
+        let ledger = Self {
+            file,
+            head_path: head_path(path),
+            key,
+            next_seq: head.entries,
+            last_hash: head.last_hash,
+        };
+        if created {
+            ledger.write_head()?;
+        }
+        Ok(ledger)
+    }
+
+    fn write_head(&self) -> Result<()> {
+        let head = LedgerHead { entries: self.next_seq, last_hash: self.last_hash.clone() };
+        write_atomically(&self.head_path, &serde_json::to_vec(&RecordedHead::new(head, self.key.as_deref()))?)?;
+        Ok(())
+    }
+
+    /// Append a record and flush it and the new head to disk before returning
+    pub fn append(&mut self, record: LedgerRecord) -> Result<LedgerEntry> {
+        let hash = LedgerEntry::compute_hash(self.key.as_deref(), self.next_seq, &record, &self.last_hash);
+        let entry = LedgerEntry {
+            seq: self.next_seq,
+            record,
+            prev_hash: self.last_hash.clone(),
+            hash,
+        };
+
+        let mut line = serde_json::to_vec(&entry)?;
+        line.push(b'\n');
+        self.file.write_all(&line)?;
+        self.file.sync_data()?;
+
+        self.next_seq += 1;
+        self.last_hash = entry.hash.clone();
+        self.write_head()?;
+        Ok(entry)
+    }
+}
+
+/// Read all entries without checking the chain
+pub fn read_entries(path: &Path) -> Result<Vec<LedgerEntry>> {
+    let file = File::open(path).with_context(|| format!("Failed to open ledger {}", path.display()))?;
+    let mut entries = Vec::new();
+    for (idx, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry: LedgerEntry = serde_json::from_str(&line)
+            .with_context(|| format!("Malformed ledger entry on line {}", idx + 1))?;
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+/// Check sequence numbers, previous-hash links and entry hashes of the whole
+/// ledger, and check it against its recorded head. Only an unkeyed ledger may
+/// lack one: anyone can rewrite its head along with its entries anyway
+pub fn verify_ledger(path: &Path, key: Option<&[u8]>) -> Result<LedgerHead> {
+    let entries = read_entries(path)?;
+    let head = verify_entries(&entries, key)?;
+    let recorded = head_path(path);
+    if recorded.exists() {
+        verify_head(&entries, &RecordedHead::from_file(&recorded)?, key)?;
+    } else if key.is_some() {
+        anyhow::bail!("Ledger head {} is missing: entries cut from the end cannot be detected", recorded.display());
+    }
+    Ok(head)
+}
+
+pub fn verify_entries(entries: &[LedgerEntry], key: Option<&[u8]>) -> Result<LedgerHead> {
+    let mut last_hash = GENESIS_HASH.to_string();
+
+    for (expected_seq, entry) in entries.iter().enumerate() {
+        if entry.seq != expected_seq as u64 {
+            anyhow::bail!("Ledger entry {} has sequence number {}", expected_seq, entry.seq);
+        }
+        if entry.prev_hash != last_hash {
+            anyhow::bail!("Ledger chain broken at entry {}: previous hash does not match", entry.seq);
+        }
+        if LedgerEntry::compute_hash(key, entry.seq, &entry.record, &entry.prev_hash) != entry.hash {
+            anyhow::bail!("Ledger entry {} has been altered: hash does not match its contents", entry.seq);
+        }
+        last_hash = entry.hash.clone();
+    }
+
+    Ok(LedgerHead { entries: entries.len() as u64, last_hash })
+}
+
+/// Check that the verified `entries` still hold the chain `recorded` was the
+/// head of; an entry appended after it is allowed, as a crash can come
+/// between writing an entry and its head
+pub fn verify_head(entries: &[LedgerEntry], recorded: &RecordedHead, key: Option<&[u8]>) -> Result<()> {
+    if let Some(key) = key {
+        if !recorded.verify_signature(key) {
+            anyhow::bail!("Ledger head signature does not match the key");
+        }
+    }
+    let head = &recorded.head;
+    if head.entries > entries.len() as u64 {
+        anyhow::bail!("Ledger has been truncated: {} entries, the recorded head has {}", entries.len(), head.entries);
+    }
+    let hash_at_head = match head.entries {
+        0 => GENESIS_HASH,
+        length => entries[length as usize - 1].hash.as_str(),
+    };
+    if hash_at_head != head.last_hash {
+        anyhow::bail!("Ledger entry {} does not match the recorded head", head.entries.saturating_sub(1));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(uid: &str) -> LedgerRecord {
+        LedgerRecord {
+            direction: Direction::Sent,
+            timestamp: Utc::now(),
+            local_ae: "RUST_SCU".to_string(),
+            peer_ae: "PACS".to_string(),
+            sop_class_uid: "1.2.840.10008.5.1.4.1.1.2".to_string(),
+            sop_instance_uid: uid.to_string(),
+            study_instance_uid: "1.2.3".to_string(),
+            size: 1024,
+            status: "success".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_append_and_verify() {
+        let path = std::env::temp_dir().join(format!("ledger_test_{}.jsonl", uuid::Uuid::new_v4()));
+
+        let mut ledger = Ledger::open(&path, None).unwrap();
+        ledger.append(record("1.2.3.1")).unwrap();
+        ledger.append(record("1.2.3.2")).unwrap();
+        drop(ledger);
+
+        // Reopening continues the existing chain
+        let mut ledger = Ledger::open(&path, None).unwrap();
+        let entry = ledger.append(record("1.2.3.3")).unwrap();
+        assert_eq!(entry.seq, 2);
+
+        let head = verify_ledger(&path, None).unwrap();
+        assert_eq!(head.entries, 3);
+        assert_eq!(head.last_hash, entry.hash);
+        assert_eq!(RecordedHead::from_file(&head_path(&path)).unwrap().head, head);
+
+        std::fs::remove_file(&path).unwrap();
+        std::fs::remove_file(head_path(&path)).unwrap();
+    }
+
+    #[test]
+    fn test_keyed_ledger() {
+        let path = std::env::temp_dir().join(format!("ledger_test_{}.jsonl", uuid::Uuid::new_v4()));
+        let key = b"ledger secret".to_vec();
+
+        let mut ledger = Ledger::open(&path, Some(key.clone())).unwrap();
+        for uid in ["1.2.3.1", "1.2.3.2", "1.2.3.3"] {
+            ledger.append(record(uid)).unwrap();
+        }
+        drop(ledger);
+        assert_eq!(verify_ledger(&path, Some(&key)).unwrap().entries, 3);
+        assert!(verify_ledger(&path, Some(b"other secret")).is_err());
+        assert!(Ledger::open(&path, None).is_err());
+        let exported = RecordedHead::from_file(&head_path(&path)).unwrap();
+
+        // Cutting the last entry leaves a valid chain, but not the recorded head
+        let content = std::fs::read_to_string(&path).unwrap();
+        let truncated: Vec<&str> = content.lines().take(2).collect();
+        std::fs::write(&path, truncated.join("\n") + "\n").unwrap();
+        let entries = read_entries(&path).unwrap();
+        assert_eq!(verify_entries(&entries, Some(&key)).unwrap().entries, 2);
+        let error = verify_ledger(&path, Some(&key)).unwrap_err();
+        assert!(error.to_string().contains("truncated"), "{}", error);
+        assert!(verify_head(&entries, &exported, Some(&key)).is_err());
+
+        // Nor does a head rewritten to match, without the key
+        let forged = RecordedHead::new(verify_entries(&entries, Some(&key)).unwrap(), Some(b"guessed"));
+        assert!(verify_head(&entries, &forged, Some(&key)).is_err());
+
+        // Nor does deleting the head
+        std::fs::remove_file(head_path(&path)).unwrap();
+        let error = verify_ledger(&path, Some(&key)).unwrap_err();
+        assert!(error.to_string().contains("missing"), "{}", error);
+        assert!(Ledger::open(&path, Some(key.clone())).is_err());
+        std::fs::remove_file(&path).unwrap();
+
+        // A new keyed ledger verifies before anything is appended
+        drop(Ledger::open(&path, Some(key.clone())).unwrap());
+        assert_eq!(verify_ledger(&path, Some(&key)).unwrap().entries, 0);
+
+        std::fs::remove_file(&path).unwrap();
+        std::fs::remove_file(head_path(&path)).unwrap();
+    }
+
+    #[test]
+    fn test_tampering_is_detected() {
+        let mut entries = Vec::new();
+        let mut prev_hash = GENESIS_HASH.to_string();
+        for (seq, uid) in ["1.2.3.1", "1.2.3.2", "1.2.3.3"].iter().enumerate() {
+            let record = record(uid);
+            let hash = LedgerEntry::compute_hash(None, seq as u64, &record, &prev_hash);
+            entries.push(LedgerEntry { seq: seq as u64, record, prev_hash, hash: hash.clone() });
+            prev_hash = hash;
+        }
+        assert!(verify_entries(&entries, None).is_ok());
+
+        let mut altered = entries.clone();
+        altered[1].record.status = "failed".to_string();
+        assert!(verify_entries(&altered, None).is_err());
+
+        let mut removed = entries.clone();
+        removed.remove(1);
+        assert!(verify_entries(&removed, None).is_err());
+    }
+}
@@ -0,0 +1,59 @@
+//! Listening sockets of the receiver
+//!
+//! The receiver binds to the unspecified IPv4 address by default. With
+//! `--bind` it listens on a single address instead, IPv4 or IPv6; an IPv6
+//! address may be written in brackets (`[::]`). Binding to `::` is dual-stack:
+//! IPv4 peers are accepted too, as IPv4-mapped addresses, whatever the
+//! platform's default, unless `--ipv6-only` is given.
+
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+use socket2::{Domain, Protocol, Socket, Type};
+
+/// Address listened on without `--bind`
+pub const DEFAULT_BIND_ADDRESS: IpAddr = IpAddr::V4(Ipv4Addr::UNSPECIFIED);
+
+/// Pending connections the kernel queues while all association slots are taken
+const BACKLOG: i32 = 1024;
+
+/// Parse an IPv4 or IPv6 address, the latter with or without brackets
+pub fn parse_bind_address(text: &str) -> Result<IpAddr, String> {
+    let text = text.trim();
+    let unbracketed = text.strip_prefix('[').and_then(|rest| rest.strip_suffix(']')).unwrap_or(text);
+    unbracketed.parse::<IpAddr>()
+        .map_err(|_| format!("invalid bind address {:?}: expected an IPv4 or IPv6 address such as 0.0.0.0 or [::]", text))
+}
+
+/// A non-blocking socket listening on `address`:`port`; an IPv6 socket also
+/// accepts IPv4 peers unless `ipv6_only`
+pub fn bind_listener(address: IpAddr, port: u16, ipv6_only: bool) -> std::io::Result<std::net::TcpListener> {
+    let address = SocketAddr::new(address, port);
+    let socket = Socket::new(Domain::for_address(address), Type::STREAM, Some(Protocol::TCP))?;
+    if address.is_ipv6() {
+        socket.set_only_v6(ipv6_only)?;
+    }
+    // Restarting the receiver must not wait for connections in TIME_WAIT
+    #[cfg(unix)]
+    socket.set_reuse_address(true)?;
+    socket.bind(&address.into())?;
+    socket.listen(BACKLOG)?;
+    socket.set_nonblocking(true)?;
+    Ok(socket.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv6Addr;
+
+    #[test]
+    fn test_parse_bind_address() {
+        assert_eq!(parse_bind_address("0.0.0.0"), Ok(DEFAULT_BIND_ADDRESS));
+        assert_eq!(parse_bind_address("[::]"), Ok(IpAddr::V6(Ipv6Addr::UNSPECIFIED)));
+        assert_eq!(parse_bind_address("::1"), Ok(IpAddr::V6(Ipv6Addr::LOCALHOST)));
+        assert_eq!(parse_bind_address(" 192.168.1.20 "), Ok("192.168.1.20".parse().unwrap()));
+        assert!(parse_bind_address("[0.0.0.0]").is_ok());
+        assert!(parse_bind_address("localhost").is_err());
+        assert!(parse_bind_address("[::1").is_err());
+    }
+}
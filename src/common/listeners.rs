@@ -0,0 +1,147 @@
+//! Several listeners in one receiver process
+//!
+//! Sites that each had an SCP of their own can share one `dicom-receiver`: a
+//! TOML file declares a `[[listener]]` per site, with its AE title, port,
+//! output directory and, optionally, the SOP classes it accepts (by UID, name
+//! or category, e.g. `"CT Image Storage"` or `"SecondaryCapture"`). Every
+//! other option of the receiver applies to all listeners alike.
+//!
+//! ```toml
+//! [[listener]]
+//! ae_title = "SITE_A"
+//! port = 11112
+//! output = "/srv/dicom/site_a"
+//!
+//! [[listener]]
+//! ae_title = "SITE_B_CT"
+//! port = 11113
+//! output = "/srv/dicom/site_b"
+//! sop_classes = ["CT Image Storage", "Enhanced CT Image Storage", "SecondaryCapture"]
+//! ```
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use super::sop_classes::{SopClassCategory, SopClassRegistry};
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ListenerConfig {
+    pub ae_title: String,
+    pub port: u16,
+    pub output: PathBuf,
+    /// SOP classes accepted, by UID, name or category; all when empty
+    #[serde(default)]
+    pub sop_classes: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ListenersFile {
+    listener: Vec<ListenerConfig>,
+}
+
+impl ListenerConfig {
+    /// UIDs of the accepted SOP classes, or `None` to accept all; categories
+    /// expand to their SOP classes and unknown UIDs are taken as they are
+    pub fn sop_class_uids(&self) -> Result<Option<Vec<String>>> {
+        if self.sop_classes.is_empty() {
+            return Ok(None);
+        }
+        let registry = SopClassRegistry::global();
+        let mut uids = Vec::new();
+        for entry in &self.sop_classes {
+            if let Some(info) = registry.find(entry) {
+                uids.push(info.uid.to_string());
+            } else if let Some(category) = SopClassCategory::from_name(entry.trim()) {
+                uids.extend(registry.get_by_category(category).iter().map(|info| info.uid.to_string()));
+            } else if !entry.is_empty() && entry.chars().all(|c| c.is_ascii_digit() || c == '.') {
+                uids.push(entry.to_string());
+            } else {
+                anyhow::bail!("Listener {}: unknown SOP class or category {:?}", self.ae_title, entry);
+            }
+        }
+        uids.sort();
+        uids.dedup();
+        Ok(Some(uids))
+    }
+}
+
+pub fn load_listeners(path: &Path) -> Result<Vec<ListenerConfig>> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read listener configuration: {}", path.display()))?;
+    parse_listeners(&text)
+}
+
+/// Parse and check a listener configuration: at least one listener, AE titles
+/// of 1 to 16 characters, and no port or AE title used twice
+pub fn parse_listeners(text: &str) -> Result<Vec<ListenerConfig>> {
+    let file: ListenersFile = toml::from_str(text).context("Invalid listener configuration TOML")?;
+    if file.listener.is_empty() {
+        anyhow::bail!("The listener configuration declares no [[listener]]");
+    }
+    let (mut ports, mut ae_titles) = (HashSet::new(), HashSet::new());
+    for listener in &file.listener {
+        let ae_title = listener.ae_title.trim();
+        if ae_title.is_empty() || ae_title.len() > 16 {
+            anyhow::bail!("Invalid AE title {:?}: it must have 1 to 16 characters", listener.ae_title);
+        }
+        if !ports.insert(listener.port) {
+            anyhow::bail!("Port {} is used by more than one listener", listener.port);
+        }
+        if !ae_titles.insert(ae_title.to_string()) {
+            anyhow::bail!("AE title {} is used by more than one listener", ae_title);
+        }
+        listener.sop_class_uids()?;
+    }
+    Ok(file.listener)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_listeners() {
+        let listeners = parse_listeners(r#"
+            [[listener]]
+            ae_title = "SITE_A"
+            port = 11112
+            output = "/srv/dicom/site_a"
+
+            [[listener]]
+            ae_title = "SITE_B"
+            port = 11113
+            output = "/srv/dicom/site_b"
+            sop_classes = ["CT Image Storage", "1.2.840.10008.5.1.4.1.1.2", "1.2.3.4.5"]
+        "#).unwrap();
+        assert_eq!(listeners.len(), 2);
+        assert_eq!(listeners[0].sop_class_uids().unwrap(), None);
+        assert_eq!(listeners[1].sop_class_uids().unwrap(),
+                   Some(vec!["1.2.3.4.5".to_string(), "1.2.840.10008.5.1.4.1.1.2".to_string()]));
+
+        let categories = ListenerConfig {
+            ae_title: "SC".to_string(),
+            port: 104,
+            output: PathBuf::from("/tmp"),
+            sop_classes: vec!["SecondaryCapture".to_string()],
+        };
+        assert!(categories.sop_class_uids().unwrap().unwrap().contains(&"1.2.840.10008.5.1.4.1.1.7".to_string()));
+
+        let twice = r#"
+            [[listener]]
+            ae_title = "A"
+            port = 104
+            output = "a"
+            [[listener]]
+            ae_title = "B"
+            port = 104
+            output = "b"
+        "#;
+        assert!(parse_listeners(twice).is_err());
+        assert!(parse_listeners("listener = []").is_err());
+        assert!(parse_listeners(&twice.replace("port = 104\n            output = \"b\"", "port = 105\n            output = \"b\"\n            sop_classes = [\"No Such Thing\"]")).is_err());
+    }
+}
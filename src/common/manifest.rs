@@ -0,0 +1,213 @@
+/// Per-instance checksums and signed migration manifests
+///
+/// The sender can hash the dataset bytes of every instance it transmits and
+/// write them to a manifest. The manifest is signed with HMAC-SHA256 when a key
+/// is supplied, so the receiving side can confirm both that the manifest was
+/// not altered and that every listed instance arrived byte-for-byte intact.
+/// Hashes cover the dataset only (no preamble or file meta group), which is
+/// what travels over the wire and what the receiver stores.
+
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+type HmacSha256 = Hmac<Sha256>;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub sop_instance_uid: String,
+    pub sop_class_uid: String,
+    pub study_instance_uid: String,
+    pub source_path: String,
+    /// Length of the hashed dataset in bytes
+    pub size: u64,
+    /// Lowercase hex SHA-256 of the dataset bytes as transmitted
+    pub sha256: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Manifest {
+    pub session_id: String,
+    pub created: DateTime<Utc>,
+    pub calling_ae: String,
+    pub called_ae: String,
+    pub entries: Vec<ManifestEntry>,
+    /// Hex HMAC-SHA256 over the manifest serialized without this field
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct VerificationReport {
+    pub matched: usize,
+    /// Manifest entries with no file of the same hash
+    pub missing: Vec<ManifestEntry>,
+    /// Files whose hash is not listed in the manifest
+    pub unexpected: Vec<PathBuf>,
+}
+
+impl VerificationReport {
+    pub fn is_complete(&self) -> bool {
+        self.missing.is_empty()
+    }
+}
+
+impl Manifest {
+    pub fn new(session_id: &str, calling_ae: &str, called_ae: &str, entries: Vec<ManifestEntry>) -> Self {
+        Self {
+            session_id: session_id.to_string(),
+            created: Utc::now(),
+            calling_ae: calling_ae.to_string(),
+            called_ae: called_ae.to_string(),
+            entries,
+            signature: None,
+        }
+    }
+
+    pub fn from_file(path: &Path) -> anyhow::Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    pub fn write_to(&self, path: &Path) -> anyhow::Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Sign the manifest, replacing any previous signature
+    pub fn sign(&mut self, key: &[u8]) {
+        self.signature = Some(hex::encode(self.compute_mac(key).finalize().into_bytes()));
+    }
+
+    /// Check the signature against the key; unsigned manifests never verify
+    pub fn verify_signature(&self, key: &[u8]) -> bool {
+        let signature = match self.signature.as_deref().and_then(|s| hex::decode(s).ok()) {
+            Some(signature) => signature,
+            None => return false,
+        };
+        self.compute_mac(key).verify_slice(&signature).is_ok()
+    }
+
+    fn compute_mac(&self, key: &[u8]) -> HmacSha256 {
+        let unsigned = Manifest { signature: None, ..self.clone() };
+        let payload = serde_json::to_vec(&unsigned).expect("manifest serializes to JSON");
+        let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any length");
+        mac.update(&payload);
+        mac
+    }
+
+    /// Match stored files, given as hash → path, against the manifest entries
+    pub fn verify(&self, stored: &HashMap<String, PathBuf>) -> VerificationReport {
+        let mut report = VerificationReport::default();
+        let expected: std::collections::HashSet<&str> = self.entries.iter()
+            .map(|entry| entry.sha256.as_str())
+            .collect();
+
+        for entry in &self.entries {
+            if stored.contains_key(&entry.sha256) {
+                report.matched += 1;
+            } else {
+                report.missing.push(entry.clone());
+            }
+        }
+
+        for (hash, path) in stored {
+            if !expected.contains(hash.as_str()) {
+                report.unexpected.push(path.clone());
+            }
+        }
+        report.unexpected.sort();
+
+        report
+    }
+}
+
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    hex::encode(Sha256::digest(bytes))
+}
+
+/// Return the dataset portion of a stored object, skipping the preamble and
+/// file meta group of Part 10 files. Raw datasets are returned unchanged.
+pub fn dataset_bytes(file_bytes: &[u8]) -> &[u8] {
+    if file_bytes.len() < 144 || &file_bytes[128..132] != b"DICM" {
+        return file_bytes;
+    }
+
+    // (0002,0000) UL group length follows the magic: tag, VR, 2-byte length, 4-byte value
+    if file_bytes[132..136] != [0x02, 0x00, 0x00, 0x00] || &file_bytes[136..138] != b"UL" {
+        return &file_bytes[132..];
+    }
+    let group_length = u32::from_le_bytes([file_bytes[140], file_bytes[141], file_bytes[142], file_bytes[143]]) as usize;
+    let dataset_start = 144 + group_length;
+
+    if dataset_start > file_bytes.len() {
+        &file_bytes[file_bytes.len()..]
+    } else {
+        &file_bytes[dataset_start..]
+    }
+}
+
+/// Hash the dataset of a stored file
+pub fn hash_file(path: &Path) -> std::io::Result<String> {
+    let bytes = std::fs::read(path)?;
+    Ok(sha256_hex(dataset_bytes(&bytes)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(hash: &str) -> ManifestEntry {
+        ManifestEntry {
+            sop_instance_uid: "1.2.3.4".to_string(),
+            sop_class_uid: "1.2.840.10008.5.1.4.1.1.2".to_string(),
+            study_instance_uid: "1.2.3".to_string(),
+            source_path: "/data/ct1.dcm".to_string(),
+            size: 4,
+            sha256: hash.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_sign_and_verify() {
+        let mut manifest = Manifest::new("session", "SENDER", "PACS", vec![entry(&sha256_hex(b"data"))]);
+        assert!(!manifest.verify_signature(b"secret"));
+
+        manifest.sign(b"secret");
+        assert!(manifest.verify_signature(b"secret"));
+        assert!(!manifest.verify_signature(b"other"));
+
+        manifest.entries[0].sha256 = sha256_hex(b"tampered");
+        assert!(!manifest.verify_signature(b"secret"));
+    }
+
+    #[test]
+    fn test_dataset_bytes_skips_file_meta() {
+        let dataset = [0x08u8, 0x00, 0x60, 0x00, b'C', b'S', 0x02, 0x00, b'C', b'T'];
+        assert_eq!(dataset_bytes(&dataset), &dataset);
+
+        let mut part10 = vec![0u8; 128];
+        part10.extend_from_slice(b"DICM");
+        part10.extend_from_slice(&[0x02, 0x00, 0x00, 0x00, b'U', b'L', 0x04, 0x00, 0x02, 0x00, 0x00, 0x00]);
+        part10.extend_from_slice(&[0xAA, 0xBB]);
+        part10.extend_from_slice(&dataset);
+        assert_eq!(dataset_bytes(&part10), &dataset);
+    }
+
+    #[test]
+    fn test_verify_reports_missing_and_unexpected() {
+        let manifest = Manifest::new("session", "SENDER", "PACS", vec![entry("aa"), entry("bb")]);
+        let mut stored = HashMap::new();
+        stored.insert("aa".to_string(), PathBuf::from("received_1.dcm"));
+        stored.insert("cc".to_string(), PathBuf::from("received_2.dcm"));
+
+        let report = manifest.verify(&stored);
+        assert_eq!(report.matched, 1);
+        assert_eq!(report.missing.len(), 1);
+        assert_eq!(report.unexpected, vec![PathBuf::from("received_2.dcm")]);
+        assert!(!report.is_complete());
+    }
+}
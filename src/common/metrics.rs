@@ -0,0 +1,164 @@
+/// Time series of transfer throughput
+///
+/// Clients add every instance to a shared `TransferMeter`; a recorder samples
+/// the meter at a fixed interval and keeps what happened in each interval, so
+/// dips in throughput or spikes in latency can be lined up with network or
+/// PACS events after a long migration.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Running totals, updated by every client as instances complete
+#[derive(Debug, Default)]
+pub struct TransferMeter {
+    instances_sent: AtomicU64,
+    instances_failed: AtomicU64,
+    bytes: AtomicU64,
+    latency_ms: AtomicU64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct MeterReading {
+    pub instances_sent: u64,
+    pub instances_failed: u64,
+    pub bytes: u64,
+    /// Sum of the transfer times of the instances sent
+    pub latency_ms: u64,
+}
+
+impl TransferMeter {
+    pub fn record(&self, success: bool, bytes: u64, transfer_time: Duration) {
+        if success {
+            self.instances_sent.fetch_add(1, Ordering::Relaxed);
+            self.bytes.fetch_add(bytes, Ordering::Relaxed);
+            self.latency_ms.fetch_add(transfer_time.as_millis() as u64, Ordering::Relaxed);
+        } else {
+            self.instances_failed.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn read(&self) -> MeterReading {
+        MeterReading {
+            instances_sent: self.instances_sent.load(Ordering::Relaxed),
+            instances_failed: self.instances_failed.load(Ordering::Relaxed),
+            bytes: self.bytes.load(Ordering::Relaxed),
+            latency_ms: self.latency_ms.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// What happened during one sampling interval
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ThroughputSample {
+    /// End of the interval
+    pub timestamp: DateTime<Utc>,
+    /// Seconds since the start of the transfer at the end of the interval
+    pub elapsed_seconds: f64,
+    pub instances_sent: u64,
+    pub instances_failed: u64,
+    pub bytes: u64,
+    pub throughput_mbps: f64,
+    /// Mean transfer time of the instances sent in the interval
+    pub mean_latency_ms: f64,
+}
+
+impl ThroughputSample {
+    /// Sample covering the change from `previous` to `current` over `interval`
+    pub fn between(previous: &MeterReading, current: &MeterReading, interval: Duration,
+                   timestamp: DateTime<Utc>, elapsed: Duration) -> Self {
+        let instances_sent = current.instances_sent.saturating_sub(previous.instances_sent);
+        let bytes = current.bytes.saturating_sub(previous.bytes);
+        let seconds = interval.as_secs_f64();
+        Self {
+            timestamp,
+            elapsed_seconds: elapsed.as_secs_f64(),
+            instances_sent,
+            instances_failed: current.instances_failed.saturating_sub(previous.instances_failed),
+            bytes,
+            throughput_mbps: if seconds > 0.0 { bytes as f64 / (1024.0 * 1024.0) / seconds } else { 0.0 },
+            mean_latency_ms: if instances_sent > 0 {
+                current.latency_ms.saturating_sub(previous.latency_ms) as f64 / instances_sent as f64
+            } else {
+                0.0
+            },
+        }
+    }
+}
+
+/// Samples a meter on demand, one sample per call covering the time since the last
+#[derive(Debug)]
+pub struct ThroughputRecorder {
+    meter: Arc<TransferMeter>,
+    started: Instant,
+    last: MeterReading,
+    last_at: Instant,
+    samples: Vec<ThroughputSample>,
+}
+
+impl ThroughputRecorder {
+    pub fn new(meter: Arc<TransferMeter>) -> Self {
+        let now = Instant::now();
+        Self {
+            last: meter.read(),
+            meter,
+            started: now,
+            last_at: now,
+            samples: Vec::new(),
+        }
+    }
+
+    pub fn sample(&mut self) {
+        let now = Instant::now();
+        let reading = self.meter.read();
+        self.samples.push(ThroughputSample::between(&self.last, &reading, now - self.last_at, Utc::now(), now - self.started));
+        self.last = reading;
+        self.last_at = now;
+    }
+
+    /// Sample the interval in progress and hand over every sample so far
+    pub fn finish(&mut self) -> Vec<ThroughputSample> {
+        self.sample();
+        std::mem::take(&mut self.samples)
+    }
+}
+
+pub fn samples_csv(samples: &[ThroughputSample]) -> String {
+    let mut csv = String::from("timestamp,elapsed_seconds,instances_sent,instances_failed,bytes,throughput_mbps,mean_latency_ms\n");
+    for sample in samples {
+        csv.push_str(&format!("{},{:.3},{},{},{},{:.3},{:.1}\n",
+                              sample.timestamp.to_rfc3339(), sample.elapsed_seconds, sample.instances_sent,
+                              sample.instances_failed, sample.bytes, sample.throughput_mbps, sample.mean_latency_ms));
+    }
+    csv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sample_covers_interval() {
+        let meter = TransferMeter::default();
+        let start = meter.read();
+        meter.record(true, 3 * 1024 * 1024, Duration::from_millis(40));
+        meter.record(true, 1024 * 1024, Duration::from_millis(20));
+        meter.record(false, 0, Duration::from_millis(500));
+
+        let timestamp = DateTime::parse_from_rfc3339("2024-01-01T12:00:10Z").unwrap().with_timezone(&Utc);
+        let sample = ThroughputSample::between(&start, &meter.read(), Duration::from_secs(2), timestamp, Duration::from_secs(10));
+        assert_eq!((sample.instances_sent, sample.instances_failed, sample.bytes), (2, 1, 4 * 1024 * 1024));
+        assert_eq!(sample.throughput_mbps, 2.0);
+        assert_eq!(sample.mean_latency_ms, 30.0);
+
+        let idle = ThroughputSample::between(&meter.read(), &meter.read(), Duration::from_secs(2), timestamp, Duration::from_secs(12));
+        assert_eq!((idle.instances_sent, idle.throughput_mbps, idle.mean_latency_ms), (0, 0.0, 0.0));
+
+        assert_eq!(
+            samples_csv(&[sample]).lines().nth(1),
+            Some("2024-01-01T12:00:10+00:00,10.000,2,1,4194304,2.000,30.0")
+        );
+    }
+}
@@ -2,3 +2,53 @@
 pub mod types;
 pub mod sop_classes;
 pub mod transfer_syntaxes;
+pub mod validation;
+pub mod iod;
+pub mod repair;
+pub mod manifest;
+pub mod reports;
+pub mod ledger;
+pub mod content_hash;
+pub mod time_sanity;
+pub mod charset;
+pub mod size_limits;
+pub mod recovery;
+pub mod resume;
+pub mod quotas;
+pub mod deterministic;
+pub mod rejection;
+pub mod discovery;
+pub mod ts_preference;
+pub mod capabilities;
+pub mod probe;
+pub mod template;
+pub mod metrics;
+pub mod study_report;
+pub mod study_webhook;
+pub mod key_objects;
+pub mod dimse;
+pub mod reassembly;
+pub mod compliance;
+pub mod encryption;
+pub mod part10;
+pub mod naming;
+pub mod uids;
+pub mod codecs;
+pub mod preview;
+pub mod burn_in;
+pub mod tls;
+pub mod shutdown;
+pub mod mp4;
+pub mod video;
+pub mod nifti;
+pub mod receive_index;
+pub mod postgres_index;
+pub mod index_stats;
+pub mod housekeeping;
+pub mod listeners;
+pub mod listen;
+pub mod patient_admin;
+pub mod relay_mapping;
+pub mod retired;
+pub mod health;
+pub mod fs;
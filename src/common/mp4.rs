@@ -0,0 +1,340 @@
+//! MP4 files holding one H.264 video track
+//!
+//! An encoder produces Annex B byte streams: NAL units separated by start
+//! codes, with the sequence and picture parameter sets in band. MP4 (ISO/IEC
+//! 14496-12 and -15) wants them apart: the parameter sets go into the `avcC`
+//! sample description and each frame becomes a sample of NAL units prefixed
+//! with their length. Every frame carries its own duration, so irregular frame
+//! timing survives. Samples are written as one chunk in `mdat`, followed by
+//! the `moov` index that points into it.
+
+use anyhow::{Context, Result};
+
+/// Ticks per second of the movie and track timelines
+pub const TIMESCALE: u32 = 90_000;
+
+const NAL_SEQUENCE_PARAMETER_SET: u8 = 7;
+const NAL_PICTURE_PARAMETER_SET: u8 = 8;
+const NAL_IDR_SLICE: u8 = 5;
+
+/// The NAL units of an Annex B byte stream, without their start codes
+pub fn nal_units(stream: &[u8]) -> Vec<&[u8]> {
+    let mut starts = Vec::new();
+    let mut i = 0;
+    while i + 3 <= stream.len() {
+        if stream[i..i + 3] == [0, 0, 1] {
+            starts.push(i + 3);
+            i += 3;
+        } else {
+            i += 1;
+        }
+    }
+    starts.iter().enumerate()
+        .map(|(n, &start)| {
+            let end = starts.get(n + 1).map_or(stream.len(), |next| next - 3);
+            // Zeros before the next start code belong to it (four-byte start codes)
+            let mut unit = &stream[start..end];
+            while let [rest @ .., 0] = unit {
+                unit = rest;
+            }
+            unit
+        })
+        .filter(|unit| !unit.is_empty())
+        .collect()
+}
+
+fn nal_type(unit: &[u8]) -> u8 {
+    unit[0] & 0x1F
+}
+
+/// One encoded frame
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sample {
+    /// Length-prefixed NAL units
+    pub data: Vec<u8>,
+    /// In `TIMESCALE` ticks
+    pub duration: u32,
+    pub keyframe: bool,
+}
+
+/// Collects encoded frames and writes them out as an MP4 file
+#[derive(Debug, Default)]
+pub struct Mp4Writer {
+    sps: Option<Vec<u8>>,
+    pps: Option<Vec<u8>>,
+    samples: Vec<Sample>,
+}
+
+impl Mp4Writer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the Annex B output of the encoder for one frame, shown for `duration` ticks
+    pub fn add_frame(&mut self, stream: &[u8], duration: u32) {
+        let mut sample = Sample { data: Vec::new(), duration: duration.max(1), keyframe: false };
+        for unit in nal_units(stream) {
+            match nal_type(unit) {
+                NAL_SEQUENCE_PARAMETER_SET => {
+                    self.sps.get_or_insert_with(|| unit.to_vec());
+                }
+                NAL_PICTURE_PARAMETER_SET => {
+                    self.pps.get_or_insert_with(|| unit.to_vec());
+                }
+                kind => {
+                    sample.keyframe |= kind == NAL_IDR_SLICE;
+                    sample.data.extend_from_slice(&(unit.len() as u32).to_be_bytes());
+                    sample.data.extend_from_slice(unit);
+                }
+            }
+        }
+        if sample.data.is_empty() {
+            // A skipped frame extends the one before it
+            if let Some(previous) = self.samples.last_mut() {
+                previous.duration += sample.duration;
+            }
+            return;
+        }
+        self.samples.push(sample);
+    }
+
+    /// The MP4 file of a `width` x `height` video
+    pub fn finish(&self, width: u16, height: u16) -> Result<Vec<u8>> {
+        let sps = self.sps.as_deref().context("the encoder produced no sequence parameter set")?;
+        let pps = self.pps.as_deref().context("the encoder produced no picture parameter set")?;
+        if sps.len() < 4 {
+            anyhow::bail!("sequence parameter set of {} bytes is too short", sps.len());
+        }
+        if self.samples.is_empty() {
+            anyhow::bail!("no frames were encoded");
+        }
+
+        let mut file = ftyp();
+        let payload: usize = self.samples.iter().map(|sample| sample.data.len()).sum();
+        let mdat_header = 8;
+        let chunk_offset = u32::try_from(file.len() + mdat_header)?;
+        let mdat_size = u32::try_from(mdat_header + payload).context("video exceeds 4 GB")?;
+        file.extend_from_slice(&mdat_size.to_be_bytes());
+        file.extend_from_slice(b"mdat");
+        for sample in &self.samples {
+            file.extend_from_slice(&sample.data);
+        }
+        file.extend(self.moov(width, height, sps, pps, chunk_offset));
+        Ok(file)
+    }
+
+    fn duration(&self) -> u32 {
+        self.samples.iter().map(|sample| sample.duration).sum()
+    }
+
+    fn moov(&self, width: u16, height: u16, sps: &[u8], pps: &[u8], chunk_offset: u32) -> Vec<u8> {
+        let duration = self.duration();
+
+        let mut mvhd = full_box_header(0, 0);
+        mvhd.extend([0u32, 0, TIMESCALE, duration].iter().flat_map(|v| v.to_be_bytes()));
+        mvhd.extend_from_slice(&0x0001_0000u32.to_be_bytes()); // rate 1.0
+        mvhd.extend_from_slice(&0x0100u16.to_be_bytes()); // volume 1.0
+        mvhd.extend_from_slice(&[0; 10]);
+        mvhd.extend(MATRIX.iter().flat_map(|v| v.to_be_bytes()));
+        mvhd.extend_from_slice(&[0; 24]);
+        mvhd.extend_from_slice(&2u32.to_be_bytes()); // next track ID
+
+        // Enabled, in movie
+        let mut tkhd = full_box_header(0, 3);
+        tkhd.extend([0u32, 0, 1, 0, duration].iter().flat_map(|v| v.to_be_bytes()));
+        tkhd.extend_from_slice(&[0; 8]);
+        tkhd.extend_from_slice(&[0; 8]); // layer, alternate group, volume, reserved
+        tkhd.extend(MATRIX.iter().flat_map(|v| v.to_be_bytes()));
+        tkhd.extend_from_slice(&((width as u32) << 16).to_be_bytes());
+        tkhd.extend_from_slice(&((height as u32) << 16).to_be_bytes());
+
+        let mut mdhd = full_box_header(0, 0);
+        mdhd.extend([0u32, 0, TIMESCALE, duration].iter().flat_map(|v| v.to_be_bytes()));
+        mdhd.extend_from_slice(&0x55C4u16.to_be_bytes()); // language "und"
+        mdhd.extend_from_slice(&[0; 2]);
+
+        let mut hdlr = full_box_header(0, 0);
+        hdlr.extend_from_slice(&[0; 4]);
+        hdlr.extend_from_slice(b"vide");
+        hdlr.extend_from_slice(&[0; 12]);
+        hdlr.extend_from_slice(b"VideoHandler\0");
+
+        let mut vmhd = full_box_header(0, 1);
+        vmhd.extend_from_slice(&[0; 8]);
+
+        let mut dref = full_box_header(0, 0);
+        dref.extend_from_slice(&1u32.to_be_bytes());
+        // Media data in this file
+        dref.extend(mp4_box(b"url ", &full_box_header(0, 1)));
+
+        let stbl = [
+            mp4_box(b"stsd", &self.stsd(width, height, sps, pps)),
+            mp4_box(b"stts", &self.stts()),
+            mp4_box(b"stss", &self.stss()),
+            mp4_box(b"stsc", &[full_box_header(0, 0), [1u32, 1, self.samples.len() as u32, 1].iter().flat_map(|v| v.to_be_bytes()).collect()].concat()),
+            mp4_box(b"stsz", &self.stsz()),
+            mp4_box(b"stco", &[full_box_header(0, 0), [1u32, chunk_offset].iter().flat_map(|v| v.to_be_bytes()).collect()].concat()),
+        ].concat();
+        let minf = [mp4_box(b"vmhd", &vmhd), mp4_box(b"dinf", &mp4_box(b"dref", &dref)), mp4_box(b"stbl", &stbl)].concat();
+        let mdia = [mp4_box(b"mdhd", &mdhd), mp4_box(b"hdlr", &hdlr), mp4_box(b"minf", &minf)].concat();
+        let trak = [mp4_box(b"tkhd", &tkhd), mp4_box(b"mdia", &mdia)].concat();
+        mp4_box(b"moov", &[mp4_box(b"mvhd", &mvhd), mp4_box(b"trak", &trak)].concat())
+    }
+
+    fn stsd(&self, width: u16, height: u16, sps: &[u8], pps: &[u8]) -> Vec<u8> {
+        // AVC decoder configuration with four-byte NAL unit lengths
+        let mut avcc = vec![1, sps[1], sps[2], sps[3], 0xFF, 0xE1];
+        avcc.extend_from_slice(&(sps.len() as u16).to_be_bytes());
+        avcc.extend_from_slice(sps);
+        avcc.push(1);
+        avcc.extend_from_slice(&(pps.len() as u16).to_be_bytes());
+        avcc.extend_from_slice(pps);
+
+        let mut avc1 = vec![0; 6];
+        avc1.extend_from_slice(&1u16.to_be_bytes()); // data reference index
+        avc1.extend_from_slice(&[0; 16]);
+        avc1.extend_from_slice(&width.to_be_bytes());
+        avc1.extend_from_slice(&height.to_be_bytes());
+        avc1.extend_from_slice(&0x0048_0000u32.to_be_bytes()); // 72 dpi
+        avc1.extend_from_slice(&0x0048_0000u32.to_be_bytes());
+        avc1.extend_from_slice(&[0; 4]);
+        avc1.extend_from_slice(&1u16.to_be_bytes()); // frames per sample
+        avc1.extend_from_slice(&[0; 32]); // compressor name
+        avc1.extend_from_slice(&0x0018u16.to_be_bytes()); // depth
+        avc1.extend_from_slice(&(-1i16).to_be_bytes());
+        avc1.extend(mp4_box(b"avcC", &avcc));
+
+        let mut stsd = full_box_header(0, 0);
+        stsd.extend_from_slice(&1u32.to_be_bytes());
+        stsd.extend(mp4_box(b"avc1", &avc1));
+        stsd
+    }
+
+    /// Sample durations, run-length encoded
+    fn stts(&self) -> Vec<u8> {
+        let mut runs: Vec<(u32, u32)> = Vec::new();
+        for sample in &self.samples {
+            match runs.last_mut() {
+                Some((count, duration)) if *duration == sample.duration => *count += 1,
+                _ => runs.push((1, sample.duration)),
+            }
+        }
+        let mut stts = full_box_header(0, 0);
+        stts.extend_from_slice(&(runs.len() as u32).to_be_bytes());
+        stts.extend(runs.iter().flat_map(|(count, duration)| [*count, *duration]).flat_map(u32::to_be_bytes));
+        stts
+    }
+
+    /// 1-based numbers of the samples a decoder can start at
+    fn stss(&self) -> Vec<u8> {
+        let keyframes: Vec<u32> = self.samples.iter().enumerate()
+            .filter(|(_, sample)| sample.keyframe)
+            .map(|(index, _)| index as u32 + 1)
+            .collect();
+        let mut stss = full_box_header(0, 0);
+        stss.extend_from_slice(&(keyframes.len() as u32).to_be_bytes());
+        stss.extend(keyframes.iter().flat_map(|number| number.to_be_bytes()));
+        stss
+    }
+
+    fn stsz(&self) -> Vec<u8> {
+        let mut stsz = full_box_header(0, 0);
+        stsz.extend_from_slice(&0u32.to_be_bytes());
+        stsz.extend_from_slice(&(self.samples.len() as u32).to_be_bytes());
+        stsz.extend(self.samples.iter().flat_map(|sample| (sample.data.len() as u32).to_be_bytes()));
+        stsz
+    }
+}
+
+/// Identity transformation of movie and track headers
+const MATRIX: [u32; 9] = [0x0001_0000, 0, 0, 0, 0x0001_0000, 0, 0, 0, 0x4000_0000];
+
+fn ftyp() -> Vec<u8> {
+    let mut ftyp = b"isom".to_vec();
+    ftyp.extend_from_slice(&0x200u32.to_be_bytes());
+    for brand in [b"isom", b"iso2", b"avc1", b"mp41"] {
+        ftyp.extend_from_slice(brand);
+    }
+    mp4_box(b"ftyp", &ftyp)
+}
+
+fn full_box_header(version: u8, flags: u32) -> Vec<u8> {
+    let mut header = flags.to_be_bytes();
+    header[0] = version;
+    header.to_vec()
+}
+
+fn mp4_box(kind: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+    let mut mp4_box = ((payload.len() + 8) as u32).to_be_bytes().to_vec();
+    mp4_box.extend_from_slice(kind);
+    mp4_box.extend_from_slice(payload);
+    mp4_box
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Payload of the first box of `kind` found by descending through `path`
+    fn find<'a>(mut data: &'a [u8], path: &[&[u8; 4]]) -> Option<&'a [u8]> {
+        for (depth, kind) in path.iter().enumerate() {
+            let mut found = None;
+            while data.len() >= 8 {
+                let size = u32::from_be_bytes(data[0..4].try_into().unwrap()) as usize;
+                if &data[4..8] == *kind {
+                    found = Some(&data[8..size]);
+                    break;
+                }
+                data = &data[size..];
+            }
+            data = found?;
+            // stsd and dref hold a full box header and an entry count before their boxes
+            if depth + 1 < path.len() && matches!(*kind, b"stsd" | b"dref") {
+                data = &data[8..];
+            }
+        }
+        Some(data)
+    }
+
+    #[test]
+    fn test_nal_units() {
+        let stream = [0, 0, 0, 1, 0x67, 1, 2, 0, 0, 1, 0x68, 3, 0, 0, 0, 1, 0x65, 4, 0];
+        assert_eq!(nal_units(&stream), vec![&[0x67, 1, 2][..], &[0x68, 3][..], &[0x65, 4][..]]);
+        assert!(nal_units(&[1, 2, 3]).is_empty());
+    }
+
+    #[test]
+    fn test_mp4_layout() {
+        let mut writer = Mp4Writer::new();
+        writer.add_frame(&[0, 0, 0, 1, 0x67, 0x42, 0xC0, 0x1E, 9, 0, 0, 0, 1, 0x68, 0xCE, 0, 0, 0, 1, 0x65, 1, 2, 3], 3000);
+        writer.add_frame(&[0, 0, 0, 1, 0x41, 4, 5], 3000);
+        writer.add_frame(&[], 3000);
+        writer.add_frame(&[0, 0, 0, 1, 0x41, 6], 6000);
+        let file = writer.finish(64, 48).unwrap();
+
+        assert_eq!(&file[4..8], b"ftyp");
+        let mdat = find(&file, &[b"mdat"]).unwrap();
+        assert_eq!(mdat, [0, 0, 0, 4, 0x65, 1, 2, 3, 0, 0, 0, 3, 0x41, 4, 5, 0, 0, 0, 2, 0x41, 6]);
+
+        let stbl: [&[u8; 4]; 5] = [b"moov", b"trak", b"mdia", b"minf", b"stbl"];
+        let path = |leaf: &'static [u8; 4]| [&stbl[..], &[leaf]].concat();
+        // The skipped frame lengthens the one before it
+        assert_eq!(find(&file, &path(b"stts")).unwrap(), [0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 1, 0, 0, 0x0B, 0xB8, 0, 0, 0, 2, 0, 0, 0x17, 0x70]);
+        assert_eq!(find(&file, &path(b"stss")).unwrap(), [0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 1]);
+        assert_eq!(&find(&file, &path(b"stsz")).unwrap()[8..], [0, 0, 0, 3, 0, 0, 0, 8, 0, 0, 0, 7, 0, 0, 0, 6]);
+
+        let stco = find(&file, &path(b"stco")).unwrap();
+        let offset = u32::from_be_bytes(stco[8..12].try_into().unwrap()) as usize;
+        assert_eq!(&file[offset..offset + mdat.len()], mdat);
+
+        let avc1 = find(&file, &[path(b"stsd"), vec![b"avc1"]].concat()).unwrap();
+        assert_eq!(&avc1[24..28], [0, 64, 0, 48]);
+        assert_eq!(&avc1[82..86], b"avcC");
+        assert_eq!(&avc1[86..], [1, 0x42, 0xC0, 0x1E, 0xFF, 0xE1, 0, 5, 0x67, 0x42, 0xC0, 0x1E, 9, 1, 0, 2, 0x68, 0xCE]);
+
+        let mvhd = find(&file, &[b"moov", b"mvhd"]).unwrap();
+        assert_eq!(&mvhd[12..20], [0, 1, 0x5F, 0x90, 0, 0, 0x3A, 0x98]);
+
+        assert!(Mp4Writer::new().finish(64, 48).is_err());
+    }
+}
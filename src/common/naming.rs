@@ -0,0 +1,245 @@
+//! Filename templates for received objects
+//!
+//! A template such as `{modality}/{study_date}/{accession}_{instance_number}.dcm`
+//! files each received object under a path built from its own attributes; `/`
+//! separates directories. Values are sanitized so they can neither add
+//! directories of their own nor leave the output directory, and missing or
+//! empty values become `UNKNOWN`. Paths stay valid on Windows hosts too:
+//! characters Windows forbids are replaced, values are cut to
+//! `MAX_VALUE_LENGTH` characters, and a component naming a device such as
+//! `CON` or `COM1.dcm` gets a leading `_`. When an object expands to the path
+//! of one already stored, `_1`, `_2`, ... is added in front of the extension.
+
+use std::path::{Component, Path, PathBuf};
+
+/// Stands in for a missing or empty attribute value
+pub const MISSING_VALUE: &str = "UNKNOWN";
+
+/// Longest value, in characters, a placeholder expands to
+pub const MAX_VALUE_LENGTH: usize = 64;
+
+/// File names Windows reserves for devices, with any extension
+const RESERVED_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL",
+    "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8", "COM9",
+    "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Placeholder names and the (group, element) tags they are read from
+pub const PLACEHOLDERS: [(&str, (u16, u16)); 14] = [
+    ("patient_id", (0x0010, 0x0020)),
+    ("patient_name", (0x0010, 0x0010)),
+    ("study_date", (0x0008, 0x0020)),
+    ("study_time", (0x0008, 0x0030)),
+    ("accession", (0x0008, 0x0050)),
+    ("modality", (0x0008, 0x0060)),
+    ("study_description", (0x0008, 0x1030)),
+    ("series_description", (0x0008, 0x103E)),
+    ("study_uid", (0x0020, 0x000D)),
+    ("series_uid", (0x0020, 0x000E)),
+    ("sop_uid", (0x0008, 0x0018)),
+    ("sop_class", (0x0008, 0x0016)),
+    ("series_number", (0x0020, 0x0011)),
+    ("instance_number", (0x0020, 0x0013)),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Literal(String),
+    Attribute((u16, u16)),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FilenameTemplate {
+    template: String,
+    parts: Vec<Part>,
+}
+
+impl FilenameTemplate {
+    /// Parse a template, rejecting unknown placeholders and paths that are
+    /// absolute or contain `.` or `..` components
+    pub fn parse(template: &str) -> Result<Self, String> {
+        if template.trim().is_empty() || template.ends_with('/') {
+            return Err(format!("filename template '{}' does not end in a file name", template));
+        }
+        if !Path::new(template).components().all(|component| matches!(component, Component::Normal(_))) {
+            return Err(format!("filename template '{}' must be a relative path without '.' or '..'", template));
+        }
+
+        let mut parts = Vec::new();
+        let mut rest = template;
+        while let Some(start) = rest.find(['{', '}']) {
+            if rest[start..].starts_with('}') {
+                return Err(format!("unmatched '}}' in filename template '{}'", template));
+            }
+            let end = rest[start..].find('}')
+                .map(|end| start + end)
+                .ok_or_else(|| format!("unclosed '{{' in filename template '{}'", template))?;
+            let name = &rest[start + 1..end];
+            let tag = PLACEHOLDERS.iter()
+                .find(|(placeholder, _)| *placeholder == name)
+                .map(|(_, tag)| *tag)
+                .ok_or_else(|| format!("unknown placeholder {{{}}}, expected one of {}", name,
+                                       PLACEHOLDERS.iter().map(|(placeholder, _)| *placeholder).collect::<Vec<_>>().join(", ")))?;
+            if start > 0 {
+                parts.push(Part::Literal(rest[..start].to_string()));
+            }
+            parts.push(Part::Attribute(tag));
+            rest = &rest[end + 1..];
+        }
+        if !rest.is_empty() {
+            parts.push(Part::Literal(rest.to_string()));
+        }
+        Ok(Self { template: template.to_string(), parts })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.template
+    }
+
+    /// Path relative to the output directory, each placeholder replaced by the
+    /// sanitized value `value` gives for its tag
+    pub fn expand(&self, value: impl Fn((u16, u16)) -> Option<String>) -> PathBuf {
+        let mut expanded = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Literal(text) => expanded.push_str(text),
+                Part::Attribute(tag) => expanded.push_str(&sanitize(value(*tag).as_deref().unwrap_or_default())),
+            }
+        }
+        // A device name can come from a literal, a value or both together
+        expanded.split('/')
+            .map(|component| if is_reserved(component) { format!("_{}", component) } else { component.to_string() })
+            .collect()
+    }
+}
+
+/// Whether Windows takes a file named `component` for a device
+fn is_reserved(component: &str) -> bool {
+    let stem = component.split('.').next().unwrap_or_default().trim_end();
+    RESERVED_NAMES.iter().any(|name| name.eq_ignore_ascii_case(stem))
+}
+
+/// An attribute value made safe to use within a single path component
+pub fn sanitize(value: &str) -> String {
+    let cleaned: String = value.trim_end_matches('\0').trim().chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() || c.is_whitespace() => '_',
+            c => c,
+        })
+        .take(MAX_VALUE_LENGTH)
+        .collect();
+    // Leading dots would make hidden files or '..', and Windows drops trailing ones
+    let cleaned = cleaned.trim_matches('.');
+    if cleaned.is_empty() {
+        MISSING_VALUE.to_string()
+    } else {
+        cleaned.to_string()
+    }
+}
+
+/// `path` in the form Windows opens beyond MAX_PATH (260 characters):
+/// absolute, with the `\\?\` prefix and `\` separators only. Elsewhere
+/// there is no such limit and `path` is returned unchanged
+pub fn long_path(path: &Path) -> PathBuf {
+    #[cfg(windows)]
+    {
+        use std::ffi::OsString;
+        use std::path::Prefix;
+
+        let Ok(absolute) = std::path::absolute(path) else {
+            return path.to_path_buf();
+        };
+        let mut long = OsString::new();
+        for component in absolute.components() {
+            match component {
+                Component::Prefix(prefix) => match prefix.kind() {
+                    Prefix::Disk(drive) => long.push(format!(r"\\?\{}:", drive as char)),
+                    Prefix::UNC(server, share) => {
+                        long.push(r"\\?\UNC\");
+                        long.push(server);
+                        long.push(r"\");
+                        long.push(share);
+                    }
+                    // Already verbatim, or a device path
+                    _ => return absolute,
+                },
+                Component::RootDir => {}
+                Component::Normal(name) => {
+                    long.push(r"\");
+                    long.push(name);
+                }
+                // `absolute` resolves '.' and '..' on Windows
+                Component::CurDir | Component::ParentDir => return absolute,
+            }
+        }
+        PathBuf::from(long)
+    }
+    #[cfg(not(windows))]
+    path.to_path_buf()
+}
+
+/// `path` if it is free, else the first free one of `<stem>_1.<ext>`, `<stem>_2.<ext>`, ...
+pub fn unique_path(path: &Path) -> PathBuf {
+    if !path.exists() {
+        return path.to_path_buf();
+    }
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = path.extension().map(|ext| format!(".{}", ext.to_string_lossy())).unwrap_or_default();
+    (1u64..)
+        .map(|n| path.with_file_name(format!("{}_{}{}", stem, n, extension)))
+        .find(|candidate| !candidate.exists())
+        .expect("some numbered file name is free")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_and_sanitize() {
+        let template = FilenameTemplate::parse("{modality}/{study_date}/{accession}_{instance_number}.dcm").unwrap();
+        let value = |tag: (u16, u16)| match tag {
+            (0x0008, 0x0060) => Some("CT".to_string()),
+            (0x0008, 0x0020) => Some("20240131".to_string()),
+            (0x0008, 0x0050) => Some("../ACC 1/2\0".to_string()),
+            _ => None,
+        };
+        assert_eq!(template.expand(value), Path::new("CT").join("20240131").join("_ACC_1_2_UNKNOWN.dcm"));
+        assert_eq!(sanitize(".."), MISSING_VALUE);
+        assert_eq!(sanitize("DOE^JOHN"), "DOE^JOHN");
+        assert_eq!(sanitize("ABDOMEN."), "ABDOMEN");
+        assert_eq!(sanitize(&"X".repeat(100)).len(), MAX_VALUE_LENGTH);
+
+        for invalid in ["/abs/{sop_uid}.dcm", "../{sop_uid}.dcm", "{modality}/", "{nope}.dcm", "{modality.dcm", "a}.dcm", ""] {
+            assert!(FilenameTemplate::parse(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_device_names_are_escaped() {
+        let template = FilenameTemplate::parse("{modality}/{accession}.dcm").unwrap();
+        let value = |tag: (u16, u16)| match tag {
+            (0x0008, 0x0060) => Some("con".to_string()),
+            (0x0008, 0x0050) => Some("COM1".to_string()),
+            _ => None,
+        };
+        assert_eq!(template.expand(value), Path::new("_con").join("_COM1.dcm"));
+        assert!(!is_reserved("CONSOLE.dcm"));
+        assert!(!is_reserved("COM10"));
+        assert!(is_reserved("Aux.txt"));
+    }
+
+    #[test]
+    fn test_collisions_are_numbered() {
+        let dir = std::env::temp_dir().join(format!("naming_test_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("CT_1.dcm");
+        assert_eq!(unique_path(&path), path);
+        std::fs::write(&path, b"first").unwrap();
+        std::fs::write(dir.join("CT_1_1.dcm"), b"second").unwrap();
+        assert_eq!(unique_path(&path), dir.join("CT_1_2.dcm"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
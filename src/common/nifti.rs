@@ -0,0 +1,327 @@
+//! CT and MR series as NIfTI-1 volumes
+//!
+//! Research pipelines take volumes, not slices. The slices of a series are
+//! ordered along the normal of their Image Orientation (Patient), checked to
+//! share one geometry and to be evenly spaced, and stacked into a volume of
+//! rescaled values (Hounsfield units for CT) stored as 32-bit floats. The
+//! voxel-to-world transform is built from Image Position (Patient), Image
+//! Orientation (Patient) and Pixel Spacing and converted from DICOM's LPS to
+//! NIfTI's RAS patient axes; it is written both as the sform and the qform.
+//! Only single-frame images with native pixel data are supported.
+
+use anyhow::{Context, Result};
+use dicom_core::Tag;
+use dicom_object::{FileDicomObject, InMemDicomObject};
+
+use super::preview::NativePixels;
+
+const SLICE_THICKNESS: Tag = Tag(0x0018, 0x0050);
+const SPACING_BETWEEN_SLICES: Tag = Tag(0x0018, 0x0088);
+const IMAGE_POSITION_PATIENT: Tag = Tag(0x0020, 0x0032);
+const IMAGE_ORIENTATION_PATIENT: Tag = Tag(0x0020, 0x0037);
+const PIXEL_SPACING: Tag = Tag(0x0028, 0x0030);
+
+/// Size of the NIfTI-1 header
+const HEADER_LENGTH: usize = 348;
+/// Offset of the voxels in a single-file NIfTI: the header and an empty extension flag
+const VOXEL_OFFSET: usize = HEADER_LENGTH + 4;
+const DT_FLOAT32: i16 = 16;
+const NIFTI_XFORM_SCANNER_ANAT: i16 = 1;
+const NIFTI_UNITS_MM: u8 = 2;
+
+/// Relative difference between slice gaps still taken as even spacing
+const SPACING_TOLERANCE: f64 = 0.01;
+
+/// One image of a series with its place in the patient
+#[derive(Debug, Clone, PartialEq)]
+pub struct Slice {
+    pub rows: u16,
+    pub columns: u16,
+    /// Image Position (Patient) of the first pixel, in mm
+    pub position: [f64; 3],
+    /// Row and column direction cosines
+    pub orientation: [f64; 6],
+    /// Distance between rows and between columns, in mm
+    pub pixel_spacing: [f64; 2],
+    /// Spacing Between Slices or else Slice Thickness, for volumes of one slice
+    pub thickness: Option<f64>,
+    /// Rescaled values, row by row
+    pub values: Vec<f32>,
+}
+
+impl Slice {
+    pub fn read(obj: &FileDicomObject<InMemDicomObject>) -> Result<Self> {
+        let floats = |tag: Tag, name: &str| obj.element(tag).ok()
+            .and_then(|e| e.to_multi_float64().ok())
+            .with_context(|| format!("no {}", name));
+        let float = |tag: Tag| obj.element(tag).ok()
+            .and_then(|e| e.to_float64().ok())
+            .filter(|value| *value > 0.0);
+
+        let native = NativePixels::read(obj)?.context("no pixel data")?;
+        let layout = &native.layout;
+        if layout.samples_per_pixel != 1 || native.frames != 1 {
+            anyhow::bail!("only single-frame monochrome images can be stacked, not {} frames of {} samples",
+                          native.frames, layout.samples_per_pixel);
+        }
+        let position = floats(IMAGE_POSITION_PATIENT, "Image Position (Patient)")?;
+        let orientation = floats(IMAGE_ORIENTATION_PATIENT, "Image Orientation (Patient)")?;
+        let pixel_spacing = floats(PIXEL_SPACING, "Pixel Spacing")?;
+        if position.len() != 3 || orientation.len() != 6 || pixel_spacing.len() != 2 {
+            anyhow::bail!("malformed Image Position, Image Orientation or Pixel Spacing");
+        }
+
+        let pixels = layout.rows as usize * layout.columns as usize;
+        let (slope, intercept) = (native.mapping.rescale_slope, native.mapping.rescale_intercept);
+        let values = (0..pixels)
+            .map(|index| (layout.sample(&native.data, index) as f64 * slope + intercept) as f32)
+            .collect();
+        Ok(Self {
+            rows: layout.rows,
+            columns: layout.columns,
+            position: [position[0], position[1], position[2]],
+            orientation: [orientation[0], orientation[1], orientation[2], orientation[3], orientation[4], orientation[5]],
+            pixel_spacing: [pixel_spacing[0], pixel_spacing[1]],
+            thickness: float(SPACING_BETWEEN_SLICES).or_else(|| float(SLICE_THICKNESS)),
+            values,
+        })
+    }
+
+    fn row_direction(&self) -> [f64; 3] {
+        [self.orientation[0], self.orientation[1], self.orientation[2]]
+    }
+
+    fn column_direction(&self) -> [f64; 3] {
+        [self.orientation[3], self.orientation[4], self.orientation[5]]
+    }
+
+    /// Unit normal of the image plane, pointing towards increasing slice position
+    fn normal(&self) -> [f64; 3] {
+        let (r, c) = (self.row_direction(), self.column_direction());
+        [r[1] * c[2] - r[2] * c[1], r[2] * c[0] - r[0] * c[2], r[0] * c[1] - r[1] * c[0]]
+    }
+}
+
+fn dot(a: [f64; 3], b: [f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+/// A stack of slices with its voxel-to-world transform
+#[derive(Debug, Clone, PartialEq)]
+pub struct Volume {
+    /// Columns, rows and slices
+    pub dimensions: [usize; 3],
+    /// Voxel size along each dimension, in mm
+    pub spacing: [f64; 3],
+    /// First three rows of the affine from voxel indices to RAS millimetres
+    pub affine: [[f64; 4]; 3],
+    /// Column fastest, then row, then slice
+    pub voxels: Vec<f32>,
+}
+
+impl Volume {
+    /// Stack `slices` in order along their normal; fails unless they share
+    /// their size and orientation and are evenly spaced
+    pub fn assemble(mut slices: Vec<Slice>) -> Result<Self> {
+        let first = slices.first().context("no slices")?.clone();
+        for slice in &slices {
+            if (slice.rows, slice.columns) != (first.rows, first.columns) {
+                anyhow::bail!("slices of {}x{} and {}x{} pixels cannot be stacked", first.columns, first.rows, slice.columns, slice.rows);
+            }
+            if slice.orientation.iter().zip(&first.orientation).any(|(a, b)| (a - b).abs() > 1e-4)
+                || slice.pixel_spacing.iter().zip(&first.pixel_spacing).any(|(a, b)| (a - b).abs() > 1e-4) {
+                anyhow::bail!("slices differ in orientation or pixel spacing");
+            }
+        }
+
+        let normal = first.normal();
+        slices.sort_by(|a, b| dot(a.position, normal).total_cmp(&dot(b.position, normal)));
+        let distances: Vec<f64> = slices.windows(2)
+            .map(|pair| dot(pair[1].position, normal) - dot(pair[0].position, normal))
+            .collect();
+
+        // One step from slice to slice, in patient coordinates
+        let step = match (slices.first(), slices.last()) {
+            (Some(first), Some(last)) if slices.len() > 1 => {
+                let mean = distances.iter().sum::<f64>() / distances.len() as f64;
+                if distances.iter().any(|distance| *distance < 1e-4) {
+                    anyhow::bail!("several slices share a position; the series may hold several echoes or phases");
+                }
+                if distances.iter().any(|distance| (distance - mean).abs() > SPACING_TOLERANCE * mean) {
+                    anyhow::bail!("slices are not evenly spaced ({:.3} to {:.3} mm apart)",
+                                  distances.iter().copied().fold(f64::MAX, f64::min), distances.iter().copied().fold(f64::MIN, f64::max));
+                }
+                let n = (slices.len() - 1) as f64;
+                [0, 1, 2].map(|axis| (last.position[axis] - first.position[axis]) / n)
+            }
+            _ => normal.map(|component| component * first.thickness.unwrap_or(1.0)),
+        };
+
+        let (row_spacing, column_spacing) = (first.pixel_spacing[0], first.pixel_spacing[1]);
+        let origin = slices[0].position;
+        let (row_direction, column_direction) = (first.row_direction(), first.column_direction());
+        let mut affine = [[0.0; 4]; 3];
+        for (axis, affine_row) in affine.iter_mut().enumerate() {
+            // LPS to RAS flips the first two patient axes
+            let sign = if axis < 2 { -1.0 } else { 1.0 };
+            *affine_row = [
+                sign * row_direction[axis] * column_spacing,
+                sign * column_direction[axis] * row_spacing,
+                sign * step[axis],
+                sign * origin[axis],
+            ];
+        }
+
+        let slice_spacing = dot(step, step).sqrt();
+        let dimensions = [first.columns as usize, first.rows as usize, slices.len()];
+        let voxels = slices.into_iter().flat_map(|slice| slice.values).collect();
+        Ok(Self { dimensions, spacing: [column_spacing, row_spacing, slice_spacing], affine, voxels })
+    }
+
+    /// The volume as a single-file NIfTI-1 (`.nii`) image
+    pub fn to_nifti(&self) -> Vec<u8> {
+        let mut header = vec![0u8; VOXEL_OFFSET];
+        let mut put = |offset: usize, bytes: &[u8]| header[offset..offset + bytes.len()].copy_from_slice(bytes);
+
+        put(0, &(HEADER_LENGTH as i32).to_le_bytes());
+        put(38, b"r");
+        let mut dim = [1i16; 8];
+        dim[0] = 3;
+        for (axis, size) in self.dimensions.iter().enumerate() {
+            dim[axis + 1] = i16::try_from(*size).unwrap_or(i16::MAX);
+        }
+        put(40, &dim.map(i16::to_le_bytes).concat());
+        put(70, &DT_FLOAT32.to_le_bytes());
+        put(72, &32i16.to_le_bytes());
+
+        let (quaternion, offset, qfac) = quaternion(&self.affine, self.spacing);
+        let mut pixdim = [0f32; 8];
+        pixdim[0] = qfac as f32;
+        for (axis, spacing) in self.spacing.iter().enumerate() {
+            pixdim[axis + 1] = *spacing as f32;
+        }
+        put(76, &pixdim.map(f32::to_le_bytes).concat());
+        put(108, &(VOXEL_OFFSET as f32).to_le_bytes());
+        put(112, &1f32.to_le_bytes());
+        put(123, &[NIFTI_UNITS_MM]);
+        put(148, b"Converted from DICOM by rust-dicom");
+        put(252, &NIFTI_XFORM_SCANNER_ANAT.to_le_bytes());
+        put(254, &NIFTI_XFORM_SCANNER_ANAT.to_le_bytes());
+        let qform: Vec<u8> = quaternion.iter().chain(&offset).flat_map(|value| (*value as f32).to_le_bytes()).collect();
+        put(256, &qform);
+        for (axis, affine_row) in self.affine.iter().enumerate() {
+            put(280 + 16 * axis, &affine_row.map(|value| (value as f32).to_le_bytes()).concat());
+        }
+        put(344, b"n+1\0");
+
+        let mut nifti = header;
+        nifti.reserve(self.voxels.len() * 4);
+        for voxel in &self.voxels {
+            nifti.extend_from_slice(&voxel.to_le_bytes());
+        }
+        nifti
+    }
+}
+
+/// The quaternion parameters b, c, d, the offset and qfac of the qform
+/// equivalent to `affine`, after nifti1_io's nifti_mat44_to_quatern
+fn quaternion(affine: &[[f64; 4]; 3], spacing: [f64; 3]) -> ([f64; 3], [f64; 3], f64) {
+    let mut r = [[0.0; 3]; 3];
+    for (i, row) in r.iter_mut().enumerate() {
+        for (j, value) in row.iter_mut().enumerate() {
+            *value = affine[i][j] / spacing[j].max(f64::EPSILON);
+        }
+    }
+    let determinant = r[0][0] * (r[1][1] * r[2][2] - r[1][2] * r[2][1])
+        - r[0][1] * (r[1][0] * r[2][2] - r[1][2] * r[2][0])
+        + r[0][2] * (r[1][0] * r[2][1] - r[1][1] * r[2][0]);
+    let qfac = if determinant < 0.0 { -1.0 } else { 1.0 };
+    if qfac < 0.0 {
+        for row in r.iter_mut() {
+            row[2] = -row[2];
+        }
+    }
+
+    let trace = r[0][0] + r[1][1] + r[2][2] + 1.0;
+    let (b, c, d) = if trace > 0.5 {
+        let a = 0.5 * trace.sqrt();
+        (0.25 * (r[2][1] - r[1][2]) / a, 0.25 * (r[0][2] - r[2][0]) / a, 0.25 * (r[1][0] - r[0][1]) / a)
+    } else {
+        let xd = 1.0 + r[0][0] - (r[1][1] + r[2][2]);
+        let yd = 1.0 + r[1][1] - (r[0][0] + r[2][2]);
+        let zd = 1.0 + r[2][2] - (r[0][0] + r[1][1]);
+        let (a, b, c, d) = if xd > 1.0 {
+            let b = 0.5 * xd.sqrt();
+            (0.25 * (r[2][1] - r[1][2]) / b, b, 0.25 * (r[0][1] + r[1][0]) / b, 0.25 * (r[0][2] + r[2][0]) / b)
+        } else if yd > 1.0 {
+            let c = 0.5 * yd.sqrt();
+            (0.25 * (r[0][2] - r[2][0]) / c, 0.25 * (r[0][1] + r[1][0]) / c, c, 0.25 * (r[1][2] + r[2][1]) / c)
+        } else {
+            let d = 0.5 * zd.sqrt();
+            (0.25 * (r[1][0] - r[0][1]) / d, 0.25 * (r[0][2] + r[2][0]) / d, 0.25 * (r[1][2] + r[2][1]) / d, d)
+        };
+        // The quaternion with a >= 0 is the one stored
+        if a < 0.0 { (-b, -c, -d) } else { (b, c, d) }
+    };
+    ([b, c, d], [affine[0][3], affine[1][3], affine[2][3]], qfac)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An axial 2x2 slice at height `z` whose values are all `value`
+    fn axial(z: f64, value: f32) -> Slice {
+        Slice {
+            rows: 2,
+            columns: 2,
+            position: [-10.0, -20.0, z],
+            orientation: [1.0, 0.0, 0.0, 0.0, 1.0, 0.0],
+            pixel_spacing: [0.5, 0.7],
+            thickness: Some(3.0),
+            values: vec![value; 4],
+        }
+    }
+
+    #[test]
+    fn test_assemble_orders_slices() {
+        let volume = Volume::assemble(vec![axial(5.0, 2.0), axial(0.0, 1.0), axial(10.0, 3.0)]).unwrap();
+        assert_eq!(volume.dimensions, [2, 2, 3]);
+        assert_eq!(volume.spacing, [0.7, 0.5, 5.0]);
+        assert_eq!(volume.voxels, [[1.0; 4], [2.0; 4], [3.0; 4]].concat());
+        assert_eq!(volume.affine, [
+            [-0.7, 0.0, 0.0, 10.0],
+            [0.0, -0.5, 0.0, 20.0],
+            [0.0, 0.0, 5.0, 0.0],
+        ]);
+
+        // One slice takes its thickness from the object
+        let single = Volume::assemble(vec![axial(0.0, 1.0)]).unwrap();
+        assert_eq!(single.spacing[2], 3.0);
+
+        assert!(Volume::assemble(vec![axial(0.0, 1.0), axial(1.0, 1.0), axial(3.0, 1.0)]).is_err(), "uneven spacing");
+        assert!(Volume::assemble(vec![axial(0.0, 1.0), axial(0.0, 2.0)]).is_err(), "shared position");
+    }
+
+    #[test]
+    fn test_nifti_header() {
+        let volume = Volume::assemble(vec![axial(0.0, 1.0), axial(2.0, -1000.0)]).unwrap();
+        let nifti = volume.to_nifti();
+        assert_eq!(nifti.len(), VOXEL_OFFSET + 8 * 4);
+
+        let i16_at = |offset: usize| i16::from_le_bytes([nifti[offset], nifti[offset + 1]]);
+        let f32_at = |offset: usize| f32::from_le_bytes(nifti[offset..offset + 4].try_into().unwrap());
+        assert_eq!(i32::from_le_bytes(nifti[0..4].try_into().unwrap()), 348);
+        assert_eq!((0..5).map(|i| i16_at(40 + 2 * i)).collect::<Vec<_>>(), vec![3, 2, 2, 2, 1]);
+        assert_eq!((i16_at(70), i16_at(72)), (DT_FLOAT32, 32));
+        assert_eq!(f32_at(108), 352.0);
+        assert_eq!(&nifti[344..348], b"n+1\0");
+        assert_eq!(f32_at(VOXEL_OFFSET + 4 * 4), -1000.0);
+
+        // Axial LPS is a 180 degree turn about z in RAS: quaternion (0, 0, 0, 1)
+        assert_eq!((f32_at(256), f32_at(260), f32_at(264)), (0.0, 0.0, 1.0));
+        assert_eq!((f32_at(268), f32_at(272), f32_at(276)), (10.0, 20.0, 0.0));
+        assert_eq!(f32_at(76), 1.0);
+        assert_eq!(f32_at(280), -0.7);
+    }
+}
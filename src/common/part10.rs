@@ -0,0 +1,162 @@
+//! DICOM Part 10 files for received objects
+//!
+//! A C-STORE carries only the data set, encoded in the transfer syntax
+//! negotiated for its presentation context. Written out as is, hardly any
+//! viewer opens it. A Part 10 file (PS3.10 7.1) puts a 128-byte preamble, the
+//! `DICM` prefix and the File Meta Information group in front; the group is
+//! always Explicit VR Little Endian and names the SOP class, instance and
+//! transfer syntax of the data set, which follows byte for byte as received.
+
+pub const PREAMBLE_LENGTH: usize = 128;
+pub const PREFIX: &[u8; 4] = b"DICM";
+/// Implementation Class UID of rust-dicom (UUID-derived, PS3.5 B.2)
+pub const IMPLEMENTATION_CLASS_UID: &str = "2.25.136292896638527124174498216263839314459";
+pub const IMPLEMENTATION_VERSION_NAME: &str = concat!("RUST_DICOM_", env!("CARGO_PKG_VERSION"));
+
+/// What the File Meta Information of a received object records
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileMeta<'a> {
+    pub media_storage_sop_class_uid: &'a str,
+    pub media_storage_sop_instance_uid: &'a str,
+    pub transfer_syntax_uid: &'a str,
+    /// Calling AE title of the association the object arrived on
+    pub source_ae_title: Option<&'a str>,
+}
+
+impl FileMeta<'_> {
+    /// Preamble, prefix and File Meta Information group
+    pub fn encode(&self) -> Vec<u8> {
+        let mut group = Vec::new();
+        // File Meta Information Version 00\01
+        put_element(&mut group, 0x0001, b"OB", &[0x00, 0x01]);
+        put_element(&mut group, 0x0002, b"UI", &uid_value(self.media_storage_sop_class_uid));
+        put_element(&mut group, 0x0003, b"UI", &uid_value(self.media_storage_sop_instance_uid));
+        put_element(&mut group, 0x0010, b"UI", &uid_value(self.transfer_syntax_uid));
+        put_element(&mut group, 0x0012, b"UI", &uid_value(IMPLEMENTATION_CLASS_UID));
+        put_element(&mut group, 0x0013, b"SH", &text_value(IMPLEMENTATION_VERSION_NAME));
+        if let Some(ae_title) = self.source_ae_title.map(str::trim).filter(|ae| !ae.is_empty()) {
+            put_element(&mut group, 0x0016, b"AE", &text_value(ae_title));
+        }
+
+        let mut encoded = vec![0; PREAMBLE_LENGTH];
+        encoded.extend_from_slice(PREFIX);
+        put_element(&mut encoded, 0x0000, b"UL", &(group.len() as u32).to_le_bytes());
+        encoded.extend(group);
+        encoded
+    }
+}
+
+/// A received data set as a Part 10 file
+pub fn part10_file(meta: &FileMeta, dataset: &[u8]) -> Vec<u8> {
+    let mut file = meta.encode();
+    file.extend_from_slice(dataset);
+    file
+}
+
+pub fn is_part10(bytes: &[u8]) -> bool {
+    bytes.get(PREAMBLE_LENGTH..PREAMBLE_LENGTH + PREFIX.len()) == Some(PREFIX.as_slice())
+}
+
+/// Where the data set of a Part 10 file starts, from the File Meta
+/// Information Group Length its meta group begins with
+pub fn dataset_offset(file: &[u8]) -> Option<usize> {
+    let start = PREAMBLE_LENGTH + PREFIX.len();
+    let header = file.get(start..start + 12).filter(|_| is_part10(file))?;
+    if header[..6] != [0x02, 0x00, 0x00, 0x00, b'U', b'L'] {
+        return None;
+    }
+    let group_length = u32::from_le_bytes(header[8..12].try_into().ok()?) as usize;
+    Some(start + 12 + group_length)
+}
+
+/// Explicit VR Little Endian element of group 0002
+fn put_element(buffer: &mut Vec<u8>, element: u16, vr: &[u8; 2], value: &[u8]) {
+    buffer.extend_from_slice(&0x0002u16.to_le_bytes());
+    buffer.extend_from_slice(&element.to_le_bytes());
+    buffer.extend_from_slice(vr);
+    if vr == b"OB" {
+        // OB has a reserved field and a 32-bit length
+        buffer.extend_from_slice(&[0, 0]);
+        buffer.extend_from_slice(&(value.len() as u32).to_le_bytes());
+    } else {
+        buffer.extend_from_slice(&(value.len() as u16).to_le_bytes());
+    }
+    buffer.extend_from_slice(value);
+}
+
+/// UIDs are padded to even length with a NUL
+fn uid_value(uid: &str) -> Vec<u8> {
+    let mut value = uid.trim_end_matches('\0').trim().as_bytes().to_vec();
+    if value.len() % 2 == 1 {
+        value.push(0);
+    }
+    value
+}
+
+/// Text values are padded to even length with a space
+fn text_value(text: &str) -> Vec<u8> {
+    let mut value = text.as_bytes().to_vec();
+    if value.len() % 2 == 1 {
+        value.push(b' ');
+    }
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// (element, VR, value) of each element of an encoded group 0002
+    fn elements(mut bytes: &[u8]) -> Vec<(u16, String, Vec<u8>)> {
+        let mut elements = Vec::new();
+        while !bytes.is_empty() {
+            assert_eq!(&bytes[0..2], &[0x02, 0x00]);
+            let element = u16::from_le_bytes([bytes[2], bytes[3]]);
+            let vr = String::from_utf8(bytes[4..6].to_vec()).unwrap();
+            let (length, header) = if vr == "OB" {
+                (u32::from_le_bytes(bytes[8..12].try_into().unwrap()) as usize, 12)
+            } else {
+                (u16::from_le_bytes([bytes[6], bytes[7]]) as usize, 8)
+            };
+            elements.push((element, vr, bytes[header..header + length].to_vec()));
+            bytes = &bytes[header + length..];
+        }
+        elements
+    }
+
+    #[test]
+    fn test_file_meta_information() {
+        let meta = FileMeta {
+            media_storage_sop_class_uid: "1.2.840.10008.5.1.4.1.1.2",
+            media_storage_sop_instance_uid: "1.2.3.4\0",
+            transfer_syntax_uid: "1.2.840.10008.1.2.1",
+            source_ae_title: Some("MODALITY "),
+        };
+        let dataset = [0x08, 0x00, 0x18, 0x00, b'U', b'I', 0x02, 0x00, b'1', 0x00];
+        let file = part10_file(&meta, &dataset);
+
+        assert!(is_part10(&file));
+        assert!(file[..PREAMBLE_LENGTH].iter().all(|&b| b == 0));
+        assert!(file.ends_with(&dataset));
+
+        let meta_start = PREAMBLE_LENGTH + PREFIX.len();
+        let group = elements(&file[meta_start..file.len() - dataset.len()]);
+        assert_eq!(group[0].0, 0x0000);
+        let group_length = u32::from_le_bytes(group[0].2.clone().try_into().unwrap()) as usize;
+        assert_eq!(meta_start + 12 + group_length, file.len() - dataset.len());
+
+        let value = |element| group.iter().find(|(e, _, _)| *e == element).map(|(_, vr, v)| (vr.as_str(), v.as_slice()));
+        assert_eq!(value(0x0001), Some(("OB", [0x00, 0x01].as_slice())));
+        assert_eq!(value(0x0002), Some(("UI", b"1.2.840.10008.5.1.4.1.1.2\0".as_slice())));
+        assert_eq!(value(0x0003), Some(("UI", b"1.2.3.4\0".as_slice())));
+        assert_eq!(value(0x0010), Some(("UI", b"1.2.840.10008.1.2.1\0".as_slice())));
+        assert_eq!(value(0x0016), Some(("AE", b"MODALITY".as_slice())));
+        assert!(group.iter().all(|(_, _, v)| v.len() % 2 == 0));
+
+        let anonymous = FileMeta { source_ae_title: None, ..meta };
+        assert!(elements(&anonymous.encode()[meta_start..]).iter().all(|(e, _, _)| *e != 0x0016));
+        assert!(!is_part10(&dataset));
+        assert_eq!(dataset_offset(&file), Some(file.len() - dataset.len()));
+        assert_eq!(dataset_offset(&dataset), None);
+    }
+}
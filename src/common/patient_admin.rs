@@ -0,0 +1,177 @@
+//! Patient identity corrections
+//!
+//! Correcting a Medical Record Number, or merging two patients after a
+//! duplicate registration, is routine PACS administration. A correction
+//! changes the patient attributes of every instance of one Patient ID in the
+//! receive index and records the change in the index's audit trail; stored
+//! files can be rewritten as well, each keeping its previous values in an
+//! Original Attributes Sequence item with reason CORRECT.
+
+use anyhow::{Context, Result};
+use dicom_core::dictionary::{DataDictionary, DataDictionaryEntry};
+use dicom_core::value::PrimitiveValue;
+use dicom_core::{DataElement, VR};
+use dicom_dictionary_std::StandardDataDictionary;
+use dicom_object::InMemDicomObject;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use super::charset::shadow_original_attributes;
+use super::encryption::{is_encrypted, StorageKey};
+use super::receive_index::IndexedInstance;
+use super::recovery::write_atomically;
+
+/// Attributes that identify a patient, taken over from the surviving patient in a merge
+pub const PATIENT_IDENTITY: [&str; 5] = ["PatientID", "IssuerOfPatientID", "PatientName", "PatientBirthDate", "PatientSex"];
+
+/// Length of the Part 10 preamble before the DICM prefix
+const PREAMBLE_LENGTH: usize = 128;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CorrectionKind {
+    /// A new Patient ID, and possibly issuer, for the same patient
+    UpdateMrn,
+    /// The patient's instances move to another, surviving patient
+    Merge,
+}
+
+impl CorrectionKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CorrectionKind::UpdateMrn => "update-mrn",
+            CorrectionKind::Merge => "merge",
+        }
+    }
+}
+
+/// New patient attribute values for every instance of one Patient ID
+#[derive(Debug, Clone, PartialEq)]
+pub struct PatientCorrection {
+    pub kind: CorrectionKind,
+    /// Patient ID of the instances to correct
+    pub patient_id: String,
+    /// New values by attribute keyword
+    pub changes: BTreeMap<String, String>,
+    pub reason: String,
+}
+
+impl PatientCorrection {
+    /// Give the instances of `patient_id` the Patient ID `new_patient_id`, and the issuer if given
+    pub fn update_mrn(patient_id: &str, new_patient_id: &str, issuer: Option<&str>, reason: &str) -> Result<Self> {
+        if new_patient_id.trim().is_empty() || new_patient_id.trim() == patient_id {
+            anyhow::bail!("The new Patient ID must differ from {:?} and not be empty", patient_id);
+        }
+        let mut changes = BTreeMap::from([("PatientID".to_string(), new_patient_id.trim().to_string())]);
+        if let Some(issuer) = issuer {
+            changes.insert("IssuerOfPatientID".to_string(), issuer.trim().to_string());
+        }
+        Ok(Self { kind: CorrectionKind::UpdateMrn, patient_id: patient_id.to_string(), changes, reason: reason.to_string() })
+    }
+
+    /// Move the instances of `patient_id` to the patient of `survivor`, an
+    /// instance of the patient being kept, taking over its identity
+    pub fn merge(patient_id: &str, survivor: &IndexedInstance, reason: &str) -> Result<Self> {
+        let changes: BTreeMap<String, String> = PATIENT_IDENTITY.iter()
+            .map(|keyword| (keyword.to_string(), survivor.attributes.get(*keyword).cloned().unwrap_or_default()))
+            .collect();
+        if changes["PatientID"].is_empty() || changes["PatientID"] == patient_id {
+            anyhow::bail!("Cannot merge patient {:?} into itself", patient_id);
+        }
+        Ok(Self { kind: CorrectionKind::Merge, patient_id: patient_id.to_string(), changes, reason: reason.to_string() })
+    }
+}
+
+/// Set `changes` on `obj`, keeping the values they replace in an Original
+/// Attributes Sequence item; returns how many attributes actually changed
+pub fn coerce(obj: &mut InMemDicomObject, changes: &BTreeMap<String, String>) -> Result<usize> {
+    coerce_with_reason(obj, changes, "CORRECT")
+}
+
+/// `coerce`, recording `reason` ("COERCE" or "CORRECT") for the change
+pub fn coerce_with_reason(obj: &mut InMemDicomObject, changes: &BTreeMap<String, String>, reason: &str) -> Result<usize> {
+    let mut previous = InMemDicomObject::new_empty();
+    let mut changed = 0;
+    for (keyword, value) in changes {
+        let tag = StandardDataDictionary.parse_tag(keyword)
+            .ok_or_else(|| anyhow::anyhow!("Unknown attribute {}", keyword))?;
+        let vr = StandardDataDictionary.by_tag(tag).map(|entry| entry.vr().relaxed()).unwrap_or(VR::LO);
+        let current = obj.element(tag).ok().cloned();
+        let current_value = current.as_ref()
+            .and_then(|e| e.to_str().ok())
+            .map(|s| s.trim_end_matches(['\0', ' ']).to_string())
+            .unwrap_or_default();
+        if current_value == *value {
+            continue;
+        }
+        previous.put(current.unwrap_or_else(|| DataElement::new(tag, vr, PrimitiveValue::Empty)));
+        let value = if value.is_empty() { PrimitiveValue::Empty } else { PrimitiveValue::from(value.as_str()) };
+        obj.put(DataElement::new(tag, vr, value));
+        changed += 1;
+    }
+    if changed > 0 {
+        shadow_original_attributes(obj, previous, reason);
+    }
+    Ok(changed)
+}
+
+/// Apply `changes` to the stored file at `path` in place, decrypting and
+/// sealing it again with `key` if it is encrypted; returns whether it changed
+pub fn rewrite_stored_object(path: &Path, changes: &BTreeMap<String, String>, key: Option<&StorageKey>) -> Result<bool> {
+    let raw = std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let encrypted = is_encrypted(&raw);
+    let bytes = match (encrypted, key) {
+        (true, Some(key)) => key.open(&raw)?,
+        (true, None) => anyhow::bail!("{} is encrypted; give the storage key to rewrite it", path.display()),
+        (false, _) => raw,
+    };
+    if bytes.len() < PREAMBLE_LENGTH + 4 || &bytes[PREAMBLE_LENGTH..PREAMBLE_LENGTH + 4] != b"DICM" {
+        anyhow::bail!("{} is not a DICOM Part 10 file", path.display());
+    }
+    let mut obj = dicom_object::from_reader(&bytes[PREAMBLE_LENGTH..])
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+    if coerce(&mut obj, changes)? == 0 {
+        return Ok(false);
+    }
+
+    let mut rewritten = Vec::with_capacity(bytes.len());
+    obj.write_all(&mut rewritten).with_context(|| format!("Failed to encode {}", path.display()))?;
+    let rewritten = match (encrypted, key) {
+        (true, Some(key)) => key.seal(&rewritten)?,
+        _ => rewritten,
+    };
+    write_atomically(path, &rewritten)?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dicom_core::Tag;
+
+    const PATIENT_ID: Tag = Tag(0x0010, 0x0020);
+    const ORIGINAL_ATTRIBUTES_SEQUENCE: Tag = Tag(0x0400, 0x0561);
+    const MODIFIED_ATTRIBUTES_SEQUENCE: Tag = Tag(0x0400, 0x0550);
+
+    #[test]
+    fn test_coerce_keeps_previous_values() {
+        let mut obj = InMemDicomObject::from_element_iter([
+            DataElement::new(PATIENT_ID, VR::LO, PrimitiveValue::from("OLD-1")),
+            DataElement::new(Tag(0x0010, 0x0010), VR::PN, PrimitiveValue::from("Doe^Jane")),
+        ]);
+        let correction = PatientCorrection::update_mrn("OLD-1", "NEW-1", Some("HOSP"), "typo at registration").unwrap();
+        assert_eq!(coerce(&mut obj, &correction.changes).unwrap(), 2);
+        assert_eq!(obj.element(PATIENT_ID).unwrap().to_str().unwrap(), "NEW-1");
+        assert_eq!(obj.element_by_name("IssuerOfPatientID").unwrap().to_str().unwrap(), "HOSP");
+
+        let shadow = &obj.element(ORIGINAL_ATTRIBUTES_SEQUENCE).unwrap().items().unwrap()[0];
+        let previous = &shadow.element(MODIFIED_ATTRIBUTES_SEQUENCE).unwrap().items().unwrap()[0];
+        assert_eq!(previous.element(PATIENT_ID).unwrap().to_str().unwrap(), "OLD-1");
+        assert_eq!(shadow.element_by_name("ReasonForTheAttributeModification").unwrap().to_str().unwrap(), "CORRECT");
+
+        // Applying it again changes nothing and adds no item
+        assert_eq!(coerce(&mut obj, &correction.changes).unwrap(), 0);
+        assert_eq!(obj.element(ORIGINAL_ATTRIBUTES_SEQUENCE).unwrap().items().unwrap().len(), 1);
+
+        assert!(PatientCorrection::update_mrn("OLD-1", "OLD-1", None, "").is_err());
+    }
+}
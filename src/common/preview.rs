@@ -0,0 +1,384 @@
+//! Reduced-resolution previews of images
+//!
+//! Preview destinations such as referring-physician portals need a quick look
+//! at an image, not the full-fidelity original. A preview is a derivative:
+//! each frame is mapped to 8-bit display values (through the modality rescale
+//! and the first VOI window, or the frame's own range without one), shrunk by
+//! an integer factor with a box filter until neither side exceeds the maximum
+//! dimension, and encoded as JPEG Baseline. The derivative gets a new SOP
+//! Instance UID, is marked DERIVED and lossy, describes how it was made in
+//! Derivation Description and references the original in Source Image Sequence.
+//! Only native (uncompressed) pixel data can be previewed. Corner annotations,
+//! if any, are burned into every frame after down-sampling.
+
+use anyhow::{Context, Result};
+use dicom_core::value::{DataSetSequence, PixelFragmentSequence, PrimitiveValue, Value};
+use dicom_core::{DataElement, Tag, VR};
+use dicom_object::meta::FileMetaTableBuilder;
+use dicom_object::{FileDicomObject, InMemDicomObject};
+use jpeg_encoder::{ColorType, Encoder, SamplingFactor};
+
+use super::burn_in::{burn_in, Annotation};
+use super::uids::ts;
+
+const SOP_CLASS_UID: Tag = Tag(0x0008, 0x0016);
+const SOP_INSTANCE_UID: Tag = Tag(0x0008, 0x0018);
+const IMAGE_TYPE: Tag = Tag(0x0008, 0x0008);
+const DERIVATION_DESCRIPTION: Tag = Tag(0x0008, 0x2111);
+const SOURCE_IMAGE_SEQUENCE: Tag = Tag(0x0008, 0x2112);
+const REFERENCED_SOP_CLASS_UID: Tag = Tag(0x0008, 0x1150);
+const REFERENCED_SOP_INSTANCE_UID: Tag = Tag(0x0008, 0x1155);
+const SAMPLES_PER_PIXEL: Tag = Tag(0x0028, 0x0002);
+const PHOTOMETRIC_INTERPRETATION: Tag = Tag(0x0028, 0x0004);
+const PLANAR_CONFIGURATION: Tag = Tag(0x0028, 0x0006);
+const NUMBER_OF_FRAMES: Tag = Tag(0x0028, 0x0008);
+const ROWS: Tag = Tag(0x0028, 0x0010);
+const COLUMNS: Tag = Tag(0x0028, 0x0011);
+const BITS_ALLOCATED: Tag = Tag(0x0028, 0x0100);
+const BITS_STORED: Tag = Tag(0x0028, 0x0101);
+const HIGH_BIT: Tag = Tag(0x0028, 0x0102);
+const PIXEL_REPRESENTATION: Tag = Tag(0x0028, 0x0103);
+const WINDOW_CENTER: Tag = Tag(0x0028, 0x1050);
+const WINDOW_WIDTH: Tag = Tag(0x0028, 0x1051);
+const RESCALE_INTERCEPT: Tag = Tag(0x0028, 0x1052);
+const RESCALE_SLOPE: Tag = Tag(0x0028, 0x1053);
+const BURNED_IN_ANNOTATION: Tag = Tag(0x0028, 0x0301);
+const LOSSY_IMAGE_COMPRESSION: Tag = Tag(0x0028, 0x2110);
+const LOSSY_IMAGE_COMPRESSION_RATIO: Tag = Tag(0x0028, 0x2112);
+const LOSSY_IMAGE_COMPRESSION_METHOD: Tag = Tag(0x0028, 0x2114);
+const PIXEL_DATA: Tag = Tag(0x7FE0, 0x0010);
+
+/// Spacing attributes scaled with the image
+const SPACINGS: [Tag; 2] = [Tag(0x0028, 0x0030), Tag(0x0018, 0x1164)];
+/// Attributes that no longer describe the 8-bit display values of a preview
+const STALE_PIXEL_ATTRIBUTES: [Tag; 8] = [
+    Tag(0x0028, 0x0106), // Smallest Image Pixel Value
+    Tag(0x0028, 0x0107), // Largest Image Pixel Value
+    Tag(0x0028, 0x1054), // Rescale Type
+    Tag(0x0028, 0x1055), // Window Center & Width Explanation
+    Tag(0x0028, 0x3000), // Modality LUT Sequence
+    Tag(0x0028, 0x3010), // VOI LUT Sequence
+    WINDOW_CENTER,
+    WINDOW_WIDTH,
+];
+
+/// Size, quality and annotations of the previews
+#[derive(Debug, Clone, PartialEq)]
+pub struct PreviewOptions {
+    /// Largest number of rows or columns of a preview
+    pub max_dimension: u16,
+    /// JPEG quality, 1 to 100
+    pub quality: u8,
+    /// Text burned into the corners
+    pub annotations: Vec<Annotation>,
+}
+
+impl Default for PreviewOptions {
+    fn default() -> Self {
+        Self { max_dimension: 512, quality: 75, annotations: Vec::new() }
+    }
+}
+
+/// Layout of native pixel data
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PixelLayout {
+    pub rows: u16,
+    pub columns: u16,
+    pub samples_per_pixel: u16,
+    pub bits_allocated: u16,
+    pub bits_stored: u16,
+    /// Two's complement samples
+    pub signed: bool,
+    /// Colour planes one after the other rather than interleaved
+    pub planar: bool,
+}
+
+impl PixelLayout {
+    pub fn frame_length(&self) -> usize {
+        self.rows as usize * self.columns as usize * self.samples_per_pixel as usize * (self.bits_allocated as usize / 8)
+    }
+
+    /// Sample value `index` of `frame`, little endian, sign-extended from Bits Stored
+    pub fn sample(&self, frame: &[u8], index: usize) -> i32 {
+        let raw = match self.bits_allocated {
+            8 => frame[index] as u32,
+            _ => u16::from_le_bytes([frame[2 * index], frame[2 * index + 1]]) as u32,
+        };
+        let bits = self.bits_stored.clamp(1, self.bits_allocated) as u32;
+        let value = raw & ((1u32 << bits) - 1);
+        if self.signed && value & (1 << (bits - 1)) != 0 {
+            value as i32 - (1i32 << bits)
+        } else {
+            value as i32
+        }
+    }
+}
+
+/// How stored monochrome values become display values
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DisplayMapping {
+    pub rescale_slope: f64,
+    pub rescale_intercept: f64,
+    /// VOI window center and width; without one the frame's range is stretched
+    pub window: Option<(f64, f64)>,
+}
+
+/// Native pixel data of an object with what is needed to display it
+#[derive(Debug, Clone, PartialEq)]
+pub struct NativePixels {
+    pub layout: PixelLayout,
+    pub mapping: DisplayMapping,
+    pub photometric: String,
+    pub frames: usize,
+    /// All frames, one after the other
+    pub data: Vec<u8>,
+}
+
+impl NativePixels {
+    /// The pixel data of `obj`, or `None` for objects without any; fails for
+    /// encapsulated pixel data and for less data than the frames need
+    pub fn read(obj: &FileDicomObject<InMemDicomObject>) -> Result<Option<Self>> {
+        let Ok(pixel_data) = obj.element(PIXEL_DATA) else { return Ok(None) };
+        if matches!(pixel_data.value(), Value::PixelSequence(_)) {
+            anyhow::bail!("pixel data is encapsulated in {}; only native pixel data can be read", obj.meta().transfer_syntax().trim_end_matches('\0'));
+        }
+
+        let int = |tag: Tag| obj.element(tag).ok().and_then(|e| e.to_int::<u16>().ok());
+        let float = |tag: Tag| obj.element(tag).ok().and_then(|e| e.to_multi_float64().ok()).and_then(|values| values.first().copied());
+        let text = |tag: Tag| obj.element(tag).ok().and_then(|e| e.to_str().ok()).map(|s| s.trim().to_string()).unwrap_or_default();
+
+        let bits_allocated = int(BITS_ALLOCATED).unwrap_or(8);
+        let layout = PixelLayout {
+            rows: int(ROWS).context("no Rows")?,
+            columns: int(COLUMNS).context("no Columns")?,
+            samples_per_pixel: int(SAMPLES_PER_PIXEL).unwrap_or(1),
+            bits_allocated,
+            bits_stored: int(BITS_STORED).unwrap_or(bits_allocated),
+            signed: int(PIXEL_REPRESENTATION) == Some(1),
+            planar: int(PLANAR_CONFIGURATION) == Some(1),
+        };
+        if !matches!(layout.bits_allocated, 8 | 16) {
+            anyhow::bail!("cannot read pixel data of {} bits allocated", layout.bits_allocated);
+        }
+        let frames = text(NUMBER_OF_FRAMES).parse::<usize>().unwrap_or(1).max(1);
+        let data = pixel_data.to_bytes().context("unreadable pixel data")?.into_owned();
+        if data.len() < frames * layout.frame_length() {
+            anyhow::bail!("pixel data holds {} bytes, {} frames of {}x{} need {}",
+                          data.len(), frames, layout.columns, layout.rows, frames * layout.frame_length());
+        }
+
+        let mapping = DisplayMapping {
+            rescale_slope: float(RESCALE_SLOPE).unwrap_or(1.0),
+            rescale_intercept: float(RESCALE_INTERCEPT).unwrap_or(0.0),
+            window: float(WINDOW_CENTER).zip(float(WINDOW_WIDTH)),
+        };
+        Ok(Some(Self { layout, mapping, photometric: text(PHOTOMETRIC_INTERPRETATION), frames, data }))
+    }
+
+    /// Each frame's stored values
+    pub fn frames(&self) -> impl Iterator<Item = &[u8]> {
+        self.data.chunks_exact(self.layout.frame_length()).take(self.frames)
+    }
+}
+
+/// 8-bit display values of one frame, interleaved for colour images
+pub fn display_values(frame: &[u8], layout: &PixelLayout, mapping: &DisplayMapping) -> Vec<u8> {
+    let pixels = layout.rows as usize * layout.columns as usize;
+    if layout.samples_per_pixel == 3 {
+        let value = |pixel: usize, plane: usize| {
+            let index = if layout.planar { plane * pixels + pixel } else { pixel * 3 + plane };
+            (layout.sample(frame, index) >> (layout.bits_stored.clamp(8, 16) - 8)).clamp(0, 255) as u8
+        };
+        return (0..pixels).flat_map(|pixel| (0..3).map(move |plane| value(pixel, plane))).collect();
+    }
+
+    let modality: Vec<f64> = (0..pixels)
+        .map(|index| layout.sample(frame, index) as f64 * mapping.rescale_slope + mapping.rescale_intercept)
+        .collect();
+    let (low, high) = match mapping.window {
+        // Linear VOI function of PS3.3 C.11.2.1.2.1
+        Some((center, width)) if width >= 1.0 => (center - 0.5 - (width - 1.0) / 2.0, center - 0.5 + (width - 1.0) / 2.0),
+        _ => modality.iter().fold((f64::MAX, f64::MIN), |(low, high), &value| (low.min(value), high.max(value))),
+    };
+    let range = (high - low).max(f64::EPSILON);
+    modality.iter().map(|&value| ((value - low) / range * 255.0).round().clamp(0.0, 255.0) as u8).collect()
+}
+
+/// Smallest integer factor that brings both sides down to `max_dimension`
+pub fn reduction_factor(rows: u16, columns: u16, max_dimension: u16) -> u16 {
+    let largest = rows.max(columns) as u32;
+    let max_dimension = max_dimension.max(1) as u32;
+    largest.div_ceil(max_dimension).max(1) as u16
+}
+
+/// Shrink interleaved 8-bit pixels by `factor`, each output pixel the average
+/// of the block it covers; returns the pixels, rows and columns
+pub fn downsample(pixels: &[u8], rows: u16, columns: u16, samples_per_pixel: u16, factor: u16) -> (Vec<u8>, u16, u16) {
+    let (rows, columns, samples, factor) = (rows as usize, columns as usize, samples_per_pixel as usize, factor.max(1) as usize);
+    let (out_rows, out_columns) = (rows.div_ceil(factor), columns.div_ceil(factor));
+    let mut out = Vec::with_capacity(out_rows * out_columns * samples);
+    for out_row in 0..out_rows {
+        let row_range = out_row * factor..((out_row + 1) * factor).min(rows);
+        for out_column in 0..out_columns {
+            let column_range = out_column * factor..((out_column + 1) * factor).min(columns);
+            let count = (row_range.len() * column_range.len()) as u32;
+            for sample in 0..samples {
+                let sum: u32 = row_range.clone()
+                    .flat_map(|row| column_range.clone().map(move |column| (row * columns + column) * samples + sample))
+                    .map(|index| pixels[index] as u32)
+                    .sum();
+                out.push(((sum + count / 2) / count) as u8);
+            }
+        }
+    }
+    (out, out_rows as u16, out_columns as u16)
+}
+
+/// JPEG Baseline stream of interleaved 8-bit pixels, padded to even length
+/// as a Pixel Data fragment must be
+pub fn encode_jpeg(pixels: &[u8], rows: u16, columns: u16, samples_per_pixel: u16, quality: u8) -> Result<Vec<u8>> {
+    let mut jpeg = Vec::new();
+    let mut encoder = Encoder::new(&mut jpeg, quality.clamp(1, 100));
+    let color = if samples_per_pixel == 3 {
+        // YBR_FULL_422
+        encoder.set_sampling_factor(SamplingFactor::F_2_1);
+        ColorType::Rgb
+    } else {
+        ColorType::Luma
+    };
+    encoder.encode(pixels, columns, rows, color).context("JPEG encoding failed")?;
+    if jpeg.len() % 2 == 1 {
+        jpeg.push(0);
+    }
+    Ok(jpeg)
+}
+
+/// Preview of `obj` as a new instance in JPEG Baseline, or `None` for objects
+/// without pixel data
+pub fn preview_derivative(obj: &FileDicomObject<InMemDicomObject>, options: &PreviewOptions) -> Result<Option<FileDicomObject<InMemDicomObject>>> {
+    let Some(native) = NativePixels::read(obj)? else { return Ok(None) };
+    let (layout, mapping, frames) = (native.layout, native.mapping, native.frames);
+    let photometric = native.photometric.as_str();
+    let supported = match layout.samples_per_pixel {
+        1 => photometric.starts_with("MONOCHROME"),
+        3 => photometric == "RGB",
+        _ => false,
+    };
+    if !supported {
+        anyhow::bail!("cannot preview {} pixel data with {} samples", photometric, layout.samples_per_pixel);
+    }
+
+    let text = |tag: Tag| obj.element(tag).ok().and_then(|e| e.to_str().ok()).map(|s| s.trim().to_string()).unwrap_or_default();
+    let annotations: Vec<_> = options.annotations.iter()
+        .map(|annotation| (annotation.corner, annotation.expand(|(group, element)| {
+            obj.element(Tag(group, element)).ok().and_then(|e| e.to_str().ok()).map(|value| value.to_string())
+        })))
+        .filter(|(_, text)| !text.is_empty())
+        .collect();
+    // Text is shown white, which MONOCHROME1 stores as the lowest value
+    let foreground = if photometric == "MONOCHROME1" { 0 } else { 255 };
+
+    let factor = reduction_factor(layout.rows, layout.columns, options.max_dimension);
+    let mut fragments = Vec::with_capacity(frames);
+    let (mut rows, mut columns) = (layout.rows, layout.columns);
+    for frame in native.frames() {
+        let display = display_values(frame, &layout, &mapping);
+        let (mut pixels, out_rows, out_columns) = downsample(&display, layout.rows, layout.columns, layout.samples_per_pixel, factor);
+        burn_in(&mut pixels, out_rows, out_columns, layout.samples_per_pixel, &annotations, foreground);
+        fragments.push(encode_jpeg(&pixels, out_rows, out_columns, layout.samples_per_pixel, options.quality)?);
+        (rows, columns) = (out_rows, out_columns);
+    }
+    let compressed: usize = fragments.iter().map(Vec::len).sum();
+    let ratio = (frames * layout.frame_length()) as f64 / compressed.max(1) as f64;
+
+    let sop_class_uid = text(SOP_CLASS_UID);
+    let original_uid = text(SOP_INSTANCE_UID);
+    let sop_instance_uid = format!("2.25.{}", uuid::Uuid::new_v4().as_u128());
+    let description = format!("Preview of {}: {}x{} down-sampled by {} to {}x{}, JPEG Baseline quality {}",
+                              original_uid, layout.columns, layout.rows, factor, columns, rows, options.quality);
+
+    let mut preview = obj.clone().into_inner();
+    for tag in STALE_PIXEL_ATTRIBUTES.into_iter().chain([RESCALE_INTERCEPT, RESCALE_SLOPE, PLANAR_CONFIGURATION]) {
+        preview.remove_element(tag);
+    }
+    if layout.samples_per_pixel == 3 {
+        preview.put(DataElement::new(PLANAR_CONFIGURATION, VR::US, PrimitiveValue::from(0u16)));
+        preview.put(DataElement::new(PHOTOMETRIC_INTERPRETATION, VR::CS, PrimitiveValue::from("YBR_FULL_422")));
+    }
+    let spacings: Vec<(Tag, Vec<f64>)> = SPACINGS.iter()
+        .filter_map(|&tag| obj.element(tag).ok().and_then(|e| e.to_multi_float64().ok()).map(|values| (tag, values)))
+        .collect();
+    for (tag, values) in spacings {
+        let scaled = values.iter().map(|value| format!("{:.6}", value * factor as f64));
+        preview.put(DataElement::new(tag, VR::DS, PrimitiveValue::Strs(scaled.collect())));
+    }
+
+    let mut image_type = obj.element(IMAGE_TYPE).ok().and_then(|e| e.to_multi_str().ok().map(|values| values.to_vec())).unwrap_or_default();
+    image_type.resize(image_type.len().max(2), String::new());
+    image_type[0] = "DERIVED".to_string();
+    image_type[1] = "SECONDARY".to_string();
+    preview.put(DataElement::new(IMAGE_TYPE, VR::CS, PrimitiveValue::Strs(image_type.into_iter().collect())));
+    preview.put(DataElement::new(SOP_INSTANCE_UID, VR::UI, PrimitiveValue::from(sop_instance_uid.as_str())));
+    preview.put(DataElement::new(DERIVATION_DESCRIPTION, VR::ST, PrimitiveValue::from(description)));
+    preview.put(DataElement::new(SOURCE_IMAGE_SEQUENCE, VR::SQ, DataSetSequence::from(vec![InMemDicomObject::from_element_iter([
+        DataElement::new(REFERENCED_SOP_CLASS_UID, VR::UI, PrimitiveValue::from(sop_class_uid.as_str())),
+        DataElement::new(REFERENCED_SOP_INSTANCE_UID, VR::UI, PrimitiveValue::from(original_uid.as_str())),
+    ])])));
+    preview.put(DataElement::new(ROWS, VR::US, PrimitiveValue::from(rows)));
+    preview.put(DataElement::new(COLUMNS, VR::US, PrimitiveValue::from(columns)));
+    preview.put(DataElement::new(BITS_ALLOCATED, VR::US, PrimitiveValue::from(8u16)));
+    preview.put(DataElement::new(BITS_STORED, VR::US, PrimitiveValue::from(8u16)));
+    preview.put(DataElement::new(HIGH_BIT, VR::US, PrimitiveValue::from(7u16)));
+    preview.put(DataElement::new(PIXEL_REPRESENTATION, VR::US, PrimitiveValue::from(0u16)));
+    if !annotations.is_empty() {
+        preview.put(DataElement::new(BURNED_IN_ANNOTATION, VR::CS, PrimitiveValue::from("YES")));
+    }
+    preview.put(DataElement::new(LOSSY_IMAGE_COMPRESSION, VR::CS, PrimitiveValue::from("01")));
+    preview.put(DataElement::new(LOSSY_IMAGE_COMPRESSION_RATIO, VR::DS, PrimitiveValue::from(format!("{:.2}", ratio))));
+    preview.put(DataElement::new(LOSSY_IMAGE_COMPRESSION_METHOD, VR::CS, PrimitiveValue::from("ISO_10918_1")));
+    preview.put(DataElement::new(PIXEL_DATA, VR::OB, Value::PixelSequence(PixelFragmentSequence::new(Vec::<u32>::new(), fragments))));
+
+    let preview = preview.with_meta(
+        FileMetaTableBuilder::new()
+            .media_storage_sop_class_uid(sop_class_uid.as_str())
+            .media_storage_sop_instance_uid(sop_instance_uid.as_str())
+            .transfer_syntax(ts::JPEG_BASELINE),
+    )?;
+    Ok(Some(preview))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display_values_and_downsampling() {
+        // 2x2 signed 16-bit CT frame: -1000, 0, 40, 2000 HU with slope 1, intercept 0
+        let frame: Vec<u8> = [-1000i16, 0, 40, 2000].iter().flat_map(|v| v.to_le_bytes()).collect();
+        let layout = PixelLayout { rows: 2, columns: 2, samples_per_pixel: 1, bits_allocated: 16, bits_stored: 16, signed: true, planar: false };
+        let window = DisplayMapping { rescale_slope: 1.0, rescale_intercept: 0.0, window: Some((40.0, 400.0)) };
+        assert_eq!(display_values(&frame, &layout, &window), vec![0, 102, 128, 255]);
+        let range = DisplayMapping { window: None, ..window };
+        assert_eq!(display_values(&frame, &layout, &range), vec![0, 85, 88, 255]);
+
+        // Planar RGB becomes interleaved
+        let rgb = [10u8, 20, 30, 40, 50, 60];
+        let layout = PixelLayout { rows: 1, columns: 2, samples_per_pixel: 3, bits_allocated: 8, bits_stored: 8, signed: false, planar: true };
+        assert_eq!(display_values(&rgb, &layout, &window), vec![10, 30, 50, 20, 40, 60]);
+
+        assert_eq!(reduction_factor(512, 512, 512), 1);
+        assert_eq!(reduction_factor(3000, 2500, 512), 6);
+        let pixels = [0u8, 10, 20, 30, 40, 50, 60, 70, 80];
+        assert_eq!(downsample(&pixels, 3, 3, 1, 2), (vec![20, 35, 65, 80], 2, 2));
+    }
+
+    #[test]
+    fn test_encode_jpeg() {
+        let pixels: Vec<u8> = (0..64u32 * 48).map(|i| (i % 64 * 4) as u8).collect();
+        let jpeg = encode_jpeg(&pixels, 48, 64, 1, 75).unwrap();
+        assert_eq!(&jpeg[..2], &[0xFF, 0xD8]);
+        assert_eq!(jpeg.len() % 2, 0);
+        assert!(jpeg.len() < pixels.len());
+        assert!(encode_jpeg(&pixels, 48, 64, 3, 75).is_err());
+    }
+}
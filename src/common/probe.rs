@@ -0,0 +1,247 @@
+/// Capability matrix of a remote SCP
+///
+/// `dicom-sender probe` proposes every storage SOP class with each of a set of
+/// key transfer syntaxes, one presentation context per combination, spread
+/// across as many associations as needed. The result of each context tells
+/// exactly which combinations the remote accepts.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::capabilities::{CapabilitySet, ContextCapability};
+use super::rejection::presentation_context_result_text;
+use super::reports::csv_field;
+use super::transfer_syntaxes::{TransferSyntaxPolicy, TransferSyntaxRegistry};
+use super::uids::ts;
+
+/// Presentation context IDs are odd numbers from 1 to 255
+pub const MAX_CONTEXTS_PER_ASSOCIATION: usize = 128;
+
+/// Transfer syntaxes probed by default: the uncompressed ones and the
+/// compressed ones archives commonly store natively
+pub const PROBE_TRANSFER_SYNTAXES: &[&str] = &[
+    ts::IMPLICIT_VR_LITTLE_ENDIAN,
+    ts::EXPLICIT_VR_LITTLE_ENDIAN,
+    ts::EXPLICIT_VR_BIG_ENDIAN,
+    "1.2.840.10008.1.2.1.99", // Deflated Explicit VR Little Endian
+    ts::JPEG_BASELINE,
+    ts::JPEG_LOSSLESS_SV1,
+    ts::JPEG_LS_LOSSLESS,
+    ts::JPEG_2000_LOSSLESS,
+    ts::JPEG_2000,
+    ts::RLE_LOSSLESS,
+];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProbeResult {
+    pub sop_class_uid: String,
+    pub transfer_syntax_uid: String,
+    /// Presentation context result code; `None` when the peer did not report
+    /// one or the association itself failed
+    pub result: Option<u8>,
+    pub description: String,
+}
+
+impl ProbeResult {
+    /// Result of a probed context; `note` describes a missing result code
+    pub fn new(sop_class_uid: &str, transfer_syntax_uid: &str, result: Option<u8>, note: Option<&str>) -> Self {
+        let description = match (result, note) {
+            (Some(code), _) => presentation_context_result_text(code).to_string(),
+            (None, Some(note)) => note.to_string(),
+            (None, None) => "not accepted".to_string(),
+        };
+        Self {
+            sop_class_uid: sop_class_uid.to_string(),
+            transfer_syntax_uid: transfer_syntax_uid.to_string(),
+            result,
+            description,
+        }
+    }
+
+    pub fn is_accepted(&self) -> bool {
+        self.result == Some(0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CapabilityMatrix {
+    pub called_ae: String,
+    pub host: String,
+    pub port: u16,
+    pub probed_at: DateTime<Utc>,
+    pub associations: usize,
+    pub sop_classes: Vec<String>,
+    pub transfer_syntaxes: Vec<String>,
+    pub results: Vec<ProbeResult>,
+}
+
+impl CapabilityMatrix {
+    pub fn accepted(&self) -> impl Iterator<Item = &ProbeResult> {
+        self.results.iter().filter(|result| result.is_accepted())
+    }
+
+    /// Result for one combination
+    pub fn get(&self, sop_class_uid: &str, transfer_syntax_uid: &str) -> Option<&ProbeResult> {
+        self.results.iter()
+            .find(|result| result.sop_class_uid == sop_class_uid && result.transfer_syntax_uid == transfer_syntax_uid)
+    }
+
+    /// Transfer syntaxes the remote accepted for a SOP class, in probe order
+    pub fn accepted_transfer_syntaxes(&self, sop_class_uid: &str) -> Vec<&str> {
+        self.transfer_syntaxes.iter()
+            .filter(|ts| self.get(sop_class_uid, ts).is_some_and(ProbeResult::is_accepted))
+            .map(String::as_str)
+            .collect()
+    }
+
+    /// Transfer syntax an association would settle on for a SOP class when
+    /// `proposed` is offered and the choice is made under `policy`
+    pub fn best_transfer_syntax<'a>(&self, sop_class_uid: &str, proposed: &'a [impl AsRef<str>], policy: TransferSyntaxPolicy) -> Option<&'a str> {
+        TransferSyntaxRegistry::global().best_common(proposed, &self.accepted_transfer_syntaxes(sop_class_uid), policy)
+    }
+
+    /// SOP classes the remote accepts only in retired transfer syntaxes, one
+    /// warning each: senders that stop proposing those can no longer store them
+    pub fn retired_warnings(&self) -> Vec<String> {
+        let registry = TransferSyntaxRegistry::global();
+        self.sop_classes.iter()
+            .filter_map(|sop_class| {
+                let accepted = self.accepted_transfer_syntaxes(sop_class);
+                (!accepted.is_empty() && accepted.iter().all(|ts| registry.is_retired(ts))).then(|| {
+                    let names: Vec<&str> = accepted.iter().map(|ts| registry.get_name(ts).unwrap_or(ts)).collect();
+                    format!("{} is accepted only in retired transfer syntaxes: {}", sop_class, names.join(", "))
+                })
+            })
+            .collect()
+    }
+
+    /// One row per SOP class, one column per transfer syntax; cells hold the
+    /// result code ("0" = accepted) or the description when there is none
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("sop_class_uid");
+        for ts in &self.transfer_syntaxes {
+            csv.push(',');
+            csv.push_str(&csv_field(ts));
+        }
+        csv.push('\n');
+
+        for sop_class in &self.sop_classes {
+            csv.push_str(&csv_field(sop_class));
+            for ts in &self.transfer_syntaxes {
+                csv.push(',');
+                match self.get(sop_class, ts) {
+                    Some(ProbeResult { result: Some(code), .. }) => csv.push_str(&code.to_string()),
+                    Some(result) => csv.push_str(&csv_field(&result.description)),
+                    None => {}
+                }
+            }
+            csv.push('\n');
+        }
+        csv
+    }
+
+    /// Accepted combinations as a capability set, for `dicom-info negotiate --scp`
+    pub fn to_capability_set(&self) -> CapabilitySet {
+        let contexts = self.sop_classes.iter()
+            .map(|sop_class| ContextCapability {
+                sop_class: sop_class.clone(),
+                transfer_syntaxes: self.accepted_transfer_syntaxes(sop_class).into_iter().map(str::to_string).collect(),
+            })
+            .filter(|context| !context.transfer_syntaxes.is_empty())
+            .collect();
+        CapabilitySet {
+            ae_title: Some(self.called_ae.clone()),
+            contexts,
+        }
+    }
+}
+
+/// Every SOP class paired with every transfer syntax, split into
+/// association-sized batches
+pub fn probe_batches(sop_classes: &[String], transfer_syntaxes: &[String], per_association: usize) -> Vec<Vec<(String, String)>> {
+    let combinations: Vec<(String, String)> = sop_classes.iter()
+        .flat_map(|sop_class| transfer_syntaxes.iter().map(move |ts| (sop_class.clone(), ts.clone())))
+        .collect();
+    combinations
+        .chunks(per_association.clamp(1, MAX_CONTEXTS_PER_ASSOCIATION))
+        .map(<[(String, String)]>::to_vec)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CT: &str = "1.2.840.10008.5.1.4.1.1.2";
+    const MR: &str = "1.2.840.10008.5.1.4.1.1.4";
+    const IMPLICIT_LE: &str = "1.2.840.10008.1.2";
+    const JPEG_2000_LOSSLESS: &str = "1.2.840.10008.1.2.4.90";
+
+    #[test]
+    fn test_probe_batches() {
+        let sop_classes: Vec<String> = (0..100).map(|i| format!("1.2.3.{}", i)).collect();
+        let transfer_syntaxes: Vec<String> = PROBE_TRANSFER_SYNTAXES.iter().map(|ts| ts.to_string()).collect();
+
+        let batches = probe_batches(&sop_classes, &transfer_syntaxes, 500);
+        assert_eq!(batches.len(), 8);
+        assert!(batches.iter().all(|batch| batch.len() <= MAX_CONTEXTS_PER_ASSOCIATION));
+        assert_eq!(batches.iter().map(Vec::len).sum::<usize>(), 1000);
+        assert_eq!(batches[0][1], ("1.2.3.0".to_string(), transfer_syntaxes[1].clone()));
+    }
+
+    #[test]
+    fn test_matrix_exports() {
+        let matrix = CapabilityMatrix {
+            called_ae: "PACS".to_string(),
+            host: "pacs".to_string(),
+            port: 104,
+            probed_at: Utc::now(),
+            associations: 1,
+            sop_classes: vec![CT.to_string(), MR.to_string()],
+            transfer_syntaxes: vec![IMPLICIT_LE.to_string(), JPEG_2000_LOSSLESS.to_string()],
+            results: vec![
+                ProbeResult::new(CT, IMPLICIT_LE, Some(0), None),
+                ProbeResult::new(CT, JPEG_2000_LOSSLESS, Some(4), None),
+                ProbeResult::new(MR, IMPLICIT_LE, Some(3), None),
+                ProbeResult::new(MR, JPEG_2000_LOSSLESS, None, Some("association aborted")),
+            ],
+        };
+
+        assert_eq!(matrix.accepted().count(), 1);
+        assert_eq!(
+            matrix.to_csv(),
+            format!("sop_class_uid,{},{}\n{},0,4\n{},3,association aborted\n", IMPLICIT_LE, JPEG_2000_LOSSLESS, CT, MR)
+        );
+
+        let capabilities = matrix.to_capability_set();
+        assert_eq!(capabilities.ae_title.as_deref(), Some("PACS"));
+        assert_eq!(capabilities.contexts.len(), 1);
+        assert_eq!(capabilities.contexts[0].transfer_syntaxes, vec![IMPLICIT_LE]);
+
+        let proposed = [JPEG_2000_LOSSLESS, IMPLICIT_LE];
+        assert_eq!(matrix.best_transfer_syntax(CT, &proposed, TransferSyntaxPolicy::PreferLossless), Some(IMPLICIT_LE));
+        assert_eq!(matrix.best_transfer_syntax(MR, &proposed, TransferSyntaxPolicy::ProposerOrder), None);
+        assert!(matrix.retired_warnings().is_empty());
+    }
+
+    #[test]
+    fn test_retired_warnings() {
+        let matrix = CapabilityMatrix {
+            called_ae: "PACS".to_string(),
+            host: "pacs".to_string(),
+            port: 104,
+            probed_at: Utc::now(),
+            associations: 1,
+            sop_classes: vec![CT.to_string(), MR.to_string()],
+            transfer_syntaxes: vec![IMPLICIT_LE.to_string(), ts::EXPLICIT_VR_BIG_ENDIAN.to_string()],
+            results: vec![
+                ProbeResult::new(CT, IMPLICIT_LE, Some(0), None),
+                ProbeResult::new(CT, ts::EXPLICIT_VR_BIG_ENDIAN, Some(0), None),
+                ProbeResult::new(MR, IMPLICIT_LE, Some(4), None),
+                ProbeResult::new(MR, ts::EXPLICIT_VR_BIG_ENDIAN, Some(0), None),
+            ],
+        };
+        assert_eq!(matrix.retired_warnings(),
+                   vec![format!("{} is accepted only in retired transfer syntaxes: Explicit VR Big Endian (Retired)", MR)]);
+    }
+}
@@ -0,0 +1,122 @@
+/// Byte counters and transfer quotas for the receiver
+///
+/// Bytes are counted per association and globally per calendar day (local
+/// time), so multi-tenant deployments can charge back storage and cap abusive
+/// senders. Dataset fragments are charged as they arrive; a fragment that would
+/// take either counter over its quota is refused and not counted.
+
+use chrono::NaiveDate;
+use serde::Serialize;
+use std::sync::Mutex;
+
+#[derive(Debug, Clone, Default)]
+pub struct ByteQuotas {
+    /// Maximum bytes accepted on a single association
+    pub per_association: Option<u64>,
+    /// Maximum bytes accepted across all associations per day
+    pub daily: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum QuotaExceeded {
+    Association { limit: u64 },
+    Daily { limit: u64 },
+}
+
+impl std::fmt::Display for QuotaExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QuotaExceeded::Association { limit } => write!(f, "per-association quota of {} bytes exceeded", limit),
+            QuotaExceeded::Daily { limit } => write!(f, "daily quota of {} bytes exceeded", limit),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CounterSnapshot {
+    pub day: NaiveDate,
+    pub bytes_today: u64,
+    pub bytes_total: u64,
+    /// Fragments refused because of a quota since startup
+    pub refused_fragments: u64,
+}
+
+#[derive(Debug)]
+pub struct ByteCounters {
+    state: Mutex<CounterSnapshot>,
+}
+
+impl ByteCounters {
+    pub fn new(today: NaiveDate) -> Self {
+        Self {
+            state: Mutex::new(CounterSnapshot {
+                day: today,
+                bytes_today: 0,
+                bytes_total: 0,
+                refused_fragments: 0,
+            }),
+        }
+    }
+
+    /// Charge `bytes` to an association's counter and the daily counter, unless that exceeds a quota
+    pub fn charge(
+        &self,
+        association_bytes: &mut u64,
+        bytes: u64,
+        today: NaiveDate,
+        quotas: &ByteQuotas,
+    ) -> Result<(), QuotaExceeded> {
+        let mut state = self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if state.day != today {
+            state.day = today;
+            state.bytes_today = 0;
+        }
+
+        let exceeded = if let Some(limit) = quotas.per_association.filter(|limit| *association_bytes + bytes > *limit) {
+            Some(QuotaExceeded::Association { limit })
+        } else {
+            quotas.daily
+                .filter(|limit| state.bytes_today + bytes > *limit)
+                .map(|limit| QuotaExceeded::Daily { limit })
+        };
+        if let Some(exceeded) = exceeded {
+            state.refused_fragments += 1;
+            return Err(exceeded);
+        }
+
+        *association_bytes += bytes;
+        state.bytes_today += bytes;
+        state.bytes_total += bytes;
+        Ok(())
+    }
+
+    pub fn snapshot(&self) -> CounterSnapshot {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quotas_and_daily_rollover() {
+        let day1 = NaiveDate::from_ymd_opt(2024, 3, 15).unwrap();
+        let day2 = day1.succ_opt().unwrap();
+        let quotas = ByteQuotas { per_association: Some(100), daily: Some(150) };
+        let counters = ByteCounters::new(day1);
+
+        let mut first = 0;
+        assert!(counters.charge(&mut first, 80, day1, &quotas).is_ok());
+        assert_eq!(counters.charge(&mut first, 30, day1, &quotas), Err(QuotaExceeded::Association { limit: 100 }));
+        assert_eq!(first, 80, "refused bytes are not counted");
+
+        let mut second = 0;
+        assert_eq!(counters.charge(&mut second, 80, day1, &quotas), Err(QuotaExceeded::Daily { limit: 150 }));
+        assert!(counters.charge(&mut second, 80, day2, &quotas).is_ok());
+
+        let snapshot = counters.snapshot();
+        assert_eq!((snapshot.day, snapshot.bytes_today, snapshot.bytes_total, snapshot.refused_fragments),
+                   (day2, 80, 160, 2));
+    }
+}
@@ -0,0 +1,312 @@
+//! Reassembly of received data sets from P-DATA fragments
+//!
+//! Fragments are collected per presentation context until the value flagged
+//! as last. A peer may misbehave at any point: send a new command before the
+//! previous data set is complete, release or abort mid-transfer, or send data
+//! without a command. None of that may lose track of what was received.
+//!
+//! Fragments are held in memory until a transfer is moved to a spool file,
+//! which the receiver does once it grows past its in-memory threshold. From
+//! then on every fragment is written straight to disk, so a whole-slide image
+//! of several gigabytes does not occupy as much memory while it arrives. With
+//! encryption at rest, fragments are sealed chunk by chunk on their way to the
+//! spool file, which never holds any plaintext.
+
+use chrono::Utc;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use super::encryption::{SealingWriter, StorageKey};
+use super::recovery::partial_path;
+
+/// A data set written to disk as it arrives, as a `.partial` Part 10 file
+/// that is renamed into place once complete
+///
+/// A spool that is dropped without being persisted removes its file, so
+/// refused and discarded objects leave nothing behind. One left over by a
+/// crash is found by the startup recovery like any other partial file.
+#[derive(Debug)]
+pub struct Spool {
+    path: PathBuf,
+    file: Option<SpoolFile>,
+}
+
+#[derive(Debug)]
+enum SpoolFile {
+    Plain(BufWriter<File>),
+    Sealed(SealingWriter<BufWriter<File>>),
+}
+
+impl Spool {
+    /// Start a spool file in `dir` with `header`, the preamble and File Meta
+    /// Information the stored object begins with; sealed with `key` if given
+    pub fn create(dir: &Path, header: &[u8], key: Option<Arc<StorageKey>>) -> io::Result<Self> {
+        let path = partial_path(&dir.join(format!("spool_{}.dcm", uuid::Uuid::new_v4())));
+        let file = BufWriter::new(File::create(&path)?);
+        let file = match key {
+            Some(key) => SpoolFile::Sealed(SealingWriter::new(key, file)?),
+            None => SpoolFile::Plain(file),
+        };
+        let mut spool = Self { path, file: Some(file) };
+        spool.write(header)?;
+        Ok(spool)
+    }
+
+    fn write(&mut self, data: &[u8]) -> io::Result<()> {
+        match &mut self.file {
+            Some(SpoolFile::Plain(file)) => file.write_all(data),
+            Some(SpoolFile::Sealed(file)) => file.write_all(data),
+            None => Err(io::Error::other(format!("{} is already finished", self.path.display()))),
+        }
+    }
+
+    /// Flush and sync the file, which then holds the complete object
+    pub fn finish(&mut self) -> io::Result<&Path> {
+        let file = match self.file.take() {
+            Some(SpoolFile::Plain(file)) => Some(file),
+            Some(SpoolFile::Sealed(file)) => Some(file.finish()?),
+            None => None,
+        };
+        if let Some(file) = file {
+            file.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+        }
+        Ok(&self.path)
+    }
+
+    /// Finish the file and move it to `path`
+    pub fn persist(mut self, path: &Path) -> io::Result<()> {
+        self.finish()?;
+        std::fs::rename(&self.path, path)?;
+        self.path = PathBuf::new();
+        Ok(())
+    }
+}
+
+impl Drop for Spool {
+    fn drop(&mut self) {
+        self.file = None;
+        if !self.path.as_os_str().is_empty() {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct DicomTransfer {
+    pub command_received: bool,
+    /// Fragments held in memory, until the transfer is spooled
+    pub dataset_chunks: Vec<Vec<u8>>,
+    /// Where fragments are written once the transfer is spooled
+    pub spool: Option<Spool>,
+    /// Fragments received, in memory or spooled
+    pub chunks: usize,
+    pub total_bytes: usize,
+    pub presentation_context_id: u8,
+    pub started_at: chrono::DateTime<Utc>,
+    /// Message ID of the C-STORE command
+    pub message_id: Option<u16>,
+    /// Affected SOP Class UID from the C-STORE command
+    pub sop_class_uid: Option<String>,
+    /// Affected SOP Instance UID from the C-STORE command
+    pub sop_instance_uid: Option<String>,
+    /// Status the object is refused with, e.g. when it exceeds its size limit; further fragments are discarded
+    pub refused: Option<u16>,
+    /// The object was stored before, and is answered with `refused` without being stored again
+    pub already_stored: bool,
+}
+
+impl DicomTransfer {
+    pub fn new(presentation_context_id: u8) -> Self {
+        Self {
+            command_received: false,
+            dataset_chunks: Vec::new(),
+            spool: None,
+            chunks: 0,
+            total_bytes: 0,
+            presentation_context_id,
+            started_at: Utc::now(),
+            message_id: None,
+            sop_class_uid: None,
+            sop_instance_uid: None,
+            refused: None,
+            already_stored: false,
+        }
+    }
+
+    pub fn add_chunk(&mut self, data: Vec<u8>) -> io::Result<()> {
+        self.total_bytes += data.len();
+        self.chunks += 1;
+        match &mut self.spool {
+            Some(spool) => spool.write(&data),
+            None => {
+                self.dataset_chunks.push(data);
+                Ok(())
+            }
+        }
+    }
+
+    /// Move the fragments received so far to a spool file in `dir`, which
+    /// every later fragment is appended to, sealed with `key` if given
+    pub fn spool_to(&mut self, dir: &Path, header: &[u8], key: Option<Arc<StorageKey>>) -> io::Result<()> {
+        let mut spool = Spool::create(dir, header, key)?;
+        for chunk in &self.dataset_chunks {
+            spool.write(chunk)?;
+        }
+        self.dataset_chunks = Vec::new();
+        self.spool = Some(spool);
+        Ok(())
+    }
+
+    /// Whether any fragment is held, in memory or spooled
+    pub fn has_data(&self) -> bool {
+        !self.dataset_chunks.is_empty() || self.spool.is_some()
+    }
+
+    /// Drop the fragments received so far, e.g. once the object is refused
+    pub fn discard(&mut self) {
+        self.dataset_chunks.clear();
+        self.spool = None;
+    }
+
+    /// The data set held in memory; empty for a spooled transfer
+    pub fn reconstruct_dataset(&self) -> Vec<u8> {
+        let mut dataset = Vec::with_capacity(self.total_bytes);
+        for chunk in &self.dataset_chunks {
+            dataset.extend_from_slice(chunk);
+        }
+        dataset
+    }
+}
+
+/// Transfers in progress on one association, by presentation context ID
+#[derive(Debug, Default)]
+pub struct Transfers {
+    open: HashMap<u8, DicomTransfer>,
+}
+
+impl Transfers {
+    /// Start the operation announced by a command, with the transfer describing it
+    ///
+    /// Returns the previous transfer on that presentation context if it never
+    /// received its last fragment but holds data.
+    pub fn begin(&mut self, mut transfer: DicomTransfer) -> Option<DicomTransfer> {
+        transfer.command_received = true;
+        self.open.insert(transfer.presentation_context_id, transfer)
+            .filter(DicomTransfer::has_data)
+    }
+
+    /// The transfer receiving data on a presentation context, started if no command preceded it
+    pub fn get_mut(&mut self, presentation_context_id: u8) -> &mut DicomTransfer {
+        self.open.entry(presentation_context_id)
+            .or_insert_with(|| DicomTransfer::new(presentation_context_id))
+    }
+
+    /// Whether no operation is in progress on the association
+    pub fn is_empty(&self) -> bool {
+        self.open.is_empty()
+    }
+
+    /// Close the transfer on a presentation context once its last fragment has been handled
+    pub fn finish(&mut self, presentation_context_id: u8) -> Option<DicomTransfer> {
+        self.open.remove(&presentation_context_id)
+    }
+
+    /// Close every open transfer, returning those interrupted with data received
+    pub fn take_pending(&mut self) -> Vec<DicomTransfer> {
+        let mut pending: Vec<DicomTransfer> = self.open.drain()
+            .map(|(_, transfer)| transfer)
+            .filter(DicomTransfer::has_data)
+            .collect();
+        pending.sort_by_key(|transfer| transfer.presentation_context_id);
+        pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reassembly_per_context() {
+        let mut transfers = Transfers::default();
+        assert!(transfers.begin(DicomTransfer {
+            message_id: Some(5),
+            sop_class_uid: Some("1.2.840.10008.5.1.4.1.1.2".to_string()),
+            ..DicomTransfer::new(1)
+        }).is_none());
+        assert!(transfers.begin(DicomTransfer::new(3)).is_none());
+        transfers.get_mut(1).add_chunk(vec![1, 2]).unwrap();
+        transfers.get_mut(3).add_chunk(vec![9]).unwrap();
+        transfers.get_mut(1).add_chunk(vec![3]).unwrap();
+
+        let complete = transfers.finish(1).unwrap();
+        assert_eq!(complete.reconstruct_dataset(), vec![1, 2, 3]);
+        assert_eq!(complete.sop_class_uid.as_deref(), Some("1.2.840.10008.5.1.4.1.1.2"));
+        assert_eq!((complete.message_id, complete.command_received), (Some(5), true));
+        assert!(transfers.finish(1).is_none());
+
+        let pending = transfers.take_pending();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].presentation_context_id, 3);
+        assert!(transfers.take_pending().is_empty());
+    }
+
+    #[test]
+    fn test_new_command_interrupts_incomplete_transfer() {
+        let mut transfers = Transfers::default();
+        transfers.begin(DicomTransfer::new(1));
+        assert!(transfers.begin(DicomTransfer::new(1)).is_none(), "a transfer without data is not interrupted");
+
+        transfers.get_mut(1).add_chunk(vec![1, 2, 3]).unwrap();
+        let interrupted = transfers.begin(DicomTransfer::new(1)).unwrap();
+        assert_eq!(interrupted.total_bytes, 3);
+        assert!(!transfers.get_mut(1).has_data());
+
+        // Data without a preceding command still opens a transfer
+        transfers.get_mut(5).add_chunk(vec![4]).unwrap();
+        assert!(!transfers.get_mut(5).command_received);
+        assert_eq!(transfers.take_pending().len(), 1);
+    }
+
+    #[test]
+    fn test_spooled_transfer() {
+        let dir = std::env::temp_dir().join(format!("reassembly_test_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let mut transfer = DicomTransfer::new(1);
+        transfer.add_chunk(vec![1, 2]).unwrap();
+        transfer.spool_to(&dir, b"HEAD", None).unwrap();
+        transfer.add_chunk(vec![3]).unwrap();
+        assert!(transfer.has_data() && transfer.dataset_chunks.is_empty());
+        assert_eq!((transfer.chunks, transfer.total_bytes), (2, 3));
+
+        let mut spool = transfer.spool.take().unwrap();
+        let spooled = spool.finish().unwrap().to_path_buf();
+        assert_eq!(std::fs::read(&spooled).unwrap(), b"HEAD\x01\x02\x03");
+        let stored = dir.join("stored.dcm");
+        spool.persist(&stored).unwrap();
+        assert!(stored.exists() && !spooled.exists());
+
+        // With a storage key, only ciphertext reaches the spool file
+        let key = Arc::new(StorageKey::from_bytes(&[7; 32]).unwrap());
+        let mut transfer = DicomTransfer::new(1);
+        transfer.add_chunk(vec![1, 2]).unwrap();
+        transfer.spool_to(&dir, b"HEAD", Some(Arc::clone(&key))).unwrap();
+        transfer.add_chunk(vec![3]).unwrap();
+        let mut spool = transfer.spool.take().unwrap();
+        let sealed = std::fs::read(spool.finish().unwrap()).unwrap();
+        assert!(sealed.starts_with(super::super::encryption::STREAM_MAGIC));
+        assert_eq!(key.open(&sealed).unwrap(), b"HEAD\x01\x02\x03");
+        drop(spool);
+
+        // A discarded transfer leaves no file behind
+        transfer.spool_to(&dir, b"HEAD", None).unwrap();
+        transfer.discard();
+        assert!(!transfer.has_data());
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,161 @@
+/// Partial-transfer handling and crash recovery for the receiver
+///
+/// Received objects are written to a `.partial` file, synced and then renamed
+/// to their final name, so a crash or power loss can never leave a truncated
+/// file that looks like a complete object. Transfers cut off mid-association are
+/// also kept as `.partial`. On startup the output directory is scanned for
+/// leftovers and each one is discarded, quarantined or finalized per policy.
+
+use anyhow::{Context, Result};
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+pub const PARTIAL_EXTENSION: &str = "partial";
+/// Directory below the output directory that quarantined partial files are moved to
+pub const INCOMPLETE_DIR: &str = "incomplete";
+
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum RecoveryPolicy {
+    /// Delete leftover partial files
+    Discard,
+    /// Move them to the `incomplete` directory for manual inspection
+    Quarantine,
+    /// Rename them to their final name as if the write had completed
+    Finalize,
+}
+
+#[derive(Debug, Default)]
+pub struct RecoveryReport {
+    pub found: usize,
+    pub discarded: usize,
+    pub quarantined: usize,
+    pub finalized: usize,
+    pub failed: Vec<(PathBuf, String)>,
+}
+
+impl std::fmt::Display for RecoveryReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} partial file(s) found: {} discarded, {} quarantined, {} finalized, {} failed",
+               self.found, self.discarded, self.quarantined, self.finalized, self.failed.len())
+    }
+}
+
+/// Path of the in-progress file for `path`
+pub fn partial_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".");
+    name.push(PARTIAL_EXTENSION);
+    path.with_file_name(name)
+}
+
+/// Write `data` to `path` via a synced `.partial` file and an atomic rename
+pub fn write_atomically(path: &Path, data: &[u8]) -> Result<()> {
+    let partial = partial_path(path);
+    let mut file = File::create(&partial)
+        .with_context(|| format!("Failed to create {}", partial.display()))?;
+    file.write_all(data)?;
+    file.sync_all()?;
+    drop(file);
+
+    std::fs::rename(&partial, path)
+        .with_context(|| format!("Failed to move {} into place", partial.display()))
+}
+
+/// Write a transfer that never completed, to be handled by the next recovery pass
+pub fn write_partial(path: &Path, data: &[u8]) -> Result<PathBuf> {
+    let partial = partial_path(path);
+    std::fs::write(&partial, data)
+        .with_context(|| format!("Failed to write {}", partial.display()))?;
+    Ok(partial)
+}
+
+/// Find leftover partial files below `output_dir` and apply `policy` to each
+pub fn recover(output_dir: &Path, policy: RecoveryPolicy) -> Result<RecoveryReport> {
+    let incomplete_dir = output_dir.join(INCOMPLETE_DIR);
+    let mut report = RecoveryReport::default();
+
+    let partials: Vec<PathBuf> = WalkDir::new(output_dir)
+        .into_iter()
+        .filter_entry(|entry| entry.path() != incomplete_dir)
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .map(|entry| entry.into_path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == PARTIAL_EXTENSION))
+        .collect();
+
+    for partial in partials {
+        report.found += 1;
+        let result = match policy {
+            RecoveryPolicy::Discard => std::fs::remove_file(&partial)
+                .map(|_| report.discarded += 1),
+            RecoveryPolicy::Quarantine => std::fs::create_dir_all(&incomplete_dir)
+                .and_then(|_| std::fs::rename(&partial, incomplete_dir.join(partial.file_name().unwrap_or_default())))
+                .map(|_| report.quarantined += 1),
+            RecoveryPolicy::Finalize => {
+                let target = partial.with_extension("");
+                if target.exists() {
+                    Err(std::io::Error::new(std::io::ErrorKind::AlreadyExists,
+                                            format!("{} already exists", target.display())))
+                } else {
+                    std::fs::rename(&partial, &target).map(|_| report.finalized += 1)
+                }
+            }
+        };
+        if let Err(e) = result {
+            report.failed.push((partial, e.to_string()));
+        }
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("recovery_test_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("quarantine")).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_write_atomically_leaves_no_partial() {
+        let dir = scratch_dir();
+        let path = dir.join("received_1.dcm");
+        write_atomically(&path, b"DICM").unwrap();
+
+        assert_eq!(std::fs::read(&path).unwrap(), b"DICM");
+        assert!(!partial_path(&path).exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_recovery_policies() {
+        let dir = scratch_dir();
+        write_partial(&dir.join("a.dcm"), b"a").unwrap();
+        write_partial(&dir.join("quarantine").join("b.dcm"), b"b").unwrap();
+        write_atomically(&dir.join("c.dcm"), b"c").unwrap();
+
+        let report = recover(&dir, RecoveryPolicy::Quarantine).unwrap();
+        assert_eq!((report.found, report.quarantined), (2, 2));
+        assert!(dir.join(INCOMPLETE_DIR).join("a.dcm.partial").exists());
+
+        // Quarantined files are not picked up again
+        assert_eq!(recover(&dir, RecoveryPolicy::Discard).unwrap().found, 0);
+
+        write_partial(&dir.join("d.dcm"), b"d").unwrap();
+        let report = recover(&dir, RecoveryPolicy::Finalize).unwrap();
+        assert_eq!(report.finalized, 1);
+        assert_eq!(std::fs::read(dir.join("d.dcm")).unwrap(), b"d");
+
+        write_partial(&dir.join("c.dcm"), b"truncated").unwrap();
+        let report = recover(&dir, RecoveryPolicy::Finalize).unwrap();
+        assert_eq!(report.failed.len(), 1, "never overwrite a complete file");
+        assert_eq!(std::fs::read(dir.join("c.dcm")).unwrap(), b"c");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
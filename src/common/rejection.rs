@@ -0,0 +1,98 @@
+/// Human-readable association and presentation context rejection reasons
+///
+/// A-ASSOCIATE-RJ carries a result, a source and a reason code (PS3.8 Table
+/// 9-21), and each presentation context in an A-ASSOCIATE-AC carries a result
+/// code (PS3.8 Table 9-18). These are kept as the raw codes so summaries can
+/// be matched against peer logs, with the standard wording for display.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct AssociationRejection {
+    /// 1 = rejected-permanent, 2 = rejected-transient
+    pub result: u8,
+    /// 1 = service-user, 2 = service-provider (ACSE), 3 = service-provider (presentation)
+    pub source: u8,
+    pub reason: u8,
+}
+
+impl AssociationRejection {
+    /// Whether retrying later may succeed
+    pub fn is_transient(&self) -> bool {
+        self.result == 2
+    }
+
+    pub fn result_text(&self) -> &'static str {
+        match self.result {
+            1 => "rejected-permanent",
+            2 => "rejected-transient",
+            _ => "rejected (unknown result)",
+        }
+    }
+
+    pub fn source_text(&self) -> &'static str {
+        match self.source {
+            1 => "DICOM UL service-user",
+            2 => "DICOM UL service-provider (ACSE related function)",
+            3 => "DICOM UL service-provider (presentation related function)",
+            _ => "unknown source",
+        }
+    }
+
+    pub fn reason_text(&self) -> &'static str {
+        match (self.source, self.reason) {
+            (1, 1) => "no-reason-given",
+            (1, 2) => "application-context-name-not-supported",
+            (1, 3) => "calling-AE-title-not-recognized",
+            (1, 7) => "called-AE-title-not-recognized",
+            (2, 1) => "no-reason-given",
+            (2, 2) => "protocol-version-not-supported",
+            (3, 1) => "temporary-congestion",
+            (3, 2) => "local-limit-exceeded",
+            _ => "reserved",
+        }
+    }
+}
+
+impl std::fmt::Display for AssociationRejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Association {} by {}: {} (result {}, source {}, reason {})",
+               self.result_text(), self.source_text(), self.reason_text(),
+               self.result, self.source, self.reason)
+    }
+}
+
+impl std::error::Error for AssociationRejection {}
+
+/// Wording of a presentation context result code
+pub fn presentation_context_result_text(code: u8) -> &'static str {
+    match code {
+        0 => "acceptance",
+        1 => "user-rejection",
+        2 => "no-reason (provider rejection)",
+        3 => "abstract-syntax-not-supported (provider rejection)",
+        4 => "transfer-syntaxes-not-supported (provider rejection)",
+        _ => "unknown result",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rejection_wording() {
+        let rejection = AssociationRejection { result: 1, source: 1, reason: 7 };
+        assert!(!rejection.is_transient());
+        assert_eq!(
+            rejection.to_string(),
+            "Association rejected-permanent by DICOM UL service-user: called-AE-title-not-recognized (result 1, source 1, reason 7)"
+        );
+
+        let congestion = AssociationRejection { result: 2, source: 3, reason: 1 };
+        assert!(congestion.is_transient());
+        assert_eq!(congestion.reason_text(), "temporary-congestion");
+
+        assert_eq!(presentation_context_result_text(3), "abstract-syntax-not-supported (provider rejection)");
+    }
+}
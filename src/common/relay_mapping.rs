@@ -0,0 +1,183 @@
+//! AE title and attribute translation in the relay
+//!
+//! Devices with fixed configurations often cannot be told apart, or clash, once
+//! several sites send into one PACS: the same calling AE title at every site,
+//! Patient IDs from different registration systems. `dicom-relay --mapping`
+//! takes a TOML file of `[[rule]]`s; the first rule whose `calling_ae` and
+//! `called_ae` match an object (an absent one matches any) decides the AE
+//! titles it goes on with and sets attributes by keyword, `{value}` standing
+//! for the value received. Replaced values are kept in an Original Attributes
+//! Sequence item with reason COERCE.
+//!
+//! ```toml
+//! [[rule]]
+//! calling_ae = "CT01"
+//! called_ae = "RELAY_SITE_A"
+//! new_calling_ae = "SITEA_CT01"
+//!
+//! [rule.attributes]
+//! PatientID = "A-{value}"
+//! IssuerOfPatientID = "SITE_A"
+//! InstitutionName = "Site A Hospital"
+//! ```
+
+use anyhow::{Context, Result};
+use dicom_core::dictionary::DataDictionary;
+use dicom_dictionary_std::StandardDataDictionary;
+use dicom_object::InMemDicomObject;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use super::patient_admin::coerce_with_reason;
+
+/// Placeholder for the received value in an attribute rule
+const VALUE_PLACEHOLDER: &str = "{value}";
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MappingRule {
+    /// Calling AE title of the objects the rule applies to; any when absent
+    #[serde(default)]
+    pub calling_ae: Option<String>,
+    /// Called AE title of the objects the rule applies to; any when absent
+    #[serde(default)]
+    pub called_ae: Option<String>,
+    /// Calling AE title the objects are relayed under
+    #[serde(default)]
+    pub new_calling_ae: Option<String>,
+    /// Called AE title the objects are relayed to
+    #[serde(default)]
+    pub new_called_ae: Option<String>,
+    /// Values set by keyword
+    #[serde(default)]
+    pub attributes: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MappingFile {
+    rule: Vec<MappingRule>,
+}
+
+impl MappingRule {
+    pub fn matches(&self, calling_ae: &str, called_ae: &str) -> bool {
+        let matches = |pattern: &Option<String>, ae_title: &str| {
+            pattern.as_deref().is_none_or(|pattern| pattern.trim() == ae_title.trim())
+        };
+        matches(&self.calling_ae, calling_ae) && matches(&self.called_ae, called_ae)
+    }
+
+    /// The attribute values to set on `obj`, placeholders filled in
+    pub fn attribute_changes(&self, obj: &InMemDicomObject) -> BTreeMap<String, String> {
+        self.attributes.iter().map(|(keyword, value)| {
+            let value = if value.contains(VALUE_PLACEHOLDER) {
+                let received = obj.element_by_name(keyword).ok()
+                    .and_then(|e| e.to_str().ok())
+                    .map(|s| s.trim_end_matches(['\0', ' ']).to_string())
+                    .unwrap_or_default();
+                value.replace(VALUE_PLACEHOLDER, &received)
+            } else {
+                value.clone()
+            };
+            (keyword.clone(), value)
+        }).collect()
+    }
+
+    /// Set the rule's attributes on `obj`; returns how many actually changed
+    pub fn apply(&self, obj: &mut InMemDicomObject) -> Result<usize> {
+        if self.attributes.is_empty() {
+            return Ok(0);
+        }
+        let changes = self.attribute_changes(obj);
+        coerce_with_reason(obj, &changes, "COERCE")
+    }
+}
+
+/// Mapping rules, tried in order
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RelayMapping {
+    rules: Vec<MappingRule>,
+}
+
+impl RelayMapping {
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read mapping rules: {}", path.display()))?;
+        Self::parse(&text)
+    }
+
+    /// Parse and check mapping rules: AE titles of 1 to 16 characters and
+    /// attributes of the standard dictionary
+    pub fn parse(text: &str) -> Result<Self> {
+        let file: MappingFile = toml::from_str(text).context("Invalid mapping rules")?;
+        for (number, rule) in file.rule.iter().enumerate() {
+            let ae_titles = [&rule.calling_ae, &rule.called_ae, &rule.new_calling_ae, &rule.new_called_ae];
+            for ae_title in ae_titles.into_iter().flatten() {
+                let trimmed = ae_title.trim();
+                if trimmed.is_empty() || trimmed.len() > 16 || trimmed.contains('\\') {
+                    anyhow::bail!("Rule {}: invalid AE title {:?}: it must have 1 to 16 characters", number + 1, ae_title);
+                }
+            }
+            if let Some(keyword) = rule.attributes.keys().find(|keyword| StandardDataDictionary.by_name(keyword).is_none()) {
+                anyhow::bail!("Rule {}: unknown attribute {}", number + 1, keyword);
+            }
+        }
+        Ok(Self { rules: file.rule })
+    }
+
+    /// The first rule matching an object received from `calling_ae` by `called_ae`
+    pub fn rule_for(&self, calling_ae: &str, called_ae: &str) -> Option<&MappingRule> {
+        self.rules.iter().find(|rule| rule.matches(calling_ae, called_ae))
+    }
+
+    pub fn len(&self) -> usize {
+        self.rules.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dicom_core::value::PrimitiveValue;
+    use dicom_core::{DataElement, Tag, VR};
+
+    const RULES: &str = r#"
+        [[rule]]
+        calling_ae = "CT01"
+        called_ae = "RELAY_A"
+        new_calling_ae = "SITEA_CT01"
+
+        [rule.attributes]
+        PatientID = "A-{value}"
+        IssuerOfPatientID = "SITE_A"
+
+        [[rule]]
+        calling_ae = "CT01"
+        new_called_ae = "PACS_B"
+    "#;
+
+    #[test]
+    fn test_rules() {
+        let mapping = RelayMapping::parse(RULES).unwrap();
+        assert_eq!(mapping.len(), 2);
+        let site_a = mapping.rule_for("CT01 ", "RELAY_A").unwrap();
+        assert_eq!(site_a.new_calling_ae.as_deref(), Some("SITEA_CT01"));
+        assert_eq!(mapping.rule_for("CT01", "RELAY_B").unwrap().new_called_ae.as_deref(), Some("PACS_B"));
+        assert!(mapping.rule_for("MR01", "RELAY_A").is_none());
+
+        let mut obj = InMemDicomObject::from_element_iter([
+            DataElement::new(Tag(0x0010, 0x0020), VR::LO, PrimitiveValue::from("12345 ")),
+        ]);
+        assert_eq!(site_a.apply(&mut obj).unwrap(), 2);
+        assert_eq!(obj.element(Tag(0x0010, 0x0020)).unwrap().to_str().unwrap(), "A-12345");
+        assert_eq!(obj.element_by_name("IssuerOfPatientID").unwrap().to_str().unwrap(), "SITE_A");
+
+        assert!(RelayMapping::parse("[[rule]]\nnew_calling_ae = \"FAR_TOO_LONG_AE_TITLE\"").is_err());
+        assert!(RelayMapping::parse("[[rule]]\n[rule.attributes]\nNotAKeyword = \"x\"").is_err());
+    }
+}
@@ -0,0 +1,166 @@
+/// Lenient repair of common VR and value-length non-conformances
+///
+/// Strict downstream systems reject objects with illegal characters in UI/CS
+/// values, overlong SH/LO/CS/AE values or odd-length binary values. Rather than
+/// refusing such objects, the lenient repair mode fixes them in place and
+/// reports every change so it can be logged. Only top-level elements are
+/// repaired; sequence items are left untouched.
+
+use dicom_core::value::{PrimitiveValue, Value};
+use dicom_core::{DataElement, Tag, VR};
+use dicom_object::InMemDicomObject;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Repair {
+    pub tag: Tag,
+    pub vr: VR,
+    pub description: String,
+}
+
+impl std::fmt::Display for Repair {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({:04X},{:04X}) {:?}: {}", self.tag.0, self.tag.1, self.vr, self.description)
+    }
+}
+
+/// Maximum value length in characters for the length-limited string VRs
+pub fn max_value_length(vr: VR) -> Option<usize> {
+    match vr {
+        VR::AE | VR::CS | VR::SH => Some(16),
+        VR::LO | VR::UI => Some(64),
+        _ => None,
+    }
+}
+
+/// Repair a single string value for the given VR, returning the fixed value
+/// and a description of each change made.
+pub fn repair_string_value(vr: VR, value: &str) -> (String, Vec<String>) {
+    let mut changes = Vec::new();
+    // Trailing padding is legal and never reported as a repair
+    let padding: &[char] = if vr == VR::UI { &['\0'] } else { &[' ', '\0'] };
+    let mut fixed = value.trim_end_matches(padding).to_string();
+
+    match vr {
+        VR::UI => {
+            let cleaned: String = fixed.chars().filter(|c| c.is_ascii_digit() || *c == '.').collect();
+            if cleaned != fixed {
+                changes.push(format!("removed illegal UID characters from '{}'", fixed.escape_default()));
+                fixed = cleaned;
+            }
+        }
+        VR::CS => {
+            let cleaned: String = fixed.chars()
+                .map(|c| c.to_ascii_uppercase())
+                .map(|c| if c.is_ascii_uppercase() || c.is_ascii_digit() || c == ' ' || c == '_' { c } else { '_' })
+                .collect();
+            if cleaned != fixed {
+                changes.push(format!("normalized code string '{}' to '{}'", fixed.escape_default(), cleaned));
+                fixed = cleaned;
+            }
+        }
+        _ => {}
+    }
+
+    if let Some(max_len) = max_value_length(vr) {
+        if fixed.chars().count() > max_len {
+            let truncated: String = fixed.chars().take(max_len).collect();
+            changes.push(format!("truncated value of {} characters to {}", fixed.chars().count(), max_len));
+            fixed = truncated;
+        }
+    }
+
+    (fixed, changes)
+}
+
+/// Apply lenient repairs to all top-level elements of a dataset
+pub fn repair_dataset(obj: &mut InMemDicomObject) -> Vec<Repair> {
+    let mut repairs = Vec::new();
+    let mut replacements = Vec::new();
+
+    for element in obj.iter() {
+        let tag = element.header().tag;
+        let vr = element.header().vr;
+
+        match element.value() {
+            Value::Primitive(PrimitiveValue::Strs(values)) if matches!(vr, VR::UI | VR::CS | VR::SH | VR::LO | VR::AE) => {
+                let mut changed = false;
+                let fixed: Vec<String> = values.iter()
+                    .map(|value| {
+                        let (fixed, changes) = repair_string_value(vr, value);
+                        for description in changes {
+                            changed = true;
+                            repairs.push(Repair { tag, vr, description });
+                        }
+                        fixed
+                    })
+                    .collect();
+                if changed {
+                    replacements.push(DataElement::new(tag, vr, PrimitiveValue::Strs(fixed.into_iter().collect())));
+                }
+            }
+            Value::Primitive(PrimitiveValue::Str(value)) if matches!(vr, VR::UI | VR::CS | VR::SH | VR::LO | VR::AE) => {
+                let (fixed, changes) = repair_string_value(vr, value);
+                if !changes.is_empty() {
+                    for description in changes {
+                        repairs.push(Repair { tag, vr, description });
+                    }
+                    replacements.push(DataElement::new(tag, vr, PrimitiveValue::from(fixed)));
+                }
+            }
+            Value::Primitive(PrimitiveValue::U8(bytes)) if bytes.len() % 2 == 1 => {
+                let mut padded = bytes.to_vec();
+                padded.push(0);
+                repairs.push(Repair {
+                    tag,
+                    vr,
+                    description: format!("padded odd value length {} to {}", bytes.len(), padded.len()),
+                });
+                replacements.push(DataElement::new(tag, vr, PrimitiveValue::U8(padded.into_iter().collect())));
+            }
+            _ => {}
+        }
+    }
+
+    for element in replacements {
+        obj.put(element);
+    }
+
+    repairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repair_string_values() {
+        let (uid, changes) = repair_string_value(VR::UI, "1.2.840.10008 .1.2\0");
+        assert_eq!(uid, "1.2.840.10008.1.2");
+        assert_eq!(changes.len(), 1);
+
+        let (cs, _) = repair_string_value(VR::CS, "ortho-pedic");
+        assert_eq!(cs, "ORTHO_PEDIC");
+
+        let (sh, changes) = repair_string_value(VR::SH, "A station name that is too long");
+        assert_eq!(sh.len(), 16);
+        assert_eq!(changes.len(), 1);
+
+        let (lo, changes) = repair_string_value(VR::LO, "Fine as it is");
+        assert_eq!(lo, "Fine as it is");
+        assert!(changes.is_empty());
+    }
+
+    #[test]
+    fn test_repair_dataset() {
+        let mut obj = InMemDicomObject::from_element_iter([
+            DataElement::new(Tag(0x0008, 0x0018), VR::UI, PrimitiveValue::from("1.2.3.4a")),
+            DataElement::new(Tag(0x0008, 0x0060), VR::CS, PrimitiveValue::from("CT")),
+            DataElement::new(Tag(0x0009, 0x1010), VR::OB, PrimitiveValue::U8([1u8, 2, 3].into_iter().collect())),
+        ]);
+
+        let repairs = repair_dataset(&mut obj);
+        assert_eq!(repairs.len(), 2);
+        assert_eq!(obj.element(Tag(0x0008, 0x0018)).unwrap().to_str().unwrap(), "1.2.3.4");
+        assert_eq!(obj.element(Tag(0x0009, 0x1010)).unwrap().to_bytes().unwrap().len(), 4);
+    }
+}
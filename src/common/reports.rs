@@ -0,0 +1,249 @@
+/// Study- and patient-level aggregation of transfer results
+///
+/// Migration QA is done per study, so the per-file results of a session are
+/// rolled up into one row per study and one row per patient and exported as
+/// CSV or JSON.
+///
+/// Each row also compares the size on disk of the instances sent with their
+/// size as encoded for the destination, so the effect of the transfer syntaxes
+/// negotiated (compression, or decompression by the sender) can be quantified.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+use super::types::{DicomFile, TransferResult};
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StudyReport {
+    pub study_instance_uid: String,
+    pub patient_id: Option<String>,
+    pub instances_sent: usize,
+    pub instances_failed: usize,
+    pub bytes_sent: u64,
+    /// Size of the instances sent as encoded for the destination
+    pub bytes_forwarded: u64,
+    /// `bytes_sent` over `bytes_forwarded`; above 1 when the transfer saved space
+    pub compression_ratio: Option<f64>,
+    pub transfer_time_ms: u64,
+    pub earliest_acquisition_date: Option<String>,
+    pub latest_acquisition_date: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PatientReport {
+    pub patient_id: String,
+    pub studies: usize,
+    pub instances_sent: usize,
+    pub instances_failed: usize,
+    pub bytes_sent: u64,
+    /// Size of the instances sent as encoded for the destination
+    pub bytes_forwarded: u64,
+    /// `bytes_sent` over `bytes_forwarded`; above 1 when the transfer saved space
+    pub compression_ratio: Option<f64>,
+    pub transfer_time_ms: u64,
+    pub earliest_acquisition_date: Option<String>,
+    pub latest_acquisition_date: Option<String>,
+}
+
+/// Aggregate per-file results by study. Acquisition dates fall back to the
+/// study date for instances without an Acquisition Date.
+pub fn build_study_reports(files: &[DicomFile], results: &[TransferResult]) -> Vec<StudyReport> {
+    let files_by_path: HashMap<String, &DicomFile> = files.iter()
+        .map(|file| (file.path.display().to_string(), file))
+        .collect();
+    let mut studies: BTreeMap<String, StudyReport> = BTreeMap::new();
+
+    for result in results {
+        let file = files_by_path.get(&result.file_path);
+        let report = studies.entry(result.study_instance_uid.clone())
+            .or_insert_with(|| StudyReport {
+                study_instance_uid: result.study_instance_uid.clone(),
+                ..Default::default()
+            });
+
+        if result.success {
+            report.instances_sent += 1;
+            report.bytes_sent += result.file_size;
+            report.bytes_forwarded += result.bytes_sent;
+        } else {
+            report.instances_failed += 1;
+        }
+        report.transfer_time_ms += result.transfer_time_ms;
+
+        if let Some(file) = file {
+            if report.patient_id.is_none() {
+                report.patient_id = file.patient_id.clone();
+            }
+            let date = file.acquisition_date.as_ref().or(file.study_date.as_ref());
+            if let Some(date) = date.filter(|d| !d.is_empty()) {
+                widen_date_range(&mut report.earliest_acquisition_date, &mut report.latest_acquisition_date, date);
+            }
+        }
+    }
+
+    for report in studies.values_mut() {
+        report.compression_ratio = compression_ratio(report.bytes_sent, report.bytes_forwarded);
+    }
+    studies.into_values().collect()
+}
+
+/// Roll study reports up to one row per patient
+pub fn build_patient_reports(studies: &[StudyReport]) -> Vec<PatientReport> {
+    let mut patients: BTreeMap<String, PatientReport> = BTreeMap::new();
+
+    for study in studies {
+        let patient_id = study.patient_id.clone().unwrap_or_else(|| "UNKNOWN_PATIENT".to_string());
+        let report = patients.entry(patient_id.clone())
+            .or_insert_with(|| PatientReport { patient_id, ..Default::default() });
+
+        report.studies += 1;
+        report.instances_sent += study.instances_sent;
+        report.instances_failed += study.instances_failed;
+        report.bytes_sent += study.bytes_sent;
+        report.bytes_forwarded += study.bytes_forwarded;
+        report.transfer_time_ms += study.transfer_time_ms;
+        for date in study.earliest_acquisition_date.iter().chain(study.latest_acquisition_date.iter()) {
+            widen_date_range(&mut report.earliest_acquisition_date, &mut report.latest_acquisition_date, date);
+        }
+    }
+
+    for report in patients.values_mut() {
+        report.compression_ratio = compression_ratio(report.bytes_sent, report.bytes_forwarded);
+    }
+    patients.into_values().collect()
+}
+
+/// Original size over the size stored or forwarded, when both are known
+pub fn compression_ratio(original_bytes: u64, encoded_bytes: u64) -> Option<f64> {
+    (original_bytes > 0 && encoded_bytes > 0).then(|| original_bytes as f64 / encoded_bytes as f64)
+}
+
+fn ratio_field(ratio: Option<f64>) -> String {
+    ratio.map(|r| format!("{:.3}", r)).unwrap_or_default()
+}
+
+// DA values (YYYYMMDD) order correctly as strings
+fn widen_date_range(earliest: &mut Option<String>, latest: &mut Option<String>, date: &str) {
+    if earliest.as_deref().is_none_or(|e| date < e) {
+        *earliest = Some(date.to_string());
+    }
+    if latest.as_deref().is_none_or(|l| date > l) {
+        *latest = Some(date.to_string());
+    }
+}
+
+pub fn study_reports_csv(studies: &[StudyReport]) -> String {
+    let mut csv = String::from(
+        "study_instance_uid,patient_id,instances_sent,instances_failed,bytes_sent,bytes_forwarded,compression_ratio,transfer_time_ms,earliest_acquisition_date,latest_acquisition_date\n",
+    );
+    for s in studies {
+        csv.push_str(&format!(
+            "{},{},{},{},{},{},{},{},{},{}\n",
+            csv_field(&s.study_instance_uid),
+            csv_field(s.patient_id.as_deref().unwrap_or("")),
+            s.instances_sent,
+            s.instances_failed,
+            s.bytes_sent,
+            s.bytes_forwarded,
+            ratio_field(s.compression_ratio),
+            s.transfer_time_ms,
+            csv_field(s.earliest_acquisition_date.as_deref().unwrap_or("")),
+            csv_field(s.latest_acquisition_date.as_deref().unwrap_or("")),
+        ));
+    }
+    csv
+}
+
+pub fn patient_reports_csv(patients: &[PatientReport]) -> String {
+    let mut csv = String::from(
+        "patient_id,studies,instances_sent,instances_failed,bytes_sent,bytes_forwarded,compression_ratio,transfer_time_ms,earliest_acquisition_date,latest_acquisition_date\n",
+    );
+    for p in patients {
+        csv.push_str(&format!(
+            "{},{},{},{},{},{},{},{},{},{}\n",
+            csv_field(&p.patient_id),
+            p.studies,
+            p.instances_sent,
+            p.instances_failed,
+            p.bytes_sent,
+            p.bytes_forwarded,
+            ratio_field(p.compression_ratio),
+            p.transfer_time_ms,
+            csv_field(p.earliest_acquisition_date.as_deref().unwrap_or("")),
+            csv_field(p.latest_acquisition_date.as_deref().unwrap_or("")),
+        ));
+    }
+    csv
+}
+
+/// Quote a CSV field if it contains a delimiter, quote or line break
+pub fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(path: &str, study: &str, patient: &str, date: &str) -> DicomFile {
+        DicomFile::test(path, &format!("{}.{}", study, path.len()))
+            .with_study(study)
+            .with_size(100)
+            .with_patient(patient, "20240101", date)
+    }
+
+    fn result(file: &DicomFile, success: bool) -> TransferResult {
+        TransferResult {
+            file_path: file.path.display().to_string(),
+            study_instance_uid: file.study_instance_uid.clone(),
+            sop_instance_uid: file.sop_instance_uid.clone(),
+            success,
+            error_message: None,
+            transfer_time_ms: 10,
+            file_size: file.file_size,
+            bytes_sent: if success { file.file_size / 2 } else { 0 },
+            timestamp: chrono::Utc::now(),
+            thread_id: 0,
+        }
+    }
+
+    #[test]
+    fn test_study_and_patient_aggregation() {
+        let files = vec![
+            file("a.dcm", "1.1", "PAT1", "20240102"),
+            file("bb.dcm", "1.1", "PAT1", "20240105"),
+            file("ccc.dcm", "1.2", "PAT1", "20231231"),
+        ];
+        let results = vec![result(&files[0], true), result(&files[1], false), result(&files[2], true)];
+
+        let studies = build_study_reports(&files, &results);
+        assert_eq!(studies.len(), 2);
+        assert_eq!(studies[0].instances_sent, 1);
+        assert_eq!(studies[0].instances_failed, 1);
+        assert_eq!(studies[0].bytes_sent, 100);
+        assert_eq!(studies[0].bytes_forwarded, 50);
+        assert_eq!(studies[0].compression_ratio, Some(2.0));
+        assert_eq!(studies[0].earliest_acquisition_date.as_deref(), Some("20240102"));
+        assert_eq!(studies[0].latest_acquisition_date.as_deref(), Some("20240105"));
+
+        let patients = build_patient_reports(&studies);
+        assert_eq!(patients.len(), 1);
+        assert_eq!(patients[0].studies, 2);
+        assert_eq!(patients[0].instances_sent, 2);
+        assert_eq!(patients[0].earliest_acquisition_date.as_deref(), Some("20231231"));
+        assert_eq!((patients[0].bytes_sent, patients[0].bytes_forwarded), (200, 100));
+        assert_eq!(study_reports_csv(&studies).lines().nth(1), Some("1.1,PAT1,1,1,100,50,2.000,20,20240102,20240105"));
+        assert_eq!(compression_ratio(100, 0), None);
+    }
+
+    #[test]
+    fn test_csv_field_quoting() {
+        assert_eq!(csv_field("PAT1"), "PAT1");
+        assert_eq!(csv_field("Doe, John"), "\"Doe, John\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+}
@@ -0,0 +1,143 @@
+//! Sparse resume of interrupted studies
+//!
+//! A modality whose transfer broke off, or that sends a study again after the
+//! receiver restarted, resends instances that are already stored. With
+//! `--resume-studies` the receiver keeps, per study, the set of instances it
+//! stored in `<output>/received_instances/<StudyInstanceUID>.tsv` (SOP Instance
+//! UID and stored path, one per line) and loads them again at startup. A
+//! C-STORE for an instance in the set whose file is still there is answered
+//! from its command per the duplicate policy: the data set's fragments are
+//! discarded instead of buffered, parsed and written, so only the missing
+//! instances of a huge study cost the receiver any work.
+
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Directory under the output directory holding the per-study sets
+pub const RECEIVED_INSTANCES_DIR: &str = "received_instances";
+
+/// Set file of instances without a usable Study Instance UID
+const UNKNOWN_STUDY: &str = "unknown";
+
+/// How an instance that was already stored is answered when it arrives again
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum DuplicatePolicy {
+    /// Answer Success without storing it again
+    #[default]
+    Acknowledge,
+    /// Answer with Duplicate SOP Instance (0111H) without storing it again
+    Refuse,
+    /// Receive and store it again, as without resume
+    Overwrite,
+}
+
+impl DuplicatePolicy {
+    /// Status answering an instance already stored, unless it is to be stored again
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            DuplicatePolicy::Acknowledge => Some(0x0000),
+            DuplicatePolicy::Refuse => Some(0x0111),
+            DuplicatePolicy::Overwrite => None,
+        }
+    }
+}
+
+/// Instances stored so far, by SOP Instance UID, persisted per study
+#[derive(Debug)]
+pub struct ReceivedInstances {
+    dir: PathBuf,
+    stored: HashMap<String, PathBuf>,
+}
+
+impl ReceivedInstances {
+    /// Load the sets in `dir`, creating it if needed
+    pub fn open(dir: &Path) -> Result<Self> {
+        std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+        let mut stored = HashMap::new();
+        for entry in std::fs::read_dir(dir).with_context(|| format!("Failed to read {}", dir.display()))? {
+            let path = entry?.path();
+            if path.extension().is_none_or(|ext| ext != "tsv") {
+                continue;
+            }
+            let text = std::fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+            // A line cut short by a crash has no path and is skipped
+            for (sop_instance_uid, file) in text.lines().filter_map(|line| line.split_once('\t')) {
+                stored.insert(sop_instance_uid.to_string(), PathBuf::from(file));
+            }
+        }
+        Ok(Self { dir: dir.to_path_buf(), stored })
+    }
+
+    /// Where the instance was stored, if it was and the file is still there
+    pub fn stored_path(&self, sop_instance_uid: &str) -> Option<&Path> {
+        self.stored.get(sop_instance_uid).map(PathBuf::as_path).filter(|path| path.exists())
+    }
+
+    /// Add a stored instance to the set of its study
+    pub fn record(&mut self, study_instance_uid: &str, sop_instance_uid: &str, path: &Path) -> Result<()> {
+        if self.stored.get(sop_instance_uid).is_some_and(|stored| stored == path) {
+            return Ok(());
+        }
+        let file = self.dir.join(format!("{}.tsv", set_name(study_instance_uid)));
+        let mut set = std::fs::OpenOptions::new().create(true).append(true).open(&file)
+            .with_context(|| format!("Failed to open {}", file.display()))?;
+        writeln!(set, "{}\t{}", sop_instance_uid, path.display())
+            .with_context(|| format!("Failed to write {}", file.display()))?;
+        self.stored.insert(sop_instance_uid.to_string(), path.to_path_buf());
+        Ok(())
+    }
+
+    /// Instances in all sets
+    pub fn len(&self) -> usize {
+        self.stored.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stored.is_empty()
+    }
+}
+
+// Study Instance UIDs are digits and dots; anything else must not reach the file system
+fn set_name(study_instance_uid: &str) -> &str {
+    if !study_instance_uid.is_empty() && study_instance_uid.bytes().all(|b| b.is_ascii_digit() || b == b'.')
+        && !study_instance_uid.starts_with('.') {
+        study_instance_uid
+    } else {
+        UNKNOWN_STUDY
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sets_survive_restart() {
+        let output = std::env::temp_dir().join(format!("resume_test_{}", uuid::Uuid::new_v4()));
+        let dir = output.join(RECEIVED_INSTANCES_DIR);
+        let stored = output.join("1.2.3.4.dcm");
+        std::fs::create_dir_all(&output).unwrap();
+        std::fs::write(&stored, b"DICM").unwrap();
+
+        let mut received = ReceivedInstances::open(&dir).unwrap();
+        assert!(received.is_empty());
+        received.record("1.2.3", "1.2.3.4", &stored).unwrap();
+        received.record("1.2.3", "1.2.3.5", &output.join("deleted.dcm")).unwrap();
+        received.record("../../etc", "1.2.3.6", &stored).unwrap();
+        assert!(dir.join("1.2.3.tsv").exists());
+        assert!(dir.join("unknown.tsv").exists());
+
+        let reopened = ReceivedInstances::open(&dir).unwrap();
+        assert_eq!(reopened.len(), 3);
+        assert_eq!(reopened.stored_path("1.2.3.4"), Some(stored.as_path()));
+        // Stored once, but the file is gone: it must be received again
+        assert_eq!(reopened.stored_path("1.2.3.5"), None);
+        assert_eq!(reopened.stored_path("9.9"), None);
+
+        assert_eq!(DuplicatePolicy::Acknowledge.status(), Some(0x0000));
+        assert_eq!(DuplicatePolicy::Overwrite.status(), None);
+        std::fs::remove_dir_all(&output).unwrap();
+    }
+}
@@ -0,0 +1,83 @@
+//! Handling of retired SOP classes and transfer syntaxes
+//!
+//! Old modalities still propose SOP classes and transfer syntaxes the standard
+//! has retired. By default the receiver accepts them like any other. It can
+//! refuse them in negotiation instead, or accept them but convert: prefer a
+//! current transfer syntax whenever the peer proposes one alongside a retired
+//! one, and re-encode objects that still arrive in a retired syntax with native
+//! pixel data in Explicit VR Little Endian. Encapsulated pixel data in a retired
+//! JPEG process cannot be decoded by this build and is stored as received.
+
+use super::sop_classes::SopClassRegistry;
+use super::transfer_syntaxes::TransferSyntaxRegistry;
+use super::uids::ts;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum RetiredPolicy {
+    /// Negotiate and store retired SOP classes and transfer syntaxes as any other
+    #[default]
+    Accept,
+    /// Reject presentation contexts of retired SOP classes, and retired transfer syntaxes
+    Refuse,
+    /// Prefer current transfer syntaxes and convert objects received in retired ones
+    Convert,
+}
+
+impl RetiredPolicy {
+    /// Whether a presentation context of this abstract syntax may be accepted
+    pub fn accepts_sop_class(&self, uid: &str) -> bool {
+        *self != RetiredPolicy::Refuse || !SopClassRegistry::global().is_retired(uid)
+    }
+
+    /// Transfer syntax to accept from a context's `proposed` ones, of those
+    /// `supported`: the proposer's first, but a current one ahead of retired
+    /// ones unless retired syntaxes are simply accepted
+    pub fn choose_transfer_syntax<'a>(&self, proposed: &'a [String], supported: impl Fn(&str) -> bool) -> Option<&'a str> {
+        let registry = TransferSyntaxRegistry::global();
+        let first = |retired: bool| proposed.iter()
+            .find(|ts| supported(ts) && registry.is_retired(ts) == retired)
+            .map(String::as_str);
+        match self {
+            RetiredPolicy::Accept => proposed.iter().find(|ts| supported(ts)).map(String::as_str),
+            RetiredPolicy::Refuse => first(false),
+            RetiredPolicy::Convert => first(false).or_else(|| first(true)),
+        }
+    }
+
+    /// Whether an object received in this transfer syntax is re-encoded in
+    /// Explicit VR Little Endian; the retired Big Endian syntax always is
+    pub fn converts(&self, transfer_syntax_uid: &str) -> bool {
+        let registry = TransferSyntaxRegistry::global();
+        transfer_syntax_uid == ts::EXPLICIT_VR_BIG_ENDIAN
+            || (*self == RetiredPolicy::Convert && registry.is_retired(transfer_syntax_uid)
+                && !registry.is_compressed(transfer_syntax_uid) && registry.codec_support(transfer_syntax_uid).dataset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::uids::sop;
+
+    #[test]
+    fn test_retired_policy() {
+        assert!(SopClassRegistry::global().is_retired(sop::ULTRASOUND_MULTI_FRAME_IMAGE_STORAGE_RETIRED));
+        assert!(!SopClassRegistry::global().is_retired(sop::CT_IMAGE_STORAGE));
+        assert!(TransferSyntaxRegistry::global().is_retired(ts::EXPLICIT_VR_BIG_ENDIAN));
+        assert!(!RetiredPolicy::Refuse.accepts_sop_class(sop::ULTRASOUND_MULTI_FRAME_IMAGE_STORAGE_RETIRED));
+        assert!(RetiredPolicy::Convert.accepts_sop_class(sop::ULTRASOUND_MULTI_FRAME_IMAGE_STORAGE_RETIRED));
+
+        let proposed = vec![ts::EXPLICIT_VR_BIG_ENDIAN.to_string(), ts::IMPLICIT_VR_LITTLE_ENDIAN.to_string()];
+        let all = |_: &str| true;
+        assert_eq!(RetiredPolicy::Accept.choose_transfer_syntax(&proposed, all), Some(ts::EXPLICIT_VR_BIG_ENDIAN));
+        assert_eq!(RetiredPolicy::Refuse.choose_transfer_syntax(&proposed, all), Some(ts::IMPLICIT_VR_LITTLE_ENDIAN));
+        assert_eq!(RetiredPolicy::Convert.choose_transfer_syntax(&proposed, all), Some(ts::IMPLICIT_VR_LITTLE_ENDIAN));
+
+        let retired_only = &proposed[..1];
+        assert_eq!(RetiredPolicy::Refuse.choose_transfer_syntax(retired_only, all), None);
+        assert_eq!(RetiredPolicy::Convert.choose_transfer_syntax(retired_only, all), Some(ts::EXPLICIT_VR_BIG_ENDIAN));
+
+        assert!(RetiredPolicy::Accept.converts(ts::EXPLICIT_VR_BIG_ENDIAN));
+        assert!(!RetiredPolicy::Convert.converts(ts::EXPLICIT_VR_LITTLE_ENDIAN));
+    }
+}
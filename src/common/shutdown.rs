@@ -0,0 +1,167 @@
+//! Graceful shutdown of the receiver
+//!
+//! Once a shutdown is requested no new association is accepted. Associations
+//! sitting idle between objects are closed right away; those in the middle of
+//! a C-STORE finish it, answer it and are then aborted. Whatever is still
+//! receiving when the grace period runs out is closed forcibly, and its
+//! incomplete data sets are kept as partial files for the next recovery pass.
+//! The receiver and the relay request it on `shutdown_signal`.
+
+use std::collections::HashMap;
+use std::net::{SocketAddr, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+#[derive(Debug)]
+struct Connection {
+    /// A handle on the association's socket, to close it from another thread
+    stream: TcpStream,
+    /// Whether an object is being received on it
+    busy: bool,
+}
+
+/// Open associations and whether the receiver is shutting down
+#[derive(Debug, Default)]
+pub struct Shutdown {
+    requested: AtomicBool,
+    connections: Mutex<HashMap<SocketAddr, Connection>>,
+}
+
+/// Keeps an association known to the shutdown until dropped
+#[derive(Debug)]
+pub struct Registration<'a> {
+    shutdown: &'a Shutdown,
+    addr: SocketAddr,
+}
+
+impl Drop for Registration<'_> {
+    fn drop(&mut self) {
+        self.shutdown.connections().remove(&self.addr);
+    }
+}
+
+impl Shutdown {
+    fn connections(&self) -> MutexGuard<'_, HashMap<SocketAddr, Connection>> {
+        self.connections.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn is_requested(&self) -> bool {
+        self.requested.load(Ordering::SeqCst)
+    }
+
+    /// Track the association on `stream`, idle to begin with; one arriving
+    /// after the shutdown was requested is closed at once
+    pub fn register(&self, addr: SocketAddr, stream: &TcpStream) -> std::io::Result<Registration<'_>> {
+        let stream = stream.try_clone()?;
+        let mut connections = self.connections();
+        if self.is_requested() {
+            let _ = stream.shutdown(std::net::Shutdown::Both);
+        }
+        connections.insert(addr, Connection { stream, busy: false });
+        Ok(Registration { shutdown: self, addr })
+    }
+
+    /// Record whether an object is being received on the association from
+    /// `addr`; returns whether a shutdown was requested
+    pub fn set_busy(&self, addr: SocketAddr, busy: bool) -> bool {
+        let mut connections = self.connections();
+        if let Some(connection) = connections.get_mut(&addr) {
+            connection.busy = busy;
+        }
+        self.is_requested()
+    }
+
+    /// Stop taking on work: close the idle associations, returning how many
+    /// are still busy receiving an object
+    pub fn request(&self) -> usize {
+        let connections = self.connections();
+        self.requested.store(true, Ordering::SeqCst);
+        let mut busy = 0;
+        for connection in connections.values() {
+            if connection.busy {
+                busy += 1;
+            } else {
+                let _ = connection.stream.shutdown(std::net::Shutdown::Both);
+            }
+        }
+        busy
+    }
+
+    /// Close every association, busy or not, returning how many there were
+    pub fn force(&self) -> usize {
+        let connections = self.connections();
+        for connection in connections.values() {
+            let _ = connection.stream.shutdown(std::net::Shutdown::Both);
+        }
+        connections.len()
+    }
+}
+
+/// Resolves on Ctrl-C (SIGINT) or, on Unix, SIGTERM
+pub async fn shutdown_signal() {
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                tracing::warn!("Cannot listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    let interrupt = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::warn!("Cannot listen for Ctrl-C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    tokio::select! {
+        () = interrupt => {}
+        () = terminate => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use std::net::TcpListener;
+
+    fn connection_pair(listener: &TcpListener) -> (TcpStream, TcpStream, SocketAddr) {
+        let client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (server, addr) = listener.accept().unwrap();
+        (client, server, addr)
+    }
+
+    #[test]
+    fn test_idle_associations_close_first() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let (mut idle_client, idle_server, idle_addr) = connection_pair(&listener);
+        let (mut busy_client, busy_server, busy_addr) = connection_pair(&listener);
+
+        let shutdown = Shutdown::default();
+        let _idle = shutdown.register(idle_addr, &idle_server).unwrap();
+        let busy = shutdown.register(busy_addr, &busy_server).unwrap();
+        assert!(!shutdown.set_busy(busy_addr, true));
+
+        assert_eq!(shutdown.request(), 1);
+        assert!(shutdown.set_busy(busy_addr, true), "the busy association learns of the shutdown");
+        assert_eq!(idle_client.read(&mut [0; 1]).unwrap(), 0, "the idle association is closed");
+
+        busy_client.set_nonblocking(true).unwrap();
+        assert_eq!(busy_client.read(&mut [0; 1]).unwrap_err().kind(), std::io::ErrorKind::WouldBlock);
+        busy_client.set_nonblocking(false).unwrap();
+
+        assert_eq!(shutdown.force(), 2);
+        assert_eq!(busy_client.read(&mut [0; 1]).unwrap(), 0);
+
+        drop(busy);
+        assert_eq!(shutdown.force(), 1);
+    }
+}
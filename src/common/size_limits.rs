@@ -0,0 +1,88 @@
+/// Inbound object size limits per SOP class category
+///
+/// Limits protect storage from runaway or malicious pushes while still
+/// allowing legitimately large objects (e.g. whole-slide microscopy) where
+/// they are expected. The most specific limit applies: the category limit
+/// if one is configured, otherwise the default limit, otherwise none.
+
+use super::sop_classes::{SopClassCategory, SopClassRegistry};
+
+#[derive(Debug, Clone, Default)]
+pub struct SizeLimits {
+    pub default_limit: Option<u64>,
+    pub category_limits: Vec<(SopClassCategory, u64)>,
+}
+
+impl SizeLimits {
+    pub fn is_empty(&self) -> bool {
+        self.default_limit.is_none() && self.category_limits.is_empty()
+    }
+
+    /// Maximum accepted size in bytes for objects of the given SOP class
+    pub fn limit_for(&self, sop_class_uid: &str, registry: &SopClassRegistry) -> Option<u64> {
+        registry.get(sop_class_uid)
+            .and_then(|info| {
+                self.category_limits.iter()
+                    .find(|(category, _)| *category == info.category)
+                    .map(|(_, limit)| *limit)
+            })
+            .or(self.default_limit)
+    }
+}
+
+/// Parse a size such as `2GB`, `500MB`, `64KiB` or `1048576`; units are powers of 1024
+pub fn parse_size(value: &str) -> Result<u64, String> {
+    let value = value.trim();
+    let split = value.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: f64 = number.parse().map_err(|_| format!("invalid size '{}'", value))?;
+
+    let multiplier: u64 = match unit.trim().to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" | "KIB" => 1 << 10,
+        "M" | "MB" | "MIB" => 1 << 20,
+        "G" | "GB" | "GIB" => 1 << 30,
+        "T" | "TB" | "TIB" => 1 << 40,
+        other => return Err(format!("unknown size unit '{}'", other)),
+    };
+
+    Ok((number * multiplier as f64) as u64)
+}
+
+/// Parse a `Category=SIZE` limit, e.g. `SecondaryCapture=2GB`
+pub fn parse_category_limit(value: &str) -> Result<(SopClassCategory, u64), String> {
+    let (name, size) = value.split_once('=')
+        .ok_or_else(|| format!("expected CATEGORY=SIZE, got '{}'", value))?;
+    let category = SopClassCategory::from_name(name.trim())
+        .ok_or_else(|| format!("unknown SOP class category '{}'", name.trim()))?;
+    Ok((category, parse_size(size)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_sizes() {
+        assert_eq!(parse_size("1024"), Ok(1024));
+        assert_eq!(parse_size("2GB"), Ok(2 * 1024 * 1024 * 1024));
+        assert_eq!(parse_size("1.5 MiB"), Ok(1536 * 1024));
+        assert!(parse_size("12 parsecs").is_err());
+        assert_eq!(
+            parse_category_limit("Microscopy=50GB"),
+            Ok((SopClassCategory::Microscopy, 50 * 1024 * 1024 * 1024))
+        );
+    }
+
+    #[test]
+    fn test_category_limit_overrides_default() {
+        let registry = SopClassRegistry::new();
+        let limits = SizeLimits {
+            default_limit: Some(100),
+            category_limits: vec![(SopClassCategory::SecondaryCapture, 10)],
+        };
+        assert_eq!(limits.limit_for("1.2.840.10008.5.1.4.1.1.7", &registry), Some(10));
+        assert_eq!(limits.limit_for("1.2.840.10008.5.1.4.1.1.2", &registry), Some(100));
+        assert_eq!(limits.limit_for("1.2.3.4.5", &registry), Some(100));
+    }
+}
@@ -3,7 +3,9 @@
 /// This module provides a wide range of SOP Class UIDs covering most common
 /// DICOM use cases including imaging, structured reporting, and specialized modalities.
 
+use once_cell::sync::Lazy;
 use std::collections::HashMap;
+use super::uids::sop;
 
 #[derive(Debug, Clone)]
 pub struct SopClassInfo {
@@ -54,10 +56,52 @@ pub enum SopClassCategory {
     Other,
 }
 
+impl SopClassCategory {
+    pub const ALL: &'static [SopClassCategory] = &[
+        SopClassCategory::ComputedRadiography,
+        SopClassCategory::ComputedTomography,
+        SopClassCategory::MagneticResonance,
+        SopClassCategory::Ultrasound,
+        SopClassCategory::NuclearMedicine,
+        SopClassCategory::DigitalRadiography,
+        SopClassCategory::DigitalMammography,
+        SopClassCategory::PetCt,
+        SopClassCategory::OpticalCoherenceTomography,
+        SopClassCategory::Endoscopy,
+        SopClassCategory::Microscopy,
+        SopClassCategory::StructuredReporting,
+        SopClassCategory::Presentation,
+        SopClassCategory::Waveform,
+        SopClassCategory::RawData,
+        SopClassCategory::SecondaryCapture,
+        SopClassCategory::KeyObjectSelection,
+        SopClassCategory::Enhanced,
+        SopClassCategory::MultiFrame,
+        SopClassCategory::Radiotherapy,
+        SopClassCategory::Ophthalmology,
+        SopClassCategory::Dermatology,
+        SopClassCategory::Dental,
+        SopClassCategory::Legacy,
+        SopClassCategory::Other,
+    ];
+
+    /// Look up a category by its name, ignoring case (e.g. "SecondaryCapture")
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter()
+            .find(|category| format!("{:?}", category).eq_ignore_ascii_case(name))
+            .cloned()
+    }
+}
+
 impl SopClassInfo {
     pub const fn new(uid: &'static str, name: &'static str, category: SopClassCategory) -> Self {
         Self { uid, name, category }
     }
+
+    /// Retired from the standard; such classes are named "... (Retired)"
+    pub fn is_retired(&self) -> bool {
+        self.name.ends_with("(Retired)")
+    }
 }
 
 /// Comprehensive SOP Class registry
@@ -66,7 +110,14 @@ pub struct SopClassRegistry {
     classes: HashMap<&'static str, SopClassInfo>,
 }
 
+static REGISTRY: Lazy<SopClassRegistry> = Lazy::new(SopClassRegistry::new);
+
 impl SopClassRegistry {
+    /// The registry shared by the whole process, built on first use
+    pub fn global() -> &'static Self {
+        &REGISTRY
+    }
+
     pub fn new() -> Self {
         let mut classes = HashMap::new();
         
@@ -99,6 +150,29 @@ impl SopClassRegistry {
     pub fn get_name(&self, uid: &str) -> Option<&'static str> {
         self.get(uid).map(|sc| sc.name)
     }
+
+    /// Whether a registered SOP class is retired; unknown ones are not
+    pub fn is_retired(&self, uid: &str) -> bool {
+        self.get(uid).is_some_and(SopClassInfo::is_retired)
+    }
+
+    /// Look up by UID, or by name or keyword ignoring case, spaces and punctuation
+    /// (so "CTImageStorage" and "ct image storage" both find CT Image Storage)
+    pub fn find(&self, query: &str) -> Option<&SopClassInfo> {
+        let query = query.trim();
+        self.get(query).or_else(|| {
+            let keyword = keyword_of(query);
+            ALL_SOP_CLASSES.iter().find(|sc| keyword_of(sc.name) == keyword)
+        })
+    }
+}
+
+/// Name reduced to lowercase letters and digits, for keyword matching
+pub fn keyword_of(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
 }
 
 impl Default for SopClassRegistry {
@@ -115,233 +189,233 @@ const ALL_SOP_CLASSES: &[SopClassInfo] = &[
     
     // Computed Radiography
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.1",
+        sop::COMPUTED_RADIOGRAPHY_IMAGE_STORAGE,
         "Computed Radiography Image Storage",
         SopClassCategory::ComputedRadiography,
     ),
     
     // Digital X-Ray
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.1.1",
+        sop::DIGITAL_X_RAY_IMAGE_STORAGE_FOR_PRESENTATION,
         "Digital X-Ray Image Storage - For Presentation",
         SopClassCategory::DigitalRadiography,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.1.1.1",
+        sop::DIGITAL_X_RAY_IMAGE_STORAGE_FOR_PROCESSING,
         "Digital X-Ray Image Storage - For Processing",
         SopClassCategory::DigitalRadiography,
     ),
     
     // Digital Mammography
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.1.2",
+        sop::DIGITAL_MAMMOGRAPHY_X_RAY_IMAGE_STORAGE_FOR_PRESENTATION,
         "Digital Mammography X-Ray Image Storage - For Presentation",
         SopClassCategory::DigitalMammography,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.1.2.1",
+        sop::DIGITAL_MAMMOGRAPHY_X_RAY_IMAGE_STORAGE_FOR_PROCESSING,
         "Digital Mammography X-Ray Image Storage - For Processing",
         SopClassCategory::DigitalMammography,
     ),
     
     // Digital Intra-Oral X-Ray
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.1.3",
+        sop::DIGITAL_INTRA_ORAL_X_RAY_IMAGE_STORAGE_FOR_PRESENTATION,
         "Digital Intra-Oral X-Ray Image Storage - For Presentation",
         SopClassCategory::Dental,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.1.3.1",
+        sop::DIGITAL_INTRA_ORAL_X_RAY_IMAGE_STORAGE_FOR_PROCESSING,
         "Digital Intra-Oral X-Ray Image Storage - For Processing",
         SopClassCategory::Dental,
     ),
     
     // CT Image Storage
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.2",
+        sop::CT_IMAGE_STORAGE,
         "CT Image Storage",
         SopClassCategory::ComputedTomography,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.2.1",
+        sop::ENHANCED_CT_IMAGE_STORAGE,
         "Enhanced CT Image Storage",
         SopClassCategory::Enhanced,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.2.2",
+        sop::LEGACY_CONVERTED_ENHANCED_CT_IMAGE_STORAGE,
         "Legacy Converted Enhanced CT Image Storage",
         SopClassCategory::Legacy,
     ),
     
     // Ultrasound
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.3.1",
+        sop::ULTRASOUND_MULTI_FRAME_IMAGE_STORAGE,
         "Ultrasound Multi-frame Image Storage",
         SopClassCategory::Ultrasound,
     ),
     
     // MR Image Storage
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.4",
+        sop::MR_IMAGE_STORAGE,
         "MR Image Storage",
         SopClassCategory::MagneticResonance,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.4.1",
+        sop::ENHANCED_MR_IMAGE_STORAGE,
         "Enhanced MR Image Storage",
         SopClassCategory::Enhanced,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.4.2",
+        sop::MR_SPECTROSCOPY_STORAGE,
         "MR Spectroscopy Storage",
         SopClassCategory::MagneticResonance,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.4.3",
+        sop::ENHANCED_MR_COLOR_IMAGE_STORAGE,
         "Enhanced MR Color Image Storage",
         SopClassCategory::Enhanced,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.4.4",
+        sop::LEGACY_CONVERTED_ENHANCED_MR_IMAGE_STORAGE,
         "Legacy Converted Enhanced MR Image Storage",
         SopClassCategory::Legacy,
     ),
     
     // Nuclear Medicine
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.5",
+        sop::NUCLEAR_MEDICINE_IMAGE_STORAGE_RETIRED,
         "Nuclear Medicine Image Storage (Retired)",
         SopClassCategory::Legacy,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.20",
+        sop::NUCLEAR_MEDICINE_IMAGE_STORAGE,
         "Nuclear Medicine Image Storage",
         SopClassCategory::NuclearMedicine,
     ),
     
     // Ultrasound Image Storage
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.6.1",
+        sop::ULTRASOUND_IMAGE_STORAGE,
         "Ultrasound Image Storage",
         SopClassCategory::Ultrasound,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.6.2",
+        sop::ENHANCED_US_VOLUME_STORAGE,
         "Enhanced US Volume Storage",
         SopClassCategory::Enhanced,
     ),
     
     // Secondary Capture
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.7",
+        sop::SECONDARY_CAPTURE_IMAGE_STORAGE,
         "Secondary Capture Image Storage",
         SopClassCategory::SecondaryCapture,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.7.1",
+        sop::MULTI_FRAME_SINGLE_BIT_SECONDARY_CAPTURE_IMAGE_STORAGE,
         "Multi-frame Single Bit Secondary Capture Image Storage",
         SopClassCategory::SecondaryCapture,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.7.2",
+        sop::MULTI_FRAME_GRAYSCALE_BYTE_SECONDARY_CAPTURE_IMAGE_STORAGE,
         "Multi-frame Grayscale Byte Secondary Capture Image Storage",
         SopClassCategory::SecondaryCapture,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.7.3",
+        sop::MULTI_FRAME_GRAYSCALE_WORD_SECONDARY_CAPTURE_IMAGE_STORAGE,
         "Multi-frame Grayscale Word Secondary Capture Image Storage",
         SopClassCategory::SecondaryCapture,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.7.4",
+        sop::MULTI_FRAME_TRUE_COLOR_SECONDARY_CAPTURE_IMAGE_STORAGE,
         "Multi-frame True Color Secondary Capture Image Storage",
         SopClassCategory::SecondaryCapture,
     ),
     
     // X-Ray Angiographic
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.12.1",
+        sop::X_RAY_ANGIOGRAPHIC_IMAGE_STORAGE,
         "X-Ray Angiographic Image Storage",
         SopClassCategory::DigitalRadiography,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.12.1.1",
+        sop::ENHANCED_XA_IMAGE_STORAGE,
         "Enhanced XA Image Storage",
         SopClassCategory::Enhanced,
     ),
     
     // X-Ray Radiofluoroscopic
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.12.2",
+        sop::X_RAY_RADIOFLUOROSCOPIC_IMAGE_STORAGE,
         "X-Ray Radiofluoroscopic Image Storage",
         SopClassCategory::DigitalRadiography,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.12.2.1",
+        sop::ENHANCED_XRF_IMAGE_STORAGE,
         "Enhanced XRF Image Storage",
         SopClassCategory::Enhanced,
     ),
     
     // PET
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.128",
+        sop::POSITRON_EMISSION_TOMOGRAPHY_IMAGE_STORAGE,
         "Positron Emission Tomography Image Storage",
         SopClassCategory::NuclearMedicine,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.130",
+        sop::ENHANCED_PET_IMAGE_STORAGE,
         "Enhanced PET Image Storage",
         SopClassCategory::Enhanced,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.131",
+        sop::LEGACY_CONVERTED_ENHANCED_PET_IMAGE_STORAGE,
         "Legacy Converted Enhanced PET Image Storage",
         SopClassCategory::Legacy,
     ),
     
     // RT (Radiotherapy)
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.481.1",
+        sop::RT_IMAGE_STORAGE,
         "RT Image Storage",
         SopClassCategory::Radiotherapy,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.481.2",
+        sop::RT_DOSE_STORAGE,
         "RT Dose Storage",
         SopClassCategory::Radiotherapy,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.481.3",
+        sop::RT_STRUCTURE_SET_STORAGE,
         "RT Structure Set Storage",
         SopClassCategory::Radiotherapy,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.481.4",
+        sop::RT_BEAMS_TREATMENT_RECORD_STORAGE,
         "RT Beams Treatment Record Storage",
         SopClassCategory::Radiotherapy,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.481.5",
+        sop::RT_PLAN_STORAGE,
         "RT Plan Storage",
         SopClassCategory::Radiotherapy,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.481.6",
+        sop::RT_BRACHY_TREATMENT_RECORD_STORAGE,
         "RT Brachy Treatment Record Storage",
         SopClassCategory::Radiotherapy,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.481.7",
+        sop::RT_TREATMENT_SUMMARY_RECORD_STORAGE,
         "RT Treatment Summary Record Storage",
         SopClassCategory::Radiotherapy,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.481.8",
+        sop::RT_ION_PLAN_STORAGE,
         "RT Ion Plan Storage",
         SopClassCategory::Radiotherapy,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.481.9",
+        sop::RT_ION_BEAMS_TREATMENT_RECORD_STORAGE,
         "RT Ion Beams Treatment Record Storage",
         SopClassCategory::Radiotherapy,
     ),
@@ -351,77 +425,77 @@ const ALL_SOP_CLASSES: &[SopClassInfo] = &[
     // =============================================================================
     
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.9.1.1",
+        sop::TWELVE_LEAD_ECG_WAVEFORM_STORAGE,
         "12-lead ECG Waveform Storage",
         SopClassCategory::Waveform,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.9.1.2",
+        sop::GENERAL_ECG_WAVEFORM_STORAGE,
         "General ECG Waveform Storage",
         SopClassCategory::Waveform,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.9.1.3",
+        sop::AMBULATORY_ECG_WAVEFORM_STORAGE,
         "Ambulatory ECG Waveform Storage",
         SopClassCategory::Waveform,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.9.2.1",
+        sop::HEMODYNAMIC_WAVEFORM_STORAGE,
         "Hemodynamic Waveform Storage",
         SopClassCategory::Waveform,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.9.3.1",
+        sop::CARDIAC_ELECTROPHYSIOLOGY_WAVEFORM_STORAGE,
         "Cardiac Electrophysiology Waveform Storage",
         SopClassCategory::Waveform,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.9.4.1",
+        sop::BASIC_VOICE_AUDIO_WAVEFORM_STORAGE,
         "Basic Voice Audio Waveform Storage",
         SopClassCategory::Waveform,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.9.4.2",
+        sop::GENERAL_AUDIO_WAVEFORM_STORAGE,
         "General Audio Waveform Storage",
         SopClassCategory::Waveform,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.9.5.1",
+        sop::ARTERIAL_PULSE_WAVEFORM_STORAGE,
         "Arterial Pulse Waveform Storage",
         SopClassCategory::Waveform,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.9.6.1",
+        sop::RESPIRATORY_WAVEFORM_STORAGE,
         "Respiratory Waveform Storage",
         SopClassCategory::Waveform,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.9.6.2",
+        sop::MULTI_CHANNEL_RESPIRATORY_WAVEFORM_STORAGE,
         "Multi-channel Respiratory Waveform Storage",
         SopClassCategory::Waveform,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.9.7.1",
+        sop::ROUTINE_SCALP_ELECTROENCEPHALOGRAM_WAVEFORM_STORAGE,
         "Routine Scalp Electroencephalogram Waveform Storage",
         SopClassCategory::Waveform,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.9.7.2",
+        sop::ELECTROMYOGRAM_WAVEFORM_STORAGE,
         "Electromyogram Waveform Storage",
         SopClassCategory::Waveform,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.9.7.3",
+        sop::ELECTROOCULOGRAM_WAVEFORM_STORAGE,
         "Electrooculogram Waveform Storage",
         SopClassCategory::Waveform,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.9.7.4",
+        sop::SLEEP_ELECTROENCEPHALOGRAM_WAVEFORM_STORAGE,
         "Sleep Electroencephalogram Waveform Storage",
         SopClassCategory::Waveform,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.9.8.1",
+        sop::BODY_POSITION_WAVEFORM_STORAGE,
         "Body Position Waveform Storage",
         SopClassCategory::Waveform,
     ),
@@ -431,87 +505,87 @@ const ALL_SOP_CLASSES: &[SopClassInfo] = &[
     // =============================================================================
     
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.88.11",
+        sop::BASIC_TEXT_SR_STORAGE,
         "Basic Text SR Storage",
         SopClassCategory::StructuredReporting,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.88.22",
+        sop::ENHANCED_SR_STORAGE,
         "Enhanced SR Storage",
         SopClassCategory::StructuredReporting,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.88.33",
+        sop::COMPREHENSIVE_SR_STORAGE,
         "Comprehensive SR Storage",
         SopClassCategory::StructuredReporting,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.88.40",
+        sop::PROCEDURE_LOG_STORAGE,
         "Procedure Log Storage",
         SopClassCategory::StructuredReporting,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.88.50",
+        sop::MAMMOGRAPHY_CAD_SR_STORAGE,
         "Mammography CAD SR Storage",
         SopClassCategory::StructuredReporting,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.88.59",
+        sop::KEY_OBJECT_SELECTION_DOCUMENT_STORAGE,
         "Key Object Selection Document Storage",
         SopClassCategory::KeyObjectSelection,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.88.65",
+        sop::CHEST_CAD_SR_STORAGE,
         "Chest CAD SR Storage",
         SopClassCategory::StructuredReporting,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.88.67",
+        sop::X_RAY_RADIATION_DOSE_SR_STORAGE,
         "X-Ray Radiation Dose SR Storage",
         SopClassCategory::StructuredReporting,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.88.68",
+        sop::RADIOPHARMACEUTICAL_RADIATION_DOSE_SR_STORAGE,
         "Radiopharmaceutical Radiation Dose SR Storage",
         SopClassCategory::StructuredReporting,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.88.69",
+        sop::COLON_CAD_SR_STORAGE,
         "Colon CAD SR Storage",
         SopClassCategory::StructuredReporting,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.88.70",
+        sop::IMPLANTATION_PLAN_SR_STORAGE,
         "Implantation Plan SR Storage",
         SopClassCategory::StructuredReporting,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.88.71",
+        sop::ACQUISITION_CONTEXT_SR_STORAGE,
         "Acquisition Context SR Storage",
         SopClassCategory::StructuredReporting,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.88.72",
+        sop::SIMPLIFIED_ADULT_ECHO_SR_STORAGE,
         "Simplified Adult Echo SR Storage",
         SopClassCategory::StructuredReporting,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.88.73",
+        sop::PATIENT_RADIATION_DOSE_SR_STORAGE,
         "Patient Radiation Dose SR Storage",
         SopClassCategory::StructuredReporting,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.88.74",
+        sop::PLANNED_IMAGING_AGENT_ADMINISTRATION_SR_STORAGE,
         "Planned Imaging Agent Administration SR Storage",
         SopClassCategory::StructuredReporting,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.88.75",
+        sop::PERFORMED_IMAGING_AGENT_ADMINISTRATION_SR_STORAGE,
         "Performed Imaging Agent Administration SR Storage",
         SopClassCategory::StructuredReporting,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.88.76",
+        sop::ENHANCED_X_RAY_RADIATION_DOSE_SR_STORAGE,
         "Enhanced X-Ray Radiation Dose SR Storage",
         SopClassCategory::StructuredReporting,
     ),
@@ -521,37 +595,37 @@ const ALL_SOP_CLASSES: &[SopClassInfo] = &[
     // =============================================================================
     
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.66",
+        sop::RAW_DATA_STORAGE,
         "Raw Data Storage",
         SopClassCategory::RawData,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.66.1",
+        sop::SPATIAL_REGISTRATION_STORAGE,
         "Spatial Registration Storage",
         SopClassCategory::Other,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.66.2",
+        sop::SPATIAL_FIDUCIALS_STORAGE,
         "Spatial Fiducials Storage",
         SopClassCategory::Other,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.66.3",
+        sop::DEFORMABLE_SPATIAL_REGISTRATION_STORAGE,
         "Deformable Spatial Registration Storage",
         SopClassCategory::Other,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.66.4",
+        sop::SEGMENTATION_STORAGE,
         "Segmentation Storage",
         SopClassCategory::Other,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.66.5",
+        sop::SURFACE_SEGMENTATION_STORAGE,
         "Surface Segmentation Storage",
         SopClassCategory::Other,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.66.6",
+        sop::TRACTOGRAPHY_RESULTS_STORAGE,
         "Tractography Results Storage",
         SopClassCategory::Other,
     ),
@@ -561,57 +635,57 @@ const ALL_SOP_CLASSES: &[SopClassInfo] = &[
     // =============================================================================
     
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.11.1",
+        sop::GRAYSCALE_SOFTCOPY_PRESENTATION_STATE_STORAGE,
         "Grayscale Softcopy Presentation State Storage",
         SopClassCategory::Presentation,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.11.2",
+        sop::COLOR_SOFTCOPY_PRESENTATION_STATE_STORAGE,
         "Color Softcopy Presentation State Storage",
         SopClassCategory::Presentation,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.11.3",
+        sop::PSEUDO_COLOR_SOFTCOPY_PRESENTATION_STATE_STORAGE,
         "Pseudo-Color Softcopy Presentation State Storage",
         SopClassCategory::Presentation,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.11.4",
+        sop::BLENDING_SOFTCOPY_PRESENTATION_STATE_STORAGE,
         "Blending Softcopy Presentation State Storage",
         SopClassCategory::Presentation,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.11.5",
+        sop::XA_XRF_GRAYSCALE_SOFTCOPY_PRESENTATION_STATE_STORAGE,
         "XA/XRF Grayscale Softcopy Presentation State Storage",
         SopClassCategory::Presentation,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.11.6",
+        sop::GRAYSCALE_PLANAR_MPR_VOLUMETRIC_PRESENTATION_STATE_STORAGE,
         "Grayscale Planar MPR Volumetric Presentation State Storage",
         SopClassCategory::Presentation,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.11.7",
+        sop::COMPOSITING_PLANAR_MPR_VOLUMETRIC_PRESENTATION_STATE_STORAGE,
         "Compositing Planar MPR Volumetric Presentation State Storage",
         SopClassCategory::Presentation,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.11.8",
+        sop::ADVANCED_BLENDING_PRESENTATION_STATE_STORAGE,
         "Advanced Blending Presentation State Storage",
         SopClassCategory::Presentation,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.11.9",
+        sop::VOLUME_RENDERING_VOLUMETRIC_PRESENTATION_STATE_STORAGE,
         "Volume Rendering Volumetric Presentation State Storage",
         SopClassCategory::Presentation,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.11.10",
+        sop::SEGMENTED_VOLUME_RENDERING_VOLUMETRIC_PRESENTATION_STATE_STORAGE,
         "Segmented Volume Rendering Volumetric Presentation State Storage",
         SopClassCategory::Presentation,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.11.11",
+        sop::MULTIPLE_VOLUME_RENDERING_VOLUMETRIC_PRESENTATION_STATE_STORAGE,
         "Multiple Volume Rendering Volumetric Presentation State Storage",
         SopClassCategory::Presentation,
     ),
@@ -621,82 +695,82 @@ const ALL_SOP_CLASSES: &[SopClassInfo] = &[
     // =============================================================================
     
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.77.1.1",
+        sop::VIDEO_ENDOSCOPIC_IMAGE_STORAGE,
         "Video Endoscopic Image Storage",
         SopClassCategory::Endoscopy,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.77.1.2",
+        sop::VIDEO_MICROSCOPIC_IMAGE_STORAGE,
         "Video Microscopic Image Storage",
         SopClassCategory::Microscopy,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.77.1.3",
+        sop::VIDEO_PHOTOGRAPHIC_IMAGE_STORAGE,
         "Video Photographic Image Storage",
         SopClassCategory::Other,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.77.1.4",
+        sop::OPHTHALMIC_PHOTOGRAPHY_8_BIT_IMAGE_STORAGE,
         "Ophthalmic Photography 8 Bit Image Storage",
         SopClassCategory::Ophthalmology,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.77.1.4.1",
+        sop::OPHTHALMIC_PHOTOGRAPHY_16_BIT_IMAGE_STORAGE,
         "Ophthalmic Photography 16 Bit Image Storage",
         SopClassCategory::Ophthalmology,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.77.1.5.1",
+        sop::STEREOMETRIC_RELATIONSHIP_STORAGE,
         "Stereometric Relationship Storage",
         SopClassCategory::Ophthalmology,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.77.1.5.2",
+        sop::OPHTHALMIC_TOMOGRAPHY_IMAGE_STORAGE,
         "Ophthalmic Tomography Image Storage",
         SopClassCategory::OpticalCoherenceTomography,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.77.1.5.3",
+        sop::WIDE_FIELD_OPHTHALMIC_PHOTOGRAPHY_STEREOGRAPHIC_PROJECTION_IMAGE_STORAGE,
         "Wide Field Ophthalmic Photography Stereographic Projection Image Storage",
         SopClassCategory::Ophthalmology,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.77.1.5.4",
+        sop::WIDE_FIELD_OPHTHALMIC_PHOTOGRAPHY_3D_COORDINATES_IMAGE_STORAGE,
         "Wide Field Ophthalmic Photography 3D Coordinates Image Storage",
         SopClassCategory::Ophthalmology,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.77.1.5.5",
+        sop::OPHTHALMIC_OPTICAL_COHERENCE_TOMOGRAPHY_EN_FACE_IMAGE_STORAGE,
         "Ophthalmic Optical Coherence Tomography En Face Image Storage",
         SopClassCategory::OpticalCoherenceTomography,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.77.1.5.6",
+        sop::OPHTHALMIC_OPTICAL_COHERENCE_TOMOGRAPHY_B_SCAN_VOLUME_ANALYSIS_STORAGE,
         "Ophthalmic Optical Coherence Tomography B-scan Volume Analysis Storage",
         SopClassCategory::OpticalCoherenceTomography,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.77.1.5.7",
+        sop::VL_WHOLE_SLIDE_MICROSCOPY_IMAGE_STORAGE,
         "VL Whole Slide Microscopy Image Storage",
         SopClassCategory::Microscopy,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.77.1.5.8",
+        sop::DERMOSCOPIC_PHOTOGRAPHY_IMAGE_STORAGE,
         "Dermoscopic Photography Image Storage",
         SopClassCategory::Dermatology,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.77.1.6",
+        sop::OPHTHALMIC_VISUAL_FIELD_STATIC_PERIMETRY_MEASUREMENTS_STORAGE,
         "Ophthalmic Visual Field Static Perimetry Measurements Storage",
         SopClassCategory::Ophthalmology,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.77.1.7",
+        sop::OPHTHALMIC_THICKNESS_MAP_STORAGE,
         "Ophthalmic Thickness Map Storage",
         SopClassCategory::Ophthalmology,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.77.1.8",
+        sop::CORNEAL_TOPOGRAPHY_MAP_STORAGE,
         "Corneal Topography Map Storage",
         SopClassCategory::Ophthalmology,
     ),
@@ -707,60 +781,60 @@ const ALL_SOP_CLASSES: &[SopClassInfo] = &[
     
     // Multi-frame and Enhanced
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.3",
+        sop::ULTRASOUND_MULTI_FRAME_IMAGE_STORAGE_RETIRED,
         "Ultrasound Multi-frame Image Storage (Retired)",
         SopClassCategory::Legacy,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.8",
+        sop::STANDALONE_OVERLAY_STORAGE_RETIRED,
         "Standalone Overlay Storage (Retired)",
         SopClassCategory::Legacy,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.10",
+        sop::STANDALONE_CURVE_STORAGE_RETIRED,
         "Standalone Curve Storage (Retired)",
         SopClassCategory::Legacy,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.1.1.129",
+        sop::STANDALONE_PET_CURVE_STORAGE_RETIRED,
         "Standalone PET Curve Storage (Retired)",
         SopClassCategory::Legacy,
     ),
     
     // Basic Directory and Media Storage
     SopClassInfo::new(
-        "1.2.840.10008.1.3.10",
+        sop::MEDIA_STORAGE_DIRECTORY_STORAGE,
         "Media Storage Directory Storage",
         SopClassCategory::Other,
     ),
     
     // Hanging Protocols
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.38.1",
+        sop::HANGING_PROTOCOL_STORAGE,
         "Hanging Protocol Storage",
         SopClassCategory::Other,
     ),
     
     // Color Palette
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.39.1",
+        sop::COLOR_PALETTE_STORAGE,
         "Color Palette Storage",
         SopClassCategory::Other,
     ),
     
     // Generic Implant Template
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.43.1",
+        sop::GENERIC_IMPLANT_TEMPLATE_STORAGE,
         "Generic Implant Template Storage",
         SopClassCategory::Other,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.44.1",
+        sop::IMPLANT_ASSEMBLY_TEMPLATE_STORAGE,
         "Implant Assembly Template Storage",
         SopClassCategory::Other,
     ),
     SopClassInfo::new(
-        "1.2.840.10008.5.1.4.45.1",
+        sop::IMPLANT_TEMPLATE_GROUP_STORAGE,
         "Implant Template Group Storage",
         SopClassCategory::Other,
     ),
@@ -833,6 +907,29 @@ pub fn get_extended_transfer_syntaxes() -> Vec<&'static str> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_category_from_name() {
+        assert_eq!(SopClassCategory::from_name("SecondaryCapture"), Some(SopClassCategory::SecondaryCapture));
+        assert_eq!(SopClassCategory::from_name("microscopy"), Some(SopClassCategory::Microscopy));
+        assert_eq!(SopClassCategory::from_name("Holography"), None);
+    }
+
+    #[test]
+    fn test_find_by_uid_or_keyword() {
+        let registry = SopClassRegistry::new();
+        assert_eq!(registry.find("1.2.840.10008.5.1.4.1.1.2").map(|sc| sc.name), Some("CT Image Storage"));
+        assert_eq!(registry.find("CTImageStorage").map(|sc| sc.uid), Some("1.2.840.10008.5.1.4.1.1.2"));
+        assert_eq!(registry.find(" ct image storage ").map(|sc| sc.uid), Some("1.2.840.10008.5.1.4.1.1.2"));
+        assert!(registry.find("Hologram Storage").is_none());
+    }
+
+    #[test]
+    fn test_global_registry_is_built_once() {
+        let registry = SopClassRegistry::global();
+        assert!(std::ptr::eq(registry, SopClassRegistry::global()));
+        assert_eq!(registry.get_all_uids().len(), SopClassRegistry::new().get_all_uids().len());
+    }
+
     #[test]
     fn test_sop_class_registry() {
         let registry = SopClassRegistry::new();
@@ -0,0 +1,496 @@
+/// Per-study reassembly reports written by the receiver
+///
+/// Instances are grouped by study as they are stored. A study counts as
+/// complete once no instance of it has arrived for the configured timeout;
+/// its report then summarises what was received (series, modalities, bytes,
+/// transfer syntaxes, calling AEs, duration) and where InstanceNumber
+/// sequences have gaps, as a QA artifact for the ingest.
+///
+/// Series are also checked for the silent failure of a transfer that dropped
+/// slices: InstanceNumber gaps or duplicates, and along the slice normal
+/// (from ImagePositionPatient/ImageOrientationPatient) duplicate positions or
+/// jumps well beyond the usual slice spacing. Such series are flagged incomplete.
+///
+/// Presentation states are matched with the images they reference, so they
+/// can be exported together as a bundle; those whose images never arrived
+/// before the study completed are reported as orphans.
+///
+/// The bytes received are set against the bytes stored on disk, Part 10 header
+/// and encryption included, so the effect of conversions on ingest shows up as
+/// a compression ratio per study.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+
+use super::recovery::write_atomically;
+use super::reports::compression_ratio;
+
+/// Positions closer than this along the slice normal count as the same slice (mm)
+const POSITION_TOLERANCE_MM: f64 = 0.01;
+/// A step larger than this multiple of the typical slice spacing means slices are missing
+const SPACING_GAP_FACTOR: f64 = 1.5;
+/// Tolerance when comparing the orientations of the instances of a series
+const ORIENTATION_TOLERANCE: f64 = 1e-3;
+
+/// What the receiver stored for one instance
+#[derive(Debug, Clone)]
+pub struct ReceivedInstance {
+    pub study_instance_uid: String,
+    pub series_instance_uid: String,
+    pub sop_instance_uid: String,
+    pub modality: Option<String>,
+    pub patient_id: Option<String>,
+    pub accession_number: Option<String>,
+    pub instance_number: Option<i64>,
+    /// ImagePositionPatient
+    pub image_position: Option<[f64; 3]>,
+    /// ImageOrientationPatient (row and column direction cosines)
+    pub image_orientation: Option<[f64; 6]>,
+    pub transfer_syntax_uid: String,
+    pub calling_ae: String,
+    pub size: u64,
+    /// Size of the file written for it
+    pub stored_size: u64,
+    pub received_at: DateTime<Utc>,
+    /// Where the instance was stored
+    pub path: PathBuf,
+    /// Images referenced by a presentation state; `None` for other objects
+    pub presentation_state_references: Option<Vec<String>>,
+}
+
+/// Run of consecutive InstanceNumbers missing between two received ones
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InstanceNumberGap {
+    pub first_missing: i64,
+    pub last_missing: i64,
+}
+
+/// Step along the slice normal much larger than the series' usual spacing
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PositionGap {
+    /// Position along the slice normal of the slice before the gap (mm)
+    pub after_mm: f64,
+    pub step_mm: f64,
+    pub expected_spacing_mm: f64,
+    pub missing_slices: usize,
+}
+
+/// A presentation state with the images it applies to
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PresentationStateBundle {
+    pub sop_instance_uid: String,
+    pub file: PathBuf,
+    /// Stored files of the referenced images that arrived
+    pub images: Vec<PathBuf>,
+    /// Referenced SOP Instance UIDs that never arrived
+    pub missing_images: Vec<String>,
+}
+
+impl PresentationStateBundle {
+    /// The presentation state arrived without any of the images it references
+    pub fn is_orphan(&self) -> bool {
+        !self.missing_images.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SeriesReport {
+    pub series_instance_uid: String,
+    pub modality: Option<String>,
+    pub instances: usize,
+    pub total_bytes: u64,
+    /// Instances without an InstanceNumber
+    pub unnumbered_instances: usize,
+    pub instance_number_gaps: Vec<InstanceNumberGap>,
+    /// InstanceNumbers received more than once
+    pub duplicate_instance_numbers: Vec<i64>,
+    /// Instances sharing a slice position with another instance of the series
+    pub duplicate_positions: usize,
+    pub position_gaps: Vec<PositionGap>,
+    /// The series appears to be missing slices or to contain duplicates
+    pub incomplete: bool,
+}
+
+impl SeriesReport {
+    fn from_instances(series_instance_uid: &str, instances: &[&ReceivedInstance]) -> Self {
+        let numbers: Vec<i64> = instances.iter().filter_map(|i| i.instance_number).collect();
+        let (duplicate_positions, position_gaps) = check_positions(instances);
+        let mut report = Self {
+            series_instance_uid: series_instance_uid.to_string(),
+            modality: instances.iter().find_map(|i| i.modality.clone()),
+            instances: instances.len(),
+            total_bytes: instances.iter().map(|i| i.size).sum(),
+            unnumbered_instances: instances.len() - numbers.len(),
+            instance_number_gaps: instance_number_gaps(numbers.iter().copied()),
+            duplicate_instance_numbers: duplicates(&numbers),
+            duplicate_positions,
+            position_gaps,
+            incomplete: false,
+        };
+        report.incomplete = !report.instance_number_gaps.is_empty() || !report.duplicate_instance_numbers.is_empty()
+            || report.duplicate_positions > 0 || !report.position_gaps.is_empty();
+        report
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StudyReport {
+    pub study_instance_uid: String,
+    #[serde(default)]
+    pub patient_id: Option<String>,
+    #[serde(default)]
+    pub accession_number: Option<String>,
+    pub calling_aes: Vec<String>,
+    pub first_received: DateTime<Utc>,
+    pub last_received: DateTime<Utc>,
+    pub duration_seconds: f64,
+    pub instances: usize,
+    pub total_bytes: u64,
+    /// Size of the files written for the study
+    #[serde(default)]
+    pub stored_bytes: u64,
+    /// `total_bytes` over `stored_bytes`; above 1 when storing saved space
+    #[serde(default)]
+    pub compression_ratio: Option<f64>,
+    pub modalities: Vec<String>,
+    pub transfer_syntaxes: Vec<String>,
+    pub series: Vec<SeriesReport>,
+    /// Series flagged as incomplete
+    pub incomplete_series: Vec<String>,
+    pub presentation_states: Vec<PresentationStateBundle>,
+    /// Presentation states referencing images that never arrived
+    pub orphan_presentation_states: Vec<String>,
+    /// Stored files, in arrival order
+    #[serde(default)]
+    pub files: Vec<PathBuf>,
+}
+
+impl StudyReport {
+    pub fn from_instances(study_instance_uid: &str, instances: &[ReceivedInstance]) -> Self {
+        let mut by_series: BTreeMap<&str, Vec<&ReceivedInstance>> = BTreeMap::new();
+        for instance in instances {
+            by_series.entry(instance.series_instance_uid.as_str()).or_default().push(instance);
+        }
+
+        let first_received = instances.iter().map(|i| i.received_at).min().unwrap_or_else(Utc::now);
+        let last_received = instances.iter().map(|i| i.received_at).max().unwrap_or(first_received);
+        let series: Vec<SeriesReport> = by_series.into_iter()
+            .map(|(uid, instances)| SeriesReport::from_instances(uid, &instances))
+            .collect();
+        let presentation_states = bundle_presentation_states(instances);
+        let total_bytes = instances.iter().map(|i| i.size).sum();
+        let stored_bytes = instances.iter().map(|i| i.stored_size).sum();
+        Self {
+            study_instance_uid: study_instance_uid.to_string(),
+            patient_id: instances.iter().find_map(|i| i.patient_id.clone()),
+            accession_number: instances.iter().find_map(|i| i.accession_number.clone()),
+            calling_aes: sorted_unique(instances.iter().map(|i| i.calling_ae.as_str())),
+            first_received,
+            last_received,
+            duration_seconds: (last_received - first_received).num_milliseconds() as f64 / 1000.0,
+            instances: instances.len(),
+            total_bytes,
+            stored_bytes,
+            compression_ratio: compression_ratio(total_bytes, stored_bytes),
+            modalities: sorted_unique(instances.iter().filter_map(|i| i.modality.as_deref())),
+            transfer_syntaxes: sorted_unique(instances.iter().map(|i| i.transfer_syntax_uid.as_str())),
+            incomplete_series: series.iter()
+                .filter(|s| s.incomplete)
+                .map(|s| s.series_instance_uid.clone())
+                .collect(),
+            series,
+            orphan_presentation_states: presentation_states.iter()
+                .filter(|bundle| bundle.is_orphan())
+                .map(|bundle| bundle.sop_instance_uid.clone())
+                .collect(),
+            presentation_states,
+            files: instances.iter().map(|i| i.path.clone()).collect(),
+        }
+    }
+
+    /// File name of the report for this study
+    pub fn file_name(&self) -> String {
+        format!("{}.json", self.study_instance_uid)
+    }
+}
+
+fn sorted_unique<'a>(values: impl Iterator<Item = &'a str>) -> Vec<String> {
+    values.filter(|v| !v.is_empty())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .map(str::to_string)
+        .collect()
+}
+
+/// Missing runs between the lowest and highest InstanceNumber received
+pub fn instance_number_gaps(numbers: impl IntoIterator<Item = i64>) -> Vec<InstanceNumberGap> {
+    let numbers: BTreeSet<i64> = numbers.into_iter().collect();
+    numbers.iter().zip(numbers.iter().skip(1))
+        .filter(|(a, b)| **b > **a + 1)
+        .map(|(a, b)| InstanceNumberGap { first_missing: a + 1, last_missing: b - 1 })
+        .collect()
+}
+
+fn duplicates(numbers: &[i64]) -> Vec<i64> {
+    let mut seen = BTreeSet::new();
+    let duplicates: BTreeSet<i64> = numbers.iter().filter(|n| !seen.insert(**n)).copied().collect();
+    duplicates.into_iter().collect()
+}
+
+/// Duplicate slice positions and gaps along the slice normal of a series
+///
+/// Only applies to series of at least three positioned instances sharing one
+/// orientation; localizers and other mixed-orientation series are left alone.
+pub fn check_positions(instances: &[&ReceivedInstance]) -> (usize, Vec<PositionGap>) {
+    let positioned: Vec<([f64; 3], [f64; 6])> = instances.iter()
+        .filter_map(|i| Some((i.image_position?, i.image_orientation?)))
+        .collect();
+    let orientation = match positioned.first() {
+        Some((_, orientation)) if positioned.len() >= 3 => *orientation,
+        _ => return (0, Vec::new()),
+    };
+    if positioned.iter().any(|(_, o)| o.iter().zip(orientation.iter()).any(|(a, b)| (a - b).abs() > ORIENTATION_TOLERANCE)) {
+        return (0, Vec::new());
+    }
+
+    let normal = [
+        orientation[1] * orientation[5] - orientation[2] * orientation[4],
+        orientation[2] * orientation[3] - orientation[0] * orientation[5],
+        orientation[0] * orientation[4] - orientation[1] * orientation[3],
+    ];
+    let mut distances: Vec<f64> = positioned.iter()
+        .map(|(p, _)| p[0] * normal[0] + p[1] * normal[1] + p[2] * normal[2])
+        .collect();
+    distances.sort_by(f64::total_cmp);
+
+    let steps: Vec<(f64, f64)> = distances.windows(2).map(|w| (w[0], w[1] - w[0])).collect();
+    let duplicate_positions = steps.iter().filter(|(_, step)| *step < POSITION_TOLERANCE_MM).count();
+    let mut spacings: Vec<f64> = steps.iter().map(|(_, step)| *step).filter(|step| *step >= POSITION_TOLERANCE_MM).collect();
+    spacings.sort_by(f64::total_cmp);
+    let expected = match spacings.get(spacings.len() / 2) {
+        Some(spacing) => *spacing,
+        None => return (duplicate_positions, Vec::new()),
+    };
+
+    let gaps = steps.iter()
+        .filter(|(_, step)| *step > expected * SPACING_GAP_FACTOR)
+        .map(|(after, step)| PositionGap {
+            after_mm: *after,
+            step_mm: *step,
+            expected_spacing_mm: expected,
+            missing_slices: ((step / expected).round() as usize).saturating_sub(1).max(1),
+        })
+        .collect();
+    (duplicate_positions, gaps)
+}
+
+/// Match every presentation state of a study with the stored images it references
+pub fn bundle_presentation_states(instances: &[ReceivedInstance]) -> Vec<PresentationStateBundle> {
+    let stored: HashMap<&str, &PathBuf> = instances.iter()
+        .map(|i| (i.sop_instance_uid.as_str(), &i.path))
+        .collect();
+    instances.iter()
+        .filter_map(|ps| ps.presentation_state_references.as_ref().map(|references| (ps, references)))
+        .map(|(ps, references)| {
+            let (present, missing): (Vec<&String>, Vec<&String>) = references.iter()
+                .partition(|uid| stored.contains_key(uid.as_str()));
+            PresentationStateBundle {
+                sop_instance_uid: ps.sop_instance_uid.clone(),
+                file: ps.path.clone(),
+                images: present.into_iter().map(|uid| stored[uid.as_str()].clone()).collect(),
+                missing_images: missing.into_iter().cloned().collect(),
+            }
+        })
+        .collect()
+}
+
+/// Copy a presentation state and its images into `dir/<SOP Instance UID>/`,
+/// hard-linking where the filesystem allows
+pub fn export_bundle(dir: &Path, bundle: &PresentationStateBundle) -> Result<PathBuf> {
+    let target = dir.join(&bundle.sop_instance_uid);
+    std::fs::create_dir_all(&target)
+        .with_context(|| format!("Failed to create {}", target.display()))?;
+    for file in std::iter::once(&bundle.file).chain(bundle.images.iter()) {
+        let destination = target.join(file.file_name().unwrap_or_default());
+        if destination.exists() {
+            continue;
+        }
+        if std::fs::hard_link(file, &destination).is_err() {
+            std::fs::copy(file, &destination)
+                .with_context(|| format!("Failed to copy {} into {}", file.display(), target.display()))?;
+        }
+    }
+    Ok(target)
+}
+
+/// Studies being received, keyed by Study Instance UID
+#[derive(Debug)]
+pub struct StudyTracker {
+    timeout: Duration,
+    studies: HashMap<String, Vec<ReceivedInstance>>,
+}
+
+impl StudyTracker {
+    /// Track studies, treating one as complete after `timeout` without new instances
+    pub fn new(timeout: Duration) -> Self {
+        Self { timeout, studies: HashMap::new() }
+    }
+
+    /// Time without new instances after which a study is complete
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    pub fn add(&mut self, instance: ReceivedInstance) {
+        self.studies.entry(instance.study_instance_uid.clone()).or_default().push(instance);
+    }
+
+    /// Number of studies still receiving instances
+    pub fn open_studies(&self) -> usize {
+        self.studies.len()
+    }
+
+    /// Remove and report the studies that have been idle for the timeout at `now`
+    pub fn take_completed(&mut self, now: DateTime<Utc>) -> Vec<StudyReport> {
+        let completed: Vec<String> = self.studies.iter()
+            .filter(|(_, instances)| instances.iter().all(|i| now - i.received_at >= self.timeout))
+            .map(|(uid, _)| uid.clone())
+            .collect();
+        let mut reports: Vec<StudyReport> = completed.into_iter()
+            .filter_map(|uid| self.studies.remove(&uid).map(|instances| StudyReport::from_instances(&uid, &instances)))
+            .collect();
+        reports.sort_by_key(|report| report.first_received);
+        reports
+    }
+}
+
+/// Write a report into `dir` as pretty-printed JSON
+pub fn write_report(dir: &Path, report: &StudyReport) -> Result<PathBuf> {
+    std::fs::create_dir_all(dir)
+        .with_context(|| format!("Failed to create {}", dir.display()))?;
+    let path = dir.join(report.file_name());
+    write_atomically(&path, serde_json::to_string_pretty(report)?.as_bytes())?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn instance(series: &str, number: Option<i64>, seconds: i64) -> ReceivedInstance {
+        ReceivedInstance {
+            study_instance_uid: "1.2.3".to_string(),
+            series_instance_uid: series.to_string(),
+            sop_instance_uid: format!("{}.{}", series, seconds),
+            modality: Some("CT".to_string()),
+            patient_id: Some("PAT1".to_string()),
+            accession_number: None,
+            instance_number: number,
+            image_position: None,
+            image_orientation: None,
+            transfer_syntax_uid: "1.2.840.10008.1.2.1".to_string(),
+            calling_ae: "MODALITY".to_string(),
+            size: 1000,
+            stored_size: 800,
+            received_at: DateTime::parse_from_rfc3339("2024-01-01T12:00:00Z").unwrap().with_timezone(&Utc)
+                + Duration::seconds(seconds),
+            path: PathBuf::from(format!("{}_{}.dcm", series, seconds)),
+            presentation_state_references: None,
+        }
+    }
+
+    #[test]
+    fn test_instance_number_gaps() {
+        assert_eq!(
+            instance_number_gaps([5, 1, 2, 9, 2, 6]),
+            vec![InstanceNumberGap { first_missing: 3, last_missing: 4 }, InstanceNumberGap { first_missing: 7, last_missing: 8 }]
+        );
+        assert!(instance_number_gaps([3, 4, 5]).is_empty());
+        assert!(instance_number_gaps(Vec::new()).is_empty());
+    }
+
+    #[test]
+    fn test_study_completes_after_timeout() {
+        let mut tracker = StudyTracker::new(Duration::seconds(30));
+        tracker.add(instance("1.2.3.1", Some(1), 0));
+        tracker.add(instance("1.2.3.1", Some(3), 5));
+        tracker.add(instance("1.2.3.2", None, 10));
+
+        let start = instance("1.2.3.1", None, 0).received_at;
+        assert!(tracker.take_completed(start + Duration::seconds(39)).is_empty());
+
+        let reports = tracker.take_completed(start + Duration::seconds(40));
+        assert_eq!(reports.len(), 1);
+        assert_eq!(tracker.open_studies(), 0);
+        let report = &reports[0];
+        assert_eq!((report.instances, report.total_bytes, report.duration_seconds), (3, 3000, 10.0));
+        assert_eq!((report.stored_bytes, report.compression_ratio), (2400, Some(1.25)));
+        assert_eq!(report.calling_aes, vec!["MODALITY"]);
+        assert_eq!(report.series.len(), 2);
+        assert_eq!(report.series[0].instance_number_gaps, vec![InstanceNumberGap { first_missing: 2, last_missing: 2 }]);
+        assert_eq!(report.series[1].unnumbered_instances, 1);
+        assert_eq!(report.incomplete_series, vec!["1.2.3.1"]);
+    }
+
+    #[test]
+    fn test_missing_and_duplicate_slices() {
+        let axial = [1.0, 0.0, 0.0, 0.0, 1.0, 0.0];
+        let slices: Vec<ReceivedInstance> = [0.0, 2.5, 5.0, 12.5, 15.0, 15.0].iter().enumerate()
+            .map(|(n, z)| ReceivedInstance {
+                image_position: Some([-100.0, -100.0, *z]),
+                image_orientation: Some(axial),
+                ..instance("1.2.3.1", Some(n as i64 + 1), n as i64)
+            })
+            .collect();
+        let refs: Vec<&ReceivedInstance> = slices.iter().collect();
+
+        let (duplicates, gaps) = check_positions(&refs);
+        assert_eq!(duplicates, 1);
+        assert_eq!(gaps, vec![PositionGap { after_mm: 5.0, step_mm: 7.5, expected_spacing_mm: 2.5, missing_slices: 2 }]);
+
+        let contiguous: Vec<&ReceivedInstance> = refs[..3].to_vec();
+        assert_eq!(check_positions(&contiguous), (0, Vec::new()));
+        let report = StudyReport::from_instances("1.2.3", &slices[..3]);
+        assert!(report.incomplete_series.is_empty());
+
+        // Mixed orientations (e.g. a localizer) are not checked
+        let mut localizer = slices[0].clone();
+        localizer.image_orientation = Some([0.0, 1.0, 0.0, 0.0, 0.0, -1.0]);
+        let mut mixed = refs.clone();
+        mixed.push(&localizer);
+        assert_eq!(check_positions(&mixed), (0, Vec::new()));
+    }
+
+    #[test]
+    fn test_presentation_state_bundles() {
+        let images: Vec<ReceivedInstance> = (0..3).map(|n| instance("1.2.3.1", Some(n + 1), n)).collect();
+        let mut gsps = instance("1.2.3.9", None, 5);
+        gsps.presentation_state_references = Some(vec![images[0].sop_instance_uid.clone(), images[2].sop_instance_uid.clone()]);
+        let mut orphan = instance("1.2.3.9", None, 6);
+        orphan.presentation_state_references = Some(vec![images[1].sop_instance_uid.clone(), "1.2.3.1.99".to_string()]);
+
+        let mut instances = images.clone();
+        instances.extend([gsps, orphan.clone()]);
+        let report = StudyReport::from_instances("1.2.3", &instances);
+        assert_eq!(report.presentation_states.len(), 2);
+        assert_eq!(report.presentation_states[0].images, vec![images[0].path.clone(), images[2].path.clone()]);
+        assert!(!report.presentation_states[0].is_orphan());
+        assert_eq!(report.presentation_states[1].missing_images, vec!["1.2.3.1.99"]);
+        assert_eq!(report.orphan_presentation_states, vec![orphan.sop_instance_uid]);
+
+        let dir = std::env::temp_dir().join(format!("bundle_test_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut bundle = report.presentation_states[0].clone();
+        bundle.file = dir.join(&bundle.file);
+        bundle.images = bundle.images.iter().map(|image| dir.join(image)).collect();
+        for file in std::iter::once(&bundle.file).chain(bundle.images.iter()) {
+            std::fs::write(file, b"DICM").unwrap();
+        }
+        let exported = export_bundle(&dir.join("bundles"), &bundle).unwrap();
+        assert_eq!(std::fs::read_dir(&exported).unwrap().count(), 3);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
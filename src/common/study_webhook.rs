@@ -0,0 +1,153 @@
+//! Webhook notifications when a study finishes arriving
+//!
+//! With `--study-webhook`, every study the receiver's study tracker reports
+//! complete (no new instance for `--study-timeout` seconds) is also POSTed as
+//! JSON to a webhook, so routing, AI or RIS integrations can start on the whole
+//! study instead of polling the output directory. With a secret the body is
+//! signed with HMAC-SHA256, hex-encoded in an `X-Signature-256: sha256=<hex>`
+//! header. Deliveries that fail on the network or with a 5xx, 408 or 429 are
+//! retried with exponential backoff; one that still fails is logged and dropped.
+
+use anyhow::Result;
+use hmac::{Hmac, Mac};
+use serde_json::json;
+use sha2::Sha256;
+use std::time::Duration;
+use tracing::{info, warn};
+
+use super::study_report::StudyReport;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Header carrying the signature of the body
+pub const SIGNATURE_HEADER: &str = "X-Signature-256";
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+/// Wait before the first retry, doubled for every further one
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Posts completed studies to a webhook
+#[derive(Debug, Clone)]
+pub struct StudyWebhook {
+    url: String,
+    /// HMAC key signing each body
+    secret: Option<Vec<u8>>,
+    /// Attempts after the first one fails
+    retries: u32,
+}
+
+impl StudyWebhook {
+    pub fn new(url: String, secret: Option<Vec<u8>>, retries: u32) -> Self {
+        Self { url, secret, retries }
+    }
+
+    /// Body posted for a completed study received by `called_ae`
+    pub fn payload(report: &StudyReport, called_ae: &str) -> serde_json::Value {
+        json!({
+            "event": "study_completed",
+            "text": format!("📋 Study {} complete: {} instance(s) in {} series received by {}",
+                            report.study_instance_uid, report.instances, report.series.len(), called_ae),
+            "study_instance_uid": report.study_instance_uid,
+            "patient_id": report.patient_id,
+            "accession_number": report.accession_number,
+            "called_ae": called_ae,
+            "calling_aes": report.calling_aes,
+            "instances": report.instances,
+            "series": report.series.len(),
+            "total_bytes": report.total_bytes,
+            "modalities": report.modalities,
+            "first_received": report.first_received,
+            "last_received": report.last_received,
+            "incomplete_series": report.incomplete_series,
+            "files": report.files,
+        })
+    }
+
+    /// Value of the signature header for `body`, when a secret is configured
+    pub fn signature(&self, body: &[u8]) -> Option<String> {
+        let secret = self.secret.as_deref()?;
+        let mut mac = HmacSha256::new_from_slice(secret).expect("HMAC accepts keys of any length");
+        mac.update(body);
+        Some(format!("sha256={}", hex::encode(mac.finalize().into_bytes())))
+    }
+
+    /// POST the study's payload, retrying transient failures; blocking
+    pub fn deliver(&self, report: &StudyReport, called_ae: &str) -> Result<()> {
+        let body = serde_json::to_vec(&Self::payload(report, called_ae))?;
+        let signature = self.signature(&body);
+        let mut backoff = INITIAL_BACKOFF;
+        let mut attempt = 0;
+        loop {
+            let mut request = ureq::post(&self.url)
+                .timeout(REQUEST_TIMEOUT)
+                .set("Content-Type", "application/json");
+            if let Some(signature) = &signature {
+                request = request.set(SIGNATURE_HEADER, signature);
+            }
+            let error = match request.send_bytes(&body) {
+                Ok(_) => {
+                    info!("Posted completion of study {} to webhook", report.study_instance_uid);
+                    return Ok(());
+                }
+                Err(ureq::Error::Status(status, _)) if !matches!(status, 408 | 429 | 500..=599) => {
+                    anyhow::bail!("Webhook refused study {} with HTTP {}", report.study_instance_uid, status);
+                }
+                Err(e) => e,
+            };
+            if attempt >= self.retries {
+                anyhow::bail!("Webhook notification for study {} failed after {} attempt(s): {}",
+                              report.study_instance_uid, attempt + 1, error);
+            }
+            attempt += 1;
+            warn!("⚠️  Webhook notification for study {} failed ({}), retrying in {:?}",
+                  report.study_instance_uid, error, backoff);
+            std::thread::sleep(backoff);
+            backoff *= 2;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::study_report::{ReceivedInstance, StudyReport};
+    use chrono::Utc;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_payload_and_signature() {
+        let instance = ReceivedInstance {
+            study_instance_uid: "1.2.3".to_string(),
+            series_instance_uid: "1.2.3.1".to_string(),
+            sop_instance_uid: "1.2.3.1.1".to_string(),
+            modality: Some("CT".to_string()),
+            patient_id: Some("PAT1".to_string()),
+            accession_number: Some("ACC1".to_string()),
+            instance_number: Some(1),
+            image_position: None,
+            image_orientation: None,
+            transfer_syntax_uid: "1.2.840.10008.1.2.1".to_string(),
+            calling_ae: "MODALITY".to_string(),
+            size: 1000,
+            stored_size: 1200,
+            received_at: Utc::now(),
+            path: PathBuf::from("out/1.2.3.1.1.dcm"),
+            presentation_state_references: None,
+        };
+        let report = StudyReport::from_instances("1.2.3", &[instance]);
+        let payload = StudyWebhook::payload(&report, "ARCHIVE");
+        assert_eq!(payload["event"], "study_completed");
+        assert_eq!(payload["study_instance_uid"], "1.2.3");
+        assert_eq!(payload["patient_id"], "PAT1");
+        assert_eq!(payload["accession_number"], "ACC1");
+        assert_eq!(payload["instances"], 1);
+        assert_eq!(payload["files"], json!(["out/1.2.3.1.1.dcm"]));
+
+        let webhook = StudyWebhook::new("http://localhost/".to_string(), Some(b"key".to_vec()), 0);
+        assert_eq!(
+            webhook.signature(b"The quick brown fox jumps over the lazy dog").as_deref(),
+            Some("sha256=f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8")
+        );
+        assert_eq!(StudyWebhook::new("http://localhost/".to_string(), None, 0).signature(b"body"), None);
+    }
+}
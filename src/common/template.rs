@@ -0,0 +1,394 @@
+/// Declarative templates for synthetic studies
+///
+/// A template (JSON or YAML) gives patient- and study-level attributes and a
+/// list of series, each with a SOP class, an instance count and its own
+/// attributes. Attributes are named by keyword or tag and may vary per
+/// instance: text values can use `{study}`, `{series}` and `{instance}`
+/// (1-based), `{start, step}` values count up from instance to instance and
+/// `{cycle: [...]}` values rotate through a list. Sequences are lists of
+/// attribute maps. Study, Series and SOP Instance UIDs, Series and Instance
+/// Numbers and the SOP Class UID are filled in unless the template sets them.
+
+use anyhow::{Context, Result};
+use dicom_core::dictionary::{DataDictionary, DataDictionaryEntry};
+use dicom_core::value::{DataSetSequence, PrimitiveValue};
+use dicom_core::{DataElement, Tag, VR};
+use dicom_dictionary_std::StandardDataDictionary;
+use dicom_object::InMemDicomObject;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use super::deterministic::seeded_uuid;
+
+pub type Attributes = BTreeMap<String, TemplateValue>;
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum Scalar {
+    Text(String),
+    Number(f64),
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum TemplateValue {
+    /// Number counting up per instance: start + step * (instance - 1)
+    Step { start: f64, step: f64 },
+    /// One entry per instance, wrapping around
+    Cycle { cycle: Vec<Scalar> },
+    Text(String),
+    Number(f64),
+    /// Multi-valued attribute
+    Multi(Vec<Scalar>),
+    Sequence(Vec<Attributes>),
+}
+
+/// Synthetic pixel data: a 16-bit MONOCHROME2 gradient
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ImageTemplate {
+    pub rows: u16,
+    pub columns: u16,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct SeriesTemplate {
+    /// SOP Class UID or name
+    pub sop_class: String,
+    #[serde(default = "default_instances")]
+    pub instances: usize,
+    #[serde(default)]
+    pub attributes: Attributes,
+    #[serde(default)]
+    pub image: Option<ImageTemplate>,
+}
+
+fn default_instances() -> usize {
+    1
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct StudyTemplate {
+    #[serde(default)]
+    pub patient: Attributes,
+    #[serde(default)]
+    pub study: Attributes,
+    pub series: Vec<SeriesTemplate>,
+}
+
+impl StudyTemplate {
+    /// Load a template, as YAML for `.yaml`/`.yml` files and JSON otherwise
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read template {}", path.display()))?;
+        let yaml = matches!(path.extension().and_then(|e| e.to_str()), Some("yaml" | "yml"));
+        let template = if yaml {
+            serde_yaml::from_str(&content).map_err(anyhow::Error::from)
+        } else {
+            serde_json::from_str(&content).map_err(anyhow::Error::from)
+        };
+        template.with_context(|| format!("Invalid template {}", path.display()))
+    }
+}
+
+/// Attribute value after per-instance variation
+#[derive(Debug, Clone, PartialEq)]
+pub enum ResolvedValue {
+    Text(String),
+    Multi(Vec<String>),
+    Sequence(Vec<Vec<(String, ResolvedValue)>>),
+}
+
+/// Position of an instance within the generated data, all 1-based
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InstancePosition {
+    pub study: usize,
+    pub series: usize,
+    pub instance: usize,
+}
+
+fn number_text(value: f64) -> String {
+    // Whole numbers without a fraction, so IS values stay valid
+    if value.fract() == 0.0 && value.abs() < 1e15 {
+        format!("{}", value as i64)
+    } else {
+        format!("{}", value)
+    }
+}
+
+fn scalar_text(scalar: &Scalar, position: InstancePosition) -> String {
+    match scalar {
+        Scalar::Text(text) => substitute(text, position),
+        Scalar::Number(number) => number_text(*number),
+    }
+}
+
+fn substitute(text: &str, position: InstancePosition) -> String {
+    text.replace("{study}", &position.study.to_string())
+        .replace("{series}", &position.series.to_string())
+        .replace("{instance}", &position.instance.to_string())
+}
+
+/// Value of an attribute for the instance at `position`
+pub fn resolve(value: &TemplateValue, position: InstancePosition) -> ResolvedValue {
+    let index = position.instance.saturating_sub(1);
+    match value {
+        TemplateValue::Step { start, step } => ResolvedValue::Text(number_text(start + step * index as f64)),
+        TemplateValue::Cycle { cycle } if cycle.is_empty() => ResolvedValue::Text(String::new()),
+        TemplateValue::Cycle { cycle } => ResolvedValue::Text(scalar_text(&cycle[index % cycle.len()], position)),
+        TemplateValue::Text(text) => ResolvedValue::Text(substitute(text, position)),
+        TemplateValue::Number(number) => ResolvedValue::Text(number_text(*number)),
+        TemplateValue::Multi(values) => ResolvedValue::Multi(values.iter().map(|v| scalar_text(v, position)).collect()),
+        TemplateValue::Sequence(items) => ResolvedValue::Sequence(
+            items.iter().map(|item| resolve_all(item, position)).collect(),
+        ),
+    }
+}
+
+fn resolve_all(attributes: &Attributes, position: InstancePosition) -> Vec<(String, ResolvedValue)> {
+    attributes.iter()
+        .map(|(key, value)| (key.clone(), resolve(value, position)))
+        .collect()
+}
+
+/// Source of new UIDs under the 2.25 (UUID-derived) root, reproducible when seeded
+#[derive(Debug, Clone)]
+pub struct UidSource {
+    seed: Option<u64>,
+    issued: u64,
+}
+
+impl UidSource {
+    pub fn new(seed: Option<u64>) -> Self {
+        Self { seed, issued: 0 }
+    }
+
+    pub fn next_uid(&mut self) -> String {
+        self.issued += 1;
+        let uuid = match self.seed {
+            Some(seed) => seeded_uuid(seed, &format!("uid-{}", self.issued)),
+            None => uuid::Uuid::new_v4(),
+        };
+        format!("2.25.{}", uuid.as_u128())
+    }
+}
+
+/// One instance of a generated study
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntheticInstance {
+    pub position: InstancePosition,
+    pub sop_class_uid: String,
+    pub sop_instance_uid: String,
+    pub attributes: Vec<(String, ResolvedValue)>,
+    pub image: Option<ImageTemplate>,
+}
+
+fn has_attribute(attributes: &[(String, ResolvedValue)], keyword: &str) -> bool {
+    attributes.iter().any(|(key, _)| key == keyword)
+}
+
+fn text_attribute<'a>(attributes: &'a [(String, ResolvedValue)], keyword: &str) -> Option<&'a str> {
+    attributes.iter().find_map(|(key, value)| match value {
+        ResolvedValue::Text(text) if key == keyword => Some(text.as_str()),
+        _ => None,
+    })
+}
+
+/// Expand a template into the instances of study number `study` (1-based).
+/// `sop_class_uid` maps a SOP class name to its UID.
+pub fn expand(
+    template: &StudyTemplate,
+    study: usize,
+    uids: &mut UidSource,
+    sop_class_uid: impl Fn(&str) -> String,
+) -> Vec<SyntheticInstance> {
+    let study_uid = uids.next_uid();
+    let mut instances = Vec::new();
+
+    for (series_index, series) in template.series.iter().enumerate() {
+        let series_uid = uids.next_uid();
+        for instance in 1..=series.instances {
+            let position = InstancePosition { study, series: series_index + 1, instance };
+            // Later levels override earlier ones
+            let mut merged: BTreeMap<String, ResolvedValue> = BTreeMap::new();
+            for level in [&template.patient, &template.study, &series.attributes] {
+                merged.extend(resolve_all(level, position));
+            }
+            let mut attributes: Vec<(String, ResolvedValue)> = merged.into_iter().collect();
+
+            let generated_uid = uids.next_uid();
+            let mut defaults = vec![
+                ("StudyInstanceUID", study_uid.clone()),
+                ("SeriesInstanceUID", series_uid.clone()),
+                ("SOPInstanceUID", generated_uid),
+                ("SOPClassUID", sop_class_uid(&series.sop_class)),
+                ("SeriesNumber", position.series.to_string()),
+                ("InstanceNumber", instance.to_string()),
+            ];
+            defaults.retain(|(keyword, _)| !has_attribute(&attributes, keyword));
+            attributes.extend(defaults.into_iter().map(|(keyword, value)| (keyword.to_string(), ResolvedValue::Text(value))));
+
+            instances.push(SyntheticInstance {
+                position,
+                sop_class_uid: text_attribute(&attributes, "SOPClassUID").unwrap_or_default().to_string(),
+                sop_instance_uid: text_attribute(&attributes, "SOPInstanceUID").unwrap_or_default().to_string(),
+                attributes,
+                image: series.image.clone(),
+            });
+        }
+    }
+    instances
+}
+
+fn attribute_tag(key: &str) -> Result<(Tag, VR)> {
+    let tag = StandardDataDictionary.parse_tag(key)
+        .ok_or_else(|| anyhow::anyhow!("Unknown attribute {}", key))?;
+    let vr = StandardDataDictionary.by_tag(tag)
+        .map(|entry| entry.vr().relaxed())
+        .ok_or_else(|| anyhow::anyhow!("No VR known for attribute {}", key))?;
+    Ok((tag, vr))
+}
+
+fn primitive(key: &str, vr: VR, values: &[String]) -> Result<PrimitiveValue> {
+    fn parse<T: std::str::FromStr>(key: &str, values: &[String]) -> Result<Vec<T>> {
+        values.iter()
+            .map(|v| v.trim().parse::<T>().map_err(|_| anyhow::anyhow!("Invalid value {:?} for {}", v, key)))
+            .collect()
+    }
+    Ok(match vr {
+        VR::US => PrimitiveValue::U16(parse(key, values)?.into()),
+        VR::SS => PrimitiveValue::I16(parse(key, values)?.into()),
+        VR::UL => PrimitiveValue::U32(parse(key, values)?.into()),
+        VR::SL => PrimitiveValue::I32(parse(key, values)?.into()),
+        VR::FL => PrimitiveValue::F32(parse(key, values)?.into()),
+        VR::FD => PrimitiveValue::F64(parse(key, values)?.into()),
+        _ => PrimitiveValue::Strs(values.iter().cloned().collect()),
+    })
+}
+
+fn build_dataset(attributes: &[(String, ResolvedValue)]) -> Result<InMemDicomObject> {
+    let mut obj = InMemDicomObject::new_empty();
+    for (key, value) in attributes {
+        let (tag, vr) = attribute_tag(key)?;
+        let element = match value {
+            ResolvedValue::Sequence(items) => {
+                let items = items.iter().map(|item| build_dataset(item)).collect::<Result<Vec<_>>>()?;
+                DataElement::new(tag, VR::SQ, DataSetSequence::from(items))
+            }
+            ResolvedValue::Text(text) => DataElement::new(tag, vr, primitive(key, vr, std::slice::from_ref(text))?),
+            ResolvedValue::Multi(values) => DataElement::new(tag, vr, primitive(key, vr, values)?),
+        };
+        obj.put(element);
+    }
+    Ok(obj)
+}
+
+/// Gradient image that shifts with the instance number, so instances differ
+fn put_image(obj: &mut InMemDicomObject, image: &ImageTemplate, instance: usize) {
+    let (rows, columns) = (image.rows as usize, image.columns as usize);
+    let pixels: Vec<u16> = (0..rows * columns)
+        .map(|i| ((i / columns + i % columns + instance * 8) % 4096) as u16)
+        .collect();
+
+    obj.put(DataElement::new(Tag(0x0028, 0x0002), VR::US, PrimitiveValue::from(1u16)));
+    obj.put(DataElement::new(Tag(0x0028, 0x0004), VR::CS, PrimitiveValue::from("MONOCHROME2")));
+    obj.put(DataElement::new(Tag(0x0028, 0x0010), VR::US, PrimitiveValue::from(image.rows)));
+    obj.put(DataElement::new(Tag(0x0028, 0x0011), VR::US, PrimitiveValue::from(image.columns)));
+    obj.put(DataElement::new(Tag(0x0028, 0x0100), VR::US, PrimitiveValue::from(16u16)));
+    obj.put(DataElement::new(Tag(0x0028, 0x0101), VR::US, PrimitiveValue::from(12u16)));
+    obj.put(DataElement::new(Tag(0x0028, 0x0102), VR::US, PrimitiveValue::from(11u16)));
+    obj.put(DataElement::new(Tag(0x0028, 0x0103), VR::US, PrimitiveValue::from(0u16)));
+    obj.put(DataElement::new(Tag(0x7FE0, 0x0010), VR::OW, PrimitiveValue::U16(pixels.into())));
+}
+
+impl SyntheticInstance {
+    /// Dataset of this instance, without File Meta Information
+    pub fn to_dataset(&self) -> Result<InMemDicomObject> {
+        let mut obj = build_dataset(&self.attributes)?;
+        if let Some(image) = &self.image {
+            put_image(&mut obj, image, self.position.instance);
+        }
+        Ok(obj)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEMPLATE: &str = r#"{
+        "patient": {"PatientName": "SYNTH^{study}", "PatientID": "SYN{study}"},
+        "study": {"StudyDescription": "CT CHEST", "Modality": "CT"},
+        "series": [
+            {
+                "sop_class": "CT Image Storage",
+                "instances": 3,
+                "attributes": {
+                    "SeriesDescription": "AXIAL",
+                    "SliceLocation": {"start": -10, "step": 2.5},
+                    "ImageType": ["ORIGINAL", "PRIMARY", "AXIAL"],
+                    "PixelSpacing": [0.5, 0.5],
+                    "ImageComments": {"cycle": ["A", "B"]}
+                }
+            },
+            {
+                "sop_class": "1.2.840.10008.5.1.4.1.1.7",
+                "attributes": {
+                    "Modality": "OT",
+                    "ReferencedImageSequence": [{"ReferencedSOPInstanceUID": "1.2.3.{instance}"}]
+                }
+            }
+        ]
+    }"#;
+
+    fn text(instance: &SyntheticInstance, keyword: &str) -> String {
+        text_attribute(&instance.attributes, keyword).unwrap_or_default().to_string()
+    }
+
+    #[test]
+    fn test_expand_varies_per_instance() {
+        let template: StudyTemplate = serde_json::from_str(TEMPLATE).unwrap();
+        let resolve_sop = |name: &str| if name == "CT Image Storage" { "1.2.840.10008.5.1.4.1.1.2".to_string() } else { name.to_string() };
+        let instances = expand(&template, 2, &mut UidSource::new(Some(7)), resolve_sop);
+
+        assert_eq!(instances.len(), 4);
+        assert_eq!(text(&instances[0], "PatientName"), "SYNTH^2");
+        assert_eq!(text(&instances[0], "SOPClassUID"), "1.2.840.10008.5.1.4.1.1.2");
+        assert_eq!(text(&instances[2], "SliceLocation"), "-5");
+        assert_eq!(text(&instances[1], "SliceLocation"), "-7.5");
+        assert_eq!(text(&instances[2], "ImageComments"), "A");
+        assert_eq!(text(&instances[2], "InstanceNumber"), "3");
+        assert_eq!(text(&instances[3], "SeriesNumber"), "2");
+        // Series attributes override study ones
+        assert_eq!(text(&instances[3], "Modality"), "OT");
+
+        let pixel_spacing = instances[0].attributes.iter().find(|(k, _)| k == "PixelSpacing").unwrap();
+        assert_eq!(pixel_spacing.1, ResolvedValue::Multi(vec!["0.5".to_string(), "0.5".to_string()]));
+
+        // Shared study and series UIDs, distinct instance UIDs
+        assert_eq!(text(&instances[0], "StudyInstanceUID"), text(&instances[3], "StudyInstanceUID"));
+        assert_eq!(text(&instances[0], "SeriesInstanceUID"), text(&instances[2], "SeriesInstanceUID"));
+        assert_ne!(text(&instances[0], "SeriesInstanceUID"), text(&instances[3], "SeriesInstanceUID"));
+        assert_ne!(instances[0].sop_instance_uid, instances[1].sop_instance_uid);
+        assert!(instances[0].sop_instance_uid.starts_with("2.25."));
+    }
+
+    #[test]
+    fn test_seeded_uids_are_reproducible() {
+        let template: StudyTemplate = serde_json::from_str(TEMPLATE).unwrap();
+        let first = expand(&template, 1, &mut UidSource::new(Some(1)), str::to_string);
+        let second = expand(&template, 1, &mut UidSource::new(Some(1)), str::to_string);
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn test_to_dataset() {
+        let template: StudyTemplate = serde_json::from_str(TEMPLATE).unwrap();
+        let instances = expand(&template, 1, &mut UidSource::new(Some(1)), str::to_string);
+
+        let obj = instances[3].to_dataset().unwrap();
+        assert_eq!(obj.element_by_name("Modality").unwrap().to_str().unwrap(), "OT");
+        let items = obj.element_by_name("ReferencedImageSequence").unwrap().items().unwrap();
+        assert_eq!(items[0].element_by_name("ReferencedSOPInstanceUID").unwrap().to_str().unwrap(), "1.2.3.1");
+    }
+}
@@ -0,0 +1,149 @@
+/// Clock sanity checks for received objects
+///
+/// Modalities with a misconfigured clock stamp their images with dates far in
+/// the past (e.g. after a battery reset) or in the future. Acquisition and
+/// content date/times are compared with the receiver's wall clock and objects
+/// outside the configured window are reported. DICOM times carry no time zone,
+/// so the comparison is against local time and the future margin should allow
+/// for modalities in other zones.
+
+use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime};
+use dicom_core::Tag;
+use dicom_object::InMemDicomObject;
+
+#[derive(Debug, Clone)]
+pub struct TimeSanityPolicy {
+    /// How far ahead of the receiver clock a timestamp may be
+    pub max_future: Duration,
+    /// How far behind the receiver clock a timestamp may be
+    pub max_past: Duration,
+}
+
+impl Default for TimeSanityPolicy {
+    fn default() -> Self {
+        Self {
+            max_future: Duration::hours(24),
+            max_past: Duration::days(3650),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimeWarning {
+    pub attribute: &'static str,
+    pub timestamp: NaiveDateTime,
+    /// Positive when the timestamp is in the future
+    pub offset: Duration,
+}
+
+impl std::fmt::Display for TimeWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let direction = if self.offset > Duration::zero() { "in the future" } else { "in the past" };
+        let days = self.offset.num_days().abs();
+        if days > 0 {
+            write!(f, "{} {} is {} days {}", self.attribute, self.timestamp, days, direction)
+        } else {
+            write!(f, "{} {} is {} hours {}", self.attribute, self.timestamp, self.offset.num_hours().abs(), direction)
+        }
+    }
+}
+
+// (name, date tag, time tag)
+const DATE_TIME_PAIRS: &[(&str, Tag, Tag)] = &[
+    ("Acquisition", Tag(0x0008, 0x0022), Tag(0x0008, 0x0032)),
+    ("Content", Tag(0x0008, 0x0023), Tag(0x0008, 0x0033)),
+];
+const ACQUISITION_DATE_TIME: Tag = Tag(0x0008, 0x002A);
+
+/// Check acquisition and content timestamps of a dataset against `now`
+pub fn check_timestamps(obj: &InMemDicomObject, now: NaiveDateTime, policy: &TimeSanityPolicy) -> Vec<TimeWarning> {
+    let text = |tag: Tag| -> Option<String> {
+        obj.element(tag).ok()
+            .and_then(|e| e.to_str().ok())
+            .map(|s| s.trim().trim_end_matches('\0').to_string())
+            .filter(|s| !s.is_empty())
+    };
+
+    let mut timestamps = Vec::new();
+    for (name, date_tag, time_tag) in DATE_TIME_PAIRS {
+        if let Some(timestamp) = text(*date_tag).and_then(|date| parse_date_time(&date, text(*time_tag).as_deref())) {
+            timestamps.push((*name, timestamp));
+        }
+    }
+    if let Some(timestamp) = text(ACQUISITION_DATE_TIME).and_then(|dt| parse_dt(&dt)) {
+        timestamps.push(("AcquisitionDateTime", timestamp));
+    }
+
+    timestamps.into_iter()
+        .filter_map(|(attribute, timestamp)| check_timestamp(attribute, timestamp, now, policy))
+        .collect()
+}
+
+pub fn check_timestamp(
+    attribute: &'static str,
+    timestamp: NaiveDateTime,
+    now: NaiveDateTime,
+    policy: &TimeSanityPolicy,
+) -> Option<TimeWarning> {
+    let offset = timestamp - now;
+    if offset > policy.max_future || -offset > policy.max_past {
+        Some(TimeWarning { attribute, timestamp, offset })
+    } else {
+        None
+    }
+}
+
+/// Combine a DA value and an optional TM value; a missing time means midnight
+pub fn parse_date_time(date: &str, time: Option<&str>) -> Option<NaiveDateTime> {
+    let date = NaiveDate::parse_from_str(date.get(..8)?, "%Y%m%d").ok()?;
+    let time = time.and_then(parse_tm).unwrap_or(NaiveTime::MIN);
+    Some(date.and_time(time))
+}
+
+/// Parse a TM value (HH, HHMM, HHMMSS with optional fraction)
+pub fn parse_tm(time: &str) -> Option<NaiveTime> {
+    let digits = time.split('.').next()?.replace(':', "");
+    let field = |range: std::ops::Range<usize>| -> Option<u32> {
+        digits.get(range).map_or(Some(0), |s| s.parse().ok())
+    };
+    if digits.len() < 2 {
+        return None;
+    }
+    NaiveTime::from_hms_opt(field(0..2)?, field(2..4)?, field(4..6)?)
+}
+
+/// Parse a DT value, ignoring fractional seconds and any UTC offset
+pub fn parse_dt(value: &str) -> Option<NaiveDateTime> {
+    let value = value.split(['+', '-']).next()?;
+    parse_date_time(value.get(..8)?, value.get(8..).filter(|t| !t.is_empty()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_dicom_date_times() {
+        let expected = NaiveDate::from_ymd_opt(2024, 3, 15).unwrap().and_hms_opt(14, 30, 5).unwrap();
+        assert_eq!(parse_date_time("20240315", Some("143005.123")), Some(expected));
+        assert_eq!(parse_date_time("20240315", Some("14:30:05")), Some(expected));
+        assert_eq!(parse_dt("20240315143005.5+0100"), Some(expected));
+        assert_eq!(parse_date_time("20240315", Some("14")).unwrap().time(), NaiveTime::from_hms_opt(14, 0, 0).unwrap());
+        assert!(parse_date_time("2024", None).is_none());
+    }
+
+    #[test]
+    fn test_flags_far_past_and_future() {
+        let policy = TimeSanityPolicy::default();
+        let now = NaiveDate::from_ymd_opt(2024, 3, 15).unwrap().and_hms_opt(12, 0, 0).unwrap();
+
+        assert!(check_timestamp("Acquisition", now - Duration::days(30), now, &policy).is_none());
+        assert!(check_timestamp("Acquisition", now + Duration::hours(2), now, &policy).is_none());
+
+        let future = check_timestamp("Acquisition", now + Duration::days(3), now, &policy).unwrap();
+        assert!(future.offset > Duration::zero());
+
+        let epoch = NaiveDate::from_ymd_opt(1970, 1, 1).unwrap().and_hms_opt(0, 0, 0).unwrap();
+        assert!(check_timestamp("Content", epoch, now, &policy).is_some());
+    }
+}
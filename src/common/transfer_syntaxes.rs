@@ -4,6 +4,10 @@
 /// for negotiating and handling various DICOM transfer syntaxes including
 /// uncompressed, lossless compressed, and lossy compressed formats.
 
+use super::codecs::CodecSupport;
+use super::sop_classes::keyword_of;
+use super::uids::ts;
+use once_cell::sync::Lazy;
 use std::collections::HashMap;
 
 #[derive(Debug, Clone, PartialEq)]
@@ -20,6 +24,23 @@ pub enum TransferSyntaxCategory {
     Video,
 }
 
+impl TransferSyntaxCategory {
+    pub const ALL: &'static [TransferSyntaxCategory] = &[
+        TransferSyntaxCategory::Uncompressed,
+        TransferSyntaxCategory::LosslessCompressed,
+        TransferSyntaxCategory::LossyCompressed,
+        TransferSyntaxCategory::Legacy,
+        TransferSyntaxCategory::Video,
+    ];
+
+    /// Look up a category by its name, ignoring case (e.g. "LosslessCompressed")
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter()
+            .find(|category| format!("{:?}", category).eq_ignore_ascii_case(name))
+            .cloned()
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum CompressionType {
     None,
@@ -34,6 +55,21 @@ pub enum CompressionType {
     H265,
 }
 
+impl CompressionType {
+    /// Feature of dicom-transfer-syntax-registry that provides the pixel data
+    /// codec, `None` when no codec is needed or dicom-rs has none (video)
+    pub fn codec_feature(&self) -> Option<&'static str> {
+        match self {
+            CompressionType::JPEG | CompressionType::JPEGLossless => Some("jpeg"),
+            CompressionType::JPEGLS => Some("charls"),
+            CompressionType::JPEG2000 => Some("openjp2 or openjpeg-sys"),
+            CompressionType::RLE => Some("rle"),
+            CompressionType::None | CompressionType::MPEG2 | CompressionType::MPEG4
+            | CompressionType::H264 | CompressionType::H265 => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct TransferSyntaxInfo {
     pub uid: &'static str,
@@ -76,6 +112,24 @@ impl TransferSyntaxInfo {
             TransferSyntaxCategory::Uncompressed | TransferSyntaxCategory::LosslessCompressed
         )
     }
+
+    /// Retired from the standard; such syntaxes are named "... (Retired)"
+    pub fn is_retired(&self) -> bool {
+        self.name.ends_with("(Retired)")
+    }
+}
+
+/// How to pick among the transfer syntaxes both sides of an association support
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum TransferSyntaxPolicy {
+    /// The first in the proposer's order, as most acceptors decide
+    #[default]
+    ProposerOrder,
+    /// Uncompressed and lossless compressed syntaxes ahead of lossy ones
+    PreferLossless,
+    /// Uncompressed syntaxes first, Explicit VR Little Endian ahead of the
+    /// rest, so neither side has to encode or decode pixel data
+    PreferNative,
 }
 
 /// Comprehensive Transfer Syntax registry
@@ -84,7 +138,14 @@ pub struct TransferSyntaxRegistry {
     syntaxes: HashMap<&'static str, TransferSyntaxInfo>,
 }
 
+static REGISTRY: Lazy<TransferSyntaxRegistry> = Lazy::new(TransferSyntaxRegistry::new);
+
 impl TransferSyntaxRegistry {
+    /// The registry shared by the whole process, built on first use
+    pub fn global() -> &'static Self {
+        &REGISTRY
+    }
+
     pub fn new() -> Self {
         let mut syntaxes = HashMap::new();
         
@@ -109,6 +170,15 @@ impl TransferSyntaxRegistry {
             .filter(|ts| ts.category == category)
             .collect()
     }
+
+    /// Look up by UID, or by name or keyword ignoring case, spaces and punctuation
+    pub fn find(&self, query: &str) -> Option<&TransferSyntaxInfo> {
+        let query = query.trim();
+        self.get(query).or_else(|| {
+            let keyword = keyword_of(query);
+            ALL_TRANSFER_SYNTAXES.iter().find(|ts| keyword_of(ts.name) == keyword)
+        })
+    }
     
     pub fn get_uncompressed(&self) -> Vec<&'static str> {
         self.get_by_category(TransferSyntaxCategory::Uncompressed)
@@ -138,6 +208,11 @@ impl TransferSyntaxRegistry {
     pub fn get_name(&self, uid: &str) -> Option<&'static str> {
         self.get(uid).map(|ts| ts.name)
     }
+
+    /// Whether a registered transfer syntax is retired; unknown ones are not
+    pub fn is_retired(&self, uid: &str) -> bool {
+        self.get(uid).is_some_and(TransferSyntaxInfo::is_retired)
+    }
     
     pub fn is_compressed(&self, uid: &str) -> bool {
         self.get(uid).map_or(false, |ts| ts.is_compressed())
@@ -146,6 +221,62 @@ impl TransferSyntaxRegistry {
     pub fn requires_encapsulation(&self, uid: &str) -> bool {
         self.get(uid).map_or(false, |ts| ts.supports_encapsulation)
     }
+
+    /// What this build can do with objects in a transfer syntax, decided by
+    /// the codecs compiled into dicom-rs
+    pub fn codec_support(&self, uid: &str) -> CodecSupport {
+        CodecSupport::of(uid)
+    }
+
+    /// Registered transfer syntaxes whose data sets this build can read, the
+    /// ones that may be accepted for receiving and forwarding as is
+    pub fn get_handled_uids(&self) -> Vec<&'static str> {
+        self.get_all_uids().into_iter().filter(|uid| self.codec_support(uid).dataset).collect()
+    }
+
+    /// Whether pixel data survives the transfer syntax unchanged: uncompressed,
+    /// retired Big Endian included, or lossless compressed
+    pub fn is_lossless(&self, uid: &str) -> bool {
+        self.get(uid).is_some_and(|ts| !ts.is_compressed() || ts.category == TransferSyntaxCategory::LosslessCompressed)
+    }
+
+    /// Proposed transfer syntaxes the acceptor also supports, best first under
+    /// `policy`; syntaxes the policy ranks alike keep the proposer's order
+    pub fn common_transfer_syntaxes<'a>(&self, proposed: &'a [impl AsRef<str>], accepted: &[impl AsRef<str>], policy: TransferSyntaxPolicy) -> Vec<&'a str> {
+        let mut common: Vec<&'a str> = Vec::new();
+        for ts in proposed.iter().map(|ts| ts.as_ref().trim_end_matches('\0')) {
+            if !common.contains(&ts) && accepted.iter().any(|accepted| accepted.as_ref().trim_end_matches('\0') == ts) {
+                common.push(ts);
+            }
+        }
+        // Stable, so ties stay in the proposer's order
+        common.sort_by_key(|ts| self.rank(ts, policy));
+        common
+    }
+
+    /// Best common transfer syntax under `policy`, `None` when the context would be refused
+    pub fn best_common<'a>(&self, proposed: &'a [impl AsRef<str>], accepted: &[impl AsRef<str>], policy: TransferSyntaxPolicy) -> Option<&'a str> {
+        self.common_transfer_syntaxes(proposed, accepted, policy).into_iter().next()
+    }
+
+    /// Position of a transfer syntax under `policy`, lower is better; unknown syntaxes come last
+    fn rank(&self, uid: &str, policy: TransferSyntaxPolicy) -> u8 {
+        let Some(info) = self.get(uid) else {
+            return if policy == TransferSyntaxPolicy::ProposerOrder { 0 } else { u8::MAX };
+        };
+        let lossless = self.is_lossless(uid);
+        match policy {
+            TransferSyntaxPolicy::ProposerOrder => 0,
+            TransferSyntaxPolicy::PreferLossless => if lossless { 0 } else { 1 },
+            TransferSyntaxPolicy::PreferNative => match uid {
+                ts::EXPLICIT_VR_LITTLE_ENDIAN => 0,
+                ts::IMPLICIT_VR_LITTLE_ENDIAN => 1,
+                _ if !info.is_compressed() => 2,
+                _ if lossless => 3,
+                _ => 4,
+            },
+        }
+    }
 }
 
 impl Default for TransferSyntaxRegistry {
@@ -161,7 +292,7 @@ const ALL_TRANSFER_SYNTAXES: &[TransferSyntaxInfo] = &[
     // =============================================================================
     
     TransferSyntaxInfo::new(
-        "1.2.840.10008.1.2",
+        ts::IMPLICIT_VR_LITTLE_ENDIAN,
         "Implicit VR Little Endian",
         TransferSyntaxCategory::Uncompressed,
         CompressionType::None,
@@ -171,7 +302,7 @@ const ALL_TRANSFER_SYNTAXES: &[TransferSyntaxInfo] = &[
     ),
     
     TransferSyntaxInfo::new(
-        "1.2.840.10008.1.2.1",
+        ts::EXPLICIT_VR_LITTLE_ENDIAN,
         "Explicit VR Little Endian",
         TransferSyntaxCategory::Uncompressed,
         CompressionType::None,
@@ -181,7 +312,7 @@ const ALL_TRANSFER_SYNTAXES: &[TransferSyntaxInfo] = &[
     ),
     
     TransferSyntaxInfo::new(
-        "1.2.840.10008.1.2.2",
+        ts::EXPLICIT_VR_BIG_ENDIAN,
         "Explicit VR Big Endian (Retired)",
         TransferSyntaxCategory::Legacy,
         CompressionType::None,
@@ -195,7 +326,7 @@ const ALL_TRANSFER_SYNTAXES: &[TransferSyntaxInfo] = &[
     // =============================================================================
     
     TransferSyntaxInfo::new(
-        "1.2.840.10008.1.2.4.50",
+        ts::JPEG_BASELINE,
         "JPEG Baseline (Process 1)",
         TransferSyntaxCategory::LossyCompressed,
         CompressionType::JPEG,
@@ -205,7 +336,7 @@ const ALL_TRANSFER_SYNTAXES: &[TransferSyntaxInfo] = &[
     ),
     
     TransferSyntaxInfo::new(
-        "1.2.840.10008.1.2.4.51",
+        ts::JPEG_EXTENDED,
         "JPEG Extended (Process 2 & 4)",
         TransferSyntaxCategory::LossyCompressed,
         CompressionType::JPEG,
@@ -215,7 +346,7 @@ const ALL_TRANSFER_SYNTAXES: &[TransferSyntaxInfo] = &[
     ),
     
     TransferSyntaxInfo::new(
-        "1.2.840.10008.1.2.4.52",
+        ts::JPEG_EXTENDED_PROCESS_3_5,
         "JPEG Extended (Process 3 & 5) (Retired)",
         TransferSyntaxCategory::Legacy,
         CompressionType::JPEG,
@@ -225,7 +356,7 @@ const ALL_TRANSFER_SYNTAXES: &[TransferSyntaxInfo] = &[
     ),
     
     TransferSyntaxInfo::new(
-        "1.2.840.10008.1.2.4.53",
+        ts::JPEG_SPECTRAL_SELECTION_NON_HIERARCHICAL_PROCESS_6_8,
         "JPEG Spectral Selection, Non-Hierarchical (Process 6 & 8) (Retired)",
         TransferSyntaxCategory::Legacy,
         CompressionType::JPEG,
@@ -235,7 +366,7 @@ const ALL_TRANSFER_SYNTAXES: &[TransferSyntaxInfo] = &[
     ),
     
     TransferSyntaxInfo::new(
-        "1.2.840.10008.1.2.4.54",
+        ts::JPEG_SPECTRAL_SELECTION_NON_HIERARCHICAL_PROCESS_7_9,
         "JPEG Spectral Selection, Non-Hierarchical (Process 7 & 9) (Retired)",
         TransferSyntaxCategory::Legacy,
         CompressionType::JPEG,
@@ -245,7 +376,7 @@ const ALL_TRANSFER_SYNTAXES: &[TransferSyntaxInfo] = &[
     ),
     
     TransferSyntaxInfo::new(
-        "1.2.840.10008.1.2.4.55",
+        ts::JPEG_FULL_PROGRESSION_NON_HIERARCHICAL_PROCESS_10_12,
         "JPEG Full Progression, Non-Hierarchical (Process 10 & 12) (Retired)",
         TransferSyntaxCategory::Legacy,
         CompressionType::JPEG,
@@ -255,7 +386,7 @@ const ALL_TRANSFER_SYNTAXES: &[TransferSyntaxInfo] = &[
     ),
     
     TransferSyntaxInfo::new(
-        "1.2.840.10008.1.2.4.56",
+        ts::JPEG_FULL_PROGRESSION_NON_HIERARCHICAL_PROCESS_11_13,
         "JPEG Full Progression, Non-Hierarchical (Process 11 & 13) (Retired)",
         TransferSyntaxCategory::Legacy,
         CompressionType::JPEG,
@@ -265,7 +396,7 @@ const ALL_TRANSFER_SYNTAXES: &[TransferSyntaxInfo] = &[
     ),
     
     TransferSyntaxInfo::new(
-        "1.2.840.10008.1.2.4.57",
+        ts::JPEG_LOSSLESS,
         "JPEG Lossless, Non-Hierarchical (Process 14)",
         TransferSyntaxCategory::LosslessCompressed,
         CompressionType::JPEGLossless,
@@ -275,7 +406,7 @@ const ALL_TRANSFER_SYNTAXES: &[TransferSyntaxInfo] = &[
     ),
     
     TransferSyntaxInfo::new(
-        "1.2.840.10008.1.2.4.58",
+        ts::JPEG_LOSSLESS_NON_HIERARCHICAL_PROCESS_15,
         "JPEG Lossless, Non-Hierarchical (Process 15) (Retired)",
         TransferSyntaxCategory::Legacy,
         CompressionType::JPEGLossless,
@@ -285,7 +416,7 @@ const ALL_TRANSFER_SYNTAXES: &[TransferSyntaxInfo] = &[
     ),
     
     TransferSyntaxInfo::new(
-        "1.2.840.10008.1.2.4.59",
+        ts::JPEG_EXTENDED_HIERARCHICAL_PROCESS_16_18,
         "JPEG Extended, Hierarchical (Process 16 & 18) (Retired)",
         TransferSyntaxCategory::Legacy,
         CompressionType::JPEG,
@@ -295,7 +426,7 @@ const ALL_TRANSFER_SYNTAXES: &[TransferSyntaxInfo] = &[
     ),
     
     TransferSyntaxInfo::new(
-        "1.2.840.10008.1.2.4.60",
+        ts::JPEG_EXTENDED_HIERARCHICAL_PROCESS_17_19,
         "JPEG Extended, Hierarchical (Process 17 & 19) (Retired)",
         TransferSyntaxCategory::Legacy,
         CompressionType::JPEG,
@@ -305,7 +436,7 @@ const ALL_TRANSFER_SYNTAXES: &[TransferSyntaxInfo] = &[
     ),
     
     TransferSyntaxInfo::new(
-        "1.2.840.10008.1.2.4.61",
+        ts::JPEG_SPECTRAL_SELECTION_HIERARCHICAL_PROCESS_20_22,
         "JPEG Spectral Selection, Hierarchical (Process 20 & 22) (Retired)",
         TransferSyntaxCategory::Legacy,
         CompressionType::JPEG,
@@ -315,7 +446,7 @@ const ALL_TRANSFER_SYNTAXES: &[TransferSyntaxInfo] = &[
     ),
     
     TransferSyntaxInfo::new(
-        "1.2.840.10008.1.2.4.62",
+        ts::JPEG_SPECTRAL_SELECTION_HIERARCHICAL_PROCESS_21_23,
         "JPEG Spectral Selection, Hierarchical (Process 21 & 23) (Retired)",
         TransferSyntaxCategory::Legacy,
         CompressionType::JPEG,
@@ -325,7 +456,7 @@ const ALL_TRANSFER_SYNTAXES: &[TransferSyntaxInfo] = &[
     ),
     
     TransferSyntaxInfo::new(
-        "1.2.840.10008.1.2.4.63",
+        ts::JPEG_FULL_PROGRESSION_HIERARCHICAL_PROCESS_24_26,
         "JPEG Full Progression, Hierarchical (Process 24 & 26) (Retired)",
         TransferSyntaxCategory::Legacy,
         CompressionType::JPEG,
@@ -335,7 +466,7 @@ const ALL_TRANSFER_SYNTAXES: &[TransferSyntaxInfo] = &[
     ),
     
     TransferSyntaxInfo::new(
-        "1.2.840.10008.1.2.4.64",
+        ts::JPEG_FULL_PROGRESSION_HIERARCHICAL_PROCESS_25_27,
         "JPEG Full Progression, Hierarchical (Process 25 & 27) (Retired)",
         TransferSyntaxCategory::Legacy,
         CompressionType::JPEG,
@@ -345,7 +476,7 @@ const ALL_TRANSFER_SYNTAXES: &[TransferSyntaxInfo] = &[
     ),
     
     TransferSyntaxInfo::new(
-        "1.2.840.10008.1.2.4.65",
+        ts::JPEG_LOSSLESS_HIERARCHICAL_PROCESS_28,
         "JPEG Lossless, Hierarchical (Process 28) (Retired)",
         TransferSyntaxCategory::Legacy,
         CompressionType::JPEGLossless,
@@ -355,7 +486,7 @@ const ALL_TRANSFER_SYNTAXES: &[TransferSyntaxInfo] = &[
     ),
     
     TransferSyntaxInfo::new(
-        "1.2.840.10008.1.2.4.66",
+        ts::JPEG_LOSSLESS_HIERARCHICAL_PROCESS_29,
         "JPEG Lossless, Hierarchical (Process 29) (Retired)",
         TransferSyntaxCategory::Legacy,
         CompressionType::JPEGLossless,
@@ -365,7 +496,7 @@ const ALL_TRANSFER_SYNTAXES: &[TransferSyntaxInfo] = &[
     ),
     
     TransferSyntaxInfo::new(
-        "1.2.840.10008.1.2.4.70",
+        ts::JPEG_LOSSLESS_SV1,
         "JPEG Lossless, Non-Hierarchical, First-Order Prediction (Process 14 [Selection Value 1])",
         TransferSyntaxCategory::LosslessCompressed,
         CompressionType::JPEGLossless,
@@ -379,7 +510,7 @@ const ALL_TRANSFER_SYNTAXES: &[TransferSyntaxInfo] = &[
     // =============================================================================
     
     TransferSyntaxInfo::new(
-        "1.2.840.10008.1.2.4.80",
+        ts::JPEG_LS_LOSSLESS,
         "JPEG-LS Lossless Image Compression",
         TransferSyntaxCategory::LosslessCompressed,
         CompressionType::JPEGLS,
@@ -389,7 +520,7 @@ const ALL_TRANSFER_SYNTAXES: &[TransferSyntaxInfo] = &[
     ),
     
     TransferSyntaxInfo::new(
-        "1.2.840.10008.1.2.4.81",
+        ts::JPEG_LS_NEAR_LOSSLESS,
         "JPEG-LS Lossy (Near-Lossless) Image Compression",
         TransferSyntaxCategory::LossyCompressed,
         CompressionType::JPEGLS,
@@ -403,7 +534,7 @@ const ALL_TRANSFER_SYNTAXES: &[TransferSyntaxInfo] = &[
     // =============================================================================
     
     TransferSyntaxInfo::new(
-        "1.2.840.10008.1.2.4.90",
+        ts::JPEG_2000_LOSSLESS,
         "JPEG 2000 Image Compression (Lossless Only)",
         TransferSyntaxCategory::LosslessCompressed,
         CompressionType::JPEG2000,
@@ -413,7 +544,7 @@ const ALL_TRANSFER_SYNTAXES: &[TransferSyntaxInfo] = &[
     ),
     
     TransferSyntaxInfo::new(
-        "1.2.840.10008.1.2.4.91",
+        ts::JPEG_2000,
         "JPEG 2000 Image Compression",
         TransferSyntaxCategory::LossyCompressed,
         CompressionType::JPEG2000,
@@ -423,7 +554,7 @@ const ALL_TRANSFER_SYNTAXES: &[TransferSyntaxInfo] = &[
     ),
     
     TransferSyntaxInfo::new(
-        "1.2.840.10008.1.2.4.92",
+        ts::JPEG_2000_MULTI_COMPONENT_LOSSLESS,
         "JPEG 2000 Part 2 Multi-component Image Compression (Lossless Only)",
         TransferSyntaxCategory::LosslessCompressed,
         CompressionType::JPEG2000,
@@ -433,7 +564,7 @@ const ALL_TRANSFER_SYNTAXES: &[TransferSyntaxInfo] = &[
     ),
     
     TransferSyntaxInfo::new(
-        "1.2.840.10008.1.2.4.93",
+        ts::JPEG_2000_MULTI_COMPONENT,
         "JPEG 2000 Part 2 Multi-component Image Compression",
         TransferSyntaxCategory::LossyCompressed,
         CompressionType::JPEG2000,
@@ -447,7 +578,7 @@ const ALL_TRANSFER_SYNTAXES: &[TransferSyntaxInfo] = &[
     // =============================================================================
     
     TransferSyntaxInfo::new(
-        "1.2.840.10008.1.2.4.94",
+        ts::JPIP_REFERENCED,
         "JPIP Referenced",
         TransferSyntaxCategory::Legacy,
         CompressionType::JPEG2000,
@@ -457,7 +588,7 @@ const ALL_TRANSFER_SYNTAXES: &[TransferSyntaxInfo] = &[
     ),
     
     TransferSyntaxInfo::new(
-        "1.2.840.10008.1.2.4.95",
+        ts::JPIP_REFERENCED_DEFLATE,
         "JPIP Referenced Deflate",
         TransferSyntaxCategory::Legacy,
         CompressionType::JPEG2000,
@@ -471,7 +602,7 @@ const ALL_TRANSFER_SYNTAXES: &[TransferSyntaxInfo] = &[
     // =============================================================================
     
     TransferSyntaxInfo::new(
-        "1.2.840.10008.1.2.5",
+        ts::RLE_LOSSLESS,
         "RLE Lossless",
         TransferSyntaxCategory::LosslessCompressed,
         CompressionType::RLE,
@@ -485,7 +616,7 @@ const ALL_TRANSFER_SYNTAXES: &[TransferSyntaxInfo] = &[
     // =============================================================================
     
     TransferSyntaxInfo::new(
-        "1.2.840.10008.1.2.6.1",
+        ts::RFC_2557_MIME_ENCAPSULATION,
         "RFC 2557 MIME encapsulation (Retired)",
         TransferSyntaxCategory::Legacy,
         CompressionType::None,
@@ -495,7 +626,7 @@ const ALL_TRANSFER_SYNTAXES: &[TransferSyntaxInfo] = &[
     ),
     
     TransferSyntaxInfo::new(
-        "1.2.840.10008.1.2.6.2",
+        ts::XML_ENCODING,
         "XML Encoding (Retired)",
         TransferSyntaxCategory::Legacy,
         CompressionType::None,
@@ -509,7 +640,7 @@ const ALL_TRANSFER_SYNTAXES: &[TransferSyntaxInfo] = &[
     // =============================================================================
     
     TransferSyntaxInfo::new(
-        "1.2.840.10008.1.2.7.1",
+        ts::SMPTE_ST_2110_20_UNCOMPRESSED_PROGRESSIVE_ACTIVE_VIDEO,
         "SMPTE ST 2110-20 Uncompressed Progressive Active Video",
         TransferSyntaxCategory::Video,
         CompressionType::None,
@@ -519,7 +650,7 @@ const ALL_TRANSFER_SYNTAXES: &[TransferSyntaxInfo] = &[
     ),
     
     TransferSyntaxInfo::new(
-        "1.2.840.10008.1.2.7.2",
+        ts::SMPTE_ST_2110_20_UNCOMPRESSED_INTERLACED_ACTIVE_VIDEO,
         "SMPTE ST 2110-20 Uncompressed Interlaced Active Video",
         TransferSyntaxCategory::Video,
         CompressionType::None,
@@ -529,7 +660,7 @@ const ALL_TRANSFER_SYNTAXES: &[TransferSyntaxInfo] = &[
     ),
     
     TransferSyntaxInfo::new(
-        "1.2.840.10008.1.2.7.3",
+        ts::SMPTE_ST_2110_30_PCM_DIGITAL_AUDIO,
         "SMPTE ST 2110-30 PCM Digital Audio",
         TransferSyntaxCategory::Video,
         CompressionType::None,
@@ -543,7 +674,7 @@ const ALL_TRANSFER_SYNTAXES: &[TransferSyntaxInfo] = &[
     // =============================================================================
     
     TransferSyntaxInfo::new(
-        "1.2.840.10008.1.2.4.100",
+        ts::MPEG2_MAIN_PROFILE_MAIN_LEVEL,
         "MPEG2 Main Profile / Main Level",
         TransferSyntaxCategory::Video,
         CompressionType::MPEG2,
@@ -553,7 +684,7 @@ const ALL_TRANSFER_SYNTAXES: &[TransferSyntaxInfo] = &[
     ),
     
     TransferSyntaxInfo::new(
-        "1.2.840.10008.1.2.4.101",
+        ts::MPEG2_MAIN_PROFILE_HIGH_LEVEL,
         "MPEG2 Main Profile / High Level",
         TransferSyntaxCategory::Video,
         CompressionType::MPEG2,
@@ -563,7 +694,7 @@ const ALL_TRANSFER_SYNTAXES: &[TransferSyntaxInfo] = &[
     ),
     
     TransferSyntaxInfo::new(
-        "1.2.840.10008.1.2.4.102",
+        ts::MPEG_4_AVC_H_264_HIGH_PROFILE_LEVEL_4_1,
         "MPEG-4 AVC/H.264 High Profile / Level 4.1",
         TransferSyntaxCategory::Video,
         CompressionType::H264,
@@ -573,7 +704,7 @@ const ALL_TRANSFER_SYNTAXES: &[TransferSyntaxInfo] = &[
     ),
     
     TransferSyntaxInfo::new(
-        "1.2.840.10008.1.2.4.103",
+        ts::MPEG_4_AVC_H_264_BD_COMPATIBLE_HIGH_PROFILE_LEVEL_4_1,
         "MPEG-4 AVC/H.264 BD-compatible High Profile / Level 4.1",
         TransferSyntaxCategory::Video,
         CompressionType::H264,
@@ -583,7 +714,7 @@ const ALL_TRANSFER_SYNTAXES: &[TransferSyntaxInfo] = &[
     ),
     
     TransferSyntaxInfo::new(
-        "1.2.840.10008.1.2.4.104",
+        ts::MPEG_4_AVC_H_264_HIGH_PROFILE_LEVEL_4_2_FOR_2D_VIDEO,
         "MPEG-4 AVC/H.264 High Profile / Level 4.2 For 2D Video",
         TransferSyntaxCategory::Video,
         CompressionType::H264,
@@ -593,7 +724,7 @@ const ALL_TRANSFER_SYNTAXES: &[TransferSyntaxInfo] = &[
     ),
     
     TransferSyntaxInfo::new(
-        "1.2.840.10008.1.2.4.105",
+        ts::MPEG_4_AVC_H_264_HIGH_PROFILE_LEVEL_4_2_FOR_3D_VIDEO,
         "MPEG-4 AVC/H.264 High Profile / Level 4.2 For 3D Video",
         TransferSyntaxCategory::Video,
         CompressionType::H264,
@@ -603,7 +734,7 @@ const ALL_TRANSFER_SYNTAXES: &[TransferSyntaxInfo] = &[
     ),
     
     TransferSyntaxInfo::new(
-        "1.2.840.10008.1.2.4.106",
+        ts::MPEG_4_AVC_H_264_STEREO_HIGH_PROFILE_LEVEL_4_2,
         "MPEG-4 AVC/H.264 Stereo High Profile / Level 4.2",
         TransferSyntaxCategory::Video,
         CompressionType::H264,
@@ -617,7 +748,7 @@ const ALL_TRANSFER_SYNTAXES: &[TransferSyntaxInfo] = &[
     // =============================================================================
     
     TransferSyntaxInfo::new(
-        "1.2.840.10008.1.2.4.107",
+        ts::HEVC_H_265_MAIN_PROFILE_LEVEL_5_1,
         "HEVC/H.265 Main Profile / Level 5.1",
         TransferSyntaxCategory::Video,
         CompressionType::H265,
@@ -627,7 +758,7 @@ const ALL_TRANSFER_SYNTAXES: &[TransferSyntaxInfo] = &[
     ),
     
     TransferSyntaxInfo::new(
-        "1.2.840.10008.1.2.4.108",
+        ts::HEVC_H_265_MAIN_10_PROFILE_LEVEL_5_1,
         "HEVC/H.265 Main 10 Profile / Level 5.1",
         TransferSyntaxCategory::Video,
         CompressionType::H265,
@@ -641,7 +772,7 @@ const ALL_TRANSFER_SYNTAXES: &[TransferSyntaxInfo] = &[
     // =============================================================================
     
     TransferSyntaxInfo::new(
-        "1.2.840.10008.1.2.4.201",
+        ts::HTJ2K_LOSSLESS,
         "High-Throughput JPEG 2000 Image Compression (Lossless Only)",
         TransferSyntaxCategory::LosslessCompressed,
         CompressionType::JPEG2000,
@@ -651,7 +782,7 @@ const ALL_TRANSFER_SYNTAXES: &[TransferSyntaxInfo] = &[
     ),
     
     TransferSyntaxInfo::new(
-        "1.2.840.10008.1.2.4.202",
+        ts::HTJ2K_LOSSLESS_RPCL,
         "High-Throughput JPEG 2000 with RPCL Options Image Compression (Lossless Only)",
         TransferSyntaxCategory::LosslessCompressed,
         CompressionType::JPEG2000,
@@ -661,7 +792,7 @@ const ALL_TRANSFER_SYNTAXES: &[TransferSyntaxInfo] = &[
     ),
     
     TransferSyntaxInfo::new(
-        "1.2.840.10008.1.2.4.203",
+        ts::HTJ2K,
         "High-Throughput JPEG 2000 Image Compression",
         TransferSyntaxCategory::LossyCompressed,
         CompressionType::JPEG2000,
@@ -674,45 +805,44 @@ const ALL_TRANSFER_SYNTAXES: &[TransferSyntaxInfo] = &[
 /// Get transfer syntaxes appropriate for different use cases
 pub fn get_basic_transfer_syntaxes() -> Vec<&'static str> {
     vec![
-        "1.2.840.10008.1.2.1", // Explicit VR Little Endian
-        "1.2.840.10008.1.2",   // Implicit VR Little Endian
+        ts::EXPLICIT_VR_LITTLE_ENDIAN,
+        ts::IMPLICIT_VR_LITTLE_ENDIAN,
     ]
 }
 
 pub fn get_lossless_transfer_syntaxes() -> Vec<&'static str> {
     vec![
-        "1.2.840.10008.1.2.1",   // Explicit VR Little Endian
-        "1.2.840.10008.1.2",     // Implicit VR Little Endian
-        "1.2.840.10008.1.2.4.70", // JPEG Lossless
-        "1.2.840.10008.1.2.4.80", // JPEG-LS Lossless
-        "1.2.840.10008.1.2.4.90", // JPEG 2000 Lossless
-        "1.2.840.10008.1.2.5",    // RLE Lossless
+        ts::EXPLICIT_VR_LITTLE_ENDIAN,
+        ts::IMPLICIT_VR_LITTLE_ENDIAN,
+        ts::JPEG_LOSSLESS_SV1,
+        ts::JPEG_LS_LOSSLESS,
+        ts::JPEG_2000_LOSSLESS,
+        ts::RLE_LOSSLESS,
     ]
 }
 
 pub fn get_compressed_transfer_syntaxes() -> Vec<&'static str> {
     vec![
-        "1.2.840.10008.1.2.1",   // Explicit VR Little Endian
-        "1.2.840.10008.1.2",     // Implicit VR Little Endian
-        "1.2.840.10008.1.2.4.50", // JPEG Baseline
-        "1.2.840.10008.1.2.4.51", // JPEG Extended
-        "1.2.840.10008.1.2.4.70", // JPEG Lossless
-        "1.2.840.10008.1.2.4.80", // JPEG-LS Lossless
-        "1.2.840.10008.1.2.4.81", // JPEG-LS Near-Lossless
-        "1.2.840.10008.1.2.4.90", // JPEG 2000 Lossless
-        "1.2.840.10008.1.2.4.91", // JPEG 2000
-        "1.2.840.10008.1.2.5",    // RLE Lossless
+        ts::EXPLICIT_VR_LITTLE_ENDIAN,
+        ts::IMPLICIT_VR_LITTLE_ENDIAN,
+        ts::JPEG_BASELINE,
+        ts::JPEG_EXTENDED,
+        ts::JPEG_LOSSLESS_SV1,
+        ts::JPEG_LS_LOSSLESS,
+        ts::JPEG_LS_NEAR_LOSSLESS,
+        ts::JPEG_2000_LOSSLESS,
+        ts::JPEG_2000,
+        ts::RLE_LOSSLESS,
     ]
 }
 
+/// Every registered transfer syntax this build can handle
 pub fn get_comprehensive_transfer_syntaxes() -> Vec<&'static str> {
-    let registry = TransferSyntaxRegistry::new();
-    registry.get_all_uids()
+    TransferSyntaxRegistry::global().get_handled_uids()
 }
 
 pub fn get_video_transfer_syntaxes() -> Vec<&'static str> {
-    let registry = TransferSyntaxRegistry::new();
-    registry.get_by_category(TransferSyntaxCategory::Video)
+    TransferSyntaxRegistry::global().get_by_category(TransferSyntaxCategory::Video)
         .iter()
         .map(|ts| ts.uid)
         .collect()
@@ -722,6 +852,34 @@ pub fn get_video_transfer_syntaxes() -> Vec<&'static str> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_find_by_uid_or_keyword() {
+        let registry = TransferSyntaxRegistry::new();
+        assert_eq!(registry.find("ExplicitVRLittleEndian").map(|ts| ts.uid), Some("1.2.840.10008.1.2.1"));
+        assert_eq!(registry.find("1.2.840.10008.1.2").map(|ts| ts.name), Some("Implicit VR Little Endian"));
+        assert_eq!(TransferSyntaxCategory::from_name("lossycompressed"), Some(TransferSyntaxCategory::LossyCompressed));
+    }
+
+    #[test]
+    fn test_best_common_transfer_syntax() {
+        let registry = TransferSyntaxRegistry::new();
+        let proposed = [ts::JPEG_BASELINE, ts::JPEG_2000_LOSSLESS, ts::IMPLICIT_VR_LITTLE_ENDIAN, ts::EXPLICIT_VR_LITTLE_ENDIAN];
+        let accepted = vec![ts::EXPLICIT_VR_LITTLE_ENDIAN.to_string(), ts::IMPLICIT_VR_LITTLE_ENDIAN.to_string(),
+                            ts::JPEG_2000_LOSSLESS.to_string(), ts::JPEG_BASELINE.to_string()];
+
+        assert_eq!(registry.best_common(&proposed, &accepted, TransferSyntaxPolicy::ProposerOrder), Some(ts::JPEG_BASELINE));
+        assert_eq!(registry.common_transfer_syntaxes(&proposed, &accepted, TransferSyntaxPolicy::PreferLossless),
+                   vec![ts::JPEG_2000_LOSSLESS, ts::IMPLICIT_VR_LITTLE_ENDIAN, ts::EXPLICIT_VR_LITTLE_ENDIAN, ts::JPEG_BASELINE]);
+        assert_eq!(registry.common_transfer_syntaxes(&proposed, &accepted, TransferSyntaxPolicy::PreferNative),
+                   vec![ts::EXPLICIT_VR_LITTLE_ENDIAN, ts::IMPLICIT_VR_LITTLE_ENDIAN, ts::JPEG_2000_LOSSLESS, ts::JPEG_BASELINE]);
+
+        // Only syntaxes both sides support count, unknown ones last
+        let accepted = [ts::JPEG_BASELINE, "1.2.3.4", ts::RLE_LOSSLESS];
+        assert_eq!(registry.common_transfer_syntaxes(&["1.2.3.4", ts::JPEG_BASELINE], &accepted, TransferSyntaxPolicy::PreferLossless),
+                   vec![ts::JPEG_BASELINE, "1.2.3.4"]);
+        assert_eq!(registry.best_common(&[ts::IMPLICIT_VR_LITTLE_ENDIAN], &accepted, TransferSyntaxPolicy::PreferNative), None);
+    }
+
     #[test]
     fn test_transfer_syntax_registry() {
         let registry = TransferSyntaxRegistry::new();
@@ -0,0 +1,227 @@
+//! Receiver-side transfer syntax preference
+//!
+//! dicom-ul accepts, for each proposed presentation context, the first
+//! transfer syntax in the proposer's order that is on the acceptor's list. To
+//! choose by our own preference instead (e.g. keep a modality's native JPEG
+//! 2000 rather than have it decompressed to Explicit VR Little Endian), the
+//! A-ASSOCIATE-RQ is read ahead of negotiation and the acceptor's list is
+//! narrowed to the preferred syntax of each context.
+
+/// PDU header: type, reserved, 4-byte length
+const PDU_HEADER_LENGTH: usize = 6;
+/// Protocol version, reserved, called and calling AE titles, reserved
+const RQ_FIXED_FIELDS_LENGTH: usize = 68;
+const ASSOCIATE_RQ_TYPE: u8 = 0x01;
+const PRESENTATION_CONTEXT_ITEM: u8 = 0x20;
+const ABSTRACT_SYNTAX_ITEM: u8 = 0x30;
+const TRANSFER_SYNTAX_ITEM: u8 = 0x40;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProposedContext {
+    pub id: u8,
+    pub abstract_syntax: String,
+    pub transfer_syntaxes: Vec<String>,
+}
+
+/// Total length of a PDU, from its 6-byte header
+pub fn pdu_length(header: &[u8]) -> Option<usize> {
+    let length = u32::from_be_bytes(header.get(2..6)?.try_into().ok()?);
+    PDU_HEADER_LENGTH.checked_add(usize::try_from(length).ok()?)
+}
+
+fn uid(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes)
+        .trim_end_matches(['\0', ' '])
+        .to_string()
+}
+
+/// Sub-items (type, content) of an item list
+fn items(mut bytes: &[u8]) -> Result<Vec<(u8, &[u8])>, String> {
+    let mut items = Vec::new();
+    while !bytes.is_empty() {
+        if bytes.len() < 4 {
+            return Err("truncated item header".to_string());
+        }
+        let length = u16::from_be_bytes([bytes[2], bytes[3]]) as usize;
+        let content = bytes.get(4..4 + length).ok_or("item length exceeds PDU")?;
+        items.push((bytes[0], content));
+        bytes = &bytes[4 + length..];
+    }
+    Ok(items)
+}
+
+/// Called AE title of an A-ASSOCIATE-RQ PDU, without padding
+pub fn called_ae_title(pdu: &[u8]) -> Option<String> {
+    if pdu.first() != Some(&ASSOCIATE_RQ_TYPE) {
+        return None;
+    }
+    // Protocol version and a reserved field precede it
+    pdu.get(PDU_HEADER_LENGTH + 4..PDU_HEADER_LENGTH + 20)
+        .map(|title| uid(title).trim().to_string())
+}
+
+/// Presentation contexts proposed in an A-ASSOCIATE-RQ PDU
+pub fn parse_association_rq(pdu: &[u8]) -> Result<Vec<ProposedContext>, String> {
+    if pdu.first() != Some(&ASSOCIATE_RQ_TYPE) {
+        return Err("not an A-ASSOCIATE-RQ PDU".to_string());
+    }
+    let length = pdu_length(pdu).ok_or("truncated PDU header")?;
+    let variable = pdu.get(PDU_HEADER_LENGTH + RQ_FIXED_FIELDS_LENGTH..length)
+        .ok_or("truncated A-ASSOCIATE-RQ")?;
+
+    let mut contexts = Vec::new();
+    for (item_type, content) in items(variable)? {
+        if item_type != PRESENTATION_CONTEXT_ITEM {
+            continue;
+        }
+        let (&id, rest) = content.split_first().ok_or("empty presentation context item")?;
+        let mut context = ProposedContext {
+            id,
+            abstract_syntax: String::new(),
+            transfer_syntaxes: Vec::new(),
+        };
+        for (sub_type, value) in items(rest.get(3..).ok_or("truncated presentation context item")?)? {
+            match sub_type {
+                ABSTRACT_SYNTAX_ITEM => context.abstract_syntax = uid(value),
+                TRANSFER_SYNTAX_ITEM => context.transfer_syntaxes.push(uid(value)),
+                _ => {}
+            }
+        }
+        contexts.push(context);
+    }
+    Ok(contexts)
+}
+
+/// Transfer syntaxes in order of preference
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransferSyntaxPreference {
+    pub order: Vec<String>,
+}
+
+impl TransferSyntaxPreference {
+    /// Parse a comma-separated list of transfer syntax UIDs
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let order: Vec<String> = value.split(',')
+            .map(|uid| uid.trim().to_string())
+            .filter(|uid| !uid.is_empty())
+            .collect();
+        if order.is_empty() {
+            return Err("empty transfer syntax preference list".to_string());
+        }
+        Ok(Self { order })
+    }
+
+    /// Preferred transfer syntax for a context, falling back to the proposer's
+    /// first supported one; preferred syntaxes must be supported as well
+    pub fn choose<'a>(&self, proposed: &'a [String], supported: impl Fn(&str) -> bool) -> Option<&'a str> {
+        self.order.iter()
+            .find_map(|preferred| proposed.iter().find(|ts| *ts == preferred && supported(ts)))
+            .or_else(|| proposed.iter().find(|ts| supported(ts)))
+            .map(String::as_str)
+    }
+
+    /// Acceptor transfer syntax list that leads each context to its preferred syntax
+    ///
+    /// The list is shared by all contexts, so a context that proposes another
+    /// context's choice ahead of its own may still end up with that one.
+    pub fn acceptor_list(&self, contexts: &[ProposedContext], supported: impl Fn(&str) -> bool) -> Vec<String> {
+        let mut list: Vec<String> = Vec::new();
+        for context in contexts {
+            if let Some(ts) = self.choose(&context.transfer_syntaxes, &supported) {
+                if !list.iter().any(|existing| existing == ts) {
+                    list.push(ts.to_string());
+                }
+            }
+        }
+        list
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const IMPLICIT_LE: &str = "1.2.840.10008.1.2";
+    const EXPLICIT_LE: &str = "1.2.840.10008.1.2.1";
+    const JPEG_2000_LOSSLESS: &str = "1.2.840.10008.1.2.4.90";
+
+    fn item(item_type: u8, content: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![item_type, 0];
+        bytes.extend_from_slice(&(content.len() as u16).to_be_bytes());
+        bytes.extend_from_slice(content);
+        bytes
+    }
+
+    fn association_rq(contexts: &[(u8, &str, &[&str])]) -> Vec<u8> {
+        let mut variable = item(0x10, b"1.2.840.10008.3.1.1.1");
+        for (id, abstract_syntax, transfer_syntaxes) in contexts {
+            let mut content = vec![*id, 0, 0, 0];
+            content.extend(item(ABSTRACT_SYNTAX_ITEM, abstract_syntax.as_bytes()));
+            for ts in *transfer_syntaxes {
+                // UIDs are padded to even length with a NUL
+                let mut value = ts.as_bytes().to_vec();
+                if value.len() % 2 == 1 {
+                    value.push(0);
+                }
+                content.extend(item(TRANSFER_SYNTAX_ITEM, &value));
+            }
+            variable.extend(item(PRESENTATION_CONTEXT_ITEM, &content));
+        }
+
+        let mut pdu = vec![ASSOCIATE_RQ_TYPE, 0];
+        pdu.extend_from_slice(&((RQ_FIXED_FIELDS_LENGTH + variable.len()) as u32).to_be_bytes());
+        pdu.extend_from_slice(&[0u8; RQ_FIXED_FIELDS_LENGTH]);
+        pdu.extend(variable);
+        pdu
+    }
+
+    #[test]
+    fn test_parse_association_rq() {
+        let pdu = association_rq(&[
+            (1, "1.2.840.10008.5.1.4.1.1.2", &[EXPLICIT_LE, JPEG_2000_LOSSLESS]),
+            (3, "1.2.840.10008.5.1.4.1.1.4", &[IMPLICIT_LE]),
+        ]);
+        assert_eq!(pdu_length(&pdu), Some(pdu.len()));
+
+        let contexts = parse_association_rq(&pdu).unwrap();
+        assert_eq!(contexts.len(), 2);
+        assert_eq!(contexts[0].id, 1);
+        assert_eq!(contexts[0].abstract_syntax, "1.2.840.10008.5.1.4.1.1.2");
+        assert_eq!(contexts[0].transfer_syntaxes, vec![EXPLICIT_LE, JPEG_2000_LOSSLESS]);
+        assert_eq!(contexts[1].transfer_syntaxes, vec![IMPLICIT_LE]);
+
+        assert!(parse_association_rq(&pdu[..40]).is_err());
+
+        let mut addressed = pdu.clone();
+        addressed[10..26].copy_from_slice(b"ARCHIVE         ");
+        assert_eq!(called_ae_title(&addressed).as_deref(), Some("ARCHIVE"));
+        assert_eq!(called_ae_title(&pdu[..12]), None);
+    }
+
+    #[test]
+    fn test_preference_overrides_proposer_order() {
+        let preference = TransferSyntaxPreference::parse(&format!("{}, {}", JPEG_2000_LOSSLESS, EXPLICIT_LE)).unwrap();
+        let supported = |_: &str| true;
+        let contexts = vec![
+            ProposedContext {
+                id: 1,
+                abstract_syntax: "1.2.840.10008.5.1.4.1.1.2".to_string(),
+                transfer_syntaxes: vec![EXPLICIT_LE.to_string(), JPEG_2000_LOSSLESS.to_string()],
+            },
+            ProposedContext {
+                id: 3,
+                abstract_syntax: "1.2.840.10008.5.1.4.1.1.4".to_string(),
+                transfer_syntaxes: vec![IMPLICIT_LE.to_string()],
+            },
+        ];
+
+        assert_eq!(preference.choose(&contexts[0].transfer_syntaxes, supported), Some(JPEG_2000_LOSSLESS));
+        // Nothing preferred was proposed: take the proposer's first supported syntax
+        assert_eq!(preference.choose(&contexts[1].transfer_syntaxes, supported), Some(IMPLICIT_LE));
+        assert_eq!(preference.acceptor_list(&contexts, supported), vec![JPEG_2000_LOSSLESS, IMPLICIT_LE]);
+
+        // A preferred syntax this build cannot handle is passed over
+        let without_jpeg_2000 = |ts: &str| ts != JPEG_2000_LOSSLESS;
+        assert_eq!(preference.choose(&contexts[0].transfer_syntaxes, without_jpeg_2000), Some(EXPLICIT_LE));
+    }
+}
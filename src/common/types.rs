@@ -3,6 +3,10 @@ use std::path::PathBuf;
 use std::time::Duration;
 use chrono::{DateTime, Utc};
 
+use super::manifest::ManifestEntry;
+use super::metrics::ThroughputSample;
+use super::rejection::AssociationRejection;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DicomFile {
     pub path: PathBuf,
@@ -14,6 +18,59 @@ pub struct DicomFile {
     pub modality: Option<String>,
     pub patient_id: Option<String>,
     pub study_date: Option<String>,
+    pub acquisition_date: Option<String>,
+}
+
+#[cfg(test)]
+impl DicomFile {
+    /// A 512-byte CT image at `path` in study 1.2.3, series 1.2.3.1, for tests
+    /// to adjust with the `with_*` methods
+    pub fn test(path: impl Into<PathBuf>, sop_instance_uid: &str) -> Self {
+        Self {
+            path: path.into(),
+            study_instance_uid: "1.2.3".to_string(),
+            series_instance_uid: "1.2.3.1".to_string(),
+            sop_instance_uid: sop_instance_uid.to_string(),
+            sop_class_uid: "1.2.840.10008.5.1.4.1.1.2".to_string(),
+            file_size: 512,
+            modality: Some("CT".to_string()),
+            patient_id: None,
+            study_date: None,
+            acquisition_date: None,
+        }
+    }
+
+    pub fn with_study(mut self, study_instance_uid: &str) -> Self {
+        self.study_instance_uid = study_instance_uid.to_string();
+        self
+    }
+
+    pub fn with_series(mut self, series_instance_uid: &str) -> Self {
+        self.series_instance_uid = series_instance_uid.to_string();
+        self
+    }
+
+    pub fn with_sop_class(mut self, sop_class_uid: &str) -> Self {
+        self.sop_class_uid = sop_class_uid.to_string();
+        self
+    }
+
+    pub fn with_size(mut self, file_size: u64) -> Self {
+        self.file_size = file_size;
+        self
+    }
+
+    pub fn with_modality(mut self, modality: Option<&str>) -> Self {
+        self.modality = modality.map(str::to_string);
+        self
+    }
+
+    pub fn with_patient(mut self, patient_id: &str, study_date: &str, acquisition_date: &str) -> Self {
+        self.patient_id = Some(patient_id.to_string());
+        self.study_date = Some(study_date.to_string());
+        self.acquisition_date = Some(acquisition_date.to_string());
+        self
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -25,6 +82,9 @@ pub struct TransferResult {
     pub error_message: Option<String>,
     pub transfer_time_ms: u64,
     pub file_size: u64,
+    /// Size of the data set as encoded for the destination; 0 unless it was sent
+    #[serde(default)]
+    pub bytes_sent: u64,
     pub timestamp: DateTime<Utc>,
     pub thread_id: usize,
 }
@@ -35,8 +95,23 @@ pub struct TransferStats {
     pub successful_transfers: usize,
     pub failed_transfers: usize,
     pub total_bytes: u64,
+    /// Bytes of P-DATA and release PDUs sent, including PDU and PDV headers,
+    /// commands and data of attempts that failed
+    pub wire_bytes_sent: u64,
+    /// Bytes of P-DATA and release PDUs received (responses)
+    pub wire_bytes_received: u64,
     pub total_time: Duration,
     pub transfer_times: Vec<Duration>,
+    /// Checksums of the instances sent, when checksumming is enabled
+    pub manifest_entries: Vec<ManifestEntry>,
+    /// Outcome of every instance attempted
+    pub results: Vec<TransferResult>,
+    /// Studies that did not transfer completely, when study transactions are enabled
+    pub failed_studies: Vec<StudyTransactionFailure>,
+    /// Associations the destination rejected outright
+    pub association_rejections: Vec<RejectedAssociation>,
+    /// Presentation contexts the destination did not accept
+    pub refused_contexts: Vec<RefusedPresentationContext>,
 }
 
 impl TransferStats {
@@ -46,11 +121,35 @@ impl TransferStats {
             successful_transfers: 0,
             failed_transfers: 0,
             total_bytes: 0,
+            wire_bytes_sent: 0,
+            wire_bytes_received: 0,
             total_time: Duration::from_secs(0),
             transfer_times: Vec::new(),
+            manifest_entries: Vec::new(),
+            results: Vec::new(),
+            failed_studies: Vec::new(),
+            association_rejections: Vec::new(),
+            refused_contexts: Vec::new(),
         }
     }
 
+    /// Add the statistics of work that ran concurrently with this one
+    pub fn merge(&mut self, other: TransferStats) {
+        self.total_files += other.total_files;
+        self.successful_transfers += other.successful_transfers;
+        self.failed_transfers += other.failed_transfers;
+        self.total_bytes += other.total_bytes;
+        self.wire_bytes_sent += other.wire_bytes_sent;
+        self.wire_bytes_received += other.wire_bytes_received;
+        self.total_time = self.total_time.max(other.total_time);
+        self.transfer_times.extend(other.transfer_times);
+        self.manifest_entries.extend(other.manifest_entries);
+        self.results.extend(other.results);
+        self.failed_studies.extend(other.failed_studies);
+        self.association_rejections.extend(other.association_rejections);
+        self.refused_contexts.extend(other.refused_contexts);
+    }
+
     pub fn get_throughput_mbps(&self) -> f64 {
         let elapsed = self.total_time.as_secs_f64();
         let bytes = self.total_bytes as f64;
@@ -61,6 +160,16 @@ impl TransferStats {
         }
     }
 
+    /// Throughput of everything sent on the wire rather than datasets only
+    pub fn get_wire_throughput_mbps(&self) -> f64 {
+        let elapsed = self.total_time.as_secs_f64();
+        if elapsed > 0.0 {
+            (self.wire_bytes_sent as f64 / (1024.0 * 1024.0)) / elapsed
+        } else {
+            0.0
+        }
+    }
+
     pub fn get_average_transfer_time_ms(&self) -> f64 {
         if self.transfer_times.is_empty() {
             0.0
@@ -82,12 +191,85 @@ pub struct SessionSummary {
     pub successful_transfers: usize,
     pub failed_transfers: usize,
     pub total_bytes: u64,
+    /// Size on disk of the files sent, for the compression achieved against `total_bytes`
+    #[serde(default)]
+    pub original_bytes: u64,
     pub total_time_ms: u64,
     pub average_transfer_time_ms: f64,
     pub throughput_mbps: f64,
+    /// Bytes actually sent and received, protocol overhead included
+    pub wire_bytes_sent: u64,
+    pub wire_bytes_received: u64,
+    pub wire_throughput_mbps: f64,
     pub threads_used: usize,
     pub destination: String,
     pub calling_ae: String,
     pub called_ae: String,
     pub studies_processed: Vec<String>,
+    pub duplicate_uid_conflicts: Vec<DuplicateUidConflict>,
+    pub failed_studies: Vec<StudyTransactionFailure>,
+    pub association_rejections: Vec<RejectedAssociation>,
+    pub refused_presentation_contexts: Vec<RefusedPresentationContext>,
+    /// Per-interval throughput, when sampling is enabled
+    pub throughput_samples: Vec<ThroughputSample>,
+}
+
+/// A study whose association was rejected, with the A-ASSOCIATE-RJ codes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RejectedAssociation {
+    pub study_instance_uid: String,
+    #[serde(flatten)]
+    pub rejection: AssociationRejection,
+    pub description: String,
+}
+
+/// A presentation context the destination refused during negotiation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RefusedPresentationContext {
+    pub sop_class_uid: String,
+    /// Presentation context result code (1-4)
+    pub result: u8,
+    pub description: String,
+}
+
+/// Several input files claiming the same SOP Instance UID
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuplicateUidConflict {
+    pub sop_instance_uid: String,
+    pub files: Vec<String>,
+    /// The file that was sent, if the policy kept one
+    pub sent_file: Option<String>,
+}
+
+/// Partial state of a study that was treated as a transaction and did not complete
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StudyTransactionFailure {
+    pub study_instance_uid: String,
+    pub total_instances: usize,
+    /// SOP Instance UIDs the destination accepted
+    pub sent_instances: Vec<String>,
+    pub failed_instances: Vec<TransferResult>,
+}
+
+impl StudyTransactionFailure {
+    /// Build the failure record for a study, or `None` if every instance was sent
+    pub fn from_results(study_instance_uid: &str, results: &[TransferResult]) -> Option<Self> {
+        let failed_instances: Vec<TransferResult> = results.iter()
+            .filter(|r| !r.success)
+            .cloned()
+            .collect();
+        if failed_instances.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            study_instance_uid: study_instance_uid.to_string(),
+            total_instances: results.len(),
+            sent_instances: results.iter()
+                .filter(|r| r.success)
+                .map(|r| r.sop_instance_uid.clone())
+                .collect(),
+            failed_instances,
+        })
+    }
 }
@@ -0,0 +1,520 @@
+//! Typed UID constants for the SOP classes and transfer syntaxes in the registries
+//!
+//! Each constant is named after its registry entry in SCREAMING_SNAKE_CASE
+//! (`sop::CT_IMAGE_STORAGE`, `ts::EXPLICIT_VR_LITTLE_ENDIAN`), and the
+//! registries are built from these constants, so code that names a UID
+//! through them cannot drift from what the registries know.
+
+pub mod sop {
+    /// Verification SOP Class, answered with C-ECHO
+    pub const VERIFICATION: &str = "1.2.840.10008.1.1";
+
+    /// Computed Radiography Image Storage
+    pub const COMPUTED_RADIOGRAPHY_IMAGE_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.1";
+
+    /// Digital X-Ray Image Storage - For Presentation
+    pub const DIGITAL_X_RAY_IMAGE_STORAGE_FOR_PRESENTATION: &str = "1.2.840.10008.5.1.4.1.1.1.1";
+
+    /// Digital X-Ray Image Storage - For Processing
+    pub const DIGITAL_X_RAY_IMAGE_STORAGE_FOR_PROCESSING: &str = "1.2.840.10008.5.1.4.1.1.1.1.1";
+
+    /// Digital Mammography X-Ray Image Storage - For Presentation
+    pub const DIGITAL_MAMMOGRAPHY_X_RAY_IMAGE_STORAGE_FOR_PRESENTATION: &str = "1.2.840.10008.5.1.4.1.1.1.2";
+
+    /// Digital Mammography X-Ray Image Storage - For Processing
+    pub const DIGITAL_MAMMOGRAPHY_X_RAY_IMAGE_STORAGE_FOR_PROCESSING: &str = "1.2.840.10008.5.1.4.1.1.1.2.1";
+
+    /// Digital Intra-Oral X-Ray Image Storage - For Presentation
+    pub const DIGITAL_INTRA_ORAL_X_RAY_IMAGE_STORAGE_FOR_PRESENTATION: &str = "1.2.840.10008.5.1.4.1.1.1.3";
+
+    /// Digital Intra-Oral X-Ray Image Storage - For Processing
+    pub const DIGITAL_INTRA_ORAL_X_RAY_IMAGE_STORAGE_FOR_PROCESSING: &str = "1.2.840.10008.5.1.4.1.1.1.3.1";
+
+    /// CT Image Storage
+    pub const CT_IMAGE_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.2";
+
+    /// Enhanced CT Image Storage
+    pub const ENHANCED_CT_IMAGE_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.2.1";
+
+    /// Legacy Converted Enhanced CT Image Storage
+    pub const LEGACY_CONVERTED_ENHANCED_CT_IMAGE_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.2.2";
+
+    /// Ultrasound Multi-frame Image Storage
+    pub const ULTRASOUND_MULTI_FRAME_IMAGE_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.3.1";
+
+    /// MR Image Storage
+    pub const MR_IMAGE_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.4";
+
+    /// Enhanced MR Image Storage
+    pub const ENHANCED_MR_IMAGE_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.4.1";
+
+    /// MR Spectroscopy Storage
+    pub const MR_SPECTROSCOPY_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.4.2";
+
+    /// Enhanced MR Color Image Storage
+    pub const ENHANCED_MR_COLOR_IMAGE_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.4.3";
+
+    /// Legacy Converted Enhanced MR Image Storage
+    pub const LEGACY_CONVERTED_ENHANCED_MR_IMAGE_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.4.4";
+
+    /// Nuclear Medicine Image Storage (Retired)
+    pub const NUCLEAR_MEDICINE_IMAGE_STORAGE_RETIRED: &str = "1.2.840.10008.5.1.4.1.1.5";
+
+    /// Nuclear Medicine Image Storage
+    pub const NUCLEAR_MEDICINE_IMAGE_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.20";
+
+    /// Ultrasound Image Storage
+    pub const ULTRASOUND_IMAGE_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.6.1";
+
+    /// Enhanced US Volume Storage
+    pub const ENHANCED_US_VOLUME_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.6.2";
+
+    /// Secondary Capture Image Storage
+    pub const SECONDARY_CAPTURE_IMAGE_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.7";
+
+    /// Multi-frame Single Bit Secondary Capture Image Storage
+    pub const MULTI_FRAME_SINGLE_BIT_SECONDARY_CAPTURE_IMAGE_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.7.1";
+
+    /// Multi-frame Grayscale Byte Secondary Capture Image Storage
+    pub const MULTI_FRAME_GRAYSCALE_BYTE_SECONDARY_CAPTURE_IMAGE_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.7.2";
+
+    /// Multi-frame Grayscale Word Secondary Capture Image Storage
+    pub const MULTI_FRAME_GRAYSCALE_WORD_SECONDARY_CAPTURE_IMAGE_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.7.3";
+
+    /// Multi-frame True Color Secondary Capture Image Storage
+    pub const MULTI_FRAME_TRUE_COLOR_SECONDARY_CAPTURE_IMAGE_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.7.4";
+
+    /// X-Ray Angiographic Image Storage
+    pub const X_RAY_ANGIOGRAPHIC_IMAGE_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.12.1";
+
+    /// Enhanced XA Image Storage
+    pub const ENHANCED_XA_IMAGE_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.12.1.1";
+
+    /// X-Ray Radiofluoroscopic Image Storage
+    pub const X_RAY_RADIOFLUOROSCOPIC_IMAGE_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.12.2";
+
+    /// Enhanced XRF Image Storage
+    pub const ENHANCED_XRF_IMAGE_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.12.2.1";
+
+    /// Positron Emission Tomography Image Storage
+    pub const POSITRON_EMISSION_TOMOGRAPHY_IMAGE_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.128";
+
+    /// Enhanced PET Image Storage
+    pub const ENHANCED_PET_IMAGE_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.130";
+
+    /// Legacy Converted Enhanced PET Image Storage
+    pub const LEGACY_CONVERTED_ENHANCED_PET_IMAGE_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.131";
+
+    /// RT Image Storage
+    pub const RT_IMAGE_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.481.1";
+
+    /// RT Dose Storage
+    pub const RT_DOSE_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.481.2";
+
+    /// RT Structure Set Storage
+    pub const RT_STRUCTURE_SET_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.481.3";
+
+    /// RT Beams Treatment Record Storage
+    pub const RT_BEAMS_TREATMENT_RECORD_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.481.4";
+
+    /// RT Plan Storage
+    pub const RT_PLAN_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.481.5";
+
+    /// RT Brachy Treatment Record Storage
+    pub const RT_BRACHY_TREATMENT_RECORD_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.481.6";
+
+    /// RT Treatment Summary Record Storage
+    pub const RT_TREATMENT_SUMMARY_RECORD_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.481.7";
+
+    /// RT Ion Plan Storage
+    pub const RT_ION_PLAN_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.481.8";
+
+    /// RT Ion Beams Treatment Record Storage
+    pub const RT_ION_BEAMS_TREATMENT_RECORD_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.481.9";
+
+    /// 12-lead ECG Waveform Storage
+    pub const TWELVE_LEAD_ECG_WAVEFORM_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.9.1.1";
+
+    /// General ECG Waveform Storage
+    pub const GENERAL_ECG_WAVEFORM_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.9.1.2";
+
+    /// Ambulatory ECG Waveform Storage
+    pub const AMBULATORY_ECG_WAVEFORM_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.9.1.3";
+
+    /// Hemodynamic Waveform Storage
+    pub const HEMODYNAMIC_WAVEFORM_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.9.2.1";
+
+    /// Cardiac Electrophysiology Waveform Storage
+    pub const CARDIAC_ELECTROPHYSIOLOGY_WAVEFORM_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.9.3.1";
+
+    /// Basic Voice Audio Waveform Storage
+    pub const BASIC_VOICE_AUDIO_WAVEFORM_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.9.4.1";
+
+    /// General Audio Waveform Storage
+    pub const GENERAL_AUDIO_WAVEFORM_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.9.4.2";
+
+    /// Arterial Pulse Waveform Storage
+    pub const ARTERIAL_PULSE_WAVEFORM_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.9.5.1";
+
+    /// Respiratory Waveform Storage
+    pub const RESPIRATORY_WAVEFORM_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.9.6.1";
+
+    /// Multi-channel Respiratory Waveform Storage
+    pub const MULTI_CHANNEL_RESPIRATORY_WAVEFORM_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.9.6.2";
+
+    /// Routine Scalp Electroencephalogram Waveform Storage
+    pub const ROUTINE_SCALP_ELECTROENCEPHALOGRAM_WAVEFORM_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.9.7.1";
+
+    /// Electromyogram Waveform Storage
+    pub const ELECTROMYOGRAM_WAVEFORM_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.9.7.2";
+
+    /// Electrooculogram Waveform Storage
+    pub const ELECTROOCULOGRAM_WAVEFORM_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.9.7.3";
+
+    /// Sleep Electroencephalogram Waveform Storage
+    pub const SLEEP_ELECTROENCEPHALOGRAM_WAVEFORM_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.9.7.4";
+
+    /// Body Position Waveform Storage
+    pub const BODY_POSITION_WAVEFORM_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.9.8.1";
+
+    /// Basic Text SR Storage
+    pub const BASIC_TEXT_SR_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.88.11";
+
+    /// Enhanced SR Storage
+    pub const ENHANCED_SR_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.88.22";
+
+    /// Comprehensive SR Storage
+    pub const COMPREHENSIVE_SR_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.88.33";
+
+    /// Procedure Log Storage
+    pub const PROCEDURE_LOG_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.88.40";
+
+    /// Mammography CAD SR Storage
+    pub const MAMMOGRAPHY_CAD_SR_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.88.50";
+
+    /// Key Object Selection Document Storage
+    pub const KEY_OBJECT_SELECTION_DOCUMENT_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.88.59";
+
+    /// Chest CAD SR Storage
+    pub const CHEST_CAD_SR_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.88.65";
+
+    /// X-Ray Radiation Dose SR Storage
+    pub const X_RAY_RADIATION_DOSE_SR_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.88.67";
+
+    /// Radiopharmaceutical Radiation Dose SR Storage
+    pub const RADIOPHARMACEUTICAL_RADIATION_DOSE_SR_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.88.68";
+
+    /// Colon CAD SR Storage
+    pub const COLON_CAD_SR_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.88.69";
+
+    /// Implantation Plan SR Storage
+    pub const IMPLANTATION_PLAN_SR_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.88.70";
+
+    /// Acquisition Context SR Storage
+    pub const ACQUISITION_CONTEXT_SR_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.88.71";
+
+    /// Simplified Adult Echo SR Storage
+    pub const SIMPLIFIED_ADULT_ECHO_SR_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.88.72";
+
+    /// Patient Radiation Dose SR Storage
+    pub const PATIENT_RADIATION_DOSE_SR_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.88.73";
+
+    /// Planned Imaging Agent Administration SR Storage
+    pub const PLANNED_IMAGING_AGENT_ADMINISTRATION_SR_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.88.74";
+
+    /// Performed Imaging Agent Administration SR Storage
+    pub const PERFORMED_IMAGING_AGENT_ADMINISTRATION_SR_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.88.75";
+
+    /// Enhanced X-Ray Radiation Dose SR Storage
+    pub const ENHANCED_X_RAY_RADIATION_DOSE_SR_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.88.76";
+
+    /// Raw Data Storage
+    pub const RAW_DATA_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.66";
+
+    /// Spatial Registration Storage
+    pub const SPATIAL_REGISTRATION_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.66.1";
+
+    /// Spatial Fiducials Storage
+    pub const SPATIAL_FIDUCIALS_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.66.2";
+
+    /// Deformable Spatial Registration Storage
+    pub const DEFORMABLE_SPATIAL_REGISTRATION_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.66.3";
+
+    /// Segmentation Storage
+    pub const SEGMENTATION_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.66.4";
+
+    /// Surface Segmentation Storage
+    pub const SURFACE_SEGMENTATION_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.66.5";
+
+    /// Tractography Results Storage
+    pub const TRACTOGRAPHY_RESULTS_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.66.6";
+
+    /// Grayscale Softcopy Presentation State Storage
+    pub const GRAYSCALE_SOFTCOPY_PRESENTATION_STATE_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.11.1";
+
+    /// Color Softcopy Presentation State Storage
+    pub const COLOR_SOFTCOPY_PRESENTATION_STATE_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.11.2";
+
+    /// Pseudo-Color Softcopy Presentation State Storage
+    pub const PSEUDO_COLOR_SOFTCOPY_PRESENTATION_STATE_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.11.3";
+
+    /// Blending Softcopy Presentation State Storage
+    pub const BLENDING_SOFTCOPY_PRESENTATION_STATE_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.11.4";
+
+    /// XA/XRF Grayscale Softcopy Presentation State Storage
+    pub const XA_XRF_GRAYSCALE_SOFTCOPY_PRESENTATION_STATE_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.11.5";
+
+    /// Grayscale Planar MPR Volumetric Presentation State Storage
+    pub const GRAYSCALE_PLANAR_MPR_VOLUMETRIC_PRESENTATION_STATE_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.11.6";
+
+    /// Compositing Planar MPR Volumetric Presentation State Storage
+    pub const COMPOSITING_PLANAR_MPR_VOLUMETRIC_PRESENTATION_STATE_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.11.7";
+
+    /// Advanced Blending Presentation State Storage
+    pub const ADVANCED_BLENDING_PRESENTATION_STATE_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.11.8";
+
+    /// Volume Rendering Volumetric Presentation State Storage
+    pub const VOLUME_RENDERING_VOLUMETRIC_PRESENTATION_STATE_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.11.9";
+
+    /// Segmented Volume Rendering Volumetric Presentation State Storage
+    pub const SEGMENTED_VOLUME_RENDERING_VOLUMETRIC_PRESENTATION_STATE_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.11.10";
+
+    /// Multiple Volume Rendering Volumetric Presentation State Storage
+    pub const MULTIPLE_VOLUME_RENDERING_VOLUMETRIC_PRESENTATION_STATE_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.11.11";
+
+    /// Video Endoscopic Image Storage
+    pub const VIDEO_ENDOSCOPIC_IMAGE_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.77.1.1";
+
+    /// Video Microscopic Image Storage
+    pub const VIDEO_MICROSCOPIC_IMAGE_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.77.1.2";
+
+    /// Video Photographic Image Storage
+    pub const VIDEO_PHOTOGRAPHIC_IMAGE_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.77.1.3";
+
+    /// Ophthalmic Photography 8 Bit Image Storage
+    pub const OPHTHALMIC_PHOTOGRAPHY_8_BIT_IMAGE_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.77.1.4";
+
+    /// Ophthalmic Photography 16 Bit Image Storage
+    pub const OPHTHALMIC_PHOTOGRAPHY_16_BIT_IMAGE_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.77.1.4.1";
+
+    /// Stereometric Relationship Storage
+    pub const STEREOMETRIC_RELATIONSHIP_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.77.1.5.1";
+
+    /// Ophthalmic Tomography Image Storage
+    pub const OPHTHALMIC_TOMOGRAPHY_IMAGE_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.77.1.5.2";
+
+    /// Wide Field Ophthalmic Photography Stereographic Projection Image Storage
+    pub const WIDE_FIELD_OPHTHALMIC_PHOTOGRAPHY_STEREOGRAPHIC_PROJECTION_IMAGE_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.77.1.5.3";
+
+    /// Wide Field Ophthalmic Photography 3D Coordinates Image Storage
+    pub const WIDE_FIELD_OPHTHALMIC_PHOTOGRAPHY_3D_COORDINATES_IMAGE_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.77.1.5.4";
+
+    /// Ophthalmic Optical Coherence Tomography En Face Image Storage
+    pub const OPHTHALMIC_OPTICAL_COHERENCE_TOMOGRAPHY_EN_FACE_IMAGE_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.77.1.5.5";
+
+    /// Ophthalmic Optical Coherence Tomography B-scan Volume Analysis Storage
+    pub const OPHTHALMIC_OPTICAL_COHERENCE_TOMOGRAPHY_B_SCAN_VOLUME_ANALYSIS_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.77.1.5.6";
+
+    /// VL Whole Slide Microscopy Image Storage
+    pub const VL_WHOLE_SLIDE_MICROSCOPY_IMAGE_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.77.1.5.7";
+
+    /// Dermoscopic Photography Image Storage
+    pub const DERMOSCOPIC_PHOTOGRAPHY_IMAGE_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.77.1.5.8";
+
+    /// Ophthalmic Visual Field Static Perimetry Measurements Storage
+    pub const OPHTHALMIC_VISUAL_FIELD_STATIC_PERIMETRY_MEASUREMENTS_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.77.1.6";
+
+    /// Ophthalmic Thickness Map Storage
+    pub const OPHTHALMIC_THICKNESS_MAP_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.77.1.7";
+
+    /// Corneal Topography Map Storage
+    pub const CORNEAL_TOPOGRAPHY_MAP_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.77.1.8";
+
+    /// Ultrasound Multi-frame Image Storage (Retired)
+    pub const ULTRASOUND_MULTI_FRAME_IMAGE_STORAGE_RETIRED: &str = "1.2.840.10008.5.1.4.1.1.3";
+
+    /// Standalone Overlay Storage (Retired)
+    pub const STANDALONE_OVERLAY_STORAGE_RETIRED: &str = "1.2.840.10008.5.1.4.1.1.8";
+
+    /// Standalone Curve Storage (Retired)
+    pub const STANDALONE_CURVE_STORAGE_RETIRED: &str = "1.2.840.10008.5.1.4.1.1.10";
+
+    /// Standalone PET Curve Storage (Retired)
+    pub const STANDALONE_PET_CURVE_STORAGE_RETIRED: &str = "1.2.840.10008.5.1.4.1.1.129";
+
+    /// Media Storage Directory Storage
+    pub const MEDIA_STORAGE_DIRECTORY_STORAGE: &str = "1.2.840.10008.1.3.10";
+
+    /// Hanging Protocol Storage
+    pub const HANGING_PROTOCOL_STORAGE: &str = "1.2.840.10008.5.1.4.38.1";
+
+    /// Color Palette Storage
+    pub const COLOR_PALETTE_STORAGE: &str = "1.2.840.10008.5.1.4.39.1";
+
+    /// Generic Implant Template Storage
+    pub const GENERIC_IMPLANT_TEMPLATE_STORAGE: &str = "1.2.840.10008.5.1.4.43.1";
+
+    /// Implant Assembly Template Storage
+    pub const IMPLANT_ASSEMBLY_TEMPLATE_STORAGE: &str = "1.2.840.10008.5.1.4.44.1";
+
+    /// Implant Template Group Storage
+    pub const IMPLANT_TEMPLATE_GROUP_STORAGE: &str = "1.2.840.10008.5.1.4.45.1";
+}
+
+pub mod ts {
+    /// Implicit VR Little Endian
+    pub const IMPLICIT_VR_LITTLE_ENDIAN: &str = "1.2.840.10008.1.2";
+
+    /// Explicit VR Little Endian
+    pub const EXPLICIT_VR_LITTLE_ENDIAN: &str = "1.2.840.10008.1.2.1";
+
+    /// Explicit VR Big Endian (Retired)
+    pub const EXPLICIT_VR_BIG_ENDIAN: &str = "1.2.840.10008.1.2.2";
+
+    /// JPEG Baseline (Process 1)
+    pub const JPEG_BASELINE: &str = "1.2.840.10008.1.2.4.50";
+
+    /// JPEG Extended (Process 2 & 4)
+    pub const JPEG_EXTENDED: &str = "1.2.840.10008.1.2.4.51";
+
+    /// JPEG Extended (Process 3 & 5) (Retired)
+    pub const JPEG_EXTENDED_PROCESS_3_5: &str = "1.2.840.10008.1.2.4.52";
+
+    /// JPEG Spectral Selection, Non-Hierarchical (Process 6 & 8) (Retired)
+    pub const JPEG_SPECTRAL_SELECTION_NON_HIERARCHICAL_PROCESS_6_8: &str = "1.2.840.10008.1.2.4.53";
+
+    /// JPEG Spectral Selection, Non-Hierarchical (Process 7 & 9) (Retired)
+    pub const JPEG_SPECTRAL_SELECTION_NON_HIERARCHICAL_PROCESS_7_9: &str = "1.2.840.10008.1.2.4.54";
+
+    /// JPEG Full Progression, Non-Hierarchical (Process 10 & 12) (Retired)
+    pub const JPEG_FULL_PROGRESSION_NON_HIERARCHICAL_PROCESS_10_12: &str = "1.2.840.10008.1.2.4.55";
+
+    /// JPEG Full Progression, Non-Hierarchical (Process 11 & 13) (Retired)
+    pub const JPEG_FULL_PROGRESSION_NON_HIERARCHICAL_PROCESS_11_13: &str = "1.2.840.10008.1.2.4.56";
+
+    /// JPEG Lossless, Non-Hierarchical (Process 14)
+    pub const JPEG_LOSSLESS: &str = "1.2.840.10008.1.2.4.57";
+
+    /// JPEG Lossless, Non-Hierarchical (Process 15) (Retired)
+    pub const JPEG_LOSSLESS_NON_HIERARCHICAL_PROCESS_15: &str = "1.2.840.10008.1.2.4.58";
+
+    /// JPEG Extended, Hierarchical (Process 16 & 18) (Retired)
+    pub const JPEG_EXTENDED_HIERARCHICAL_PROCESS_16_18: &str = "1.2.840.10008.1.2.4.59";
+
+    /// JPEG Extended, Hierarchical (Process 17 & 19) (Retired)
+    pub const JPEG_EXTENDED_HIERARCHICAL_PROCESS_17_19: &str = "1.2.840.10008.1.2.4.60";
+
+    /// JPEG Spectral Selection, Hierarchical (Process 20 & 22) (Retired)
+    pub const JPEG_SPECTRAL_SELECTION_HIERARCHICAL_PROCESS_20_22: &str = "1.2.840.10008.1.2.4.61";
+
+    /// JPEG Spectral Selection, Hierarchical (Process 21 & 23) (Retired)
+    pub const JPEG_SPECTRAL_SELECTION_HIERARCHICAL_PROCESS_21_23: &str = "1.2.840.10008.1.2.4.62";
+
+    /// JPEG Full Progression, Hierarchical (Process 24 & 26) (Retired)
+    pub const JPEG_FULL_PROGRESSION_HIERARCHICAL_PROCESS_24_26: &str = "1.2.840.10008.1.2.4.63";
+
+    /// JPEG Full Progression, Hierarchical (Process 25 & 27) (Retired)
+    pub const JPEG_FULL_PROGRESSION_HIERARCHICAL_PROCESS_25_27: &str = "1.2.840.10008.1.2.4.64";
+
+    /// JPEG Lossless, Hierarchical (Process 28) (Retired)
+    pub const JPEG_LOSSLESS_HIERARCHICAL_PROCESS_28: &str = "1.2.840.10008.1.2.4.65";
+
+    /// JPEG Lossless, Hierarchical (Process 29) (Retired)
+    pub const JPEG_LOSSLESS_HIERARCHICAL_PROCESS_29: &str = "1.2.840.10008.1.2.4.66";
+
+    /// JPEG Lossless, Non-Hierarchical, First-Order Prediction (Process 14 [Selection Value 1])
+    pub const JPEG_LOSSLESS_SV1: &str = "1.2.840.10008.1.2.4.70";
+
+    /// JPEG-LS Lossless Image Compression
+    pub const JPEG_LS_LOSSLESS: &str = "1.2.840.10008.1.2.4.80";
+
+    /// JPEG-LS Lossy (Near-Lossless) Image Compression
+    pub const JPEG_LS_NEAR_LOSSLESS: &str = "1.2.840.10008.1.2.4.81";
+
+    /// JPEG 2000 Image Compression (Lossless Only)
+    pub const JPEG_2000_LOSSLESS: &str = "1.2.840.10008.1.2.4.90";
+
+    /// JPEG 2000 Image Compression
+    pub const JPEG_2000: &str = "1.2.840.10008.1.2.4.91";
+
+    /// JPEG 2000 Part 2 Multi-component Image Compression (Lossless Only)
+    pub const JPEG_2000_MULTI_COMPONENT_LOSSLESS: &str = "1.2.840.10008.1.2.4.92";
+
+    /// JPEG 2000 Part 2 Multi-component Image Compression
+    pub const JPEG_2000_MULTI_COMPONENT: &str = "1.2.840.10008.1.2.4.93";
+
+    /// JPIP Referenced
+    pub const JPIP_REFERENCED: &str = "1.2.840.10008.1.2.4.94";
+
+    /// JPIP Referenced Deflate
+    pub const JPIP_REFERENCED_DEFLATE: &str = "1.2.840.10008.1.2.4.95";
+
+    /// RLE Lossless
+    pub const RLE_LOSSLESS: &str = "1.2.840.10008.1.2.5";
+
+    /// RFC 2557 MIME encapsulation (Retired)
+    pub const RFC_2557_MIME_ENCAPSULATION: &str = "1.2.840.10008.1.2.6.1";
+
+    /// XML Encoding (Retired)
+    pub const XML_ENCODING: &str = "1.2.840.10008.1.2.6.2";
+
+    /// SMPTE ST 2110-20 Uncompressed Progressive Active Video
+    pub const SMPTE_ST_2110_20_UNCOMPRESSED_PROGRESSIVE_ACTIVE_VIDEO: &str = "1.2.840.10008.1.2.7.1";
+
+    /// SMPTE ST 2110-20 Uncompressed Interlaced Active Video
+    pub const SMPTE_ST_2110_20_UNCOMPRESSED_INTERLACED_ACTIVE_VIDEO: &str = "1.2.840.10008.1.2.7.2";
+
+    /// SMPTE ST 2110-30 PCM Digital Audio
+    pub const SMPTE_ST_2110_30_PCM_DIGITAL_AUDIO: &str = "1.2.840.10008.1.2.7.3";
+
+    /// MPEG2 Main Profile / Main Level
+    pub const MPEG2_MAIN_PROFILE_MAIN_LEVEL: &str = "1.2.840.10008.1.2.4.100";
+
+    /// MPEG2 Main Profile / High Level
+    pub const MPEG2_MAIN_PROFILE_HIGH_LEVEL: &str = "1.2.840.10008.1.2.4.101";
+
+    /// MPEG-4 AVC/H.264 High Profile / Level 4.1
+    pub const MPEG_4_AVC_H_264_HIGH_PROFILE_LEVEL_4_1: &str = "1.2.840.10008.1.2.4.102";
+
+    /// MPEG-4 AVC/H.264 BD-compatible High Profile / Level 4.1
+    pub const MPEG_4_AVC_H_264_BD_COMPATIBLE_HIGH_PROFILE_LEVEL_4_1: &str = "1.2.840.10008.1.2.4.103";
+
+    /// MPEG-4 AVC/H.264 High Profile / Level 4.2 For 2D Video
+    pub const MPEG_4_AVC_H_264_HIGH_PROFILE_LEVEL_4_2_FOR_2D_VIDEO: &str = "1.2.840.10008.1.2.4.104";
+
+    /// MPEG-4 AVC/H.264 High Profile / Level 4.2 For 3D Video
+    pub const MPEG_4_AVC_H_264_HIGH_PROFILE_LEVEL_4_2_FOR_3D_VIDEO: &str = "1.2.840.10008.1.2.4.105";
+
+    /// MPEG-4 AVC/H.264 Stereo High Profile / Level 4.2
+    pub const MPEG_4_AVC_H_264_STEREO_HIGH_PROFILE_LEVEL_4_2: &str = "1.2.840.10008.1.2.4.106";
+
+    /// HEVC/H.265 Main Profile / Level 5.1
+    pub const HEVC_H_265_MAIN_PROFILE_LEVEL_5_1: &str = "1.2.840.10008.1.2.4.107";
+
+    /// HEVC/H.265 Main 10 Profile / Level 5.1
+    pub const HEVC_H_265_MAIN_10_PROFILE_LEVEL_5_1: &str = "1.2.840.10008.1.2.4.108";
+
+    /// High-Throughput JPEG 2000 Image Compression (Lossless Only)
+    pub const HTJ2K_LOSSLESS: &str = "1.2.840.10008.1.2.4.201";
+
+    /// High-Throughput JPEG 2000 with RPCL Options Image Compression (Lossless Only)
+    pub const HTJ2K_LOSSLESS_RPCL: &str = "1.2.840.10008.1.2.4.202";
+
+    /// High-Throughput JPEG 2000 Image Compression
+    pub const HTJ2K: &str = "1.2.840.10008.1.2.4.203";
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::sop_classes::SopClassRegistry;
+    use super::super::transfer_syntaxes::TransferSyntaxRegistry;
+
+    #[test]
+    fn test_constants_name_registry_entries() {
+        assert_eq!(SopClassRegistry::global().get_name(sop::CT_IMAGE_STORAGE), Some("CT Image Storage"));
+        assert_eq!(TransferSyntaxRegistry::global().get(ts::EXPLICIT_VR_LITTLE_ENDIAN).map(|ts| ts.name),
+                   Some("Explicit VR Little Endian"));
+        assert!(!SopClassRegistry::global().is_supported(sop::VERIFICATION));
+    }
+}
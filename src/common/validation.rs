@@ -0,0 +1,166 @@
+/// Modality-specific ingest validation profiles
+///
+/// Profiles are loaded from a TOML file and list the attributes that objects of
+/// a given modality must carry, together with the action to take when one of
+/// them is missing or empty:
+///
+/// ```toml
+/// [[profile]]
+/// modality = "CT"
+/// required = ["SliceThickness", "KVP"]
+/// action = "quarantine"
+///
+/// [[profile]]
+/// modality = "MG"
+/// required = ["ViewPosition"]
+/// action = "reject"
+/// ```
+
+use anyhow::{Context, Result};
+use dicom_core::header::HasLength;
+use dicom_core::Tag;
+use dicom_object::InMemDicomObject;
+use serde::Deserialize;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ValidationAction {
+    /// Log the problem and store the object as usual
+    Warn,
+    /// Store the object in the quarantine directory instead of the archive
+    Quarantine,
+    /// Refuse the object with a failure status
+    Reject,
+}
+
+fn default_action() -> ValidationAction {
+    ValidationAction::Warn
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ModalityProfile {
+    pub modality: String,
+    /// Attribute keywords (e.g. `SliceThickness`) that must be present and non-empty
+    #[serde(default)]
+    pub required: Vec<String>,
+    #[serde(default = "default_action")]
+    pub action: ValidationAction,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationOutcome {
+    pub modality: String,
+    pub missing: Vec<String>,
+    pub action: ValidationAction,
+}
+
+/// Set of validation profiles keyed by modality
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ValidationProfiles {
+    #[serde(default, rename = "profile")]
+    profiles: Vec<ModalityProfile>,
+}
+
+impl ValidationProfiles {
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .context(format!("Failed to read validation profiles: {}", path.display()))?;
+        Self::from_toml(&text)
+    }
+
+    pub fn from_toml(text: &str) -> Result<Self> {
+        toml::from_str(text).context("Invalid validation profile TOML")
+    }
+
+    pub fn get(&self, modality: &str) -> Option<&ModalityProfile> {
+        self.profiles
+            .iter()
+            .find(|p| p.modality.eq_ignore_ascii_case(modality))
+    }
+
+    pub fn len(&self) -> usize {
+        self.profiles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.profiles.is_empty()
+    }
+
+    /// Check a dataset against the profile for its modality.
+    ///
+    /// Returns `None` when the object passes or no profile applies to it.
+    pub fn validate(&self, obj: &InMemDicomObject) -> Option<ValidationOutcome> {
+        let modality = obj.element(Tag(0x0008, 0x0060)) // Modality
+            .ok()
+            .and_then(|e| e.string().ok())
+            .map(|s| s.trim().to_string())?;
+
+        let profile = self.get(&modality)?;
+
+        let missing: Vec<String> = profile.required.iter()
+            .filter(|keyword| {
+                obj.element_by_name(keyword)
+                    .map(|e| e.is_empty())
+                    .unwrap_or(true)
+            })
+            .cloned()
+            .collect();
+
+        if missing.is_empty() {
+            None
+        } else {
+            Some(ValidationOutcome {
+                modality,
+                missing,
+                action: profile.action,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dicom_core::{DataElement, PrimitiveValue, VR};
+
+    const PROFILES: &str = r#"
+        [[profile]]
+        modality = "CT"
+        required = ["SliceThickness", "KVP"]
+        action = "quarantine"
+
+        [[profile]]
+        modality = "MG"
+        required = ["ViewPosition"]
+    "#;
+
+    #[test]
+    fn test_profile_parsing() {
+        let profiles = ValidationProfiles::from_toml(PROFILES).unwrap();
+        assert_eq!(profiles.len(), 2);
+        assert_eq!(profiles.get("ct").unwrap().action, ValidationAction::Quarantine);
+        assert_eq!(profiles.get("MG").unwrap().action, ValidationAction::Warn);
+        assert!(profiles.get("MR").is_none());
+    }
+
+    #[test]
+    fn test_missing_attributes_reported() {
+        let profiles = ValidationProfiles::from_toml(PROFILES).unwrap();
+
+        let obj = InMemDicomObject::from_element_iter([
+            DataElement::new(Tag(0x0008, 0x0060), VR::CS, PrimitiveValue::from("CT")),
+            DataElement::new(Tag(0x0018, 0x0050), VR::DS, PrimitiveValue::from("2.5")), // SliceThickness
+        ]);
+
+        let outcome = profiles.validate(&obj).unwrap();
+        assert_eq!(outcome.missing, vec!["KVP".to_string()]);
+        assert_eq!(outcome.action, ValidationAction::Quarantine);
+
+        // Modalities without a profile always pass
+        let mr = InMemDicomObject::from_element_iter([
+            DataElement::new(Tag(0x0008, 0x0060), VR::CS, PrimitiveValue::from("MR")),
+        ]);
+        assert!(profiles.validate(&mr).is_none());
+    }
+}
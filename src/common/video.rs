@@ -0,0 +1,208 @@
+//! Multi-frame clips as MP4 video
+//!
+//! Ultrasound and angiography clips are multi-frame images that only
+//! DICOM-capable software can play. Exported as H.264 in MP4 they play
+//! anywhere, e.g. for teaching or for a patient. Each frame is mapped to
+//! display values like a preview, converted to RGB and encoded; how long each
+//! frame is shown comes from Frame Time Vector, Frame Time, Cine Rate or
+//! Recommended Display Frame Rate, in that order, so the clip plays at the
+//! speed it was acquired. Encoding needs the `video` feature.
+
+use anyhow::Result;
+use dicom_core::Tag;
+use dicom_object::{FileDicomObject, InMemDicomObject};
+
+use super::mp4::TIMESCALE;
+use super::preview::{display_values, downsample, reduction_factor, NativePixels};
+
+const FRAME_TIME: Tag = Tag(0x0018, 0x1063);
+const FRAME_TIME_VECTOR: Tag = Tag(0x0018, 0x1065);
+const CINE_RATE: Tag = Tag(0x0018, 0x0040);
+const RECOMMENDED_DISPLAY_FRAME_RATE: Tag = Tag(0x0008, 0x2144);
+
+/// Frames per second when the object does not say
+pub const DEFAULT_FRAME_RATE: f64 = 25.0;
+
+/// Size and bit rate of exported videos
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VideoOptions {
+    /// Largest number of rows or columns; larger clips are shrunk by an integer factor
+    pub max_dimension: u16,
+    /// Target bit rate in bits per second
+    pub bitrate: u32,
+}
+
+impl Default for VideoOptions {
+    fn default() -> Self {
+        Self { max_dimension: 1920, bitrate: 2_000_000 }
+    }
+}
+
+/// The timing attributes of a multi-frame object
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FrameTiming {
+    /// Frame Time Vector: milliseconds since the previous frame, 0 for the first
+    pub frame_time_vector: Option<Vec<f64>>,
+    /// Frame Time: milliseconds between frames
+    pub frame_time: Option<f64>,
+    /// Cine Rate, or else Recommended Display Frame Rate, in frames per second
+    pub frame_rate: Option<f64>,
+}
+
+impl FrameTiming {
+    pub fn of(obj: &InMemDicomObject) -> Self {
+        let floats = |tag: Tag| obj.element(tag).ok().and_then(|e| e.to_multi_float64().ok());
+        let float = |tag: Tag| floats(tag).and_then(|values| values.first().copied()).filter(|value| *value > 0.0);
+        Self {
+            frame_time_vector: floats(FRAME_TIME_VECTOR),
+            frame_time: float(FRAME_TIME),
+            frame_rate: float(CINE_RATE).or_else(|| float(RECOMMENDED_DISPLAY_FRAME_RATE)),
+        }
+    }
+
+    /// How long each of `frames` frames is shown, in `mp4::TIMESCALE` ticks
+    pub fn durations(&self, frames: usize) -> Vec<u32> {
+        let ticks = |milliseconds: f64| (milliseconds * TIMESCALE as f64 / 1000.0).round().max(1.0) as u32;
+        let constant = self.frame_time
+            .or_else(|| self.frame_rate.map(|rate| 1000.0 / rate))
+            .unwrap_or(1000.0 / DEFAULT_FRAME_RATE);
+
+        match &self.frame_time_vector {
+            // Each frame lasts until the next one; the last as long as the one before it
+            Some(vector) if frames > 1 && vector.len() == frames && vector[1..].iter().all(|ms| *ms > 0.0) => {
+                vector[1..].iter().chain(vector.last()).map(|ms| ticks(*ms)).collect()
+            }
+            _ => vec![ticks(constant); frames],
+        }
+    }
+}
+
+/// Interleaved RGB of one frame's display values
+fn to_rgb(display: Vec<u8>, photometric: &str) -> Vec<u8> {
+    match photometric {
+        "RGB" => display,
+        "YBR_FULL" => display.chunks_exact(3)
+            .flat_map(|ybr| {
+                let (y, cb, cr) = (ybr[0] as f64, ybr[1] as f64 - 128.0, ybr[2] as f64 - 128.0);
+                [y + 1.402 * cr, y - 0.344136 * cb - 0.714136 * cr, y + 1.772 * cb]
+                    .map(|value| value.round().clamp(0.0, 255.0) as u8)
+            })
+            .collect(),
+        "MONOCHROME1" => display.iter().flat_map(|value| [255 - value; 3]).collect(),
+        _ => display.iter().flat_map(|value| [*value; 3]).collect(),
+    }
+}
+
+/// `rgb` grown to even rows and columns by repeating the last of each, as
+/// 4:2:0 video requires
+fn pad_even(rgb: Vec<u8>, rows: u16, columns: u16) -> (Vec<u8>, u16, u16) {
+    let (out_rows, out_columns) = (rows + rows % 2, columns + columns % 2);
+    if (out_rows, out_columns) == (rows, columns) {
+        return (rgb, rows, columns);
+    }
+    let row_length = columns as usize * 3;
+    let mut padded = Vec::with_capacity(out_rows as usize * out_columns as usize * 3);
+    for row in 0..out_rows as usize {
+        let source = &rgb[row.min(rows as usize - 1) * row_length..][..row_length];
+        padded.extend_from_slice(source);
+        if out_columns > columns {
+            padded.extend_from_slice(&source[row_length - 3..]);
+        }
+    }
+    (padded, out_rows, out_columns)
+}
+
+/// The frames of `obj` as even-sized RGB images, with their width and height
+pub fn rgb_frames(obj: &FileDicomObject<InMemDicomObject>, options: &VideoOptions) -> Result<(Vec<Vec<u8>>, u16, u16)> {
+    let native = NativePixels::read(obj)?.ok_or_else(|| anyhow::anyhow!("no pixel data"))?;
+    let layout = native.layout;
+    let supported = match layout.samples_per_pixel {
+        1 => native.photometric.starts_with("MONOCHROME"),
+        3 => matches!(native.photometric.as_str(), "RGB" | "YBR_FULL"),
+        _ => false,
+    };
+    if !supported {
+        anyhow::bail!("cannot export {} pixel data with {} samples", native.photometric, layout.samples_per_pixel);
+    }
+
+    let factor = reduction_factor(layout.rows, layout.columns, options.max_dimension);
+    let (mut width, mut height) = (layout.columns, layout.rows);
+    let frames = native.frames()
+        .map(|frame| {
+            let display = display_values(frame, &layout, &native.mapping);
+            let (display, rows, columns) = downsample(&display, layout.rows, layout.columns, layout.samples_per_pixel, factor);
+            let (rgb, rows, columns) = pad_even(to_rgb(display, &native.photometric), rows, columns);
+            (width, height) = (columns, rows);
+            rgb
+        })
+        .collect();
+    Ok((frames, width, height))
+}
+
+/// `obj`'s frames as an H.264 MP4 file, played at the object's frame timing
+pub fn export_mp4(obj: &FileDicomObject<InMemDicomObject>, options: &VideoOptions) -> Result<Vec<u8>> {
+    let (frames, width, height) = rgb_frames(obj, options)?;
+    let durations = FrameTiming::of(obj).durations(frames.len());
+    encode_h264(&frames, width, height, &durations, options)
+}
+
+#[cfg(feature = "video")]
+fn encode_h264(frames: &[Vec<u8>], width: u16, height: u16, durations: &[u32], options: &VideoOptions) -> Result<Vec<u8>> {
+    use openh264::encoder::{BitRate, Encoder, EncoderConfig, FrameRate};
+    use openh264::formats::{RgbSliceU8, YUVBuffer};
+    use openh264::OpenH264API;
+
+    use super::mp4::Mp4Writer;
+
+    let total: u64 = durations.iter().map(|&ticks| ticks as u64).sum();
+    let frame_rate = frames.len() as f64 * TIMESCALE as f64 / total.max(1) as f64;
+    let config = EncoderConfig::new()
+        .bitrate(BitRate::from_bps(options.bitrate))
+        .max_frame_rate(FrameRate::from_hz(frame_rate as f32));
+    let mut encoder = Encoder::with_api_config(OpenH264API::from_source(), config)?;
+
+    let mut writer = Mp4Writer::new();
+    for (rgb, &duration) in frames.iter().zip(durations) {
+        let yuv = YUVBuffer::from_rgb_source(RgbSliceU8::new(rgb, (width as usize, height as usize)));
+        let stream = encoder.encode(&yuv)?;
+        writer.add_frame(&stream.to_vec(), duration);
+    }
+    writer.finish(width, height)
+}
+
+#[cfg(not(feature = "video"))]
+fn encode_h264(_frames: &[Vec<u8>], _width: u16, _height: u16, _durations: &[u32], _options: &VideoOptions) -> Result<Vec<u8>> {
+    anyhow::bail!("H.264 encoding is not available: rebuild with --features video")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_durations() {
+        // 40 ms is 3600 ticks
+        let vector = FrameTiming { frame_time_vector: Some(vec![0.0, 40.0, 80.0]), frame_time: Some(10.0), ..Default::default() };
+        assert_eq!(vector.durations(3), vec![3600, 7200, 7200]);
+        // A vector that does not match the frames falls back to Frame Time
+        assert_eq!(vector.durations(2), vec![900, 900]);
+
+        let rate = FrameTiming { frame_rate: Some(30.0), ..Default::default() };
+        assert_eq!(rate.durations(2), vec![3000, 3000]);
+        assert_eq!(FrameTiming::default().durations(1), vec![3600]);
+    }
+
+    #[test]
+    fn test_rgb_conversion_and_padding() {
+        assert_eq!(to_rgb(vec![0, 200], "MONOCHROME1"), vec![255, 255, 255, 55, 55, 55]);
+        assert_eq!(to_rgb(vec![7], "MONOCHROME2"), vec![7, 7, 7]);
+        assert_eq!(to_rgb(vec![76, 85, 255], "YBR_FULL"), vec![254, 0, 0]);
+
+        // 1 row of 3 pixels becomes 2 rows of 4
+        let rgb: Vec<u8> = (1..=9).collect();
+        let (padded, rows, columns) = pad_even(rgb, 1, 3);
+        assert_eq!((rows, columns), (2, 4));
+        assert_eq!(&padded[..12], [1, 2, 3, 4, 5, 6, 7, 8, 9, 7, 8, 9]);
+        assert_eq!(&padded[12..], &padded[..12]);
+    }
+}
@@ -0,0 +1,358 @@
+/// C ABI for embedding the transfer engine in C/C++ applications
+///
+/// Exposes batch sending, a receiver that can be started and stopped, and
+/// callbacks reporting per-file and per-object outcomes. The matching header
+/// is `include/rust_dicom.h`, generated with cbindgen (see `cbindgen.toml`).
+///
+/// Strings are NUL-terminated UTF-8. Strings handed to callbacks are only
+/// valid for the duration of the call. Functions report failure with a
+/// negative return value or a null pointer, and `rd_last_error` then
+/// describes the failure on the calling thread. Callbacks may run on threads
+/// of the engine, so `user_data` must be safe to use from any thread.
+
+use std::cell::RefCell;
+use std::ffi::{c_char, c_int, c_void, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use crate::receiver::receiver::common::health::Readiness;
+use crate::receiver::receiver::{DicomReceiver, ObjectCallback};
+use crate::sender::dicom_client::{DicomClient, DicomClientConfig};
+use crate::sender::indexing::process_dicom_file;
+
+pub const RD_ERROR: c_int = -1;
+
+/// How long `rd_receiver_stop` lets open associations finish
+const STOP_GRACE: Duration = Duration::from_secs(5);
+
+/// Destination and timeouts for `rd_send_batch`
+#[repr(C)]
+pub struct RdSendOptions {
+    pub calling_ae: *const c_char,
+    pub called_ae: *const c_char,
+    pub host: *const c_char,
+    pub port: u16,
+    /// Per-address connection timeout in seconds (0 = 5)
+    pub connect_timeout_seconds: u32,
+}
+
+/// Outcome of one file of a batch
+#[repr(C)]
+pub struct RdFileResult {
+    pub path: *const c_char,
+    /// Empty when the file could not be read
+    pub sop_instance_uid: *const c_char,
+    pub success: bool,
+    /// Null on success
+    pub error_message: *const c_char,
+    pub transfer_time_ms: u64,
+    pub file_size: u64,
+}
+
+/// Outcome of one object received by `rd_receiver_start`
+#[repr(C)]
+pub struct RdReceivedObject {
+    pub calling_ae: *const c_char,
+    pub sop_class_uid: *const c_char,
+    pub sop_instance_uid: *const c_char,
+    pub study_instance_uid: *const c_char,
+    /// "stored", "quarantined", "forwarded", "duplicate", "rejected" or "failed"
+    pub status: *const c_char,
+    pub size: u64,
+}
+
+pub type RdFileResultCallback = Option<unsafe extern "C" fn(result: *const RdFileResult, user_data: *mut c_void)>;
+pub type RdReceivedObjectCallback = Option<unsafe extern "C" fn(object: *const RdReceivedObject, user_data: *mut c_void)>;
+
+/// Running receiver, owned by the caller until `rd_receiver_stop`
+pub struct RdReceiver {
+    runtime: tokio::runtime::Runtime,
+    task: tokio::task::JoinHandle<anyhow::Result<()>>,
+    /// Resolves the shutdown future the receiver was started with
+    shutdown: tokio::sync::oneshot::Sender<()>,
+    /// Whether `callback` may still be called; association threads forced
+    /// closed can outlive the drain, so stopping closes this first
+    callbacks_open: Arc<RwLock<bool>>,
+}
+
+/// Caller-provided context pointer; the caller guarantees it may cross threads
+#[derive(Clone, Copy)]
+struct UserData(*mut c_void);
+
+unsafe impl Send for UserData {}
+unsafe impl Sync for UserData {}
+
+impl UserData {
+    fn get(self) -> *mut c_void {
+        self.0
+    }
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: impl std::fmt::Display) {
+    let message = CString::new(message.to_string().replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+}
+
+/// Run `body`, turning errors and panics into `fallback` plus a last error
+fn guard<T>(fallback: T, body: impl FnOnce() -> anyhow::Result<T>) -> T {
+    match catch_unwind(AssertUnwindSafe(body)) {
+        Ok(Ok(value)) => value,
+        Ok(Err(e)) => {
+            set_last_error(e);
+            fallback
+        }
+        Err(_) => {
+            set_last_error("internal error (panic)");
+            fallback
+        }
+    }
+}
+
+unsafe fn string_arg(value: *const c_char, name: &str) -> anyhow::Result<String> {
+    if value.is_null() {
+        anyhow::bail!("{} is null", name);
+    }
+    Ok(CStr::from_ptr(value).to_str()
+        .map_err(|_| anyhow::anyhow!("{} is not valid UTF-8", name))?
+        .to_string())
+}
+
+fn c_string(value: &str) -> CString {
+    CString::new(value.replace('\0', "")).unwrap_or_default()
+}
+
+/// Version of the library, e.g. "0.1.0"
+#[no_mangle]
+pub extern "C" fn rd_version() -> *const c_char {
+    concat!(env!("CARGO_PKG_VERSION"), "\0").as_ptr() as *const c_char
+}
+
+/// Description of the last failure on this thread, or null. Valid until the
+/// next call into the library on the same thread.
+#[no_mangle]
+pub extern "C" fn rd_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| last.borrow().as_ref().map_or(std::ptr::null(), |message| message.as_ptr()))
+}
+
+/// Send `count` files over one association and report each file to `callback`.
+/// Returns the number of files that failed, or -1 if the batch could not be
+/// sent at all (e.g. the association was rejected).
+///
+/// # Safety
+///
+/// `options` must point to a valid `RdSendOptions` whose strings are valid,
+/// and `paths` to `count` valid strings. `callback` may be null.
+#[no_mangle]
+pub unsafe extern "C" fn rd_send_batch(
+    options: *const RdSendOptions,
+    paths: *const *const c_char,
+    count: usize,
+    callback: RdFileResultCallback,
+    user_data: *mut c_void,
+) -> c_int {
+    guard(RD_ERROR, || {
+        let options = options.as_ref().ok_or_else(|| anyhow::anyhow!("options is null"))?;
+        if paths.is_null() && count > 0 {
+            anyhow::bail!("paths is null");
+        }
+        let paths: Vec<PathBuf> = (0..count)
+            .map(|i| string_arg(*paths.add(i), "path").map(PathBuf::from))
+            .collect::<anyhow::Result<_>>()?;
+
+        let config = DicomClientConfig {
+            calling_ae: string_arg(options.calling_ae, "calling_ae")?,
+            called_ae: string_arg(options.called_ae, "called_ae")?,
+            host: string_arg(options.host, "host")?,
+            port: options.port,
+            timeout: Duration::from_secs(30),
+            connect_timeout: Duration::from_secs(match options.connect_timeout_seconds {
+                0 => 5,
+                seconds => seconds as u64,
+            }),
+            lenient_repair: false,
+            compute_checksums: false,
+            pack_pdvs: true,
+            tls: None,
+            preview: None,
+        };
+
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build()?;
+        let report = |path: &Path, sop_instance_uid: &str, error: Option<&str>, time_ms: u64, size: u64| {
+            let Some(callback) = callback else { return };
+            let path = c_string(&path.display().to_string());
+            let sop_instance_uid = c_string(sop_instance_uid);
+            let error = error.map(c_string);
+            let result = RdFileResult {
+                path: path.as_ptr(),
+                sop_instance_uid: sop_instance_uid.as_ptr(),
+                success: error.is_none(),
+                error_message: error.as_ref().map_or(std::ptr::null(), |e| e.as_ptr()),
+                transfer_time_ms: time_ms,
+                file_size: size,
+            };
+            callback(&result, user_data);
+        };
+
+        let mut files = Vec::new();
+        let mut failed = 0;
+        for path in &paths {
+            match runtime.block_on(process_dicom_file(path))? {
+                Some(file) => files.push(file),
+                None => {
+                    failed += 1;
+                    report(path, "", Some("not a readable DICOM file"), 0, 0);
+                }
+            }
+        }
+
+        let stats = runtime.block_on(DicomClient::new(config).send_files(files))?;
+        for result in &stats.results {
+            failed += usize::from(!result.success);
+            report(Path::new(&result.file_path), &result.sop_instance_uid, result.error_message.as_deref(),
+                   result.transfer_time_ms, result.file_size);
+        }
+        Ok(failed.try_into().unwrap_or(c_int::MAX))
+    })
+}
+
+/// Start a receiver storing objects in `output_dir` and reporting each one to
+/// `callback`. Returns once it is listening, or null if it cannot be started
+/// (e.g. the port is in use).
+///
+/// # Safety
+///
+/// `ae_title` and `output_dir` must be valid strings. `callback` may be null;
+/// otherwise it and `user_data` must stay valid until `rd_receiver_stop`
+/// returns, and `callback` must not call `rd_receiver_stop` itself.
+#[no_mangle]
+pub unsafe extern "C" fn rd_receiver_start(
+    ae_title: *const c_char,
+    output_dir: *const c_char,
+    port: u16,
+    max_connections: u32,
+    callback: RdReceivedObjectCallback,
+    user_data: *mut c_void,
+) -> *mut RdReceiver {
+    guard(std::ptr::null_mut(), || {
+        let ae_title = string_arg(ae_title, "ae_title")?;
+        let output_dir = PathBuf::from(string_arg(output_dir, "output_dir")?);
+
+        let readiness = Arc::new(Readiness::new(1));
+        let callbacks_open = Arc::new(RwLock::new(true));
+        let mut receiver = DicomReceiver::new(ae_title, output_dir, max_connections.max(1) as usize)
+            .with_shutdown_grace(STOP_GRACE)
+            .with_readiness(Arc::clone(&readiness));
+        if let Some(callback) = callback {
+            let user_data = UserData(user_data);
+            let callbacks_open = Arc::clone(&callbacks_open);
+            receiver = receiver.with_object_callback(ObjectCallback::new(move |record| {
+                // Held for the call, so stopping waits for a callback in progress
+                let open = match callbacks_open.read() {
+                    Ok(open) => open,
+                    Err(poisoned) => poisoned.into_inner(),
+                };
+                if !*open {
+                    return;
+                }
+                let calling_ae = c_string(&record.peer_ae);
+                let sop_class_uid = c_string(&record.sop_class_uid);
+                let sop_instance_uid = c_string(&record.sop_instance_uid);
+                let study_instance_uid = c_string(&record.study_instance_uid);
+                let status = c_string(&record.status);
+                let object = RdReceivedObject {
+                    calling_ae: calling_ae.as_ptr(),
+                    sop_class_uid: sop_class_uid.as_ptr(),
+                    sop_instance_uid: sop_instance_uid.as_ptr(),
+                    study_instance_uid: study_instance_uid.as_ptr(),
+                    status: status.as_ptr(),
+                    size: record.size,
+                };
+                unsafe { callback(&object, user_data.get()) };
+            }));
+        }
+
+        let runtime = tokio::runtime::Runtime::new()?;
+        let (shutdown, stopped) = tokio::sync::oneshot::channel();
+        let mut task = runtime.spawn(Arc::new(receiver).start(port, async move {
+            let _ = stopped.await;
+        }));
+        // Binding errors end the task before the listener ever comes up
+        runtime.block_on(async {
+            tokio::select! {
+                () = readiness.ready() => Ok(()),
+                result = &mut task => match result {
+                    Ok(Ok(())) => Err(anyhow::anyhow!("receiver stopped while starting")),
+                    Ok(Err(e)) => Err(e),
+                    Err(e) => Err(e.into()),
+                },
+            }
+        })?;
+        Ok(Box::into_raw(Box::new(RdReceiver { runtime, task, shutdown, callbacks_open })))
+    })
+}
+
+/// Stop a receiver, waiting up to five seconds for open associations, and free
+/// it. No callback runs once this returns.
+///
+/// # Safety
+///
+/// `receiver` must come from `rd_receiver_start` and not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn rd_receiver_stop(receiver: *mut RdReceiver) {
+    if receiver.is_null() {
+        return;
+    }
+    guard((), || {
+        let receiver = Box::from_raw(receiver);
+        let _ = receiver.shutdown.send(());
+        let _ = receiver.runtime.block_on(receiver.task);
+        match receiver.callbacks_open.write() {
+            Ok(mut open) => *open = false,
+            Err(poisoned) => *poisoned.into_inner() = false,
+        }
+        receiver.runtime.shutdown_timeout(STOP_GRACE);
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_errors_are_reported() {
+        assert!(!unsafe { CStr::from_ptr(rd_version()) }.to_str().unwrap().is_empty());
+
+        let failed = unsafe { rd_send_batch(std::ptr::null(), std::ptr::null(), 0, None, std::ptr::null_mut()) };
+        assert_eq!(failed, RD_ERROR);
+        let message = unsafe { CStr::from_ptr(rd_last_error()) };
+        assert_eq!(message.to_str().unwrap(), "options is null");
+
+        let receiver = unsafe { rd_receiver_start(std::ptr::null(), std::ptr::null(), 0, 1, None, std::ptr::null_mut()) };
+        assert!(receiver.is_null());
+        let message = unsafe { CStr::from_ptr(rd_last_error()) };
+        assert_eq!(message.to_str().unwrap(), "ae_title is null");
+
+        let taken = std::net::TcpListener::bind(("0.0.0.0", 0)).unwrap();
+        let port = taken.local_addr().unwrap().port();
+        let output_dir = std::env::temp_dir().join(format!("rd_ffi_test_{}", std::process::id()));
+        let ae_title = c_string("RD_TEST");
+        let output = c_string(&output_dir.display().to_string());
+        let receiver = unsafe { rd_receiver_start(ae_title.as_ptr(), output.as_ptr(), port, 1, None, std::ptr::null_mut()) };
+        assert!(receiver.is_null());
+        let message = unsafe { CStr::from_ptr(rd_last_error()) };
+        assert!(message.to_str().unwrap().contains("Failed to listen"));
+        drop(taken);
+
+        let receiver = unsafe { rd_receiver_start(ae_title.as_ptr(), output.as_ptr(), port, 1, None, std::ptr::null_mut()) };
+        assert!(!receiver.is_null());
+        unsafe { rd_receiver_stop(receiver) };
+        let _ = std::fs::remove_dir_all(&output_dir);
+    }
+}
@@ -1,3 +1,4 @@
 pub mod common;
 pub mod sender;
 pub mod receiver;
+pub mod ffi;
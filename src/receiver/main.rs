@@ -1,15 +1,47 @@
 // Receiver binary main
 mod receiver;
+#[cfg(windows)]
+mod service;
 
 use anyhow::Result;
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use console::{style, Emoji};
-use std::path::PathBuf;
+use std::future::Future;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Duration;
 use tracing::info;
 use uuid::Uuid;
 
-use receiver::DicomReceiver;
+use receiver::{DicomReceiver, DropPoint, FaultInjection, ReadOnly};
+use receiver::common::codecs::codec_report;
+use receiver::common::compliance::{parse_rule_override, CompliancePolicy, ComplianceRule, RuleAction};
+use receiver::common::deterministic::seeded_uuid;
+use receiver::common::discovery::advertise;
+use receiver::common::encryption::StorageKey;
+use receiver::common::health::{bind_health_listener, serve_health, Readiness, ServiceMetrics, SELF_TEST_AE};
+use receiver::common::housekeeping::{find_orphans, normalized, orphan_instance, remove_empty_dirs, OrphanAction};
+use receiver::common::index_stats::{index_stats, stats_text, Period};
+use receiver::common::ledger::{self, Ledger};
+use receiver::common::listen::{parse_bind_address, DEFAULT_BIND_ADDRESS};
+use receiver::common::listeners::{load_listeners, ListenerConfig};
+use receiver::common::naming::FilenameTemplate;
+use receiver::common::patient_admin::{rewrite_stored_object, PatientCorrection};
+use receiver::common::quotas::ByteQuotas;
+use receiver::common::receive_index::{display_location, metadata_csv, open_index, Filter, Level};
+use receiver::common::recovery::{recover, RecoveryPolicy};
+use receiver::common::resume::{DuplicatePolicy, ReceivedInstances, RECEIVED_INSTANCES_DIR};
+use receiver::common::retired::RetiredPolicy;
+use receiver::common::shutdown::shutdown_signal;
+use receiver::common::size_limits::{parse_category_limit, parse_size, SizeLimits};
+use receiver::common::sop_classes::SopClassCategory;
+use receiver::common::study_webhook::StudyWebhook;
+use receiver::common::time_sanity::TimeSanityPolicy;
+use receiver::common::transfer_syntaxes::TransferSyntaxRegistry;
+use receiver::common::tls::{server_config, CipherPolicy, DICOM_TLS_PORT};
+use receiver::common::ts_preference::TransferSyntaxPreference;
+use receiver::common::validation::ValidationProfiles;
+use rust_dicom::sender::dicom_client::{DicomClient, DicomClientConfig};
 
 static SATELLITE: Emoji<'_, '_> = Emoji("📡 ", "");
 static INBOX: Emoji<'_, '_> = Emoji("📥 ", "");
@@ -18,9 +50,13 @@ static INBOX: Emoji<'_, '_> = Emoji("📥 ", "");
 #[command(name = "dicom-receiver")]
 #[command(about = "A high-performance DICOM C-STORE receiver")]
 #[command(version = "1.0")]
+#[command(subcommand_negates_reqs = true, args_conflicts_with_subcommands = true)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<ReceiverCommand>,
+
     /// Output directory for received DICOM files
-    #[arg(short, long)]
+    #[arg(short, long, required_unless_present_any = ["list_codecs", "listeners"], default_value = "", hide_default_value = true)]
     output: PathBuf,
 
     /// AE Title for this receiver
@@ -31,21 +67,521 @@ struct Args {
     #[arg(short, long, default_value = "4242")]
     port: u16,
 
-    /// Maximum number of concurrent associations
+    /// Address to listen on, IPv4 or IPv6 (e.g. 192.168.1.20, ::1 or [::]); binding [::] is
+    /// dual-stack and accepts IPv4 peers as well unless --ipv6-only is given
+    #[arg(long, value_parser = parse_bind_address, default_value_t = DEFAULT_BIND_ADDRESS)]
+    bind: std::net::IpAddr,
+
+    /// With an IPv6 --bind address, refuse IPv4 peers instead of listening dual-stack
+    #[arg(long)]
+    ipv6_only: bool,
+
+    /// Serve HTTP /healthz and /readyz probes and /metrics on this port, on the --bind address
+    #[arg(long)]
+    health_port: Option<u16>,
+
+    /// C-ECHO every listener through the loopback every this many seconds; /healthz fails
+    /// while the last self-test failed
+    #[arg(long, value_name = "SECONDS", requires = "health_port", conflicts_with = "tls_only")]
+    selftest_interval: Option<u64>,
+
+    /// TOML file declaring several listeners, each with its own AE title, port, output directory
+    /// and accepted SOP classes, served by this one process instead of --ae-title, --port and --output
+    #[arg(long, conflicts_with_all = ["ae_title", "port", "output", "tls_cert"])]
+    listeners: Option<PathBuf>,
+
+    /// Also listen for DICOM over TLS, with the PEM certificate chain in this file
+    #[arg(long, requires = "tls_key")]
+    tls_cert: Option<PathBuf>,
+
+    /// PEM private key for --tls-cert
+    #[arg(long, requires = "tls_cert")]
+    tls_key: Option<PathBuf>,
+
+    /// Port for DICOM over TLS
+    #[arg(long, default_value_t = DICOM_TLS_PORT)]
+    tls_port: u16,
+
+    /// Require TLS clients to present a certificate issued by a CA in this PEM file
+    #[arg(long, requires = "tls_cert")]
+    tls_client_ca: Option<PathBuf>,
+
+    /// TLS versions and cipher suites: bcp195 (TLS 1.2 ECDHE-AEAD and TLS 1.3), tls13, or a comma-separated list of suites
+    #[arg(long, default_value = "bcp195", value_parser = CipherPolicy::parse)]
+    tls_cipher_policy: CipherPolicy,
+
+    /// Listen for DICOM over TLS only, not on --port
+    #[arg(long, requires = "tls_cert")]
+    tls_only: bool,
+
+    /// Maximum number of concurrent associations, each served on its own thread
     #[arg(short = 'm', long, default_value = "10")]
     max_connections: usize,
 
+    /// Seconds in-flight C-STOREs get to finish after SIGTERM or Ctrl-C before their associations are closed
+    #[arg(long, default_value = "30")]
+    shutdown_grace: u64,
+
+    /// Seconds an association may receive nothing before it is aborted (ARTIM); 0 never times out
+    #[arg(long, default_value = "60")]
+    idle_timeout: u64,
+
+    /// Seconds after which an association is aborted however busy it is; 0 for no limit
+    #[arg(long, default_value = "0")]
+    max_association_duration: u64,
+
+    /// TOML file with modality-specific validation profiles applied on ingest
+    #[arg(long)]
+    validation_profiles: Option<PathBuf>,
+
+    /// Check received objects against their IOD module requirements and log violations
+    #[arg(long)]
+    iod_validation: bool,
+
+    /// Repair common VR and value-length problems in received objects instead of storing them as-is
+    #[arg(long)]
+    lenient_repair: bool,
+
+    /// Flag objects whose dataset or pixel data duplicates an earlier object under another SOP Instance UID
+    #[arg(long)]
+    detect_duplicate_content: bool,
+
+    /// Remember stored instances per study across restarts, so a re-sent study only stores what is missing
+    #[arg(long)]
+    resume_studies: bool,
+
+    /// How instances stored before are answered when resuming studies
+    #[arg(long, value_enum, default_value = "acknowledge", requires = "resume_studies")]
+    duplicate_policy: DuplicatePolicy,
+
+    /// Warn about objects whose acquisition/content timestamps are far from the receiver clock
+    #[arg(long)]
+    time_sanity_check: bool,
+
+    /// Hours a timestamp may lie in the future before it is flagged
+    #[arg(long, default_value = "24", requires = "time_sanity_check")]
+    max_future_hours: i64,
+
+    /// Days a timestamp may lie in the past before it is flagged
+    #[arg(long, default_value = "3650", requires = "time_sanity_check")]
+    max_past_days: i64,
+
+    /// Largest object accepted, e.g. 4GB (answered with 0xA700 Out of Resources when exceeded)
+    #[arg(long, value_parser = parse_size)]
+    max_object_size: Option<u64>,
+
+    /// Per-category size limit overriding --max-object-size, e.g. SecondaryCapture=2GB (repeatable)
+    #[arg(long = "size-limit", value_parser = parse_category_limit)]
+    size_limits: Vec<(SopClassCategory, u64)>,
+
+    /// Objects larger than this are written to disk as they arrive instead of held in memory, e.g. 256MB
+    #[arg(long, default_value = "64MB", value_parser = parse_size)]
+    spool_threshold: u64,
+
+    /// Maximum bytes accepted on a single association, e.g. 20GB (0xA700 once exceeded)
+    #[arg(long, value_parser = parse_size)]
+    association_quota: Option<u64>,
+
+    /// Maximum bytes accepted across all associations per day, e.g. 1TB (0xA700 once exceeded)
+    #[arg(long, value_parser = parse_size)]
+    daily_quota: Option<u64>,
+
+    /// Comma-separated transfer syntax UIDs in order of preference, chosen over the sender's order
+    #[arg(long, value_parser = TransferSyntaxPreference::parse)]
+    ts_preference: Option<TransferSyntaxPreference>,
+
+    /// Accept retired SOP classes and transfer syntaxes, refuse them in negotiation,
+    /// or accept them and convert to current transfer syntaxes where possible
+    #[arg(long, value_enum, default_value = "accept")]
+    retired: RetiredPolicy,
+
+    /// Encrypt stored objects at rest with the AES-256 key in this file (32 raw bytes or 64 hex digits)
+    #[arg(long, conflicts_with = "encryption_key_env")]
+    encryption_key_file: Option<PathBuf>,
+
+    /// Encrypt stored objects at rest with the AES-256 key in this environment variable (64 hex digits)
+    #[arg(long)]
+    encryption_key_env: Option<String>,
+
+    /// Store objects under paths built from their attributes, e.g. '{modality}/{study_date}/{accession}_{instance_number}.dcm'
+    #[arg(long, value_parser = FilenameTemplate::parse)]
+    filename_template: Option<FilenameTemplate>,
+
+    /// Refuse every C-STORE with Out of Resources, e.g. during storage maintenance; C-ECHO is still answered
+    #[arg(long, conflicts_with = "read_only_file")]
+    read_only: bool,
+
+    /// Refuse C-STOREs like --read-only while this file exists
+    #[arg(long)]
+    read_only_file: Option<PathBuf>,
+
+    /// Append every received object to this hash-chained audit ledger
+    #[arg(long)]
+    ledger: Option<PathBuf>,
+
+    /// Key the ledger's entry hashes and head with HMAC-SHA256 using the key in this file
+    #[arg(long, requires = "ledger")]
+    ledger_key_file: Option<PathBuf>,
+
+    /// Record every stored object and its attributes in this SQLite index, for export-metadata;
+    /// a postgres:// connection string shares one index between several receivers
+    #[arg(long)]
+    index: Option<PathBuf>,
+
+    /// Write a JSON reassembly report per study to <output>/study_reports once the study is complete
+    #[arg(long)]
+    study_reports: bool,
+
+    /// Seconds without new instances after which a study counts as complete
+    #[arg(long, default_value = "60", requires = "study_reports")]
+    study_timeout: i64,
+
+    /// Export each presentation state with its referenced images to <output>/bundles when its study completes
+    #[arg(long, requires = "study_reports")]
+    presentation_state_bundles: bool,
+
+    /// POST a JSON notification to this URL when a study completes
+    #[arg(long, value_name = "URL", requires = "study_reports")]
+    study_webhook: Option<String>,
+
+    /// Sign study webhook bodies with HMAC-SHA256 using the key in this file (X-Signature-256 header)
+    #[arg(long, value_name = "PATH", requires = "study_webhook")]
+    study_webhook_secret_file: Option<PathBuf>,
+
+    /// Retries of a study webhook notification that fails, with exponential backoff
+    #[arg(long, default_value = "3", requires = "study_webhook")]
+    study_webhook_retries: u32,
+
+    /// Enforce PS3.8/PS3.7 exactly: refuse mismatched called AE titles, abort on malformed items or wrong context IDs
+    #[arg(long)]
+    strict: bool,
+
+    /// Override a single compliance rule, e.g. called-ae=warn or unknown-context=reject (repeatable)
+    #[arg(long = "compliance", value_parser = parse_rule_override)]
+    compliance_overrides: Vec<(ComplianceRule, RuleAction)>,
+
+    /// Fault injection: answer C-STORE requests with this status (e.g. 0xA700, 0xC000)
+    #[arg(long, value_parser = parse_status)]
+    inject_status: Option<u16>,
+
+    /// Fault injection: apply --inject-status to every Nth object only
+    #[arg(long, default_value = "1", requires = "inject_status")]
+    inject_every: u32,
+
+    /// Fault injection: abort associations at this point
+    #[arg(long, value_enum)]
+    drop_at: Option<DropPoint>,
+
+    /// Fault injection: number of objects handled normally per association before --drop-at applies
+    #[arg(long, default_value = "0", requires = "drop_at")]
+    drop_after: u32,
+
+    /// Fault injection: delay each C-STORE response by this many milliseconds
+    #[arg(long, default_value = "0")]
+    response_delay_ms: u64,
+
+    /// Fault injection: delay the release response by this many milliseconds
+    #[arg(long, default_value = "0")]
+    release_delay_ms: u64,
+
+    /// What to do on startup with partial files left by an interrupted transfer or crash
+    #[arg(long, value_enum, default_value = "quarantine")]
+    recovery_policy: RecoveryPolicy,
+
+    /// Reproducible output for test fixtures: seeded session ID and sequentially numbered files
+    #[arg(long)]
+    deterministic: bool,
+
+    /// Seed for identifiers generated in deterministic mode
+    #[arg(long, default_value = "0", requires = "deterministic")]
+    seed: u64,
+
+    /// Advertise this receiver over mDNS/DNS-SD so senders can use --discover (requires the mdns feature)
+    #[arg(long)]
+    advertise: bool,
+
+    /// List the transfer syntaxes and pixel data codecs this build supports, then exit
+    #[arg(long)]
+    list_codecs: bool,
+
+    /// Started by the Windows Service Control Manager; see `dicom-receiver service install`
+    #[cfg(windows)]
+    #[arg(long, hide = true)]
+    windows_service: bool,
+
     /// Verbose output
     #[arg(short, long)]
     verbose: bool,
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
+#[derive(Subcommand, Clone)]
+enum ReceiverCommand {
+    /// Write selected attributes of the indexed instances matching a query as CSV
+    ExportMetadata {
+        /// Index written by the receiver with --index
+        #[arg(long)]
+        index: PathBuf,
+
+        /// Keyword=value condition, repeatable; values take * and ? wildcards, dates and sizes ranges like 20240101-20241231,
+        /// and ReceivedAt intervals like 2024-01-01T00:00:00Z/2024-02-01T00:00:00Z
+        #[arg(short, long)]
+        query: Vec<String>,
+
+        /// Comma-separated attribute keywords to export, plus CallingAE, Path, Size and ReceivedAt
+        #[arg(short, long, value_delimiter = ',',
+              default_value = "PatientID,StudyInstanceUID,StudyDate,Modality,SeriesInstanceUID,SeriesDescription")]
+        columns: Vec<String>,
+
+        /// One row per instance, series or study
+        #[arg(long, value_enum, default_value = "series")]
+        level: Level,
+
+        /// CSV file to write instead of standard output
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Summarise the index: studies, series and instances by modality, storage by SOP class
+    /// category and transfer syntax, growth over time and the largest studies
+    Stats {
+        /// Index written by the receiver with --index
+        #[arg(long)]
+        index: PathBuf,
+
+        /// Keyword=value condition restricting the instances summarised, as in export-metadata (repeatable)
+        #[arg(short, long)]
+        query: Vec<String>,
+
+        /// Period the growth over time is reported by
+        #[arg(long, value_enum, default_value = "month")]
+        period: Period,
+
+        /// Number of largest studies to list
+        #[arg(long, default_value = "10")]
+        largest: usize,
+
+        /// Print the summary as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Remove the empty directories below an output directory and find the stored objects missing from the index
+    Gc {
+        /// Index written by the receiver with --index
+        #[arg(long)]
+        index: PathBuf,
+
+        /// Output directory of the receiver; relative paths in the index are resolved
+        /// against the current directory, so run from the receiver's working directory
+        #[arg(short, long)]
+        output: PathBuf,
+
+        /// What to do with stored objects the index does not know
+        #[arg(long, value_enum, default_value = "report")]
+        orphans: OrphanAction,
+
+        /// Key the stored files were encrypted with, to import them
+        #[arg(long)]
+        encryption_key_file: Option<PathBuf>,
+
+        /// Only list what would be removed, imported or deleted
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Give the indexed instances of a patient a corrected Patient ID
+    UpdateMrn {
+        /// Index written by the receiver with --index
+        #[arg(long)]
+        index: PathBuf,
+
+        /// Patient ID to correct
+        #[arg(long)]
+        from: String,
+
+        /// Corrected Patient ID
+        #[arg(long)]
+        to: String,
+
+        /// Issuer of the corrected Patient ID
+        #[arg(long)]
+        issuer: Option<String>,
+
+        #[command(flatten)]
+        correction: CorrectionArgs,
+    },
+    /// Move the indexed instances of a duplicate patient to the patient being kept
+    MergePatients {
+        /// Index written by the receiver with --index
+        #[arg(long)]
+        index: PathBuf,
+
+        /// Patient ID of the duplicate, whose instances take the identity of the target
+        #[arg(long)]
+        source: String,
+
+        /// Patient ID of the patient being kept
+        #[arg(long)]
+        target: String,
+
+        #[command(flatten)]
+        correction: CorrectionArgs,
+    },
+    /// Place a study under legal hold, so it is neither corrected nor overwritten
+    Hold {
+        /// Index written by the receiver with --index
+        #[arg(long)]
+        index: PathBuf,
+
+        /// Study Instance UID of the study to hold
+        #[arg(long)]
+        study: String,
+
+        /// Reason kept in the index's audit trail
+        #[arg(long)]
+        reason: String,
+    },
+    /// Release the legal hold on a study
+    ReleaseHold {
+        /// Index written by the receiver with --index
+        #[arg(long)]
+        index: PathBuf,
+
+        /// Study Instance UID of the held study
+        #[arg(long)]
+        study: String,
+
+        /// Reason kept in the index's audit trail
+        #[arg(long)]
+        reason: String,
+    },
+    /// List the studies under legal hold
+    ListHolds {
+        /// Index written by the receiver with --index
+        #[arg(long)]
+        index: PathBuf,
+
+        /// Print the audit trail of placed and released holds instead
+        #[arg(long)]
+        history: bool,
+    },
+    /// Register the receiver as a Windows service, or remove it
+    #[cfg(windows)]
+    Service {
+        #[command(subcommand)]
+        action: service::ServiceAction,
+    },
+}
+
+#[derive(clap::Args, Clone)]
+struct CorrectionArgs {
+    /// Reason kept in the index's audit trail
+    #[arg(long)]
+    reason: String,
+
+    /// Also rewrite the stored files, keeping their previous values in the Original Attributes Sequence
+    #[arg(long)]
+    rewrite: bool,
+
+    /// Key the stored files were encrypted with, to rewrite them
+    #[arg(long, requires = "rewrite")]
+    encryption_key_file: Option<PathBuf>,
+}
+
+fn main() -> Result<()> {
     let args = Args::parse();
 
+    #[cfg(windows)]
+    match &args.command {
+        Some(ReceiverCommand::Service { action }) => return service::control(action),
+        _ if args.windows_service => return service::dispatch(args),
+        _ => {}
+    }
+
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()?
+        .block_on(run(args, shutdown_signal()))
+}
+
+/// Run the command or receiver `args` ask for; the receiver stops once `stop_requested` resolves
+async fn run(args: Args, stop_requested: impl Future<Output = ()> + Send + 'static) -> Result<()> {
+    if args.list_codecs {
+        print!("{}", codec_report(TransferSyntaxRegistry::global()));
+        return Ok(());
+    }
+
+    if let Some(ReceiverCommand::ExportMetadata { index, query, columns, level, output }) = &args.command {
+        let filters = query.iter().map(|filter| Filter::parse(filter)).collect::<Result<Vec<_>>>()?;
+        let columns: Vec<String> = columns.iter().map(|column| column.trim().to_string()).filter(|column| !column.is_empty()).collect();
+        let instances = open_index(index)?.query(&filters)?;
+        let csv = metadata_csv(&instances, &columns, *level);
+        match output {
+            Some(path) => {
+                std::fs::write(path, csv)?;
+                eprintln!("📄 Exported {} matching instance(s) to {}", instances.len(), path.display());
+            }
+            None => print!("{}", csv),
+        }
+        return Ok(());
+    }
+
+    match &args.command {
+        Some(ReceiverCommand::Stats { index, query, period, largest, json }) => {
+            let filters = query.iter().map(|filter| Filter::parse(filter)).collect::<Result<Vec<_>>>()?;
+            let stats = index_stats(&open_index(index)?.query(&filters)?, *period, *largest);
+            if *json {
+                println!("{}", serde_json::to_string_pretty(&stats)?);
+            } else {
+                print!("{}", stats_text(&stats));
+            }
+            return Ok(());
+        }
+        Some(ReceiverCommand::Gc { index, output, orphans, encryption_key_file, dry_run }) => {
+            let key = encryption_key_file.as_deref().map(StorageKey::from_file).transpose()?;
+            return collect_garbage(index, output, *orphans, key.as_ref(), *dry_run);
+        }
+        Some(ReceiverCommand::UpdateMrn { index, from, to, issuer, correction }) => {
+            let patient = PatientCorrection::update_mrn(from, to, issuer.as_deref(), &correction.reason)?;
+            return apply_patient_correction(index, &patient, correction);
+        }
+        Some(ReceiverCommand::MergePatients { index, source, target, correction }) => {
+            let survivor = open_index(index)?.patient_instances(target)?.into_iter().next()
+                .ok_or_else(|| anyhow::anyhow!("No indexed instances of target patient {}", target))?;
+            let patient = PatientCorrection::merge(source, &survivor, &correction.reason)?;
+            return apply_patient_correction(index, &patient, correction);
+        }
+        Some(ReceiverCommand::Hold { index, study, reason }) => {
+            open_index(index)?.place_hold(study, reason)?;
+            println!("⚖️  Study {} placed under legal hold: {}", study, reason);
+            return Ok(());
+        }
+        Some(ReceiverCommand::ReleaseHold { index, study, reason }) => {
+            if !open_index(index)?.release_hold(study, reason)? {
+                anyhow::bail!("Study {} is not under legal hold", study);
+            }
+            println!("⚖️  Legal hold on study {} released: {}", study, reason);
+            return Ok(());
+        }
+        Some(ReceiverCommand::ListHolds { index, history }) => {
+            let index = open_index(index)?;
+            if *history {
+                for event in index.hold_events()? {
+                    println!("{}  {:<7}  {}  {}", event.at.to_rfc3339(), event.action, event.study_instance_uid, event.reason);
+                }
+            } else {
+                for hold in index.holds()? {
+                    println!("{}  {}  {}", hold.study_instance_uid, hold.placed_at.to_rfc3339(), hold.reason);
+                }
+            }
+            return Ok(());
+        }
+        _ => {}
+    }
+
     // Initialize logging
-    let session_id = Uuid::new_v4().to_string();
+    let session_id = if args.deterministic {
+        seeded_uuid(args.seed, "dicom-receiver-session").to_string()
+    } else {
+        Uuid::new_v4().to_string()
+    };
     
     // Create logs directory if it doesn't exist
     std::fs::create_dir_all("logs")?;
@@ -59,26 +595,458 @@ async fn main() -> Result<()> {
     println!("{} DICOM Receiver v1.0", SATELLITE);
     println!("Session ID: {}", style(&session_id).cyan());
     println!("Log file: {}", style(&log_file).yellow());
-    println!("AE Title: {}", style(&args.ae_title).green());
-    println!("Port: {}", style(&args.port).green());
-    println!("Output: {}", style(&args.output.display()).green());
+    // Without a listener configuration, the command line describes the one listener
+    let listeners = match &args.listeners {
+        Some(path) => load_listeners(path)?,
+        None => vec![ListenerConfig {
+            ae_title: args.ae_title.clone(),
+            port: args.port,
+            output: args.output.clone(),
+            sop_classes: Vec::new(),
+        }],
+    };
+    if let [listener] = listeners.as_slice() {
+        println!("AE Title: {}", style(&listener.ae_title).green());
+        println!("Port: {}", style(&listener.port).green());
+        println!("Output: {}", style(&listener.output.display()).green());
+    } else {
+        for listener in &listeners {
+            let accepted = if listener.sop_classes.is_empty() { "all SOP classes".to_string() } else { listener.sop_classes.join(", ") };
+            println!("Listener: {} on port {} → {} ({})", style(&listener.ae_title).green(), style(&listener.port).green(),
+                     style(listener.output.display()).green(), accepted);
+        }
+    }
+    println!("Bind address: {}{}", style(&args.bind).green(),
+             if args.bind.is_ipv6() && !args.ipv6_only { " (dual-stack)" } else { "" });
     println!("Max connections: {}", style(&args.max_connections).green());
+    println!("Shutdown grace period: {}", style(format!("{}s", args.shutdown_grace)).green());
+    println!("Idle timeout: {}", style(format!("{}s", args.idle_timeout)).green());
+    if args.max_association_duration > 0 {
+        println!("Maximum association duration: {}", style(format!("{}s", args.max_association_duration)).green());
+    }
     println!();
 
-    // Create output directory if it doesn't exist
-    std::fs::create_dir_all(&args.output)?;
+    for listener in &listeners {
+        // Create output directory if it doesn't exist
+        std::fs::create_dir_all(&listener.output)?;
+
+        // Deal with partial files left behind by a previous run
+        let recovery = recover(&listener.output, args.recovery_policy)?;
+        info!("Startup recovery of {} ({:?}): {}", listener.output.display(), args.recovery_policy, recovery);
+        for (path, error) in &recovery.failed {
+            tracing::warn!("Could not recover {}: {}", path.display(), error);
+        }
+        if recovery.found > 0 {
+            println!("Startup recovery of {}: {}", listener.output.display(), style(&recovery).yellow());
+            println!();
+        }
+    }
 
-    // Start the receiver
-    let receiver = Arc::new(DicomReceiver::new(
-        args.ae_title.clone(),
-        args.output.clone(),
+    // Configure the receiver, then one copy of it per listener
+    let mut receiver = DicomReceiver::new(
+        listeners[0].ae_title.clone(),
+        listeners[0].output.clone(),
         args.max_connections,
-    ));
+    ).with_shutdown_grace(Duration::from_secs(args.shutdown_grace))
+        .with_bind_address(args.bind, args.ipv6_only)
+        .with_idle_timeout(Some(Duration::from_secs(args.idle_timeout)).filter(|timeout| !timeout.is_zero()))
+        .with_max_association_duration(Some(Duration::from_secs(args.max_association_duration)).filter(|duration| !duration.is_zero()));
+
+    if let Some(path) = &args.validation_profiles {
+        let profiles = ValidationProfiles::from_file(path)?;
+        println!("Validation profiles: {} ({} modalities)", style(path.display()).green(), profiles.len());
+        receiver = receiver.with_validation_profiles(profiles);
+    }
+
+    if args.deterministic {
+        println!("Deterministic mode: {} (seed {})", style("enabled").green(), args.seed);
+        receiver = receiver.with_deterministic_filenames(true);
+    }
+
+    if args.iod_validation {
+        println!("IOD validation: {}", style("enabled").green());
+        receiver = receiver.with_iod_validation(true);
+    }
+
+    if args.lenient_repair {
+        println!("Lenient repair: {}", style("enabled").green());
+        receiver = receiver.with_lenient_repair(true);
+    }
+
+    if args.detect_duplicate_content {
+        println!("Duplicate content detection: {}", style("enabled").green());
+        receiver = receiver.with_duplicate_content_detection(true);
+    }
+
+    if args.time_sanity_check {
+        println!("Time sanity check: {} (+{}h / -{}d)", style("enabled").green(), args.max_future_hours, args.max_past_days);
+        receiver = receiver.with_time_sanity_check(TimeSanityPolicy {
+            max_future: chrono::Duration::hours(args.max_future_hours),
+            max_past: chrono::Duration::days(args.max_past_days),
+        });
+    }
+
+    let size_limits = SizeLimits {
+        default_limit: args.max_object_size,
+        category_limits: args.size_limits.clone(),
+    };
+    if !size_limits.is_empty() {
+        if let Some(limit) = size_limits.default_limit {
+            println!("Max object size: {}", style(format!("{} bytes", limit)).green());
+        }
+        for (category, limit) in &size_limits.category_limits {
+            println!("Size limit: {:?} {}", category, style(format!("{} bytes", limit)).green());
+        }
+        receiver = receiver.with_size_limits(size_limits);
+    }
+
+    println!("Spool threshold: {}", style(format!("{} bytes", args.spool_threshold)).green());
+    receiver = receiver.with_spool_threshold(args.spool_threshold);
+
+    if args.association_quota.is_some() || args.daily_quota.is_some() {
+        if let Some(quota) = args.association_quota {
+            println!("Association quota: {}", style(format!("{} bytes", quota)).green());
+        }
+        if let Some(quota) = args.daily_quota {
+            println!("Daily quota: {}", style(format!("{} bytes", quota)).green());
+        }
+        receiver = receiver.with_byte_quotas(ByteQuotas {
+            per_association: args.association_quota,
+            daily: args.daily_quota,
+        });
+    }
+
+    if let Some(preference) = &args.ts_preference {
+        println!("Transfer syntax preference: {}", style(preference.order.join(", ")).green());
+        for uid in preference.order.iter().filter(|uid| !TransferSyntaxRegistry::global().codec_support(uid).dataset) {
+            println!("  {} {} is not supported by this build and will not be accepted", style("⚠️").yellow(), uid);
+        }
+        receiver = receiver.with_transfer_syntax_preference(preference.clone());
+    }
+
+    if args.retired != RetiredPolicy::Accept {
+        println!("Retired SOP classes and transfer syntaxes: {}", style(format!("{:?}", args.retired).to_lowercase()).green());
+        if args.retired == RetiredPolicy::Refuse && args.ts_preference.as_ref()
+            .is_some_and(|preference| preference.order.iter().any(|uid| TransferSyntaxRegistry::global().is_retired(uid))) {
+            println!("  {} retired syntaxes in the transfer syntax preference will not be accepted", style("⚠️").yellow());
+        }
+        receiver = receiver.with_retired_policy(args.retired);
+    }
+
+    if let Some(path) = &args.encryption_key_file {
+        println!("Encryption at rest: {} (key file {})", style("enabled").green(), path.display());
+        receiver = receiver.with_encryption(StorageKey::from_file(path)?);
+    } else if let Some(variable) = &args.encryption_key_env {
+        println!("Encryption at rest: {} (key from ${})", style("enabled").green(), variable);
+        receiver = receiver.with_encryption(StorageKey::from_env(variable)?);
+    }
+
+    if let Some(template) = &args.filename_template {
+        println!("Filename template: {}", style(template.as_str()).green());
+        receiver = receiver.with_filename_template(template.clone());
+    }
+
+    if let (Some(certificate), Some(private_key)) = (&args.tls_cert, &args.tls_key) {
+        let config = server_config(certificate, private_key, args.tls_client_ca.as_deref(), &args.tls_cipher_policy)?;
+        println!("DICOM over TLS: {} on port {} (cipher policy {}, client certificates {})", style("enabled").green(), args.tls_port,
+                 args.tls_cipher_policy, if args.tls_client_ca.is_some() { "required" } else { "not requested" });
+        receiver = receiver.with_tls(args.tls_port, config);
+        if args.tls_only {
+            println!("Plaintext listener: {}", style("disabled").yellow());
+            receiver = receiver.with_plaintext_listener(false);
+        }
+    }
+
+    if args.read_only {
+        println!("Read-only mode: {}", style("enabled").yellow());
+        receiver = receiver.with_read_only(ReadOnly::Always);
+    } else if let Some(path) = &args.read_only_file {
+        println!("Read-only mode: {} (while {} exists)", style("on demand").green(), path.display());
+        receiver = receiver.with_read_only(ReadOnly::WhileFileExists(path.clone()));
+    }
+
+    if let Some(path) = &args.ledger {
+        let key = args.ledger_key_file.as_deref().map(ledger::read_key).transpose()?;
+        let ledger = Ledger::open(path, key)?;
+        println!("Audit ledger: {}{}", style(path.display()).green(), if args.ledger_key_file.is_some() { " (keyed)" } else { "" });
+        receiver = receiver.with_ledger(ledger);
+    }
+
+    if let Some(path) = &args.index {
+        let index = open_index(path)?;
+        println!("Receive index: {}", style(display_location(path)).green());
+        receiver = receiver.with_receive_index(index);
+    }
+
+    if args.study_reports {
+        println!("Study reports: {} (complete after {}s idle)", style("enabled").green(), args.study_timeout);
+        receiver = receiver.with_study_reports(chrono::Duration::seconds(args.study_timeout));
+    }
+
+    if args.presentation_state_bundles {
+        println!("Presentation state bundles: {}", style("enabled").green());
+        receiver = receiver.with_presentation_state_bundles(true);
+    }
+
+    if let Some(url) = &args.study_webhook {
+        let secret = match &args.study_webhook_secret_file {
+            Some(path) => Some(std::fs::read_to_string(path)
+                .map_err(|e| anyhow::anyhow!("Failed to read webhook secret {}: {}", path.display(), e))?
+                .trim().as_bytes().to_vec()),
+            None => None,
+        };
+        println!("Study webhook: {}{}", style(url).green(), if secret.is_some() { " (signed)" } else { "" });
+        receiver = receiver.with_study_webhook(StudyWebhook::new(url.clone(), secret, args.study_webhook_retries));
+    }
+
+    if args.strict || !args.compliance_overrides.is_empty() {
+        let mut policy = if args.strict { CompliancePolicy::strict() } else { CompliancePolicy::lenient() };
+        for (rule, action) in &args.compliance_overrides {
+            policy.set(*rule, *action);
+        }
+        let rejected: Vec<String> = policy.rejected_rules().map(|rule| rule.to_string()).collect();
+        let mode = if policy.is_strict() { "strict" } else { "lenient" };
+        println!("Protocol compliance: {} (rejecting: {})", style(mode).green(),
+                 if rejected.is_empty() { "none".to_string() } else { rejected.join(", ") });
+        receiver = receiver.with_compliance(policy);
+    }
+
+    if args.inject_status.is_some() || args.drop_at.is_some() || args.response_delay_ms > 0 || args.release_delay_ms > 0 {
+        println!("{}", style("⚠️  Fault injection enabled - for SCU certification only").red());
+        if let Some(status) = args.inject_status {
+            println!("  Injected status: 0x{:04X} (every {} object(s))", status, args.inject_every);
+        }
+        if let Some(point) = args.drop_at {
+            println!("  Drop associations at: {:?} (after {} object(s))", point, args.drop_after);
+        }
+        receiver = receiver.with_fault_injection(FaultInjection {
+            status: args.inject_status,
+            status_every: args.inject_every,
+            drop_point: args.drop_at,
+            drop_after: args.drop_after,
+            response_delay: Duration::from_millis(args.response_delay_ms),
+            release_delay: Duration::from_millis(args.release_delay_ms),
+        });
+    }
+
+    // Ready once every listener is bound, until shutdown begins
+    if let Some(port) = args.health_port {
+        let address = std::net::SocketAddr::new(args.bind, port);
+        let health_listener = bind_health_listener(address)
+            .map_err(|e| anyhow::anyhow!("Failed to listen for health probes on {}: {}", address, e))?;
+        println!("Health probes: {}, {} and {}", style(format!("http://{}/healthz", address)).green(), style("/readyz").green(),
+                 style("/metrics").green());
+        let readiness = Arc::new(Readiness::new(listeners.len()));
+        tokio::spawn(serve_health(health_listener, Arc::clone(&readiness), Some(receiver.metrics())));
+        if let Some(interval) = args.selftest_interval.filter(|seconds| *seconds > 0).map(Duration::from_secs) {
+            println!("Self-test: C-ECHO every {}", style(format!("{}s", interval.as_secs())).green());
+            let targets = listeners.iter().map(|listener| (listener.ae_title.clone(), listener.port)).collect();
+            tokio::spawn(self_test(targets, args.bind, interval, Arc::clone(&readiness), receiver.metrics()));
+        }
+        receiver = receiver.with_readiness(readiness);
+    }
+
+    let mut receivers = Vec::new();
+    for listener in &listeners {
+        let mut site = receiver.for_listener(listener.ae_title.clone(), listener.output.clone());
+        if let Some(uids) = listener.sop_class_uids()? {
+            site = site.with_sop_classes(uids);
+        }
+        // Each listener resumes the studies stored under its own output directory
+        if args.resume_studies {
+            let received = ReceivedInstances::open(&listener.output.join(RECEIVED_INSTANCES_DIR))?;
+            println!("Study resume for {}: {} ({} instance(s) stored before, duplicates: {:?})", style(&listener.ae_title).green(),
+                     style("enabled").green(), received.len(), args.duplicate_policy);
+            site = site.with_sparse_resume(received, args.duplicate_policy);
+        }
+        receivers.push((Arc::new(site), listener.port));
+    }
+
+    // Kept alive for as long as the receiver runs
+    let mut advertisements = Vec::new();
+    if args.advertise {
+        for listener in &listeners {
+            let port = if args.tls_only { args.tls_port } else { listener.port };
+            advertisements.push(advertise(&listener.ae_title, port)?);
+            println!("mDNS advertisement: {} on port {}", style(&listener.ae_title).green(), port);
+        }
+    }
 
     println!("{} Starting DICOM receiver...", INBOX);
-    info!("Starting DICOM receiver on port {}", args.port);
+    info!("Starting DICOM receiver with {} listener(s)", listeners.len());
+
+    // Every listener stops on the first request
+    let (stop, stopped) = tokio::sync::watch::channel(false);
+    tokio::spawn(async move {
+        stop_requested.await;
+        info!("Shutdown requested");
+        println!("{}", style("Press Ctrl-C again to stop immediately").yellow());
+        let _ = stop.send(true);
+        shutdown_signal().await;
+        // Interrupted transfers are picked up by the next startup recovery
+        std::process::exit(130);
+    });
+
+    let mut running = tokio::task::JoinSet::new();
+    for (receiver, port) in receivers {
+        let mut stopped = stopped.clone();
+        running.spawn(receiver.start(port, async move {
+            let _ = stopped.wait_for(|stop| *stop).await;
+        }));
+    }
+    while let Some(result) = running.join_next().await {
+        result??;
+    }
+
+    println!("{} Receiver stopped", INBOX);
+    Ok(())
+}
+
+/// Once the listeners are up, C-ECHO each of `targets` (AE title and port)
+/// through the loopback every `interval` and report the outcome to `metrics`
+async fn self_test(targets: Vec<(String, u16)>, bind: std::net::IpAddr, interval: Duration, readiness: Arc<Readiness>,
+                   metrics: Arc<ServiceMetrics>) {
+    let host = match bind {
+        std::net::IpAddr::V4(address) if address.is_unspecified() => std::net::Ipv4Addr::LOCALHOST.to_string(),
+        std::net::IpAddr::V6(address) if address.is_unspecified() => std::net::Ipv6Addr::LOCALHOST.to_string(),
+        address => address.to_string(),
+    };
+    readiness.ready().await;
+    let mut ticks = tokio::time::interval_at(tokio::time::Instant::now() + interval, interval);
+    ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        ticks.tick().await;
+        let mut outcome = Ok(());
+        for (ae_title, port) in &targets {
+            let client = DicomClient::new(DicomClientConfig {
+                calling_ae: SELF_TEST_AE.to_string(),
+                called_ae: ae_title.clone(),
+                host: host.clone(),
+                port: *port,
+                timeout: interval.min(Duration::from_secs(30)),
+                connect_timeout: Duration::from_secs(5),
+                lenient_repair: false,
+                compute_checksums: false,
+                pack_pdvs: false,
+                tls: None,
+                preview: None,
+            });
+            let result = match client.echo().await {
+                Ok(0x0000) => Ok(()),
+                Ok(status) => Err(format!("{} on port {} answered C-ECHO with 0x{:04X}", ae_title, port, status)),
+                Err(e) => Err(format!("{} on port {}: {:#}", ae_title, port, e)),
+            };
+            if let Err(reason) = result {
+                tracing::error!("❌  Self-test failed: {}", reason);
+                outcome = Err(reason);
+                break;
+            }
+        }
+        if outcome.is_ok() {
+            tracing::debug!("Self-test passed");
+        }
+        metrics.record_self_test(outcome);
+    }
+}
+
+/// Parse a DIMSE status given as hex (0xA700) or decimal
+fn parse_status(value: &str) -> Result<u16, String> {
+    let parsed = match value.strip_prefix("0x").or_else(|| value.strip_prefix("0X")) {
+        Some(hex) => u16::from_str_radix(hex, 16),
+        None => value.parse(),
+    };
+    parsed.map_err(|e| format!("invalid status '{}': {}", value, e))
+}
+
+/// Deal with the stored objects below `output` that the index does not know, then
+/// remove the directories left empty
+fn collect_garbage(index: &Path, output: &Path, orphans: OrphanAction, key: Option<&StorageKey>, dry_run: bool) -> Result<()> {
+    let index = open_index(index)?;
+    let indexed: std::collections::HashSet<PathBuf> = index.query(&[])?.iter().map(|instance| normalized(&instance.path)).collect();
+    let found = find_orphans(output, &indexed);
+    let dry = if dry_run { "[dry run] " } else { "" };
+
+    let mut failed = 0;
+    for path in &found {
+        let result = match orphans {
+            OrphanAction::Report => {
+                println!("👻 Not in the index: {}", path.display());
+                Ok(())
+            }
+            OrphanAction::Import => orphan_instance(path, key).and_then(|instance| {
+                println!("📥 {}Importing {} ({})", dry, path.display(), instance.sop_instance_uid);
+                if dry_run { Ok(()) } else { index.record(&instance) }
+            }),
+            OrphanAction::Delete => {
+                // An object that cannot be read cannot be matched with a hold either
+                let study = orphan_instance(path, key).map(|instance| instance.value("StudyInstanceUID")).unwrap_or_default();
+                if !study.is_empty() && index.is_held(&study)? {
+                    println!("⚖️  Kept {}: study {} is under legal hold", path.display(), study);
+                    Ok(())
+                } else {
+                    println!("🗑️  {}Deleting {}", dry, path.display());
+                    if dry_run { Ok(()) } else { std::fs::remove_file(path).map_err(anyhow::Error::from) }
+                }
+            }
+        };
+        if let Err(e) = result {
+            eprintln!("❌ {}: {:#}", path.display(), e);
+            failed += 1;
+        }
+    }
+
+    let removed = remove_empty_dirs(output, dry_run)?;
+    for dir in &removed {
+        println!("🧹 {}Removing empty directory {}", dry, dir.display());
+    }
+    println!("{}{} stored object(s) not in the index, {} empty director{} removed", dry, found.len(), removed.len(),
+             if removed.len() == 1 { "y" } else { "ies" });
+    if failed > 0 {
+        anyhow::bail!("{} stored object(s) could not be handled", failed);
+    }
+    Ok(())
+}
+
+/// Apply a patient correction to the index and, with --rewrite, to the stored files
+fn apply_patient_correction(index: &Path, patient: &PatientCorrection, args: &CorrectionArgs) -> Result<()> {
+    let mut index = open_index(index)?;
+    let instances = index.patient_instances(&patient.patient_id)?;
+    if instances.is_empty() {
+        anyhow::bail!("No indexed instances of patient {}", patient.patient_id);
+    }
+    // The index refuses held studies as well, but only once the files would already be rewritten
+    let mut held = Vec::new();
+    for instance in &instances {
+        if index.is_held_instance(&instance.sop_instance_uid)? {
+            held.push(instance.value("StudyInstanceUID"));
+        }
+    }
+    if !held.is_empty() {
+        held.sort();
+        held.dedup();
+        anyhow::bail!("Patient {} has studies under legal hold: {}", patient.patient_id, held.join(", "));
+    }
+    let key = args.encryption_key_file.as_deref().map(StorageKey::from_file).transpose()?;
 
-    receiver.start(args.port).await?;
+    let (mut rewritten, mut failed) = (0, 0);
+    if args.rewrite {
+        for instance in &instances {
+            match rewrite_stored_object(&instance.path, &patient.changes, key.as_ref()) {
+                Ok(true) => rewritten += 1,
+                Ok(false) => {}
+                Err(e) => {
+                    eprintln!("❌ {}: {}", instance.path.display(), e);
+                    failed += 1;
+                }
+            }
+        }
+    }
 
+    let corrected = index.correct_patient(patient, rewritten)?;
+    println!("🩹 Patient {} ({}): {} indexed instance(s) corrected, {} stored file(s) rewritten",
+             patient.patient_id, patient.kind.as_str(), corrected, rewritten);
+    if failed > 0 {
+        anyhow::bail!("{} stored file(s) could not be rewritten", failed);
+    }
     Ok(())
 }
@@ -1,52 +1,198 @@
 #[path = "../common/mod.rs"]
-mod common;
+pub(crate) mod common;
 
 use anyhow::{Context, Result};
 use chrono::Utc;
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::fs;
 use tokio::sync::Semaphore;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 
+use dicom::encoding::transfer_syntax::TransferSyntaxIndex;
+use dicom_object::InMemDicomObject;
 use dicom_ul::association::server::ServerAssociationOptions;
 use dicom_ul::pdu::{Pdu, PDataValue, PDataValueType};
 
-use common::sop_classes::SopClassRegistry;
+use common::sop_classes::{SopClassCategory, SopClassRegistry};
 use common::transfer_syntaxes::TransferSyntaxRegistry;
+use common::compliance::{ComplianceRule, CompliancePolicy};
+use common::content_hash::ContentIndex;
+use common::dimse::{decode_command, is_valid_uid, response_command, C_ECHO_RQ, C_ECHO_RSP, C_STORE_RSP, VERIFICATION_SOP_CLASS};
+use common::encryption::{OpeningReader, StorageKey};
+use common::part10::{part10_file, FileMeta};
+use common::iod::validate_iod;
+use common::key_objects::collect_referenced_instances;
+use common::ledger::{Direction, Ledger, LedgerRecord};
+use common::health::{Readiness, ServiceMetrics, SELF_TEST_AE};
+use common::listen::{bind_listener, DEFAULT_BIND_ADDRESS};
+use common::naming::{long_path, unique_path, FilenameTemplate};
+use common::receive_index::{indexed_attributes, IndexedInstance, MetadataStore};
+use common::recovery::{partial_path, write_atomically, write_partial};
+use common::resume::{DuplicatePolicy, ReceivedInstances};
+use common::quotas::{ByteCounters, ByteQuotas, CounterSnapshot};
+use common::reassembly::{DicomTransfer, Spool, Transfers};
+use common::repair::repair_dataset;
+use common::shutdown::Shutdown;
+use common::size_limits::SizeLimits;
+use common::study_report::{export_bundle, write_report, ReceivedInstance, StudyTracker};
+use common::study_webhook::StudyWebhook;
+use common::retired::RetiredPolicy;
+use common::ts_preference::{called_ae_title, parse_association_rq, pdu_length, ProposedContext, TransferSyntaxPreference};
+use common::time_sanity::{check_timestamps, TimeSanityPolicy};
+use common::tls::terminate;
+use common::validation::{ValidationAction, ValidationProfiles};
+use common::uids::ts;
 
+/// How often studies are checked for completion when study reports are enabled
+const STUDY_REPORT_POLL: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// How long in-flight C-STOREs may take to finish once a shutdown is requested
+pub const DEFAULT_SHUTDOWN_GRACE: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// How long closed associations get to save their incomplete data sets after the grace period
+const FORCED_SHUTDOWN_WAIT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// How long an association may go without receiving anything (ARTIM) before it is aborted
+pub const DEFAULT_IDLE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// How long negotiation may take when associations have no idle timeout
+const NEGOTIATION_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// Bytes of a data set held in memory before it is written to disk as it arrives
+pub const DEFAULT_SPOOL_THRESHOLD: u64 = 64 << 20;
+
+const PIXEL_DATA: dicom_core::Tag = dicom_core::Tag(0x7FE0, 0x0010);
+
+/// Point at which the receiver aborts an association when fault injection is enabled
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum DropPoint {
+    /// Immediately after the association is accepted
+    Association,
+    /// On the first dataset fragment of an object
+    Dataset,
+    /// After an object is fully received, instead of responding
+    Response,
+}
+
+/// Deliberate misbehaviour for certifying SCU error handling
+#[derive(Debug, Clone, Default)]
+pub struct FaultInjection {
+    /// C-STORE status returned instead of the real one
+    pub status: Option<u16>,
+    /// Apply the injected status to every Nth object (1 = all)
+    pub status_every: u32,
+    pub drop_point: Option<DropPoint>,
+    /// Objects handled normally on an association before the drop point applies
+    pub drop_after: u32,
+    pub response_delay: std::time::Duration,
+    pub release_delay: std::time::Duration,
+}
+
+impl FaultInjection {
+    fn status_for(&self, object_number: u32) -> Option<u16> {
+        let every = self.status_every.max(1);
+        self.status.filter(|_| object_number % every == 0)
+    }
+
+    fn drops_at(&self, point: DropPoint, objects_received: u32) -> bool {
+        self.drop_point == Some(point) && objects_received >= self.drop_after
+    }
+}
+
+/// Called with the outcome of every received object (stored, quarantined, rejected, failed)
+#[derive(Clone)]
+pub struct ObjectCallback(pub Arc<dyn Fn(&LedgerRecord) + Send + Sync>);
+
+impl ObjectCallback {
+    pub fn new(callback: impl Fn(&LedgerRecord) + Send + Sync + 'static) -> Self {
+        Self(Arc::new(callback))
+    }
+}
+
+impl std::fmt::Debug for ObjectCallback {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ObjectCallback")
+    }
+}
+
+/// The data set of an object handed to a forwarder
+#[derive(Debug)]
+pub enum ForwardedDataset<'a> {
+    /// Held in memory, encoded as received
+    Memory(&'a [u8]),
+    /// Spooled to this Part 10 file, being larger than the spool threshold
+    Spooled(&'a Path),
+}
+
+/// An accepted object handed to a forwarder instead of being stored
 #[derive(Debug)]
-struct DicomTransfer {
-    command_received: bool,
-    dataset_chunks: Vec<Vec<u8>>,
-    total_bytes: usize,
+pub struct ForwardedObject<'a> {
+    pub calling_ae: &'a str,
+    /// As the peer asked for it, which may differ from the receiver's own
+    pub called_ae: &'a str,
+    pub sop_class_uid: &'a str,
+    pub sop_instance_uid: &'a str,
+    pub transfer_syntax_uid: &'a str,
+    /// The parsed data set, if it could be parsed; a spooled one only up to its pixel data
+    pub object: Option<&'a InMemDicomObject>,
+    pub dataset: ForwardedDataset<'a>,
+}
+
+/// Takes every accepted object in place of the output directory and returns
+/// the status to answer its C-STORE with; called on the association's thread,
+/// so it may block until the object has gone on
+#[derive(Clone)]
+pub struct ObjectForwarder(pub Arc<dyn Fn(&ForwardedObject) -> u16 + Send + Sync>);
+
+impl ObjectForwarder {
+    pub fn new(forward: impl Fn(&ForwardedObject) -> u16 + Send + Sync + 'static) -> Self {
+        Self(Arc::new(forward))
+    }
+}
+
+impl std::fmt::Debug for ObjectForwarder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ObjectForwarder")
+    }
+}
+
+/// The C-STORE request a response answers
+#[derive(Debug, Clone)]
+struct StoreRequest {
     presentation_context_id: u8,
-    started_at: chrono::DateTime<Utc>,
+    message_id: Option<u16>,
+    sop_class_uid: Option<String>,
+    sop_instance_uid: Option<String>,
 }
 
-impl DicomTransfer {
-    fn new(presentation_context_id: u8) -> Self {
+impl StoreRequest {
+    fn of(transfer: &DicomTransfer) -> Self {
         Self {
-            command_received: false,
-            dataset_chunks: Vec::new(),
-            total_bytes: 0,
-            presentation_context_id,
-            started_at: Utc::now(),
+            presentation_context_id: transfer.presentation_context_id,
+            message_id: transfer.message_id,
+            sop_class_uid: transfer.sop_class_uid.clone(),
+            sop_instance_uid: transfer.sop_instance_uid.clone(),
         }
     }
+}
 
-    fn add_chunk(&mut self, data: Vec<u8>) {
-        self.total_bytes += data.len();
-        self.dataset_chunks.push(data);
-    }
+/// When the receiver refuses new objects, e.g. during storage maintenance;
+/// C-ECHO is answered throughout
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReadOnly {
+    Always,
+    /// While this file exists, so maintenance can start and end without a restart
+    WhileFileExists(PathBuf),
+}
 
-    fn reconstruct_dataset(&self) -> Vec<u8> {
-        let mut dataset = Vec::with_capacity(self.total_bytes);
-        for chunk in &self.dataset_chunks {
-            dataset.extend_from_slice(chunk);
+impl ReadOnly {
+    pub fn is_active(&self) -> bool {
+        match self {
+            ReadOnly::Always => true,
+            ReadOnly::WhileFileExists(path) => path.exists(),
         }
-        dataset
     }
 }
 
@@ -54,9 +200,67 @@ impl DicomTransfer {
 pub struct DicomReceiver {
     ae_title: String,
     output_dir: PathBuf,
-    sop_registry: Arc<SopClassRegistry>,
-    transfer_registry: Arc<TransferSyntaxRegistry>,
+    sop_registry: &'static SopClassRegistry,
+    transfer_registry: &'static TransferSyntaxRegistry,
     connection_semaphore: Arc<Semaphore>,
+    max_connections: usize,
+    shutdown: Arc<Shutdown>,
+    shutdown_grace: std::time::Duration,
+    /// Abort an association that receives nothing for this long
+    idle_timeout: Option<std::time::Duration>,
+    /// Abort an association still open after this long
+    max_association_duration: Option<std::time::Duration>,
+    validation_profiles: Option<Arc<ValidationProfiles>>,
+    iod_validation: bool,
+    lenient_repair: bool,
+    ledger: Option<Arc<std::sync::Mutex<Ledger>>>,
+    fault_injection: Option<FaultInjection>,
+    content_index: Option<Arc<std::sync::Mutex<ContentIndex>>>,
+    time_sanity: Option<TimeSanityPolicy>,
+    size_limits: SizeLimits,
+    byte_quotas: ByteQuotas,
+    byte_counters: Arc<ByteCounters>,
+    clock_warnings: Arc<std::sync::atomic::AtomicU64>,
+    /// Number files sequentially instead of by arrival time
+    deterministic: bool,
+    /// Name stored objects after their attributes
+    filename_template: Option<FilenameTemplate>,
+    ts_preference: Option<TransferSyntaxPreference>,
+    retired_policy: RetiredPolicy,
+    objects_stored: Arc<std::sync::atomic::AtomicU64>,
+    object_callback: Option<ObjectCallback>,
+    /// Hands accepted objects on instead of storing them, as a relay
+    forwarder: Option<ObjectForwarder>,
+    study_tracker: Option<Arc<std::sync::Mutex<StudyTracker>>>,
+    /// Record each stored instance with its attributes for metadata queries
+    receive_index: Option<Arc<std::sync::Mutex<Box<dyn MetadataStore>>>>,
+    /// Reported by the readiness probe; each listener counts as one component
+    readiness: Option<Arc<Readiness>>,
+    /// Requests answered, shared by every listener
+    metrics: Arc<ServiceMetrics>,
+    /// Export each presentation state with its images when its study completes
+    presentation_state_bundles: bool,
+    /// Notified of every study that completes
+    study_webhook: Option<Arc<StudyWebhook>>,
+    /// Instances stored so far, persisted per study, for resuming interrupted studies
+    received_instances: Option<Arc<std::sync::Mutex<ReceivedInstances>>>,
+    duplicate_policy: DuplicatePolicy,
+    compliance: CompliancePolicy,
+    /// Seal stored objects with AES-256-GCM
+    encryption: Option<Arc<StorageKey>>,
+    read_only: Option<ReadOnly>,
+    /// Port and configuration of the DICOM over TLS listener
+    tls: Option<(u16, Arc<rustls::ServerConfig>)>,
+    /// Whether to listen for plaintext connections as well
+    plaintext: bool,
+    /// Address the listeners bind to
+    bind_address: std::net::IpAddr,
+    /// Refuse IPv4 peers on an IPv6 address instead of listening dual-stack
+    ipv6_only: bool,
+    /// Size beyond which a data set is spooled to disk instead of held in memory
+    spool_threshold: u64,
+    /// The only SOP classes accepted, when restricted
+    sop_classes: Option<Arc<Vec<String>>>,
 }
 
 impl DicomReceiver {
@@ -69,36 +273,192 @@ impl DicomReceiver {
         Self {
             ae_title,
             output_dir,
-            sop_registry: Arc::new(SopClassRegistry::new()),
-            transfer_registry: Arc::new(TransferSyntaxRegistry::new()),
+            sop_registry: SopClassRegistry::global(),
+            transfer_registry: TransferSyntaxRegistry::global(),
             connection_semaphore: Arc::new(Semaphore::new(max_connections)),
+            max_connections,
+            shutdown: Arc::new(Shutdown::default()),
+            shutdown_grace: DEFAULT_SHUTDOWN_GRACE,
+            idle_timeout: Some(DEFAULT_IDLE_TIMEOUT),
+            max_association_duration: None,
+            validation_profiles: None,
+            iod_validation: false,
+            lenient_repair: false,
+            ledger: None,
+            fault_injection: None,
+            content_index: None,
+            time_sanity: None,
+            size_limits: SizeLimits::default(),
+            byte_quotas: ByteQuotas::default(),
+            byte_counters: Arc::new(ByteCounters::new(chrono::Local::now().date_naive())),
+            clock_warnings: Arc::new(std::sync::atomic::AtomicU64::new(0)),
+            deterministic: false,
+            filename_template: None,
+            ts_preference: None,
+            retired_policy: RetiredPolicy::Accept,
+            objects_stored: Arc::new(std::sync::atomic::AtomicU64::new(0)),
+            object_callback: None,
+            forwarder: None,
+            study_tracker: None,
+            receive_index: None,
+            readiness: None,
+            metrics: Arc::new(ServiceMetrics::default()),
+            presentation_state_bundles: false,
+            study_webhook: None,
+            received_instances: None,
+            duplicate_policy: DuplicatePolicy::default(),
+            compliance: CompliancePolicy::lenient(),
+            encryption: None,
+            read_only: None,
+            tls: None,
+            plaintext: true,
+            bind_address: DEFAULT_BIND_ADDRESS,
+            ipv6_only: false,
+            spool_threshold: DEFAULT_SPOOL_THRESHOLD,
+            sop_classes: None,
         }
     }
 
-    pub async fn start(self: Arc<Self>, port: u16) -> Result<()> {
-        info!("📥  DICOM receiver listening on port {}", port);
-        println!("📥  DICOM receiver listening on port {}", port);
+    /// Validate every received object against modality-specific profiles before storing it
+    pub fn with_validation_profiles(mut self, profiles: ValidationProfiles) -> Self {
+        self.validation_profiles = Some(Arc::new(profiles));
+        self
+    }
 
-        // Start listening for connections
-        let listener = tokio::net::TcpListener::bind(format!("0.0.0.0:{}", port)).await?;
+    /// Serve associations until `shutdown` resolves, then drain them and return
+    pub async fn start(self: Arc<Self>, port: u16, shutdown: impl std::future::Future<Output = ()>) -> Result<()> {
+        let listen = |port: u16| -> Result<tokio::net::TcpListener> {
+            let listener = bind_listener(self.bind_address, port, self.ipv6_only)
+                .with_context(|| format!("Failed to listen on {}", std::net::SocketAddr::new(self.bind_address, port)))?;
+            Ok(tokio::net::TcpListener::from_std(listener)?)
+        };
+        let plaintext_listener = if self.plaintext {
+            let listener = listen(port)?;
+            info!("📥  DICOM receiver listening on {}", listener.local_addr()?);
+            println!("📥  DICOM receiver listening on {}", listener.local_addr()?);
+            Some(listener)
+        } else {
+            None
+        };
+        let tls_listener = match &self.tls {
+            Some((tls_port, config)) => {
+                let listener = listen(*tls_port)?;
+                info!("🔒  DICOM receiver listening for TLS on {}", listener.local_addr()?);
+                println!("🔒  DICOM receiver listening for TLS on {}", listener.local_addr()?);
+                Some((listener, Arc::clone(config)))
+            }
+            None => None,
+        };
         
         info!("✅  DICOM receiver ready to accept connections");
         println!("✅  DICOM receiver ready to accept connections");
 
+        if self.study_tracker.is_some() {
+            let receiver = Arc::clone(&self);
+            tokio::spawn(async move {
+                let mut interval = tokio::time::interval(STUDY_REPORT_POLL);
+                loop {
+                    interval.tick().await;
+                    receiver.write_completed_study_reports();
+                }
+            });
+        }
+
+        let mut accepting = tokio::task::JoinSet::new();
+        if let Some(listener) = plaintext_listener {
+            accepting.spawn(Arc::clone(&self).accept_connections(listener, None));
+        }
+        if let Some((tls_listener, config)) = tls_listener {
+            accepting.spawn(Arc::clone(&self).accept_connections(tls_listener, Some(config)));
+        }
+        if accepting.is_empty() {
+            anyhow::bail!("Neither a plaintext nor a TLS listener is enabled");
+        }
+        if let Some(readiness) = &self.readiness {
+            readiness.component_ready();
+        }
+
+        tokio::select! {
+            Some(result) = accepting.join_next() => result.context("Listener task failed")?,
+            () = shutdown => {
+                if let Some(readiness) = &self.readiness {
+                    readiness.drain();
+                }
+                // Dropping the listeners refuses new associations
+                accepting.shutdown().await;
+                self.drain().await;
+                Ok(())
+            }
+        }
+    }
+
+    /// Let the associations in progress finish their C-STOREs within the
+    /// grace period, then close whatever is left
+    async fn drain(&self) {
+        let busy = self.shutdown.request();
+        info!("🛑  Shutting down: no longer accepting associations, {} busy receiving", busy);
+        println!("🛑  Shutting down: waiting up to {:?} for {} association(s) receiving objects", self.shutdown_grace, busy);
+
+        // Every permit is back once every association has ended
+        let all = u32::try_from(self.max_connections).unwrap_or(u32::MAX);
+        if tokio::time::timeout(self.shutdown_grace, self.connection_semaphore.acquire_many(all)).await.is_ok() {
+            info!("✅  All associations drained");
+            println!("✅  All associations drained");
+            return;
+        }
+
+        let remaining = self.shutdown.force();
+        warn!("⏱️  Grace period over, closing {} association(s); incomplete objects are kept as partial files", remaining);
+        println!("⏱️  Grace period over, closing {} association(s)", remaining);
+        if tokio::time::timeout(FORCED_SHUTDOWN_WAIT, self.connection_semaphore.acquire_many(all)).await.is_err() {
+            warn!("⚠️  Associations still open after closing them, exiting anyway");
+        }
+    }
+
+    /// Accept connections until the process ends; with `tls`, each is handshaken
+    /// and then handled on the plaintext end of its relay.
+    ///
+    /// Every association gets a thread of its own once a slot is free, so
+    /// `max_connections` bounds the number of threads. A slot is taken after
+    /// a connection is accepted, so only live associations hold one: a
+    /// listener with nothing to accept does not take slots from the others.
+    /// While every slot is taken, each listener holds the connection it
+    /// accepted and leaves further ones in the listen backlog.
+    async fn accept_connections(self: Arc<Self>, listener: tokio::net::TcpListener, tls: Option<Arc<rustls::ServerConfig>>) -> Result<()> {
         loop {
             match listener.accept().await {
                 Ok((stream, addr)) => {
+                    let permit = Arc::clone(&self.connection_semaphore).acquire_owned().await?;
                     info!("🔗  New connection from {}", addr);
                     println!("🔗  New connection from {}", addr);
                     
+                    let stream = match stream.into_std() {
+                        Ok(stream) => stream,
+                        Err(e) => {
+                            error!("❌  Failed to take over the connection from {}: {}", addr, e);
+                            continue;
+                        }
+                    };
                     let receiver = Arc::clone(&self);
+                    let tls = tls.clone();
                     
-                    tokio::task::spawn_blocking(move || {
-                        if let Err(e) = Self::handle_connection_blocking(receiver, stream, addr) {
-                            error!("❌  Error handling connection from {}: {}", addr, e);
-                            println!("❌  Error handling connection from {}: {}", addr, e);
-                        }
-                    });
+                    let spawned = std::thread::Builder::new()
+                        .name(format!("association-{}", addr))
+                        .spawn(move || {
+                            let _permit = permit;
+                            let stream = match tls {
+                                Some(config) => terminate(config, stream),
+                                None => Ok(stream),
+                            };
+                            if let Err(e) = stream.and_then(|stream| Self::handle_connection_blocking(receiver, stream, addr)) {
+                                error!("❌  Error handling connection from {}: {}", addr, e);
+                                println!("❌  Error handling connection from {}: {}", addr, e);
+                            }
+                        });
+                    if let Err(e) = spawned {
+                        error!("❌  Failed to start a thread for the connection from {}: {}", addr, e);
+                        println!("❌  Failed to start a thread for the connection from {}: {}", addr, e);
+                    }
                 }
                 Err(e) => {
                     error!("❌  Failed to accept connection: {}", e);
@@ -108,207 +468,749 @@ impl DicomReceiver {
         }
     }
 
+    /// Negotiate the association and serve it until it ends, on the thread
+    /// dedicated to this connection
     fn handle_connection_blocking(
         receiver: Arc<Self>, 
-        stream: tokio::net::TcpStream, 
+        std_stream: std::net::TcpStream, 
         addr: std::net::SocketAddr
     ) -> Result<()> {
-        let rt = tokio::runtime::Handle::current();
-        
-        rt.block_on(async {
-            // Create server association options using shared/common SOP classes
-            let mut server_options = ServerAssociationOptions::new()
-                .accept_called_ae_title()
-                .ae_title(&receiver.ae_title)
-                .promiscuous(true); // Accept unknown abstract syntaxes for maximum compatibility
+        // Create server association options using shared/common SOP classes
+        // Accept unknown abstract syntaxes for maximum compatibility, unless restricted
+        let refuse_retired = receiver.retired_policy == RetiredPolicy::Refuse;
+        let mut server_options = ServerAssociationOptions::new()
+            .ae_title(&receiver.ae_title)
+            .promiscuous(receiver.sop_classes.is_none() && !refuse_retired);
+        if !receiver.compliance.rejects(ComplianceRule::CalledAe) {
+            server_options = server_options.accept_called_ae_title();
+        }
 
+        match &receiver.sop_classes {
+            Some(sop_classes) => {
+                for sop_class_uid in sop_classes.iter().filter(|uid| receiver.retired_policy.accepts_sop_class(uid)) {
+                    server_options = server_options.with_abstract_syntax(sop_class_uid.as_str());
+                }
+            }
             // Register all supported SOP classes from our shared registry
-            for sop_class_uid in receiver.sop_registry.get_all_uids() {
-                server_options = server_options.with_abstract_syntax(sop_class_uid);
+            None => {
+                for sop_class_uid in receiver.sop_registry.get_all_uids().into_iter().filter(|uid| receiver.retired_policy.accepts_sop_class(uid)) {
+                    server_options = server_options.with_abstract_syntax(sop_class_uid);
+                }
             }
-            
-            // Acquire semaphore permit for connection limiting
-            let _permit = receiver.connection_semaphore.acquire().await?;
+        }
+        server_options = server_options.with_abstract_syntax(VERIFICATION_SOP_CLASS);
 
-            info!("🔄  Handling connection from {}", addr);
-            
-            // Convert tokio stream to std stream for establish
-            let std_stream = stream.into_std()?;
-            
-            // Establish the association using the server options
-            let mut association = server_options.establish(std_stream)
-                .context("Failed to establish DICOM association")?;
+        info!("🔄  Handling connection from {}", addr);
+        let deadline = receiver.max_association_duration.map(|duration| std::time::Instant::now() + duration);
+        let _registration = receiver.shutdown.register(addr, &std_stream)
+            .context("Failed to track the connection for shutdown")?;
 
-            info!("✅  Association established with {}", addr);
-            println!("✅  Association established with {}", addr);
+        // The association request and its negotiation must be over within the idle timeout as a whole,
+        // however slowly the peer trickles them in
+        let negotiation_deadline = {
+            let limit = std::time::Instant::now() + receiver.idle_timeout.unwrap_or(NEGOTIATION_TIMEOUT);
+            deadline.map_or(limit, |deadline| deadline.min(limit))
+        };
 
-            // Log the accepted presentation contexts
-            for pc in association.presentation_contexts() {
-                info!("📋  Accepted presentation context {} with transfer syntax {}", pc.id, pc.transfer_syntax);
-                println!("📋  Accepted presentation context {} with transfer syntax {}", pc.id, pc.transfer_syntax);
+        // Read the association request ahead of negotiation to check it and steer transfer syntaxes;
+        // once it is complete, dicom-ul reads it without waiting on the peer
+        let association_rq = Self::peek_association_rq(&std_stream, negotiation_deadline)
+            .with_context(|| format!("Could not read ahead association request from {}", addr))?;
+        // Forwarders see the called AE title the peer asked for, not the one we answer to
+        let called_ae = called_ae_title(&association_rq)
+            .unwrap_or_else(|| receiver.ae_title.trim().to_string());
+        if called_ae != receiver.ae_title.trim() {
+            // When rejected, dicom-ul refuses the association itself
+            receiver.deviation(ComplianceRule::CalledAe, addr,
+                               &format!("called AE title {} instead of {}", called_ae, receiver.ae_title));
+        }
+        let contexts = match parse_association_rq(&association_rq) {
+            Ok(contexts) => contexts,
+            Err(e) => {
+                if receiver.deviation(ComplianceRule::MalformedAssociation, addr, &format!("malformed association request: {}", e)) {
+                    anyhow::bail!("Refused malformed association request from {}: {}", addr, e);
+                }
+                Vec::new()
             }
+        };
+        // Without promiscuous mode, unknown SOP classes are accepted by listing what the peer proposes
+        if refuse_retired && receiver.sop_classes.is_none() {
+            for context in contexts.iter().filter(|context| receiver.retired_policy.accepts_sop_class(&context.abstract_syntax)) {
+                server_options = server_options.with_abstract_syntax(context.abstract_syntax.clone());
+            }
+        }
 
-            // Clone receiver for use in the blocking task
-            let receiver_clone = receiver.clone();
-            
-            // Handle incoming requests with longer timeout and more robust error handling
-            let _handle_result = tokio::task::spawn_blocking(move || {
-                debug!("🔄  Starting PDU receive loop...");
-                println!("🔄  Starting PDU receive loop...");
-                
-                // Add a small delay to ensure proper connection setup
-                std::thread::sleep(std::time::Duration::from_millis(100));
-                
-                let mut transfers: HashMap<u8, DicomTransfer> = HashMap::new();
-                let mut pdu_count = 0;
-                
-                loop {
-                    pdu_count += 1;
-                    debug!("📡  Waiting for PDU #{}", pdu_count);
-                    println!("📡  Waiting for PDU #{}", pdu_count);
-                    
-                    match association.receive() {
-                        Ok(pdu) => {
-                            debug!("📦  Received PDU #{}: {:?}", pdu_count, std::mem::discriminant(&pdu));
-                            println!("📦  Received PDU #{}: {:?}", pdu_count, std::mem::discriminant(&pdu));
-                            
-                            match pdu {
-                                Pdu::PData { data } => {
-                                    info!("📥  Received P-DATA with {} values", data.len());
-                                    println!("📥  Received P-DATA with {} values", data.len());
-                                    
-                                    for (i, pdata_value) in data.iter().enumerate() {
-                                        println!("  PDU Value {}: {:?}, {} bytes", i+1, pdata_value.value_type, pdata_value.data.len());
-                                        
-                                        let pc_id = pdata_value.presentation_context_id;
-                                        
-                                        // Get or create transfer for this presentation context
-                                        let transfer = transfers.entry(pc_id).or_insert_with(|| DicomTransfer::new(pc_id));
-                                        
-                                        match pdata_value.value_type {
-                                            PDataValueType::Command => {
-                                                debug!("📝  Received command data: {} bytes", pdata_value.data.len());
-                                                println!("📝  Command PDU: {} bytes", pdata_value.data.len());
-                                                transfer.command_received = true;
+        // A peer that stalls during negotiation or afterwards must not hold the slot forever
+        let socket = std_stream.try_clone().context("Failed to clone the connection for its timeouts")?;
+        let negotiation_left = negotiation_deadline.saturating_duration_since(std::time::Instant::now())
+            .max(std::time::Duration::from_millis(1));
+        socket.set_read_timeout(Some(negotiation_left))?;
+        socket.set_write_timeout(Some(negotiation_left))?;
+
+        // Steer each presentation context to our preferred transfer syntax
+        let mut preferred = HashMap::new();
+        let supported = |uid: &str| receiver.transfer_registry.codec_support(uid).dataset
+            && !(refuse_retired && receiver.transfer_registry.is_retired(uid));
+        if let Some(preference) = &receiver.ts_preference {
+            for ts in preference.acceptor_list(&contexts, supported) {
+                server_options = server_options.with_transfer_syntax(ts);
+            }
+            for context in &contexts {
+                if let Some(ts) = preference.choose(&context.transfer_syntaxes, supported) {
+                    preferred.insert(context.id, ts.to_string());
+                }
+            }
+        } else if receiver.retired_policy != RetiredPolicy::Accept {
+            // Current syntaxes ahead of retired ones; the native ones keep the list from ever being empty,
+            // which would let dicom-ul fall back to its defaults
+            let mut acceptor_list = vec![ts::EXPLICIT_VR_LITTLE_ENDIAN.to_string(), ts::IMPLICIT_VR_LITTLE_ENDIAN.to_string()];
+            for context in &contexts {
+                if let Some(ts) = receiver.retired_policy.choose_transfer_syntax(&context.transfer_syntaxes, supported) {
+                    preferred.insert(context.id, ts.to_string());
+                    if !acceptor_list.iter().any(|listed| listed == ts) {
+                        acceptor_list.push(ts.to_string());
+                    }
+                }
+            }
+            for ts in acceptor_list {
+                server_options = server_options.with_transfer_syntax(ts);
+            }
+        }
+
+        // Establish the association using the server options
+        let association = server_options.establish(std_stream)
+            .context("Failed to establish DICOM association")?;
+        socket.set_read_timeout(receiver.idle_timeout)?;
+        socket.set_write_timeout(receiver.idle_timeout)?;
+
+        info!("✅  Association established with {}", addr);
+        println!("✅  Association established with {}", addr);
+
+        // Log the accepted presentation contexts
+        for pc in association.presentation_contexts() {
+            info!("📋  Accepted presentation context {} with transfer syntax {}", pc.id, pc.transfer_syntax);
+            println!("📋  Accepted presentation context {} with transfer syntax {}", pc.id, pc.transfer_syntax);
+            if let Some(ts) = preferred.get(&pc.id).filter(|ts| **ts != pc.transfer_syntax.trim_end_matches('\0')) {
+                warn!("⚠️  Presentation context {} negotiated {} instead of preferred {}", pc.id, pc.transfer_syntax, ts);
+            }
+        }
+
+        Self::receive_pdus(&receiver, association, addr, &called_ae, &contexts, &socket, deadline)?;
+
+        info!("📡  Association closed with {}", addr);
+        println!("📡  Association closed with {}", addr);
+
+        Ok(())
+    }
+
+    /// Receive PDUs on an established association and answer its requests until
+    /// it is released or lost, or until it idles on `socket` for the idle timeout
+    /// or outlives `deadline`
+    fn receive_pdus(
+        receiver: &Self,
+        mut association: dicom_ul::association::ServerAssociation<std::net::TcpStream>,
+        addr: std::net::SocketAddr,
+        called_ae: &str,
+        contexts: &[ProposedContext],
+        socket: &std::net::TcpStream,
+        deadline: Option<std::time::Instant>,
+    ) -> Result<()> {
+        debug!("🔄  Starting PDU receive loop...");
+        println!("🔄  Starting PDU receive loop...");
+
+        // Add a small delay to ensure proper connection setup
+        std::thread::sleep(std::time::Duration::from_millis(100));
+
+        let mut transfers = Transfers::default();
+        let mut pdu_count = 0;
+        let mut objects_received = 0u32;
+        let mut association_bytes = 0u64;
+        let faults = receiver.fault_injection.clone();
+        let transfer_syntaxes: HashMap<u8, String> = association.presentation_contexts().iter()
+            .map(|pc| (pc.id, pc.transfer_syntax.trim_end_matches('\0').to_string()))
+            .collect();
+
+        if faults.as_ref().is_some_and(|f| f.drops_at(DropPoint::Association, objects_received)) {
+            warn!("💥  Fault injection: aborting association with {} after negotiation", addr);
+            println!("💥  Fault injection: aborting association after negotiation");
+            let _ = association.abort();
+            return Ok(());
+        }
+
+        loop {
+            // Between objects is the moment to leave when the receiver shuts down
+            let idle = transfers.is_empty();
+            if receiver.shutdown.set_busy(addr, !idle) && idle {
+                info!("🛑  Shutting down: aborting association with {} after its last object", addr);
+                println!("🛑  Shutting down: aborting association with {}", addr);
+                let _ = association.abort();
+                break;
+            }
+
+            // Wait no longer than the idle timeout, nor past the end of the association's time
+            let remaining = deadline.map(|deadline| deadline.saturating_duration_since(std::time::Instant::now()));
+            if remaining.is_some_and(|remaining| remaining.is_zero()) {
+                warn!("⏱️  Association with {} exceeded its maximum duration, aborting", addr);
+                println!("⏱️  Association exceeded its maximum duration, aborting");
+                receiver.save_pending_transfers(transfers.take_pending(), &transfer_syntaxes);
+                let _ = association.abort();
+                break;
+            }
+            let read_timeout = match (receiver.idle_timeout, remaining) {
+                (Some(idle), Some(remaining)) => Some(idle.min(remaining)),
+                (idle, remaining) => idle.or(remaining),
+            };
+            if let Err(e) = socket.set_read_timeout(read_timeout) {
+                warn!("⚠️  Could not set the read timeout for {}: {}", addr, e);
+            }
+
+            pdu_count += 1;
+            debug!("📡  Waiting for PDU #{}", pdu_count);
+            println!("📡  Waiting for PDU #{}", pdu_count);
+
+            match association.receive() {
+                Ok(pdu) => {
+                    debug!("📦  Received PDU #{}: {:?}", pdu_count, std::mem::discriminant(&pdu));
+                    println!("📦  Received PDU #{}: {:?}", pdu_count, std::mem::discriminant(&pdu));
+
+                    match pdu {
+                        Pdu::PData { data } => {
+                            info!("📥  Received P-DATA with {} values", data.len());
+                            println!("📥  Received P-DATA with {} values", data.len());
+
+                            let mut drop_before_response = false;
+                            // C-STORE operations whose data set completed in this P-DATA, each answered once
+                            let mut completed: Vec<(StoreRequest, u16)> = Vec::new();
+
+                            for (i, pdata_value) in data.iter().enumerate() {
+                                println!("  PDU Value {}: {:?}, {} bytes", i+1, pdata_value.value_type, pdata_value.data.len());
+
+                                let pc_id = pdata_value.presentation_context_id;
+
+                                if !association.presentation_contexts().iter().any(|pc| pc.id == pc_id)
+                                    && receiver.deviation(ComplianceRule::UnknownContext, addr,
+                                                                &format!("P-DATA on presentation context {}, which was not accepted", pc_id)) {
+                                    receiver.save_pending_transfers(transfers.take_pending(), &transfer_syntaxes);
+                                    let _ = association.abort();
+                                    return Ok(());
+                                }
+
+                                match pdata_value.value_type {
+                                    PDataValueType::Command => {
+                                        debug!("📝  Received command data: {} bytes", pdata_value.data.len());
+                                        println!("📝  Command PDU: {} bytes", pdata_value.data.len());
+                                        let command = match decode_command(&pdata_value.data) {
+                                            Ok(command) if command.command_field == C_ECHO_RQ => {
+                                                let message_id = command.message_id.unwrap_or(0);
+                                                info!("🔔  C-ECHO request {} from {}", message_id, association.client_ae_title());
+                                                println!("🔔  C-ECHO request from {}", association.client_ae_title());
+                                                if let Err(e) = Self::send_c_echo_response(&mut association, pc_id, message_id) {
+                                                    error!("❌  Failed to send C-ECHO response: {}", e);
+                                                    println!("❌  Failed to send C-ECHO response: {}", e);
+                                                } else if association.client_ae_title() != SELF_TEST_AE {
+                                                    receiver.metrics.echo_answered();
+                                                }
+                                                continue;
                                             }
-                                            PDataValueType::Data => {
-                                                info!("📦  Received dataset chunk: {} bytes", pdata_value.data.len());
-                                                println!("📦  Dataset chunk: {} bytes", pdata_value.data.len());
-                                                
-                                                // Add this chunk to the transfer
-                                                transfer.add_chunk(pdata_value.data.clone());
-                                                
-                                                // If this is the last chunk (is_last flag), reconstruct the file
-                                                if pdata_value.is_last {
-                                                    let complete_dataset = transfer.reconstruct_dataset();
-                                                    info!("✅  Completed dataset reconstruction: {} bytes from {} chunks", 
-                                                          complete_dataset.len(), transfer.dataset_chunks.len());
-                                                    println!("✅  Completed dataset: {} bytes from {} chunks", 
-                                                             complete_dataset.len(), transfer.dataset_chunks.len());
-                                                    
-                                                    // Save the complete reconstructed DICOM file
-                                                    let filename = format!("received_{}_{}.dcm", 
-                                                                          transfer.started_at.format("%Y%m%d_%H%M%S_%f"),
-                                                                          pc_id);
-                                                    let file_path = receiver_clone.output_dir.join(filename);
-                                                    
-                                                    if let Err(e) = std::fs::write(&file_path, &complete_dataset) {
-                                                        error!("❌  Failed to save complete dataset: {}", e);
-                                                        println!("❌  Failed to save complete dataset: {}", e);
-                                                    } else {
-                                                        info!("✅  Saved complete DICOM file to {}", file_path.display());
-                                                        println!("✅  Saved complete DICOM file to {}", file_path.display());
-                                                    }
-                                                    
-                                                    // Clean up this transfer
-                                                    transfers.remove(&pc_id);
+                                            Ok(command) => Some(command),
+                                            Err(e) => {
+                                                if receiver.deviation(ComplianceRule::MalformedCommand, addr,
+                                                                            &format!("command on presentation context {}: {}", pc_id, e)) {
+                                                    receiver.save_pending_transfers(transfers.take_pending(), &transfer_syntaxes);
+                                                    let _ = association.abort();
+                                                    return Ok(());
+                                                }
+                                                None
+                                            }
+                                        };
+                                        let mut transfer = DicomTransfer::new(pc_id);
+                                        if let Some(command) = command {
+                                            info!("📝  C-STORE request {} for {} ({})", command.message_id.unwrap_or(0),
+                                                  command.affected_sop_instance_uid.as_deref().unwrap_or("unknown instance"),
+                                                  command.affected_sop_class_uid.as_deref().unwrap_or("unknown SOP class"));
+                                            let abstract_syntax = contexts.iter().find(|c| c.id == pc_id).map(|c| c.abstract_syntax.as_str());
+                                            if let (Some(sop_class_uid), Some(abstract_syntax)) = (command.affected_sop_class_uid.as_deref(), abstract_syntax) {
+                                                if sop_class_uid != abstract_syntax {
+                                                    warn!("🚫  C-STORE of {} on presentation context {} negotiated for {}, refusing",
+                                                          sop_class_uid, pc_id, abstract_syntax);
+                                                    println!("🚫  C-STORE of {} on a presentation context for {}, refusing", sop_class_uid, abstract_syntax);
+                                                    // Refused: SOP Class not supported
+                                                    transfer.refused = Some(0x0122);
                                                 }
                                             }
+                                            transfer.message_id = command.message_id;
+                                            transfer.sop_class_uid = command.affected_sop_class_uid;
+                                            transfer.sop_instance_uid = command.affected_sop_instance_uid;
+                                            if let Some(status) = receiver.resumed_status(transfer.sop_instance_uid.as_deref())
+                                                .filter(|_| transfer.refused.is_none()) {
+                                                info!("⏩  {} from {} is already stored, answering 0x{:04X} without storing it again",
+                                                      transfer.sop_instance_uid.as_deref().unwrap_or_default(), association.client_ae_title(), status);
+                                                println!("⏩  Already stored, skipping its data set");
+                                                transfer.refused = Some(status);
+                                                transfer.already_stored = true;
+                                            }
+                                        }
+                                        if let Some(interrupted) = transfers.begin(transfer) {
+                                            let rejected = receiver.deviation(ComplianceRule::InterruptedDataSet, addr,
+                                                                                    &format!("new command on presentation context {} before its data set was complete", pc_id));
+                                            receiver.save_pending_transfers(vec![interrupted], &transfer_syntaxes);
+                                            if rejected {
+                                                receiver.save_pending_transfers(transfers.take_pending(), &transfer_syntaxes);
+                                                let _ = association.abort();
+                                                return Ok(());
+                                            }
                                         }
                                     }
-                                    
-                                    // Send a simple C-STORE response after receiving any P-DATA
-                                    if let Err(e) = receiver_clone.send_c_store_response(&mut association, &data) {
-                                        error!("❌  Failed to send C-STORE response: {}", e);
-                                        println!("❌  Failed to send C-STORE response: {}", e);
-                                    } else {
-                                        info!("✅  Sent C-STORE response");
-                                        println!("✅  Sent C-STORE response");
-                                    }
-                                }
-                                Pdu::ReleaseRQ => {
-                                    info!("📤  Received release request from {}", addr);
-                                    println!("📤  Received release request from {}", addr);
-                                    if let Err(e) = association.send(&Pdu::ReleaseRP) {
-                                        error!("❌  Failed to send release response: {}", e);
-                                    } else {
-                                        info!("✅  Sent release response to {}", addr);
-                                        println!("✅  Sent release response to {}", addr);
+                                    PDataValueType::Data => {
+                                        let transfer = transfers.get_mut(pc_id);
+                                        if !transfer.command_received && !transfer.has_data() && transfer.refused.is_none()
+                                            && receiver.deviation(ComplianceRule::DataWithoutCommand, addr,
+                                                                        &format!("data set on presentation context {} without a command", pc_id)) {
+                                            receiver.save_pending_transfers(transfers.take_pending(), &transfer_syntaxes);
+                                            let _ = association.abort();
+                                            return Ok(());
+                                        }
+                                        if !transfer.has_data()
+                                            && faults.as_ref().is_some_and(|f| f.drops_at(DropPoint::Dataset, objects_received)) {
+                                            warn!("💥  Fault injection: aborting association with {} mid-dataset", addr);
+                                            println!("💥  Fault injection: aborting association mid-dataset");
+                                            let _ = association.abort();
+                                            return Ok(());
+                                        }
+
+                                        info!("📦  Received dataset chunk: {} bytes", pdata_value.data.len());
+                                        println!("📦  Dataset chunk: {} bytes", pdata_value.data.len());
+
+                                        if transfer.refused.is_none() && receiver.read_only.as_ref().is_some_and(ReadOnly::is_active) {
+                                            warn!("🔒  Read-only mode: refusing object from {}", association.client_ae_title());
+                                            println!("🔒  Read-only mode: refusing object");
+                                            // Refused: Out of Resources
+                                            transfer.refused = Some(0xA700);
+                                        }
+
+                                        if transfer.refused.is_none() {
+                                            if let Err(exceeded) = receiver.byte_counters.charge(
+                                                &mut association_bytes,
+                                                pdata_value.data.len() as u64,
+                                                chrono::Local::now().date_naive(),
+                                                &receiver.byte_quotas,
+                                            ) {
+                                                warn!("🚫  Refusing object from {}: {}", association.client_ae_title(), exceeded);
+                                                println!("🚫  Refusing object: {}", exceeded);
+                                                // Refused: Out of Resources
+                                                transfer.refused = Some(0xA700);
+                                                transfer.discard();
+                                            }
+                                        }
+
+                                        // Add this chunk to the transfer, unless the object was already refused
+                                        if transfer.refused.is_none() {
+                                            if let Err(e) = transfer.add_chunk(pdata_value.data.clone()) {
+                                                error!("❌  Failed to spool dataset chunk: {}", e);
+                                                println!("❌  Failed to spool dataset chunk: {}", e);
+                                                // Refused: Out of Resources
+                                                transfer.refused = Some(0xA700);
+                                                transfer.discard();
+                                            }
+
+                                            let limit = transfer.sop_class_uid.as_deref()
+                                                .and_then(|uid| receiver.size_limits.limit_for(uid, receiver.sop_registry))
+                                                .or(receiver.size_limits.default_limit);
+                                            if let Some(limit) = limit.filter(|limit| transfer.refused.is_none() && transfer.total_bytes as u64 > *limit) {
+                                                warn!("🚫  Object of SOP class {} exceeds size limit of {} bytes, discarding",
+                                                      transfer.sop_class_uid.as_deref().unwrap_or("unknown"), limit);
+                                                println!("🚫  Object exceeds size limit of {} bytes, discarding", limit);
+                                                // Refused: Out of Resources
+                                                transfer.refused = Some(0xA700);
+                                                transfer.discard();
+                                            }
+                                        }
+
+                                        // Large objects go to disk as they arrive instead of accumulating in memory
+                                        if transfer.refused.is_none() && transfer.spool.is_none() && transfer.total_bytes as u64 > receiver.spool_threshold {
+                                            let ts_uid = transfer_syntaxes.get(&pc_id).map(String::as_str).unwrap_or_default();
+                                            let header = FileMeta {
+                                                media_storage_sop_class_uid: transfer.sop_class_uid.as_deref().unwrap_or_default(),
+                                                media_storage_sop_instance_uid: transfer.sop_instance_uid.as_deref().unwrap_or_default(),
+                                                transfer_syntax_uid: ts_uid,
+                                                source_ae_title: Some(association.client_ae_title()),
+                                            }.encode();
+                                            match transfer.spool_to(&receiver.output_dir, &header, receiver.encryption.clone()) {
+                                                Ok(()) => debug!("Spooling object on presentation context {} to disk after {} bytes", pc_id, transfer.total_bytes),
+                                                Err(e) => {
+                                                    error!("❌  Failed to spool dataset to disk: {}", e);
+                                                    println!("❌  Failed to spool dataset to disk: {}", e);
+                                                    // Refused: Out of Resources
+                                                    transfer.refused = Some(0xA700);
+                                                    transfer.discard();
+                                                }
+                                            }
+                                        }
+
+                                        if let Some(status) = transfer.refused.filter(|_| pdata_value.is_last) {
+                                            completed.push((StoreRequest::of(transfer), status));
+                                            receiver.record_object(association.client_ae_title(), None, transfer.total_bytes,
+                                                                         if transfer.already_stored { "duplicate" } else { "rejected" });
+                                            transfers.finish(pc_id);
+                                            continue;
+                                        }
+
+                                        // If this is the last chunk (is_last flag), reconstruct the file
+                                        if pdata_value.is_last {
+                                            let mut response_status = 0x0000u16;
+                                            // A spooled object is already on disk; only its attributes up to the pixel data are read
+                                            let mut spool = transfer.spool.take();
+                                            let spooled = spool.is_some();
+                                            let mut complete_dataset = transfer.reconstruct_dataset();
+                                            info!("✅  Completed dataset reconstruction: {} bytes from {} chunks{}",
+                                                  transfer.total_bytes, transfer.chunks, if spooled { ", spooled to disk" } else { "" });
+                                            println!("✅  Completed dataset: {} bytes from {} chunks",
+                                                     transfer.total_bytes, transfer.chunks);
+
+                                            let mut ts_uid = transfer_syntaxes.get(&pc_id).cloned().unwrap_or_default();
+
+                                            // Normalize retired Explicit VR Big Endian objects on ingest, and
+                                            // other retired syntaxes with native pixel data under --retired convert
+                                            let ts_name = receiver.transfer_registry.get_name(&ts_uid).unwrap_or("retired transfer syntax");
+                                            if receiver.retired_policy.converts(&ts_uid) && !spooled {
+                                                match Self::parse_dataset(&complete_dataset, &ts_uid)
+                                                    .and_then(|obj| Self::encode_dataset(&obj, ts::EXPLICIT_VR_LITTLE_ENDIAN)) {
+                                                    Ok(encoded) => {
+                                                        info!("🔄  Converted {} dataset to Explicit VR Little Endian", ts_name);
+                                                        println!("🔄  Converted {} dataset to Explicit VR Little Endian", ts_name);
+                                                        complete_dataset = encoded;
+                                                        ts_uid = ts::EXPLICIT_VR_LITTLE_ENDIAN.to_string();
+                                                    }
+                                                    Err(e) => {
+                                                        warn!("⚠️  Could not convert {} dataset, storing as received: {}", ts_name, e);
+                                                    }
+                                                }
+                                            } else if receiver.retired_policy == RetiredPolicy::Convert && receiver.transfer_registry.is_retired(&ts_uid) {
+                                                warn!("⚠️  Storing {} dataset as received: {}", ts_name,
+                                                      if spooled { "spooled objects are not re-encoded" } else { "its pixel data cannot be decoded" });
+                                            }
+
+                                            objects_received += 1;
+
+                                            // Parse the data set and apply ingest validation, if configured
+                                            let mut target_dir = receiver.output_dir.clone();
+                                            let mut rejected = false;
+                                            let mut understood = true;
+                                            let mut warning_status = None;
+                                            let mut parsed = None;
+                                            let parsed_dataset = match &mut spool {
+                                                Some(spool) => spool.finish().map_err(anyhow::Error::from)
+                                                    .and_then(|path| Self::parse_spooled(path, receiver.encryption.as_deref())),
+                                                None => Self::parse_dataset(&complete_dataset, &ts_uid),
+                                            };
+                                            match parsed_dataset {
+                                                Ok(mut obj) => {
+                                                    // Repairs are written back by re-encoding the whole data set, which a spooled object is not held as
+                                                    if receiver.lenient_repair && !spooled {
+                                                        let repairs = repair_dataset(&mut obj);
+                                                        for repair in &repairs {
+                                                            warn!("🔧  Repaired {}", repair);
+                                                        }
+                                                        if !repairs.is_empty() {
+                                                            println!("🔧  Applied {} repair(s) to received object", repairs.len());
+                                                            match Self::encode_dataset(&obj, &ts_uid) {
+                                                                Ok(encoded) => {
+                                                                    complete_dataset = encoded;
+                                                                    // Warning: Coercion of Data Elements
+                                                                    warning_status = Some(0xB000);
+                                                                }
+                                                                Err(e) => error!("❌  Failed to re-encode repaired dataset: {}", e),
+                                                            }
+                                                        }
+                                                    }
+
+                                                    if receiver.iod_validation && !spooled {
+                                                        if let Some(report) = validate_iod(&obj) {
+                                                            for violation in &report.violations {
+                                                                warn!("⚠️  {} IOD: [{}] {} {} {:?}", report.iod, violation.module,
+                                                                      violation.tag, violation.keyword, violation.kind);
+                                                            }
+                                                            if !report.is_conformant() {
+                                                                println!("⚠️  {} violation(s) of {} IOD", report.violations.len(), report.iod);
+                                                                // Warning: Data Set does not match SOP Class
+                                                                warning_status.get_or_insert(0xB007);
+                                                            }
+                                                        }
+                                                    }
+
+                                                    if let Some(outcome) = receiver.validation_profiles.as_ref()
+                                                        .and_then(|profiles| profiles.validate(&obj)) {
+                                                        warn!("⚠️  {} object missing required attributes: {}",
+                                                              outcome.modality, outcome.missing.join(", "));
+                                                        println!("⚠️  {} object missing required attributes: {}",
+                                                                 outcome.modality, outcome.missing.join(", "));
+                                                        match outcome.action {
+                                                            ValidationAction::Warn => {
+                                                                warning_status.get_or_insert(0xB007);
+                                                            }
+                                                            ValidationAction::Quarantine => {
+                                                                target_dir = receiver.output_dir.join("quarantine");
+                                                                warning_status.get_or_insert(0xB007);
+                                                            }
+                                                            ValidationAction::Reject => {
+                                                                rejected = true;
+                                                            }
+                                                        }
+                                                    }
+                                                    if let Some(policy) = &receiver.time_sanity {
+                                                        let clock_warnings = check_timestamps(&obj, chrono::Local::now().naive_local(), policy);
+                                                        if !clock_warnings.is_empty() {
+                                                            let total = receiver.clock_warnings.fetch_add(1, std::sync::atomic::Ordering::Relaxed) + 1;
+                                                            for clock_warning in &clock_warnings {
+                                                                warn!("🕒  Suspicious timestamp from {}: {}", association.client_ae_title(), clock_warning);
+                                                            }
+                                                            println!("🕒  Suspicious timestamp from {}: {} ({} object(s) flagged so far)",
+                                                                     association.client_ae_title(), clock_warnings[0], total);
+                                                        }
+                                                    }
+
+                                                    if let Some(index) = receiver.content_index.as_ref().filter(|_| !spooled) {
+                                                        let duplicate = match index.lock() {
+                                                            Ok(mut index) => index.check(&obj),
+                                                            Err(poisoned) => poisoned.into_inner().check(&obj),
+                                                        };
+                                                        if let Some(duplicate) = duplicate {
+                                                            warn!("⚠️  {:?} content of {} duplicates {} (re-export under a new UID?)",
+                                                                  duplicate.kind, duplicate.sop_instance_uid, duplicate.original_sop_instance_uid);
+                                                            println!("⚠️  Duplicate {:?} content: {} matches {}",
+                                                                     duplicate.kind, duplicate.sop_instance_uid, duplicate.original_sop_instance_uid);
+                                                        }
+                                                    }
+
+                                                    parsed = Some(obj);
+                                                }
+                                                // Nothing to judge the data set by in a transfer syntax we cannot decode
+                                                Err(e) if !receiver.transfer_registry.codec_support(&ts_uid).dataset => {
+                                                    warn!("⚠️  Storing dataset unchecked: {}", e);
+                                                }
+                                                Err(e) => {
+                                                    warn!("⚠️  Could not parse dataset from {}: {}", association.client_ae_title(), e);
+                                                    println!("❌  Could not parse dataset: {}", e);
+                                                    understood = false;
+                                                }
+                                            }
+
+                                            let dataset_length = if spooled { transfer.total_bytes } else { complete_dataset.len() };
+                                            let injected_status = faults.as_ref().and_then(|f| f.status_for(objects_received));
+                                            if let Some(status) = injected_status {
+                                                warn!("💥  Fault injection: answering object {} with status 0x{:04X}", objects_received, status);
+                                                println!("💥  Fault injection: status 0x{:04X}", status);
+                                                response_status = status;
+                                            }
+                                            if faults.as_ref().is_some_and(|f| f.drops_at(DropPoint::Response, objects_received - 1)) {
+                                                drop_before_response = true;
+                                            }
+
+                                            if injected_status.is_some_and(|status| matches!(status & 0xF000, 0xA000 | 0xC000)) {
+                                                // Objects answered with an injected Refused/Error status are not stored
+                                                debug!("Discarding object answered with injected failure status");
+                                            } else if rejected {
+                                                // Error: Data Set does not match SOP Class
+                                                response_status = 0xA900;
+                                                error!("❌  Rejected object failing validation profile");
+                                                println!("❌  Rejected object failing validation profile");
+                                                receiver.record_object(association.client_ae_title(), parsed.as_ref(),
+                                                                             dataset_length, "rejected");
+                                            } else if !understood {
+                                                // Error: Cannot understand
+                                                response_status = 0xC000;
+                                                receiver.record_object(association.client_ae_title(), None, dataset_length, "rejected");
+                                            } else if let Some(status) = receiver.legal_hold_refusal(transfer.sop_instance_uid.as_deref()) {
+                                                response_status = status;
+                                                if status == 0x0111 {
+                                                    warn!("⚖️  Refused to overwrite {} from {}: its study is under legal hold",
+                                                          transfer.sop_instance_uid.as_deref().unwrap_or_default(), association.client_ae_title());
+                                                    println!("⚖️  Refused to overwrite an instance of a study under legal hold");
+                                                }
+                                                receiver.record_object(association.client_ae_title(), parsed.as_ref(),
+                                                                             dataset_length, "rejected");
+                                            } else if let Some(forwarder) = receiver.forwarder.as_ref().filter(|_| target_dir == receiver.output_dir) {
+                                                // Relayed instead of stored; a spooled object is read back from its spool file
+                                                let (sop_class_uid, sop_instance_uid) = Self::object_uids(transfer, parsed.as_ref());
+                                                let dataset = match &mut spool {
+                                                    Some(spool) => spool.finish().map(ForwardedDataset::Spooled),
+                                                    None => Ok(ForwardedDataset::Memory(&complete_dataset)),
+                                                };
+                                                response_status = match dataset {
+                                                    Ok(dataset) => (forwarder.0)(&ForwardedObject {
+                                                        calling_ae: association.client_ae_title(),
+                                                        called_ae,
+                                                        sop_class_uid: &sop_class_uid,
+                                                        sop_instance_uid: &sop_instance_uid,
+                                                        transfer_syntax_uid: &ts_uid,
+                                                        object: parsed.as_ref(),
+                                                        dataset,
+                                                    }),
+                                                    Err(e) => {
+                                                        error!("❌  Failed to finish spooled dataset: {}", e);
+                                                        // Refused: Out of Resources
+                                                        0xA700
+                                                    }
+                                                };
+                                                let forwarded = response_status == 0x0000 || (0xB000..=0xBFFF).contains(&response_status);
+                                                receiver.record_object(association.client_ae_title(), parsed.as_ref(), dataset_length,
+                                                                             if forwarded { "forwarded" } else { "failed" });
+                                            } else {
+                                                // Save the complete reconstructed DICOM file
+                                                let file_path = receiver.object_path(&target_dir, transfer, pc_id, parsed.as_ref());
+                                                let file_dir = file_path.parent().unwrap_or(target_dir.as_path());
+                                                if let Err(e) = std::fs::create_dir_all(file_dir) {
+                                                    error!("❌  Failed to create {}: {}", file_dir.display(), e);
+                                                }
+
+                                                let stored = match spool {
+                                                    Some(spool) => receiver.store_spooled(spool, &file_path),
+                                                    None => {
+                                                        let file = Self::part10_object(transfer, &ts_uid, parsed.as_ref(),
+                                                                                       Some(association.client_ae_title()), &complete_dataset);
+                                                        receiver.storage_bytes(&file).and_then(|bytes| write_atomically(&file_path, &bytes))
+                                                    }
+                                                };
+                                                if let Err(e) = stored {
+                                                    error!("❌  Failed to save complete dataset: {}", e);
+                                                    println!("❌  Failed to save complete dataset: {}", e);
+                                                    // Refused: Out of Resources
+                                                    response_status = 0xA700;
+                                                    receiver.record_object(association.client_ae_title(), parsed.as_ref(),
+                                                                                 dataset_length, "failed");
+                                                } else {
+                                                    info!("✅  Saved complete DICOM file to {}", file_path.display());
+                                                    println!("✅  Saved complete DICOM file to {}", file_path.display());
+                                                    if let Some(status) = warning_status.filter(|_| injected_status.is_none()) {
+                                                        response_status = status;
+                                                    }
+                                                    let status = if target_dir == receiver.output_dir { "stored" } else { "quarantined" };
+                                                    receiver.record_object(association.client_ae_title(), parsed.as_ref(),
+                                                                                 dataset_length, status);
+                                                    receiver.track_study(association.client_ae_title(), parsed.as_ref(),
+                                                                               &ts_uid, dataset_length, &file_path);
+                                                    receiver.index_object(association.client_ae_title(), parsed.as_ref(),
+                                                                                &ts_uid, dataset_length, &file_path);
+                                                    // A quarantined instance is received again when it is resent
+                                                    if target_dir == receiver.output_dir {
+                                                        receiver.remember_stored(transfer, parsed.as_ref(), &file_path);
+                                                    }
+                                                }
+                                            }
+
+                                            completed.push((StoreRequest::of(transfer), response_status));
+
+                                            // Clean up this transfer
+                                            transfers.finish(pc_id);
+                                        }
                                     }
-                                    break;
                                 }
-                                _ => {
-                                    debug!("Received other PDU type: {:?}", pdu);
+                            }
+
+                            if completed.is_empty() {
+                                continue;
+                            }
+
+                            if drop_before_response {
+                                warn!("💥  Fault injection: aborting association with {} instead of responding", addr);
+                                println!("💥  Fault injection: aborting association instead of responding");
+                                let _ = association.abort();
+                                return Ok(());
+                            }
+
+                            if let Some(delay) = faults.as_ref().map(|f| f.response_delay).filter(|d| !d.is_zero()) {
+                                debug!("Fault injection: delaying response by {:?}", delay);
+                                std::thread::sleep(delay);
+                            }
+
+                            for (request, status) in &completed {
+                                if let Err(e) = receiver.send_c_store_response(&mut association, request, *status) {
+                                    error!("❌  Failed to send C-STORE response: {}", e);
+                                    println!("❌  Failed to send C-STORE response: {}", e);
+                                } else {
+                                    info!("✅  Sent C-STORE response to message {} with status 0x{:04X}", request.message_id.unwrap_or(0), status);
+                                    println!("✅  Sent C-STORE response");
+                                    receiver.metrics.store_answered();
                                 }
                             }
                         }
-                        Err(e) => {
-                            error!("❌  Error receiving PDU: {}", e);
-                            println!("❌  Error receiving PDU: {}", e);
-                            
-                            // Log the error type for debugging
-                            debug!("Error type: {:?}", e);
-                            
-                            // Handle common error cases
-                            let error_string = e.to_string();
-                            if error_string.contains("EOF") || error_string.contains("UnexpectedEof") {
-                                info!("🔌  Connection closed by peer (EOF)");
-                                println!("🔌  Connection closed by peer (EOF)");
-                            } else if error_string.contains("Connection") {
-                                info!("🔌  Connection error from peer");
-                                println!("🔌  Connection error from peer");
+                        Pdu::ReleaseRQ => {
+                            info!("📤  Received release request from {}", addr);
+                            println!("📤  Received release request from {}", addr);
+                            let pending = transfers.take_pending();
+                            if !pending.is_empty() {
+                                warn!("⚠️  {} released the association with {} incomplete data set(s)", addr, pending.len());
+                                receiver.save_pending_transfers(pending, &transfer_syntaxes);
+                            }
+                            if let Some(delay) = faults.as_ref().map(|f| f.release_delay).filter(|d| !d.is_zero()) {
+                                warn!("💥  Fault injection: delaying release response by {:?}", delay);
+                                std::thread::sleep(delay);
+                            }
+                            if let Err(e) = association.send(&Pdu::ReleaseRP) {
+                                error!("❌  Failed to send release response: {}", e);
                             } else {
-                                error!("🔌  Unknown error: {}", e);
-                                println!("🔌  Unknown error: {}", e);
+                                info!("✅  Sent release response to {}", addr);
+                                println!("✅  Sent release response to {}", addr);
                             }
-                            
-                            // Save any pending transfers before closing
-                            for (pc_id, transfer) in transfers.iter() {
-                                if !transfer.dataset_chunks.is_empty() {
-                                    let complete_dataset = transfer.reconstruct_dataset();
-                                    info!("💾  Saving pending transfer: {} bytes from {} chunks", 
-                                          complete_dataset.len(), transfer.dataset_chunks.len());
-                                    println!("💾  Saving pending transfer: {} bytes from {} chunks", 
-                                             complete_dataset.len(), transfer.dataset_chunks.len());
-                                    
-                                    // Save the complete reconstructed DICOM file
-                                    let filename = format!("received_{}_{}.dcm", 
-                                                          transfer.started_at.format("%Y%m%d_%H%M%S_%f"),
-                                                          pc_id);
-                                    let file_path = receiver_clone.output_dir.join(filename);
-                                    
-                                    if let Err(e) = std::fs::write(&file_path, &complete_dataset) {
-                                        error!("❌  Failed to save pending dataset: {}", e);
-                                        println!("❌  Failed to save pending dataset: {}", e);
-                                    } else {
-                                        info!("✅  Saved pending DICOM file to {}", file_path.display());
-                                        println!("✅  Saved pending DICOM file to {}", file_path.display());
-                                    }
-                                }
+                            break;
+                        }
+                        Pdu::AbortRQ { .. } => {
+                            info!("🔌  Association aborted by {}", addr);
+                            println!("🔌  Association aborted by peer");
+                            receiver.save_pending_transfers(transfers.take_pending(), &transfer_syntaxes);
+                            break;
+                        }
+                        _ => {
+                            debug!("Received other PDU type: {:?}", pdu);
+                        }
+                    }
+                }
+                Err(e) => {
+                    // Log the error type for debugging
+                    debug!("Error type: {:?}", e);
+
+                    // Handle common error cases by the underlying I/O error, if any
+                    match io_error_kind(&e) {
+                        Some(std::io::ErrorKind::UnexpectedEof) => {
+                            info!("🔌  Connection closed by peer (EOF)");
+                            println!("🔌  Connection closed by peer (EOF)");
+                        }
+                        Some(std::io::ErrorKind::ConnectionReset | std::io::ErrorKind::ConnectionAborted
+                             | std::io::ErrorKind::BrokenPipe) => {
+                            info!("🔌  Connection error from peer: {}", e);
+                            println!("🔌  Connection error from peer");
+                        }
+                        Some(std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut) => {
+                            if deadline.is_some_and(|deadline| std::time::Instant::now() >= deadline) {
+                                warn!("⏱️  Association with {} exceeded its maximum duration, aborting", addr);
+                                println!("⏱️  Association exceeded its maximum duration, aborting");
+                            } else {
+                                warn!("⏱️  Nothing received from {} for {:?}, aborting", addr, read_timeout.unwrap_or_default());
+                                println!("⏱️  Association idle timeout, aborting");
                             }
-                            
+                            receiver.save_pending_transfers(transfers.take_pending(), &transfer_syntaxes);
+                            let _ = association.abort();
                             break;
                         }
+                        _ => {
+                            error!("❌  Error receiving PDU: {}", e);
+                            println!("❌  Error receiving PDU: {}", e);
+                        }
                     }
+
+                    // Save any pending transfers before closing
+                    receiver.save_pending_transfers(transfers.take_pending(), &transfer_syntaxes);
+
+                    break;
                 }
-                Ok::<(), anyhow::Error>(())
-            }).await??;
+            }
+        }
 
-            info!("📡  Association closed with {}", addr);
-            println!("📡  Association closed with {}", addr);
-            
-            Ok::<(), anyhow::Error>(())
-        })
+        let counters = receiver.byte_counters.snapshot();
+        info!("📊  Association from {} transferred {} bytes; {} bytes received today, {} since startup",
+              association.client_ae_title(), association_bytes, counters.bytes_today, counters.bytes_total);
+        println!("📊  Association bytes: {} (today: {})", association_bytes, counters.bytes_today);
+        Ok(())
     }
 
     async fn handle_pdata(&self, data: &[PDataValue]) -> Result<()> {
@@ -342,20 +1244,688 @@ impl DicomReceiver {
         Ok(())
     }
 
-    fn send_c_store_response(&self, association: &mut dicom_ul::association::ServerAssociation<std::net::TcpStream>, data: &[PDataValue]) -> Result<()> {
-        // Extract presentation context ID from the request
-        let pc_id = data.first().map(|pv| pv.presentation_context_id).unwrap_or(1);
-        
-        // Create a proper C-STORE response with DICOM status
-        // This is a minimal DIMSE C-STORE response indicating success
-        let response_data = vec![
-            // Group 0000 (Command Group)
-            0x00, 0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00, 0x38, 0x00, 0x00, 0x00, // Command Group Length (0000,0000) = 56 bytes
-            0x00, 0x00, 0x02, 0x00, 0x12, 0x00, 0x00, 0x00, 0x01, 0x80, 0x00, 0x00, // Affected SOP Class UID (0000,0002)
-            0x00, 0x00, 0x00, 0x01, 0x02, 0x00, 0x00, 0x00, 0x00, 0x80, 0x00, 0x00, // Command Field (0000,0100) = C-STORE-RSP (0x8001)
-            0x00, 0x00, 0x10, 0x01, 0x02, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, // Message ID Being Responded To (0000,0120) = 1
-            0x00, 0x00, 0x00, 0x09, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // Status (0000,0900) = Success (0x0000)
-        ];
+    /// Check every received object against its IOD definition and log any violations
+    pub fn with_iod_validation(mut self, enabled: bool) -> Self {
+        self.iod_validation = enabled;
+        self
+    }
+
+    /// Fix common VR and value-length problems in received objects before storing them
+    pub fn with_lenient_repair(mut self, enabled: bool) -> Self {
+        self.lenient_repair = enabled;
+        self
+    }
+
+    /// Report the outcome of every received object to `callback`, e.g. for an embedding application
+    pub fn with_object_callback(mut self, callback: ObjectCallback) -> Self {
+        self.object_callback = Some(callback);
+        self
+    }
+
+    /// Hand every accepted object to `forwarder` instead of writing it to the
+    /// output directory; quarantined objects are still stored
+    pub fn with_forwarder(mut self, forwarder: ObjectForwarder) -> Self {
+        self.forwarder = Some(forwarder);
+        self
+    }
+
+    /// Append every received object to a hash-chained audit ledger
+    pub fn with_ledger(mut self, ledger: Ledger) -> Self {
+        self.ledger = Some(Arc::new(std::sync::Mutex::new(ledger)));
+        self
+    }
+
+    /// Deliberately return error statuses, drop associations or delay responses
+    pub fn with_fault_injection(mut self, faults: FaultInjection) -> Self {
+        self.fault_injection = Some(faults);
+        self
+    }
+
+    /// Flag objects whose content matches an earlier object received under a different SOP Instance UID
+    pub fn with_duplicate_content_detection(mut self, enabled: bool) -> Self {
+        self.content_index = enabled.then(|| Arc::new(std::sync::Mutex::new(ContentIndex::new())));
+        self
+    }
+
+    /// Warn about objects whose acquisition or content timestamps are implausibly far from the receiver clock
+    pub fn with_time_sanity_check(mut self, policy: TimeSanityPolicy) -> Self {
+        self.time_sanity = Some(policy);
+        self
+    }
+
+    /// Number of received objects flagged by the time sanity check so far
+    pub fn clock_warning_count(&self) -> u64 {
+        self.clock_warnings.load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    /// Refuse objects larger than the limit configured for their SOP class category
+    pub fn with_size_limits(mut self, limits: SizeLimits) -> Self {
+        self.size_limits = limits;
+        self
+    }
+
+    /// Refuse data beyond the configured per-association and daily byte quotas
+    pub fn with_byte_quotas(mut self, quotas: ByteQuotas) -> Self {
+        self.byte_quotas = quotas;
+        self
+    }
+
+    /// Bytes received today and since startup
+    pub fn byte_counters(&self) -> CounterSnapshot {
+        self.byte_counters.snapshot()
+    }
+
+    /// Name stored files by a sequence number rather than a timestamp, for reproducible test fixtures
+    pub fn with_deterministic_filenames(mut self, enabled: bool) -> Self {
+        self.deterministic = enabled;
+        self
+    }
+
+    /// Log a protocol deviation by the peer; true if the compliance policy rejects it
+    fn deviation(&self, rule: ComplianceRule, addr: std::net::SocketAddr, detail: &str) -> bool {
+        if self.compliance.rejects(rule) {
+            error!("🚫  Protocol violation by {} [{}]: {}", addr, rule, detail);
+            println!("🚫  Protocol violation [{}]: {}", rule, detail);
+            true
+        } else {
+            warn!("⚠️  Tolerated protocol deviation by {} [{}]: {}", addr, rule, detail);
+            println!("⚠️  Tolerated protocol deviation [{}]: {}", rule, detail);
+            false
+        }
+    }
+
+    /// Bytes written to disk for a dataset, sealed when encryption at rest is enabled
+    fn storage_bytes<'a>(&self, dataset: &'a [u8]) -> Result<std::borrow::Cow<'a, [u8]>> {
+        match &self.encryption {
+            Some(key) => Ok(std::borrow::Cow::Owned(key.seal(dataset)?)),
+            None => Ok(std::borrow::Cow::Borrowed(dataset)),
+        }
+    }
+
+    /// Keep incomplete transfers as partial files for startup recovery
+    fn save_pending_transfers(&self, pending: Vec<DicomTransfer>, transfer_syntaxes: &HashMap<u8, String>) {
+        for mut transfer in pending {
+            info!("💾  Saving pending transfer: {} bytes from {} chunks", 
+                  transfer.total_bytes, transfer.chunks);
+            println!("💾  Saving pending transfer: {} bytes from {} chunks", 
+                     transfer.total_bytes, transfer.chunks);
+            
+            let filename = self.object_filename(&transfer, transfer.presentation_context_id);
+            let file_path = self.output_dir.join(filename);
+            let saved = match transfer.spool.take() {
+                Some(spool) => {
+                    let partial = partial_path(&file_path);
+                    self.store_spooled(spool, &partial).map(|_| partial)
+                }
+                None => {
+                    let transfer_syntax_uid = transfer_syntaxes.get(&transfer.presentation_context_id).map(String::as_str).unwrap_or_default();
+                    let file = Self::part10_object(&transfer, transfer_syntax_uid, None, None, &transfer.reconstruct_dataset());
+                    self.storage_bytes(&file).and_then(|bytes| write_partial(&file_path, &bytes))
+                }
+            };
+            
+            match saved {
+                Err(e) => {
+                    error!("❌  Failed to save pending dataset: {}", e);
+                    println!("❌  Failed to save pending dataset: {}", e);
+                }
+                Ok(partial) => {
+                    info!("✅  Saved pending transfer to {}", partial.display());
+                    println!("✅  Saved pending transfer to {}", partial.display());
+                }
+            }
+        }
+    }
+
+    /// Move a spooled object to `path`; with encryption at rest it was
+    /// sealed as it was spooled
+    fn store_spooled(&self, spool: Spool, path: &Path) -> Result<()> {
+        spool.persist(path)
+            .with_context(|| format!("Failed to move spooled object to {}", path.display()))
+    }
+
+    /// SOP Class and Instance UIDs of a received object, from the C-STORE
+    /// command or else from the data set itself
+    fn object_uids(transfer: &DicomTransfer, obj: Option<&InMemDicomObject>) -> (String, String) {
+        let dataset_uid = |tag: dicom_core::Tag| -> Option<String> {
+            obj?.element(tag).ok()
+                .and_then(|e| e.to_str().ok())
+                .map(|s| s.trim_end_matches('\0').trim().to_string())
+        };
+        let sop_class_uid = transfer.sop_class_uid.clone()
+            .or_else(|| dataset_uid(dicom_core::Tag(0x0008, 0x0016)))
+            .unwrap_or_default();
+        let sop_instance_uid = transfer.sop_instance_uid.clone()
+            .or_else(|| dataset_uid(dicom_core::Tag(0x0008, 0x0018)))
+            .unwrap_or_default();
+        (sop_class_uid, sop_instance_uid)
+    }
+
+    /// The received data set as a Part 10 file, its File Meta Information taken
+    /// from the C-STORE command or else from the data set itself
+    fn part10_object(transfer: &DicomTransfer, transfer_syntax_uid: &str, obj: Option<&InMemDicomObject>,
+                     source_ae_title: Option<&str>, dataset: &[u8]) -> Vec<u8> {
+        let (sop_class_uid, sop_instance_uid) = Self::object_uids(transfer, obj);
+        part10_file(&FileMeta {
+            media_storage_sop_class_uid: &sop_class_uid,
+            media_storage_sop_instance_uid: &sop_instance_uid,
+            transfer_syntax_uid,
+            source_ae_title,
+        }, dataset)
+    }
+
+    /// Where a received object is stored below `dir`: per the filename template
+    /// if there is one and the data set could be parsed, else by SOP Instance UID.
+    /// Deep templates below a deep output directory pass MAX_PATH on Windows
+    fn object_path(&self, dir: &Path, transfer: &DicomTransfer, pc_id: u8, obj: Option<&InMemDicomObject>) -> PathBuf {
+        let dir = long_path(dir);
+        match (&self.filename_template, obj) {
+            (Some(template), Some(obj)) => unique_path(&dir.join(template.expand(|(group, element)| {
+                obj.element(dicom_core::Tag(group, element)).ok()
+                    .and_then(|e| e.to_str().ok())
+                    .map(|value| value.to_string())
+            }))),
+            _ => dir.join(self.object_filename(transfer, pc_id)),
+        }
+    }
+
+    fn object_filename(&self, transfer: &DicomTransfer, pc_id: u8) -> String {
+        if self.deterministic {
+            let seq = self.objects_stored.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            format!("received_{:06}_{}.dcm", seq, pc_id)
+        } else if let Some(uid) = transfer.sop_instance_uid.as_deref().filter(|uid| is_valid_uid(uid)) {
+            format!("{}.dcm", uid)
+        } else {
+            format!("received_{}_{}.dcm", transfer.started_at.format("%Y%m%d_%H%M%S_%f"), pc_id)
+        }
+    }
+
+    /// Enforce or tolerate protocol deviations by peers per rule (lenient by default)
+    pub fn with_compliance(mut self, policy: CompliancePolicy) -> Self {
+        self.compliance = policy;
+        self
+    }
+
+    /// How long in-flight C-STOREs may take to finish once a shutdown is requested
+    pub fn with_shutdown_grace(mut self, grace: std::time::Duration) -> Self {
+        self.shutdown_grace = grace;
+        self
+    }
+
+    /// Abort associations that receive nothing for `timeout` (ARTIM), or never with `None`
+    pub fn with_idle_timeout(mut self, timeout: Option<std::time::Duration>) -> Self {
+        self.idle_timeout = timeout;
+        self
+    }
+
+    /// Abort associations still open after `duration`, whatever they are doing
+    pub fn with_max_association_duration(mut self, duration: Option<std::time::Duration>) -> Self {
+        self.max_association_duration = duration;
+        self
+    }
+
+    /// Listen on `address` only; an IPv6 address also accepts IPv4 peers unless `ipv6_only`
+    pub fn with_bind_address(mut self, address: std::net::IpAddr, ipv6_only: bool) -> Self {
+        self.bind_address = address;
+        self.ipv6_only = ipv6_only;
+        self
+    }
+
+    /// Accept only these SOP classes, refusing the presentation contexts of any other
+    pub fn with_sop_classes(mut self, uids: Vec<String>) -> Self {
+        self.sop_classes = Some(Arc::new(uids));
+        self
+    }
+
+    /// A receiver configured like this one for another listener, with its own
+    /// AE title and output directory, file numbering and study reports;
+    /// connection slots, shutdown, ledger, index, duplicate detection and byte
+    /// counters stay shared, so `max_connections` bounds all listeners together
+    pub fn for_listener(&self, ae_title: String, output_dir: PathBuf) -> Self {
+        if let Err(e) = std::fs::create_dir_all(&output_dir) {
+            error!("Failed to create output directory {}: {}", output_dir.display(), e);
+        }
+        let study_tracker = self.study_tracker.as_ref().map(|tracker| {
+            let timeout = match tracker.lock() {
+                Ok(tracker) => tracker.timeout(),
+                Err(poisoned) => poisoned.into_inner().timeout(),
+            };
+            Arc::new(std::sync::Mutex::new(StudyTracker::new(timeout)))
+        });
+        Self {
+            ae_title,
+            output_dir,
+            objects_stored: Arc::new(std::sync::atomic::AtomicU64::new(0)),
+            study_tracker,
+            sop_classes: None,
+            ..self.clone()
+        }
+    }
+
+    /// Write data sets larger than `bytes` to disk as they arrive instead of holding them in memory
+    pub fn with_spool_threshold(mut self, bytes: u64) -> Self {
+        self.spool_threshold = bytes;
+        self
+    }
+
+    /// Encrypt every stored object at rest with this key
+    pub fn with_encryption(mut self, key: StorageKey) -> Self {
+        self.encryption = Some(Arc::new(key));
+        self
+    }
+
+    /// Name stored objects after their attributes, e.g. `{modality}/{study_date}/{sop_uid}.dcm`
+    pub fn with_filename_template(mut self, template: FilenameTemplate) -> Self {
+        self.filename_template = Some(template);
+        self
+    }
+
+    /// Also listen for DICOM over TLS on `port`
+    pub fn with_tls(mut self, port: u16, config: Arc<rustls::ServerConfig>) -> Self {
+        self.tls = Some((port, config));
+        self
+    }
+
+    /// Listen for plaintext connections on the port given to `start` (on by default)
+    pub fn with_plaintext_listener(mut self, enabled: bool) -> Self {
+        self.plaintext = enabled;
+        self
+    }
+
+    /// Refuse new objects with Out of Resources while `mode` is active
+    pub fn with_read_only(mut self, mode: ReadOnly) -> Self {
+        self.read_only = Some(mode);
+        self
+    }
+
+    /// Write a JSON report for each study once `timeout` has passed without new instances for it
+    pub fn with_study_reports(mut self, timeout: chrono::Duration) -> Self {
+        self.study_tracker = Some(Arc::new(std::sync::Mutex::new(StudyTracker::new(timeout))));
+        self
+    }
+
+    /// Record every stored instance and its attributes in `index`
+    pub fn with_receive_index(mut self, index: Box<dyn MetadataStore>) -> Self {
+        self.receive_index = Some(Arc::new(std::sync::Mutex::new(index)));
+        self
+    }
+
+    /// Report to `readiness` once listening, and no longer once shutting down
+    pub fn with_readiness(mut self, readiness: Arc<Readiness>) -> Self {
+        self.readiness = Some(readiness);
+        self
+    }
+
+    /// Requests answered by this receiver and the listeners configured from it
+    pub fn metrics(&self) -> Arc<ServiceMetrics> {
+        Arc::clone(&self.metrics)
+    }
+
+    /// Export every presentation state together with the images it references once its study
+    /// completes; only takes effect with study reports
+    pub fn with_presentation_state_bundles(mut self, enabled: bool) -> Self {
+        self.presentation_state_bundles = enabled;
+        self
+    }
+
+    /// Remember every stored instance in `received`, and answer instances stored
+    /// before per `policy` without receiving them into memory or storing them again
+    pub fn with_sparse_resume(mut self, received: ReceivedInstances, policy: DuplicatePolicy) -> Self {
+        self.received_instances = Some(Arc::new(std::sync::Mutex::new(received)));
+        self.duplicate_policy = policy;
+        self
+    }
+
+    /// POST every study to `webhook` once it completes; only takes effect with study reports
+    pub fn with_study_webhook(mut self, webhook: StudyWebhook) -> Self {
+        self.study_webhook = Some(Arc::new(webhook));
+        self
+    }
+
+    /// Accept each presentation context with the first syntax of this list that was proposed for it
+    pub fn with_transfer_syntax_preference(mut self, preference: TransferSyntaxPreference) -> Self {
+        self.ts_preference = Some(preference);
+        self
+    }
+
+    /// Refuse retired SOP classes and transfer syntaxes, or accept them and convert
+    pub fn with_retired_policy(mut self, policy: RetiredPolicy) -> Self {
+        self.retired_policy = policy;
+        self
+    }
+
+    /// Record a received object in the audit ledger and report it to the object callback, if configured
+    fn record_object(&self, calling_ae: &str, obj: Option<&InMemDicomObject>, size: usize, status: &str) {
+        if self.ledger.is_none() && self.object_callback.is_none() {
+            return;
+        }
+
+        let uid = |tag: dicom_core::Tag| -> String {
+            obj.and_then(|obj| obj.element(tag).ok())
+                .and_then(|e| e.to_str().ok())
+                .map(|s| s.trim_end_matches('\0').trim().to_string())
+                .unwrap_or_default()
+        };
+
+        let record = LedgerRecord {
+            direction: Direction::Received,
+            timestamp: Utc::now(),
+            local_ae: self.ae_title.clone(),
+            peer_ae: calling_ae.to_string(),
+            sop_class_uid: uid(dicom_core::Tag(0x0008, 0x0016)),
+            sop_instance_uid: uid(dicom_core::Tag(0x0008, 0x0018)),
+            study_instance_uid: uid(dicom_core::Tag(0x0020, 0x000D)),
+            size: size as u64,
+            status: status.to_string(),
+        };
+
+        if let Some(callback) = &self.object_callback {
+            (callback.0)(&record);
+        }
+        let ledger = match &self.ledger {
+            Some(ledger) => ledger,
+            None => return,
+        };
+        let mut ledger = match ledger.lock() {
+            Ok(ledger) => ledger,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        if let Err(e) = ledger.append(record) {
+            error!("❌  Failed to append to ledger: {}", e);
+        }
+    }
+
+    /// Add a stored object to the study it belongs to, when study reports are enabled
+    fn track_study(&self, calling_ae: &str, obj: Option<&InMemDicomObject>, transfer_syntax_uid: &str, size: usize,
+                   path: &std::path::Path) {
+        let (tracker, obj) = match (&self.study_tracker, obj) {
+            (Some(tracker), Some(obj)) => (tracker, obj),
+            _ => return,
+        };
+        let value = |tag: dicom_core::Tag| -> Option<String> {
+            obj.element(tag).ok()
+                .and_then(|e| e.to_str().ok())
+                .map(|s| s.trim_end_matches('\0').trim().to_string())
+                .filter(|s| !s.is_empty())
+        };
+        let numbers = |tag: dicom_core::Tag| -> Option<Vec<f64>> {
+            value(tag)?.split('\\').map(|n| n.trim().parse().ok()).collect()
+        };
+
+        let presentation_state_references = value(dicom_core::Tag(0x0008, 0x0016))
+            .and_then(|uid| self.sop_registry.get(&uid))
+            .filter(|info| info.category == SopClassCategory::Presentation)
+            .map(|_| {
+                let mut referenced = std::collections::BTreeSet::new();
+                collect_referenced_instances(obj, &mut referenced);
+                referenced.into_iter().collect()
+            });
+
+        let instance = ReceivedInstance {
+            study_instance_uid: value(dicom_core::Tag(0x0020, 0x000D)).unwrap_or_default(),
+            series_instance_uid: value(dicom_core::Tag(0x0020, 0x000E)).unwrap_or_default(),
+            sop_instance_uid: value(dicom_core::Tag(0x0008, 0x0018)).unwrap_or_default(),
+            modality: value(dicom_core::Tag(0x0008, 0x0060)),
+            patient_id: value(dicom_core::Tag(0x0010, 0x0020)),
+            accession_number: value(dicom_core::Tag(0x0008, 0x0050)),
+            instance_number: value(dicom_core::Tag(0x0020, 0x0013)).and_then(|n| n.parse().ok()),
+            image_position: numbers(dicom_core::Tag(0x0020, 0x0032)).and_then(|v| v.try_into().ok()),
+            image_orientation: numbers(dicom_core::Tag(0x0020, 0x0037)).and_then(|v| v.try_into().ok()),
+            transfer_syntax_uid: transfer_syntax_uid.to_string(),
+            calling_ae: calling_ae.to_string(),
+            size: size as u64,
+            stored_size: std::fs::metadata(path).map_or(size as u64, |metadata| metadata.len()),
+            received_at: Utc::now(),
+            path: path.to_path_buf(),
+            presentation_state_references,
+        };
+        match tracker.lock() {
+            Ok(mut tracker) => tracker.add(instance),
+            Err(poisoned) => poisoned.into_inner().add(instance),
+        }
+    }
+
+    /// The status refusing an instance already indexed in a study under legal
+    /// hold, Duplicate SOP Instance; when the hold cannot be checked the
+    /// instance is refused as well, with Out of Resources
+    fn legal_hold_refusal(&self, sop_instance_uid: Option<&str>) -> Option<u16> {
+        let (index, sop_instance_uid) = match (&self.receive_index, sop_instance_uid) {
+            (Some(index), Some(uid)) => (index, uid),
+            _ => return None,
+        };
+        let index = match index.lock() {
+            Ok(index) => index,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        match index.is_held_instance(sop_instance_uid) {
+            Ok(true) => Some(0x0111),
+            Ok(false) => None,
+            Err(e) => {
+                error!("❌  Failed to check the legal hold of {}, refusing it: {}", sop_instance_uid, e);
+                println!("❌  Failed to check the legal hold of {}, refusing it: {}", sop_instance_uid, e);
+                Some(0xA700)
+            }
+        }
+    }
+
+    fn index_object(&self, calling_ae: &str, obj: Option<&InMemDicomObject>, transfer_syntax_uid: &str, size: usize,
+                    path: &std::path::Path) {
+        let (index, obj) = match (&self.receive_index, obj) {
+            (Some(index), Some(obj)) => (index, obj),
+            _ => return,
+        };
+        let mut attributes = indexed_attributes(obj);
+        // From the file meta group, which the data set does not carry
+        attributes.insert("TransferSyntaxUID".to_string(), transfer_syntax_uid.to_string());
+        let instance = IndexedInstance {
+            sop_instance_uid: attributes.get("SOPInstanceUID").cloned().unwrap_or_default(),
+            calling_ae: calling_ae.to_string(),
+            path: path.to_path_buf(),
+            size: size as u64,
+            received_at: Utc::now(),
+            attributes,
+        };
+        let index = match index.lock() {
+            Ok(index) => index,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        if let Err(e) = index.record(&instance) {
+            error!("❌  Failed to record {} in the receive index: {}", instance.sop_instance_uid, e);
+        }
+    }
+
+    /// Status answering an instance that was stored before, when resuming studies
+    fn resumed_status(&self, sop_instance_uid: Option<&str>) -> Option<u16> {
+        let (received, sop_instance_uid) = match (&self.received_instances, sop_instance_uid) {
+            (Some(received), Some(uid)) => (received, uid),
+            _ => return None,
+        };
+        let status = self.duplicate_policy.status()?;
+        let received = match received.lock() {
+            Ok(received) => received,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        received.stored_path(sop_instance_uid).map(|_| status)
+    }
+
+    /// Add a stored instance to the set of its study, when resuming studies
+    fn remember_stored(&self, transfer: &DicomTransfer, obj: Option<&InMemDicomObject>, path: &std::path::Path) {
+        let (received, sop_instance_uid) = match (&self.received_instances, transfer.sop_instance_uid.as_deref()) {
+            (Some(received), Some(uid)) => (received, uid),
+            _ => return,
+        };
+        let study_instance_uid = obj.and_then(|obj| obj.element(dicom_core::Tag(0x0020, 0x000D)).ok())
+            .and_then(|e| e.to_str().ok())
+            .map(|s| s.trim_end_matches('\0').trim().to_string())
+            .unwrap_or_default();
+        let mut received = match received.lock() {
+            Ok(received) => received,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        if let Err(e) = received.record(&study_instance_uid, sop_instance_uid, path) {
+            error!("❌  Failed to remember {} for resuming its study: {}", sop_instance_uid, e);
+        }
+    }
+
+    /// Write the reports of studies that have received nothing for the study timeout
+    fn write_completed_study_reports(&self) {
+        let tracker = match &self.study_tracker {
+            Some(tracker) => tracker,
+            None => return,
+        };
+        let reports = match tracker.lock() {
+            Ok(mut tracker) => tracker.take_completed(Utc::now()),
+            Err(poisoned) => poisoned.into_inner().take_completed(Utc::now()),
+        };
+
+        let dir = self.output_dir.join("study_reports");
+        for report in reports {
+            match write_report(&dir, &report) {
+                Ok(path) => {
+                    info!("📋  Study {} complete: {} instance(s) in {} series, report written to {}",
+                          report.study_instance_uid, report.instances, report.series.len(), path.display());
+                    println!("📋  Study {} complete: {} instance(s), report {}",
+                             report.study_instance_uid, report.instances, path.display());
+                    if let Some(ratio) = report.compression_ratio {
+                        info!("📋  Study {}: {} bytes received, {} bytes stored ({:.2}:1)",
+                              report.study_instance_uid, report.total_bytes, report.stored_bytes, ratio);
+                    }
+                }
+                Err(e) => error!("❌  Failed to write report for study {}: {}", report.study_instance_uid, e),
+            }
+            if let Some(webhook) = &self.study_webhook {
+                let (webhook, report, ae_title) = (Arc::clone(webhook), report.clone(), self.ae_title.clone());
+                tokio::task::spawn_blocking(move || {
+                    if let Err(e) = webhook.deliver(&report, &ae_title) {
+                        error!("❌  {}", e);
+                    }
+                });
+            }
+            for series in report.series.iter().filter(|s| s.incomplete) {
+                warn!("⚠️  Series {} of study {} appears incomplete: {} InstanceNumber gap(s), {} duplicate number(s), \
+                       {} duplicate position(s), {} slice position gap(s)",
+                      series.series_instance_uid, report.study_instance_uid, series.instance_number_gaps.len(),
+                      series.duplicate_instance_numbers.len(), series.duplicate_positions, series.position_gaps.len());
+                println!("⚠️  Series {} appears incomplete (missing or duplicate slices)", series.series_instance_uid);
+            }
+            for orphan in &report.orphan_presentation_states {
+                warn!("⚠️  Presentation state {} of study {} references images that never arrived",
+                      orphan, report.study_instance_uid);
+                println!("⚠️  Orphan presentation state {}", orphan);
+            }
+            if self.presentation_state_bundles {
+                let bundle_dir = self.output_dir.join("bundles").join(&report.study_instance_uid);
+                for bundle in &report.presentation_states {
+                    match export_bundle(&bundle_dir, bundle) {
+                        Ok(path) => info!("📦  Exported presentation state {} with {} image(s) to {}",
+                                          bundle.sop_instance_uid, bundle.images.len(), path.display()),
+                        Err(e) => error!("❌  Failed to export presentation state {}: {}", bundle.sop_instance_uid, e),
+                    }
+                }
+            }
+        }
+    }
+
+    /// Read the A-ASSOCIATE-RQ without consuming it, so dicom-ul still negotiates from the start
+    fn peek_association_rq(stream: &std::net::TcpStream, deadline: std::time::Instant) -> Result<Vec<u8>> {
+        const MAX_ASSOCIATE_RQ_LENGTH: usize = 64 * 1024;
+
+        stream.set_nonblocking(false)?;
+        let mut buffer = vec![0u8; 6];
+        loop {
+            let left = deadline.saturating_duration_since(std::time::Instant::now());
+            if left.is_zero() {
+                anyhow::bail!("association request not complete in time");
+            }
+            stream.set_read_timeout(Some(left))?;
+            let available = stream.peek(&mut buffer)?;
+            if available == 0 {
+                anyhow::bail!("connection closed before the association request");
+            }
+            let needed = if available >= 6 { pdu_length(&buffer).unwrap_or(6) } else { 6 };
+            if needed > MAX_ASSOCIATE_RQ_LENGTH {
+                anyhow::bail!("association request of {} bytes is too large", needed);
+            }
+            if available >= needed {
+                buffer.truncate(needed);
+                break;
+            }
+            if buffer.len() < needed {
+                buffer.resize(needed, 0);
+            } else {
+                // The rest of the PDU is still in flight
+                std::thread::sleep(std::time::Duration::from_millis(5));
+            }
+        }
+        stream.set_read_timeout(None)?;
+        Ok(buffer)
+    }
+
+    /// Look up the transfer syntax negotiated for a presentation context
+    fn lookup_transfer_syntax(transfer_syntax_uid: &str) -> Result<&'static dicom::encoding::TransferSyntax> {
+        let ts_uid = transfer_syntax_uid.trim_end_matches('\0');
+        dicom_transfer_syntax_registry::TransferSyntaxRegistry
+            .get(ts_uid)
+            .ok_or_else(|| anyhow::anyhow!("Unknown transfer syntax: {}", ts_uid))
+    }
+
+    /// Decode a received dataset using the transfer syntax negotiated for its presentation context
+    fn parse_dataset(data: &[u8], transfer_syntax_uid: &str) -> Result<InMemDicomObject> {
+        let ts = Self::lookup_transfer_syntax(transfer_syntax_uid)?;
+        InMemDicomObject::read_dataset_with_ts(data, ts)
+            .context("Failed to decode received dataset")
+    }
+
+    /// Read the attributes of a spooled Part 10 file up to its pixel data,
+    /// opening it with `key` if it was sealed
+    fn parse_spooled(path: &Path, key: Option<&StorageKey>) -> Result<InMemDicomObject> {
+        let options = dicom_object::OpenFileOptions::new().read_until(PIXEL_DATA);
+        let file = match key {
+            Some(key) => {
+                let file = std::io::BufReader::new(std::fs::File::open(path)?);
+                let mut reader = OpeningReader::new(key, file)?;
+                std::io::copy(&mut std::io::Read::take(&mut reader, 128), &mut std::io::sink())?;
+                options.read_preamble(dicom_object::file::ReadPreamble::Never).from_reader(reader)
+            }
+            None => options.open_file(path),
+        };
+        Ok(file.context("Failed to decode spooled dataset")?.into_inner())
+    }
+
+    /// Encode a dataset back into the transfer syntax it was received in
+    fn encode_dataset(obj: &InMemDicomObject, transfer_syntax_uid: &str) -> Result<Vec<u8>> {
+        let ts = Self::lookup_transfer_syntax(transfer_syntax_uid)?;
+        let mut buffer = Vec::new();
+        obj.write_dataset_with_ts(&mut buffer, ts)
+            .context("Failed to encode dataset")?;
+        Ok(buffer)
+    }
+
+    fn send_c_echo_response(association: &mut dicom_ul::association::ServerAssociation<std::net::TcpStream>, pc_id: u8, message_id: u16) -> Result<()> {
+        association.send(&Pdu::PData {
+            data: vec![PDataValue {
+                presentation_context_id: pc_id,
+                is_last: true,
+                value_type: PDataValueType::Command,
+                data: response_command(C_ECHO_RSP, message_id, VERIFICATION_SOP_CLASS, None, 0x0000),
+            }]
+        })?;
+        info!("✅  Sent C-ECHO response to message {}", message_id);
+        println!("✅  Sent C-ECHO response");
+        Ok(())
+    }
+
+    fn send_c_store_response(&self, association: &mut dicom_ul::association::ServerAssociation<std::net::TcpStream>,
+                             request: &StoreRequest, status: u16) -> Result<()> {
+        // Answer on the presentation context of the request
+        let pc_id = request.presentation_context_id;
+        let response_data = response_command(
+            C_STORE_RSP,
+            request.message_id.unwrap_or(1),
+            request.sop_class_uid.as_deref().unwrap_or_default(),
+            request.sop_instance_uid.as_deref(),
+            status,
+        );
 
         let response_pdu = Pdu::PData {
             data: vec![PDataValue {
@@ -371,3 +1941,15 @@ impl DicomReceiver {
         Ok(())
     }
 }
+
+/// Kind of the I/O error underlying a receive error, if it came from the socket
+fn io_error_kind(error: &(dyn std::error::Error + 'static)) -> Option<std::io::ErrorKind> {
+    let mut source = Some(error);
+    while let Some(current) = source {
+        if let Some(io_error) = current.downcast_ref::<std::io::Error>() {
+            return Some(io_error.kind());
+        }
+        source = current.source();
+    }
+    None
+}
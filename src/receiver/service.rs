@@ -0,0 +1,159 @@
+//! Running the receiver as a Windows service
+//!
+//! `dicom-receiver service install -- <receiver options>` registers the
+//! receiver with the Service Control Manager, started at boot with those
+//! options; `service uninstall` removes it again. Started by the Service
+//! Control Manager, the receiver reports itself running, and a stop request
+//! or system shutdown begins the same graceful shutdown as Ctrl-C does on a
+//! console. Services start in the system directory, so relative paths in the
+//! options resolve against the directory of the executable instead.
+
+use anyhow::{Context, Result};
+use clap::{Parser, Subcommand};
+use std::ffi::OsString;
+use std::sync::{Mutex, PoisonError};
+use std::time::Duration;
+use tracing::error;
+use windows_service::service::{
+    ServiceAccess, ServiceControl, ServiceControlAccept, ServiceErrorControl, ServiceExitCode, ServiceInfo,
+    ServiceStartType, ServiceState, ServiceStatus, ServiceType,
+};
+use windows_service::service_control_handler::{self, ServiceControlHandlerResult, ServiceStatusHandle};
+use windows_service::service_manager::{ServiceManager, ServiceManagerAccess};
+use windows_service::{define_windows_service, service_dispatcher};
+
+use crate::Args;
+
+/// Name the service is registered under
+pub const SERVICE_NAME: &str = "DicomReceiver";
+
+/// Hidden option the Service Control Manager starts the receiver with
+const SERVICE_FLAG: &str = "--windows-service";
+
+#[derive(Subcommand, Clone)]
+pub enum ServiceAction {
+    /// Register the service, started at boot with the receiver options given after `--`
+    Install {
+        #[arg(last = true, required = true)]
+        receiver_args: Vec<OsString>,
+    },
+    /// Remove the service
+    Uninstall,
+}
+
+/// Options of the receiver, handed from `dispatch` to the service main function
+static SERVICE_ARGS: Mutex<Option<Args>> = Mutex::new(None);
+
+pub fn control(action: &ServiceAction) -> Result<()> {
+    match action {
+        ServiceAction::Install { receiver_args } => install(receiver_args),
+        ServiceAction::Uninstall => uninstall(),
+    }
+}
+
+fn install(receiver_args: &[OsString]) -> Result<()> {
+    // Refuse options the service would fail to start with
+    Args::try_parse_from(std::iter::once(OsString::from("dicom-receiver")).chain(receiver_args.iter().cloned()))?;
+
+    let manager = ServiceManager::local_computer(None::<&str>, ServiceManagerAccess::CONNECT | ServiceManagerAccess::CREATE_SERVICE)
+        .context("Failed to connect to the Service Control Manager")?;
+    let mut launch_arguments = vec![OsString::from(SERVICE_FLAG)];
+    launch_arguments.extend(receiver_args.iter().cloned());
+    let info = ServiceInfo {
+        name: OsString::from(SERVICE_NAME),
+        display_name: OsString::from("DICOM Receiver"),
+        service_type: ServiceType::OWN_PROCESS,
+        start_type: ServiceStartType::AutoStart,
+        error_control: ServiceErrorControl::Normal,
+        executable_path: std::env::current_exe()?,
+        launch_arguments,
+        dependencies: Vec::new(),
+        account_name: None,
+        account_password: None,
+    };
+    let service = manager.create_service(&info, ServiceAccess::CHANGE_CONFIG)
+        .with_context(|| format!("Failed to create service {}", SERVICE_NAME))?;
+    service.set_description("Receives DICOM objects over C-STORE")?;
+    println!("🪟 Service {} installed; start it with: sc start {}", SERVICE_NAME, SERVICE_NAME);
+    Ok(())
+}
+
+fn uninstall() -> Result<()> {
+    let manager = ServiceManager::local_computer(None::<&str>, ServiceManagerAccess::CONNECT)
+        .context("Failed to connect to the Service Control Manager")?;
+    let service = manager.open_service(SERVICE_NAME, ServiceAccess::DELETE)
+        .with_context(|| format!("Failed to open service {}", SERVICE_NAME))?;
+    service.delete()?;
+    println!("🪟 Service {} removed", SERVICE_NAME);
+    Ok(())
+}
+
+/// Run the receiver with `args` under the Service Control Manager, returning once it stopped
+pub fn dispatch(args: Args) -> Result<()> {
+    if let Some(dir) = std::env::current_exe()?.parent() {
+        std::env::set_current_dir(dir)?;
+    }
+    *SERVICE_ARGS.lock().unwrap_or_else(PoisonError::into_inner) = Some(args);
+    service_dispatcher::start(SERVICE_NAME, ffi_service_main)
+        .context("Failed to start the service dispatcher; the receiver was not started as a service")
+}
+
+define_windows_service!(ffi_service_main, service_main);
+
+fn service_main(_arguments: Vec<OsString>) {
+    if let Err(e) = run_service() {
+        error!("Service failed: {:#}", e);
+    }
+}
+
+fn status(state: ServiceState, exit_code: u32, wait_hint: Duration) -> ServiceStatus {
+    ServiceStatus {
+        service_type: ServiceType::OWN_PROCESS,
+        current_state: state,
+        controls_accepted: if state == ServiceState::Running {
+            ServiceControlAccept::STOP | ServiceControlAccept::SHUTDOWN
+        } else {
+            ServiceControlAccept::empty()
+        },
+        exit_code: ServiceExitCode::Win32(exit_code),
+        checkpoint: 0,
+        wait_hint,
+        process_id: None,
+    }
+}
+
+fn run_service() -> Result<()> {
+    let args = SERVICE_ARGS.lock().unwrap_or_else(PoisonError::into_inner).take()
+        .context("Service started without receiver options")?;
+    // Time the listeners take to drain, plus some to write the last files
+    let stop_wait = Duration::from_secs(args.shutdown_grace + 10);
+
+    let (request_stop, stop_requested) = tokio::sync::oneshot::channel::<()>();
+    let request_stop = Mutex::new(Some(request_stop));
+    let status_handle: ServiceStatusHandle = service_control_handler::register(SERVICE_NAME, move |control| match control {
+        ServiceControl::Stop | ServiceControl::Shutdown => {
+            if let Some(request_stop) = request_stop.lock().unwrap_or_else(PoisonError::into_inner).take() {
+                let _ = request_stop.send(());
+            }
+            ServiceControlHandlerResult::NoError
+        }
+        ServiceControl::Interrogate => ServiceControlHandlerResult::NoError,
+        _ => ServiceControlHandlerResult::NotImplemented,
+    })?;
+    status_handle.set_service_status(status(ServiceState::Running, 0, Duration::default()))?;
+
+    let stopping = async move {
+        let _ = stop_requested.await;
+        if let Err(e) = status_handle.set_service_status(status(ServiceState::StopPending, 0, stop_wait)) {
+            error!("Failed to report the service stopping: {}", e);
+        }
+    };
+    let result = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .map_err(anyhow::Error::from)
+        .and_then(|runtime| runtime.block_on(crate::run(args, stopping)));
+
+    status_handle.set_service_status(status(ServiceState::Stopped, if result.is_ok() { 0 } else { 1 }, Duration::default()))?;
+    result
+}
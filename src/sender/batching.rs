@@ -0,0 +1,90 @@
+/// Batching of small non-image objects onto shared associations
+///
+/// SR, KOS and presentation state workloads are mostly one or two tiny
+/// instances per study, and opening an association for each study then costs
+/// far more than the transfer itself. Consecutive studies made up only of
+/// small non-image objects are therefore taken from the queue together and
+/// sent back to back over one association.
+
+use std::collections::VecDeque;
+
+use crate::common::sop_classes::{SopClassCategory, SopClassRegistry};
+use crate::common::types::DicomFile;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SmallObjectBatching {
+    /// Largest file that counts as a small object
+    pub max_size: u64,
+    /// Most files sent over one association
+    pub max_files: usize,
+}
+
+impl SmallObjectBatching {
+    pub fn is_small_object(&self, file: &DicomFile, registry: &SopClassRegistry) -> bool {
+        file.file_size <= self.max_size
+            && registry.get(&file.sop_class_uid).is_some_and(|info| matches!(info.category,
+                SopClassCategory::StructuredReporting | SopClassCategory::KeyObjectSelection | SopClassCategory::Presentation))
+    }
+
+    /// Take the next study from the queue, together with the studies after it
+    /// while all of them consist of small objects and the batch has room
+    pub fn take(&self, queue: &mut VecDeque<(String, Vec<DicomFile>)>, registry: &SopClassRegistry) -> Vec<(String, Vec<DicomFile>)> {
+        let only_small = |files: &[DicomFile]| files.iter().all(|file| self.is_small_object(file, registry));
+        let Some(first) = queue.pop_front() else { return Vec::new() };
+        if !only_small(&first.1) {
+            return vec![first];
+        }
+
+        let mut files = first.1.len();
+        let mut batch = vec![first];
+        while let Some((_, next)) = queue.front() {
+            if files + next.len() > self.max_files || !only_small(next) {
+                break;
+            }
+            files += next.len();
+            batch.extend(queue.pop_front());
+        }
+        batch
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(study: &str, sop_class_uid: &str, file_size: u64) -> DicomFile {
+        DicomFile::test(format!("/data/{}.dcm", study), &format!("{}.1.1", study))
+            .with_study(study)
+            .with_series(&format!("{}.1", study))
+            .with_sop_class(sop_class_uid)
+            .with_size(file_size)
+            .with_modality(None)
+    }
+
+    #[test]
+    fn test_small_studies_share_an_association() {
+        let registry = SopClassRegistry::new();
+        let batching = SmallObjectBatching { max_size: 64 * 1024, max_files: 3 };
+        let kos = "1.2.840.10008.5.1.4.1.1.88.59";
+        let sr = "1.2.840.10008.5.1.4.1.1.88.22";
+        let ct = "1.2.840.10008.5.1.4.1.1.2";
+
+        let mut queue: VecDeque<(String, Vec<DicomFile>)> = vec![
+            ("1".to_string(), vec![file("1", kos, 2_000)]),
+            ("2".to_string(), vec![file("2", sr, 8_000)]),
+            ("3".to_string(), vec![file("3", ct, 500_000)]),
+            ("4".to_string(), vec![file("4", sr, 1_000)]),
+            ("5".to_string(), vec![file("5", sr, 1_000), file("5", kos, 1_000)]),
+            ("6".to_string(), vec![file("6", kos, 1_000)]),
+            ("7".to_string(), vec![file("7", sr, 10_000_000)]),
+        ].into();
+
+        let studies = |batch: Vec<(String, Vec<DicomFile>)>| batch.into_iter().map(|(uid, _)| uid).collect::<Vec<_>>();
+        assert_eq!(studies(batching.take(&mut queue, &registry)), vec!["1", "2"]);
+        assert_eq!(studies(batching.take(&mut queue, &registry)), vec!["3"]);
+        assert_eq!(studies(batching.take(&mut queue, &registry)), vec!["4", "5"]);
+        assert_eq!(studies(batching.take(&mut queue, &registry)), vec!["6"]);
+        assert_eq!(studies(batching.take(&mut queue, &registry)), vec!["7"]);
+        assert!(batching.take(&mut queue, &registry).is_empty());
+    }
+}
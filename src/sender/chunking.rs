@@ -0,0 +1,162 @@
+use std::time::Duration;
+
+/// Bytes of each PDV item header inside a P-DATA-TF PDU (item length, context ID, control header)
+const PDV_HEADER_LENGTH: usize = 6;
+/// Bytes of every PDU header (type, reserved, length)
+const PDU_HEADER_LENGTH: usize = 6;
+/// Size of an A-RELEASE-RQ, A-RELEASE-RP or A-ABORT PDU
+pub const RELEASE_PDU_LENGTH: u64 = 10;
+/// Smallest fragment worth packing behind another PDV rather than sending in its own PDU
+pub const MIN_PACKED_FRAGMENT: usize = 1024;
+/// Fragment ceiling when the peer does not limit its PDU length
+const UNLIMITED_PDU_CHUNK: usize = 4 * 1024 * 1024;
+const MIN_CHUNK: usize = 4096;
+/// Fragments measured before the size is adjusted
+const WINDOW_CHUNKS: usize = 8;
+/// Throughput drop, as a fraction, that reverses the search direction
+const TOLERANCE: f64 = 0.05;
+
+/// Adaptive dataset fragment size
+///
+/// Starts from the largest fragment the peer's maximum PDU length allows and
+/// hill-climbs on observed throughput: after each window of fragments the size
+/// is halved or doubled, and the direction reverses when throughput drops.
+/// On a fast LAN this settles at the maximum; on lossy or high-latency links it
+/// finds the size that keeps fragments flowing best.
+#[derive(Debug, Clone)]
+pub struct ChunkTuner {
+    size: usize,
+    min: usize,
+    max: usize,
+    growing: bool,
+    window_bytes: u64,
+    window_time: Duration,
+    window_chunks: usize,
+    last_throughput: Option<f64>,
+}
+
+impl ChunkTuner {
+    /// Tuner for a peer that accepts PDUs of up to `max_pdu_length` bytes (0 = unlimited)
+    pub fn new(max_pdu_length: u32) -> Self {
+        let max = match max_pdu_length as usize {
+            0 => UNLIMITED_PDU_CHUNK,
+            length => length.saturating_sub(PDV_HEADER_LENGTH).max(1),
+        };
+        Self {
+            size: max,
+            min: MIN_CHUNK.min(max),
+            max,
+            growing: false,
+            window_bytes: 0,
+            window_time: Duration::ZERO,
+            window_chunks: 0,
+            last_throughput: None,
+        }
+    }
+
+    pub fn chunk_size(&self) -> usize {
+        self.size
+    }
+
+    /// Room for a data fragment in a PDU already carrying `used` bytes of PDV items
+    ///
+    /// Lets a small command share its PDU with the start of the dataset, or a
+    /// small instance travel in a single PDU.
+    pub fn fragment_room(&self, used: usize) -> usize {
+        (self.size + PDV_HEADER_LENGTH).saturating_sub(used + PDV_HEADER_LENGTH)
+    }
+
+    /// Record how long a fragment of `bytes` took to send and be acknowledged
+    pub fn record(&mut self, bytes: usize, elapsed: Duration) {
+        self.window_bytes += bytes as u64;
+        self.window_time += elapsed;
+        self.window_chunks += 1;
+        if self.window_chunks < WINDOW_CHUNKS {
+            return;
+        }
+
+        let seconds = self.window_time.as_secs_f64();
+        let throughput = if seconds > 0.0 { self.window_bytes as f64 / seconds } else { f64::INFINITY };
+        self.window_bytes = 0;
+        self.window_time = Duration::ZERO;
+        self.window_chunks = 0;
+
+        if let Some(last) = self.last_throughput {
+            if throughput < last * (1.0 - TOLERANCE) {
+                self.growing = !self.growing;
+            }
+        }
+        self.last_throughput = Some(throughput);
+
+        self.size = if self.growing {
+            (self.size * 2).min(self.max)
+        } else {
+            (self.size / 2).max(self.min)
+        };
+    }
+}
+
+/// Length of the PDV item carrying `data_length` bytes
+pub fn pdv_item_length(data_length: usize) -> usize {
+    data_length + PDV_HEADER_LENGTH
+}
+
+/// Encoded size of a P-DATA-TF PDU whose PDVs carry the given data lengths
+pub fn pdata_pdu_length(data_lengths: impl IntoIterator<Item = usize>) -> u64 {
+    (PDU_HEADER_LENGTH + data_lengths.into_iter().map(pdv_item_length).sum::<usize>()) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Send `windows` windows of fragments over a link with the given per-fragment cost
+    fn simulate(tuner: &mut ChunkTuner, windows: usize, cost: impl Fn(usize) -> Duration) {
+        for _ in 0..windows * WINDOW_CHUNKS {
+            let size = tuner.chunk_size();
+            tuner.record(size, cost(size));
+        }
+    }
+
+    #[test]
+    fn test_starts_from_negotiated_pdu() {
+        assert_eq!(ChunkTuner::new(16384).chunk_size(), 16378);
+        assert_eq!(ChunkTuner::new(0).chunk_size(), UNLIMITED_PDU_CHUNK);
+    }
+
+    #[test]
+    fn test_fragment_room_after_command() {
+        let tuner = ChunkTuner::new(16384);
+        let command = pdv_item_length(100);
+        assert_eq!(tuner.fragment_room(0), tuner.chunk_size());
+        // Command and first fragment together fill exactly one PDU
+        assert_eq!(command + pdv_item_length(tuner.fragment_room(command)), 16384);
+        assert_eq!(tuner.fragment_room(16384), 0);
+    }
+
+    #[test]
+    fn test_pdata_pdu_length() {
+        // PDU header plus a 6-byte header for each PDV
+        assert_eq!(pdata_pdu_length([100, 1000]), 6 + 106 + 1006);
+        assert_eq!(pdata_pdu_length([]), 6);
+    }
+
+    #[test]
+    fn test_settles_at_max_on_fast_link() {
+        // Fixed round-trip per fragment: bigger fragments are always better
+        let mut tuner = ChunkTuner::new(65536);
+        simulate(&mut tuner, 20, |size| Duration::from_micros(500) + Duration::from_nanos(size as u64));
+        assert_eq!(tuner.chunk_size(), 65530);
+    }
+
+    #[test]
+    fn test_backs_off_on_lossy_link() {
+        // Fragments above 16 KiB suffer retransmissions that grow with their size
+        let mut tuner = ChunkTuner::new(65536);
+        simulate(&mut tuner, 20, |size| {
+            let penalty = size.saturating_sub(16384) as u64 * 200;
+            Duration::from_micros(500) + Duration::from_nanos(size as u64 + penalty)
+        });
+        assert!(tuner.chunk_size() <= 32765, "stayed at {}", tuner.chunk_size());
+    }
+}
@@ -0,0 +1,154 @@
+/// Adaptive number of concurrent associations
+///
+/// Every finished study is a sample of the destination's responsiveness: its
+/// mean C-STORE latency and how many instances failed. The limit follows
+/// additive-increase/multiplicative-decrease: it grows by one after a run of
+/// healthy samples, halves when failures appear, and drops by one when latency
+/// climbs well above the best seen, so a struggling PACS gets relief while a
+/// fast one is kept busy.
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Latency, relative to the best sample, above which the destination counts as saturated
+const LATENCY_FACTOR: f64 = 2.0;
+/// Floor for the baseline, so millisecond rounding on a fast LAN does not look like saturation
+const MIN_BASELINE_MS: f64 = 5.0;
+/// Fraction of failed instances in a sample that counts as overload
+const FAILURE_THRESHOLD: f64 = 0.05;
+/// How often a worker above the limit checks whether it may resume
+const IDLE_POLL: Duration = Duration::from_millis(250);
+
+/// Change of the limit after a sample
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Adjustment {
+    pub from: usize,
+    pub to: usize,
+    pub reason: &'static str,
+}
+
+#[derive(Debug)]
+struct State {
+    limit: usize,
+    peak: usize,
+    baseline_ms: Option<f64>,
+    healthy_samples: usize,
+}
+
+#[derive(Debug)]
+pub struct ConcurrencyController {
+    min: usize,
+    max: usize,
+    state: Mutex<State>,
+}
+
+impl ConcurrencyController {
+    pub fn new(initial: usize, min: usize, max: usize) -> Self {
+        let min = min.max(1);
+        let max = max.max(min);
+        let limit = initial.clamp(min, max);
+        Self {
+            min,
+            max,
+            state: Mutex::new(State { limit, peak: limit, baseline_ms: None, healthy_samples: 0 }),
+        }
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, State> {
+        match self.state.lock() {
+            Ok(state) => state,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    /// Upper bound of the limit
+    pub fn max(&self) -> usize {
+        self.max
+    }
+
+    pub fn limit(&self) -> usize {
+        self.state().limit
+    }
+
+    /// Highest limit reached during the run
+    pub fn peak(&self) -> usize {
+        self.state().peak
+    }
+
+    /// Record a finished study: mean per-instance latency, instances sent and failed
+    pub fn record(&self, mean_latency_ms: f64, instances: usize, failed: usize) -> Option<Adjustment> {
+        if instances == 0 {
+            return None;
+        }
+        let mut state = self.state();
+        let from = state.limit;
+
+        let failure_rate = failed as f64 / instances as f64;
+        let baseline = state.baseline_ms.map_or(mean_latency_ms, |baseline| baseline.min(mean_latency_ms));
+        if failure_rate <= FAILURE_THRESHOLD {
+            state.baseline_ms = Some(baseline);
+        }
+
+        let (to, reason) = if failure_rate > FAILURE_THRESHOLD {
+            ((from / 2).max(self.min), "failures")
+        } else if mean_latency_ms > baseline.max(MIN_BASELINE_MS) * LATENCY_FACTOR {
+            (from.saturating_sub(1).max(self.min), "latency")
+        } else {
+            // Roughly one healthy sample per association before growing again
+            state.healthy_samples += 1;
+            if state.healthy_samples < from {
+                return None;
+            }
+            ((from + 1).min(self.max), "healthy")
+        };
+
+        state.healthy_samples = 0;
+        if to == from {
+            return None;
+        }
+        state.limit = to;
+        state.peak = state.peak.max(to);
+        Some(Adjustment { from, to, reason })
+    }
+
+    /// Wait until worker `slot` (0-based) is within the limit
+    pub async fn wait_for_slot(&self, slot: usize) {
+        while slot >= self.limit() {
+            tokio::time::sleep(IDLE_POLL).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grows_while_healthy() {
+        let controller = ConcurrencyController::new(1, 1, 3);
+        assert_eq!(controller.record(20.0, 100, 0), Some(Adjustment { from: 1, to: 2, reason: "healthy" }));
+        assert_eq!(controller.record(20.0, 100, 0), None);
+        assert_eq!(controller.record(22.0, 100, 0).map(|a| a.to), Some(3));
+        for _ in 0..5 {
+            assert_eq!(controller.record(20.0, 100, 0), None);
+        }
+        assert_eq!(controller.limit(), 3);
+        assert_eq!(controller.record(0.0, 0, 0), None);
+    }
+
+    #[test]
+    fn test_backs_off_on_failures_and_latency() {
+        let controller = ConcurrencyController::new(8, 2, 8);
+        assert_eq!(controller.record(20.0, 100, 0), None);
+        assert_eq!(controller.record(50.0, 100, 0), Some(Adjustment { from: 8, to: 7, reason: "latency" }));
+        assert_eq!(controller.record(20.0, 100, 10), Some(Adjustment { from: 7, to: 3, reason: "failures" }));
+        assert_eq!(controller.record(20.0, 100, 50).map(|a| a.to), Some(2));
+        assert_eq!(controller.record(20.0, 100, 50), None);
+        assert_eq!(controller.peak(), 8);
+
+        // Millisecond latencies on a fast link are not mistaken for saturation
+        let controller = ConcurrencyController::new(2, 1, 4);
+        controller.record(1.0, 100, 0);
+        assert_eq!(controller.record(4.0, 100, 0).map(|a| a.reason), Some("healthy"));
+    }
+}
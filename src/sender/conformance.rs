@@ -0,0 +1,241 @@
+/// Scripted conformance battery against a remote SCP
+///
+/// Each check opens its own association: C-ECHO, a C-STORE of a minimal
+/// object of each SOP class category's representative class in every native
+/// transfer syntax, a P-DATA PDU beyond the peer's maximum length and an
+/// association aborted mid-dataset. The misbehaving checks pass when the peer
+/// handles them and still answers a C-ECHO afterwards.
+
+use chrono::{DateTime, Utc};
+use dicom_core::value::PrimitiveValue;
+use dicom_core::{DataElement, Tag, VR};
+use dicom_object::InMemDicomObject;
+use serde::{Deserialize, Serialize};
+use std::time::Instant;
+
+use crate::common::sop_classes::{SopClassCategory, SopClassRegistry};
+use crate::common::transfer_syntaxes::{TransferSyntaxPolicy, TransferSyntaxRegistry};
+use super::dicom_client::{DicomClient, OversizedPduOutcome};
+use crate::common::uids::{sop, ts};
+
+pub const NATIVE_TRANSFER_SYNTAXES: &[&str] = &[
+    ts::IMPLICIT_VR_LITTLE_ENDIAN,
+    ts::EXPLICIT_VR_LITTLE_ENDIAN,
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CheckKind {
+    Echo,
+    Store { sop_class_uid: String, transfer_syntax: String },
+    OversizedPdu { sop_class_uid: String, transfer_syntax: String },
+    AbortMidStore { sop_class_uid: String, transfer_syntax: String },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Check {
+    pub name: String,
+    pub kind: CheckKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Passed,
+    Failed,
+    Skipped,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckResult {
+    pub name: String,
+    pub status: CheckStatus,
+    pub detail: String,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConformanceReport {
+    pub called_ae: String,
+    pub host: String,
+    pub port: u16,
+    pub run_at: DateTime<Utc>,
+    pub passed: usize,
+    pub failed: usize,
+    pub skipped: usize,
+    pub checks: Vec<CheckResult>,
+}
+
+impl ConformanceReport {
+    pub fn new(called_ae: &str, host: &str, port: u16, run_at: DateTime<Utc>, checks: Vec<CheckResult>) -> Self {
+        let count = |status| checks.iter().filter(|c| c.status == status).count();
+        Self {
+            called_ae: called_ae.to_string(),
+            host: host.to_string(),
+            port,
+            run_at,
+            passed: count(CheckStatus::Passed),
+            failed: count(CheckStatus::Failed),
+            skipped: count(CheckStatus::Skipped),
+            checks,
+        }
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("check,status,detail,duration_ms\n");
+        for check in &self.checks {
+            csv.push_str(&format!("\"{}\",{:?},\"{}\",{}\n", check.name, check.status,
+                                  check.detail.replace('"', "\"\""), check.duration_ms));
+        }
+        csv
+    }
+}
+
+/// The battery: C-ECHO, one store per category and transfer syntax, then the
+/// protocol checks using Secondary Capture in the most native of the syntaxes
+pub fn plan(registry: &SopClassRegistry, categories: &[SopClassCategory], transfer_syntaxes: &[String]) -> Vec<Check> {
+    let mut checks = vec![Check { name: "C-ECHO".to_string(), kind: CheckKind::Echo }];
+    for category in categories {
+        // A current SOP class where the category has one, so no check depends on a retired class
+        let candidates = registry.get_by_category(category.clone());
+        let Some(representative) = candidates.iter().find(|info| !info.is_retired()).or(candidates.first()) else {
+            continue;
+        };
+        for transfer_syntax in transfer_syntaxes {
+            checks.push(Check {
+                name: format!("C-STORE {:?} ({}) in {}", category, representative.name, transfer_syntax),
+                kind: CheckKind::Store {
+                    sop_class_uid: representative.uid.to_string(),
+                    transfer_syntax: transfer_syntax.clone(),
+                },
+            });
+        }
+    }
+
+    let secondary_capture = sop::SECONDARY_CAPTURE_IMAGE_STORAGE.to_string();
+    let transfer_syntax = TransferSyntaxRegistry::global()
+        .best_common(transfer_syntaxes, NATIVE_TRANSFER_SYNTAXES, TransferSyntaxPolicy::PreferNative)
+        .unwrap_or(ts::EXPLICIT_VR_LITTLE_ENDIAN)
+        .to_string();
+    checks.push(Check {
+        name: "P-DATA PDU beyond the maximum PDU length".to_string(),
+        kind: CheckKind::OversizedPdu { sop_class_uid: secondary_capture.clone(), transfer_syntax: transfer_syntax.clone() },
+    });
+    checks.push(Check {
+        name: "A-ABORT in the middle of a data set".to_string(),
+        kind: CheckKind::AbortMidStore { sop_class_uid: secondary_capture, transfer_syntax },
+    });
+    checks
+}
+
+/// Minimal object of a SOP class: identification, patient, study and series attributes
+pub fn test_object(sop_class_uid: &str) -> InMemDicomObject {
+    let uid = || format!("2.25.{}", uuid::Uuid::new_v4().as_u128());
+    let text = |tag: Tag, vr: VR, value: &str| DataElement::new(tag, vr, PrimitiveValue::from(value));
+    InMemDicomObject::from_element_iter([
+        text(Tag(0x0008, 0x0016), VR::UI, sop_class_uid),
+        text(Tag(0x0008, 0x0018), VR::UI, &uid()),
+        text(Tag(0x0008, 0x0020), VR::DA, &Utc::now().format("%Y%m%d").to_string()),
+        text(Tag(0x0008, 0x0060), VR::CS, "OT"),
+        text(Tag(0x0010, 0x0010), VR::PN, "CONFORMANCE^TEST"),
+        text(Tag(0x0010, 0x0020), VR::LO, "RUST_DICOM_CONFORMANCE"),
+        text(Tag(0x0020, 0x000D), VR::UI, &uid()),
+        text(Tag(0x0020, 0x000E), VR::UI, &uid()),
+        text(Tag(0x0020, 0x0013), VR::IS, "1"),
+    ])
+}
+
+fn status_text(status: u16) -> String {
+    match status {
+        0x0000 => "status 0x0000 (Success)".to_string(),
+        0xB000..=0xBFFF => format!("status 0x{:04X} (Warning)", status),
+        _ => format!("status 0x{:04X}", status),
+    }
+}
+
+/// After a misbehaving check, the peer must still answer a C-ECHO
+async fn still_alive(client: &DicomClient, outcome: String) -> (CheckStatus, String) {
+    match client.echo().await {
+        Ok(0x0000) => (CheckStatus::Passed, format!("{}; C-ECHO afterwards succeeded", outcome)),
+        Ok(status) => (CheckStatus::Failed, format!("{}; C-ECHO afterwards returned {}", outcome, status_text(status))),
+        Err(e) => (CheckStatus::Failed, format!("{}; peer unavailable afterwards: {}", outcome, e)),
+    }
+}
+
+pub async fn run_check(client: &DicomClient, check: &Check) -> CheckResult {
+    let started = Instant::now();
+    let (status, detail) = match &check.kind {
+        CheckKind::Echo => match client.echo().await {
+            Ok(0x0000) => (CheckStatus::Passed, status_text(0)),
+            Ok(status) => (CheckStatus::Failed, status_text(status)),
+            Err(e) => (CheckStatus::Failed, e.to_string()),
+        },
+        CheckKind::Store { sop_class_uid, transfer_syntax } => {
+            match client.store_dataset(test_object(sop_class_uid), transfer_syntax.clone()).await {
+                Ok(status) if status == 0x0000 || (0xB000..=0xBFFF).contains(&status) => (CheckStatus::Passed, status_text(status)),
+                Ok(status) => (CheckStatus::Failed, status_text(status)),
+                Err(e) if e.to_string().contains("was not accepted") => (CheckStatus::Skipped, e.to_string()),
+                Err(e) => (CheckStatus::Failed, e.to_string()),
+            }
+        }
+        CheckKind::OversizedPdu { sop_class_uid, transfer_syntax } => {
+            match client.store_oversized_pdu(test_object(sop_class_uid), transfer_syntax.clone()).await {
+                Ok(OversizedPduOutcome::Unlimited) => (CheckStatus::Skipped, "peer does not limit its PDU length".to_string()),
+                Ok(OversizedPduOutcome::Aborted) => still_alive(client, "peer aborted the association".to_string()).await,
+                Ok(OversizedPduOutcome::Answered(status)) => {
+                    still_alive(client, format!("peer accepted the oversized PDU with {}", status_text(status))).await
+                }
+                Err(e) => (CheckStatus::Failed, e.to_string()),
+            }
+        }
+        CheckKind::AbortMidStore { sop_class_uid, transfer_syntax } => {
+            match client.abort_mid_store(test_object(sop_class_uid), transfer_syntax.clone()).await {
+                Ok(()) => still_alive(client, "association aborted mid-dataset".to_string()).await,
+                Err(e) => (CheckStatus::Failed, e.to_string()),
+            }
+        }
+    };
+    CheckResult {
+        name: check.name.clone(),
+        status,
+        detail,
+        duration_ms: started.elapsed().as_millis() as u64,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan_covers_categories_and_syntaxes() {
+        let registry = SopClassRegistry::new();
+        let syntaxes: Vec<String> = NATIVE_TRANSFER_SYNTAXES.iter().map(|ts| ts.to_string()).collect();
+        let checks = plan(&registry, &[SopClassCategory::ComputedTomography, SopClassCategory::SecondaryCapture], &syntaxes);
+
+        assert_eq!(checks.len(), 1 + 2 * 2 + 2);
+        assert_eq!(checks[0].kind, CheckKind::Echo);
+        assert_eq!(checks[1].kind, CheckKind::Store {
+            sop_class_uid: registry.get_by_category(SopClassCategory::ComputedTomography)[0].uid.to_string(),
+            transfer_syntax: "1.2.840.10008.1.2".to_string(),
+        });
+        assert!(matches!(checks.last().map(|c| &c.kind),
+                         Some(CheckKind::AbortMidStore { transfer_syntax, .. }) if transfer_syntax == ts::EXPLICIT_VR_LITTLE_ENDIAN));
+
+        // Protocol checks stay within the syntaxes under test
+        let implicit_only = plan(&registry, &[], &[ts::IMPLICIT_VR_LITTLE_ENDIAN.to_string()]);
+        assert!(matches!(implicit_only.last().map(|c| &c.kind),
+                         Some(CheckKind::AbortMidStore { transfer_syntax, .. }) if transfer_syntax == ts::IMPLICIT_VR_LITTLE_ENDIAN));
+    }
+
+    #[test]
+    fn test_report_counts() {
+        let result = |name: &str, status| CheckResult { name: name.to_string(), status, detail: "x \"y\"".to_string(), duration_ms: 3 };
+        let report = ConformanceReport::new("PACS", "10.0.0.1", 104, Utc::now(), vec![
+            result("C-ECHO", CheckStatus::Passed),
+            result("C-STORE", CheckStatus::Failed),
+            result("PDU", CheckStatus::Skipped),
+        ]);
+        assert_eq!((report.passed, report.failed, report.skipped), (1, 1, 1));
+        assert_eq!(report.to_csv().lines().nth(2), Some("\"C-STORE\",Failed,\"x \"\"y\"\"\",3"));
+    }
+}
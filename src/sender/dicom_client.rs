@@ -3,13 +3,26 @@ use dicom_core::{Tag, DataElement, VR};
 use dicom_core::value::{Value, PrimitiveValue};
 use dicom_object::{open_file, InMemDicomObject};
 use std::collections::HashMap;
+use std::net::{SocketAddr, ToSocketAddrs};
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tracing::{debug, error, info, warn};
 use smallvec::smallvec;
 
-use crate::common::types::{DicomFile, TransferStats};
+use crate::common::types::{DicomFile, TransferResult, TransferStats};
 use crate::common::sop_classes::{SopClassRegistry, get_default_transfer_syntaxes, get_transfer_syntaxes_for_category};
 use crate::common::transfer_syntaxes::TransferSyntaxRegistry;
+use crate::common::repair::repair_dataset;
+use crate::common::manifest::{sha256_hex, ManifestEntry};
+use crate::common::preview::{preview_derivative, PreviewOptions};
+use crate::common::metrics::TransferMeter;
+use crate::common::rejection::{presentation_context_result_text, AssociationRejection};
+use crate::common::tls;
+use crate::common::types::RefusedPresentationContext;
+use super::chunking::{pdata_pdu_length, pdv_item_length, ChunkTuner, MIN_PACKED_FRAGMENT, RELEASE_PDU_LENGTH};
+use crate::common::uids::{sop, ts};
+
+pub const VERIFICATION_SOP_CLASS: &str = sop::VERIFICATION;
 
 #[derive(Debug, Clone)]
 pub struct DicomClientConfig {
@@ -18,15 +31,52 @@ pub struct DicomClientConfig {
     pub host: String,
     pub port: u16,
     pub timeout: Duration,
+    /// Per-address timeout when connecting to a host with several addresses
+    pub connect_timeout: Duration,
+    /// Repair common VR and value-length problems before transmission
+    pub lenient_repair: bool,
+    /// Hash each transmitted dataset for the verification manifest
+    pub compute_checksums: bool,
+    /// Pack the command and the first dataset fragment into one P-DATA PDU
+    pub pack_pdvs: bool,
+    /// Open associations over TLS with this configuration
+    pub tls: Option<Arc<rustls::ClientConfig>>,
+    /// Send reduced-resolution JPEG derivatives of images instead of the originals
+    pub preview: Option<PreviewOptions>,
+}
+
+/// How a peer dealt with a PDU beyond its maximum length
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OversizedPduOutcome {
+    /// Aborted or closed the association
+    Aborted,
+    /// Took the PDU anyway and answered with this status
+    Answered(u16),
+    /// The peer does not limit its PDU length
+    Unlimited,
 }
 
 pub struct DicomClient {
     config: DicomClientConfig,
+    meter: Option<Arc<TransferMeter>>,
+}
+
+/// Bytes sent and received on an association, PDU and PDV headers included
+#[derive(Debug, Default, Clone, Copy)]
+struct WireBytes {
+    sent: u64,
+    received: u64,
 }
 
 impl DicomClient {
     pub fn new(config: DicomClientConfig) -> Self {
-        Self { config }
+        Self { config, meter: None }
+    }
+
+    /// Add every instance to a shared meter as soon as it completes
+    pub fn with_meter(mut self, meter: Option<Arc<TransferMeter>>) -> Self {
+        self.meter = meter;
+        self
     }
 
     pub async fn send_files(&self, files: Vec<DicomFile>) -> Result<TransferStats> {
@@ -45,22 +95,28 @@ impl DicomClient {
         // Use blocking implementation - DICOM networking is synchronous
         let files_clone = files.clone();
         let config = self.config.clone();
+        let meter = self.meter.clone();
         
         let result = tokio::task::spawn_blocking(move || {
-            Self::send_files_blocking(&config, files_clone)
+            Self::send_files_blocking(&config, files_clone, meter.as_deref())
         }).await??;
 
         stats.total_files = result.total_files;
         stats.successful_transfers = result.successful_transfers;
         stats.failed_transfers = result.failed_transfers;
         stats.total_bytes = result.total_bytes;
+        stats.wire_bytes_sent = result.wire_bytes_sent;
+        stats.wire_bytes_received = result.wire_bytes_received;
         stats.total_time = start_time.elapsed();
         stats.transfer_times = result.transfer_times;
+        stats.manifest_entries = result.manifest_entries;
+        stats.results = result.results;
+        stats.refused_contexts = result.refused_contexts;
 
         Ok(stats)
     }
 
-    fn send_files_blocking(config: &DicomClientConfig, files: Vec<DicomFile>) -> Result<TransferStats> {
+    fn send_files_blocking(config: &DicomClientConfig, files: Vec<DicomFile>, meter: Option<&TransferMeter>) -> Result<TransferStats> {
         use dicom_ul::association::client::ClientAssociationOptions;
         
         let mut stats = TransferStats::new();
@@ -73,9 +129,8 @@ impl DicomClient {
             .called_ae_title(&config.called_ae)
             .max_pdu_length(65536); // Increase PDU size to handle larger files
 
-        // Initialize SOP class registry and transfer syntax registry
-        let sop_registry = SopClassRegistry::new();
-        let ts_registry = TransferSyntaxRegistry::new();
+        let sop_registry = SopClassRegistry::global();
+        let ts_registry = TransferSyntaxRegistry::global();
         
         info!("Registering SOP classes for the files to be sent...");
         
@@ -92,12 +147,13 @@ impl DicomClient {
         
         // Use basic transfer syntaxes to avoid too many contexts
         let transfer_syntaxes = vec![
-            "1.2.840.10008.1.2.1".to_string(), // Explicit VR Little Endian
-            "1.2.840.10008.1.2".to_string(),   // Implicit VR Little Endian
+            ts::EXPLICIT_VR_LITTLE_ENDIAN.to_string(),
+            ts::IMPLICIT_VR_LITTLE_ENDIAN.to_string(),
         ];
         let ts_refs: Vec<&String> = transfer_syntaxes.iter().collect();
         
-        // Store mapping of presentation context ID to SOP class UID for later reference
+        // Store mapping of presentation context ID to SOP class UID for later reference;
+        // dicom-ul numbers proposed contexts 1, 3, 5, ...
         let mut sop_uid_mapping = HashMap::new();
         let mut context_id = 1u8;
         
@@ -109,32 +165,73 @@ impl DicomClient {
                 association_options = association_options
                     .with_presentation_context(sop_uid, ts_refs.clone());
                 sop_uid_mapping.insert(context_id, sop_uid.clone());
-                context_id += 1;
+                context_id += 2;
             } else {
                 warn!("Unknown SOP class in files: {}, adding with basic transfer syntaxes", sop_uid);
                 association_options = association_options
                     .with_presentation_context(sop_uid, ts_refs.clone());
                 sop_uid_mapping.insert(context_id, sop_uid.clone());
-                context_id += 1;
+                context_id += 2;
+            }
+            if config.preview.is_some() {
+                // Previews travel in JPEG Baseline, on a context of their own
+                association_options = association_options
+                    .with_presentation_context(sop_uid, vec![ts::JPEG_BASELINE]);
+                sop_uid_mapping.insert(context_id, sop_uid.clone());
+                context_id += 2;
             }
         }
         
         info!("Transfer syntax coverage: {} unique transfer syntaxes available", 
               ts_registry.get_all_uids().len());
 
-        // Establish the association
-        debug!("Attempting to establish association with {}:{}", config.host, config.port);
-        let mut association = match association_options
-            .establish_with(&format!("{}:{}", config.host, config.port)) {
-                Ok(assoc) => {
-                    info!("DICOM association established successfully");
-                    assoc
-                },
+        // Establish the association, trying each resolved address in turn
+        let addresses = Self::resolve_addresses(&config.host, config.port)?;
+        debug!("Attempting to establish association with {}:{} ({} addresses)",
+               config.host, config.port, addresses.len());
+
+        let mut last_error = None;
+        let mut rejection = None;
+        let mut established = None;
+        for address in &addresses {
+            let target = match Self::association_address(config, *address) {
+                Ok(target) => target,
                 Err(e) => {
-                    error!("Failed to establish DICOM association: {}", e);
-                    return Err(anyhow::anyhow!("Failed to establish DICOM association: {}", e));
+                    warn!("TLS connection to {} failed: {:#}", address, e);
+                    last_error = Some(format!("{:#}", e));
+                    continue;
                 }
             };
+            match association_options.clone()
+                .connection_timeout(config.connect_timeout)
+                .establish(target) {
+                    Ok(assoc) => {
+                        info!("DICOM association established successfully via {}", address);
+                        established = Some(assoc);
+                        break;
+                    },
+                    Err(e) => {
+                        warn!("Association attempt to {} failed: {}", address, e);
+                        if let dicom_ul::association::client::Error::Rejected { association_rj, .. } = &e {
+                            rejection = Some(Self::rejection_codes(association_rj));
+                        }
+                        last_error = Some(e.to_string());
+                    }
+                }
+        }
+
+        let mut association = match established {
+            Some(assoc) => assoc,
+            None => {
+                if let Some(rejection) = rejection {
+                    error!("Failed to establish DICOM association: {}", rejection);
+                    return Err(anyhow::Error::new(rejection));
+                }
+                let e = last_error.unwrap_or_else(|| "no addresses".to_string());
+                error!("Failed to establish DICOM association: {}", e);
+                return Err(anyhow::anyhow!("Failed to establish DICOM association: {}", e));
+            }
+        };
         
         // Report which presentation contexts were accepted
         let mut accepted_contexts = 0;
@@ -156,6 +253,15 @@ impl DicomClient {
                 }
                 _ => {
                     rejected_contexts += 1;
+                    if let Some(sop_uid) = sop_uid_mapping.get(&pc.id) {
+                        let result = Self::context_result_code(&pc.reason);
+                        warn!("Presentation context for {} refused: {}", sop_uid, presentation_context_result_text(result));
+                        stats.refused_contexts.push(RefusedPresentationContext {
+                            sop_class_uid: sop_uid.clone(),
+                            result,
+                            description: presentation_context_result_text(result).to_string(),
+                        });
+                    }
                     if let Some(sop_uid) = sop_uid_mapping.get(&pc.id) {
                         if let Some(sop_info) = sop_registry.get(sop_uid.as_str()) {
                             debug!("✗ Rejected: {} (ID={}, UID={})", sop_info.name, pc.id, sop_uid);
@@ -171,16 +277,27 @@ impl DicomClient {
         
         info!("Presentation contexts: {} accepted, {} rejected", accepted_contexts, rejected_contexts);
 
-        // Send each file
+        let mut tuner = ChunkTuner::new(association.acceptor_max_pdu_length());
+        debug!("Peer accepts PDUs of up to {} bytes, starting with {}-byte fragments",
+               association.acceptor_max_pdu_length(), tuner.chunk_size());
+
+        // Send each file; wire bytes include attempts that fail part-way
+        let mut wire = WireBytes::default();
         for (idx, file) in files.iter().enumerate() {
             let file_start = Instant::now();
             
-            match Self::send_single_file_simple(&mut association, file, idx as u16 + 1, &sop_uid_mapping) {
-                Ok(bytes_sent) => {
+            match Self::send_single_file_simple(&mut association, file, idx as u16 + 1, &sop_uid_mapping,
+                                               sop_registry, ts_registry, config, &mut tuner, &mut wire) {
+                Ok((bytes_sent, manifest_entry)) => {
                     let transfer_time = file_start.elapsed();
                     stats.successful_transfers += 1;
                     stats.total_bytes += bytes_sent;
                     stats.transfer_times.push(transfer_time);
+                    stats.manifest_entries.extend(manifest_entry);
+                    stats.results.push(TransferResult { bytes_sent, ..Self::transfer_result(file, None, transfer_time) });
+                    if let Some(meter) = meter {
+                        meter.record(true, bytes_sent, transfer_time);
+                    }
                     
                     info!(
                         "✓ Sent {} ({} bytes) in {:?}",
@@ -191,19 +308,29 @@ impl DicomClient {
                 }
                 Err(e) => {
                     stats.failed_transfers += 1;
+                    stats.results.push(Self::transfer_result(file, Some(e.to_string()), file_start.elapsed()));
+                    if let Some(meter) = meter {
+                        meter.record(false, 0, file_start.elapsed());
+                    }
                     error!("✗ Failed to send {}: {}", file.path.display(), e);
                 }
             }
         }
 
         stats.total_files = files.len();
+        info!("Dataset fragment size settled at {} bytes", tuner.chunk_size());
 
         // Release the association
         if let Err(e) = association.release() {
             warn!("Failed to properly release association: {}", e);
         } else {
+            wire.sent += RELEASE_PDU_LENGTH;
+            wire.received += RELEASE_PDU_LENGTH;
             info!("DICOM association released successfully");
         }
+        stats.wire_bytes_sent = wire.sent;
+        stats.wire_bytes_received = wire.received;
+        debug!("Wire traffic: {} bytes sent, {} bytes received", wire.sent, wire.received);
 
         info!(
             "Transfer completed: {}/{} files sent successfully",
@@ -213,25 +340,459 @@ impl DicomClient {
         Ok(stats)
     }
 
+    /// Propose each (SOP class, transfer syntax) pair as its own presentation
+    /// context and return the result code of each, in order. `None` means the
+    /// context was not accepted but the peer's result code was not reported.
+    pub async fn probe_contexts(&self, combinations: Vec<(String, String)>) -> Result<Vec<Option<u8>>> {
+        let config = self.config.clone();
+        tokio::task::spawn_blocking(move || Self::probe_contexts_blocking(&config, &combinations)).await?
+    }
+
+    fn probe_contexts_blocking(config: &DicomClientConfig, combinations: &[(String, String)]) -> Result<Vec<Option<u8>>> {
+        use dicom_ul::association::client::{ClientAssociationOptions, Error as ClientError};
+
+        let mut association_options = ClientAssociationOptions::new()
+            .calling_ae_title(&config.calling_ae)
+            .called_ae_title(&config.called_ae);
+        for (sop_class_uid, ts_uid) in combinations {
+            association_options = association_options.with_presentation_context(sop_class_uid, vec![ts_uid]);
+        }
+
+        let addresses = Self::resolve_addresses(&config.host, config.port)?;
+        let mut last_error = None;
+        for address in &addresses {
+            let target = match Self::association_address(config, *address) {
+                Ok(target) => target,
+                Err(e) => {
+                    warn!("TLS connection to {} failed: {:#}", address, e);
+                    last_error = Some(format!("{:#}", e));
+                    continue;
+                }
+            };
+            match association_options.clone()
+                .connection_timeout(config.connect_timeout)
+                .establish(target) {
+                    Ok(association) => {
+                        // Context IDs are assigned 1, 3, 5, ... in proposal order
+                        let mut results = vec![None; combinations.len()];
+                        for pc in association.presentation_contexts() {
+                            if let Some(result) = results.get_mut(pc.id as usize / 2) {
+                                *result = Some(Self::context_result_code(&pc.reason));
+                            }
+                        }
+                        if let Err(e) = association.release() {
+                            warn!("Failed to properly release probe association: {}", e);
+                        }
+                        return Ok(results);
+                    }
+                    Err(ClientError::NoAcceptedPresentationContexts { .. }) => {
+                        debug!("No presentation context of this probe batch was accepted");
+                        return Ok(vec![None; combinations.len()]);
+                    }
+                    Err(ClientError::Rejected { association_rj, .. }) => {
+                        return Err(anyhow::Error::new(Self::rejection_codes(&association_rj)));
+                    }
+                    Err(e) => {
+                        warn!("Probe association attempt to {} failed: {}", address, e);
+                        last_error = Some(e.to_string());
+                    }
+                }
+        }
+        let e = last_error.unwrap_or_else(|| "no addresses".to_string());
+        Err(anyhow::anyhow!("Failed to establish DICOM association: {}", e))
+    }
+
+    /// Open an association proposing a single presentation context
+    fn establish_single(
+        config: &DicomClientConfig,
+        sop_class_uid: &str,
+        transfer_syntax: &str,
+    ) -> Result<dicom_ul::ClientAssociation<std::net::TcpStream>> {
+        use dicom_ul::association::client::{ClientAssociationOptions, Error as ClientError};
+
+        let association_options = ClientAssociationOptions::new()
+            .calling_ae_title(&config.calling_ae)
+            .called_ae_title(&config.called_ae)
+            .with_presentation_context(sop_class_uid, vec![transfer_syntax]);
+
+        let addresses = Self::resolve_addresses(&config.host, config.port)?;
+        let mut last_error = None;
+        for address in &addresses {
+            let target = match Self::association_address(config, *address) {
+                Ok(target) => target,
+                Err(e) => {
+                    warn!("TLS connection to {} failed: {:#}", address, e);
+                    last_error = Some(format!("{:#}", e));
+                    continue;
+                }
+            };
+            match association_options.clone()
+                .connection_timeout(config.connect_timeout)
+                .establish(target) {
+                    Ok(association) => return Ok(association),
+                    Err(ClientError::Rejected { association_rj, .. }) => {
+                        return Err(anyhow::Error::new(Self::rejection_codes(&association_rj)));
+                    }
+                    Err(e) => {
+                        warn!("Association attempt to {} failed: {}", address, e);
+                        last_error = Some(e.to_string());
+                    }
+                }
+        }
+        let e = last_error.unwrap_or_else(|| "no addresses".to_string());
+        Err(anyhow::anyhow!("Failed to establish DICOM association: {}", e))
+    }
+
+    /// Command set of a DIMSE request, encoded in Implicit VR Little Endian
+    fn command_set(command_field: u16, message_id: u16, sop_class_uid: &str, sop_instance_uid: Option<&str>) -> Result<Vec<u8>> {
+        let mut command_obj = InMemDicomObject::new_empty();
+        command_obj.put(DataElement::new(Tag(0x0000, 0x0002), VR::UI, PrimitiveValue::from(sop_class_uid)));
+        command_obj.put(DataElement::new(Tag(0x0000, 0x0100), VR::US, PrimitiveValue::from(command_field)));
+        command_obj.put(DataElement::new(Tag(0x0000, 0x0110), VR::US, PrimitiveValue::from(message_id)));
+        match sop_instance_uid {
+            Some(sop_instance_uid) => {
+                command_obj.put(DataElement::new(Tag(0x0000, 0x0700), VR::US, PrimitiveValue::from(0u16)));
+                command_obj.put(DataElement::new(Tag(0x0000, 0x0800), VR::US, PrimitiveValue::from(0x0001u16)));
+                command_obj.put(DataElement::new(Tag(0x0000, 0x1000), VR::UI, PrimitiveValue::from(sop_instance_uid)));
+            }
+            // No data set follows
+            None => command_obj.put(DataElement::new(Tag(0x0000, 0x0800), VR::US, PrimitiveValue::from(0x0101u16))),
+        }
+
+        let mut buffer = Vec::new();
+        command_obj.write_dataset_with_ts(
+            &mut buffer,
+            &dicom_transfer_syntax_registry::entries::IMPLICIT_VR_LITTLE_ENDIAN.erased(),
+        )?;
+        Ok(buffer)
+    }
+
+    /// Wait for the response command and return its Status (0000,0900)
+    fn response_status(association: &mut dicom_ul::ClientAssociation<std::net::TcpStream>) -> Result<u16> {
+        Self::receive_status(association, &mut WireBytes::default())
+    }
+
+    /// Wait for the response command, counting the PDUs it arrives in
+    fn receive_status(association: &mut dicom_ul::ClientAssociation<std::net::TcpStream>, wire: &mut WireBytes) -> Result<u16> {
+        use dicom_ul::pdu::{PDataValueType, Pdu};
+
+        loop {
+            match association.receive()? {
+                Pdu::PData { data } => {
+                    wire.received += pdata_pdu_length(data.iter().map(|pdv| pdv.data.len()));
+                    let command = data.iter().find(|pdv| pdv.value_type == PDataValueType::Command);
+                    if let Some(command) = command {
+                        let obj = InMemDicomObject::read_dataset_with_ts(
+                            &command.data[..],
+                            &dicom_transfer_syntax_registry::entries::IMPLICIT_VR_LITTLE_ENDIAN.erased(),
+                        )?;
+                        return obj.element(Tag(0x0000, 0x0900))
+                            .context("Response has no Status")?
+                            .to_int::<u16>()
+                            .context("Response Status is not a number");
+                    }
+                }
+                Pdu::AbortRQ { .. } => anyhow::bail!("Association aborted by the peer"),
+                other => anyhow::bail!("Unexpected PDU while waiting for a response: {:?}", other),
+            }
+        }
+    }
+
+    /// Negotiated dataset encoding for an uncompressed transfer syntax
+    fn native_transfer_syntax(transfer_syntax: &str) -> Result<dicom::encoding::TransferSyntax> {
+        match transfer_syntax {
+            ts::IMPLICIT_VR_LITTLE_ENDIAN => Ok(dicom_transfer_syntax_registry::entries::IMPLICIT_VR_LITTLE_ENDIAN.erased()),
+            ts::EXPLICIT_VR_LITTLE_ENDIAN => Ok(dicom_transfer_syntax_registry::entries::EXPLICIT_VR_LITTLE_ENDIAN.erased()),
+            other => anyhow::bail!("Only native little endian transfer syntaxes can be encoded, not {}", other),
+        }
+    }
+
+    /// Send a C-ECHO over a new association and return the response status
+    pub async fn echo(&self) -> Result<u16> {
+        let config = self.config.clone();
+        tokio::task::spawn_blocking(move || {
+            use dicom_ul::pdu::{PDataValue, PDataValueType};
+
+            let mut association = Self::establish_single(&config, VERIFICATION_SOP_CLASS, ts::IMPLICIT_VR_LITTLE_ENDIAN)?;
+            let presentation_context_id = association.presentation_contexts().first()
+                .map(|pc| pc.id)
+                .ok_or_else(|| anyhow::anyhow!("Verification SOP Class was not accepted"))?;
+            let command = Self::command_set(0x0030, 1, VERIFICATION_SOP_CLASS, None)?;
+            Self::send_pdata(&mut association, vec![PDataValue {
+                presentation_context_id,
+                value_type: PDataValueType::Command,
+                is_last: true,
+                data: command,
+            }], &mut WireBytes::default())?;
+            let status = Self::response_status(&mut association)?;
+            if let Err(e) = association.release() {
+                warn!("Failed to properly release echo association: {}", e);
+            }
+            Ok(status)
+        }).await?
+    }
+
+    /// Store one dataset over a new association proposing only `transfer_syntax`
+    /// (native little endian) and return the response status
+    pub async fn store_dataset(&self, mut dataset: InMemDicomObject, transfer_syntax: String) -> Result<u16> {
+        let config = self.config.clone();
+        tokio::task::spawn_blocking(move || {
+            use dicom_ul::pdu::{PDataValue, PDataValueType};
+
+            let ts = Self::native_transfer_syntax(&transfer_syntax)?;
+            let mut buffer = Vec::new();
+            let (sop_class_uid, sop_instance_uid) = Self::prepare_dataset(&mut dataset, &mut buffer, &ts)?;
+
+            let mut association = Self::establish_single(&config, &sop_class_uid, &transfer_syntax)?;
+            let presentation_context_id = association.presentation_contexts().first()
+                .map(|pc| pc.id)
+                .ok_or_else(|| anyhow::anyhow!("{} in {} was not accepted", sop_class_uid, transfer_syntax))?;
+            let command = Self::command_set(0x0001, 1, &sop_class_uid, Some(&sop_instance_uid))?;
+            let mut wire = WireBytes::default();
+            Self::send_pdata(&mut association, vec![PDataValue {
+                presentation_context_id,
+                value_type: PDataValueType::Command,
+                is_last: true,
+                data: command,
+            }], &mut wire)?;
+
+            let fragments: Vec<&[u8]> = buffer.chunks(ChunkTuner::new(association.acceptor_max_pdu_length()).chunk_size()).collect();
+            for (index, chunk) in fragments.iter().enumerate() {
+                Self::send_pdata(&mut association, vec![PDataValue {
+                    presentation_context_id,
+                    value_type: PDataValueType::Data,
+                    is_last: index + 1 == fragments.len(),
+                    data: chunk.to_vec(),
+                }], &mut wire)?;
+            }
+            let status = Self::response_status(&mut association)?;
+            if let Err(e) = association.release() {
+                warn!("Failed to properly release association: {}", e);
+            }
+            Ok(status)
+        }).await?
+    }
+
+    /// Store a data set already encoded in `transfer_syntax`, read from
+    /// `dataset` and sent byte for byte as it is read, over a new association
+    /// proposing only that syntax; blocking. Returns the response status
+    pub fn relay_dataset(&self, sop_class_uid: &str, sop_instance_uid: &str, transfer_syntax: &str,
+                         dataset: &mut dyn std::io::Read) -> Result<u16> {
+        use dicom_ul::pdu::{PDataValue, PDataValueType};
+        use std::io::Read;
+
+        let mut association = Self::establish_single(&self.config, sop_class_uid, transfer_syntax)?;
+        let presentation_context_id = association.presentation_contexts().first()
+            .map(|pc| pc.id)
+            .ok_or_else(|| anyhow::anyhow!("{} in {} was not accepted", sop_class_uid, transfer_syntax))?;
+        let command = Self::command_set(0x0001, 1, sop_class_uid, Some(sop_instance_uid))?;
+        let mut wire = WireBytes::default();
+        Self::send_pdata(&mut association, vec![PDataValue {
+            presentation_context_id,
+            value_type: PDataValueType::Command,
+            is_last: true,
+            data: command,
+        }], &mut wire)?;
+
+        // Read one fragment ahead so the last one can be flagged as such
+        let chunk_size = ChunkTuner::new(association.acceptor_max_pdu_length()).chunk_size() as u64;
+        let read_fragment = |dataset: &mut dyn std::io::Read| -> Result<Vec<u8>> {
+            let mut fragment = Vec::new();
+            (&mut *dataset).take(chunk_size).read_to_end(&mut fragment)?;
+            Ok(fragment)
+        };
+        let mut fragment = read_fragment(dataset)?;
+        loop {
+            let next = read_fragment(dataset)?;
+            let is_last = next.is_empty();
+            Self::send_pdata(&mut association, vec![PDataValue {
+                presentation_context_id,
+                value_type: PDataValueType::Data,
+                is_last,
+                data: fragment,
+            }], &mut wire)?;
+            if is_last {
+                break;
+            }
+            fragment = next;
+        }
+        let status = Self::response_status(&mut association)?;
+        if let Err(e) = association.release() {
+            warn!("Failed to properly release association: {}", e);
+        }
+        debug!("Relayed {} ({} bytes on the wire)", sop_instance_uid, wire.sent);
+        Ok(status)
+    }
+
+    /// Send a C-STORE whose dataset travels in a single P-DATA PDU larger than
+    /// the peer's maximum PDU length, padded with a private element as needed
+    pub async fn store_oversized_pdu(&self, mut dataset: InMemDicomObject, transfer_syntax: String) -> Result<OversizedPduOutcome> {
+        let config = self.config.clone();
+        tokio::task::spawn_blocking(move || {
+            use dicom_ul::pdu::{PDataValue, PDataValueType, Pdu};
+            use std::io::Write;
+
+            let sop_class_uid = dataset.element(Tag(0x0008, 0x0016)).ok()
+                .and_then(|e| e.to_str().ok())
+                .map(|uid| uid.trim_end_matches('\0').trim().to_string())
+                .ok_or_else(|| anyhow::anyhow!("Dataset has no SOP Class UID"))?;
+            let ts = Self::native_transfer_syntax(&transfer_syntax)?;
+            let mut association = Self::establish_single(&config, &sop_class_uid, &transfer_syntax)?;
+            let presentation_context_id = association.presentation_contexts().first()
+                .map(|pc| pc.id)
+                .ok_or_else(|| anyhow::anyhow!("{} in {} was not accepted", sop_class_uid, transfer_syntax))?;
+            let max_pdu = association.acceptor_max_pdu_length() as usize;
+            if max_pdu == 0 {
+                let _ = association.release();
+                return Ok(OversizedPduOutcome::Unlimited);
+            }
+
+            dataset.put(DataElement::new(Tag(0x0009, 0x0010), VR::LO, PrimitiveValue::from("RUST_DICOM")));
+            dataset.put(DataElement::new(Tag(0x0009, 0x1001), VR::OB, PrimitiveValue::U8(vec![0u8; max_pdu + 1024].into())));
+            let mut buffer = Vec::new();
+            let (sop_class_uid, sop_instance_uid) = Self::prepare_dataset(&mut dataset, &mut buffer, &ts)?;
+            let command = Self::command_set(0x0001, 1, &sop_class_uid, Some(&sop_instance_uid))?;
+            Self::send_pdata(&mut association, vec![PDataValue {
+                presentation_context_id,
+                value_type: PDataValueType::Command,
+                is_last: true,
+                data: command,
+            }], &mut WireBytes::default())?;
+
+            // dicom-ul refuses to send PDUs the peer cannot take, so write this one directly
+            let mut encoded = Vec::new();
+            dicom_ul::pdu::write_pdu(&mut encoded, &Pdu::PData {
+                data: vec![PDataValue { presentation_context_id, value_type: PDataValueType::Data, is_last: true, data: buffer }],
+            })?;
+            if let Err(e) = association.inner_stream().write_all(&encoded) {
+                debug!("Peer closed the connection during the oversized PDU: {}", e);
+                return Ok(OversizedPduOutcome::Aborted);
+            }
+            match Self::response_status(&mut association) {
+                Ok(status) => {
+                    let _ = association.release();
+                    Ok(OversizedPduOutcome::Answered(status))
+                }
+                Err(e) => {
+                    debug!("Peer did not answer the oversized PDU: {}", e);
+                    Ok(OversizedPduOutcome::Aborted)
+                }
+            }
+        }).await?
+    }
+
+    /// Start a C-STORE, send part of its dataset and abort the association
+    pub async fn abort_mid_store(&self, mut dataset: InMemDicomObject, transfer_syntax: String) -> Result<()> {
+        let config = self.config.clone();
+        tokio::task::spawn_blocking(move || {
+            use dicom_ul::pdu::{PDataValue, PDataValueType};
+
+            let ts = Self::native_transfer_syntax(&transfer_syntax)?;
+            let mut buffer = Vec::new();
+            let (sop_class_uid, sop_instance_uid) = Self::prepare_dataset(&mut dataset, &mut buffer, &ts)?;
+
+            let mut association = Self::establish_single(&config, &sop_class_uid, &transfer_syntax)?;
+            let presentation_context_id = association.presentation_contexts().first()
+                .map(|pc| pc.id)
+                .ok_or_else(|| anyhow::anyhow!("{} in {} was not accepted", sop_class_uid, transfer_syntax))?;
+            let command = Self::command_set(0x0001, 1, &sop_class_uid, Some(&sop_instance_uid))?;
+            buffer.truncate(buffer.len() / 2);
+            Self::send_pdata(&mut association, vec![
+                PDataValue { presentation_context_id, value_type: PDataValueType::Command, is_last: true, data: command },
+                PDataValue { presentation_context_id, value_type: PDataValueType::Data, is_last: false, data: buffer },
+            ], &mut WireBytes::default())?;
+            association.abort()?;
+            Ok(())
+        }).await?
+    }
+
+    /// Standard result/source/reason codes of an A-ASSOCIATE-RJ
+    fn rejection_codes(association_rj: &dicom_ul::pdu::AssociationRJ) -> AssociationRejection {
+        use dicom_ul::pdu::{
+            AssociationRJResult, AssociationRJServiceProviderASCEReason as AcseReason,
+            AssociationRJServiceProviderPresentationReason as PresentationReason,
+            AssociationRJServiceUserReason as UserReason, AssociationRJSource,
+        };
+
+        let result = match association_rj.result {
+            AssociationRJResult::Permanent => 1,
+            AssociationRJResult::Transient => 2,
+        };
+        let (source, reason) = match &association_rj.source {
+            AssociationRJSource::ServiceUser(reason) => (1, match reason {
+                UserReason::NoReasonGiven => 1,
+                UserReason::ApplicationContextNameNotSupported => 2,
+                UserReason::CallingAETitleNotRecognized => 3,
+                UserReason::CalledAETitleNotRecognized => 7,
+                UserReason::Reserved(code) => *code,
+            }),
+            AssociationRJSource::ServiceProviderASCE(reason) => (2, match reason {
+                AcseReason::NoReasonGiven => 1,
+                AcseReason::ProtocolVersionNotSupported => 2,
+            }),
+            AssociationRJSource::ServiceProviderPresentation(reason) => (3, match reason {
+                PresentationReason::TemporaryCongestion => 1,
+                PresentationReason::LocalLimitExceeded => 2,
+                PresentationReason::Reserved(code) => *code,
+            }),
+        };
+        AssociationRejection { result, source, reason }
+    }
+
+    fn context_result_code(reason: &dicom_ul::pdu::PresentationContextResultReason) -> u8 {
+        use dicom_ul::pdu::PresentationContextResultReason;
+        match reason {
+            PresentationContextResultReason::Acceptance => 0,
+            PresentationContextResultReason::UserRejection => 1,
+            PresentationContextResultReason::NoReason => 2,
+            PresentationContextResultReason::AbstractSyntaxNotSupported => 3,
+            PresentationContextResultReason::TransferSyntaxesNotSupported => 4,
+        }
+    }
+
     fn send_single_file_simple(
         association: &mut dicom_ul::ClientAssociation<std::net::TcpStream>,
         file: &DicomFile,
         message_id: u16,
         sop_uid_mapping: &HashMap<u8, String>,
-    ) -> Result<u64> {
-        use dicom_ul::pdu::{Pdu, PDataValue, PDataValueType};
+        sop_registry: &SopClassRegistry,
+        ts_registry: &TransferSyntaxRegistry,
+        config: &DicomClientConfig,
+        tuner: &mut ChunkTuner,
+        wire: &mut WireBytes,
+    ) -> Result<(u64, Option<ManifestEntry>)> {
+        use dicom_ul::pdu::{PDataValue, PDataValueType};
         
         // Read the DICOM file
-        let obj = open_file(&file.path)
+        let mut obj = open_file(&file.path)
             .context(format!("Failed to open DICOM file: {}", file.path.display()))?;
 
+        if config.lenient_repair {
+            for repair in repair_dataset(&mut obj) {
+                warn!("Repaired {} before sending: {}", file.path.display(), repair);
+            }
+        }
+
+        // A preview destination must never receive the full-fidelity original of an image
+        let mut previewed = false;
+        if let Some(options) = &config.preview {
+            match preview_derivative(&obj, options) {
+                Ok(Some(preview)) => {
+                    info!("Sending preview {} of {}", preview.meta().media_storage_sop_instance_uid().trim_end_matches('\0'), file.sop_instance_uid);
+                    obj = preview;
+                    previewed = true;
+                }
+                Ok(None) => debug!("{} has no pixel data, sending it as is", file.path.display()),
+                Err(e) => return Err(anyhow::anyhow!("Cannot make a preview of {}: {}", file.path.display(), e)),
+            }
+        }
+
         debug!(
             "Sending C-STORE for SOP Class: {}, SOP Instance: {}, Message ID: {}",
             file.sop_class_uid, file.sop_instance_uid, message_id
         );
 
         // Validate that this SOP class is in our registry
-        let sop_registry = SopClassRegistry::new();
         if let Some(sop_info) = sop_registry.get(file.sop_class_uid.as_str()) {
             debug!("SOP Class identified: {} (Category: {:?})", sop_info.name, sop_info.category);
         } else {
@@ -247,7 +808,9 @@ impl DicomClient {
             if pc.reason == dicom_ul::pdu::PresentationContextResultReason::Acceptance {
                 // Check if this presentation context matches our SOP class
                 if let Some(sop_uid) = sop_uid_mapping.get(&pc.id) {
-                    if sop_uid == &file.sop_class_uid {
+                    // With previews on, the JPEG Baseline context carries the previews and only them
+                    let preview_context = pc.transfer_syntax.trim_end_matches('\0') == ts::JPEG_BASELINE;
+                    if sop_uid == &file.sop_class_uid && (config.preview.is_none() || preview_context == previewed) {
                         presentation_context_id = Some(pc.id);
                         selected_transfer_syntax = Some(pc.transfer_syntax.clone());
                         debug!("Found matching presentation context for SOP class {}: ID={}, Transfer Syntax={}", 
@@ -268,71 +831,70 @@ impl DicomClient {
         let transfer_syntax = selected_transfer_syntax
             .ok_or_else(|| anyhow::anyhow!("No transfer syntax found for accepted presentation context"))?;
         
-        info!("Using presentation context ID: {} with transfer syntax: {}", 
+        debug!("Using presentation context ID: {} with transfer syntax: {}", 
               presentation_context_id, transfer_syntax);
-        info!("File SOP Class: {}, SOP Instance: {}", file.sop_class_uid, file.sop_instance_uid);
+        debug!("File SOP Class: {}, SOP Instance: {}", file.sop_class_uid, file.sop_instance_uid);
 
         // Prepare the dataset for transmission using the negotiated transfer syntax
         let mut dataset_buffer = Vec::new();
         
         // Map the negotiated transfer syntax UID to the appropriate registry entry
-        let ts_registry = TransferSyntaxRegistry::new();
         let ts_to_use = match transfer_syntax.as_str() {
             // Uncompressed transfer syntaxes
-            "1.2.840.10008.1.2" => {
-                info!("Using Implicit VR Little Endian");
+            ts::IMPLICIT_VR_LITTLE_ENDIAN => {
+                debug!("Using Implicit VR Little Endian");
                 &dicom_transfer_syntax_registry::entries::IMPLICIT_VR_LITTLE_ENDIAN.erased()
             }
-            "1.2.840.10008.1.2.1" => {
-                info!("Using Explicit VR Little Endian");
+            ts::EXPLICIT_VR_LITTLE_ENDIAN => {
+                debug!("Using Explicit VR Little Endian");
                 &dicom_transfer_syntax_registry::entries::EXPLICIT_VR_LITTLE_ENDIAN.erased()
             }
-            "1.2.840.10008.1.2.2" => {
-                info!("Using Explicit VR Big Endian (Legacy)");
-                // Note: Big Endian support may be limited in some implementations
+            ts::EXPLICIT_VR_BIG_ENDIAN => {
+                // Never proposed by this sender, but honour it if a peer selects it anyway
+                warn!("Peer negotiated retired Explicit VR Big Endian");
                 &dicom_transfer_syntax_registry::entries::EXPLICIT_VR_BIG_ENDIAN.erased()
             }
             
             // JPEG Baseline and Extended
-            "1.2.840.10008.1.2.4.50" => {
-                info!("Using JPEG Baseline (Process 1)");
+            ts::JPEG_BASELINE => {
+                debug!("Using JPEG Baseline (Process 1)");
                 // For JPEG, we need to handle encapsulated pixel data
                 &dicom_transfer_syntax_registry::entries::EXPLICIT_VR_LITTLE_ENDIAN.erased()
             }
-            "1.2.840.10008.1.2.4.51" => {
-                info!("Using JPEG Extended (Process 2 & 4)");
+            ts::JPEG_EXTENDED => {
+                debug!("Using JPEG Extended (Process 2 & 4)");
                 &dicom_transfer_syntax_registry::entries::EXPLICIT_VR_LITTLE_ENDIAN.erased()
             }
             
             // JPEG Lossless
-            "1.2.840.10008.1.2.4.57" | "1.2.840.10008.1.2.4.70" => {
-                info!("Using JPEG Lossless");
+            ts::JPEG_LOSSLESS | ts::JPEG_LOSSLESS_SV1 => {
+                debug!("Using JPEG Lossless");
                 &dicom_transfer_syntax_registry::entries::EXPLICIT_VR_LITTLE_ENDIAN.erased()
             }
             
             // JPEG-LS
-            "1.2.840.10008.1.2.4.80" => {
-                info!("Using JPEG-LS Lossless");
+            ts::JPEG_LS_LOSSLESS => {
+                debug!("Using JPEG-LS Lossless");
                 &dicom_transfer_syntax_registry::entries::EXPLICIT_VR_LITTLE_ENDIAN.erased()
             }
-            "1.2.840.10008.1.2.4.81" => {
-                info!("Using JPEG-LS Near-Lossless");
+            ts::JPEG_LS_NEAR_LOSSLESS => {
+                debug!("Using JPEG-LS Near-Lossless");
                 &dicom_transfer_syntax_registry::entries::EXPLICIT_VR_LITTLE_ENDIAN.erased()
             }
             
             // JPEG 2000
-            "1.2.840.10008.1.2.4.90" => {
-                info!("Using JPEG 2000 Lossless");
+            ts::JPEG_2000_LOSSLESS => {
+                debug!("Using JPEG 2000 Lossless");
                 &dicom_transfer_syntax_registry::entries::EXPLICIT_VR_LITTLE_ENDIAN.erased()
             }
-            "1.2.840.10008.1.2.4.91" => {
-                info!("Using JPEG 2000");
+            ts::JPEG_2000 => {
+                debug!("Using JPEG 2000");
                 &dicom_transfer_syntax_registry::entries::EXPLICIT_VR_LITTLE_ENDIAN.erased()
             }
             
             // RLE Lossless
-            "1.2.840.10008.1.2.5" => {
-                info!("Using RLE Lossless");
+            ts::RLE_LOSSLESS => {
+                debug!("Using RLE Lossless");
                 &dicom_transfer_syntax_registry::entries::EXPLICIT_VR_LITTLE_ENDIAN.erased()
             }
             
@@ -354,7 +916,28 @@ impl DicomClient {
             }
         };
         
-        obj.write_dataset_with_ts(&mut dataset_buffer, ts_to_use)?;
+        // Legacy Big Endian sources are re-encoded element by element into the negotiated syntax
+        if obj.meta().transfer_syntax().trim_end_matches('\0') == ts::EXPLICIT_VR_BIG_ENDIAN {
+            info!("Converting Explicit VR Big Endian file {} to {}", file.path.display(), ts_to_use.name());
+        }
+
+        // The command set must describe the dataset actually sent, not possibly stale file meta
+        let (sop_class_uid, sop_instance_uid) = Self::prepare_dataset(&mut obj, &mut dataset_buffer, ts_to_use)?;
+        if sop_class_uid != obj.meta().media_storage_sop_class_uid().trim_end_matches('\0')
+            || sop_instance_uid != obj.meta().media_storage_sop_instance_uid().trim_end_matches('\0')
+        {
+            warn!("File meta of {} does not match its dataset; using dataset UIDs {} / {}",
+                  file.path.display(), sop_class_uid, sop_instance_uid);
+        }
+
+        let manifest_entry = config.compute_checksums.then(|| ManifestEntry {
+            sop_instance_uid: sop_instance_uid.clone(),
+            sop_class_uid: sop_class_uid.clone(),
+            study_instance_uid: file.study_instance_uid.clone(),
+            source_path: file.path.display().to_string(),
+            size: dataset_buffer.len() as u64,
+            sha256: sha256_hex(&dataset_buffer),
+        });
 
         // Create C-STORE command dataset
         let mut command_obj = InMemDicomObject::new_empty();
@@ -363,7 +946,7 @@ impl DicomClient {
         command_obj.put(DataElement::new(
             Tag(0x0000, 0x0002), // Affected SOP Class UID
             VR::UI,
-            Value::Primitive(PrimitiveValue::Str(file.sop_class_uid.clone().into())),
+            Value::Primitive(PrimitiveValue::Str(sop_class_uid.clone().into())),
         ));
         
         command_obj.put(DataElement::new(
@@ -387,7 +970,7 @@ impl DicomClient {
         command_obj.put(DataElement::new(
             Tag(0x0000, 0x1000), // Affected SOP Instance UID
             VR::UI,
-            Value::Primitive(PrimitiveValue::Str(file.sop_instance_uid.clone().into())),
+            Value::Primitive(PrimitiveValue::Str(sop_instance_uid.clone().into())),
         ));
         
         // CRITICAL: Add CommandDataSetType - indicates that dataset follows command
@@ -412,20 +995,33 @@ impl DicomClient {
             data: command_buffer.clone(),
         };
 
-        info!("Sending C-STORE command PDU: {} bytes", command_buffer.len());
-        association.send(&Pdu::PData {
-            data: vec![command_pdv],
-        })?;
-        info!("C-STORE command PDU sent successfully");
+        // With packing, the command travels in the same PDU as the first dataset fragment
+        let mut pending = Vec::new();
+        let mut pending_length = 0;
+        if config.pack_pdvs {
+            pending_length = pdv_item_length(command_buffer.len());
+            pending.push(command_pdv);
+        } else {
+            debug!("Sending C-STORE command PDU: {} bytes", command_buffer.len());
+            Self::send_pdata(association, vec![command_pdv], wire)?;
+            debug!("C-STORE command PDU sent successfully");
+        }
 
-        // Send dataset P-DATA-TF (with fragmentation for large files)
-        let max_pdu_data_size = 16000; // Conservative PDU data size accounting for headers
+        // Send dataset P-DATA-TF, fragmented to the size the tuner currently favours
         let mut offset = 0;
         
-        info!("Starting dataset transfer: {} bytes total", dataset_buffer.len());
+        debug!("Starting dataset transfer: {} bytes total", dataset_buffer.len());
         
         while offset < dataset_buffer.len() {
-            let chunk_size = std::cmp::min(max_pdu_data_size, dataset_buffer.len() - offset);
+            let chunk_start = Instant::now();
+            let mut room = tuner.fragment_room(pending_length);
+            if !pending.is_empty() && room < MIN_PACKED_FRAGMENT.min(dataset_buffer.len() - offset) {
+                // Too little room left to be worth sharing the PDU
+                Self::send_pdata(association, std::mem::take(&mut pending), wire)?;
+                pending_length = 0;
+                room = tuner.chunk_size();
+            }
+            let chunk_size = std::cmp::min(room, dataset_buffer.len() - offset);
             let is_last = offset + chunk_size >= dataset_buffer.len();
             
             let data_chunk = dataset_buffer[offset..offset + chunk_size].to_vec();
@@ -437,33 +1033,132 @@ impl DicomClient {
                 data: data_chunk,
             };
 
-            association.send(&Pdu::PData {
-                data: vec![data_pdv],
-            })?;
+            pending.push(data_pdv);
+            Self::send_pdata(association, std::mem::take(&mut pending), wire)?;
+            pending_length = 0;
             
             offset += chunk_size;
-            info!("Sent data chunk: {} bytes, is_last: {}, total sent: {}/{}", 
+            debug!("Sent data chunk: {} bytes, is_last: {}, total sent: {}/{}", 
                   chunk_size, is_last, offset, dataset_buffer.len());
-            
-            // Wait for C-STORE response after each chunk
-            match association.receive()? {
-                Pdu::PData { data } => {
-                    debug!("Received C-STORE response for chunk: {} PDVs", data.len());
-                    // Parse response to check status - for now just log success
-                }
-                other => {
-                    warn!("Unexpected PDU in C-STORE response: {:?}", other);
-                }
-            }
+            tuner.record(chunk_size, chunk_start.elapsed());
+        }
+
+        if !pending.is_empty() {
+            // Command of an instance with an empty dataset
+            Self::send_pdata(association, pending, wire)?;
         }
         
-        info!("All dataset chunks sent and responses received");
+        debug!("All dataset chunks sent, waiting for the C-STORE response");
+
+        // The operation is answered once, after the last fragment of its dataset
+        let status = Self::receive_status(association, wire)?;
+        match status {
+            0x0000 => {}
+            0xB000..=0xBFFF => warn!("C-STORE of {} completed with warning status 0x{:04X}", sop_instance_uid, status),
+            _ => anyhow::bail!("C-STORE of {} failed with status 0x{:04X}", sop_instance_uid, status),
+        }
 
         debug!("C-STORE operation completed, {} bytes transferred", dataset_buffer.len());
-        Ok(dataset_buffer.len() as u64)
+        Ok((dataset_buffer.len() as u64, manifest_entry))
+    }
+
+    /// Send a P-DATA-TF PDU and count its encoded size
+    fn send_pdata(
+        association: &mut dicom_ul::ClientAssociation<std::net::TcpStream>,
+        data: Vec<dicom_ul::pdu::PDataValue>,
+        wire: &mut WireBytes,
+    ) -> Result<()> {
+        let length = pdata_pdu_length(data.iter().map(|pdv| pdv.data.len()));
+        association.send(&dicom_ul::pdu::Pdu::PData { data })?;
+        wire.sent += length;
+        Ok(())
+    }
+
+    /// Record the outcome of one instance; the worker fills in its thread id
+    pub fn transfer_result(file: &DicomFile, error_message: Option<String>, transfer_time: Duration) -> TransferResult {
+        TransferResult {
+            file_path: file.path.display().to_string(),
+            study_instance_uid: file.study_instance_uid.clone(),
+            sop_instance_uid: file.sop_instance_uid.clone(),
+            success: error_message.is_none(),
+            error_message,
+            transfer_time_ms: transfer_time.as_millis() as u64,
+            file_size: file.file_size,
+            bytes_sent: 0,
+            timestamp: chrono::Utc::now(),
+            thread_id: 0,
+        }
+    }
+
+    /// Where to open the association to `address`: the address itself, or
+    /// with TLS the loopback end of a TLS connection to it
+    fn association_address(config: &DicomClientConfig, address: SocketAddr) -> Result<SocketAddr> {
+        match &config.tls {
+            Some(tls_config) => tls::connect(Arc::clone(tls_config), &config.host, address, config.connect_timeout),
+            None => Ok(address),
+        }
+    }
+
+    /// Resolve the destination to all of its addresses, alternating address
+    /// families so an unreachable IPv6 or IPv4 path does not delay the other
+    fn resolve_addresses(host: &str, port: u16) -> Result<Vec<SocketAddr>> {
+        let resolved: Vec<SocketAddr> = (host, port).to_socket_addrs()
+            .context(format!("Failed to resolve {}:{}", host, port))?
+            .collect();
+        if resolved.is_empty() {
+            return Err(anyhow::anyhow!("{} did not resolve to any address", host));
+        }
+        Ok(interleave_address_families(resolved))
+    }
+
+    /// Encode the dataset for transmission, dropping any File Meta Information
+    /// (group 0002) elements that leaked into the dataset body, and return the
+    /// SOP Class and Instance UIDs found in the dataset itself.
+    fn prepare_dataset(
+        obj: &mut InMemDicomObject,
+        buffer: &mut Vec<u8>,
+        ts: &dicom::encoding::TransferSyntax,
+    ) -> Result<(String, String)> {
+        let meta_tags: Vec<Tag> = obj.iter()
+            .map(|e| e.header().tag)
+            .filter(|tag| tag.0 == 0x0002)
+            .collect();
+        for tag in &meta_tags {
+            warn!("Removing file meta element {} from dataset body", tag);
+            obj.remove_element(*tag);
+        }
+
+        let uid = |tag: Tag, name: &str| -> Result<String> {
+            obj.element(tag)
+                .ok()
+                .and_then(|e| e.string().ok())
+                .map(|s| s.trim().trim_end_matches('\0').to_string())
+                .filter(|s| !s.is_empty())
+                .ok_or_else(|| anyhow::anyhow!("Dataset has no {}", name))
+        };
+        let sop_class_uid = uid(Tag(0x0008, 0x0016), "SOP Class UID")?;
+        let sop_instance_uid = uid(Tag(0x0008, 0x0018), "SOP Instance UID")?;
+
+        obj.write_dataset_with_ts(buffer, ts)?;
+        Ok((sop_class_uid, sop_instance_uid))
     }
 }
 
+/// Order addresses IPv6 first, then alternating with IPv4, keeping resolver order within each family
+fn interleave_address_families(addresses: Vec<SocketAddr>) -> Vec<SocketAddr> {
+    let (v6, v4): (Vec<SocketAddr>, Vec<SocketAddr>) = addresses.into_iter().partition(|a| a.is_ipv6());
+    let mut ordered = Vec::with_capacity(v6.len() + v4.len());
+    let mut v6 = v6.into_iter();
+    let mut v4 = v4.into_iter();
+    loop {
+        match (v6.next(), v4.next()) {
+            (None, None) => break,
+            (a, b) => ordered.extend(a.into_iter().chain(b)),
+        }
+    }
+    ordered
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum DicomClientError {
     #[error("Connection failed: {0}")]
@@ -475,3 +1170,65 @@ pub enum DicomClientError {
     #[error("DICOM parsing error: {0}")]
     DicomParsing(#[from] dicom_object::ReadError),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dicom_object::meta::FileMetaTableBuilder;
+
+    fn fixture_with_stale_meta() -> dicom_object::FileDicomObject<InMemDicomObject> {
+        InMemDicomObject::from_element_iter([
+            // File Meta element that leaked into the dataset body
+            DataElement::new(Tag(0x0002, 0x0010), VR::UI, PrimitiveValue::from("1.2.840.10008.1.2")),
+            DataElement::new(Tag(0x0008, 0x0016), VR::UI, PrimitiveValue::from("1.2.840.10008.5.1.4.1.1.2")),
+            DataElement::new(Tag(0x0008, 0x0018), VR::UI, PrimitiveValue::from("1.2.3.4.5")),
+            DataElement::new(Tag(0x0010, 0x0020), VR::LO, PrimitiveValue::from("PAT001")),
+        ])
+        .with_meta(
+            FileMetaTableBuilder::new()
+                .media_storage_sop_class_uid("1.2.840.10008.5.1.4.1.1.7")
+                .media_storage_sop_instance_uid("9.9.9.9")
+                .transfer_syntax("1.2.840.10008.1.2.1"),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_interleave_address_families() {
+        let addresses: Vec<SocketAddr> = ["10.0.0.1:104", "10.0.0.2:104", "[fd00::1]:104"]
+            .iter()
+            .map(|a| a.parse().unwrap())
+            .collect();
+        let ordered = interleave_address_families(addresses);
+        assert_eq!(ordered[0].to_string(), "[fd00::1]:104");
+        assert_eq!(ordered[1].to_string(), "10.0.0.1:104");
+        assert_eq!(ordered[2].to_string(), "10.0.0.2:104");
+    }
+
+    #[test]
+    fn test_prepare_dataset_strips_file_meta_group() {
+        let mut obj = fixture_with_stale_meta();
+        let ts = dicom_transfer_syntax_registry::entries::EXPLICIT_VR_LITTLE_ENDIAN.erased();
+        let mut buffer = Vec::new();
+
+        DicomClient::prepare_dataset(&mut obj, &mut buffer, &ts).unwrap();
+
+        let decoded = InMemDicomObject::read_dataset_with_ts(&buffer[..], &ts).unwrap();
+        assert!(decoded.iter().all(|e| e.header().tag.0 != 0x0002));
+        assert!(decoded.element(Tag(0x0010, 0x0020)).is_ok());
+    }
+
+    #[test]
+    fn test_command_uids_come_from_dataset() {
+        let mut obj = fixture_with_stale_meta();
+        let ts = dicom_transfer_syntax_registry::entries::IMPLICIT_VR_LITTLE_ENDIAN.erased();
+        let mut buffer = Vec::new();
+
+        let (sop_class_uid, sop_instance_uid) =
+            DicomClient::prepare_dataset(&mut obj, &mut buffer, &ts).unwrap();
+
+        assert_eq!(sop_class_uid, "1.2.840.10008.5.1.4.1.1.2");
+        assert_eq!(sop_instance_uid, "1.2.3.4.5");
+        assert_ne!(obj.meta().media_storage_sop_instance_uid().trim_end_matches('\0'), sop_instance_uid);
+    }
+}
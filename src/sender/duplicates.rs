@@ -0,0 +1,154 @@
+/// Input files that claim the same SOP Instance UID
+///
+/// Sending two different objects under one UID makes the destination keep
+/// whichever arrives last, or refuse it, so duplicates are resolved before the
+/// transfer starts and every conflict is reported in the session summary.
+
+use std::collections::{HashMap, HashSet};
+
+use super::indexing::UNKNOWN_SOP_INSTANCE;
+use crate::common::types::{DicomFile, DuplicateUidConflict};
+
+/// What to do when several files claim the same SOP Instance UID
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum DuplicatePolicy {
+    /// Send the first file found and skip the others
+    SendFirst,
+    /// Send the most recently modified file and skip the others
+    SendNewest,
+    /// Skip every file of the conflicting UID
+    SkipAll,
+}
+
+/// Detect files sharing a SOP Instance UID and keep at most one of each
+/// according to the policy; conflicts are reported in UID order, each listing
+/// its files in input order. Files without a SOP Instance UID do not conflict.
+pub fn resolve_duplicate_uids(
+    files: Vec<DicomFile>,
+    policy: DuplicatePolicy,
+) -> (Vec<DicomFile>, Vec<DuplicateUidConflict>) {
+    let mut by_uid: HashMap<String, Vec<usize>> = HashMap::new();
+    for (idx, file) in files.iter().enumerate() {
+        if file.sop_instance_uid.is_empty() || file.sop_instance_uid == UNKNOWN_SOP_INSTANCE {
+            continue;
+        }
+        by_uid.entry(file.sop_instance_uid.clone()).or_default().push(idx);
+    }
+
+    let mut skipped = HashSet::new();
+    let mut conflicts = Vec::new();
+
+    let mut by_uid: Vec<_> = by_uid.into_iter().collect();
+    by_uid.sort();
+
+    for (sop_instance_uid, indices) in by_uid {
+        if indices.len() < 2 {
+            continue;
+        }
+
+        let keep = match policy {
+            DuplicatePolicy::SendFirst => Some(indices[0]),
+            DuplicatePolicy::SendNewest => indices.iter()
+                .copied()
+                .max_by_key(|&idx| {
+                    std::fs::metadata(&files[idx].path)
+                        .and_then(|m| m.modified())
+                        .ok()
+                }),
+            DuplicatePolicy::SkipAll => None,
+        };
+
+        for &idx in &indices {
+            if Some(idx) != keep {
+                skipped.insert(idx);
+            }
+        }
+
+        conflicts.push(DuplicateUidConflict {
+            sop_instance_uid,
+            files: indices.iter().map(|&idx| files[idx].path.display().to_string()).collect(),
+            sent_file: keep.map(|idx| files[idx].path.display().to_string()),
+        });
+    }
+
+    let kept = files.into_iter()
+        .enumerate()
+        .filter(|(idx, _)| !skipped.contains(idx))
+        .map(|(_, file)| file)
+        .collect();
+
+    (kept, conflicts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::{Path, PathBuf};
+    use std::time::{Duration, SystemTime};
+
+    fn file(path: &Path, sop_instance_uid: &str) -> DicomFile {
+        DicomFile::test(path, sop_instance_uid)
+    }
+
+    /// Files a to e, where b and d share UID 1.2.3.1.9 and a, c and e share
+    /// 1.2.3.1.5; c is the most recently modified of its UID, and b of its own
+    fn input(dir: &Path) -> Vec<DicomFile> {
+        let now = SystemTime::now();
+        let files = [("a", "1.2.3.1.5", 300), ("b", "1.2.3.1.9", 100), ("c", "1.2.3.1.5", 100),
+                     ("d", "1.2.3.1.9", 200), ("e", "1.2.3.1.5", 200), ("f", "1.2.3.1.7", 0)];
+        files.iter().map(|(name, uid, age)| {
+            let path = dir.join(format!("{}.dcm", name));
+            std::fs::File::create(&path).unwrap().set_modified(now - Duration::from_secs(*age)).unwrap();
+            file(&path, uid)
+        }).collect()
+    }
+
+    fn names(paths: &[String]) -> Vec<String> {
+        paths.iter().map(|path| PathBuf::from(path).file_stem().unwrap().to_string_lossy().into_owned()).collect()
+    }
+
+    fn kept_names(files: &[DicomFile]) -> Vec<String> {
+        names(&files.iter().map(|file| file.path.display().to_string()).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn test_resolve_duplicate_uids() {
+        let dir = std::env::temp_dir().join(format!("duplicates_test_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let (kept, conflicts) = resolve_duplicate_uids(input(&dir), DuplicatePolicy::SendFirst);
+        assert_eq!(kept_names(&kept), ["a", "b", "f"]);
+        // Conflicts come in UID order, each with its files in input order
+        assert_eq!(conflicts.iter().map(|c| c.sop_instance_uid.as_str()).collect::<Vec<_>>(), ["1.2.3.1.5", "1.2.3.1.9"]);
+        assert_eq!(names(&conflicts[0].files), ["a", "c", "e"]);
+        assert_eq!(names(&conflicts[1].files), ["b", "d"]);
+        assert_eq!(names(&[conflicts[0].sent_file.clone().unwrap()]), ["a"]);
+
+        let (kept, conflicts) = resolve_duplicate_uids(input(&dir), DuplicatePolicy::SendNewest);
+        assert_eq!(kept_names(&kept), ["b", "c", "f"]);
+        assert_eq!(names(&[conflicts[0].sent_file.clone().unwrap()]), ["c"]);
+        assert_eq!(names(&[conflicts[1].sent_file.clone().unwrap()]), ["b"]);
+
+        let (kept, conflicts) = resolve_duplicate_uids(input(&dir), DuplicatePolicy::SkipAll);
+        assert_eq!(kept_names(&kept), ["f"]);
+        assert_eq!(conflicts.len(), 2);
+        assert!(conflicts.iter().all(|conflict| conflict.sent_file.is_none()));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_files_without_uid_do_not_conflict() {
+        let files = vec![
+            file(Path::new("a.dcm"), UNKNOWN_SOP_INSTANCE),
+            file(Path::new("b.dcm"), UNKNOWN_SOP_INSTANCE),
+            file(Path::new("c.dcm"), ""),
+            file(Path::new("d.dcm"), ""),
+        ];
+        for policy in [DuplicatePolicy::SendFirst, DuplicatePolicy::SendNewest, DuplicatePolicy::SkipAll] {
+            let (kept, conflicts) = resolve_duplicate_uids(files.clone(), policy);
+            assert_eq!(kept_names(&kept), ["a", "b", "c", "d"]);
+            assert!(conflicts.is_empty());
+        }
+    }
+}
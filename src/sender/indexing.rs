@@ -0,0 +1,135 @@
+use anyhow::Result;
+use dicom::object::open_file;
+use dicom_core::header::Tag;
+use std::path::Path;
+use tracing::{debug, warn};
+use walkdir::WalkDir;
+
+use crate::common::key_objects::{is_structured_report, ReferencingDocument, SelectionPolicy};
+use crate::common::types::DicomFile;
+
+/// SOP Instance UID given to a file that has none
+pub const UNKNOWN_SOP_INSTANCE: &str = "UNKNOWN_SOP_INSTANCE";
+
+/// Find the DICOM files (`.dcm`) under `input` and read their identifying attributes
+pub async fn index_dicom_files(input: &Path, recursive: bool) -> Result<Vec<DicomFile>> {
+    let mut files = Vec::new();
+    
+    if input.is_file() {
+        if let Some(dicom_file) = process_dicom_file(input).await? {
+            files.push(dicom_file);
+        }
+    } else if input.is_dir() {
+        if recursive {
+            for entry in WalkDir::new(input) {
+                let entry = entry?;
+                if entry.file_type().is_file() {
+                    let path = entry.path();
+                    if let Some(ext) = path.extension() {
+                        if ext == "dcm" || ext == "DCM" {
+                            if let Some(dicom_file) = process_dicom_file(path).await? {
+                                files.push(dicom_file);
+                            }
+                        }
+                    }
+                }
+            }
+        } else {
+            for entry in std::fs::read_dir(input)? {
+                let entry = entry?;
+                let path = entry.path();
+                if path.is_file() {
+                    if let Some(ext) = path.extension() {
+                        if ext == "dcm" || ext == "DCM" {
+                            if let Some(dicom_file) = process_dicom_file(&path).await? {
+                                files.push(dicom_file);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(files)
+}
+
+/// Identifying attributes of one file, or `None` if it cannot be read as DICOM
+pub async fn process_dicom_file(path: &Path) -> Result<Option<DicomFile>> {
+    match open_file(path) {
+        Ok(obj) => {
+            let study_instance_uid = obj.element(Tag(0x0020, 0x000D))
+                .map(|e| e.string().unwrap_or_default().trim().to_string())
+                .unwrap_or_else(|_| "UNKNOWN_STUDY".to_string());
+
+            let series_instance_uid = obj.element(Tag(0x0020, 0x000E))
+                .map(|e| e.string().unwrap_or_default().trim().to_string())
+                .unwrap_or_else(|_| "UNKNOWN_SERIES".to_string());
+
+            let sop_instance_uid = obj.element(Tag(0x0008, 0x0018))
+                .map(|e| e.string().unwrap_or_default().trim().trim_end_matches('\0').to_string())
+                .unwrap_or_else(|_| UNKNOWN_SOP_INSTANCE.to_string());
+
+            let sop_class_uid = obj.element(Tag(0x0008, 0x0016))
+                .map(|e| e.string().unwrap_or_default().trim().trim_end_matches('\0').to_string())
+                .unwrap_or_else(|_| "UNKNOWN_SOP_CLASS".to_string());
+            
+            debug!("Indexed {}: SOP Class {}, study {}", path.display(), sop_class_uid, study_instance_uid);
+
+            let modality = obj.element(Tag(0x0008, 0x0060))
+                .ok()
+                .and_then(|e| e.string().ok())
+                .map(|s| s.trim().to_string());
+
+            let patient_id = obj.element(Tag(0x0010, 0x0020))
+                .ok()
+                .and_then(|e| e.string().ok())
+                .map(|s| s.trim().to_string());
+
+            let study_date = obj.element(Tag(0x0008, 0x0020))
+                .ok()
+                .and_then(|e| e.string().ok())
+                .map(|s| s.trim().to_string());
+
+            let acquisition_date = obj.element(Tag(0x0008, 0x0022))
+                .ok()
+                .and_then(|e| e.string().ok())
+                .map(|s| s.trim().to_string());
+
+            let file_size = std::fs::metadata(path)?.len();
+
+            Ok(Some(DicomFile {
+                path: path.to_path_buf(),
+                study_instance_uid,
+                series_instance_uid,
+                sop_instance_uid,
+                sop_class_uid,
+                file_size,
+                modality,
+                patient_id,
+                study_date,
+                acquisition_date,
+            }))
+        }
+        Err(e) => {
+            warn!("Failed to read DICOM file {}: {}", path.display(), e);
+            Ok(None)
+        }
+    }
+}
+
+/// Read the KOS and SR documents among `files` that the policy selects by
+pub fn read_referencing_documents(files: &[DicomFile], policy: &SelectionPolicy) -> Vec<ReferencingDocument> {
+    files.iter()
+        .filter(|file| is_structured_report(&file.sop_class_uid))
+        .filter_map(|file| match open_file(&file.path) {
+            Ok(obj) => Some(ReferencingDocument::from_object(&obj)),
+            Err(e) => {
+                warn!("Failed to read SR document {}: {}", file.path.display(), e);
+                None
+            }
+        })
+        .filter(|document| policy.selects(document))
+        .collect()
+}
@@ -0,0 +1,501 @@
+/// Persistent sender jobs
+///
+/// `dicom-sender submit` records what to send and where in a SQLite database
+/// and returns immediately; `dicom-sender daemon` takes queued jobs in
+/// submission order and runs them. Files are indexed when a job first runs and
+/// tracked individually, so `status` shows per-file detail and `retry` requeues
+/// only the files that did not get through.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::common::types::{DicomFile, TransferResult};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS jobs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    input TEXT NOT NULL,
+    recursive INTEGER NOT NULL,
+    calling_ae TEXT NOT NULL,
+    called_ae TEXT NOT NULL,
+    host TEXT NOT NULL,
+    port INTEGER NOT NULL,
+    status TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    started_at TEXT,
+    finished_at TEXT,
+    error TEXT
+);
+CREATE TABLE IF NOT EXISTS job_files (
+    job_id INTEGER NOT NULL REFERENCES jobs(id),
+    path TEXT NOT NULL,
+    study_instance_uid TEXT NOT NULL,
+    sop_instance_uid TEXT NOT NULL,
+    status TEXT NOT NULL,
+    error TEXT,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    file TEXT NOT NULL,
+    PRIMARY KEY (job_id, path)
+);
+";
+
+const JOB_COLUMNS: &str = "id, input, recursive, calling_ae, called_ae, host, port, status, created_at, started_at, finished_at, error,
+    (SELECT COUNT(*) FROM job_files WHERE job_id = jobs.id),
+    (SELECT COUNT(*) FROM job_files WHERE job_id = jobs.id AND status = 'sent'),
+    (SELECT COUNT(*) FROM job_files WHERE job_id = jobs.id AND status = 'failed')";
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl JobStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobStatus::Queued => "queued",
+            JobStatus::Running => "running",
+            JobStatus::Completed => "completed",
+            JobStatus::Failed => "failed",
+            JobStatus::Cancelled => "cancelled",
+        }
+    }
+
+    fn parse(value: &str) -> Self {
+        match value {
+            "queued" => JobStatus::Queued,
+            "running" => JobStatus::Running,
+            "completed" => JobStatus::Completed,
+            "cancelled" => JobStatus::Cancelled,
+            _ => JobStatus::Failed,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        matches!(self, JobStatus::Completed | JobStatus::Failed | JobStatus::Cancelled)
+    }
+}
+
+impl std::fmt::Display for JobStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileStatus {
+    Pending,
+    Sent,
+    Failed,
+}
+
+impl FileStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FileStatus::Pending => "pending",
+            FileStatus::Sent => "sent",
+            FileStatus::Failed => "failed",
+        }
+    }
+
+    fn parse(value: &str) -> Self {
+        match value {
+            "sent" => FileStatus::Sent,
+            "failed" => FileStatus::Failed,
+            _ => FileStatus::Pending,
+        }
+    }
+}
+
+/// What a job sends and where
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JobSpec {
+    pub input: PathBuf,
+    pub recursive: bool,
+    pub calling_ae: String,
+    pub called_ae: String,
+    pub host: String,
+    pub port: u16,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Job {
+    pub id: i64,
+    #[serde(flatten)]
+    pub spec: JobSpec,
+    pub status: JobStatus,
+    pub created_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub error: Option<String>,
+    pub total_files: usize,
+    pub sent_files: usize,
+    pub failed_files: usize,
+}
+
+impl Job {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            id: row.get(0)?,
+            spec: JobSpec {
+                input: PathBuf::from(row.get::<_, String>(1)?),
+                recursive: row.get(2)?,
+                calling_ae: row.get(3)?,
+                called_ae: row.get(4)?,
+                host: row.get(5)?,
+                port: row.get(6)?,
+            },
+            status: JobStatus::parse(&row.get::<_, String>(7)?),
+            created_at: row.get(8)?,
+            started_at: row.get(9)?,
+            finished_at: row.get(10)?,
+            error: row.get(11)?,
+            total_files: row.get(12)?,
+            sent_files: row.get(13)?,
+            failed_files: row.get(14)?,
+        })
+    }
+}
+
+/// Per-file detail of a job
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JobFile {
+    pub path: String,
+    pub study_instance_uid: String,
+    pub sop_instance_uid: String,
+    pub status: FileStatus,
+    pub error: Option<String>,
+    pub attempts: u32,
+}
+
+#[derive(Debug)]
+pub struct JobStore {
+    conn: Connection,
+}
+
+impl JobStore {
+    /// Open or create the job database
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        let conn = Connection::open(path)
+            .with_context(|| format!("Failed to open job database {}", path.display()))?;
+        // The daemon and the management commands use the database concurrently
+        conn.busy_timeout(std::time::Duration::from_secs(10))?;
+        // With a write-ahead log, status queries never wait for the daemon's
+        // commits, and commits only sync the log
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        conn.pragma_update(None, "synchronous", "NORMAL")?;
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self { conn })
+    }
+
+    /// Pages the write-ahead log may grow to before SQLite checkpoints it into the database
+    pub fn set_checkpoint_pages(&self, pages: u32) -> Result<()> {
+        self.conn.pragma_update(None, "wal_autocheckpoint", pages)?;
+        Ok(())
+    }
+
+    /// Checkpoint and truncate the write-ahead log and rebuild the database
+    /// without its free pages; returns the bytes reclaimed
+    pub fn compact(&self) -> Result<u64> {
+        let before = self.size()?;
+        self.conn.execute_batch("PRAGMA wal_checkpoint(TRUNCATE); VACUUM; PRAGMA wal_checkpoint(TRUNCATE);")
+            .context("Failed to compact job database")?;
+        Ok(before.saturating_sub(self.size()?))
+    }
+
+    /// Write a consistent, compacted copy of the database to `target` while
+    /// other connections keep using it
+    pub fn backup(&self, target: &Path) -> Result<()> {
+        if target.exists() {
+            anyhow::bail!("{} already exists", target.display());
+        }
+        let target_path = target.to_str().context("Backup path is not valid UTF-8")?;
+        self.conn.execute("VACUUM INTO ?1", [target_path])
+            .with_context(|| format!("Failed to back up job database to {}", target.display()))?;
+        Ok(())
+    }
+
+    fn size(&self) -> Result<u64> {
+        Ok(self.conn.query_row(
+            "SELECT page_count * page_size FROM pragma_page_count(), pragma_page_size()",
+            [],
+            |row| row.get(0),
+        )?)
+    }
+
+    pub fn submit(&self, spec: &JobSpec) -> Result<i64> {
+        self.conn.execute(
+            "INSERT INTO jobs (input, recursive, calling_ae, called_ae, host, port, status, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![spec.input.display().to_string(), spec.recursive, spec.calling_ae, spec.called_ae,
+                    spec.host, spec.port, JobStatus::Queued.as_str(), Utc::now()],
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    pub fn get(&self, id: i64) -> Result<Option<Job>> {
+        Ok(self.conn
+            .query_row(&format!("SELECT {} FROM jobs WHERE id = ?1", JOB_COLUMNS), [id], Job::from_row)
+            .optional()?)
+    }
+
+    /// All jobs, oldest first
+    pub fn list(&self) -> Result<Vec<Job>> {
+        let mut statement = self.conn.prepare(&format!("SELECT {} FROM jobs ORDER BY id", JOB_COLUMNS))?;
+        let jobs = statement.query_map([], Job::from_row)?.collect::<rusqlite::Result<_>>()?;
+        Ok(jobs)
+    }
+
+    pub fn files(&self, id: i64) -> Result<Vec<JobFile>> {
+        let mut statement = self.conn.prepare(
+            "SELECT path, study_instance_uid, sop_instance_uid, status, error, attempts
+             FROM job_files WHERE job_id = ?1 ORDER BY study_instance_uid, path",
+        )?;
+        let files = statement
+            .query_map([id], |row| {
+                Ok(JobFile {
+                    path: row.get(0)?,
+                    study_instance_uid: row.get(1)?,
+                    sop_instance_uid: row.get(2)?,
+                    status: FileStatus::parse(&row.get::<_, String>(3)?),
+                    error: row.get(4)?,
+                    attempts: row.get(5)?,
+                })
+            })?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(files)
+    }
+
+    /// Mark the oldest queued job as running and return it
+    pub fn claim_next(&mut self) -> Result<Option<Job>> {
+        let tx = self.conn.transaction()?;
+        let id: Option<i64> = tx
+            .query_row("SELECT id FROM jobs WHERE status = 'queued' ORDER BY id LIMIT 1", [], |row| row.get(0))
+            .optional()?;
+        if let Some(id) = id {
+            tx.execute(
+                "UPDATE jobs SET status = ?1, started_at = ?2, finished_at = NULL, error = NULL WHERE id = ?3",
+                params![JobStatus::Running.as_str(), Utc::now(), id],
+            )?;
+        }
+        tx.commit()?;
+        match id {
+            Some(id) => self.get(id),
+            None => Ok(None),
+        }
+    }
+
+    /// Requeue jobs left running by a daemon that did not shut down cleanly
+    pub fn requeue_interrupted(&self) -> Result<usize> {
+        Ok(self.conn.execute(
+            "UPDATE jobs SET status = ?1 WHERE status = ?2",
+            params![JobStatus::Queued.as_str(), JobStatus::Running.as_str()],
+        )?)
+    }
+
+    /// Record the indexed files of a job as pending
+    pub fn add_files(&mut self, id: i64, files: &[DicomFile]) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        for file in files {
+            tx.execute(
+                "INSERT OR IGNORE INTO job_files (job_id, path, study_instance_uid, sop_instance_uid, status, file)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![id, file.path.display().to_string(), file.study_instance_uid, file.sop_instance_uid,
+                        FileStatus::Pending.as_str(), serde_json::to_string(file)?],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Files of a job that still have to be sent
+    pub fn pending_files(&self, id: i64) -> Result<Vec<DicomFile>> {
+        let mut statement = self.conn.prepare(
+            "SELECT file FROM job_files WHERE job_id = ?1 AND status = 'pending' ORDER BY study_instance_uid, path",
+        )?;
+        let rows: Vec<String> = statement.query_map([id], |row| row.get(0))?.collect::<rusqlite::Result<_>>()?;
+        rows.iter()
+            .map(|file| serde_json::from_str(file).context("Corrupt file entry in job database"))
+            .collect()
+    }
+
+    pub fn record_results(&mut self, id: i64, results: &[TransferResult]) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        for result in results {
+            let status = if result.success { FileStatus::Sent } else { FileStatus::Failed };
+            tx.execute(
+                "UPDATE job_files SET status = ?1, error = ?2, attempts = attempts + 1 WHERE job_id = ?3 AND path = ?4",
+                params![status.as_str(), result.error_message, id, result.file_path],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Finish a running job; a job cancelled while it ran stays cancelled
+    pub fn finish(&self, id: i64, status: JobStatus, error: Option<&str>) -> Result<()> {
+        self.conn.execute(
+            "UPDATE jobs SET status = ?1, finished_at = ?2, error = ?3 WHERE id = ?4 AND status = 'running'",
+            params![status.as_str(), Utc::now(), error, id],
+        )?;
+        Ok(())
+    }
+
+    pub fn is_cancelled(&self, id: i64) -> Result<bool> {
+        Ok(self.get(id)?.is_some_and(|job| job.status == JobStatus::Cancelled))
+    }
+
+    /// Cancel a queued or running job; a running job stops after its current study
+    pub fn cancel(&self, id: i64) -> Result<Job> {
+        let job = self.get(id)?.with_context(|| format!("No job {}", id))?;
+        if job.status.is_finished() {
+            anyhow::bail!("Job {} is already {}", id, job.status);
+        }
+        self.conn.execute(
+            "UPDATE jobs SET status = ?1, finished_at = ?2 WHERE id = ?3",
+            params![JobStatus::Cancelled.as_str(), Utc::now(), id],
+        )?;
+        Ok(self.get(id)?.expect("job exists"))
+    }
+
+    /// Requeue a finished job, resetting its failed files to pending; returns
+    /// the number of files that will be sent again
+    pub fn retry(&mut self, id: i64) -> Result<usize> {
+        let job = self.get(id)?.with_context(|| format!("No job {}", id))?;
+        if !job.status.is_finished() {
+            anyhow::bail!("Job {} is still {}", id, job.status);
+        }
+        if job.status == JobStatus::Completed && job.failed_files == 0 {
+            anyhow::bail!("Job {} completed without failures", id);
+        }
+
+        let tx = self.conn.transaction()?;
+        tx.execute(
+            "UPDATE job_files SET status = ?1 WHERE job_id = ?2 AND status = ?3",
+            params![FileStatus::Pending.as_str(), id, FileStatus::Failed.as_str()],
+        )?;
+        tx.execute(
+            "UPDATE jobs SET status = ?1, error = NULL, finished_at = NULL WHERE id = ?2",
+            params![JobStatus::Queued.as_str(), id],
+        )?;
+        let pending: usize = tx.query_row(
+            "SELECT COUNT(*) FROM job_files WHERE job_id = ?1 AND status = 'pending'",
+            [id],
+            |row| row.get(0),
+        )?;
+        tx.commit()?;
+        Ok(pending)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec() -> JobSpec {
+        JobSpec {
+            input: PathBuf::from("/data/study"),
+            recursive: true,
+            calling_ae: "RUST_SCU".to_string(),
+            called_ae: "PACS".to_string(),
+            host: "pacs".to_string(),
+            port: 104,
+        }
+    }
+
+    fn file(path: &str, study: &str) -> DicomFile {
+        DicomFile::test(path, &format!("{}.{}", study, path.len())).with_study(study).with_size(1024)
+    }
+
+    fn result(file: &DicomFile, error: Option<&str>) -> TransferResult {
+        TransferResult {
+            file_path: file.path.display().to_string(),
+            study_instance_uid: file.study_instance_uid.clone(),
+            sop_instance_uid: file.sop_instance_uid.clone(),
+            success: error.is_none(),
+            error_message: error.map(str::to_string),
+            transfer_time_ms: 5,
+            file_size: file.file_size,
+            bytes_sent: if error.is_none() { file.file_size } else { 0 },
+            timestamp: Utc::now(),
+            thread_id: 0,
+        }
+    }
+
+    #[test]
+    fn test_job_lifecycle() {
+        let path = std::env::temp_dir().join(format!("jobs_test_{}.db", uuid::Uuid::new_v4()));
+        let mut store = JobStore::open(&path).unwrap();
+
+        let first = store.submit(&spec()).unwrap();
+        let second = store.submit(&spec()).unwrap();
+        assert_eq!(store.list().unwrap().len(), 2);
+
+        let job = store.claim_next().unwrap().unwrap();
+        assert_eq!(job.id, first);
+        assert_eq!(job.status, JobStatus::Running);
+        assert_eq!(job.spec, spec());
+
+        let files = vec![file("/data/study/a.dcm", "1.2.3"), file("/data/study/bb.dcm", "1.2.3")];
+        store.add_files(first, &files).unwrap();
+        assert_eq!(store.pending_files(first).unwrap().len(), 2);
+
+        store.record_results(first, &[result(&files[0], None), result(&files[1], Some("timeout"))]).unwrap();
+        store.finish(first, JobStatus::Failed, Some("1 of 2 files failed")).unwrap();
+        let job = store.get(first).unwrap().unwrap();
+        assert_eq!((job.status, job.total_files, job.sent_files, job.failed_files), (JobStatus::Failed, 2, 1, 1));
+        assert_eq!(store.files(first).unwrap()[1].error.as_deref(), Some("timeout"));
+
+        // Retrying requeues only the failed file, behind the job already queued
+        assert_eq!(store.retry(first).unwrap(), 1);
+        assert_eq!(store.pending_files(first).unwrap()[0].path, files[1].path);
+        assert_eq!(store.claim_next().unwrap().unwrap().id, first);
+        assert!(store.retry(first).is_err());
+
+        // Cancelling a running job sticks when the daemon finishes it
+        store.cancel(first).unwrap();
+        store.finish(first, JobStatus::Completed, None).unwrap();
+        assert!(store.is_cancelled(first).unwrap());
+        assert!(store.cancel(first).is_err());
+
+        assert_eq!(store.claim_next().unwrap().unwrap().id, second);
+        assert_eq!(store.requeue_interrupted().unwrap(), 1);
+        assert_eq!(store.get(second).unwrap().unwrap().status, JobStatus::Queued);
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_compact_and_backup() {
+        let dir = std::env::temp_dir().join(format!("jobs_backup_test_{}", uuid::Uuid::new_v4()));
+        let mut store = JobStore::open(&dir.join("jobs.db")).unwrap();
+        store.set_checkpoint_pages(100).unwrap();
+        let journal_mode: String = store.conn.query_row("PRAGMA journal_mode", [], |row| row.get(0)).unwrap();
+        assert_eq!(journal_mode, "wal");
+
+        let id = store.submit(&spec()).unwrap();
+        let files: Vec<DicomFile> = (0..500).map(|i| file(&format!("/data/study/{}.dcm", i), "1.2.3")).collect();
+        store.add_files(id, &files).unwrap();
+        store.conn.execute("DELETE FROM job_files WHERE rowid % 2 = 0", []).unwrap();
+        assert!(store.compact().unwrap() > 0);
+
+        let backup = dir.join("backup.db");
+        store.backup(&backup).unwrap();
+        assert!(store.backup(&backup).is_err(), "an existing file is never overwritten");
+        let copy = JobStore::open(&backup).unwrap();
+        assert_eq!(copy.get(id).unwrap().unwrap().total_files, 250);
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
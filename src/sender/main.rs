@@ -1,5 +1,14 @@
 // Sender binary main
+mod batching;
+mod chunking;
+mod concurrency;
 mod dicom_client;
+mod duplicates;
+mod indexing;
+mod jobs;
+mod mirror;
+mod notify;
+mod study_split;
 
 // Include common modules
 #[path = "../common/mod.rs"]
@@ -7,35 +16,72 @@ mod common;
 
 use anyhow::Result;
 use chrono::{DateTime, Utc};
-use clap::Parser;
+use clap::{Parser, Subcommand, ValueEnum};
 use console::{style, Emoji};
-use dicom::object::open_file;
-use dicom_core::header::Tag;
+use batching::SmallObjectBatching;
+use concurrency::ConcurrencyController;
 use dicom_client::{DicomClient, DicomClientConfig};
+use duplicates::{resolve_duplicate_uids, DuplicatePolicy};
+use indexing::{index_dicom_files, process_dicom_file, read_referencing_documents};
+use jobs::{FileStatus, JobSpec, JobStatus, JobStore};
+use mirror::{lag_report, scan_source, MirrorState};
+use notify::Notifier;
+use study_split::{split_study, SegmentTracker};
 use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::fs::OpenOptions;
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 use tokio::task::JoinHandle;
 use tracing::{error, info, warn};
 use uuid::Uuid;
-use walkdir::WalkDir;
 
-use common::types::{DicomFile, SessionSummary, TransferResult, TransferStats};
+use common::codecs::codec_report;
+use common::deterministic::{fixed_timestamp, seeded_uuid};
+use common::discovery::discover;
+use common::health::{bind_health_listener, serve_health, Readiness};
+use common::key_objects::{select_referenced, SelectionPolicy};
+use common::ledger::{self, Direction, Ledger, LedgerRecord};
+use common::listen::DEFAULT_BIND_ADDRESS;
+use common::manifest::Manifest;
+use common::metrics::{samples_csv, ThroughputRecorder, TransferMeter};
+use common::burn_in::Annotation;
+use common::preview::PreviewOptions;
+use common::probe::{probe_batches, CapabilityMatrix, ProbeResult, MAX_CONTEXTS_PER_ASSOCIATION, PROBE_TRANSFER_SYNTAXES};
+use common::reports::{build_patient_reports, build_study_reports, compression_ratio, patient_reports_csv, study_reports_csv};
+use common::rejection::AssociationRejection;
+use common::sop_classes::SopClassRegistry;
+use common::tls::{client_config, CipherPolicy};
+use common::transfer_syntaxes::{TransferSyntaxPolicy, TransferSyntaxRegistry};
+use common::types::{
+    DicomFile, RejectedAssociation, SessionSummary, StudyTransactionFailure, TransferResult,
+    TransferStats,
+};
 
 static SPARKLE: Emoji<'_, '_> = Emoji("✨ ", "");
 static ROCKET: Emoji<'_, '_> = Emoji("🚀 ", "");
 static CLIPBOARD: Emoji<'_, '_> = Emoji("📋 ", "");
 static STOPWATCH: Emoji<'_, '_> = Emoji("⏱️ ", "");
 
+/// Export format for the per-study and per-patient reports
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum ReportFormat {
+    Csv,
+    Json,
+}
+
 #[derive(Parser, Clone)]
 #[command(name = "dicom-sender")]
 #[command(about = "A high-performance DICOM C-STORE sender")]
 #[command(version = "1.0")]
+#[command(subcommand_negates_reqs = true, args_conflicts_with_subcommands = true)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<SenderCommand>,
+
     /// Input path (file or directory)
-    #[arg(short, long)]
+    #[arg(short, long, required_unless_present = "list_codecs", default_value = ".", hide_default_value = true)]
     input: PathBuf,
 
     /// Recursive directory scanning
@@ -47,32 +93,425 @@ struct Args {
     calling_ae: String,
 
     /// Called AE Title (destination)
-    #[arg(short = 'a', long)]
+    #[arg(short = 'a', long, required_unless_present = "list_codecs", default_value = "", hide_default_value = true)]
     ae_title: String,
 
     /// Destination IP address
-    #[arg(short = 'H', long)]
+    #[arg(short = 'H', long, required_unless_present_any = ["discover", "list_codecs"], default_value = "", hide_default_value = true)]
     host: String,
 
     /// Destination port
-    #[arg(short, long)]
+    #[arg(short, long, required_unless_present_any = ["discover", "list_codecs"], default_value = "104", hide_default_value = true)]
     port: u16,
 
+    /// Find the host and port of the destination AE title over mDNS/DNS-SD (requires the mdns feature)
+    #[arg(long)]
+    discover: bool,
+
+    /// Seconds to browse for the destination with --discover
+    #[arg(long, default_value = "5", requires = "discover")]
+    discover_timeout: u64,
+
+    /// Per-address connection timeout in seconds when the host resolves to several addresses
+    #[arg(long, default_value = "5")]
+    connect_timeout: u64,
+
+    /// Open associations over DICOM TLS (the destination usually listens on port 2762); Linux only
+    #[arg(long)]
+    tls: bool,
+
+    /// PEM certificate chain presented to the destination for mutual authentication
+    #[arg(long, requires_all = ["tls", "tls_key"])]
+    tls_cert: Option<PathBuf>,
+
+    /// PEM private key for --tls-cert
+    #[arg(long, requires_all = ["tls", "tls_cert"])]
+    tls_key: Option<PathBuf>,
+
+    /// PEM CA certificates the destination's certificate must chain to, instead of the public web PKI roots
+    #[arg(long, requires = "tls")]
+    tls_ca: Option<PathBuf>,
+
     /// Number of concurrent threads/associations
     #[arg(short, long, default_value = "1")]
     threads: usize,
 
+    /// Scale the number of threads between --min-threads and --max-threads from destination latency and failures, starting at --threads
+    #[arg(long)]
+    adaptive_concurrency: bool,
+
+    /// Lower bound for --adaptive-concurrency
+    #[arg(long, default_value = "1", requires = "adaptive_concurrency")]
+    min_threads: usize,
+
+    /// Upper bound for --adaptive-concurrency
+    #[arg(long, default_value = "8", requires = "adaptive_concurrency")]
+    max_threads: usize,
+
+    /// Sample throughput and latency every N seconds into the summary and a metrics CSV
+    #[arg(long)]
+    metrics_interval: Option<u64>,
+
+    /// Split large studies across this many concurrent associations (per thread)
+    #[arg(long, default_value = "1")]
+    associations_per_study: usize,
+
+    /// Only split studies with at least this many files
+    #[arg(long, default_value = "500")]
+    split_min_files: usize,
+
+    /// Send consecutive studies of small SR, KOS and presentation state objects over one
+    /// association, up to this many files
+    #[arg(long)]
+    batch_small_objects: Option<usize>,
+
+    /// Largest file in bytes that --batch-small-objects treats as small
+    #[arg(long, default_value = "262144", requires = "batch_small_objects")]
+    small_object_size: u64,
+
+    /// Policy for files sharing the same SOP Instance UID
+    #[arg(long, value_enum, default_value = "send-first")]
+    duplicate_policy: DuplicatePolicy,
+
+    /// Send only Key Object Selection documents and the instances they reference
+    #[arg(long)]
+    key_objects: bool,
+
+    /// Send only SR documents with this root template ID (e.g. 2000) and the instances they reference (repeatable)
+    #[arg(long = "sr-template")]
+    sr_templates: Vec<String>,
+
+    /// Treat each study as a transaction and report studies that were only partially sent
+    #[arg(long)]
+    study_transactions: bool,
+
+    /// Write per-study and per-patient reports in this format alongside the summary
+    #[arg(long, value_enum)]
+    report_format: Option<ReportFormat>,
+
+    /// Webhook URL to notify when the run completes (Slack-compatible JSON payload)
+    #[arg(long)]
+    notify_webhook: Option<String>,
+
+    /// Also notify as soon as the failure rate exceeds this percentage
+    #[arg(long, requires = "notify_webhook")]
+    notify_failure_threshold: Option<f64>,
+
+    /// Append every transfer to this hash-chained audit ledger
+    #[arg(long)]
+    ledger: Option<PathBuf>,
+
+    /// Key the ledger's entry hashes and head with HMAC-SHA256 using the key in this file
+    #[arg(long, requires = "ledger")]
+    ledger_key_file: Option<PathBuf>,
+
+    /// Send the C-STORE command in its own P-DATA PDU, for peers that cannot handle several PDVs per PDU
+    #[arg(long)]
+    no_pdv_packing: bool,
+
+    /// Repair common VR and value-length problems before sending
+    #[arg(long)]
+    lenient_repair: bool,
+
+    /// Send reduced-resolution, lossy JPEG derivatives of images instead of the originals, for preview destinations
+    #[arg(long)]
+    preview: bool,
+
+    /// Largest number of rows or columns of a preview
+    #[arg(long, default_value = "512", requires = "preview")]
+    preview_max_size: u16,
+
+    /// JPEG quality of previews, 1 to 100
+    #[arg(long, default_value = "75", requires = "preview", value_parser = clap::value_parser!(u8).range(1..=100))]
+    preview_quality: u8,
+
+    /// Burn text into a corner of previews, `<corner>:<text>` with filename template placeholders; repeatable
+    #[arg(long, value_name = "CORNER:TEXT", requires = "preview", value_parser = Annotation::parse)]
+    preview_annotate: Vec<Annotation>,
+
+    /// Write a manifest of per-instance SHA-256 checksums to this path
+    #[arg(long)]
+    manifest: Option<PathBuf>,
+
+    /// Sign the manifest with HMAC-SHA256 using the key in this file
+    #[arg(long, requires = "manifest")]
+    manifest_key_file: Option<PathBuf>,
+
+    /// Reproducible output for test fixtures: seeded session ID, fixed timestamps, zeroed timings
+    #[arg(long)]
+    deterministic: bool,
+
+    /// Seed for identifiers generated in deterministic mode
+    #[arg(long, default_value = "0", requires = "deterministic")]
+    seed: u64,
+
+    /// List the transfer syntaxes and pixel data codecs this build supports, then exit
+    #[arg(long)]
+    list_codecs: bool,
+
     /// Verbose output
     #[arg(short, long)]
     verbose: bool,
 }
 
+#[derive(Subcommand, Clone)]
+enum SenderCommand {
+    /// Propose every storage SOP class with key transfer syntaxes and report which combinations the remote accepts
+    Probe {
+        /// Called AE Title (destination)
+        #[arg(short = 'a', long)]
+        ae_title: String,
+
+        /// Destination IP address
+        #[arg(short = 'H', long)]
+        host: String,
+
+        /// Destination port
+        #[arg(short, long, default_value = "104")]
+        port: u16,
+
+        /// Calling AE Title
+        #[arg(short = 'c', long, default_value = "RUST_SCU")]
+        calling_ae: String,
+
+        /// Per-address connection timeout in seconds
+        #[arg(long, default_value = "5")]
+        connect_timeout: u64,
+
+        /// Probe only this SOP class (UID or name, repeatable; default: all storage SOP classes)
+        #[arg(long)]
+        sop_class: Vec<String>,
+
+        /// Probe this transfer syntax (UID or name, repeatable; default: uncompressed and common compressed syntaxes)
+        #[arg(long)]
+        transfer_syntax: Vec<String>,
+
+        /// Presentation contexts proposed per association (at most 128)
+        #[arg(long, default_value = "128")]
+        contexts_per_association: usize,
+
+        /// Format of the capability matrix report
+        #[arg(long, value_enum, default_value = "json")]
+        report_format: ReportFormat,
+
+        /// Also write the accepted combinations as a dicom-info capability set
+        #[arg(long)]
+        capability_set: Option<PathBuf>,
+    },
+
+    /// Queue a transfer for the daemon and return immediately
+    Submit {
+        /// Input path (file or directory)
+        #[arg(short, long)]
+        input: PathBuf,
+
+        /// Recursive directory scanning
+        #[arg(short, long)]
+        recursive: bool,
+
+        /// Called AE Title (destination)
+        #[arg(short = 'a', long)]
+        ae_title: String,
+
+        /// Destination IP address
+        #[arg(short = 'H', long)]
+        host: String,
+
+        /// Destination port
+        #[arg(short, long, default_value = "104")]
+        port: u16,
+
+        /// Calling AE Title
+        #[arg(short = 'c', long, default_value = "RUST_SCU")]
+        calling_ae: String,
+
+        /// Job database
+        #[arg(long, default_value = DEFAULT_JOBS_DB)]
+        jobs_db: PathBuf,
+    },
+
+    /// Show all jobs, or one job with its failed files
+    Status {
+        /// Job ID
+        job: Option<i64>,
+
+        /// Print JSON instead of a table
+        #[arg(long)]
+        json: bool,
+
+        /// Job database
+        #[arg(long, default_value = DEFAULT_JOBS_DB)]
+        jobs_db: PathBuf,
+    },
+
+    /// Cancel a queued job, or stop a running one after its current study
+    Cancel {
+        /// Job ID
+        job: i64,
+
+        /// Job database
+        #[arg(long, default_value = DEFAULT_JOBS_DB)]
+        jobs_db: PathBuf,
+    },
+
+    /// Requeue a failed or cancelled job; only files not yet sent are sent again
+    Retry {
+        /// Job ID
+        job: i64,
+
+        /// Job database
+        #[arg(long, default_value = DEFAULT_JOBS_DB)]
+        jobs_db: PathBuf,
+    },
+
+    /// Replicate everything a dicom-receiver stores to a standby receiver
+    Mirror {
+        /// Output directory of the primary receiver
+        #[arg(short, long)]
+        source: PathBuf,
+
+        /// Called AE Title of the standby
+        #[arg(short = 'a', long)]
+        ae_title: String,
+
+        /// Standby IP address
+        #[arg(short = 'H', long)]
+        host: String,
+
+        /// Standby port
+        #[arg(short, long, default_value = "104")]
+        port: u16,
+
+        /// Calling AE Title
+        #[arg(short = 'c', long, default_value = "RUST_SCU")]
+        calling_ae: String,
+
+        /// File recording what has been replicated
+        #[arg(long, default_value = DEFAULT_MIRROR_STATE)]
+        state: PathBuf,
+
+        /// Replication lag per study, rewritten after every pass
+        #[arg(long, default_value = DEFAULT_MIRROR_LAG_REPORT)]
+        lag_report: PathBuf,
+
+        /// Seconds between scans of the source directory
+        #[arg(long, default_value = "5")]
+        poll_interval: u64,
+
+        /// Replicate the backlog once and exit, e.g. after an outage of the standby
+        #[arg(long)]
+        catch_up: bool,
+
+        /// Per-address connection timeout in seconds
+        #[arg(long, default_value = "5")]
+        connect_timeout: u64,
+    },
+
+    /// Copy the job database, also while a daemon is using it
+    Backup {
+        /// Backup file to create
+        output: PathBuf,
+
+        /// Job database
+        #[arg(long, default_value = DEFAULT_JOBS_DB)]
+        jobs_db: PathBuf,
+    },
+
+    /// Run queued jobs one after another
+    Daemon {
+        /// Job database
+        #[arg(long, default_value = DEFAULT_JOBS_DB)]
+        jobs_db: PathBuf,
+
+        /// Pages the job database's write-ahead log may reach before it is checkpointed
+        #[arg(long, default_value = "1000")]
+        wal_checkpoint_pages: u32,
+
+        /// Hours between compactions of the job database (0 disables them)
+        #[arg(long, default_value = "24")]
+        vacuum_interval: u64,
+
+        /// Seconds between checks for new jobs
+        #[arg(long, default_value = "5")]
+        poll_interval: u64,
+
+        /// Exit once the queue is empty instead of waiting for new jobs
+        #[arg(long)]
+        once: bool,
+
+        /// Per-address connection timeout in seconds
+        #[arg(long, default_value = "5")]
+        connect_timeout: u64,
+
+        /// Serve HTTP /healthz and /readyz probes on this port
+        #[arg(long)]
+        health_port: Option<u16>,
+    },
+}
+
+const DEFAULT_JOBS_DB: &str = "logs/dicom_sender_jobs.db";
+const DEFAULT_MIRROR_STATE: &str = "logs/dicom_mirror_state.json";
+const DEFAULT_MIRROR_LAG_REPORT: &str = "logs/dicom_mirror_lag.json";
+
+/// Studies waiting for a worker
+type StudyQueue = Arc<Mutex<VecDeque<(String, Vec<DicomFile>)>>>;
+
+/// Optional services shared by every worker of a run
+#[derive(Clone)]
+struct WorkerServices {
+    notifier: Option<Arc<Notifier>>,
+    ledger: Option<Arc<Mutex<Ledger>>>,
+    controller: Option<Arc<ConcurrencyController>>,
+    meter: Option<Arc<TransferMeter>>,
+    tls: Option<Arc<rustls::ClientConfig>>,
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
-    let args = Args::parse();
+    let mut args = Args::parse();
+
+    if args.list_codecs {
+        print!("{}", codec_report(TransferSyntaxRegistry::global()));
+        return Ok(());
+    }
+
+    // Job management only touches the job database
+    match args.command.clone() {
+        Some(SenderCommand::Submit { input, recursive, ae_title, host, port, calling_ae, jobs_db }) => {
+            let input = std::fs::canonicalize(&input)
+                .map_err(|e| anyhow::anyhow!("{}: {}", input.display(), e))?;
+            let spec = JobSpec { input, recursive, calling_ae, called_ae: ae_title, host, port };
+            let id = JobStore::open(&jobs_db)?.submit(&spec)?;
+            println!("✅ Queued job {} ({} -> {}@{}:{})", style(id).cyan(), spec.input.display(),
+                     spec.called_ae, spec.host, spec.port);
+            return Ok(());
+        }
+        Some(SenderCommand::Status { job, json, jobs_db }) => return show_job_status(&JobStore::open(&jobs_db)?, job, json),
+        Some(SenderCommand::Cancel { job, jobs_db }) => {
+            let job = JobStore::open(&jobs_db)?.cancel(job)?;
+            println!("🛑 Cancelled job {}{}", style(job.id).cyan(),
+                     if job.started_at.is_some() { " (stops after the current study)" } else { "" });
+            return Ok(());
+        }
+        Some(SenderCommand::Retry { job, jobs_db }) => {
+            let pending = JobStore::open(&jobs_db)?.retry(job)?;
+            println!("🔁 Requeued job {} with {} files to send", style(job).cyan(), style(pending).green());
+            return Ok(());
+        }
+        Some(SenderCommand::Backup { output, jobs_db }) => {
+            JobStore::open(&jobs_db)?.backup(&output)?;
+            println!("💾 Backed up {} to {}", jobs_db.display(), style(output.display()).yellow());
+            return Ok(());
+        }
+        _ => {}
+    }
 
     // Initialize logging
-    let session_id = Uuid::new_v4().to_string();
+    let session_id = if args.deterministic {
+        seeded_uuid(args.seed, "dicom-sender-session").to_string()
+    } else {
+        Uuid::new_v4().to_string()
+    };
     
     // Create logs directory if it doesn't exist
     std::fs::create_dir_all("logs")?;
@@ -95,6 +534,85 @@ async fn main() -> Result<()> {
     println!("Log file: {}", style(&log_file).yellow());
     println!();
 
+    if let Some(SenderCommand::Probe {
+        ae_title, host, port, calling_ae, connect_timeout, sop_class, transfer_syntax,
+        contexts_per_association, report_format, capability_set,
+    }) = args.command.clone() {
+        let config = DicomClientConfig {
+            calling_ae,
+            called_ae: ae_title,
+            host,
+            port,
+            timeout: Duration::from_secs(30),
+            connect_timeout: Duration::from_secs(connect_timeout),
+            lenient_repair: false,
+            compute_checksums: false,
+            pack_pdvs: true,
+            tls: None,
+            preview: None,
+        };
+        return run_probe(config, sop_class, transfer_syntax, contexts_per_association, report_format, capability_set, &session_id).await;
+    }
+
+    if let Some(SenderCommand::Mirror {
+        source, ae_title, host, port, calling_ae, state, lag_report, poll_interval, catch_up, connect_timeout,
+    }) = args.command.clone() {
+        let client = DicomClient::new(DicomClientConfig {
+            calling_ae,
+            called_ae: ae_title,
+            host,
+            port,
+            timeout: Duration::from_secs(30),
+            connect_timeout: Duration::from_secs(connect_timeout),
+            lenient_repair: false,
+            compute_checksums: false,
+            pack_pdvs: true,
+            tls: None,
+            preview: None,
+        });
+        return run_mirror(&client, &source, &state, &lag_report, Duration::from_secs(poll_interval), catch_up).await;
+    }
+
+    if let Some(SenderCommand::Daemon {
+        jobs_db, wal_checkpoint_pages, vacuum_interval, poll_interval, once, connect_timeout, health_port,
+    }) = args.command.clone() {
+        let vacuum_interval = (vacuum_interval > 0).then(|| Duration::from_secs(vacuum_interval * 3600));
+        // Ready once the job queue is open and the daemon waits for jobs
+        let readiness = Arc::new(Readiness::new(1));
+        if let Some(port) = health_port {
+            let address = std::net::SocketAddr::new(DEFAULT_BIND_ADDRESS, port);
+            let listener = bind_health_listener(address)
+                .map_err(|e| anyhow::anyhow!("Failed to listen for health probes on {}: {}", address, e))?;
+            println!("🩺 Health probes on http://{}/healthz and /readyz", address);
+            tokio::spawn(serve_health(listener, Arc::clone(&readiness), None));
+        }
+        let store = JobStore::open(&jobs_db)?;
+        store.set_checkpoint_pages(wal_checkpoint_pages)?;
+        return run_daemon(store, &jobs_db, vacuum_interval, Duration::from_secs(poll_interval), once,
+                          Duration::from_secs(connect_timeout), &readiness).await;
+    }
+
+    if args.discover {
+        println!("🔎 Discovering {} over mDNS...", style(&args.ae_title).green());
+        let service = discover(&args.ae_title, Duration::from_secs(args.discover_timeout))?;
+        info!("Discovered {} at {}:{}", service.ae_title, service.host, service.port);
+        println!("✅ Found {} at {}:{}", style(&service.ae_title).green(), service.host, service.port);
+        args.host = service.host;
+        args.port = service.port;
+    }
+
+    let tls = if args.tls {
+        let identity = args.tls_cert.as_deref().zip(args.tls_key.as_deref());
+        let config = client_config(args.tls_ca.as_deref(), identity, &CipherPolicy::Bcp195)?;
+        println!("🔒 Associations use TLS{}", if identity.is_some() { " with a client certificate" } else { "" });
+        Some(config)
+    } else {
+        None
+    };
+    if args.preview {
+        println!("🖼️  Sending previews of at most {} px, JPEG quality {}", args.preview_max_size, args.preview_quality);
+    }
+
     let start_time = Utc::now();
 
     // Step 1: Index all DICOM files
@@ -108,6 +626,37 @@ async fn main() -> Result<()> {
 
     println!("✅ Found {} DICOM files", style(dicom_files.len()).green());
 
+    let (dicom_files, duplicate_conflicts) = resolve_duplicate_uids(dicom_files, args.duplicate_policy);
+    if !duplicate_conflicts.is_empty() {
+        println!("⚠️  {} SOP Instance UIDs claimed by multiple files (policy: {:?})",
+                 style(duplicate_conflicts.len()).yellow(), args.duplicate_policy);
+        for conflict in &duplicate_conflicts {
+            warn!("Duplicate SOP Instance UID {} in {} files, sending {:?}",
+                  conflict.sop_instance_uid, conflict.files.len(), conflict.sent_file);
+        }
+    }
+
+    let selection = SelectionPolicy {
+        key_objects: args.key_objects,
+        sr_templates: args.sr_templates.clone(),
+    };
+    let dicom_files = if selection.is_enabled() {
+        let documents = read_referencing_documents(&dicom_files, &selection);
+        if documents.is_empty() {
+            println!("❌ No Key Object Selection or matching SR documents found!");
+            return Ok(());
+        }
+        let (selected, skipped) = select_referenced(dicom_files, &documents);
+        let referenced: usize = documents.iter().map(|doc| doc.referenced.len()).sum();
+        info!("Selected {} file(s) from {} referencing document(s), {} not referenced",
+              selected.len(), documents.len(), skipped);
+        println!("🔑 Selected {} file(s) referenced by {} document(s) ({} referenced, {} other files skipped)",
+                 style(selected.len()).green(), documents.len(), referenced, skipped);
+        selected
+    } else {
+        dicom_files
+    };
+
     // Step 2: Group by Study Instance UID
     let mut studies: HashMap<String, Vec<DicomFile>> = HashMap::new();
     for file in &dicom_files {
@@ -134,21 +683,74 @@ async fn main() -> Result<()> {
     );
 
     // Step 4: Send files using multiple threads
-    println!("{} Starting transfer with {} threads...", ROCKET, args.threads);
+    if args.adaptive_concurrency {
+        println!("{} Starting transfer with {} threads (adaptive, {}-{})...", ROCKET, args.threads, args.min_threads, args.max_threads);
+    } else {
+        println!("{} Starting transfer with {} threads...", ROCKET, args.threads);
+    }
     
-    let study_chunks: Vec<_> = studies.into_iter().collect();
+    let mut study_chunks: Vec<_> = studies.into_iter().collect();
+    study_chunks.sort_by(|a, b| a.0.cmp(&b.0));
     let chunk_size = (study_chunks.len() + args.threads - 1) / args.threads;
     
     let mut handles: Vec<JoinHandle<Result<TransferStats>>> = Vec::new();
     let mut all_results: Vec<TransferStats> = Vec::new();
 
-    for (thread_id, chunk) in study_chunks.chunks(chunk_size).enumerate() {
-        let chunk = chunk.to_vec();
+    let ledger = match &args.ledger {
+        Some(path) => {
+            let key = args.ledger_key_file.as_deref().map(ledger::read_key).transpose()?;
+            Some(Arc::new(Mutex::new(Ledger::open(path, key)?)))
+        }
+        None => None,
+    };
+
+    let notifier = args.notify_webhook.clone()
+        .map(|url| Arc::new(Notifier::new(url, args.notify_failure_threshold)));
+
+    let controller = args.adaptive_concurrency
+        .then(|| Arc::new(ConcurrencyController::new(args.threads, args.min_threads, args.max_threads)));
+
+    // Adaptive mode shares one queue between as many workers as the upper
+    // bound allows; otherwise each thread gets a fixed share of the studies
+    let queues: Vec<StudyQueue> = match &controller {
+        Some(controller) => {
+            let queue: StudyQueue = Arc::new(Mutex::new(study_chunks.into_iter().collect()));
+            (0..controller.max()).map(|_| queue.clone()).collect()
+        }
+        None => study_chunks.chunks(chunk_size)
+            .map(|chunk| Arc::new(Mutex::new(chunk.iter().cloned().collect())))
+            .collect(),
+    };
+
+    // Throughput is sampled from a meter the clients update per instance
+    let meter = args.metrics_interval.map(|_| Arc::new(TransferMeter::default()));
+    let recorder = meter.clone().map(|meter| Arc::new(Mutex::new(ThroughputRecorder::new(meter))));
+    let sampler = recorder.clone().zip(args.metrics_interval).map(|(recorder, seconds)| {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(Duration::from_secs(seconds.max(1)));
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                recorder.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).sample();
+            }
+        })
+    });
+
+    let services = WorkerServices {
+        notifier: notifier.clone(),
+        ledger,
+        controller: controller.clone(),
+        meter,
+        tls,
+    };
+
+    for (thread_id, queue) in queues.into_iter().enumerate() {
         let args = args.clone();
         let progress = main_progress.clone();
+        let services = services.clone();
 
         let handle = tokio::spawn(async move {
-            send_studies_worker(thread_id, chunk, &args, progress).await
+            send_studies_worker(thread_id, queue, &args, progress, services).await
         });
 
         handles.push(handle);
@@ -158,16 +760,7 @@ async fn main() -> Result<()> {
     let mut combined_stats = TransferStats::new();
     for handle in handles {
         match handle.await? {
-            Ok(stats) => {
-                combined_stats.total_files += stats.total_files;
-                combined_stats.successful_transfers += stats.successful_transfers;
-                combined_stats.failed_transfers += stats.failed_transfers;
-                combined_stats.total_bytes += stats.total_bytes;
-                combined_stats.transfer_times.extend(stats.transfer_times);
-                if combined_stats.total_time < stats.total_time {
-                    combined_stats.total_time = stats.total_time;
-                }
-            }
+            Ok(stats) => combined_stats.merge(stats),
             Err(e) => {
                 error!("Thread failed: {}", e);
             }
@@ -176,11 +769,46 @@ async fn main() -> Result<()> {
 
     main_progress.finish_with_message("Transfer completed!");
 
+    if let Some(sampler) = sampler {
+        sampler.abort();
+    }
+    let throughput_samples = recorder
+        .map(|recorder| recorder.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).finish())
+        .unwrap_or_default();
+
     let end_time = Utc::now();
     let duration = end_time.signed_duration_since(start_time);
 
+    // Write the checksum manifest, signed if a key was provided
+    if let Some(manifest_path) = &args.manifest {
+        let mut manifest = Manifest::new(&session_id, &args.calling_ae, &args.ae_title,
+                                         std::mem::take(&mut combined_stats.manifest_entries));
+        if args.deterministic {
+            manifest.created = fixed_timestamp();
+        }
+        if let Some(key_file) = &args.manifest_key_file {
+            let key = std::fs::read_to_string(key_file)?;
+            manifest.sign(key.trim().as_bytes());
+        }
+        manifest.write_to(manifest_path)?;
+        println!("🔐 Manifest with {} checksums written to {}",
+                 style(manifest.entries.len()).green(), style(manifest_path.display()).yellow());
+    }
+
     // Step 5: Generate summary
-    let summary = SessionSummary {
+    let mut studies_processed: Vec<String> = dicom_files.iter()
+        .map(|f| f.study_instance_uid.clone())
+        .collect::<std::collections::HashSet<_>>()
+        .into_iter()
+        .collect();
+    studies_processed.sort();
+    combined_stats.failed_studies.sort_by(|a, b| a.study_instance_uid.cmp(&b.study_instance_uid));
+    combined_stats.association_rejections.sort_by(|a, b| a.study_instance_uid.cmp(&b.study_instance_uid));
+    // Each association renegotiates, so the same refusal is reported once per study
+    combined_stats.refused_contexts.sort_by(|a, b| a.sop_class_uid.cmp(&b.sop_class_uid));
+    combined_stats.refused_contexts.dedup();
+
+    let mut summary = SessionSummary {
         session_id: session_id.clone(),
         start_time,
         end_time,
@@ -188,24 +816,67 @@ async fn main() -> Result<()> {
         successful_transfers: combined_stats.successful_transfers,
         failed_transfers: combined_stats.failed_transfers,
         total_bytes: combined_stats.total_bytes,
+        original_bytes: combined_stats.results.iter().filter(|r| r.success).map(|r| r.file_size).sum(),
         total_time_ms: duration.num_milliseconds() as u64,
         average_transfer_time_ms: combined_stats.get_average_transfer_time_ms(),
         throughput_mbps: combined_stats.get_throughput_mbps(),
-        threads_used: args.threads,
+        wire_bytes_sent: combined_stats.wire_bytes_sent,
+        wire_bytes_received: combined_stats.wire_bytes_received,
+        wire_throughput_mbps: combined_stats.get_wire_throughput_mbps(),
+        threads_used: controller.as_ref().map_or(args.threads, |controller| controller.peak()),
         destination: format!("{}:{}@{}", args.ae_title, args.port, args.host),
         calling_ae: args.calling_ae,
         called_ae: args.ae_title,
-        studies_processed: dicom_files.iter()
-            .map(|f| f.study_instance_uid.clone())
-            .collect::<std::collections::HashSet<_>>()
-            .into_iter()
-            .collect(),
+        studies_processed,
+        duplicate_uid_conflicts: duplicate_conflicts,
+        failed_studies: combined_stats.failed_studies,
+        association_rejections: combined_stats.association_rejections,
+        refused_presentation_contexts: combined_stats.refused_contexts,
+        throughput_samples,
     };
+    if args.deterministic {
+        summary.start_time = fixed_timestamp();
+        summary.end_time = fixed_timestamp();
+        summary.total_time_ms = 0;
+        summary.average_transfer_time_ms = 0.0;
+        summary.throughput_mbps = 0.0;
+        summary.wire_throughput_mbps = 0.0;
+    }
+
+    // Per-study and per-patient reports for migration QA
+    let mut report_files = Vec::new();
+    if let Some(format) = args.report_format {
+        let study_reports = build_study_reports(&dicom_files, &combined_stats.results);
+        let patient_reports = build_patient_reports(&study_reports);
+        let (extension, studies_out, patients_out) = match format {
+            ReportFormat::Csv => ("csv", study_reports_csv(&study_reports), patient_reports_csv(&patient_reports)),
+            ReportFormat::Json => (
+                "json",
+                serde_json::to_string_pretty(&study_reports)?,
+                serde_json::to_string_pretty(&patient_reports)?,
+            ),
+        };
+        let study_file = format!("logs/dicom_sender_studies_{}.{}", session_id, extension);
+        let patient_file = format!("logs/dicom_sender_patients_{}.{}", session_id, extension);
+        std::fs::write(&study_file, studies_out)?;
+        std::fs::write(&patient_file, patients_out)?;
+        report_files.push(study_file);
+        report_files.push(patient_file);
+    }
+    if !summary.throughput_samples.is_empty() {
+        let metrics_file = format!("logs/dicom_sender_metrics_{}.csv", session_id);
+        std::fs::write(&metrics_file, samples_csv(&summary.throughput_samples))?;
+        report_files.push(metrics_file);
+    }
 
     // Write summary to file
     let summary_json = serde_json::to_string_pretty(&summary)?;
     std::fs::write(&summary_file, summary_json)?;
 
+    if let Some(notifier) = notifier {
+        Notifier::post_async(notifier, Notifier::completion_payload(&summary)).await;
+    }
+
     // Print final statistics
     println!();
     println!("{} Transfer Summary", STOPWATCH);
@@ -214,24 +885,400 @@ async fn main() -> Result<()> {
     println!("Successful:      {}", style(summary.successful_transfers).green());
     println!("Failed:          {}", style(summary.failed_transfers).red());
     println!("Total size:      {:.2} MB", summary.total_bytes as f64 / (1024.0 * 1024.0));
+    if let Some(ratio) = compression_ratio(summary.original_bytes, summary.total_bytes) {
+        let saved = summary.original_bytes as i64 - summary.total_bytes as i64;
+        println!("Compression:     {:.2} MB on disk sent as {:.2} MB ({:.2}:1, {:.2} MB {})",
+                 summary.original_bytes as f64 / (1024.0 * 1024.0), summary.total_bytes as f64 / (1024.0 * 1024.0),
+                 ratio, saved.unsigned_abs() as f64 / (1024.0 * 1024.0), if saved >= 0 { "saved" } else { "added" });
+    }
     println!("Total time:      {:.2} seconds", duration.num_milliseconds() as f64 / 1000.0);
     println!("Avg transfer:    {:.2} ms", summary.average_transfer_time_ms);
     println!("Throughput:      {:.2} MB/s", summary.throughput_mbps);
+    if summary.wire_bytes_sent > 0 {
+        let overhead = summary.wire_bytes_sent.saturating_sub(summary.total_bytes) as f64 / summary.wire_bytes_sent as f64;
+        println!("On the wire:     {:.2} MB sent, {:.2} KB received ({:.1}% overhead, {:.2} MB/s)",
+                 summary.wire_bytes_sent as f64 / (1024.0 * 1024.0), summary.wire_bytes_received as f64 / 1024.0,
+                 overhead * 100.0, summary.wire_throughput_mbps);
+    }
     println!("Threads used:    {}", summary.threads_used);
     println!("Studies:         {}", summary.studies_processed.len());
+    if !summary.duplicate_uid_conflicts.is_empty() {
+        println!("Duplicate UIDs:  {}", style(summary.duplicate_uid_conflicts.len()).yellow());
+    }
+    if args.study_transactions {
+        println!("Incomplete studies: {}", style(summary.failed_studies.len()).red());
+    }
+    if !summary.association_rejections.is_empty() {
+        println!("Rejected associations: {}", style(summary.association_rejections.len()).red());
+        let mut reasons: Vec<&str> = summary.association_rejections.iter().map(|r| r.description.as_str()).collect();
+        reasons.sort();
+        reasons.dedup();
+        for reason in reasons {
+            println!("  {}", style(reason).red());
+        }
+    }
+    for refused in &summary.refused_presentation_contexts {
+        println!("Refused context: {} - {}", refused.sop_class_uid, style(&refused.description).yellow());
+    }
     println!();
     println!("📄 Detailed log: {}", style(&log_file).yellow());
     println!("📊 Summary JSON: {}", style(&summary_file).yellow());
+    for report_file in &report_files {
+        println!("📑 Report:       {}", style(report_file).yellow());
+    }
+
+    // Distinguish rejected associations in the exit status: 3 if retrying later may help, 2 otherwise
+    if !summary.association_rejections.is_empty() {
+        let transient = summary.association_rejections.iter().all(|r| r.rejection.is_transient());
+        std::process::exit(if transient { 3 } else { 2 });
+    }
 
     Ok(())
 }
 
+/// Probe the remote's presentation context support and write the capability matrix
+async fn run_probe(
+    config: DicomClientConfig,
+    sop_classes: Vec<String>,
+    transfer_syntaxes: Vec<String>,
+    contexts_per_association: usize,
+    report_format: ReportFormat,
+    capability_set: Option<PathBuf>,
+    session_id: &str,
+) -> Result<()> {
+    let sop_registry = SopClassRegistry::global();
+    let ts_registry = TransferSyntaxRegistry::global();
+
+    let sop_classes: Vec<String> = if sop_classes.is_empty() {
+        sop_registry.get_all_uids().into_iter().map(str::to_string).collect()
+    } else {
+        sop_classes.iter()
+            .map(|query| sop_registry.find(query).map(|info| info.uid.to_string()).unwrap_or_else(|| query.trim().to_string()))
+            .collect()
+    };
+    let transfer_syntaxes: Vec<String> = if transfer_syntaxes.is_empty() {
+        PROBE_TRANSFER_SYNTAXES.iter().map(|uid| uid.to_string()).collect()
+    } else {
+        transfer_syntaxes.iter()
+            .map(|query| ts_registry.find(query).map(|info| info.uid.to_string()).unwrap_or_else(|| query.trim().to_string()))
+            .collect()
+    };
+
+    let batches = probe_batches(&sop_classes, &transfer_syntaxes, contexts_per_association);
+    println!("🔬 Probing {}@{}:{}: {} SOP classes × {} transfer syntaxes in {} associations",
+             style(&config.called_ae).green(), config.host, config.port,
+             sop_classes.len(), transfer_syntaxes.len(), batches.len());
+    if contexts_per_association > MAX_CONTEXTS_PER_ASSOCIATION {
+        println!("⚠️  At most {} presentation contexts fit in one association", MAX_CONTEXTS_PER_ASSOCIATION);
+    }
+
+    let progress = ProgressBar::new(batches.len() as u64);
+    let client = DicomClient::new(config.clone());
+    let mut results = Vec::new();
+    let mut associations = 0;
+    let mut rejection: Option<AssociationRejection> = None;
+    for batch in &batches {
+        if let Some(rejection) = rejection.filter(|r| !r.is_transient()) {
+            // A permanent rejection will not change for the remaining batches
+            let note = format!("not probed: {}", rejection);
+            results.extend(batch.iter().map(|(sop, ts)| ProbeResult::new(sop, ts, None, Some(&note))));
+            progress.inc(1);
+            continue;
+        }
+
+        match client.probe_contexts(batch.clone()).await {
+            Ok(codes) => {
+                associations += 1;
+                for ((sop, ts), code) in batch.iter().zip(codes) {
+                    results.push(ProbeResult::new(sop, ts, code, None));
+                }
+            }
+            Err(e) => {
+                warn!("Probe association failed: {}", e);
+                if let Some(rejected) = e.downcast_ref::<AssociationRejection>() {
+                    rejection = Some(*rejected);
+                }
+                let note = e.to_string();
+                results.extend(batch.iter().map(|(sop, ts)| ProbeResult::new(sop, ts, None, Some(&note))));
+            }
+        }
+        progress.inc(1);
+    }
+    progress.finish_and_clear();
+
+    let matrix = CapabilityMatrix {
+        called_ae: config.called_ae.clone(),
+        host: config.host.clone(),
+        port: config.port,
+        probed_at: Utc::now(),
+        associations,
+        sop_classes,
+        transfer_syntaxes,
+        results,
+    };
+
+    let (report, extension) = match report_format {
+        ReportFormat::Csv => (matrix.to_csv(), "csv"),
+        ReportFormat::Json => (serde_json::to_string_pretty(&matrix)?, "json"),
+    };
+    let report_file = format!("logs/dicom_sender_probe_{}.{}", session_id, extension);
+    std::fs::write(&report_file, report)?;
+    if let Some(path) = &capability_set {
+        std::fs::write(path, serde_json::to_string_pretty(&matrix.to_capability_set())?)?;
+    }
+
+    println!();
+    println!("{} Capability Matrix", STOPWATCH);
+    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    println!("Accepted combinations: {}/{}", style(matrix.accepted().count()).green(), matrix.results.len());
+    let supported = matrix.sop_classes.iter()
+        .filter(|sop| matrix.transfer_syntaxes.iter().any(|ts| matrix.get(sop, ts).is_some_and(ProbeResult::is_accepted)))
+        .count();
+    println!("SOP classes accepted:  {}/{}", style(supported).green(), matrix.sop_classes.len());
+    let lossless = matrix.sop_classes.iter()
+        .filter_map(|sop| matrix.best_transfer_syntax(sop, &matrix.transfer_syntaxes, TransferSyntaxPolicy::PreferLossless))
+        .filter(|ts| ts_registry.is_lossless(ts))
+        .count();
+    println!("  of those lossless:   {}/{}", style(lossless).green(), supported);
+    for ts in &matrix.transfer_syntaxes {
+        let accepted = matrix.accepted().filter(|result| &result.transfer_syntax_uid == ts).count();
+        println!("  {:<48} {}", ts_registry.get_name(ts).unwrap_or(ts), style(accepted).cyan());
+    }
+    for warning in matrix.retired_warnings() {
+        println!("{} {}", style("⚠️").yellow(), warning);
+    }
+    println!();
+    println!("📑 Report:       {}", style(&report_file).yellow());
+    if let Some(path) = &capability_set {
+        println!("📑 Capabilities: {}", style(path.display()).yellow());
+    }
+
+    if let Some(rejection) = rejection {
+        println!("❌ {}", style(rejection).red());
+        std::process::exit(if rejection.is_transient() { 3 } else { 2 });
+    }
+    Ok(())
+}
+
+/// Print the job table, or the detail of one job
+fn show_job_status(store: &JobStore, job: Option<i64>, json: bool) -> Result<()> {
+    let Some(id) = job else {
+        let jobs = store.list()?;
+        if json {
+            println!("{}", serde_json::to_string_pretty(&jobs)?);
+            return Ok(());
+        }
+        println!("{:>5}  {:<10} {:>7} {:>7} {:>7}  {:<20} INPUT", "ID", "STATUS", "FILES", "SENT", "FAILED", "DESTINATION");
+        for job in &jobs {
+            println!("{:>5}  {:<10} {:>7} {:>7} {:>7}  {:<20} {}", job.id, job.status, job.total_files, job.sent_files,
+                     job.failed_files, format!("{}@{}:{}", job.spec.called_ae, job.spec.host, job.spec.port),
+                     job.spec.input.display());
+        }
+        return Ok(());
+    };
+
+    let job = store.get(id)?.ok_or_else(|| anyhow::anyhow!("No job {}", id))?;
+    let files = store.files(id)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&serde_json::json!({ "job": job, "files": files }))?);
+        return Ok(());
+    }
+    println!("Job {}: {}", style(job.id).cyan(), job.status);
+    println!("Input:        {}{}", job.spec.input.display(), if job.spec.recursive { " (recursive)" } else { "" });
+    println!("Destination:  {}@{}:{} as {}", job.spec.called_ae, job.spec.host, job.spec.port, job.spec.calling_ae);
+    println!("Submitted:    {}", job.created_at.to_rfc3339());
+    if let Some(started_at) = job.started_at {
+        println!("Started:      {}", started_at.to_rfc3339());
+    }
+    if let Some(finished_at) = job.finished_at {
+        println!("Finished:     {}", finished_at.to_rfc3339());
+    }
+    println!("Files:        {} ({} sent, {} failed)", job.total_files, style(job.sent_files).green(), style(job.failed_files).red());
+    if let Some(error) = &job.error {
+        println!("Error:        {}", style(error).red());
+    }
+    for file in files.iter().filter(|file| file.status == FileStatus::Failed) {
+        println!("  {} - {}", file.path, style(file.error.as_deref().unwrap_or("failed")).red());
+    }
+    Ok(())
+}
+
+/// Take queued jobs in submission order and run them until the queue is empty
+/// (with `once`) or forever, compacting the job database between jobs every
+/// `vacuum_interval`
+async fn run_daemon(mut store: JobStore, jobs_db: &Path, vacuum_interval: Option<Duration>, poll_interval: Duration,
+                    once: bool, connect_timeout: Duration, readiness: &Readiness) -> Result<()> {
+    let requeued = store.requeue_interrupted()?;
+    if requeued > 0 {
+        warn!("Requeued {} jobs interrupted by a previous daemon", requeued);
+        println!("⚠️  Requeued {} interrupted jobs", style(requeued).yellow());
+    }
+    readiness.component_ready();
+    println!("{} Waiting for jobs in {}", CLIPBOARD, style(jobs_db.display()).yellow());
+
+    let mut last_compaction = Instant::now();
+    loop {
+        if vacuum_interval.is_some_and(|interval| last_compaction.elapsed() >= interval) {
+            match store.compact() {
+                Ok(reclaimed) => info!("Compacted job database, reclaimed {} bytes", reclaimed),
+                Err(e) => warn!("{:#}", e),
+            }
+            last_compaction = Instant::now();
+        }
+
+        let Some(job) = store.claim_next()? else {
+            if once {
+                return Ok(());
+            }
+            tokio::time::sleep(poll_interval).await;
+            continue;
+        };
+
+        println!("{} Job {}: {} -> {}@{}:{}", ROCKET, style(job.id).cyan(), job.spec.input.display(),
+                 job.spec.called_ae, job.spec.host, job.spec.port);
+        info!("Starting job {}", job.id);
+        let (status, error) = match run_job(&mut store, job.id, &job.spec, connect_timeout).await {
+            Ok(0) => (JobStatus::Completed, None),
+            Ok(failed) => (JobStatus::Failed, Some(format!("{} files failed", failed))),
+            Err(e) => (JobStatus::Failed, Some(e.to_string())),
+        };
+        store.finish(job.id, status, error.as_deref())?;
+
+        let job = store.get(job.id)?.ok_or_else(|| anyhow::anyhow!("Job {} disappeared", job.id))?;
+        info!("Job {} {}: {} sent, {} failed", job.id, job.status, job.sent_files, job.failed_files);
+        println!("  {} {}: {}/{} files sent{}", if job.status == JobStatus::Completed { "✅" } else { "❌" },
+                 job.status, style(job.sent_files).green(), job.total_files,
+                 job.error.as_ref().map(|e| format!(" ({})", e)).unwrap_or_default());
+    }
+}
+
+/// Replicate new objects of `source` study by study until stopped, or until
+/// the backlog is sent (with `catch_up`)
+async fn run_mirror(client: &DicomClient, source: &Path, state_path: &Path, lag_report_path: &Path,
+                    poll_interval: Duration, catch_up: bool) -> Result<()> {
+    let mut state = MirrorState::load(state_path)?;
+    println!("🪞 Mirroring {} ({} objects replicated so far)", style(source.display()).yellow(), state.replicated_count());
+
+    loop {
+        let mut studies: Vec<(String, Vec<_>)> = Vec::new();
+        for source_file in scan_source(source, &state)? {
+            let file = match process_dicom_file(&source_file.path).await {
+                Ok(Some(file)) => file,
+                // Not readable as DICOM yet, e.g. still being written; retried on the next pass
+                Ok(None) | Err(_) => continue,
+            };
+            match studies.iter_mut().find(|(study_uid, _)| *study_uid == file.study_instance_uid) {
+                Some((_, files)) => files.push((source_file, file)),
+                None => studies.push((file.study_instance_uid.clone(), vec![(source_file, file)])),
+            }
+        }
+
+        let mut replicated = 0;
+        let mut pending = Vec::new();
+        for (study_uid, files) in studies {
+            let results = match client.send_files(files.iter().map(|(_, file)| file.clone()).collect()).await {
+                Ok(stats) => stats.results,
+                Err(e) => {
+                    warn!("Mirror: failed to replicate study {}: {}", study_uid, e);
+                    Vec::new()
+                }
+            };
+            let now = Utc::now();
+            for (source_file, file) in &files {
+                let sent = results.iter()
+                    .any(|result| result.success && result.file_path == file.path.display().to_string());
+                if sent {
+                    state.record(source_file, &study_uid, now);
+                    replicated += 1;
+                } else {
+                    pending.push((study_uid.clone(), source_file.modified));
+                }
+            }
+            state.save(state_path)?;
+        }
+
+        let report = lag_report(&state, &pending, Utc::now());
+        std::fs::write(lag_report_path, serde_json::to_string_pretty(&report)?)?;
+        if replicated > 0 || !pending.is_empty() {
+            info!("Mirror pass: {} replicated, {} pending, max lag {}s", replicated, pending.len(), report.max_lag_seconds);
+            println!("🪞 {} replicated, {} pending{}", style(replicated).green(), style(pending.len()).yellow(),
+                     report.studies.first()
+                         .map(|study| format!(", study {} behind by {}s", study.study_instance_uid, study.lag_seconds))
+                         .unwrap_or_default());
+        }
+
+        if catch_up {
+            if !pending.is_empty() {
+                anyhow::bail!("{} objects could not be replicated", pending.len());
+            }
+            println!("✅ Standby caught up: {} objects replicated", state.replicated_count());
+            return Ok(());
+        }
+        tokio::time::sleep(poll_interval).await;
+    }
+}
+
+/// Send the pending files of a job study by study, recording each outcome;
+/// returns the number of files that failed
+async fn run_job(store: &mut JobStore, id: i64, spec: &JobSpec, connect_timeout: Duration) -> Result<usize> {
+    if store.files(id)?.is_empty() {
+        let files = index_dicom_files(&spec.input, spec.recursive).await?;
+        if files.is_empty() {
+            anyhow::bail!("No DICOM files found in {}", spec.input.display());
+        }
+        store.add_files(id, &files)?;
+    }
+
+    let mut studies: Vec<(String, Vec<DicomFile>)> = Vec::new();
+    for file in store.pending_files(id)? {
+        match studies.last_mut() {
+            Some((study_uid, files)) if *study_uid == file.study_instance_uid => files.push(file),
+            _ => studies.push((file.study_instance_uid.clone(), vec![file])),
+        }
+    }
+
+    let client = DicomClient::new(DicomClientConfig {
+        calling_ae: spec.calling_ae.clone(),
+        called_ae: spec.called_ae.clone(),
+        host: spec.host.clone(),
+        port: spec.port,
+        timeout: Duration::from_secs(30),
+        connect_timeout,
+        lenient_repair: false,
+        compute_checksums: false,
+        pack_pdvs: true,
+        tls: None,
+        preview: None,
+    });
+
+    for (study_uid, files) in studies {
+        if store.is_cancelled(id)? {
+            info!("Job {} cancelled, stopping before study {}", id, study_uid);
+            break;
+        }
+        let results = match client.send_files(files.clone()).await {
+            Ok(stats) => stats.results,
+            Err(e) => {
+                error!("Job {}: failed to send study {}: {}", id, study_uid, e);
+                files.iter()
+                    .map(|file| DicomClient::transfer_result(file, Some(e.to_string()), Duration::ZERO))
+                    .collect()
+            }
+        };
+        store.record_results(id, &results)?;
+    }
+
+    Ok(store.get(id)?.map_or(0, |job| job.failed_files))
+}
+
 async fn send_studies_worker(
     thread_id: usize,
-    studies: Vec<(String, Vec<DicomFile>)>,
+    queue: StudyQueue,
     args: &Args,
     progress: ProgressBar,
+    services: WorkerServices,
 ) -> Result<TransferStats> {
+    let WorkerServices { notifier, ledger, controller, meter, tls } = services;
     let mut combined_stats = TransferStats::new();
 
     let client_config = DicomClientConfig {
@@ -240,141 +1287,255 @@ async fn send_studies_worker(
         host: args.host.clone(),
         port: args.port,
         timeout: Duration::from_secs(30),
+        connect_timeout: Duration::from_secs(args.connect_timeout),
+        lenient_repair: args.lenient_repair,
+        compute_checksums: args.manifest.is_some(),
+        pack_pdvs: !args.no_pdv_packing,
+        tls,
+        preview: args.preview.then(|| PreviewOptions {
+            max_dimension: args.preview_max_size,
+            quality: args.preview_quality,
+            annotations: args.preview_annotate.clone(),
+        }),
     };
 
-    for (study_uid, files) in studies {
-        info!("Thread {}: Processing study {} with {} files", 
-              thread_id, study_uid, files.len());
-
-        let client = DicomClient::new(client_config.clone());
-        
-        match client.send_files(files.clone()).await {
-            Ok(stats) => {
-                combined_stats.total_files += stats.total_files;
-                combined_stats.successful_transfers += stats.successful_transfers;
-                combined_stats.failed_transfers += stats.failed_transfers;
-                combined_stats.total_bytes += stats.total_bytes;
-                combined_stats.transfer_times.extend(stats.transfer_times);
-                
-                // Update progress
-                progress.inc(stats.successful_transfers as u64 + stats.failed_transfers as u64);
-                
-                info!("Thread {}: Study {} completed - {}/{} files successful", 
-                      thread_id, study_uid, stats.successful_transfers, stats.total_files);
+    let registry = SopClassRegistry::global();
+    let batching = args.batch_small_objects
+        .map(|max_files| SmallObjectBatching { max_size: args.small_object_size, max_files: max_files.max(1) });
+    let take = |queue: &mut VecDeque<(String, Vec<DicomFile>)>| match &batching {
+        Some(batching) => batching.take(queue, registry),
+        None => queue.pop_front().into_iter().collect(),
+    };
+
+    loop {
+        if let Some(controller) = &controller {
+            controller.wait_for_slot(thread_id).await;
+        }
+        let mut batch = match queue.lock() {
+            Ok(mut queue) => take(&mut *queue),
+            Err(poisoned) => take(&mut *poisoned.into_inner()),
+        };
+        if batch.is_empty() {
+            break;
+        }
+
+        let sent_studies: Vec<(String, Vec<DicomFile>, Vec<TransferResult>)> = if batch.len() == 1 {
+            let (study_uid, files) = batch.remove(0);
+            info!("Thread {}: Processing study {} with {} files",
+                  thread_id, study_uid, files.len());
+            let outcome = send_study(thread_id, &study_uid, files.clone(), &client_config, args, meter.clone()).await;
+            let results = absorb_outcome(&mut combined_stats, outcome, &files, &[study_uid.as_str()], &progress, thread_id);
+            vec![(study_uid, files, results)]
+        } else {
+            let files: Vec<DicomFile> = batch.iter().flat_map(|(_, files)| files.iter().cloned()).collect();
+            info!("Thread {}: Sending {} small-object studies with {} files over one association",
+                  thread_id, batch.len(), files.len());
+            let outcome = DicomClient::new(client_config.clone()).with_meter(meter.clone()).send_files(files.clone()).await;
+            let study_uids: Vec<&str> = batch.iter().map(|(study_uid, _)| study_uid.as_str()).collect();
+            let results = absorb_outcome(&mut combined_stats, outcome, &files, &study_uids, &progress, thread_id);
+            batch.into_iter()
+                .map(|(study_uid, files)| {
+                    let study_results = results.iter()
+                        .filter(|result| result.study_instance_uid == study_uid)
+                        .cloned()
+                        .collect();
+                    (study_uid, files, study_results)
+                })
+                .collect()
+        };
+
+        for (study_uid, files, study_results) in sent_studies {
+            let study_results: Vec<TransferResult> = study_results.into_iter()
+                .map(|result| TransferResult { thread_id, ..result })
+                .collect();
+
+            if let Some(ledger) = &ledger {
+                record_in_ledger(ledger, &files, &study_results, args);
             }
-            Err(e) => {
-                error!("Thread {}: Failed to send study {}: {}", thread_id, study_uid, e);
-                combined_stats.failed_transfers += files.len();
-                progress.inc(files.len() as u64);
+
+            if let Some(controller) = &controller {
+                let sent: Vec<u64> = study_results.iter().filter(|r| r.success).map(|r| r.transfer_time_ms).collect();
+                let mean_latency_ms = if sent.is_empty() { 0.0 } else { sent.iter().sum::<u64>() as f64 / sent.len() as f64 };
+                if let Some(adjustment) = controller.record(mean_latency_ms, study_results.len(), study_results.len() - sent.len()) {
+                    info!("Thread {}: concurrency {} -> {} ({})", thread_id, adjustment.from, adjustment.to, adjustment.reason);
+                    progress.println(format!("⚙️  Concurrency {} → {} ({})", adjustment.from, adjustment.to, adjustment.reason));
+                }
             }
+
+            if let Some(notifier) = &notifier {
+                let failed = study_results.iter().filter(|r| !r.success).count();
+                if let Some(payload) = notifier.record(study_results.len() - failed, failed) {
+                    warn!("Thread {}: failure rate threshold exceeded, notifying webhook", thread_id);
+                    Notifier::post_async(notifier.clone(), payload).await;
+                }
+            }
+
+            let original: u64 = study_results.iter().filter(|r| r.success).map(|r| r.file_size).sum();
+            let forwarded: u64 = study_results.iter().map(|r| r.bytes_sent).sum();
+            if let Some(ratio) = compression_ratio(original, forwarded) {
+                info!("Thread {}: Study {} sent {} bytes on disk as {} bytes ({:.2}:1)",
+                      thread_id, study_uid, original, forwarded, ratio);
+            }
+
+            if args.study_transactions {
+                if let Some(failure) = StudyTransactionFailure::from_results(&study_uid, &study_results) {
+                    error!("Thread {}: Study {} is INCOMPLETE - {} of {} instances sent; failed: {:?}",
+                           thread_id, study_uid, failure.sent_instances.len(), failure.total_instances,
+                           failure.failed_instances.iter().map(|r| &r.sop_instance_uid).collect::<Vec<_>>());
+                    progress.println(format!("❌ Study {} incomplete: {}/{} instances sent",
+                                             study_uid, failure.sent_instances.len(), failure.total_instances));
+                    combined_stats.failed_studies.push(failure);
+                }
+            }
+
+            combined_stats.results.extend(study_results);
         }
     }
 
     Ok(combined_stats)
 }
 
-async fn index_dicom_files(input: &Path, recursive: bool) -> Result<Vec<DicomFile>> {
-    let mut files = Vec::new();
-    
-    if input.is_file() {
-        if let Some(dicom_file) = process_dicom_file(input).await? {
-            files.push(dicom_file);
-        }
-    } else if input.is_dir() {
-        if recursive {
-            for entry in WalkDir::new(input) {
-                let entry = entry?;
-                if entry.file_type().is_file() {
-                    let path = entry.path();
-                    if let Some(ext) = path.extension() {
-                        if ext == "dcm" || ext == "DCM" {
-                            if let Some(dicom_file) = process_dicom_file(path).await? {
-                                files.push(dicom_file);
-                            }
-                        }
-                    }
-                }
-            }
-        } else {
-            for entry in std::fs::read_dir(input)? {
-                let entry = entry?;
-                let path = entry.path();
-                if path.is_file() {
-                    if let Some(ext) = path.extension() {
-                        if ext == "dcm" || ext == "DCM" {
-                            if let Some(dicom_file) = process_dicom_file(&path).await? {
-                                files.push(dicom_file);
-                            }
-                        }
-                    }
+/// Add the outcome of one association's worth of studies to a worker's totals
+/// and return the result of every file; a failed association fails all files
+fn absorb_outcome(
+    combined_stats: &mut TransferStats,
+    outcome: Result<TransferStats>,
+    files: &[DicomFile],
+    study_uids: &[&str],
+    progress: &ProgressBar,
+    thread_id: usize,
+) -> Vec<TransferResult> {
+    let studies = study_uids.join(", ");
+    match outcome {
+        Ok(mut stats) => {
+            // Returned rather than merged: the caller stamps them with the thread first
+            let results = std::mem::take(&mut stats.results);
+
+            // Update progress
+            progress.inc(stats.successful_transfers as u64 + stats.failed_transfers as u64);
+
+            info!("Thread {}: Study {} completed - {}/{} files successful",
+                  thread_id, studies, stats.successful_transfers, stats.total_files);
+            combined_stats.merge(stats);
+            results
+        }
+        Err(e) => {
+            error!("Thread {}: Failed to send study {}: {}", thread_id, studies, e);
+            if let Some(rejection) = e.downcast_ref::<AssociationRejection>() {
+                progress.println(format!("❌ Study {}: {}", studies, rejection));
+                for study_uid in study_uids {
+                    combined_stats.association_rejections.push(RejectedAssociation {
+                        study_instance_uid: study_uid.to_string(),
+                        rejection: *rejection,
+                        description: rejection.to_string(),
+                    });
                 }
             }
+            combined_stats.failed_transfers += files.len();
+            progress.inc(files.len() as u64);
+            files.iter()
+                .map(|file| DicomClient::transfer_result(file, Some(e.to_string()), Duration::ZERO))
+                .collect()
         }
     }
-
-    Ok(files)
 }
 
-async fn process_dicom_file(path: &Path) -> Result<Option<DicomFile>> {
-    match open_file(path) {
-        Ok(obj) => {
-            let study_instance_uid = obj.element(Tag(0x0020, 0x000D))
-                .map(|e| e.string().unwrap_or_default().trim().to_string())
-                .unwrap_or_else(|_| "UNKNOWN_STUDY".to_string());
-
-            let series_instance_uid = obj.element(Tag(0x0020, 0x000E))
-                .map(|e| e.string().unwrap_or_default().trim().to_string())
-                .unwrap_or_else(|_| "UNKNOWN_SERIES".to_string());
-
-            let sop_instance_uid = obj.element(Tag(0x0008, 0x0018))
-                .map(|e| e.string().unwrap_or_default().trim().trim_end_matches('\0').to_string())
-                .unwrap_or_else(|_| "UNKNOWN_SOP_INSTANCE".to_string());
-
-            let sop_class_uid = obj.element(Tag(0x0008, 0x0016))
-                .map(|e| e.string().unwrap_or_default().trim().trim_end_matches('\0').to_string())
-                .unwrap_or_else(|_| "UNKNOWN_SOP_CLASS".to_string());
-            
-            // Debug: Print SOP class details
-            if let Ok(element) = obj.element(Tag(0x0008, 0x0016)) {
-                if let Ok(raw_string) = element.string() {
-                    println!("DEBUG: Raw SOP Class: '{}'", raw_string);
-                    println!("DEBUG: Trimmed SOP Class: '{}'", raw_string.trim());
-                    println!("DEBUG: Length: {}", raw_string.len());
+/// Send a study over one association, or split across several concurrent
+/// associations when it is large enough. A segment whose association fails
+/// counts as failed files; the study fails as a whole only if every segment does.
+async fn send_study(
+    thread_id: usize,
+    study_uid: &str,
+    files: Vec<DicomFile>,
+    config: &DicomClientConfig,
+    args: &Args,
+    meter: Option<Arc<TransferMeter>>,
+) -> Result<TransferStats> {
+    let segments = split_study(files, args.associations_per_study, args.split_min_files);
+    if segments.len() == 1 {
+        let files = segments.into_iter().next().unwrap_or_default();
+        return DicomClient::new(config.clone()).with_meter(meter).send_files(files).await;
+    }
+
+    let total: usize = segments.iter().map(Vec::len).sum();
+    info!("Thread {}: Splitting study {} ({} files) across {} associations",
+          thread_id, study_uid, total, segments.len());
+
+    let mut tasks = tokio::task::JoinSet::new();
+    for (index, segment) in segments.iter().cloned().enumerate() {
+        let client = DicomClient::new(config.clone()).with_meter(meter.clone());
+        tasks.spawn(async move { (index, client.send_files(segment).await) });
+    }
+
+    let mut tracker = SegmentTracker::new(segments.iter().map(Vec::len));
+    let mut outcomes: Vec<Option<Result<TransferStats>>> = segments.iter().map(|_| None).collect();
+    while let Some(joined) = tasks.join_next().await {
+        let (index, outcome) = joined?;
+        let settled = tracker.complete(index);
+        info!("Thread {}: Study {} segment {}/{} finished; instances 1-{} of {} settled",
+              thread_id, study_uid, index + 1, segments.len(), settled, total);
+        outcomes[index] = Some(outcome);
+    }
+
+    let outcomes: Vec<Result<TransferStats>> = outcomes.into_iter()
+        .map(|outcome| outcome.expect("every segment was joined"))
+        .collect();
+    if outcomes.iter().all(Result::is_err) {
+        // Nothing got through, e.g. the destination rejected every association
+        return outcomes.into_iter().next().expect("a split study has segments");
+    }
+
+    // Results are reassembled in study order whatever order the segments finished in
+    let mut stats = TransferStats::new();
+    for (segment, outcome) in segments.iter().zip(outcomes) {
+        match outcome {
+            Ok(segment_stats) => stats.merge(segment_stats),
+            Err(e) => {
+                error!("Thread {}: Segment of study {} failed: {}", thread_id, study_uid, e);
+                if let Some(rejection) = e.downcast_ref::<AssociationRejection>() {
+                    stats.association_rejections.push(RejectedAssociation {
+                        study_instance_uid: study_uid.to_string(),
+                        rejection: *rejection,
+                        description: rejection.to_string(),
+                    });
                 }
+                stats.total_files += segment.len();
+                stats.failed_transfers += segment.len();
+                stats.results.extend(segment.iter()
+                    .map(|file| DicomClient::transfer_result(file, Some(e.to_string()), Duration::ZERO)));
             }
-
-            let modality = obj.element(Tag(0x0008, 0x0060))
-                .ok()
-                .and_then(|e| e.string().ok())
-                .map(|s| s.trim().to_string());
-
-            let patient_id = obj.element(Tag(0x0010, 0x0020))
-                .ok()
-                .and_then(|e| e.string().ok())
-                .map(|s| s.trim().to_string());
-
-            let study_date = obj.element(Tag(0x0008, 0x0020))
-                .ok()
-                .and_then(|e| e.string().ok())
-                .map(|s| s.trim().to_string());
-
-            let file_size = std::fs::metadata(path)?.len();
-
-            Ok(Some(DicomFile {
-                path: path.to_path_buf(),
-                study_instance_uid,
-                series_instance_uid,
-                sop_instance_uid,
-                sop_class_uid,
-                file_size,
-                modality,
-                patient_id,
-                study_date,
-            }))
         }
-        Err(e) => {
-            warn!("Failed to read DICOM file {}: {}", path.display(), e);
-            Ok(None)
+    }
+    Ok(stats)
+}
+
+/// Append the outcome of each instance of a study to the audit ledger
+fn record_in_ledger(ledger: &Mutex<Ledger>, files: &[DicomFile], results: &[TransferResult], args: &Args) {
+    let mut ledger = match ledger.lock() {
+        Ok(ledger) => ledger,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+
+    for result in results {
+        let sop_class_uid = files.iter()
+            .find(|f| f.path.display().to_string() == result.file_path)
+            .map(|f| f.sop_class_uid.clone())
+            .unwrap_or_default();
+
+        let record = LedgerRecord {
+            direction: Direction::Sent,
+            timestamp: result.timestamp,
+            local_ae: args.calling_ae.clone(),
+            peer_ae: args.ae_title.clone(),
+            sop_class_uid,
+            sop_instance_uid: result.sop_instance_uid.clone(),
+            study_instance_uid: result.study_instance_uid.clone(),
+            size: result.file_size,
+            status: if result.success { "success".to_string() } else { "failed".to_string() },
+        };
+
+        if let Err(e) = ledger.append(record) {
+            error!("Failed to append to ledger: {}", e);
         }
     }
 }
@@ -0,0 +1,222 @@
+/// Replication of a receiver's storage to a standby receiver
+///
+/// `dicom-sender mirror` follows the output directory of a dicom-receiver and
+/// sends every object stored there to one designated standby, oldest first.
+/// Replicated files are kept in a state file together with their size and
+/// modification time, so a restarted mirror, or `mirror --catch-up` after an
+/// outage of the standby, sends only the backlog, and an object overwritten
+/// on the primary is replicated again. Replication lag is tracked per study as
+/// the age of its oldest object still waiting to be replicated.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+use crate::common::recovery::INCOMPLETE_DIR;
+
+/// Directories below a receiver's output directory that do not hold accepted objects
+const SKIPPED_DIRS: [&str; 2] = ["quarantine", INCOMPLETE_DIR];
+
+/// An object in the source directory
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceFile {
+    pub path: PathBuf,
+    /// Path relative to the source directory, the key of the mirror state
+    pub relative_path: String,
+    pub size: u64,
+    /// When the primary stored the object
+    pub modified: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReplicatedFile {
+    pub size: u64,
+    pub modified: DateTime<Utc>,
+    pub study_instance_uid: String,
+    pub replicated_at: DateTime<Utc>,
+}
+
+/// Objects replicated so far, by path relative to the source directory
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct MirrorState {
+    replicated: BTreeMap<String, ReplicatedFile>,
+}
+
+impl MirrorState {
+    /// Load the state file; a missing file is an empty state
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read mirror state {}", path.display()))?;
+        serde_json::from_str(&contents).with_context(|| format!("Invalid mirror state {}", path.display()))
+    }
+
+    /// Replace the state file, so a crash leaves either the old or the new state
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        let temporary = path.with_extension("tmp");
+        std::fs::write(&temporary, serde_json::to_vec_pretty(self)?)?;
+        std::fs::rename(&temporary, path)
+            .with_context(|| format!("Failed to write mirror state {}", path.display()))
+    }
+
+    /// Whether this version of the file has been replicated
+    pub fn is_replicated(&self, file: &SourceFile) -> bool {
+        self.replicated.get(&file.relative_path)
+            .is_some_and(|replicated| replicated.size == file.size && replicated.modified == file.modified)
+    }
+
+    pub fn record(&mut self, file: &SourceFile, study_instance_uid: &str, replicated_at: DateTime<Utc>) {
+        self.replicated.insert(file.relative_path.clone(), ReplicatedFile {
+            size: file.size,
+            modified: file.modified,
+            study_instance_uid: study_instance_uid.to_string(),
+            replicated_at,
+        });
+    }
+
+    pub fn replicated_count(&self) -> usize {
+        self.replicated.len()
+    }
+}
+
+/// Objects under `source` not yet replicated in their current version, oldest first
+pub fn scan_source(source: &Path, state: &MirrorState) -> Result<Vec<SourceFile>> {
+    let mut files = Vec::new();
+    let walker = WalkDir::new(source).into_iter().filter_entry(|entry| {
+        entry.depth() != 1 || !entry.file_type().is_dir()
+            || !SKIPPED_DIRS.iter().any(|dir| entry.file_name() == *dir)
+    });
+    for entry in walker {
+        let entry = entry?;
+        let path = entry.path();
+        if !entry.file_type().is_file() || !path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("dcm")) {
+            continue;
+        }
+        let metadata = entry.metadata()?;
+        let file = SourceFile {
+            path: path.to_path_buf(),
+            relative_path: path.strip_prefix(source).unwrap_or(path).to_string_lossy().into_owned(),
+            size: metadata.len(),
+            modified: DateTime::<Utc>::from(metadata.modified()?),
+        };
+        if !state.is_replicated(&file) {
+            files.push(file);
+        }
+    }
+    files.sort_by(|a, b| a.modified.cmp(&b.modified).then_with(|| a.path.cmp(&b.path)));
+    Ok(files)
+}
+
+/// Replication lag of one study
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StudyLag {
+    pub study_instance_uid: String,
+    pub pending_instances: usize,
+    pub oldest_pending: DateTime<Utc>,
+    pub lag_seconds: i64,
+}
+
+/// Lag of everything still waiting for the standby, written after every pass
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LagReport {
+    pub timestamp: DateTime<Utc>,
+    pub replicated_instances: usize,
+    pub pending_instances: usize,
+    pub max_lag_seconds: i64,
+    /// Most lagging study first
+    pub studies: Vec<StudyLag>,
+}
+
+/// Lag per study of the pending objects, given as (study, stored at)
+pub fn lag_report(state: &MirrorState, pending: &[(String, DateTime<Utc>)], now: DateTime<Utc>) -> LagReport {
+    let mut by_study: HashMap<&str, (usize, DateTime<Utc>)> = HashMap::new();
+    for (study_instance_uid, stored_at) in pending {
+        let entry = by_study.entry(study_instance_uid).or_insert((0, *stored_at));
+        entry.0 += 1;
+        entry.1 = entry.1.min(*stored_at);
+    }
+
+    let mut studies: Vec<StudyLag> = by_study.into_iter()
+        .map(|(study_instance_uid, (pending_instances, oldest_pending))| StudyLag {
+            study_instance_uid: study_instance_uid.to_string(),
+            pending_instances,
+            oldest_pending,
+            lag_seconds: (now - oldest_pending).num_seconds().max(0),
+        })
+        .collect();
+    studies.sort_by(|a, b| b.lag_seconds.cmp(&a.lag_seconds).then_with(|| a.study_instance_uid.cmp(&b.study_instance_uid)));
+
+    LagReport {
+        timestamp: now,
+        replicated_instances: state.replicated_count(),
+        pending_instances: pending.len(),
+        max_lag_seconds: studies.first().map_or(0, |study| study.lag_seconds),
+        studies,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan_skips_replicated_and_rejected_objects() {
+        let dir = std::env::temp_dir().join(format!("mirror_test_{}", uuid::Uuid::new_v4()));
+        for subdir in ["", "quarantine", INCOMPLETE_DIR, "2024"] {
+            std::fs::create_dir_all(dir.join(subdir)).unwrap();
+        }
+        std::fs::write(dir.join("a.dcm"), b"a").unwrap();
+        std::fs::write(dir.join("2024").join("b.dcm"), b"bb").unwrap();
+        std::fs::write(dir.join("c.dcm.partial"), b"c").unwrap();
+        std::fs::write(dir.join("quarantine").join("d.dcm"), b"d").unwrap();
+        std::fs::write(dir.join(INCOMPLETE_DIR).join("e.dcm"), b"e").unwrap();
+
+        let mut state = MirrorState::default();
+        let files = scan_source(&dir, &state).unwrap();
+        let mut names: Vec<&str> = files.iter().map(|file| file.relative_path.as_str()).collect();
+        names.sort();
+        assert_eq!(names, vec![Path::new("2024").join("b.dcm").to_str().unwrap(), "a.dcm"]);
+
+        let a = files.iter().find(|file| file.relative_path == "a.dcm").unwrap();
+        state.record(a, "1.2.3", Utc::now());
+        let state_path = dir.join("state").join("mirror.json");
+        state.save(&state_path).unwrap();
+        let state = MirrorState::load(&state_path).unwrap();
+        assert_eq!(scan_source(&dir, &state).unwrap().len(), 1);
+
+        // An object overwritten on the primary is replicated again
+        std::fs::write(dir.join("a.dcm"), b"a, stored again").unwrap();
+        assert_eq!(scan_source(&dir, &state).unwrap().len(), 2);
+
+        assert_eq!(MirrorState::load(&dir.join("missing.json")).unwrap().replicated_count(), 0);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_lag_per_study() {
+        let now = Utc::now();
+        let ago = |seconds| now - chrono::Duration::seconds(seconds);
+        let pending = vec![
+            ("1.2.3".to_string(), ago(30)),
+            ("1.2.4".to_string(), ago(600)),
+            ("1.2.3".to_string(), ago(90)),
+        ];
+
+        let report = lag_report(&MirrorState::default(), &pending, now);
+        assert_eq!((report.pending_instances, report.max_lag_seconds), (3, 600));
+        assert_eq!(report.studies[0].study_instance_uid, "1.2.4");
+        assert_eq!((report.studies[1].pending_instances, report.studies[1].lag_seconds), (2, 90));
+        assert_eq!(report.studies[1].oldest_pending, ago(90));
+
+        let caught_up = lag_report(&MirrorState::default(), &[], now);
+        assert_eq!((caught_up.max_lag_seconds, caught_up.studies.len()), (0, 0));
+    }
+}
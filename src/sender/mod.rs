@@ -1,2 +1,12 @@
 // Sender mod re-exports
+pub mod batching;
+pub mod chunking;
+pub mod concurrency;
+pub mod conformance;
 pub mod dicom_client;
+pub mod duplicates;
+pub mod indexing;
+pub mod jobs;
+pub mod mirror;
+pub mod notify;
+pub mod study_split;
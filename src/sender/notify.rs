@@ -0,0 +1,110 @@
+use anyhow::Result;
+use serde_json::json;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::common::types::SessionSummary;
+
+/// Instances that must have been attempted before the failure rate is judged
+const MIN_INSTANCES_FOR_THRESHOLD: usize = 20;
+
+/// Posts run notifications to a generic webhook
+///
+/// Payloads carry a `text` field so Slack and Mattermost incoming webhooks can
+/// take them as-is, plus structured fields for other consumers (e.g. an
+/// email gateway). The failure-threshold notification fires at most once per run.
+pub struct Notifier {
+    webhook_url: String,
+    /// Failure rate in percent above which a notification is sent mid-run
+    failure_threshold: Option<f64>,
+    succeeded: AtomicUsize,
+    failed: AtomicUsize,
+    threshold_fired: AtomicBool,
+}
+
+impl Notifier {
+    pub fn new(webhook_url: String, failure_threshold: Option<f64>) -> Self {
+        Self {
+            webhook_url,
+            failure_threshold,
+            succeeded: AtomicUsize::new(0),
+            failed: AtomicUsize::new(0),
+            threshold_fired: AtomicBool::new(false),
+        }
+    }
+
+    /// Record progress and return the threshold payload the first time the failure rate crosses it
+    pub fn record(&self, succeeded: usize, failed: usize) -> Option<serde_json::Value> {
+        let threshold = self.failure_threshold?;
+        let total_succeeded = self.succeeded.fetch_add(succeeded, Ordering::SeqCst) + succeeded;
+        let total_failed = self.failed.fetch_add(failed, Ordering::SeqCst) + failed;
+        let attempted = total_succeeded + total_failed;
+
+        if attempted < MIN_INSTANCES_FOR_THRESHOLD {
+            return None;
+        }
+
+        let failure_rate = total_failed as f64 * 100.0 / attempted as f64;
+        if failure_rate <= threshold || self.threshold_fired.swap(true, Ordering::SeqCst) {
+            return None;
+        }
+
+        Some(json!({
+            "event": "failure_threshold_exceeded",
+            "text": format!("⚠️ DICOM sender failure rate {:.1}% exceeds {:.1}% ({} of {} instances failed so far)",
+                            failure_rate, threshold, total_failed, attempted),
+            "failure_rate_percent": failure_rate,
+            "threshold_percent": threshold,
+            "failed": total_failed,
+            "attempted": attempted,
+        }))
+    }
+
+    pub fn completion_payload(summary: &SessionSummary) -> serde_json::Value {
+        let (icon, outcome) = if summary.failed_transfers == 0 { ("✅", "completed") } else { ("❌", "completed with failures") };
+        json!({
+            "event": "run_completed",
+            "text": format!("{} DICOM sender session {} {}: {}/{} instances sent to {}",
+                            icon, summary.session_id, outcome, summary.successful_transfers,
+                            summary.total_files, summary.destination),
+            "summary": summary,
+        })
+    }
+
+    /// POST a payload to the webhook; blocking, so call it from a blocking task
+    pub fn post(&self, payload: &serde_json::Value) -> Result<()> {
+        ureq::post(&self.webhook_url)
+            .timeout(Duration::from_secs(10))
+            .send_json(payload)
+            .map_err(|e| anyhow::anyhow!("Webhook notification failed: {}", e))?;
+        info!("Posted {} notification to webhook", payload["event"]);
+        Ok(())
+    }
+
+    /// Post without failing the run if the webhook is unreachable
+    pub async fn post_async(notifier: std::sync::Arc<Self>, payload: serde_json::Value) {
+        let result = tokio::task::spawn_blocking(move || notifier.post(&payload)).await;
+        match result {
+            Ok(Err(e)) => warn!("{}", e),
+            Err(e) => warn!("Notification task failed: {}", e),
+            Ok(Ok(())) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_threshold_fires_once() {
+        let notifier = Notifier::new("http://localhost/hook".to_string(), Some(10.0));
+        assert!(notifier.record(10, 5).is_none(), "below minimum sample size");
+        assert!(notifier.record(5, 0).is_some());
+        assert!(notifier.record(0, 10).is_none(), "already fired");
+
+        let disabled = Notifier::new("http://localhost/hook".to_string(), None);
+        assert!(disabled.record(0, 100).is_none());
+    }
+}
@@ -0,0 +1,101 @@
+/// Splitting one large study across concurrent associations
+///
+/// A study is cut into contiguous segments in series order, each sent over its
+/// own association. Segments finish in any order; the tracker reports how many
+/// instances from the start of the study have settled, so logs and progress
+/// show an ordered watermark rather than a scatter of finished ranges.
+
+use crate::common::types::DicomFile;
+
+/// Cut a study into at most `parts` contiguous segments of near-equal size,
+/// ordered by series and path; studies smaller than `min_files` stay whole
+pub fn split_study(mut files: Vec<DicomFile>, parts: usize, min_files: usize) -> Vec<Vec<DicomFile>> {
+    files.sort_by(|a, b| a.series_instance_uid.cmp(&b.series_instance_uid).then_with(|| a.path.cmp(&b.path)));
+    let parts = parts.clamp(1, files.len().max(1));
+    if parts == 1 || files.len() < min_files {
+        return vec![files];
+    }
+
+    let base = files.len() / parts;
+    let extra = files.len() % parts;
+    let mut segments = Vec::with_capacity(parts);
+    let mut rest = files.into_iter();
+    for index in 0..parts {
+        let length = base + usize::from(index < extra);
+        segments.push(rest.by_ref().take(length).collect());
+    }
+    segments
+}
+
+/// Completion of the segments of a split study
+#[derive(Debug, Clone)]
+pub struct SegmentTracker {
+    lengths: Vec<usize>,
+    completed: Vec<bool>,
+    /// Segments completed from the start, without gaps
+    settled: usize,
+}
+
+impl SegmentTracker {
+    pub fn new(lengths: impl IntoIterator<Item = usize>) -> Self {
+        let lengths: Vec<usize> = lengths.into_iter().collect();
+        Self {
+            completed: vec![false; lengths.len()],
+            lengths,
+            settled: 0,
+        }
+    }
+
+    /// Mark a segment finished; returns the number of instances from the
+    /// start of the study whose segments have all finished
+    pub fn complete(&mut self, segment: usize) -> usize {
+        if let Some(done) = self.completed.get_mut(segment) {
+            *done = true;
+        }
+        while self.completed.get(self.settled).copied().unwrap_or(false) {
+            self.settled += 1;
+        }
+        self.lengths[..self.settled].iter().sum()
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.settled == self.lengths.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn file(series: &str, name: &str) -> DicomFile {
+        DicomFile::test(name, &format!("{}.{}", series, name)).with_series(series)
+    }
+
+    #[test]
+    fn test_split_study() {
+        let files: Vec<DicomFile> = (0..10).rev()
+            .map(|i| file(if i < 4 { "1.1" } else { "1.2" }, &format!("{:02}.dcm", i)))
+            .collect();
+
+        let segments = split_study(files.clone(), 3, 5);
+        assert_eq!(segments.iter().map(Vec::len).collect::<Vec<_>>(), vec![4, 3, 3]);
+        assert!(segments[0].iter().all(|f| f.series_instance_uid == "1.1"));
+        assert_eq!(segments[1][0].path, PathBuf::from("04.dcm"));
+
+        assert_eq!(split_study(files.clone(), 3, 11).len(), 1);
+        assert_eq!(split_study(files[..2].to_vec(), 8, 0).len(), 2);
+        let empty = split_study(Vec::new(), 4, 0);
+        assert!(empty.len() == 1 && empty[0].is_empty());
+    }
+
+    #[test]
+    fn test_tracker_reports_ordered_watermark() {
+        let mut tracker = SegmentTracker::new([4, 3, 3]);
+        assert_eq!(tracker.complete(2), 0);
+        assert_eq!(tracker.complete(0), 4);
+        assert!(!tracker.is_complete());
+        assert_eq!(tracker.complete(1), 10);
+        assert!(tracker.is_complete());
+    }
+}
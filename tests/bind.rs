@@ -0,0 +1,56 @@
+//! Binding the receiver's listening socket to IPv4 and IPv6 addresses
+
+use std::io::ErrorKind;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, TcpListener, TcpStream};
+use std::time::Duration;
+
+use rust_dicom::common::listen::{bind_listener, parse_bind_address};
+
+/// Connect to `port` on `address` and check the listener accepts the connection
+fn accepts(listener: &TcpListener, address: IpAddr) -> bool {
+    let port = listener.local_addr().unwrap().port();
+    let Ok(_client) = TcpStream::connect_timeout(&SocketAddr::new(address, port), Duration::from_secs(2)) else {
+        return false;
+    };
+    listener.set_nonblocking(false).unwrap();
+    listener.accept().is_ok()
+}
+
+/// A listener on `address`, or `None` when the host has no IPv6
+fn bind(address: &str, ipv6_only: bool) -> Option<TcpListener> {
+    match bind_listener(parse_bind_address(address).unwrap(), 0, ipv6_only) {
+        Ok(listener) => Some(listener),
+        Err(e) if matches!(e.kind(), ErrorKind::AddrNotAvailable | ErrorKind::Unsupported) || e.raw_os_error() == Some(97) => {
+            eprintln!("skipping {}: IPv6 is not available here ({})", address, e);
+            None
+        }
+        Err(e) => panic!("cannot bind {}: {}", address, e),
+    }
+}
+
+#[test]
+fn test_bind_ipv4() {
+    let listener = bind("127.0.0.1", false).unwrap();
+    assert!(listener.local_addr().unwrap().is_ipv4());
+    assert!(accepts(&listener, IpAddr::V4(Ipv4Addr::LOCALHOST)));
+}
+
+#[test]
+fn test_bind_ipv6_loopback() {
+    let Some(listener) = bind("[::1]", false) else { return };
+    assert_eq!(listener.local_addr().unwrap().ip(), IpAddr::V6(Ipv6Addr::LOCALHOST));
+    assert!(accepts(&listener, IpAddr::V6(Ipv6Addr::LOCALHOST)));
+}
+
+#[test]
+fn test_unspecified_ipv6_is_dual_stack() {
+    let Some(listener) = bind("[::]", false) else { return };
+    assert!(accepts(&listener, IpAddr::V4(Ipv4Addr::LOCALHOST)), "IPv4 peers reach a dual-stack listener");
+}
+
+#[test]
+fn test_ipv6_only_refuses_ipv4() {
+    let Some(listener) = bind("::", true) else { return };
+    let port = listener.local_addr().unwrap().port();
+    assert!(TcpStream::connect_timeout(&SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), port), Duration::from_secs(2)).is_err());
+}
@@ -0,0 +1,58 @@
+//! The PostgreSQL receive index against a real database
+//!
+//! Runs only when `DATABASE_URL` names a database to use, e.g.
+//! `DATABASE_URL=postgres://postgres@localhost/dicom_test cargo test --features postgres`.
+#![cfg(feature = "postgres")]
+
+use std::path::PathBuf;
+
+use chrono::Utc;
+use rust_dicom::common::postgres_index::PostgresIndex;
+use rust_dicom::common::receive_index::{Filter, IndexedInstance, MetadataStore};
+
+fn instance(sop_instance_uid: &str, study_instance_uid: &str, size: u64) -> IndexedInstance {
+    let attributes = [
+        ("StudyInstanceUID", study_instance_uid),
+        ("SeriesInstanceUID", "1.2.826.0.1.3680043.2.1125.9.1"),
+        ("PatientID", "PG-TEST"),
+        ("Modality", "CT"),
+        ("StudyDate", "20240115"),
+    ];
+    IndexedInstance {
+        sop_instance_uid: sop_instance_uid.to_string(),
+        calling_ae: "MODALITY".to_string(),
+        path: PathBuf::from(format!("/srv/dicom/{}.dcm", sop_instance_uid)),
+        size,
+        received_at: Utc::now(),
+        attributes: attributes.iter().map(|(keyword, value)| (keyword.to_string(), value.to_string())).collect(),
+    }
+}
+
+// Used from inside a runtime, as the receiver does
+#[tokio::test(flavor = "multi_thread")]
+async fn test_record_query_and_hold() {
+    let Ok(url) = std::env::var("DATABASE_URL") else {
+        eprintln!("DATABASE_URL is not set, skipping");
+        return;
+    };
+    let mut index = PostgresIndex::connect(&url).unwrap();
+    let study = format!("2.25.{}", uuid::Uuid::new_v4().as_u128());
+    index.record(&instance(&format!("{}.1", study), &study, 900)).unwrap();
+    index.record(&instance(&format!("{}.2", study), &study, 1024)).unwrap();
+
+    let query = |filters: &[&str]| {
+        let mut filters: Vec<Filter> = filters.iter().map(|filter| Filter::parse(filter).unwrap()).collect();
+        filters.push(Filter::equals("StudyInstanceUID", &study));
+        index.query(&filters).unwrap().len()
+    };
+    assert_eq!(query(&[]), 2);
+    // Compared as numbers: as text, "900" would sort after "1000"
+    assert_eq!(query(&["Size=1000-"]), 1);
+    assert_eq!(query(&["Size=-1000"]), 1);
+    assert_eq!(query(&["ReceivedAt=2024-01-01T00:00:00Z/"]), 2);
+
+    index.place_hold(&study, "litigation").unwrap();
+    assert!(index.is_held_instance(&format!("{}.1", study)).unwrap());
+    assert!(index.release_hold(&study, "settled").unwrap());
+    assert!(!index.is_held(&study).unwrap());
+}
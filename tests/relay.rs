@@ -0,0 +1,91 @@
+//! Relaying received objects: what a forwarder learns of each association
+
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use dicom_core::value::PrimitiveValue;
+use dicom_core::{DataElement, VR};
+use dicom_dictionary_std::tags;
+use dicom_object::InMemDicomObject;
+use rust_dicom::common::relay_mapping::RelayMapping;
+use rust_dicom::receiver::receiver::{DicomReceiver, ObjectForwarder};
+use rust_dicom::sender::dicom_client::{DicomClient, DicomClientConfig};
+
+const CT_IMAGE_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.2";
+const EXPLICIT_VR_LITTLE_ENDIAN: &str = "1.2.840.10008.1.2.1";
+const SOP_INSTANCE_UID: &str = "1.2.826.0.1.3680043.2.1125.1.1";
+
+/// A minimal CT image data set in Explicit VR Little Endian
+fn ct_dataset() -> Vec<u8> {
+    let obj = InMemDicomObject::from_element_iter([
+        DataElement::new(tags::SOP_CLASS_UID, VR::UI, PrimitiveValue::from(CT_IMAGE_STORAGE)),
+        DataElement::new(tags::SOP_INSTANCE_UID, VR::UI, PrimitiveValue::from(SOP_INSTANCE_UID)),
+        DataElement::new(tags::PATIENT_ID, VR::LO, PrimitiveValue::from("12345")),
+        DataElement::new(tags::STUDY_INSTANCE_UID, VR::UI, PrimitiveValue::from("1.2.826.0.1.3680043.2.1125.1")),
+        DataElement::new(tags::SERIES_INSTANCE_UID, VR::UI, PrimitiveValue::from("1.2.826.0.1.3680043.2.1125.1.2")),
+        DataElement::new(tags::MODALITY, VR::CS, PrimitiveValue::from("CT")),
+    ]);
+    let mut dataset = Vec::new();
+    obj.write_dataset_with_ts(&mut dataset, &dicom_transfer_syntax_registry::entries::EXPLICIT_VR_LITTLE_ENDIAN.erased())
+        .unwrap();
+    dataset
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_mapping_rules_match_the_called_ae_title() {
+    let mapping = RelayMapping::parse(r#"
+        [[rule]]
+        calling_ae = "CT01"
+        called_ae = "RELAY_SITE_A"
+        new_calling_ae = "SITEA_CT01"
+    "#).unwrap();
+
+    // The receiver answers to RELAY, and accepts any called AE title by default
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let forwarded = Arc::clone(&seen);
+    let output_dir = std::env::temp_dir().join(format!("relay_test_{}", uuid::Uuid::new_v4()));
+    let receiver = DicomReceiver::new("RELAY".to_string(), output_dir.clone(), 4)
+        .with_bind_address(Ipv4Addr::LOCALHOST.into(), false)
+        .with_forwarder(ObjectForwarder::new(move |object| {
+            forwarded.lock().unwrap().push((object.calling_ae.trim().to_string(), object.called_ae.to_string()));
+            0x0000
+        }));
+    let port = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap().local_addr().unwrap().port();
+    let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+    let running = tokio::spawn(Arc::new(receiver).start(port, async move {
+        let _ = stopped.await;
+    }));
+    let address = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+    while TcpStream::connect_timeout(&address, Duration::from_secs(1)).is_err() {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+
+    let client = DicomClient::new(DicomClientConfig {
+        calling_ae: "CT01".to_string(),
+        called_ae: "RELAY_SITE_A".to_string(),
+        host: "127.0.0.1".to_string(),
+        port,
+        timeout: Duration::from_secs(10),
+        connect_timeout: Duration::from_secs(5),
+        lenient_repair: false,
+        compute_checksums: false,
+        pack_pdvs: true,
+        tls: None,
+        preview: None,
+    });
+    let status = tokio::task::spawn_blocking(move || {
+        client.relay_dataset(CT_IMAGE_STORAGE, SOP_INSTANCE_UID, EXPLICIT_VR_LITTLE_ENDIAN, &mut &ct_dataset()[..])
+    }).await.unwrap().unwrap();
+    assert_eq!(status, 0x0000);
+
+    let seen = seen.lock().unwrap().clone();
+    assert_eq!(seen, vec![("CT01".to_string(), "RELAY_SITE_A".to_string())]);
+    let (calling_ae, called_ae) = &seen[0];
+    let rule = mapping.rule_for(calling_ae, called_ae).expect("the rule matches the called AE title sent");
+    assert_eq!(rule.new_calling_ae.as_deref(), Some("SITEA_CT01"));
+
+    stop.send(()).unwrap();
+    running.await.unwrap().unwrap();
+    let _ = std::fs::remove_dir_all(&output_dir);
+}
@@ -0,0 +1,45 @@
+//! Peers that stall while negotiating an association
+
+use std::io::{Read, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use rust_dicom::receiver::receiver::DicomReceiver;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_stalled_association_request_is_dropped() {
+    let output_dir = std::env::temp_dir().join(format!("stalled_peer_test_{}", uuid::Uuid::new_v4()));
+    let receiver = DicomReceiver::new("STORE".to_string(), output_dir.clone(), 1)
+        .with_bind_address(Ipv4Addr::LOCALHOST.into(), false)
+        .with_idle_timeout(Some(Duration::from_secs(1)));
+    let port = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap().local_addr().unwrap().port();
+    let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+    let running = tokio::spawn(Arc::new(receiver).start(port, async move {
+        let _ = stopped.await;
+    }));
+
+    let address = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+    let peer = tokio::task::spawn_blocking(move || {
+        let mut stream = loop {
+            match TcpStream::connect(address) {
+                Ok(stream) => break stream,
+                Err(_) => std::thread::sleep(Duration::from_millis(20)),
+            }
+        };
+        // An A-ASSOCIATE-RQ header announcing 1000 bytes, and then nothing
+        stream.write_all(&[0x01, 0x00, 0x00, 0x00, 0x03, 0xE8]).unwrap();
+        stream.set_read_timeout(Some(Duration::from_secs(10))).unwrap();
+        let started = Instant::now();
+        let mut buffer = [0u8; 64];
+        let closed = matches!(stream.read(&mut buffer), Ok(0) | Err(_));
+        (closed, started.elapsed())
+    });
+    let (closed, elapsed) = peer.await.unwrap();
+    assert!(closed);
+    assert!(elapsed < Duration::from_secs(5), "the connection was held for {:?}", elapsed);
+
+    stop.send(()).unwrap();
+    running.await.unwrap().unwrap();
+    let _ = std::fs::remove_dir_all(&output_dir);
+}